@@ -0,0 +1,243 @@
+//! A worked rollback integration: a host and a guest, each running a toy deterministic sim, connected by an in-process loopback transport with a fixed delay.
+//!
+//! Each frame, every node collects its own input, sends and receives messages, and then advances its sim:
+//!
+//! 1. Peers' inputs that haven't arrived yet are predicted (`get_peer_input_for_tick` returns the prediction).
+//! 2. When a peer's real input arrives and differs from what was predicted, `take_earliest_changed_tick` reports the earliest tick affected.
+//! 3. The sim restores its snapshot from before that tick and re-simulates up to the present with the corrected inputs.
+//!
+//! At the end, both sims must agree on every tick up to the snapshottable tick, which no later input can change.
+//!
+//! Run with `cargo run --example rollback_demo`.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use temporal_input_buffer::prelude::*;
+
+const TICKS_PER_SEC: u32 = 60;
+const NUM_PLAYERS: u8 = 2;
+const MAX_GUEST_TICKS_BEHIND: u32 = 10;
+const MAX_TICKS_TO_PREDICT: u32 = 5;
+/// Frames each message spends in flight, one way.
+const DELAY_FRAMES: u32 = 3;
+const NUM_FRAMES: u32 = 600;
+
+const HOST: PlayerNum = PlayerNum::new_host();
+const GUEST: PlayerNum = PlayerNum::new_guest(1);
+
+/// A player's input: a push on a stick, in each axis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct StickInput {
+    dx: i8,
+    dy: i8,
+}
+
+impl SimInput for StickInput {
+    type Bytes = [u8; 2];
+
+    fn to_bytes(&self) -> [u8; 2] {
+        [self.dx as u8, self.dy as u8]
+    }
+
+    fn from_bytes(bytes: [u8; 2]) -> Self {
+        StickInput {
+            dx: bytes[0] as i8,
+            dy: bytes[1] as i8,
+        }
+    }
+
+    fn sanitize(&mut self) {
+        self.dx = self.dx.clamp(-1, 1);
+        self.dy = self.dy.clamp(-1, 1);
+    }
+}
+
+/// Each player's input for a frame: the stick is held in one direction for a while, then turned, so predicting a repeat of the last input is usually (but not always) right.
+fn own_input(player: PlayerNum, frame: u32) -> StickInput {
+    let hold_frames = 17 + 6 * player.as_u8() as u32;
+    let (dx, dy) = match (frame / hold_frames + player.as_u8() as u32) % 4 {
+        0 => (1, 0),
+        1 => (0, 1),
+        2 => (-1, 0),
+        _ => (0, -1),
+    };
+    StickInput { dx, dy }
+}
+
+/// One player's body, in integer units so the sim is deterministic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Body {
+    x: i32,
+    y: i32,
+    vx: i32,
+    vy: i32,
+}
+
+/// The toy sim's whole state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct World {
+    bodies: [Body; NUM_PLAYERS as usize],
+}
+
+impl World {
+    /// Advances the world one tick with every player's input for that tick.
+    fn step(&self, inputs: [StickInput; NUM_PLAYERS as usize]) -> World {
+        let mut next = *self;
+        for (body, input) in next.bodies.iter_mut().zip(inputs) {
+            body.vx = (body.vx + input.dx as i32).clamp(-8, 8);
+            body.vy = (body.vy + input.dy as i32).clamp(-8, 8);
+            body.x += body.vx;
+            body.y += body.vy;
+        }
+        next
+    }
+}
+
+/// A node's rollback sim: a snapshot of the world before every tick simulated so far.
+struct RollbackSim {
+    /// `snapshots[tick]` is the world before `tick` was simulated, so the last is the present.
+    snapshots: Vec<World>,
+    num_rollbacks: u32,
+    num_resimulated_ticks: u32,
+}
+
+impl RollbackSim {
+    fn new() -> Self {
+        Self {
+            snapshots: vec![World::default()],
+            num_rollbacks: 0,
+            num_resimulated_ticks: 0,
+        }
+    }
+
+    fn num_simulated_ticks(&self) -> u32 {
+        self.snapshots.len() as u32 - 1
+    }
+
+    /// Rolls back to the earliest tick whose inputs changed since the last call, if it was already simulated, and then simulates every tick up to the node's own inputs.
+    fn advance(&mut self, session: &mut Session<StickInput>) {
+        if let Some(changed) = session.take_earliest_changed_tick()
+            && changed < self.num_simulated_ticks()
+        {
+            self.num_rollbacks += 1;
+            self.num_resimulated_ticks += self.num_simulated_ticks() - changed;
+            self.snapshots.truncate(changed as usize + 1);
+        }
+
+        while self.num_simulated_ticks() < session.get_own_num_inputs() {
+            let tick = self.num_simulated_ticks();
+            let inputs = [HOST, GUEST].map(|player| session.get_peer_input_for_tick(player, tick));
+            let next = self.snapshots.last().unwrap().step(inputs);
+            self.snapshots.push(next);
+        }
+    }
+}
+
+/// An in-process transport delivering every message after a fixed number of frames.
+struct Loopback {
+    /// Messages in flight, in the order they were sent: (frame due, sender, recipient, bytes)
+    in_flight: VecDeque<(u32, PlayerNum, PlayerNum, Vec<u8>)>,
+}
+
+impl Loopback {
+    fn send(&mut self, frame: u32, from: PlayerNum, outgoing: OutgoingMsgs<StickInput>) {
+        for (recipient, msg) in outgoing {
+            let to = match recipient {
+                Recipient::Player(to) => to,
+                // with two players, the other player is the only peer
+                Recipient::AllPeers => PlayerNum::from_u8(1 - from.as_u8()),
+            };
+            self.in_flight
+                .push_back((frame + DELAY_FRAMES, from, to, msg.to_bytes()));
+        }
+    }
+
+    /// Delivers the messages due by `frame` to `nodes` (indexed by player num), and sends their replies.
+    fn deliver_due(&mut self, frame: u32, nodes: &mut [Session<StickInput>]) {
+        while self
+            .in_flight
+            .front()
+            .is_some_and(|(due, ..)| *due <= frame)
+        {
+            let (_, from, to, bytes) = self.in_flight.pop_front().unwrap();
+            let replies = nodes[usize::from(to)]
+                .rx_bytes(from, &bytes)
+                .expect("the loopback never corrupts messages");
+            self.send(frame, to, replies);
+        }
+    }
+}
+
+fn main() {
+    let host = MultiplayerInputManager::<StickInput, HostInputMgr>::new(
+        NUM_PLAYERS,
+        MAX_GUEST_TICKS_BEHIND,
+        MAX_TICKS_TO_PREDICT,
+        TICKS_PER_SEC,
+    );
+    let mut guest = MultiplayerInputManager::<StickInput, GuestInputMgr>::new(
+        NUM_PLAYERS,
+        GUEST,
+        TICKS_PER_SEC,
+    );
+    // pings are timed with the wall clock, so the demo supplies the RTT
+    guest.observe_rtt_ms_to_host((2 * DELAY_FRAMES * 1000) as f32 / TICKS_PER_SEC as f32);
+
+    let mut nodes: Vec<Session<StickInput>> = vec![host.into(), guest.into()];
+    let mut sims = [RollbackSim::new(), RollbackSim::new()];
+    let mut loopback = Loopback {
+        in_flight: VecDeque::new(),
+    };
+    let delta = 1.0 / TICKS_PER_SEC as f32;
+
+    for frame in 0..NUM_FRAMES {
+        for player in [HOST, GUEST] {
+            let node = &mut nodes[usize::from(player)];
+            let num_inputs = match node.as_guest() {
+                Some(guest) => guest.num_inputs_needed(),
+                None => 1,
+            };
+            for _ in 0..num_inputs {
+                let outgoing = node.add_own_input(own_input(player, frame), delta);
+                loopback.send(frame, player, outgoing);
+            }
+            node.drain_events();
+        }
+        loopback.deliver_due(frame, &mut nodes);
+        for (sim, node) in sims.iter_mut().zip(nodes.iter_mut()) {
+            sim.advance(node);
+        }
+    }
+
+    // No input up to the snapshottable tick can change any more, so both sims
+    // must have simulated those ticks identically.
+    let snapshottable = nodes
+        .iter()
+        .map(Session::get_snapshottable_sim_tick)
+        .min()
+        .unwrap();
+    assert!(
+        snapshottable > NUM_FRAMES / 2,
+        "only {snapshottable} ticks finalized"
+    );
+    for tick in 0..=snapshottable as usize {
+        assert_eq!(
+            sims[0].snapshots[tick], sims[1].snapshots[tick],
+            "the sims diverged before tick {tick}"
+        );
+    }
+
+    for (player, sim) in [HOST, GUEST].into_iter().zip(&sims) {
+        println!(
+            "{player:?}: simulated {} ticks, with {} rollbacks re-simulating {} ticks",
+            sim.num_simulated_ticks(),
+            sim.num_rollbacks,
+            sim.num_resimulated_ticks
+        );
+    }
+    println!(
+        "both sims agree up to tick {snapshottable}: {:?}",
+        sims[0].snapshots[snapshottable as usize]
+    );
+}
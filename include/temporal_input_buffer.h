@@ -0,0 +1,69 @@
+/*
+ * C declarations for the `ffi` feature of temporal_input_buffer.
+ *
+ * Build with `cargo build --release --features ffi` and link against the
+ * resulting cdylib. See src/ffi.rs for the full documentation of each function.
+ */
+#ifndef TEMPORAL_INPUT_BUFFER_H
+#define TEMPORAL_INPUT_BUFFER_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Size in bytes of a single input. */
+#define TIB_INPUT_BYTES 16
+
+/* Recipient reported by tib_poll_outgoing for messages to send to all peers. */
+#define TIB_BROADCAST 255
+
+/* Status codes: 0 is success, positive values are non-error outcomes,
+ * negative values are errors. */
+#define TIB_OK 0
+#define TIB_NO_MESSAGE 1
+#define TIB_ERR_NULL_POINTER (-1)
+#define TIB_ERR_INVALID_ARGUMENT (-2)
+#define TIB_ERR_DECODE (-3)
+#define TIB_ERR_BUFFER_TOO_SMALL (-4)
+#define TIB_ERR_WRONG_ROLE (-5)
+#define TIB_ERR_INTERNAL (-6)
+#define TIB_ERR_INPUT_SCHEMA_MISMATCH (-7)
+
+typedef struct TibManager TibManager;
+
+/* Constructors return NULL on invalid arguments. */
+TibManager *tib_host_new(uint8_t num_players, uint32_t max_guest_ticks_behind,
+                         uint32_t max_ticks_to_predict_locf,
+                         uint32_t ticks_per_sec);
+TibManager *tib_guest_new(uint8_t num_players, uint8_t own_player_num,
+                          uint32_t ticks_per_sec);
+void tib_manager_free(TibManager *handle);
+
+int32_t tib_push_local_input(TibManager *handle, const uint8_t *input,
+                             size_t input_len, float delta_sec);
+int32_t tib_push_message(TibManager *handle, uint8_t sender,
+                         const uint8_t *bytes, size_t len);
+int32_t tib_poll_outgoing(TibManager *handle, uint8_t *out_recipient,
+                          uint8_t *out_buf, size_t out_buf_len,
+                          size_t *out_len);
+
+/* Guest only. */
+int32_t tib_guest_send_ping(TibManager *handle);
+int32_t tib_guest_num_inputs_needed(TibManager *handle, uint32_t *out);
+
+int32_t tib_snapshottable_tick(TibManager *handle, uint32_t *out);
+int32_t tib_num_inputs(TibManager *handle, uint8_t player_num,
+                       uint32_t *out_total, uint32_t *out_finalized);
+int32_t tib_get_input(TibManager *handle, uint8_t player_num, uint32_t tick,
+                      uint8_t *out_input, size_t out_input_len,
+                      bool *out_finalized);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TEMPORAL_INPUT_BUFFER_H */
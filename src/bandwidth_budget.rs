@@ -0,0 +1,141 @@
+//! Choosing which outgoing messages to send when the transport reports congestion.
+//!
+//! With an outgoing budget set (see `MultiplayerInputManager::set_outgoing_budget_bytes_per_sec`), each frame's messages are passed through `select_outgoing_within_budget`, which sends them in priority order while budget remains:
+//! 1. finalized slices, with the acks and control messages that progress depends on;
+//! 2. own input slices, trimmed to their oldest inputs if the whole slice doesn't fit (the rest are resent in later frames, since input slices always run to the newest input);
+//! 3. pings and diagnostics.
+//!
+//! The budget is a token bucket refilled by `delta` each frame, holding at most one second of budget. A priority that has had messages to send but sent none for `BANDWIDTH_STARVATION_FRAMES` consecutive frames sends its first message regardless of the budget, so that pings (and with them RTT estimates) never stop entirely.
+
+use crate::{
+    input_messages::{MsgKind, MsgPayload},
+    input_trait::SimInput,
+    session::{OutgoingMsgs, Recipient},
+};
+
+/// The number of consecutive frames a priority may be starved before its first message is sent over budget.
+pub const BANDWIDTH_STARVATION_FRAMES: u32 = 10;
+
+/// The order in which outgoing messages are sent when bandwidth is limited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MsgPriority {
+    /// Finalized slices, finalization and event acks, pre-sim syncs, round transitions, mutes and events
+    Finalized,
+    /// Own input slices, including bundles of local players' inputs
+    OwnInputs,
+    /// Pings, pongs (including clock pings and pongs), rate adjustments, chain heads and determinism samples
+    Pings,
+}
+
+impl MsgPriority {
+    const ALL: [MsgPriority; 3] = [
+        MsgPriority::Finalized,
+        MsgPriority::OwnInputs,
+        MsgPriority::Pings,
+    ];
+
+    pub fn of(kind: MsgKind) -> MsgPriority {
+        match kind {
+            MsgKind::PeerInputs | MsgKind::GuestToHostLocalInputs => MsgPriority::OwnInputs,
+            MsgKind::GuestToHostPing
+            | MsgKind::HostToGuestPong
+            | MsgKind::GuestToHostPongPong
+            | MsgKind::GuestToHostLegacyPongPong
+            | MsgKind::HostToGuestPingReport
+            | MsgKind::GuestToHostClockPing
+            | MsgKind::HostToGuestClockPong
+            | MsgKind::HostToGuestRateAdjust
+            | MsgKind::PeerInputChainHead
+            | MsgKind::PeerDeterminismSample => MsgPriority::Pings,
+            _ => MsgPriority::Finalized,
+        }
+    }
+}
+
+/// The outgoing budget, and how much of it is left.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BandwidthBudget {
+    /// CONFIG SETTING
+    /// The outgoing budget; `None` to send everything.
+    pub(crate) bytes_per_sec: Option<u32>,
+    /// Bytes that may be sent now; negative after a message is sent over budget to prevent starvation
+    available: f32,
+    /// For each priority, the consecutive frames in which it had messages to send but sent none
+    frames_starved: [u32; 3],
+    pub(crate) num_dropped: u32,
+    pub(crate) num_trimmed: u32,
+}
+
+impl BandwidthBudget {
+    pub(crate) fn set_bytes_per_sec(&mut self, bytes_per_sec: Option<u32>) {
+        self.bytes_per_sec = bytes_per_sec;
+        self.available = self.available.min(bytes_per_sec.unwrap_or(0) as f32);
+    }
+
+    /// Selects the messages to send this frame (see the module docs). A message for all peers costs its size once per peer.
+    pub(crate) fn select<T: SimInput>(
+        &mut self,
+        outgoing: OutgoingMsgs<T>,
+        num_peers: u32,
+        delta: f32,
+    ) -> OutgoingMsgs<T> {
+        let Some(bytes_per_sec) = self.bytes_per_sec else {
+            return outgoing;
+        };
+        let bucket_size = bytes_per_sec as f32;
+        self.available = (self.available + bucket_size * delta).min(bucket_size);
+
+        let mut outgoing = outgoing;
+        outgoing.sort_by_key(|(_, msg)| MsgPriority::of(msg.kind()));
+        let mut selected = Vec::with_capacity(outgoing.len());
+        let mut outgoing = outgoing.into_iter().peekable();
+        for (i, priority) in MsgPriority::ALL.into_iter().enumerate() {
+            let starving = self.frames_starved[i] >= BANDWIDTH_STARVATION_FRAMES;
+            let mut any_msgs = false;
+            let mut any_sent = false;
+            while let Some((recipient, msg)) =
+                outgoing.next_if(|(_, msg)| MsgPriority::of(msg.kind()) == priority)
+            {
+                any_msgs = true;
+                let copies = match recipient {
+                    Recipient::AllPeers => num_peers.max(1),
+                    Recipient::Player(_) => 1,
+                } as f32;
+                let (msg, trimmed) = self.trim_to_fit(msg, copies);
+                let cost = msg.to_bytes().len() as f32 * copies;
+                if cost <= self.available || (starving && !any_sent) {
+                    self.available -= cost;
+                    any_sent = true;
+                    self.num_trimmed += trimmed as u32;
+                    selected.push((recipient, msg));
+                } else {
+                    self.num_dropped += 1;
+                }
+            }
+            self.frames_starved[i] = if any_msgs && !any_sent {
+                self.frames_starved[i] + 1
+            } else {
+                0
+            };
+        }
+        selected
+    }
+
+    /// Halves an input slice until it fits the remaining budget, keeping at least one input, and returns whether it was trimmed. Other messages are returned as they are.
+    fn trim_to_fit<T: SimInput>(&self, msg: MsgPayload<T>, copies: f32) -> (MsgPayload<T>, bool) {
+        let MsgPayload::PeerInputs(mut slice) = msg else {
+            return (msg, false);
+        };
+        let full_len = slice.len();
+        loop {
+            let fits = MsgPayload::PeerInputs(slice.clone()).to_bytes().len() as f32 * copies
+                <= self.available;
+            if fits || slice.len() <= 1 {
+                break;
+            }
+            slice.truncate_before(slice.start + slice.len() / 2);
+        }
+        let trimmed = slice.len() < full_len;
+        (MsgPayload::PeerInputs(slice), trimmed)
+    }
+}
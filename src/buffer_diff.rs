@@ -0,0 +1,83 @@
+//! A comparison of two managers' input buffers, for asserting that a host and its guests (or any two nodes) have converged.
+//!
+//! `MultiplayerInputManager::diff_against` compares each player's finalized and total input counts, and their finalized inputs up to the lower of the two finalized counts, reporting the first sim tick at which they differ.
+
+use crate::{
+    input_trait::SimInput, multiplayer_input_buffer::MultiplayerInputBuffers, util_types::PlayerNum,
+};
+
+/// How one player's inputs differ between two managers; each pair is `(this manager's, the other's)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerBufferDiff {
+    pub player_num: PlayerNum,
+    pub num_finalized: (u32, u32),
+    pub num_inputs: (u32, u32),
+    /// The first sim tick, within the inputs both have finalized, at which their inputs differ
+    pub first_divergent_tick: Option<u32>,
+}
+
+impl PlayerBufferDiff {
+    /// True if both managers have finalized the same inputs for this player. Total counts may still differ, since unfinalized inputs arrive at different times on each node.
+    pub fn is_converged(&self) -> bool {
+        self.num_finalized.0 == self.num_finalized.1 && self.first_divergent_tick.is_none()
+    }
+}
+
+/// How two managers' input buffers differ, per player (see the module docs).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferDiff {
+    pub players: Vec<PlayerBufferDiff>,
+}
+
+impl BufferDiff {
+    /// True if both managers have finalized the same inputs for every player.
+    pub fn is_converged(&self) -> bool {
+        self.players.iter().all(PlayerBufferDiff::is_converged)
+    }
+
+    /// The earliest sim tick at which any player's finalized inputs differ.
+    pub fn first_divergent_tick(&self) -> Option<u32> {
+        self.players
+            .iter()
+            .filter_map(|player| player.first_divergent_tick)
+            .min()
+    }
+
+    pub fn player(&self, player_num: PlayerNum) -> Option<&PlayerBufferDiff> {
+        self.players
+            .iter()
+            .find(|player| player.player_num == player_num)
+    }
+}
+
+/// Compares the players both buffers hold.
+pub(crate) fn diff_buffers<T: SimInput>(
+    ours: &MultiplayerInputBuffers<T>,
+    theirs: &MultiplayerInputBuffers<T>,
+) -> BufferDiff {
+    let num_players = ours.num_players().min(theirs.num_players());
+    let players = PlayerNum::iter(num_players)
+        .map(|player_num| {
+            let num_finalized = (
+                ours.get_num_finalized_inputs(player_num),
+                theirs.get_num_finalized_inputs(player_num),
+            );
+            let first_divergent_input = (0..num_finalized.0.min(num_finalized.1)).find(|index| {
+                ours.get_input_or_prediction(player_num, *index).to_bytes()
+                    != theirs
+                        .get_input_or_prediction(player_num, *index)
+                        .to_bytes()
+            });
+            PlayerBufferDiff {
+                player_num,
+                num_finalized,
+                num_inputs: (
+                    ours.get_num_inputs(player_num),
+                    theirs.get_num_inputs(player_num),
+                ),
+                first_divergent_tick: first_divergent_input.map(|index| ours.tick_of_input(index)),
+            }
+        })
+        .collect();
+    BufferDiff { players }
+}
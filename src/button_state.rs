@@ -0,0 +1,65 @@
+/// The state of a button on a given tick, derived from whether it is down on that tick and the tick before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonState {
+    /// Up on this tick and the previous one
+    Up,
+    /// Went down on this tick
+    Pressed,
+    /// Down on this tick and the previous one
+    Held,
+    /// Went up on this tick
+    Released,
+}
+
+impl ButtonState {
+    pub fn from_transition(was_down: bool, is_down: bool) -> Self {
+        match (was_down, is_down) {
+            (false, false) => ButtonState::Up,
+            (false, true) => ButtonState::Pressed,
+            (true, true) => ButtonState::Held,
+            (true, false) => ButtonState::Released,
+        }
+    }
+
+    pub fn is_down(self) -> bool {
+        matches!(self, ButtonState::Pressed | ButtonState::Held)
+    }
+}
+
+/// Converts a button history (oldest first, `true` meaning down) into per-tick `ButtonState`s.
+///
+/// The button is taken to be up before the first tick of the history.
+pub fn button_states(history: impl IntoIterator<Item = bool>) -> impl Iterator<Item = ButtonState> {
+    history.into_iter().scan(false, |was_down, is_down| {
+        let state = ButtonState::from_transition(*was_down, is_down);
+        *was_down = is_down;
+        Some(state)
+    })
+}
+
+/// The number of consecutive ticks at the end of the history for which the button has been down; 0 if it is up on the last tick.
+pub fn hold_duration_ticks(history: impl IntoIterator<Item = bool>) -> u32 {
+    history
+        .into_iter()
+        .fold(0, |held, is_down| if is_down { held + 1 } else { 0 })
+}
+
+/// Whether the button was pressed on the last tick of the history, with the previous press starting at most `max_gap_ticks` earlier.
+///
+/// This is true only on the tick of the second press, so it fires once per double-tap.
+pub fn is_double_tap(history: impl IntoIterator<Item = bool>, max_gap_ticks: u32) -> bool {
+    let mut last_press = None;
+    let mut prev_press = None;
+    let mut num_ticks = 0;
+    for (tick, state) in button_states(history).enumerate() {
+        if state == ButtonState::Pressed {
+            prev_press = last_press;
+            last_press = Some(tick);
+        }
+        num_ticks = tick + 1;
+    }
+    match (prev_press, last_press) {
+        (Some(prev), Some(last)) => last + 1 == num_ticks && last - prev <= max_gap_ticks as usize,
+        _ => false,
+    }
+}
@@ -0,0 +1,47 @@
+//! Negotiation of optional protocol capabilities between the host and its guests.
+//!
+//! Optional features change what goes over the wire, so a peer can only use one if the peer it sends to supports it too. Each node has a set of enabled capabilities (by default, every one compiled into this build; see `MultiplayerInputManager::with_capabilities`), which peers agree on when joining:
+//!
+//! 1. The host advertises its capabilities in the `PreSimSync`.
+//! 2. A guest keeps the subset it also supports as the set negotiated with the host, and confirms that subset with a `GuestToHostCapabilities` message (`get_msg_capabilities`), which the host stores for that guest with `rx_guest_capabilities`.
+//!
+//! Until a peer's set is negotiated, messages to it use the baseline protocol, with no optional capabilities. `MultiplayerInputManager::msg_bytes_for_peer` and `msg_bytes_for_all_peers` serialize messages using only what the recipients negotiated, so lobbies mixing builds fall back to the baseline instead of sending bytes a peer can't decode.
+//!
+//! Capabilities are bits in a `u32`, and new ones only ever take new bits: a peer drops the bits it doesn't know when intersecting, so older builds stay compatible with newer ones.
+
+use serde::{Deserialize, Serialize};
+
+/// A set of optional protocol capabilities, as a bitmask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct Capabilities(pub u32);
+
+impl Capabilities {
+    /// The baseline protocol, with no optional capabilities
+    pub const NONE: Capabilities = Capabilities(0);
+    /// Compressed message payloads (the `compression` feature)
+    pub const COMPRESSION: Capabilities = Capabilities(1 << 0);
+
+    /// The capabilities compiled into this build.
+    pub fn supported() -> Capabilities {
+        if cfg!(feature = "compression") {
+            Capabilities::COMPRESSION
+        } else {
+            Capabilities::NONE
+        }
+    }
+
+    /// True if every capability in `other` is in this set.
+    pub fn contains(self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// The capabilities in both sets.
+    pub fn intersection(self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 & other.0)
+    }
+
+    /// The capabilities in either set.
+    pub fn union(self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 | other.0)
+    }
+}
@@ -0,0 +1,33 @@
+//! Per-subsystem change counters, so game UIs can re-render netcode panels only when something changed.
+//!
+//! `MultiplayerInputManager::change_stamps` returns a counter for each of the input buffers, the finalization acks, the RTT estimates and the queued events. Each counter only ever increases (across rounds too), and increases whenever its part of the state changes, so a UI can keep the stamps it last rendered and compare them each frame instead of re-reading and diffing the full state.
+//!
+//! Stamps are only comparable with other stamps from the same manager. A counter may occasionally increase without a visible change (e.g. an RTT sample equal to the current estimate), but never stays put across one.
+
+/// The change counters of each part of a manager's state (see `change_stamps`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ChangeStamps {
+    /// Inputs collected, received or finalized for any player, or players added
+    pub buffers: u64,
+    /// HOST: the finalized inputs acked by the guests. GUEST: the finalized inputs this guest has acked to the host.
+    pub observations: u64,
+    /// RTT estimates and ping reports
+    pub rtt: u64,
+    /// Events queued for `drain_events`
+    pub events: u64,
+}
+
+impl ChangeStamps {
+    /// True if any part changed between `earlier` and these stamps.
+    pub fn changed_since(&self, earlier: &ChangeStamps) -> bool {
+        self != earlier
+    }
+}
+
+/// The change counters a manager keeps itself; the buffers keep their own, and queued events are counted as they're drained.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ChangeCounters {
+    pub(crate) observations: u64,
+    pub(crate) rtt: u64,
+    pub(crate) events_drained: u64,
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+/// A sample whose round trip is more than twice the fastest seen, plus this slack (micros), is discarded: it most likely waited in a queue in one direction only, which skews its offset.
+pub const CLOCK_SYNC_ROUND_TRIP_SLACK_MICROS: i64 = 5_000;
+
+/// A guest's clock sync request, stamped with the guest's local time (see `MultiplayerInputManager::observe_local_time_ms`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockPing {
+    /// The guest's local time (whole microseconds) when it sent the ping
+    pub guest_send_micros: i64,
+}
+
+/// The host's reply to a `ClockPing`: the guest's stamp, echoed, with the host's sim time when it replied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockPong {
+    /// The stamp of the ping being answered
+    pub guest_send_micros: i64,
+    /// The host's sim time (whole microseconds) when it replied, i.e. the clock the host collects its own inputs against
+    pub host_micros: i64,
+}
+
+/// One completed clock sync exchange. As in NTP, the guest's send and receive times bracket the host's reply time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ClockSample {
+    pub guest_send_micros: i64,
+    pub host_micros: i64,
+    pub guest_recv_micros: i64,
+}
+
+impl ClockSample {
+    /// How far (micros) the host's clock is ahead of the guest's, assuming the reply took as long to arrive as the request.
+    pub(crate) fn offset_micros(&self) -> i64 {
+        self.host_micros - (self.guest_send_micros + self.guest_recv_micros) / 2
+    }
+
+    pub(crate) fn round_trip_micros(&self) -> i64 {
+        self.guest_recv_micros - self.guest_send_micros
+    }
+}
+
+/// A smoothed estimate of how far the host's sim clock is ahead of a guest's local clock.
+///
+/// Because the offset is measured against the host's own clock, it doesn't depend on how long the host takes to turn messages around, unlike the one-way latency estimated from the RTT.
+#[derive(Debug, Default)]
+pub(crate) struct ClockSync {
+    /// `None` until the first sample is accepted
+    offset_micros: Option<f64>,
+    /// The fastest round trip seen; `None` until the first sample is accepted
+    min_round_trip_micros: Option<i64>,
+    /// The guest's receive time of the last accepted sample; `None` until the first sample is accepted
+    last_sample_recv_micros: Option<i64>,
+    num_samples: u32,
+}
+
+impl ClockSync {
+    /// Folds a sample into the offset, with the given weight (0 to 1) once the estimate has started. Returns false if the sample was discarded (see `CLOCK_SYNC_ROUND_TRIP_SLACK_MICROS`), or if its round trip is negative.
+    pub(crate) fn observe(&mut self, sample: ClockSample, smoothing: f32) -> bool {
+        let round_trip = sample.round_trip_micros();
+        if round_trip < 0 {
+            return false;
+        }
+        let min_round_trip = self
+            .min_round_trip_micros
+            .map_or(round_trip, |min| min.min(round_trip));
+        if round_trip > 2 * min_round_trip + CLOCK_SYNC_ROUND_TRIP_SLACK_MICROS {
+            return false;
+        }
+        self.min_round_trip_micros = Some(min_round_trip);
+        let offset = sample.offset_micros() as f64;
+        self.offset_micros = Some(match self.offset_micros {
+            None => offset,
+            Some(smoothed) => smoothed + smoothing as f64 * (offset - smoothed),
+        });
+        self.last_sample_recv_micros = Some(sample.guest_recv_micros);
+        self.num_samples += 1;
+        true
+    }
+
+    /// The smoothed offset (whole micros, rounded to the nearest); `None` until a sample is accepted.
+    pub(crate) fn offset_micros(&self) -> Option<i64> {
+        self.offset_micros.map(|offset| offset.round() as i64)
+    }
+
+    /// The guest's local time (micros) when the last accepted sample arrived; `None` until a sample is accepted.
+    pub(crate) fn last_sample_recv_micros(&self) -> Option<i64> {
+        self.last_sample_recv_micros
+    }
+
+    pub(crate) fn num_samples(&self) -> u32 {
+        self.num_samples
+    }
+}
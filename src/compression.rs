@@ -0,0 +1,28 @@
+use bincode::error::DecodeError;
+
+/// Refuse to decompress payloads that claim to expand beyond this many bytes, so that a corrupt or hostile size prefix can't trigger a huge allocation.
+pub(crate) const MAX_DECOMPRESSED_BYTES: usize = 16 * 1024 * 1024;
+
+/// Compresses `bytes`, prefixing the result with the uncompressed length.
+pub(crate) fn compress(bytes: &[u8]) -> Vec<u8> {
+    lz4_flex::compress_prepend_size(bytes)
+}
+
+/// Reverses `compress`.
+pub(crate) fn decompress(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let size_prefix: [u8; 4] =
+        bytes
+            .get(..4)
+            .and_then(|b| b.try_into().ok())
+            .ok_or(DecodeError::Other(
+                "compressed payload is missing its size prefix",
+            ))?;
+    let uncompressed_len = u32::from_le_bytes(size_prefix) as usize;
+    if uncompressed_len > MAX_DECOMPRESSED_BYTES {
+        return Err(DecodeError::OtherString(format!(
+            "compressed payload claims {uncompressed_len} bytes, more than the max of {MAX_DECOMPRESSED_BYTES}"
+        )));
+    }
+    lz4_flex::decompress_size_prepended(bytes)
+        .map_err(|e| DecodeError::OtherString(format!("failed to decompress payload: {e}")))
+}
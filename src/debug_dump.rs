@@ -0,0 +1,275 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    input_buffer::InputStatus, input_trait::SimInput,
+    multiplayer_input_buffer::MultiplayerInputBuffers, util_types::PlayerNum,
+};
+
+/// The number of trailing ticks rendered for each player in a debug dump.
+pub const DEBUG_DUMP_RECENT_TICKS: u32 = 16;
+
+/// Bumped to v2 when the `~` (predicted) status symbol was added.
+const DUMP_HEADER: &str = "temporal_input_buffer dump v2";
+/// v1 dumps use a subset of the v2 symbols, so they still parse.
+const DUMP_HEADER_V1: &str = "temporal_input_buffer dump v1";
+
+/// Which side of the session produced a debug dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpRole {
+    Host,
+    Guest,
+}
+
+/// Status-level summary of a single player's input buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerDebugDump {
+    pub player_num: PlayerNum,
+    /// The number of finalized inputs in the buffer
+    pub finalized: u32,
+    /// The number of inputs in the buffer, finalized or not
+    pub total: u32,
+    /// The status of each tick in the dump's recent window, starting at `window_start`
+    pub recent: Vec<InputStatus>,
+}
+
+/// A compact, human-readable summary of the state of an input manager, intended to be pasted into bug reports.
+///
+/// Use `MultiplayerInputManager::dump_debug_text` to produce the text form, and `str::parse::<DebugDump>()` to read it back.
+///
+/// Only status-level information is captured (counts and per-tick finalization status); the input values themselves are not included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugDump {
+    pub role: DumpRole,
+    pub own_player_num: PlayerNum,
+    pub snapshottable_tick: u32,
+    /// The first tick covered by each player's `recent` statuses
+    pub window_start: u32,
+    pub players: Vec<PlayerDebugDump>,
+    /// HOST ONLY: for each guest, the number of finalized inputs that guest has acked for each peer.
+    ///
+    /// Empty for dumps taken on a guest.
+    pub observations: Vec<(PlayerNum, Vec<(PlayerNum, u32)>)>,
+}
+
+impl DebugDump {
+    pub(crate) fn new_from_buffers<T: SimInput>(
+        role: DumpRole,
+        own_player_num: PlayerNum,
+        buffers: &MultiplayerInputBuffers<T>,
+        observations: Vec<(PlayerNum, Vec<(PlayerNum, u32)>)>,
+    ) -> Self {
+        let player_nums = buffers.get_peer_player_nums();
+        // all players share the same window so that the rendered
+        // statuses line up column-wise
+        let window_end = player_nums
+            .iter()
+            .map(|p| buffers.get_num_inputs(*p))
+            .max()
+            .unwrap_or(0);
+        let window_start = window_end.saturating_sub(DEBUG_DUMP_RECENT_TICKS);
+
+        let players = player_nums
+            .iter()
+            .map(|&player_num| PlayerDebugDump {
+                player_num,
+                finalized: buffers.get_num_finalized_inputs(player_num),
+                total: buffers.get_num_inputs(player_num),
+                recent: (window_start..window_start + DEBUG_DUMP_RECENT_TICKS)
+                    .map(|t| buffers.get_input_status(player_num, t))
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            role,
+            own_player_num,
+            snapshottable_tick: buffers.get_num_finalized_inputs_across_peers(),
+            window_start,
+            players,
+            observations,
+        }
+    }
+}
+
+/// Test helpers
+#[cfg(test)]
+impl DebugDump {
+    /// Rebuilds a set of buffers with the same finalized and total input counts as this dump.
+    ///
+    /// All reconstructed inputs are `T::default()`, since dumps do not carry input values.
+    pub(crate) fn to_buffers<T: SimInput>(
+        &self,
+        max_inputs_to_predict: u32,
+    ) -> MultiplayerInputBuffers<T> {
+        let mut buffers =
+            MultiplayerInputBuffers::new(self.players.len() as u8, max_inputs_to_predict);
+        for player in &self.players {
+            for _ in 0..player.finalized {
+                buffers.append_input_finalized(player.player_num, T::default());
+            }
+            for _ in player.finalized..player.total {
+                buffers.append_input(player.player_num, T::default());
+            }
+        }
+        buffers
+    }
+}
+
+fn status_symbol(status: &InputStatus) -> char {
+    match status {
+        InputStatus::Finalized { .. } => 'F',
+        InputStatus::NonFinal { .. } => 'N',
+        InputStatus::NotReceived {
+            predicted: true, ..
+        } => '~',
+        InputStatus::NotReceived { .. } => '-',
+    }
+}
+
+/// Symbols don't carry the finalized count, so it is taken from the player's line.
+fn status_from_symbol(symbol: char, num_finalized: u32) -> Result<InputStatus, String> {
+    match symbol {
+        'F' => Ok(InputStatus::Finalized { num_finalized }),
+        'N' => Ok(InputStatus::NonFinal { num_finalized }),
+        '~' => Ok(InputStatus::NotReceived {
+            num_finalized,
+            predicted: true,
+        }),
+        '-' => Ok(InputStatus::NotReceived {
+            num_finalized,
+            predicted: false,
+        }),
+        x => Err(format!("unknown input status symbol: {x:?}")),
+    }
+}
+
+impl Display for DebugDump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{DUMP_HEADER}")?;
+        let role = match self.role {
+            DumpRole::Host => "host",
+            DumpRole::Guest => "guest",
+        };
+        writeln!(
+            f,
+            "role: {role}; own_player: {}; snapshottable: {}; window_start: {}",
+            self.own_player_num.as_u8(),
+            self.snapshottable_tick,
+            self.window_start
+        )?;
+        for player in &self.players {
+            let recent: String = player.recent.iter().map(status_symbol).collect();
+            writeln!(
+                f,
+                "player {}: finalized {}; total {}; recent {recent}",
+                player.player_num.as_u8(),
+                player.finalized,
+                player.total
+            )?;
+        }
+        for (guest, acked) in &self.observations {
+            write!(f, "observed_by_guest {}:", guest.as_u8())?;
+            for (peer, num) in acked {
+                write!(f, " {}={num}", peer.as_u8())?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the value out of a `key value` field, checking that the key matches.
+fn parse_field<V: FromStr>(field: &str, key: &str) -> Result<V, String> {
+    let value = field
+        .trim()
+        .strip_prefix(key)
+        .ok_or_else(|| format!("expected field {key:?}, got {field:?}"))?;
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid value for {key:?}: {value:?}"))
+}
+
+fn parse_player_num(s: &str) -> Result<PlayerNum, String> {
+    s.parse::<PlayerNum>()
+        .map_err(|e| format!("invalid player num {s:?}: {e}"))
+}
+
+impl FromStr for DebugDump {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(str::trim).filter(|l| !l.is_empty());
+
+        if !matches!(lines.next(), Some(DUMP_HEADER | DUMP_HEADER_V1)) {
+            return Err(format!("missing dump header {DUMP_HEADER:?}"));
+        }
+
+        let summary = lines.next().ok_or("missing summary line")?;
+        let fields: Vec<&str> = summary.split(';').collect();
+        if fields.len() != 4 {
+            return Err(format!("malformed summary line: {summary:?}"));
+        }
+        let role = match parse_field::<String>(fields[0], "role:")?.as_str() {
+            "host" => DumpRole::Host,
+            "guest" => DumpRole::Guest,
+            x => return Err(format!("unknown role: {x:?}")),
+        };
+        let own_player_num = parse_field(fields[1], "own_player:")?;
+        let snapshottable_tick = parse_field(fields[2], "snapshottable:")?;
+        let window_start = parse_field(fields[3], "window_start:")?;
+
+        let mut players = vec![];
+        let mut observations = vec![];
+        for line in lines {
+            if let Some(rest) = line.strip_prefix("player ") {
+                let (num, rest) = rest
+                    .split_once(':')
+                    .ok_or_else(|| format!("malformed player line: {line:?}"))?;
+                let fields: Vec<&str> = rest.split(';').collect();
+                if fields.len() != 3 {
+                    return Err(format!("malformed player line: {line:?}"));
+                }
+                let finalized = parse_field(fields[0], "finalized")?;
+                let recent = parse_field::<String>(fields[2], "recent")?
+                    .chars()
+                    .map(|symbol| status_from_symbol(symbol, finalized))
+                    .collect::<Result<Vec<_>, _>>()?;
+                players.push(PlayerDebugDump {
+                    player_num: parse_player_num(num)?,
+                    finalized,
+                    total: parse_field(fields[1], "total")?,
+                    recent,
+                });
+            } else if let Some(rest) = line.strip_prefix("observed_by_guest ") {
+                let (guest, rest) = rest
+                    .split_once(':')
+                    .ok_or_else(|| format!("malformed observation line: {line:?}"))?;
+                let acked = rest
+                    .split_whitespace()
+                    .map(|pair| {
+                        let (peer, num) = pair
+                            .split_once('=')
+                            .ok_or_else(|| format!("malformed observation: {pair:?}"))?;
+                        let num = num
+                            .parse::<u32>()
+                            .map_err(|_| format!("malformed observation: {pair:?}"))?;
+                        Ok((parse_player_num(peer)?, num))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                observations.push((parse_player_num(guest)?, acked));
+            } else {
+                return Err(format!("unrecognized dump line: {line:?}"));
+            }
+        }
+
+        Ok(Self {
+            role,
+            own_player_num,
+            snapshottable_tick,
+            window_start,
+            players,
+            observations,
+        })
+    }
+}
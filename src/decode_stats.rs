@@ -0,0 +1,104 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{input_messages::COMPRESSED_FLAG, util_types::PlayerNum};
+
+/// The number of most recent malformed payloads kept for inspection.
+pub const MALFORMED_QUARANTINE_LEN: usize = 8;
+
+/// By default, a peer is flagged after this many malformed messages.
+pub const DEFAULT_MALFORMED_MSG_THRESHOLD: u32 = 16;
+
+/// A received message that could not be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MalformedMsg {
+    pub player_num: PlayerNum,
+    /// The variant number from the header byte; `None` if the message was empty
+    pub variant_num: Option<u8>,
+    /// The raw received bytes, including the header byte
+    pub bytes: Vec<u8>,
+    pub error: String,
+}
+
+/// Receive-side accounting of decoded and malformed messages.
+#[derive(Debug)]
+pub(crate) struct DecodeStats {
+    decoded_by_variant: HashMap<u8, u32>,
+    malformed_by_variant: HashMap<u8, u32>,
+    malformed_by_peer: HashMap<PlayerNum, u32>,
+    /// The most recent malformed messages, oldest first
+    quarantine: VecDeque<MalformedMsg>,
+    /// CONFIG SETTING
+    /// The number of malformed messages from a single peer at which that peer is flagged.
+    pub(crate) malformed_msg_threshold: u32,
+}
+
+impl Default for DecodeStats {
+    fn default() -> Self {
+        Self {
+            decoded_by_variant: HashMap::default(),
+            malformed_by_variant: HashMap::default(),
+            malformed_by_peer: HashMap::default(),
+            quarantine: VecDeque::default(),
+            malformed_msg_threshold: DEFAULT_MALFORMED_MSG_THRESHOLD,
+        }
+    }
+}
+
+fn header_variant_num(bytes: &[u8]) -> Option<u8> {
+    bytes.first().map(|b| b & !COMPRESSED_FLAG)
+}
+
+impl DecodeStats {
+    pub fn record_decoded(&mut self, bytes: &[u8]) {
+        // empty messages decode to `MsgPayload::Empty`, variant 0
+        let variant_num = header_variant_num(bytes).unwrap_or(0);
+        *self.decoded_by_variant.entry(variant_num).or_default() += 1;
+    }
+
+    /// Records a malformed message, returning `true` if this message brought the peer up to the malformed message threshold.
+    pub fn record_malformed(&mut self, player_num: PlayerNum, bytes: &[u8], error: String) -> bool {
+        let variant_num = header_variant_num(bytes);
+        if let Some(variant_num) = variant_num {
+            *self.malformed_by_variant.entry(variant_num).or_default() += 1;
+        }
+        let num_malformed = self.malformed_by_peer.entry(player_num).or_default();
+        *num_malformed += 1;
+        let crossed_threshold = *num_malformed == self.malformed_msg_threshold;
+
+        if self.quarantine.len() == MALFORMED_QUARANTINE_LEN {
+            self.quarantine.pop_front();
+        }
+        self.quarantine.push_back(MalformedMsg {
+            player_num,
+            variant_num,
+            bytes: bytes.to_vec(),
+            error,
+        });
+        crossed_threshold
+    }
+
+    pub fn num_decoded(&self, variant_num: u8) -> u32 {
+        self.decoded_by_variant
+            .get(&variant_num)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn num_malformed_for_variant(&self, variant_num: u8) -> u32 {
+        self.malformed_by_variant
+            .get(&variant_num)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn num_malformed_from_peer(&self, player_num: PlayerNum) -> u32 {
+        self.malformed_by_peer
+            .get(&player_num)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn quarantine(&self) -> impl Iterator<Item = &MalformedMsg> {
+        self.quarantine.iter()
+    }
+}
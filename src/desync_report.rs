@@ -0,0 +1,106 @@
+//! Desync detection from per-tick hashes of finalized inputs.
+//!
+//! Every node should finalize identical inputs for every tick. A bug in input handling (or a misbehaving client) can break that silently, and the sims then drift apart. To catch it, each guest periodically sends the host an `InputHashReport` (`get_msg_input_hash_report`): one hash per tick of every player's finalized inputs, for the ticks finalized since its last report. The host checks each hash against its own for the tick (`rx_input_hash_report`), and keeps the ticks that disagree for each guest. A game can read them with `get_desync_report` and resync the guest (e.g. with a full state transfer) instead of letting it diverge.
+//!
+//! The hash is the input digest of `determinism_probe`: 64-bit FNV-1a over each player's `SimInput::Bytes` in bincode's fixed-int encoding, in player order, starting from `INPUT_CHAIN_SEED`.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::util_types::PlayerNum;
+
+/// The most ticks of hashes a guest puts in one report; later ticks go in its next report.
+pub const MAX_INPUT_HASH_REPORT_TICKS: u32 = 240;
+
+/// The most desynced ticks the host keeps for each guest; the earliest are kept, since resyncing starts from them.
+pub const DESYNC_REPORT_CAPACITY: usize = 600;
+
+/// A guest's hashes of every player's finalized inputs, for consecutive ticks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputHashReport {
+    /// The tick of the first hash
+    pub start_tick: u32,
+    /// `hashes[i]` is the hash of the inputs for `start_tick + i`
+    pub hashes: Vec<u64>,
+}
+
+impl InputHashReport {
+    pub fn end_tick(&self) -> u32 {
+        self.start_tick + self.hashes.len() as u32
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u64)> + '_ {
+        (self.start_tick..).zip(self.hashes.iter().copied())
+    }
+}
+
+/// HOST ONLY: the ticks at which each guest's reported input hashes disagreed with the host's, in the current round (see `desync_report`).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DesyncReport {
+    /// For each guest with at least one desynced tick, those ticks in order (at most `DESYNC_REPORT_CAPACITY`)
+    pub peers: BTreeMap<PlayerNum, Vec<u32>>,
+}
+
+impl DesyncReport {
+    /// True if no guest has reported a hash that disagrees with the host's.
+    pub fn is_synced(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// The ticks at which this guest disagreed with the host, in order.
+    pub fn desynced_ticks(&self, peer: PlayerNum) -> &[u32] {
+        self.peers.get(&peer).map_or(&[], Vec::as_slice)
+    }
+
+    /// The earliest tick at which this guest disagreed with the host.
+    pub fn first_desync_tick(&self, peer: PlayerNum) -> Option<u32> {
+        self.desynced_ticks(peer).first().copied()
+    }
+
+    /// The guests that disagreed with the host at any tick.
+    pub fn desynced_peers(&self) -> impl Iterator<Item = PlayerNum> + '_ {
+        self.peers.keys().copied()
+    }
+}
+
+/// The host's record of the desynced ticks reported by each guest.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DesyncTracker {
+    desynced: BTreeMap<PlayerNum, BTreeSet<u32>>,
+    /// Guests for which a desync has already been reported
+    reported: HashSet<PlayerNum>,
+}
+
+impl DesyncTracker {
+    /// Records that this guest's hash for `tick` disagreed with the host's. Returns true the first time it is called for a guest, so that each guest's desync is reported once.
+    pub(crate) fn record(&mut self, peer: PlayerNum, tick: u32) -> bool {
+        let ticks = self.desynced.entry(peer).or_default();
+        ticks.insert(tick);
+        if ticks.len() > DESYNC_REPORT_CAPACITY {
+            ticks.pop_last();
+        }
+        self.reported.insert(peer)
+    }
+
+    pub(crate) fn report(&self) -> DesyncReport {
+        DesyncReport {
+            peers: self
+                .desynced
+                .iter()
+                .map(|(peer, ticks)| (*peer, ticks.iter().copied().collect()))
+                .collect(),
+        }
+    }
+
+    /// Forgets this guest's desynced ticks, e.g. when its seat moves to a new connection.
+    pub(crate) fn remove(&mut self, peer: PlayerNum) {
+        self.desynced.remove(&peer);
+        self.reported.remove(&peer);
+    }
+
+    /// Forgets every desynced tick, e.g. when ticks restart at a new round.
+    pub(crate) fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,173 @@
+//! Per-tick digests for telling input desyncs apart from simulation desyncs.
+//!
+//! Cross-platform lockstep games often desync because the simulation itself isn't deterministic (e.g. float differences between platforms), even though every node finalized identical inputs. To tell the two apart, each node records a sample per tick: a digest of every player's finalized inputs for the tick (computed by the manager), alongside a digest of the game state after simulating it (supplied by the game). Peers exchange samples, and comparing them at the same tick shows whether the inputs diverged, or the state diverged with identical inputs.
+//!
+//! The input digest is 64-bit FNV-1a over each player's `SimInput::Bytes` in bincode's fixed-int encoding, in player order, starting from `INPUT_CHAIN_SEED`. The state digest is opaque to this crate; it only needs to be computed the same way on every node.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input_hash_chain::{INPUT_CHAIN_SEED, encode_input_bytes, fnv1a_64},
+    input_trait::SimInput,
+    util_types::PlayerNum,
+};
+
+/// The most recent samples kept for this node, and for each peer.
+pub const DETERMINISM_PROBE_CAPACITY: usize = 600;
+
+/// The digest of every player's inputs for one tick, in player order.
+pub(crate) fn tick_input_digest<T: SimInput>(inputs: &[T::Bytes]) -> u64 {
+    inputs.iter().fold(INPUT_CHAIN_SEED, |hash, input| {
+        fnv1a_64(hash, &encode_input_bytes::<T>(input))
+    })
+}
+
+/// A node's digests for one tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeterminismSample {
+    pub tick: u32,
+    /// The digest of every player's finalized inputs for the tick
+    pub input_digest: u64,
+    /// The game's digest of its state after simulating the tick
+    pub state_digest: u64,
+}
+
+impl DeterminismSample {
+    /// How `theirs` differs from this sample for the same tick, if at all.
+    fn divergence(&self, theirs: &DeterminismSample) -> Option<DivergenceKind> {
+        if self.input_digest != theirs.input_digest {
+            Some(DivergenceKind::Inputs)
+        } else if self.state_digest != theirs.state_digest {
+            Some(DivergenceKind::State)
+        } else {
+            None
+        }
+    }
+}
+
+/// How two nodes' samples for the same tick disagree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DivergenceKind {
+    /// The nodes finalized different inputs for the tick; look for the bug in input handling.
+    Inputs,
+    /// The nodes finalized identical inputs, but their states differ: the simulation is not deterministic.
+    State,
+}
+
+/// The result of checking a peer's sample against this node's own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeterminismCheck {
+    /// The samples agree.
+    Match,
+    Diverged(DivergenceKind),
+    /// This node has no sample of its own for the tick (yet).
+    Unknown,
+}
+
+/// A comparison of this node's samples against a peer's, over the ticks both have sampled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DeterminismReport {
+    pub num_ticks_compared: u32,
+    pub num_matching: u32,
+    /// The earliest compared tick at which the inputs diverged
+    pub first_input_divergence: Option<u32>,
+    /// The earliest compared tick at which the state diverged while the inputs matched
+    pub first_state_divergence: Option<u32>,
+}
+
+impl DeterminismReport {
+    /// True if every compared tick matched.
+    pub fn is_consistent(&self) -> bool {
+        self.num_matching == self.num_ticks_compared
+    }
+}
+
+/// Recent samples for this node and its peers.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DeterminismProbe {
+    own: BTreeMap<u32, DeterminismSample>,
+    peers: HashMap<PlayerNum, BTreeMap<u32, DeterminismSample>>,
+    /// Peers for which a divergence has already been reported
+    reported: HashSet<PlayerNum>,
+}
+
+fn insert_capped(samples: &mut BTreeMap<u32, DeterminismSample>, sample: DeterminismSample) {
+    samples.insert(sample.tick, sample);
+    if samples.len() > DETERMINISM_PROBE_CAPACITY {
+        samples.pop_first();
+    }
+}
+
+impl DeterminismProbe {
+    pub(crate) fn own_sample(&self, tick: u32) -> Option<DeterminismSample> {
+        self.own.get(&tick).copied()
+    }
+
+    /// Records this node's sample, returning the peers whose sample for the same tick disagrees.
+    pub(crate) fn record_own(
+        &mut self,
+        sample: DeterminismSample,
+    ) -> Vec<(PlayerNum, DivergenceKind)> {
+        insert_capped(&mut self.own, sample);
+        let mut diverged: Vec<(PlayerNum, DivergenceKind)> = self
+            .peers
+            .iter()
+            .filter_map(|(peer, samples)| {
+                let kind = sample.divergence(samples.get(&sample.tick)?)?;
+                Some((*peer, kind))
+            })
+            .collect();
+        diverged.sort_by_key(|(peer, _)| *peer);
+        diverged
+    }
+
+    /// Records a peer's sample, checking it against this node's own.
+    pub(crate) fn record_peer(
+        &mut self,
+        peer: PlayerNum,
+        sample: DeterminismSample,
+    ) -> DeterminismCheck {
+        insert_capped(self.peers.entry(peer).or_default(), sample);
+        match self.own.get(&sample.tick) {
+            Some(own) => own
+                .divergence(&sample)
+                .map_or(DeterminismCheck::Match, DeterminismCheck::Diverged),
+            None => DeterminismCheck::Unknown,
+        }
+    }
+
+    /// Returns true the first time it is called for a peer, so that each peer's divergence is reported once.
+    pub(crate) fn mark_reported(&mut self, peer: PlayerNum) -> bool {
+        self.reported.insert(peer)
+    }
+
+    pub(crate) fn report(&self, peer: PlayerNum) -> DeterminismReport {
+        let mut report = DeterminismReport::default();
+        let Some(theirs) = self.peers.get(&peer) else {
+            return report;
+        };
+        for (tick, own) in &self.own {
+            let Some(their_sample) = theirs.get(tick) else {
+                continue;
+            };
+            report.num_ticks_compared += 1;
+            match own.divergence(their_sample) {
+                None => report.num_matching += 1,
+                Some(DivergenceKind::Inputs) => {
+                    report.first_input_divergence.get_or_insert(*tick);
+                }
+                Some(DivergenceKind::State) => {
+                    report.first_state_divergence.get_or_insert(*tick);
+                }
+            }
+        }
+        report
+    }
+
+    /// Forgets every sample and reported divergence, e.g. when ticks restart at a new round.
+    pub(crate) fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{rx_outcome::RxRejection, util_types::PlayerNum};
+
+/// The most events a guest can have that aren't final yet. The host accepts no more of the guest's events until earlier ones become final, so a guest can't grow the host's channel without bound; the guest resends the refused events.
+pub const MAX_PENDING_EVENTS_PER_PLAYER: u32 = 256;
+
+/// A rarely-sent, tick-stamped event from a player (e.g. an emote or a loadout change), carried on a reliable channel alongside the player's per-tick inputs.
+///
+/// Events keep rarely-used data out of the `SimInput` struct. The contents are opaque to this crate: `kind` is a game-defined tag, and `data` is game-defined bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TickEvent {
+    /// The input index (counted from the session's start tick) at which the event takes effect
+    pub tick: u32,
+    pub kind: u16,
+    pub data: Vec<u8>,
+}
+
+/// A contiguous run of a player's events, starting at the event with sequence number `start_seq`.
+///
+/// Each player's events are numbered from 0 in the order the host accepted them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventSlice {
+    pub player_num: PlayerNum,
+    pub start_seq: u32,
+    pub events: Vec<TickEvent>,
+}
+
+/// One player's events, in sequence order.
+#[derive(Debug, Clone, Default)]
+struct PlayerEvents {
+    events: Vec<TickEvent>,
+    /// The number of events (from the start) whose sequence number and tick the host has confirmed.
+    ///
+    /// Only a guest's own events can be unconfirmed, while they wait to be accepted by the host.
+    num_confirmed: u32,
+}
+
+/// The event channel for all players.
+///
+/// An event is final once the host has confirmed it and the player's inputs are finalized through the event's tick. Since the host never moves an event to a tick that is already finalized, events are final in sequence order.
+#[derive(Debug, Clone)]
+pub(crate) struct EventChannel(Vec<PlayerEvents>);
+
+impl EventChannel {
+    pub(crate) fn new(num_players: u8) -> Self {
+        Self(vec![PlayerEvents::default(); num_players as usize])
+    }
+
+    /// Adds empty channels for new players up to `num_players`.
+    pub(crate) fn add_players_up_to(&mut self, num_players: u8) {
+        if self.0.len() < usize::from(num_players) {
+            self.0
+                .resize(usize::from(num_players), PlayerEvents::default());
+        }
+    }
+
+    fn player(&self, player_num: PlayerNum) -> Option<&PlayerEvents> {
+        self.0.get(usize::from(player_num))
+    }
+
+    fn player_mut(&mut self, player_num: PlayerNum) -> Result<&mut PlayerEvents, RxRejection> {
+        self.0
+            .get_mut(usize::from(player_num))
+            .ok_or(RxRejection::UnknownPlayer)
+    }
+
+    /// The number of the player's events; 0 for a player beyond this channel's players.
+    pub(crate) fn num_events(&self, player_num: PlayerNum) -> u32 {
+        self.player(player_num)
+            .map_or(0, |player| player.events.len() as u32)
+    }
+
+    pub(crate) fn num_confirmed(&self, player_num: PlayerNum) -> u32 {
+        self.player(player_num)
+            .map_or(0, |player| player.num_confirmed)
+    }
+
+    /// The number of confirmed events for the player, given the number of finalized inputs they have.
+    pub(crate) fn num_finalized(&self, player_num: PlayerNum, num_final_inputs: u32) -> u32 {
+        self.player(player_num).map_or(0, |player| {
+            player.events[..player.num_confirmed as usize]
+                .partition_point(|e| e.tick < num_final_inputs) as u32
+        })
+    }
+
+    /// Appends an event, moving its tick no earlier than `min_tick` or the tick of the player's previous event, so that events stay in tick order.
+    ///
+    /// Confirmed events may only be appended while all of the player's events are confirmed.
+    pub(crate) fn push(
+        &mut self,
+        player_num: PlayerNum,
+        mut event: TickEvent,
+        min_tick: u32,
+        confirmed: bool,
+    ) -> Result<(), RxRejection> {
+        let player = self.player_mut(player_num)?;
+        let prev_tick = player.events.last().map_or(0, |e| e.tick);
+        event.tick = event.tick.max(min_tick).max(prev_tick);
+        player.events.push(event);
+        if confirmed {
+            debug_assert_eq!(player.num_confirmed as usize, player.events.len() - 1);
+            player.num_confirmed += 1;
+        }
+        Ok(())
+    }
+
+    /// Stores events confirmed by the host, overwriting any unconfirmed copies (which the host may have moved to a later tick).
+    ///
+    /// Slices that would leave a gap are ignored. Returns the number of newly confirmed events.
+    pub(crate) fn receive_confirmed(&mut self, slice: EventSlice) -> Result<u32, RxRejection> {
+        let player = self.player_mut(slice.player_num)?;
+        if slice.start_seq > player.num_confirmed {
+            return Ok(0);
+        }
+        let before = player.num_confirmed;
+        for (seq, event) in (slice.start_seq..).zip(slice.events) {
+            match player.events.get_mut(seq as usize) {
+                Some(existing) => *existing = event,
+                None => player.events.push(event),
+            }
+            player.num_confirmed = player.num_confirmed.max(seq + 1);
+        }
+        Ok(player.num_confirmed - before)
+    }
+
+    /// The player's events with sequence numbers in `start_seq..end_seq`.
+    pub(crate) fn slice(&self, player_num: PlayerNum, start_seq: u32, end_seq: u32) -> EventSlice {
+        let events = self.events(player_num, self.num_events(player_num));
+        let end = (end_seq as usize).min(events.len());
+        let start = (start_seq as usize).min(end);
+        EventSlice {
+            player_num,
+            start_seq: start as u32,
+            events: events[start..end].to_vec(),
+        }
+    }
+
+    /// The player's first `num_events` events.
+    pub(crate) fn events(&self, player_num: PlayerNum, num_events: u32) -> &[TickEvent] {
+        self.player(player_num)
+            .map_or(&[], |player| &player.events[..num_events as usize])
+    }
+}
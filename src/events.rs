@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+
+use crate::{determinism_probe::DivergenceKind, util_types::PlayerNum};
+
+/// The default number of undrained events a manager holds (see `MultiplayerInputManager::with_max_queued_events`).
+pub const DEFAULT_MAX_QUEUED_EVENTS: usize = 256;
+
+/// Notable things that happened inside an input manager, which the game may want to react to.
+///
+/// Events are queued as they happen and collected with `MultiplayerInputManager::drain_events`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputMgrEvent {
+    /// A peer has sent at least the configured threshold of malformed messages (see `MultiplayerInputManager::with_malformed_msg_threshold`).
+    ///
+    /// This is raised once per peer. Hosts will usually want to disconnect the peer.
+    MalformedMsgThresholdExceeded {
+        player_num: PlayerNum,
+        num_malformed: u32,
+    },
+    /// GUEST ONLY: the guest has learned both the host's tick and its RTT to the host, and now paces its inputs against the host (see `MultiplayerInputManager::is_synced`).
+    ///
+    /// This is raised once, with the host tick known at that moment.
+    GuestSynced { host_tick: i32 },
+    /// GUEST ONLY: the guest received a host tick more than the configured threshold below the newest one it has seen (see `MultiplayerInputManager::with_host_tick_regression_threshold`), which usually means the host restarted.
+    ///
+    /// The lower tick is ignored unless the guest accepts it with `accept_host_tick_reset`. This is raised once, until a reset is accepted.
+    HostTickRegressed {
+        newest_host_tick: i32,
+        host_tick: i32,
+    },
+    /// The rollback depth has reached the configured cap (see `MultiplayerInputManager::with_max_rollback_depth_cap`).
+    ///
+    /// This is raised each time the depth reaches the cap after having been below it.
+    RollbackDepthCapReached { depth: u32 },
+    /// A peer's input chain head disagrees with this node's own chain (see `MultiplayerInputManager::rx_input_chain_head`), so the two have finalized different inputs for the player.
+    InputChainMismatch {
+        sender: PlayerNum,
+        player_num: PlayerNum,
+        num_inputs: u32,
+    },
+    /// This node's own inputs have reached the end of the session (see `MultiplayerInputManager::with_max_session_ticks`), so no more will be collected.
+    ///
+    /// After a graceful end (see `MultiplayerInputManager::end_session_at`), this is instead raised once every player's inputs are finalized up to `end_tick`, so the sim can run to the end and stop. This is raised once per round.
+    SessionEnded { end_tick: u32 },
+    /// A peer's determinism sample disagrees with this node's own for the same tick (see `MultiplayerInputManager::rx_determinism_sample`); `kind` tells whether their inputs diverged, or only their state.
+    ///
+    /// This is raised once per peer per round, for the first divergent tick found.
+    DeterminismDivergence {
+        peer: PlayerNum,
+        tick: u32,
+        kind: DivergenceKind,
+    },
+    /// HOST ONLY: a guest's input hash for `tick` disagrees with the host's (see `MultiplayerInputManager::rx_input_hash_report`), so the guest finalized different inputs than the host and its sim will diverge; `get_desync_report` lists every such tick.
+    ///
+    /// This is raised once per guest per round, for the first desynced tick found.
+    InputDesync { peer: PlayerNum, tick: u32 },
+    /// The host has moved a seat to a new connection (see `MultiplayerInputManager::transfer_seat`); the seat's inputs now come from `connection`.
+    SeatTransferred {
+        seat: PlayerNum,
+        connection: PlayerNum,
+    },
+    /// HOST ONLY: a guest has lagged past the host's lag downgrade policy for its sustained period, and its seat is now filled with default inputs (see `MultiplayerInputManager::poll_lagging_guests`); `ticks_behind` is how many sim ticks behind the host its inputs were.
+    GuestDowngraded {
+        player_num: PlayerNum,
+        ticks_behind: u32,
+    },
+    /// GUEST ONLY: the host has muted a player (see `MultiplayerInputManager::mute_player`), e.g. after downgrading a lagging guest; the player's own inputs are ignored from `from_tick` on.
+    ///
+    /// A guest muted this way stays connected, and can switch to a spectator view of the match.
+    PlayerMuted {
+        player_num: PlayerNum,
+        from_tick: u32,
+    },
+    /// HOST ONLY: the host has rebuilt its finalized history from its guests after a restart (see `MultiplayerInputManager::finish_recovery`), with this many inputs recovered for each player (indexed by player num).
+    HostRecovered { num_finalized: Vec<u32> },
+    /// GUEST ONLY: the host sent a finalized slice for a player beyond this guest's `num_players` (e.g. one who joined mid-session), so the game should process the join and call `MultiplayerInputManager::register_players`.
+    ///
+    /// The slices are held until then (see `unknown_player_slices`). This is raised once per player, until they are registered.
+    UnknownPlayer { player_num: PlayerNum },
+    /// GUEST ONLY: the host has added a player mid-session (see `MultiplayerInputManager::add_player`), whose inputs are defaults before `first_tick`, so the game can spawn them there.
+    PlayerJoined {
+        player_num: PlayerNum,
+        first_tick: u32,
+    },
+    /// GUEST ONLY: the host has removed a player for good (see `MultiplayerInputManager::remove_player`), whose inputs are defaults from `from_tick` on, so the game can despawn them there.
+    PlayerRemoved {
+        player_num: PlayerNum,
+        from_tick: u32,
+    },
+    /// HOST ONLY: a guest acked more finalized inputs of a player than the host has sent it (see `MultiplayerInputManager::rx_finalized_ticks_observations`), which only a buggy or misbehaving client does.
+    ///
+    /// The ack is clamped to `max_sent`, so the host keeps sending from there rather than skipping inputs the guest never received. This is raised for each such player in each such ack.
+    AckBeyondSent {
+        guest: PlayerNum,
+        player_num: PlayerNum,
+        acked: u32,
+        max_sent: u32,
+    },
+    /// A peer slice for this player reached past the max input lead (see `MultiplayerInputManager::with_max_input_lead`), and its last `num_dropped` inputs were dropped; only a buggy or misbehaving peer sends such slices.
+    ///
+    /// This is raised for each such slice.
+    InputsBeyondLead {
+        player_num: PlayerNum,
+        num_dropped: u32,
+    },
+    /// The consumer of a channel from `MultiplayerInputManager::finalized_input_rx` has fallen behind by more than the channel's depth, so `num_lagging` of the player's finalized inputs are waiting to be queued (see `finalized_input_channel`).
+    ///
+    /// No inputs are dropped. This is raised each time the channel falls behind, but not again until it has caught up.
+    FinalizedInputRxLagging {
+        player_num: PlayerNum,
+        num_lagging: u32,
+    },
+    /// A peer encodes inputs with a different schema than this node (see `input_schema`), so its inputs can't be read.
+    ///
+    /// This is raised each time the peer's schema id arrives.
+    InputSchemaMismatch {
+        peer: PlayerNum,
+        ours: u32,
+        theirs: u32,
+    },
+}
+
+/// Events awaiting `drain_events`. Once full, the oldest event is dropped for each new one, and counted.
+#[derive(Debug)]
+pub(crate) struct EventQueue {
+    events: VecDeque<InputMgrEvent>,
+    pub(crate) max_len: usize,
+    num_dropped: u64,
+}
+
+impl Default for EventQueue {
+    fn default() -> Self {
+        Self {
+            events: VecDeque::default(),
+            max_len: DEFAULT_MAX_QUEUED_EVENTS,
+            num_dropped: 0,
+        }
+    }
+}
+
+impl EventQueue {
+    pub(crate) fn push(&mut self, event: InputMgrEvent) {
+        if self.max_len == 0 {
+            self.num_dropped += 1;
+            return;
+        }
+        if self.events.len() >= self.max_len {
+            self.events.pop_front();
+            self.num_dropped += 1;
+        }
+        self.events.push_back(event);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub(crate) fn take(&mut self) -> Vec<InputMgrEvent> {
+        std::mem::take(&mut self.events).into()
+    }
+
+    pub(crate) fn num_dropped(&self) -> u64 {
+        self.num_dropped
+    }
+}
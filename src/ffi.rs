@@ -0,0 +1,407 @@
+//! A C ABI over the input managers, for engines that can't consume Rust generics.
+//!
+//! Inputs are opaque byte arrays of exactly `TIB_INPUT_BYTES` bytes. A manager is owned through an opaque `TibManager` handle, created with `tib_host_new`/`tib_guest_new` and released with `tib_manager_free`.
+//!
+//! Apart from the constructors and `tib_manager_free`, every function returns one of the `TIB_*` status codes: `TIB_OK` (0) on success, a positive code for non-error outcomes, and a negative code for errors. Values are returned through out-pointers.
+//!
+//! Messages produced by the manager (input slices, acks, pong replies, etc.) are queued inside the handle; the engine drains them with `tib_poll_outgoing` and sends them to the indicated recipient over its own transport. Received message bytes are passed to `tib_push_message`.
+//!
+//! The matching C declarations are in `include/temporal_input_buffer.h`.
+
+use std::{
+    collections::VecDeque,
+    panic::{AssertUnwindSafe, catch_unwind},
+    slice,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input_trait::SimInput,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    session::{OutgoingMsgs, Recipient, Session, SessionRxError},
+    util_types::PlayerNum,
+};
+
+/// The size in bytes of a single input passed across the C ABI.
+pub const TIB_INPUT_BYTES: usize = 16;
+
+/// The recipient reported by `tib_poll_outgoing` for messages that must be sent to all peers.
+pub const TIB_BROADCAST: u8 = u8::MAX;
+
+/// Success.
+pub const TIB_OK: i32 = 0;
+/// `tib_poll_outgoing` found no queued message.
+pub const TIB_NO_MESSAGE: i32 = 1;
+/// A required pointer argument was null.
+pub const TIB_ERR_NULL_POINTER: i32 = -1;
+/// An argument was out of range, e.g. an unknown player number or a wrongly sized input.
+pub const TIB_ERR_INVALID_ARGUMENT: i32 = -2;
+/// Received message bytes could not be decoded.
+pub const TIB_ERR_DECODE: i32 = -3;
+/// The output buffer passed to `tib_poll_outgoing` was too small; the required size is written to `out_len`.
+pub const TIB_ERR_BUFFER_TOO_SMALL: i32 = -4;
+/// The call or message is not valid for this manager's role (host or guest).
+pub const TIB_ERR_WRONG_ROLE: i32 = -5;
+/// An internal error occurred. The handle should not be used again, other than to free it.
+pub const TIB_ERR_INTERNAL: i32 = -6;
+/// A guest received a countdown from a host that encodes inputs with a different schema, so it can't join the host's session.
+pub const TIB_ERR_INPUT_SCHEMA_MISMATCH: i32 = -7;
+
+/// The input type used by managers created over the C ABI: an opaque, fixed-size byte array.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FfiInput(pub [u8; TIB_INPUT_BYTES]);
+
+impl SimInput for FfiInput {
+    type Bytes = [u8; TIB_INPUT_BYTES];
+
+    fn to_bytes(&self) -> Self::Bytes {
+        self.0
+    }
+
+    fn from_bytes(bytes: Self::Bytes) -> Self {
+        Self(bytes)
+    }
+}
+
+/// An opaque handle to a host or guest input manager, plus its queue of outgoing messages.
+pub struct TibManager {
+    session: Session<FfiInput>,
+    num_players: u8,
+    /// Serialized messages waiting to be polled, paired with their recipient
+    outbox: VecDeque<(u8, Vec<u8>)>,
+}
+
+impl TibManager {
+    fn new(session: Session<FfiInput>, num_players: u8) -> Self {
+        Self {
+            session,
+            num_players,
+            outbox: VecDeque::default(),
+        }
+    }
+
+    fn queue(&mut self, outgoing: OutgoingMsgs<FfiInput>) {
+        for (recipient, msg) in outgoing {
+            let bytes = self.session.msg_bytes(recipient, &msg);
+            let recipient = match recipient {
+                Recipient::Player(player_num) => player_num.as_u8(),
+                Recipient::AllPeers => TIB_BROADCAST,
+            };
+            self.outbox.push_back((recipient, bytes));
+        }
+    }
+
+    fn player_num(&self, player_num: u8) -> Result<PlayerNum, i32> {
+        if player_num < self.num_players {
+            Ok(PlayerNum(player_num))
+        } else {
+            Err(TIB_ERR_INVALID_ARGUMENT)
+        }
+    }
+
+    /// On the host, fills the host's buffer up to `delta` seconds of sim time with `input` and queues the resulting finalized slices. On a guest, adds a single input and queues it for the host.
+    fn push_local_input(&mut self, input: FfiInput, delta: f32) {
+        let outgoing = self.session.add_own_input(input, delta);
+        self.queue(outgoing);
+    }
+
+    fn rx_message(&mut self, sender: PlayerNum, bytes: &[u8]) -> Result<(), i32> {
+        let outgoing = self
+            .session
+            .rx_bytes(sender, bytes)
+            .map_err(|err| match err {
+                SessionRxError::Decode => TIB_ERR_DECODE,
+                SessionRxError::InvalidSender | SessionRxError::Rejected(_) => {
+                    TIB_ERR_INVALID_ARGUMENT
+                }
+                SessionRxError::WrongRole => TIB_ERR_WRONG_ROLE,
+                SessionRxError::InputSchemaMismatch(_) => TIB_ERR_INPUT_SCHEMA_MISMATCH,
+            })?;
+        self.queue(outgoing);
+        Ok(())
+    }
+}
+
+/// Runs `f` against the manager behind `handle`, converting null handles and panics into status codes.
+///
+/// # Safety
+/// `handle` must be null or a live pointer returned by `tib_host_new`/`tib_guest_new`.
+unsafe fn with_manager(handle: *mut TibManager, f: impl FnOnce(&mut TibManager) -> i32) -> i32 {
+    let Some(mgr) = (unsafe { handle.as_mut() }) else {
+        return TIB_ERR_NULL_POINTER;
+    };
+    catch_unwind(AssertUnwindSafe(|| f(mgr))).unwrap_or(TIB_ERR_INTERNAL)
+}
+
+/// Writes `value` through `out`, if `out` is not null.
+///
+/// # Safety
+/// `out` must be null or valid for writes.
+unsafe fn write_out<V>(out: *mut V, value: V) {
+    if let Some(out) = unsafe { out.as_mut() } {
+        *out = value;
+    }
+}
+
+/// Creates a host manager. Returns null if `num_players` or `ticks_per_sec` is 0.
+#[unsafe(no_mangle)]
+pub extern "C" fn tib_host_new(
+    num_players: u8,
+    max_guest_ticks_behind: u32,
+    max_ticks_to_predict_locf: u32,
+    ticks_per_sec: u32,
+) -> *mut TibManager {
+    if num_players == 0 || num_players == TIB_BROADCAST || ticks_per_sec == 0 {
+        return std::ptr::null_mut();
+    }
+    let host = MultiplayerInputManager::<FfiInput, HostInputMgr>::new(
+        num_players,
+        max_guest_ticks_behind,
+        max_ticks_to_predict_locf,
+        ticks_per_sec,
+    );
+    Box::into_raw(Box::new(TibManager::new(host.into(), num_players)))
+}
+
+/// Creates a guest manager. Returns null if `own_player_num` is not a guest in a session of `num_players`, or if `ticks_per_sec` is 0.
+#[unsafe(no_mangle)]
+pub extern "C" fn tib_guest_new(
+    num_players: u8,
+    own_player_num: u8,
+    ticks_per_sec: u32,
+) -> *mut TibManager {
+    if own_player_num == 0
+        || own_player_num >= num_players
+        || num_players == TIB_BROADCAST
+        || ticks_per_sec == 0
+    {
+        return std::ptr::null_mut();
+    }
+    let guest = MultiplayerInputManager::<FfiInput, GuestInputMgr>::new(
+        num_players,
+        PlayerNum(own_player_num),
+        ticks_per_sec,
+    );
+    Box::into_raw(Box::new(TibManager::new(guest.into(), num_players)))
+}
+
+/// Frees a manager. Passing null is a no-op.
+///
+/// # Safety
+/// `handle` must be null or a pointer returned by `tib_host_new`/`tib_guest_new` that has not already been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tib_manager_free(handle: *mut TibManager) {
+    if !handle.is_null() {
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+/// Adds a local input of exactly `TIB_INPUT_BYTES` bytes.
+///
+/// On the host, `delta_sec` is the time since the last call, and the host's buffer is filled up to the elapsed sim time. On a guest, `delta_sec` is ignored and exactly one input is added; use `tib_guest_num_inputs_needed` to decide how many to push.
+///
+/// # Safety
+/// `handle` must be null or a live manager handle, and `input` must be null or valid for reads of `input_len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tib_push_local_input(
+    handle: *mut TibManager,
+    input: *const u8,
+    input_len: usize,
+    delta_sec: f32,
+) -> i32 {
+    if input.is_null() {
+        return TIB_ERR_NULL_POINTER;
+    }
+    if input_len != TIB_INPUT_BYTES {
+        return TIB_ERR_INVALID_ARGUMENT;
+    }
+    let mut bytes = [0u8; TIB_INPUT_BYTES];
+    bytes.copy_from_slice(unsafe { slice::from_raw_parts(input, input_len) });
+    unsafe {
+        with_manager(handle, |mgr| {
+            mgr.push_local_input(FfiInput(bytes), delta_sec);
+            TIB_OK
+        })
+    }
+}
+
+/// Handles a message received from `sender`, queueing any replies.
+///
+/// # Safety
+/// `handle` must be null or a live manager handle, and `bytes` must be null or valid for reads of `len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tib_push_message(
+    handle: *mut TibManager,
+    sender: u8,
+    bytes: *const u8,
+    len: usize,
+) -> i32 {
+    if bytes.is_null() {
+        return TIB_ERR_NULL_POINTER;
+    }
+    let bytes = unsafe { slice::from_raw_parts(bytes, len) };
+    unsafe {
+        with_manager(handle, |mgr| {
+            let sender = match mgr.player_num(sender) {
+                Ok(sender) => sender,
+                Err(code) => return code,
+            };
+            mgr.rx_message(sender, bytes).err().unwrap_or(TIB_OK)
+        })
+    }
+}
+
+/// Pops the next queued outgoing message into `out_buf`, writing its length to `out_len` and its recipient to `out_recipient` (`TIB_BROADCAST` for messages that go to all peers).
+///
+/// Returns `TIB_NO_MESSAGE` if the queue is empty. If `out_buf_len` is too small, returns `TIB_ERR_BUFFER_TOO_SMALL` with the required size in `out_len`, and leaves the message queued.
+///
+/// # Safety
+/// `handle` must be null or a live manager handle, `out_buf` must be null or valid for writes of `out_buf_len` bytes, and `out_recipient`/`out_len` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tib_poll_outgoing(
+    handle: *mut TibManager,
+    out_recipient: *mut u8,
+    out_buf: *mut u8,
+    out_buf_len: usize,
+    out_len: *mut usize,
+) -> i32 {
+    if out_buf.is_null() || out_len.is_null() {
+        return TIB_ERR_NULL_POINTER;
+    }
+    unsafe {
+        with_manager(handle, |mgr| {
+            let Some((recipient, msg)) = mgr.outbox.front() else {
+                return TIB_NO_MESSAGE;
+            };
+            *out_len = msg.len();
+            if msg.len() > out_buf_len {
+                return TIB_ERR_BUFFER_TOO_SMALL;
+            }
+            write_out(out_recipient, *recipient);
+            slice::from_raw_parts_mut(out_buf, msg.len()).copy_from_slice(msg);
+            mgr.outbox.pop_front();
+            TIB_OK
+        })
+    }
+}
+
+/// GUEST ONLY: queues a ping to the host, used to estimate RTT.
+///
+/// # Safety
+/// `handle` must be null or a live manager handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tib_guest_send_ping(handle: *mut TibManager) -> i32 {
+    unsafe {
+        with_manager(handle, |mgr| {
+            let Some(guest) = mgr.session.as_guest_mut() else {
+                return TIB_ERR_WRONG_ROLE;
+            };
+            let ping = guest.get_msg_guest_ping();
+            mgr.queue(vec![(Recipient::Player(HOST_PLAYER_NUM), ping)]);
+            TIB_OK
+        })
+    }
+}
+
+/// GUEST ONLY: writes the number of local inputs the guest should push this frame to keep pace with the host.
+///
+/// # Safety
+/// `handle` must be null or a live manager handle, and `out` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tib_guest_num_inputs_needed(
+    handle: *mut TibManager,
+    out: *mut u32,
+) -> i32 {
+    unsafe {
+        with_manager(handle, |mgr| {
+            let Some(guest) = mgr.session.as_guest() else {
+                return TIB_ERR_WRONG_ROLE;
+            };
+            write_out(out, guest.num_inputs_needed());
+            TIB_OK
+        })
+    }
+}
+
+/// Writes the tick up to which the simulation can be snapshotted (see `MultiplayerInputManager::get_snapshottable_sim_tick`).
+///
+/// # Safety
+/// `handle` must be null or a live manager handle, and `out` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tib_snapshottable_tick(handle: *mut TibManager, out: *mut u32) -> i32 {
+    unsafe {
+        with_manager(handle, |mgr| {
+            write_out(out, mgr.session.get_snapshottable_sim_tick());
+            TIB_OK
+        })
+    }
+}
+
+/// Writes the number of inputs held for `player_num`, and how many of those are finalized.
+///
+/// # Safety
+/// `handle` must be null or a live manager handle, and `out_total`/`out_finalized` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tib_num_inputs(
+    handle: *mut TibManager,
+    player_num: u8,
+    out_total: *mut u32,
+    out_finalized: *mut u32,
+) -> i32 {
+    unsafe {
+        with_manager(handle, |mgr| {
+            let player_num = match mgr.player_num(player_num) {
+                Ok(player_num) => player_num,
+                Err(code) => return code,
+            };
+            let total = mgr.session.get_peer_num_inputs(player_num);
+            let finalized = mgr.session.get_peer_num_final_inputs(player_num);
+            write_out(out_total, total);
+            write_out(out_finalized, finalized);
+            TIB_OK
+        })
+    }
+}
+
+/// Writes the input for `player_num` at `tick` into `out_input`, which must hold `TIB_INPUT_BYTES` bytes. Inputs that have not been received are predicted.
+///
+/// `out_finalized` is set to whether the input has been finalized.
+///
+/// # Safety
+/// `handle` must be null or a live manager handle, `out_input` must be null or valid for writes of `out_input_len` bytes, and `out_finalized` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn tib_get_input(
+    handle: *mut TibManager,
+    player_num: u8,
+    tick: u32,
+    out_input: *mut u8,
+    out_input_len: usize,
+    out_finalized: *mut bool,
+) -> i32 {
+    if out_input.is_null() {
+        return TIB_ERR_NULL_POINTER;
+    }
+    if out_input_len != TIB_INPUT_BYTES {
+        return TIB_ERR_INVALID_ARGUMENT;
+    }
+    unsafe {
+        with_manager(handle, |mgr| {
+            let player_num = match mgr.player_num(player_num) {
+                Ok(player_num) => player_num,
+                Err(code) => return code,
+            };
+            let input = mgr.session.get_peer_input_for_tick(player_num, tick);
+            let finalized = mgr
+                .session
+                .get_input_statuses(tick)
+                .iter()
+                .any(|(p, status)| *p == player_num && status.is_finalized());
+            slice::from_raw_parts_mut(out_input, TIB_INPUT_BYTES).copy_from_slice(&input.0);
+            write_out(out_finalized, finalized);
+            TIB_OK
+        })
+    }
+}
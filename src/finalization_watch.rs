@@ -0,0 +1,96 @@
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+#[derive(Debug, Default)]
+struct WatchState {
+    finalized: bool,
+    #[cfg(feature = "async")]
+    waker: Option<Waker>,
+}
+
+/// Resolves once the inputs for a tick are finalized for all players (see `MultiplayerInputManager::notify_when_finalized`).
+///
+/// Poll it with `is_finalized`, or (with the `async` feature) await it. Clones share the same state.
+#[derive(Debug, Clone)]
+pub struct FinalizationHandle {
+    tick: u32,
+    state: Arc<Mutex<WatchState>>,
+}
+
+impl FinalizationHandle {
+    /// The sim tick this handle is waiting on.
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    pub fn is_finalized(&self) -> bool {
+        self.state.lock().unwrap().finalized
+    }
+
+    fn resolve(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.finalized = true;
+        #[cfg(feature = "async")]
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Resolves to the watched tick.
+#[cfg(feature = "async")]
+impl Future for FinalizationHandle {
+    type Output = u32;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u32> {
+        let mut state = self.state.lock().unwrap();
+        if state.finalized {
+            Poll::Ready(self.tick)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// The handles that are still waiting for their tick to be finalized.
+#[derive(Debug, Default)]
+pub(crate) struct FinalizationWatchers(Vec<FinalizationHandle>);
+
+impl FinalizationWatchers {
+    /// Creates a handle for `tick`, already resolved if `snapshottable_tick` is past it.
+    pub(crate) fn watch(&mut self, tick: u32, snapshottable_tick: u32) -> FinalizationHandle {
+        let handle = FinalizationHandle {
+            tick,
+            state: Arc::default(),
+        };
+        if tick < snapshottable_tick {
+            handle.resolve();
+        } else {
+            self.0.push(handle.clone());
+        }
+        handle
+    }
+
+    /// Resolves (and stops tracking) every handle whose tick is before `snapshottable_tick`.
+    pub(crate) fn resolve_before(&mut self, snapshottable_tick: u32) {
+        self.0.retain(|handle| {
+            if handle.tick < snapshottable_tick {
+                handle.resolve();
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    pub(crate) fn num_pending(&self) -> usize {
+        self.0.len()
+    }
+}
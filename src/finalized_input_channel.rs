@@ -0,0 +1,212 @@
+//! Per-player channels of finalized inputs, for game loops that consume each input exactly once and in order, e.g. a dedicated server's sim on another thread.
+//!
+//! `MultiplayerInputManager::finalized_input_rx` returns a `FinalizedInputRx` for a player. It yields `(tick, input)` for each of the player's finalized inputs in order, starting with the first input of the round. The manager feeds every open channel as inputs are finalized.
+//!
+//! The manager never queues more than the channel's depth (see `with_finalized_input_rx_depth`). When the consumer falls behind, the rest of its inputs wait in the buffers rather than being dropped, so it still sees every input once. While it is behind, the receiver reports how many inputs are waiting (`num_lagging`), and `InputMgrEvent::FinalizedInputRxLagging` is raised each time it falls behind.
+//!
+//! A channel closes when its round ends (see `start_new_round`) or its manager is dropped. The inputs already queued can still be received once it has closed. With the `async` feature, a receiver is also a `Stream` that ends once its channel has closed and been drained.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(feature = "async")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use crate::{
+    input_trait::SimInput, multiplayer_input_buffer::MultiplayerInputBuffers, util_types::PlayerNum,
+};
+
+/// The default depth of the channels returned by `MultiplayerInputManager::finalized_input_rx`: 4 seconds of inputs at 60hz.
+pub const DEFAULT_FINALIZED_INPUT_RX_DEPTH: u32 = 240;
+
+#[derive(Debug)]
+struct ChannelState<T> {
+    queue: VecDeque<(u32, T)>,
+    /// The finalized inputs waiting in the buffers because the queue was full when last fed
+    num_lagging: u32,
+    closed: bool,
+    #[cfg(feature = "async")]
+    waker: Option<Waker>,
+}
+
+impl<T> Default for ChannelState<T> {
+    fn default() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            num_lagging: 0,
+            closed: false,
+            #[cfg(feature = "async")]
+            waker: None,
+        }
+    }
+}
+
+impl<T> ChannelState<T> {
+    #[cfg(feature = "async")]
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn wake(&mut self) {}
+}
+
+/// Receives one player's finalized inputs as `(tick, input)`, in order (see `MultiplayerInputManager::finalized_input_rx`).
+///
+/// Receivers can be sent to and polled from another thread.
+#[derive(Debug)]
+pub struct FinalizedInputRx<T> {
+    player_num: PlayerNum,
+    depth: u32,
+    state: Arc<Mutex<ChannelState<T>>>,
+}
+
+impl<T> FinalizedInputRx<T> {
+    pub fn player_num(&self) -> PlayerNum {
+        self.player_num
+    }
+
+    /// The most inputs queued for this receiver at once.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Takes the next finalized input and its sim tick, if one is queued.
+    pub fn try_recv(&self) -> Option<(u32, T)> {
+        self.state.lock().unwrap().queue.pop_front()
+    }
+
+    /// The number of inputs queued for this receiver.
+    pub fn num_queued(&self) -> u32 {
+        self.state.lock().unwrap().queue.len() as u32
+    }
+
+    /// The number of finalized inputs waiting in the manager because the queue was full when the manager last fed it; 0 unless the consumer has fallen behind.
+    pub fn num_lagging(&self) -> u32 {
+        self.state.lock().unwrap().num_lagging
+    }
+
+    /// True once the round has ended or the manager has been dropped; no more inputs will be queued.
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+}
+
+/// Yields the finalized inputs in order, ending once the channel has closed and every queued input has been received.
+#[cfg(feature = "async")]
+impl<T> futures_core::Stream for FinalizedInputRx<T> {
+    type Item = (u32, T);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<(u32, T)>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(input) = state.queue.pop_front() {
+            Poll::Ready(Some(input))
+        } else if state.closed {
+            Poll::Ready(None)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FinalizedInputTx<T> {
+    player_num: PlayerNum,
+    depth: u32,
+    /// The index of the next finalized input to queue
+    next_index: u32,
+    state: Arc<Mutex<ChannelState<T>>>,
+}
+
+/// The sending ends of the open channels.
+#[derive(Debug)]
+pub(crate) struct FinalizedInputTxs<T>(Vec<FinalizedInputTx<T>>);
+
+impl<T> Default for FinalizedInputTxs<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T: SimInput> FinalizedInputTxs<T> {
+    /// Opens a channel for this player's finalized inputs, from the first input of the round.
+    pub(crate) fn open(&mut self, player_num: PlayerNum, depth: u32) -> FinalizedInputRx<T> {
+        let state = Arc::new(Mutex::new(ChannelState::default()));
+        self.0.push(FinalizedInputTx {
+            player_num,
+            depth,
+            next_index: 0,
+            state: state.clone(),
+        });
+        FinalizedInputRx {
+            player_num,
+            depth,
+            state,
+        }
+    }
+
+    /// Queues each channel's newly finalized inputs, up to its depth, and stops feeding channels whose receiver was dropped. Returns each channel that has just fallen behind, with the number of inputs waiting.
+    pub(crate) fn feed(&mut self, buffers: &MultiplayerInputBuffers<T>) -> Vec<(PlayerNum, u32)> {
+        self.0.retain(|tx| Arc::strong_count(&tx.state) > 1);
+        let mut newly_lagging = Vec::new();
+        for tx in &mut self.0 {
+            let num_finalized = buffers.get_num_finalized_inputs(tx.player_num);
+            let mut state = tx.state.lock().unwrap();
+            let num_queued_before = state.queue.len();
+            while tx.next_index < num_finalized && state.queue.len() < tx.depth as usize {
+                let input = buffers.get_input_or_prediction(tx.player_num, tx.next_index);
+                state
+                    .queue
+                    .push_back((buffers.tick_of_input(tx.next_index), input));
+                tx.next_index += 1;
+            }
+            let num_lagging = num_finalized - tx.next_index;
+            if num_lagging > 0 && state.num_lagging == 0 {
+                newly_lagging.push((tx.player_num, num_lagging));
+            }
+            state.num_lagging = num_lagging;
+            if state.queue.len() > num_queued_before {
+                state.wake();
+            }
+        }
+        newly_lagging
+    }
+
+    pub(crate) fn num_open(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The index of the next input to queue for this player, over all of the player's open channels; `None` if none are open.
+    pub(crate) fn next_index(&self, player_num: PlayerNum) -> Option<u32> {
+        self.0
+            .iter()
+            .filter(|tx| tx.player_num == player_num)
+            .map(|tx| tx.next_index)
+            .min()
+    }
+}
+
+impl<T> FinalizedInputTxs<T> {
+    /// Closes every channel, e.g. when the round ends.
+    pub(crate) fn close_all(&mut self) {
+        for tx in self.0.drain(..) {
+            let mut state = tx.state.lock().unwrap();
+            state.closed = true;
+            state.wake();
+        }
+    }
+}
+
+impl<T> Drop for FinalizedInputTxs<T> {
+    fn drop(&mut self) {
+        self.close_all();
+    }
+}
@@ -1,5 +1,19 @@
+use serde::{Deserialize, Serialize};
+
 use super::{peerwise_finalized_input::PeerwiseFinalizedInputsSeen, util_types::PlayerNum};
 
+/// The guests holding back the host's finalized slices for one peer: those that have acked the fewest of that peer's finalized inputs.
+///
+/// Since the host broadcasts each peer's finalized inputs starting from the minimum acked by any guest, these guests determine how much every guest is re-sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObservationBlocker {
+    pub peer: PlayerNum,
+    /// The fewest finalized inputs for `peer` acked by any guest
+    pub min_acked: u32,
+    /// Every guest that has acked only `min_acked`, sorted by player_num
+    pub guests: Vec<PlayerNum>,
+}
+
 /// Tracks the number of finalized input ticks that each GUEST has acked for each other peer, including the host. This is used to determine how many inputs the host needs to broadcast upon RXing inputs from a peer (including the host itself).
 ///
 /// An instance of this struct is owned by the HOST. Guests do not need to track this information.
@@ -8,27 +22,67 @@ use super::{peerwise_finalized_input::PeerwiseFinalizedInputsSeen, util_types::P
 /// Keys: player_num of GUEST
 /// Values: the PeerwiseFinalizedInput of for each other peer,
 /// as seen by this GUEST.
-pub struct FinalizedObservationsPerGuest(Vec<PeerwiseFinalizedInputsSeen>);
+///
+/// Guests that have been removed (see `remove_guest`) are no longer tracked, so they hold nothing back.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FinalizedObservationsPerGuest(Vec<Option<PeerwiseFinalizedInputsSeen>>);
 
 impl FinalizedObservationsPerGuest {
     pub fn new(num_players: u8) -> Self {
-        let num_guests = num_players - 1;
-        let vec = (0..num_guests)
-            .map(|_guest_idx| PeerwiseFinalizedInputsSeen::new(num_players))
+        let vec = PlayerNum::iter_guests(num_players)
+            .map(|_guest| Some(PeerwiseFinalizedInputsSeen::new(num_players)))
             .collect::<Vec<_>>();
         Self(vec)
     }
 
+    // every guest still tracked, with its observation; the guest's player
+    // num is derived from its own index, so removed guests leave no gap
+    fn tracked(&self) -> impl Iterator<Item = (PlayerNum, &PeerwiseFinalizedInputsSeen)> {
+        self.0.iter().enumerate().filter_map(|(idx, seen)| {
+            let guest = PlayerNum::from_guest_index(idx).expect("at most 254 guests are tracked");
+            Some((guest, seen.as_ref()?))
+        })
+    }
+
     /// For the target player_num, get the minimum number of finalized inputs observed by any guest for that player_num.
     ///
     /// Since every guest will have observed at least this many many finalized inputs for the the target player_num, if the host sends a finalized input slice to all players starting from this tick, then all guests will be able to up to the end of that slice withuout leaving gaps.
     pub(super) fn get_earliest_num_observed_final_for_peer(&self, player_num: PlayerNum) -> u32 {
-        self.0.iter().map(|v| v.get(player_num)).min().unwrap_or(0)
+        self.tracked()
+            .map(|(_, seen)| seen.get(player_num))
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Like `get_earliest_num_observed_final_for_peer`, but only over the guests for which `include` is true. `None` if it excludes every guest.
+    pub(super) fn get_earliest_num_observed_final_for_peer_among(
+        &self,
+        player_num: PlayerNum,
+        include: impl Fn(PlayerNum) -> bool,
+    ) -> Option<u32> {
+        self.tracked()
+            .filter(|(guest, _)| include(*guest))
+            .map(|(_, seen)| seen.get(player_num))
+            .min()
+    }
+
+    /// The number of finalized inputs this guest has acked for the target player_num.
+    pub(super) fn get_guest_observation(
+        &self,
+        guest_player_num: PlayerNum,
+        player_num: PlayerNum,
+    ) -> u32 {
+        guest_player_num
+            .guest_index()
+            .and_then(|idx| self.0.get(idx)?.as_ref())
+            .map_or(0, |seen| seen.get(player_num))
     }
 
     /// Update the observation for a given guest player_num with a new PeerwiseFinalizedInputsSeen.
     ///
-    /// In case observations arrive out of order, we merge the new observation with the existing one, keeping the maximum tick observed for each peer. FIXME: see comment in PeerwiseFinalizedInputsSeen::merge_needs_to_be_fixed about a bug that caused us to have to use the "needs_to_be_fixed" version of merge.
+    /// In case observations arrive out of order, we merge the new observation with the existing one, keeping the maximum tick observed for each peer. A guest that has reinitialized its buffers is reset with `reset_guest_observation` instead.
+    ///
+    /// Observations from removed guests are ignored.
     pub fn update_guest_observation(
         &mut self,
         guest_player_num: PlayerNum,
@@ -38,7 +92,70 @@ impl FinalizedObservationsPerGuest {
             .guest_index()
             .expect("not a guest player_num");
 
-        self.0[guest_idx].merge_needs_to_be_fixed(observation);
+        if let Some(seen) = &mut self.0[guest_idx] {
+            seen.merge(observation);
+        }
+    }
+
+    /// Forgets this guest's acks, e.g. when its seat moves to a new connection that has received nothing yet, or the guest has reinitialized its buffers.
+    pub(crate) fn reset_guest_observation(&mut self, guest_player_num: PlayerNum, num_players: u8) {
+        if let Some(seen) = guest_player_num
+            .guest_index()
+            .and_then(|idx| self.0.get_mut(idx)?.as_mut())
+        {
+            *seen = PeerwiseFinalizedInputsSeen::new(num_players);
+        }
+    }
+
+    /// Stops tracking this guest's acks for good, e.g. when the player has left the session.
+    pub(crate) fn remove_guest(&mut self, guest_player_num: PlayerNum) {
+        if let Some(seen) = guest_player_num
+            .guest_index()
+            .and_then(|idx| self.0.get_mut(idx))
+        {
+            *seen = None;
+        }
+    }
+
+    /// Adds guests up to `num_players`, each having acked nothing. Guests already present count nothing acked for the new players.
+    pub(crate) fn add_guests_up_to(&mut self, num_players: u8) {
+        while self.0.len() < usize::from(num_players.saturating_sub(1)) {
+            self.0
+                .push(Some(PeerwiseFinalizedInputsSeen::new(num_players)));
+        }
+    }
+
+    /// For each guest still tracked, the number of finalized inputs that guest has acked for every peer, sorted by player_num.
+    pub(crate) fn observations_by_guest(&self) -> Vec<(PlayerNum, Vec<(PlayerNum, u32)>)> {
+        self.tracked()
+            .map(|(guest, seen)| {
+                let mut acked = seen.inner().into_iter().collect::<Vec<_>>();
+                acked.sort_by_key(|(p, _)| *p);
+                (guest, acked)
+            })
+            .collect()
+    }
+
+    /// For each peer (including the host), the guests with the minimum ack; empty if no guests are tracked.
+    pub(crate) fn blockers(&self, num_players: u8) -> Vec<ObservationBlocker> {
+        if self.tracked().next().is_none() {
+            return vec![];
+        }
+        PlayerNum::iter(num_players)
+            .map(|peer| {
+                let min_acked = self.get_earliest_num_observed_final_for_peer(peer);
+                let guests = self
+                    .tracked()
+                    .filter(|(_, seen)| seen.get(peer) == min_acked)
+                    .map(|(guest, _)| guest)
+                    .collect();
+                ObservationBlocker {
+                    peer,
+                    min_acked,
+                    guests,
+                }
+            })
+            .collect()
     }
 }
 
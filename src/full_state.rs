@@ -0,0 +1,115 @@
+//! Saving and restoring a manager's full state, so that a session can be resumed by a new process.
+//!
+//! Unlike host recovery (see `host_recovery`), which rebuilds a restarted host's finalized history from its guests, a saved state also carries the host's record of what each guest has acked and been sent, so the resumed host carries on sending each guest only what it is missing.
+//!
+//! With the `compression` feature enabled, saved states are compressed like messages: once they serialize to at least `DEFAULT_COMPRESSION_THRESHOLD_BYTES`, with `COMPRESSED_FLAG` set on the version byte.
+//!
+//! The restoring manager must be constructed with the same role, own player num and tick rate as the one that saved the state. Local settings and statistics (RTT estimates, archived rounds, config) are not saved.
+
+use std::{borrow::Cow, collections::HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    finalized_observations_per_guest::FinalizedObservationsPerGuest,
+    input_messages::{COMPRESSED_FLAG, decompress_payload, frame_msg_bytes},
+    input_trait::SimInput,
+    multiplayer_input_buffer::MultiplayerInputBuffers,
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    util_types::PlayerNum,
+};
+
+/// The first bytes of every saved state.
+const FULL_STATE_MAGIC: [u8; 4] = *b"TIBS";
+/// Bumped whenever the layout of `FullState` changes. `COMPRESSED_FLAG` is set on the version byte of compressed states.
+const FULL_STATE_VERSION: u8 = 2;
+
+/// The state that differs between the two roles.
+#[derive(Serialize, Deserialize)]
+pub(crate) enum RoleState {
+    Host {
+        sim_time: f64,
+        guests_finalized_observations: FinalizedObservationsPerGuest,
+        /// The finalized slices recorded as sent to each guest, which bound its acks
+        finalized_sent: HashMap<PlayerNum, Vec<Option<u32>>>,
+    },
+    Guest {
+        host_tick: Option<i32>,
+        last_acked_finalized: PeerwiseFinalizedInputsSeen,
+    },
+}
+
+impl RoleState {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            RoleState::Host { .. } => "host",
+            RoleState::Guest { .. } => "guest",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub(crate) struct FullState<T: SimInput> {
+    pub(crate) own_player_num: PlayerNum,
+    pub(crate) ticks_per_sec: u32,
+    pub(crate) round: u32,
+    pub(crate) buffers: MultiplayerInputBuffers<T>,
+    pub(crate) role: RoleState,
+}
+
+impl<T: SimInput> FullState<T> {
+    pub(crate) fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let body = bincode::serde::encode_to_vec(self, bincode::config::standard())
+            .map_err(|e| format!("failed to encode full state: {e}"))?;
+        let mut bytes = FULL_STATE_MAGIC.to_vec();
+        bytes.extend(frame_msg_bytes(FULL_STATE_VERSION, body));
+        Ok(bytes)
+    }
+
+    /// Decodes a saved state, checking that it was saved by a manager with the given own player num and tick rate.
+    pub(crate) fn from_bytes(
+        bytes: &[u8],
+        own_player_num: PlayerNum,
+        ticks_per_sec: u32,
+    ) -> Result<Self, String> {
+        if bytes.get(..4) != Some(FULL_STATE_MAGIC.as_slice()) {
+            return Err("missing full state magic bytes".into());
+        }
+        let header = bytes[4..]
+            .first()
+            .copied()
+            .ok_or("missing full state version")?;
+        let version = header & !COMPRESSED_FLAG;
+        if version != FULL_STATE_VERSION {
+            return Err(format!("unsupported full state version {version}"));
+        }
+        let body = if header & COMPRESSED_FLAG != 0 {
+            Cow::Owned(
+                decompress_payload(&bytes[5..])
+                    .map_err(|e| format!("failed to decompress full state: {e}"))?,
+            )
+        } else {
+            Cow::Borrowed(&bytes[5..])
+        };
+        let (state, len): (Self, usize) =
+            bincode::serde::decode_from_slice(&body, bincode::config::standard())
+                .map_err(|e| format!("failed to decode full state: {e}"))?;
+        if len != body.len() {
+            return Err("trailing bytes after full state".into());
+        }
+        if state.own_player_num != own_player_num {
+            return Err(format!(
+                "full state was saved by player {}, not player {own_player_num}",
+                state.own_player_num
+            ));
+        }
+        if state.ticks_per_sec != ticks_per_sec {
+            return Err(format!(
+                "full state was saved at {} ticks per sec, not {ticks_per_sec}",
+                state.ticks_per_sec
+            ));
+        }
+        Ok(state)
+    }
+}
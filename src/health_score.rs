@@ -0,0 +1,153 @@
+//! A single 0–100 connection quality score per guest, for showing players a simple indicator.
+//!
+//! The host combines five signals for each guest (see `HealthComponents`). Each is mapped to a penalty that rises linearly from 0 at a "good" bound to 1 at a "bad" bound, and weighted:
+//!
+//! - RTT: 50ms to 300ms, weight 30.
+//! - Jitter (the smoothed deviation of RTT samples from the smoothed RTT): 5ms to 50ms, weight 15.
+//! - Loss (the smoothed fraction of the guest's pings that never arrived, from gaps in their ids): 0% to 10%, weight 25.
+//! - Finalization lag (how far the guest's inputs are behind the host's tick): 2 to 30 ticks, weight 20.
+//! - Stalls (catch-ups the host sent the guest within the last `STALL_WINDOW_SEC` of sim time): 0 to 3, weight 10.
+//!
+//! The raw score is 100 less the weighted penalties, rounded. Signals that haven't been measured yet (e.g. RTT before the first ping cycle) add no penalty.
+//!
+//! So that an indicator doesn't flicker, the reported score only follows the raw score once the two differ by at least `HEALTH_HYSTERESIS_POINTS`, or once the raw score reaches 0 or 100.
+
+use std::collections::VecDeque;
+
+use crate::ewma::Ewma;
+
+/// How far the raw score must move from the reported one before the reported score follows it.
+pub const HEALTH_HYSTERESIS_POINTS: u8 = 5;
+
+/// The window (sec of sim time) over which catch-ups count as recent stalls.
+pub const STALL_WINDOW_SEC: f64 = 10.0;
+
+/// The weight of each new ping in the smoothed loss fraction.
+const LOSS_SMOOTHING: f32 = 0.1;
+
+/// The most pings counted as lost from a single gap in ping ids, so that a jump in ids (e.g. a guest that restarted its ping ids) can't swamp the estimate.
+const MAX_LOST_PINGS_PER_GAP: u32 = 16;
+
+/// (good bound, bad bound, weight) for each signal, in the order of the module docs.
+const RTT_MS: (f32, f32, f32) = (50.0, 300.0, 30.0);
+const JITTER_MS: (f32, f32, f32) = (5.0, 50.0, 15.0);
+const LOSS: (f32, f32, f32) = (0.0, 0.1, 25.0);
+const TICKS_BEHIND: (f32, f32, f32) = (2.0, 30.0, 20.0);
+const RECENT_STALLS: (f32, f32, f32) = (0.0, 3.0, 10.0);
+
+/// The raw signals behind a guest's health score.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HealthComponents {
+    /// The host's smoothed RTT to the guest (ms); `None` until a ping cycle completes
+    pub rtt_ms: Option<f32>,
+    /// The smoothed deviation of RTT samples from the smoothed RTT (ms); `None` until two ping cycles complete
+    pub jitter_ms: Option<f32>,
+    /// The smoothed fraction (0 to 1) of the guest's pings that never reached the host; `None` until a ping arrives
+    pub loss: Option<f32>,
+    /// How many inputs the guest's collected inputs are behind the host's tick
+    pub ticks_behind: u32,
+    /// The number of catch-ups the host has sent the guest within the last `STALL_WINDOW_SEC` of sim time
+    pub recent_stalls: u32,
+    /// The number of catch-ups the host has sent the guest this match
+    pub num_stalls: u32,
+}
+
+impl HealthComponents {
+    /// The score from these components alone, without hysteresis.
+    pub fn raw_score(&self) -> u8 {
+        let penalty = [
+            (self.rtt_ms, RTT_MS),
+            (self.jitter_ms, JITTER_MS),
+            (self.loss, LOSS),
+            (Some(self.ticks_behind as f32), TICKS_BEHIND),
+            (Some(self.recent_stalls as f32), RECENT_STALLS),
+        ]
+        .into_iter()
+        .filter_map(|(value, (good, bad, weight))| {
+            Some(weight * ((value? - good) / (bad - good)).clamp(0.0, 1.0))
+        })
+        .sum::<f32>();
+        (100.0 - penalty).round().clamp(0.0, 100.0) as u8
+    }
+}
+
+/// A guest's connection quality (see `health_score`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HealthScore {
+    /// From 0 (unusable) to 100 (perfect), with hysteresis
+    pub score: u8,
+    pub components: HealthComponents,
+}
+
+/// The host's health bookkeeping for one guest.
+#[derive(Debug, Default)]
+pub(crate) struct PeerHealth {
+    last_ping_id: Option<u32>,
+    loss: Option<Ewma>,
+    /// The sim times of recent catch-ups, oldest first
+    stall_times: VecDeque<f64>,
+    num_stalls: u32,
+    /// The last reported score
+    score: Option<u8>,
+}
+
+impl PeerHealth {
+    /// Counts the pings skipped since the last one received as lost.
+    ///
+    /// A ping with an id at or below the last one (reordered, or from a guest that restarted its ids) just moves the id on.
+    pub(crate) fn observe_ping(&mut self, ping_id: u32) {
+        let num_lost = match self.last_ping_id {
+            Some(last) if ping_id > last => (ping_id - last - 1).min(MAX_LOST_PINGS_PER_GAP),
+            _ => 0,
+        };
+        self.last_ping_id = Some(ping_id);
+        let loss = self
+            .loss
+            .get_or_insert_with(|| Ewma::new_with_value(LOSS_SMOOTHING, 0.0));
+        for _ in 0..num_lost {
+            loss.observe(1.0);
+        }
+        loss.observe(0.0);
+    }
+
+    pub(crate) fn observe_stall(&mut self, sim_time: f64) {
+        self.stall_times.push_back(sim_time);
+        self.num_stalls += 1;
+    }
+
+    /// Forgets recent stalls, e.g. when sim time restarts at a new round.
+    pub(crate) fn clear_recent_stalls(&mut self) {
+        self.stall_times.clear();
+    }
+
+    pub(crate) fn loss(&self) -> Option<f32> {
+        self.loss.as_ref().map(Ewma::value)
+    }
+
+    /// The number of stalls within `STALL_WINDOW_SEC` of `sim_time`, and the total, forgetting older stalls.
+    pub(crate) fn stalls(&mut self, sim_time: f64) -> (u32, u32) {
+        while self
+            .stall_times
+            .front()
+            .is_some_and(|&time| time < sim_time - STALL_WINDOW_SEC)
+        {
+            self.stall_times.pop_front();
+        }
+        (self.stall_times.len() as u32, self.num_stalls)
+    }
+
+    /// Scores `components`, moving the reported score only past the hysteresis band.
+    pub(crate) fn score(&mut self, components: HealthComponents) -> HealthScore {
+        let raw = components.raw_score();
+        let score = match self.score {
+            Some(shown)
+                if shown.abs_diff(raw) < HEALTH_HYSTERESIS_POINTS && raw != 0 && raw != 100 =>
+            {
+                shown
+            }
+            _ => raw,
+        };
+        self.score = Some(score);
+        HealthScore { score, components }
+    }
+}
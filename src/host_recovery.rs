@@ -0,0 +1,68 @@
+//! Rebuilding a restarted host's finalized history from its guests.
+//!
+//! If a dedicated-server host process restarts mid-match, its buffers are lost, but every guest still holds the inputs the host had finalized and sent it. A fresh host calls `MultiplayerInputManager::begin_recovery` and broadcasts the returned `HostToLobbyRecoveryRequest`; each guest replies with a `GuestToHostRecoveryResponse` carrying its finalized inputs for every player. Once every connected guest has replied (or the host calls `finish_recovery`), the host reconciles the responses, keeping for each player the longest prefix of inputs on which all the guests that have them agree, finalizes that history, and resumes from there.
+//!
+//! The restarted host must be constructed with the same configuration (players, start tick, tick rate) as the original.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input_trait::SimInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+/// A guest's finalized inputs for every player, in reply to a recovery request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryResponse<T: SimInput> {
+    /// The id of the request being answered
+    pub request_id: u32,
+    /// For each player (indexed by player num), all of their inputs finalized on the guest
+    pub finalized: Vec<PlayerInputSlice<T>>,
+}
+
+/// The responses collected so far by a recovering host.
+#[derive(Debug)]
+pub(crate) struct HostRecovery<T: SimInput> {
+    pub(crate) request_id: u32,
+    pub(crate) responses: HashMap<PlayerNum, Vec<PlayerInputSlice<T>>>,
+}
+
+impl<T: SimInput> HostRecovery<T> {
+    pub(crate) fn new(request_id: u32) -> Self {
+        Self {
+            request_id,
+            responses: HashMap::default(),
+        }
+    }
+
+    /// For each player, the longest prefix of finalized inputs that at least one guest has, and on which every guest that has them agrees.
+    pub(crate) fn reconcile(&self, num_players: u8) -> Vec<Vec<T::Bytes>> {
+        PlayerNum::iter(num_players)
+            .map(|player_num| {
+                let slices: Vec<&PlayerInputSlice<T>> = self
+                    .responses
+                    .values()
+                    .filter_map(|finalized| finalized.get(usize::from(player_num)))
+                    .collect();
+                let mut prefix = Vec::new();
+                loop {
+                    let index = prefix.len() as u32;
+                    let mut inputs = slices.iter().filter_map(|slice| {
+                        let offset = index.checked_sub(slice.start)?;
+                        slice.inputs.get(offset as usize)
+                    });
+                    let Some(first) = inputs.next() else {
+                        break;
+                    };
+                    if inputs.any(|input| input != first) {
+                        break;
+                    }
+                    prefix.push(*first);
+                }
+                prefix
+            })
+            .collect()
+    }
+}
@@ -1,27 +1,90 @@
-use crate::{input_trait::SimInput, util_types::PlayerInputSlice};
+use crate::{
+    input_trait::SimInput,
+    util_types::{PlayerInputSlice, PlayerInputSliceRef},
+};
 
 use serde::{Deserialize, Serialize};
 
-/// The status of the inputs for a given tick.
+/// The status of a player's input for a given tick, with the number of that player's inputs finalized so far (e.g. for UI overlays of how far behind each player is).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputStatus {
     /// Received from a peer and finalized by the host.
-    Finalized,
+    Finalized { num_finalized: u32 },
     /// Received from a peer, but not yet finalized.
-    NonFinal,
-    /// Not yet received from a peer.
-    NotReceived,
+    NonFinal { num_finalized: u32 },
+    /// Not yet received from a peer. `predicted` is true if the player's last input is carried forward for the tick (see `InputConfidence::PredictedLOCF`), false if the default input is used.
+    NotReceived { num_finalized: u32, predicted: bool },
+}
+
+impl InputStatus {
+    pub fn is_finalized(&self) -> bool {
+        matches!(self, Self::Finalized { .. })
+    }
+
+    /// True if the input for the tick is a prediction rather than one received from the player.
+    pub fn is_predicted(&self) -> bool {
+        matches!(
+            self,
+            Self::NotReceived {
+                predicted: true,
+                ..
+            }
+        )
+    }
+
+    /// The number of the player's inputs finalized when the status was taken.
+    pub fn num_finalized(&self) -> u32 {
+        match *self {
+            Self::Finalized { num_finalized }
+            | Self::NonFinal { num_finalized }
+            | Self::NotReceived { num_finalized, .. } => num_finalized,
+        }
+    }
+}
+
+/// How much an input returned for a tick can be trusted, e.g. so that a game can draw a remote player whose inputs are pure predictions as a ghost (see `MultiplayerInputManager::get_input_with_confidence`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputConfidence {
+    /// Received and finalized by the host; it won't change.
+    Finalized,
+    /// Received from the player, but not yet finalized, so the host may still replace it.
+    ReceivedNonFinal,
+    /// Not yet received: the player's last input, carried forward. `age` is the number of ticks past that input, 1 for the tick right after it.
+    PredictedLOCF { age: u32 },
+    /// Not yet received, and either beyond the prediction window or with no input yet to carry forward, so the default input; also used for inputs dropped to bound memory.
+    DefaultFallback,
+}
+
+/// What `PlayerInputBuffer::receive_finalized_input_slice` did with a slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinalizedSliceOutcome {
+    /// The slice was applied; `newly_finalized` is 0 if all of its inputs were already finalized.
+    Applied {
+        newly_finalized: u32,
+    },
+    Ignored {
+        reason: IgnoredSliceReason,
+    },
+}
+
+/// Why a finalized slice was left unapplied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IgnoredSliceReason {
+    /// The slice starts after this input index, the next one that needs finalizing, and would leave a gap.
+    GapAtTick(u32),
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PlayerInputBuffer<T>
 where
     T: SimInput,
 {
     /// The number of inputs that have been finalized.
     finalized_inputs: u32,
-    /// The inputs that have been collected, in order, including non-finalized inputs.
+    /// The inputs that have been collected, in order, including non-finalized inputs, from input index `num_dropped` on.
     ///
-    /// Note that we never remove inputs from this buffer. Hanging on to them give some flexibility for logging and recording/replay, and means the entire input history is available to be sent to a peer that is catching up.
+    /// Note that by default we never remove inputs from this buffer (see `drop_inputs_before` for the exception). Hanging on to them give some flexibility for logging and recording/replay, and means the entire input history is available to be sent to a peer that is catching up.
     ///
     /// Running the game at 60hz for 10 hours with 12byte inputs would require:
     /// 60*(60*60*10)*12 = 25,920,000 bytes = ~25MB of memory, which is not unreasonable for modern systems.
@@ -33,6 +96,8 @@ where
     /// A more typical scenario might be 30 minutes at 60hz with 4byte inputs, which would require:
     /// 60*(60*30)*4 = 432,000 bytes = ~0.4MB of memory.
     inputs: Vec<T::Bytes>,
+    /// The number of the oldest inputs removed from the front of `inputs` (see `drop_inputs_before`), all of them finalized.
+    num_dropped: u32,
 }
 
 impl<T> PlayerInputBuffer<T>
@@ -43,11 +108,67 @@ where
         self.finalized_inputs
     }
 
+    /// Dropped inputs were necessarily finalized, so they stay counted as finalized.
     pub fn clone_with_finalization_reset(&self) -> Self {
         Self {
-            finalized_inputs: 0,
+            finalized_inputs: self.num_dropped,
             inputs: self.inputs.clone(),
+            num_dropped: self.num_dropped,
+        }
+    }
+
+    /// Checks the buffer's internal consistency: only finalized inputs are dropped, and only collected inputs are finalized. Since the finalized inputs are a prefix of the collected ones, the finalized history has no gaps.
+    ///
+    /// For tests and debug assertions; a failure is a bug in this crate.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.num_dropped > self.finalized_inputs {
+            return Err(format!(
+                "{} inputs dropped, but only {} finalized",
+                self.num_dropped, self.finalized_inputs
+            ));
+        }
+        if self.finalized_inputs > self.num_inputs_collected() {
+            return Err(format!(
+                "{} inputs finalized, but only {} collected",
+                self.finalized_inputs,
+                self.num_inputs_collected()
+            ));
+        }
+        Ok(())
+    }
+
+    /// The index of the oldest input still held; earlier inputs have been dropped.
+    pub fn first_retained_input(&self) -> u32 {
+        self.num_dropped
+    }
+
+    /// The input at this index, unless it has been dropped or not collected yet.
+    fn retained(&self, index: u32) -> Option<T::Bytes> {
+        let offset = index.checked_sub(self.num_dropped)?;
+        self.inputs.get(offset as usize).copied()
+    }
+
+    /// Drops the inputs before `index` to bound memory in long sessions, leaving every other input at the same index.
+    ///
+    /// Only finalized inputs are dropped, since the rest can still change. Afterwards, dropped inputs read as the default input and slices start at the first retained input, while their status and the finalization count are unchanged.
+    pub fn drop_inputs_before(&mut self, index: u32) {
+        let index = index.min(self.finalized_inputs);
+        if index <= self.num_dropped {
+            return;
         }
+        self.inputs.drain(..(index - self.num_dropped) as usize);
+        self.num_dropped = index;
+    }
+
+    /// Discards the inputs collected from `index` on that aren't finalized.
+    pub fn truncate_unfinalized_from(&mut self, index: u32) {
+        let index = index.max(self.finalized_inputs);
+        self.inputs.truncate((index - self.num_dropped) as usize);
+    }
+
+    /// Frees the memory held for inputs already dropped.
+    pub fn shrink_to_fit(&mut self) {
+        self.inputs.shrink_to_fit();
     }
 
     // pub fn from_bincode_bytes(bytes: &[u8]) -> Self {
@@ -58,12 +179,24 @@ where
     //     }
     // }
 
+    /// Allocates room for at least `num_inputs` inputs in total, so that collecting that many inputs never reallocates.
+    pub fn reserve_total(&mut self, num_inputs: u32) {
+        let additional = (num_inputs.saturating_sub(self.num_dropped) as usize)
+            .saturating_sub(self.inputs.len());
+        self.inputs.reserve_exact(additional);
+    }
+
+    /// The number of inputs (including any dropped) the buffer can hold without reallocating.
+    pub fn capacity(&self) -> u32 {
+        self.num_dropped + self.inputs.capacity() as u32
+    }
+
     pub fn is_finalized(&self, tick: u32) -> bool {
         tick < self.finalized_inputs
     }
 
     pub fn num_inputs_collected(&self) -> u32 {
-        self.inputs.len() as u32
+        self.num_dropped + self.inputs.len() as u32
     }
 
     pub fn append_input(&mut self, input: T::Bytes) {
@@ -76,6 +209,15 @@ where
         self.set_next_final(self.finalized_inputs, input);
     }
 
+    /// Finalizes the oldest non-finalized input already in the buffer.
+    ///
+    /// The host uses this for its own inputs under input delay, which are collected before their tick arrives.
+    pub fn finalize_next_collected(&mut self) {
+        if let Some(input) = self.retained(self.finalized_inputs) {
+            self.set_next_final(self.finalized_inputs, input);
+        }
+    }
+
     /// ALWAYS USE THIS TO FINALIZE INPUTS
     ///
     /// This method is used to finalize an input at a specific index,
@@ -97,12 +239,14 @@ where
         // we can increment the number of finalized inputs
         self.finalized_inputs += 1;
 
-        if index == self.inputs.len() as u32 {
+        // dropped inputs are all finalized, so the index is at or past them
+        let offset = (index - self.num_dropped) as usize;
+        if offset == self.inputs.len() {
             // if we are finalizing the next input for the buffer,
             // just append it
             self.inputs.push(input);
-        } else if index < self.inputs.len() as u32 {
-            self.inputs[index as usize] = input;
+        } else if offset < self.inputs.len() {
+            self.inputs[offset] = input;
         } else {
             // we should never get here
             panic!("Tried to finalize an input that doesn't exist");
@@ -121,36 +265,66 @@ where
     }
 
     pub fn get_input_or_prediction(&self, tick: u32, max_ticks_to_predict_locf: u32) -> T {
-        if tick < self.inputs.len() as u32 {
+        self.get_input_with_confidence(tick, max_ticks_to_predict_locf)
+            .0
+    }
+
+    /// Like `get_input_or_prediction`, along with how the input was arrived at.
+    pub fn get_input_with_confidence(
+        &self,
+        tick: u32,
+        max_ticks_to_predict_locf: u32,
+    ) -> (T, InputConfidence) {
+        let num_inputs = self.num_inputs_collected();
+        if tick < self.num_dropped {
+            // the input was dropped to bound memory, so there is nothing to return
+            (T::default(), InputConfidence::DefaultFallback)
+        } else if tick < num_inputs {
             // if the tick is within the buffer, return the input.
             // Do this no matter whether the input has been finalized or not;
             // even if it's a local input, it's better than predicting.
-            T::from_bytes(self.inputs[tick as usize])
-        } else if self.inputs.len() > 0
-            && (tick < self.inputs.len() as u32 + max_ticks_to_predict_locf)
+            let confidence = if tick < self.finalized_inputs {
+                InputConfidence::Finalized
+            } else {
+                InputConfidence::ReceivedNonFinal
+            };
+            (
+                T::from_bytes(self.inputs[(tick - self.num_dropped) as usize]),
+                confidence,
+            )
+        } else if let Some(&last) = self.inputs.last()
+            && tick < num_inputs + max_ticks_to_predict_locf
         {
             // if there is no input for this tick, in the buffer,
             // but we've collected at least one input, and
             // we are within the prediction window, return the last
             // observed input (even if it's not finalized, it's the best we have)
-            T::from_bytes(self.inputs[self.inputs.len() - 1])
+            let age = tick + 1 - num_inputs;
+            (T::from_bytes(last), InputConfidence::PredictedLOCF { age })
         } else {
             // if we are outside the prediction window, return default
-            T::default()
+            (T::default(), InputConfidence::DefaultFallback)
         }
     }
 
-    pub fn get_input_status(&self, input_num: u32) -> InputStatus {
-        if input_num < self.finalized_inputs {
-            InputStatus::Finalized
-        } else if input_num < self.inputs.len() as u32 {
-            InputStatus::NonFinal
+    /// The status of the input for `input_num`; inputs not yet received are predicted within `max_ticks_to_predict_locf` of the last one, as in `get_input_with_confidence`.
+    pub fn get_input_status(&self, input_num: u32, max_ticks_to_predict_locf: u32) -> InputStatus {
+        let num_finalized = self.finalized_inputs;
+        let num_inputs = self.num_inputs_collected();
+        if input_num < num_finalized {
+            InputStatus::Finalized { num_finalized }
+        } else if input_num < num_inputs {
+            InputStatus::NonFinal { num_finalized }
         } else {
-            InputStatus::NotReceived
+            InputStatus::NotReceived {
+                num_finalized,
+                predicted: !self.inputs.is_empty()
+                    && input_num < num_inputs + max_ticks_to_predict_locf,
+            }
         }
     }
 
-    /// gets slice from tick start to end. EXCLUSIVE
+    // gets slice from tick start to end. EXCLUSIVE
     // pub fn slice(&self, start: u32, end: u32) -> PlayerInputSlice<T> {
     //     PlayerInputSlice {
     //         inputs: self.inputs[start as usize..end as usize].to_vec(),
@@ -159,14 +333,39 @@ where
     // }
 
     pub fn slice_from(&self, start: u32) -> PlayerInputSlice<T> {
-        PlayerInputSlice {
-            inputs: self.inputs[start as usize..self.inputs.len()].to_vec(),
+        self.borrow_slice_from(start).to_owned_slice()
+    }
+
+    /// Like `slice_from`, but borrows the inputs rather than copying them.
+    ///
+    /// Slices start no earlier than the first retained input (see `drop_inputs_before`).
+    pub fn borrow_slice_from(&self, start: u32) -> PlayerInputSliceRef<'_, T> {
+        let start = start.max(self.num_dropped);
+        PlayerInputSliceRef {
+            inputs: &self.inputs[(start - self.num_dropped) as usize..],
+            start,
+        }
+    }
+
+    /// Like `slice_from`, but stops at the last finalized input.
+    pub fn finalized_slice_from(&self, start: u32) -> PlayerInputSlice<T> {
+        self.borrow_finalized_slice_from(start).to_owned_slice()
+    }
+
+    /// Like `finalized_slice_from`, but borrows the inputs rather than copying them.
+    pub fn borrow_finalized_slice_from(&self, start: u32) -> PlayerInputSliceRef<'_, T> {
+        let start = start.min(self.finalized_inputs).max(self.num_dropped);
+        PlayerInputSliceRef {
+            inputs: &self.inputs[(start - self.num_dropped) as usize
+                ..(self.finalized_inputs - self.num_dropped) as usize],
             start,
         }
     }
 
     /// This method is used to update the buffer when a peer sends
     /// a slice of inputs that have not yet been finalized.
+    ///
+    /// Inputs past the end of the buffer that would leave a gap are ignored, since they can't be stored at their own index.
     pub fn receive_peer_input_slice(&mut self, slice: PlayerInputSlice<T>) {
         // just append these potentially temporary inputs after the last
         // finalized input
@@ -180,11 +379,16 @@ where
             // Note that if weve seen t+1 finalized inputs, the index of the
             // newest finalized input is t, so we can write to index t+1
             if t + 1 > self.finalized_inputs as usize {
-                if t < self.inputs.len() {
-                    self.inputs[t] = *input
-                } else {
+                // dropped inputs are all finalized, so `t` is past them
+                let offset = t - self.num_dropped as usize;
+                if offset < self.inputs.len() {
+                    self.inputs[offset] = *input
+                } else if offset == self.inputs.len() {
                     // add additional inputs
                     self.inputs.push(*input);
+                } else {
+                    // the inputs before this one are missing
+                    break;
                 }
             }
         }
@@ -192,15 +396,21 @@ where
 
     /// This method is used to update the buffer when the server
     /// sends a slice of inputs that have been finalized.
-    pub fn receive_finalized_input_slice(&mut self, slice: PlayerInputSlice<T>) {
+    pub fn receive_finalized_input_slice(
+        &mut self,
+        slice: PlayerInputSlice<T>,
+    ) -> FinalizedSliceOutcome {
         // this is a no-op if it would leave a gap in the finalized
         // input history, so the new data must overlap or start
         // with the next tick that hasn't yet been finalized.
         // If this condition is not met, we will keep requesting
         // inputs slices starting at finalized_input until one arrives.
         if slice.start > self.finalized_inputs {
-            return;
+            return FinalizedSliceOutcome::Ignored {
+                reason: IgnoredSliceReason::GapAtTick(self.finalized_inputs),
+            };
         }
+        let finalized_before = self.finalized_inputs;
 
         let start = slice.start as usize;
         // at this point, we know the slice starts before or at the next tick
@@ -209,6 +419,9 @@ where
             let t = start + offset;
             self.set_next_final(t as u32, *input);
         }
+        FinalizedSliceOutcome::Applied {
+            newly_finalized: self.finalized_inputs - finalized_before,
+        }
     }
 }
 
@@ -219,6 +432,6 @@ where
     T: SimInput,
 {
     pub(crate) fn test_helper_get_input(&self, index: usize) -> T::Bytes {
-        self.inputs[index]
+        self.inputs[index - self.num_dropped as usize]
     }
 }
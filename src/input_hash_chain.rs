@@ -0,0 +1,98 @@
+//! Chained hashes of each player's finalized inputs, for detecting peers whose input histories have diverged.
+//!
+//! A player's chain starts at `INPUT_CHAIN_SEED`, and each finalized input extends it: `head_t = H(head_{t-1} || input_t)`, where `H` is 64-bit FNV-1a, the previous head is 8 little-endian bytes, and the input is its `SimInput::Bytes` in bincode's fixed-int encoding (as in replays). An accidental change to a player's input history (a bug, a corrupted replay) changes every later head.
+//!
+//! FNV is not a cryptographic hash, and the chain isn't keyed, so it is no defence against a peer that alters inputs on purpose: such a peer can compute matching heads for altered inputs.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{input_trait::SimInput, util_types::PlayerNum};
+
+/// The head of every chain before any inputs (the 64-bit FNV-1a offset basis).
+pub const INPUT_CHAIN_SEED: u64 = 0xcbf2_9ce4_8422_2325;
+
+const FNV_64_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+pub(crate) fn fnv1a_64(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(FNV_64_PRIME)
+    })
+}
+
+/// An input's `SimInput::Bytes` in bincode's fixed-int encoding, as hashed by chains and determinism probes.
+pub(crate) fn encode_input_bytes<T: SimInput>(input: &T::Bytes) -> Vec<u8> {
+    bincode::serde::encode_to_vec(input, bincode::config::standard().with_fixed_int_encoding())
+        .expect("SimInput::Bytes must be serializable")
+}
+
+/// Extends a chain head with one input.
+pub(crate) fn chain_step<T: SimInput>(head: u64, input: &T::Bytes) -> u64 {
+    let hash = fnv1a_64(INPUT_CHAIN_SEED, &head.to_le_bytes());
+    fnv1a_64(hash, &encode_input_bytes::<T>(input))
+}
+
+/// The head of a chain over these inputs.
+pub(crate) fn chain_head<T: SimInput>(inputs: &[T::Bytes]) -> u64 {
+    inputs
+        .iter()
+        .fold(INPUT_CHAIN_SEED, |head, input| chain_step::<T>(head, input))
+}
+
+/// The chained hash of a player's first `num_inputs` finalized inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputChainHead {
+    pub player_num: PlayerNum,
+    pub num_inputs: u32,
+    pub head: u64,
+}
+
+/// The result of checking a peer's chain head against this node's own chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainCheck {
+    /// The heads agree.
+    Match,
+    /// The heads disagree: the two nodes have finalized different inputs for the player.
+    Mismatch,
+    /// This node can't check the head, because it hasn't finalized that many of the player's inputs yet, or doesn't keep input chains.
+    Unknown,
+}
+
+/// Every player's chain, with the head after each of their finalized inputs.
+#[derive(Debug, Clone)]
+pub(crate) struct InputHashChains(Vec<Vec<u64>>);
+
+impl InputHashChains {
+    pub(crate) fn new(num_players: u8) -> Self {
+        Self(vec![Vec::new(); num_players as usize])
+    }
+
+    /// Adds empty chains for new players up to `num_players`.
+    pub(crate) fn add_players_up_to(&mut self, num_players: u8) {
+        if self.0.len() < usize::from(num_players) {
+            self.0.resize(usize::from(num_players), Vec::new());
+        }
+    }
+
+    /// The number of the player's inputs already in the chain.
+    pub(crate) fn num_inputs(&self, player_num: PlayerNum) -> u32 {
+        self.0[usize::from(player_num)].len() as u32
+    }
+
+    /// Extends the player's chain with newly finalized inputs.
+    pub(crate) fn extend<T: SimInput>(&mut self, player_num: PlayerNum, inputs: &[T::Bytes]) {
+        let heads = &mut self.0[usize::from(player_num)];
+        let mut head = heads.last().copied().unwrap_or(INPUT_CHAIN_SEED);
+        for input in inputs {
+            head = chain_step::<T>(head, input);
+            heads.push(head);
+        }
+    }
+
+    /// The head after the player's first `num_inputs` inputs, if the chain is that long.
+    pub(crate) fn head_at(&self, player_num: PlayerNum, num_inputs: u32) -> Option<u64> {
+        match num_inputs {
+            0 => Some(INPUT_CHAIN_SEED),
+            n => self.0[usize::from(player_num)].get(n as usize - 1).copied(),
+        }
+    }
+}
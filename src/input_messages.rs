@@ -1,13 +1,26 @@
-use std::fmt::Display;
+use std::{borrow::Cow, fmt::Display};
 
 use bincode::error::DecodeError;
 use serde::{Deserialize, Serialize};
 
-use crate::input_trait::{SimInput, TestInputBytes};
+use crate::{
+    capabilities::Capabilities,
+    clock_sync::{ClockPing, ClockPong},
+    desync_report::InputHashReport,
+    determinism_probe::DeterminismSample,
+    event_channel::EventSlice,
+    host_recovery::RecoveryResponse,
+    input_hash_chain::InputChainHead,
+    input_trait::{SimInput, TestInputBytes},
+    rtt::PingReport,
+    seed_schedule::SeedChange,
+    session_limit::MAX_SESSION_TICKS,
+    start_sync::{ScheduledStart, StartAck},
+};
 
 use super::{
     peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
-    util_types::{PlayerInputSlice, PlayerNum},
+    util_types::{PlayerInputSlice, PlayerInputSliceRef, PlayerNum},
 };
 
 /// A slice of inputs finalized by the host for a specific player.
@@ -28,6 +41,61 @@ pub struct HostFinalizedSlice<T: SimInput> {
     pub inputs: PlayerInputSlice<T>,
 }
 
+/// A borrowed view of a `HostFinalizedSlice`, which serializes to the same bytes without copying the inputs out of the buffer.
+#[derive(Debug, Serialize)]
+#[serde(bound = "")]
+pub struct HostFinalizedSliceRef<'a, T: SimInput> {
+    pub player_num: PlayerNum,
+    pub host_tick: u32,
+    pub inputs: PlayerInputSliceRef<'a, T>,
+}
+
+impl<T: SimInput> HostFinalizedSliceRef<'_, T> {
+    /// The serialized `MsgPayload::HostToLobbyFinalizedSlice` message for this slice, identical to the bytes of the owned message.
+    pub fn to_msg_bytes(&self) -> Vec<u8> {
+        frame_msg_bytes(FINALIZED_SLICE_VARIANT_NUM, to_bincode_bytes(self))
+    }
+
+    /// Like `to_msg_bytes`, but split into the same parts as `MsgPayload::split_to_max_bytes` would split the owned message into.
+    pub fn to_msg_bytes_split(&self, max_bytes: usize) -> Vec<Vec<u8>> {
+        let payload = to_bincode_bytes(self);
+        // the message is the payload behind its header byte
+        if payload.len() < max_bytes || self.inputs.len() <= 1 {
+            return vec![frame_msg_bytes(FINALIZED_SLICE_VARIANT_NUM, payload)];
+        }
+        let mid = self.inputs.inputs.len() / 2;
+        let (first, rest) = self.inputs.inputs.split_at(mid);
+        let mut parts = HostFinalizedSliceRef::<T> {
+            player_num: self.player_num,
+            host_tick: self.host_tick,
+            inputs: PlayerInputSliceRef {
+                start: self.inputs.start,
+                inputs: first,
+            },
+        }
+        .to_msg_bytes_split(max_bytes);
+        parts.extend(
+            HostFinalizedSliceRef::<T> {
+                player_num: self.player_num,
+                host_tick: self.host_tick,
+                inputs: PlayerInputSliceRef {
+                    start: self.inputs.start + mid as u32,
+                    inputs: rest,
+                },
+            }
+            .to_msg_bytes_split(max_bytes),
+        );
+        parts
+    }
+}
+
+impl<T: SimInput> PlayerInputSliceRef<'_, T> {
+    /// The serialized `MsgPayload::PeerInputs` message for this slice, identical to the bytes of the owned message.
+    pub fn to_msg_bytes(&self) -> Vec<u8> {
+        frame_msg_bytes(PEER_INPUTS_VARIANT_NUM, to_bincode_bytes(self))
+    }
+}
+
 impl<T> Display for HostFinalizedSlice<T>
 where
     T: SimInput,
@@ -52,11 +120,34 @@ impl<T: SimInput + TestInputBytes> HostFinalizedSlice<T> {
     }
 }
 
+/// One local player's inputs in a guest's `MsgPayload::GuestToHostLocalInputs` bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalPlayerInputs<T: SimInput> {
+    pub player_num: PlayerNum,
+    pub inputs: PlayerInputSlice<T>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PreSimSync {
     // represent the countdown to the sim starting
     pub host_tick_countdown: u8,
     pub peers: Vec<u32>,
+    /// The sim tick at which the synchronized input timeline starts, e.g. when resuming a saved match.
+    pub start_tick: u32,
+    /// The lobby's RNG seed from the start tick, until the host changes it (see `seed_schedule`).
+    pub initial_seed: u64,
+    /// The number of sim ticks each input covers (see `MultiplayerInputManager::with_sim_ticks_per_input`).
+    pub sim_ticks_per_input: u32,
+    /// The sim's tick rate.
+    pub ticks_per_sec: u32,
+    /// The sim tick at which the session ends (see `MultiplayerInputManager::with_max_session_ticks`).
+    pub max_session_ticks: u32,
+    /// The host's input schema id (see `SimInput::SCHEMA_ID`).
+    pub input_schema_id: u32,
+    /// The optional capabilities the host has enabled (see `capabilities`).
+    pub capabilities: Capabilities,
+    /// The display metadata registered for each seat, in seat order (see `player_metadata`).
+    pub player_metadata: Vec<(PlayerNum, Vec<u8>)>,
 }
 
 impl Default for PreSimSync {
@@ -64,10 +155,61 @@ impl Default for PreSimSync {
         Self {
             host_tick_countdown: 60,
             peers: vec![],
+            start_tick: 0,
+            initial_seed: 0,
+            sim_ticks_per_input: 1,
+            ticks_per_sec: 60,
+            max_session_ticks: MAX_SESSION_TICKS,
+            input_schema_id: 0,
+            capabilities: Capabilities::NONE,
+            player_metadata: vec![],
         }
     }
 }
 
+/// A guest's reply to a `HostToGuestPong`, completing the host's ping cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PongPong {
+    /// The id of the ping being answered
+    pub ping_id: u32,
+    /// The guest's smoothed RTT to the host (whole microseconds), including the sample from this cycle's ping and pong
+    pub guest_rtt_micros: u32,
+}
+
+/// Tells guests that the host has muted a player (see `MultiplayerInputManager::mute_player`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerMuted {
+    pub player_num: PlayerNum,
+    /// The index (counted from the session's start tick) of the first input from this player that the host ignores; the host finalizes default inputs from here on.
+    pub first_ignored_input: u32,
+}
+
+/// Tells guests that the host has moved a seat to a new connection (see `MultiplayerInputManager::transfer_seat`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeatTransfer {
+    pub seat: PlayerNum,
+    /// The player num by which the transport addresses the seat's new connection
+    pub connection: PlayerNum,
+    /// The index (counted from the session's start tick) of the first input the new connection provides; earlier inputs keep the seat's existing history.
+    pub first_input: u32,
+}
+
+/// Tells guests that the host has added a player mid-session (see `MultiplayerInputManager::add_player`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerJoined {
+    pub player_num: PlayerNum,
+    /// The index (counted from the session's start tick) of the first input the new player provides; the host finalized default inputs for every earlier one.
+    pub first_input: u32,
+}
+
+/// Tells guests that the host has removed a player for good (see `MultiplayerInputManager::remove_player`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerRemoved {
+    pub player_num: PlayerNum,
+    /// The number of inputs the player ends with, all finalized by the host; their later inputs are defaults.
+    pub num_inputs: u32,
+}
+
 /// FIXME: rather than just naming convention, break this up into separate enums for host and guest messages and broadcast vs direct messages?
 #[derive(Default, Debug, Clone)]
 pub enum MsgPayload<T: SimInput> {
@@ -96,12 +238,143 @@ pub enum MsgPayload<T: SimInput> {
     /// message from host to guest in reply to GuestPing. The u32 is the ping id
     /// so the guest can match the pong to the ping it sent.
     HostToGuestPong(u32),
-    /// message from guest to host in reply to HostPong. The u32 is the ping id
-    /// so the host can match the pong to the ping it sent.
+    /// message from guest to host in reply to HostPong. It carries the ping id
+    /// so the host can match the pong to the ping it sent, and the guest's own RTT
+    /// measurement so the host can compare the two (see `PingReport`).
     ///
     /// The time between the host sending the ping and receiving this pong
     /// can be used to estimate the round-trip time (RTT) between host and guest
-    GuestToHostPongPong(u32),
+    GuestToHostPongPong(PongPong),
+
+    /// message from host to guest reporting the guest's estimated clock skew in parts per million, as measured by the rate at which the guest's inputs arrive at the host.
+    ///
+    /// Negative values mean the guest is producing inputs slower than the tick rate.
+    HostToGuestRateAdjust(i32),
+
+    /// message from host to all peers announcing that a new round has started;
+    /// the u32 is the index of the new round.
+    ///
+    /// THIS SHOULD BE BROADCAST TO ALL PEERS
+    HostToLobbyRoundTransition(u32),
+
+    /// message from guest to host confirming that it has started the round
+    /// with the given index. Until the host receives this, it ignores inputs and
+    /// acks from that guest, since they may still refer to the previous round.
+    GuestToHostRoundTransitionAck(u32),
+
+    /// The host has muted a player: it ignores that player's inputs from a given tick on, and finalizes default inputs for their seat instead.
+    ///
+    /// The player keeps their seat, so lobby topology is unchanged.
+    HostToLobbyPlayerMuted(PlayerMuted),
+
+    /// A guest's events that the host has not yet confirmed (see `event_channel`).
+    ///
+    /// Guests resend these until the host confirms them, and the host ignores events it has already accepted.
+    GuestToHostEvents(EventSlice),
+
+    /// Events the host has accepted for a player, with their final sequence numbers and ticks.
+    HostToLobbyEvents(EventSlice),
+
+    /// For each player (indexed by player num), the number of confirmed events the sending guest has received.
+    GuestToHostAckEvents(Vec<u32>),
+
+    /// Any node to any other: the chained hash of a player's finalized inputs, to check against the receiver's own chain (see `MultiplayerInputManager::rx_input_chain_head`).
+    PeerInputChainHead(InputChainHead),
+
+    /// Any node to any other: its input and state digests for a tick, to check against the receiver's own (see `MultiplayerInputManager::rx_determinism_sample`).
+    PeerDeterminismSample(DeterminismSample),
+
+    /// A restarted host to all guests: a request for their finalized inputs, tagged with a request id (see `MultiplayerInputManager::begin_recovery`).
+    HostToLobbyRecoveryRequest(u32),
+
+    /// A guest's reply to a recovery request: its finalized inputs for every player.
+    GuestToHostRecoveryResponse(RecoveryResponse<T>),
+
+    /// The host's authoritative tick-stamped annotations (e.g. round starts, spawn seeds) that at least one guest hasn't acked, with their sequence numbers (see `MultiplayerInputManager::add_annotation`).
+    HostToLobbyAnnotations(EventSlice),
+
+    /// The number of the host's annotations the sending guest has received.
+    GuestToHostAckAnnotations(u32),
+
+    /// message from host to all peers with a change of the lobby's RNG seed (see `seed_schedule`)
+    ///
+    /// THIS SHOULD BE BROADCAST TO ALL PEERS
+    HostToLobbySeed(SeedChange),
+
+    /// message from guest to host with the number of seed changes it has received
+    GuestToHostAckSeeds(u32),
+
+    /// message from host to guest after a pong-pong completes a ping cycle, comparing the RTTs that host and guest measure for their link (see `PingReport`)
+    HostToGuestPingReport(PingReport),
+
+    /// message from guest to host in reply to HostPong, from a guest built before `GuestToHostPongPong` carried the guest's RTT;
+    /// the u32 is the ping id. It keeps the pong-pong's original wire id, so the host can still complete ping cycles with such guests, though it has no guest RTT to report back.
+    GuestToHostLegacyPongPong(u32),
+
+    /// message from host to all peers ending the session at a sim tick (see `MultiplayerInputManager::end_session_at`)
+    ///
+    /// THIS SHOULD BE BROADCAST TO ALL PEERS
+    HostToLobbyEndSession(u32),
+
+    /// message from guest to host acking the end of the session at a sim tick
+    GuestToHostEndAck(u32),
+
+    /// message from host to all peers moving a seat to a new connection (see `MultiplayerInputManager::transfer_seat`)
+    ///
+    /// THIS SHOULD BE BROADCAST TO ALL PEERS
+    HostToLobbySeatTransferred(SeatTransfer),
+
+    /// message from host to all peers proposing a lobby tick at which to start the sim (see `start_sync`)
+    ///
+    /// THIS SHOULD BE BROADCAST TO ALL PEERS
+    HostToLobbyStartProposal(ScheduledStart),
+
+    /// message from guest to host with its estimate of the ticks left until a proposed start
+    GuestToHostStartAck(StartAck),
+
+    /// message from host to all peers confirming the lobby tick at which the sim starts (see `start_sync`)
+    ///
+    /// THIS SHOULD BE BROADCAST TO ALL PEERS
+    HostToLobbyStartConfirmed(ScheduledStart),
+
+    /// message from guest to host with the guest's input schema id (see `SimInput::SCHEMA_ID`), replying to the `PreSimSync`
+    GuestToHostInputSchema(u32),
+
+    /// message from guest to host with the capabilities it confirms from the host's `PreSimSync` (see `capabilities`), replying to the `PreSimSync`
+    GuestToHostCapabilities(Capabilities),
+
+    /// message from guest to host with hashes of every player's finalized inputs for recent ticks, for the host to check against its own (see `desync_report`)
+    GuestToHostInputHashReport(InputHashReport),
+
+    /// message from host to all peers adding a player mid-session (see `MultiplayerInputManager::add_player`)
+    ///
+    /// THIS SHOULD BE BROADCAST TO ALL PEERS
+    HostToLobbyPlayerJoined(PlayerJoined),
+
+    /// message from host to all peers removing a player for good (see `MultiplayerInputManager::remove_player`)
+    ///
+    /// THIS SHOULD BE BROADCAST TO ALL PEERS
+    HostToLobbyPlayerRemoved(PlayerRemoved),
+
+    /// message from guest to host stamped with the guest's local time, to sync the guest's clock with the host's (see `clock_sync`)
+    GuestToHostClockPing(ClockPing),
+
+    /// message from host to guest in reply to a `GuestToHostClockPing`, stamped with the host's sim time
+    HostToGuestClockPong(ClockPong),
+
+    /// message from guest to host announcing that the guest has reinitialized its buffers (see `MultiplayerInputManager::reset_buffers`), so the host forgets its acks and resends the finalized inputs from the start;
+    /// the u32 is the index of the guest's current round.
+    GuestToHostBufferReset(u32),
+
+    /// message from a guest with several local players (e.g. split-screen) to the host, with each local player's inputs (see `MultiplayerInputManager::new_with_local_players`)
+    GuestToHostLocalInputs(Vec<LocalPlayerInputs<T>>),
+
+    /// Several messages for the same recipient, sent together (see `MultiplayerInputManager::get_outgoing_msgs_for_frame`). Batches can't be nested.
+    Batch(Vec<MsgPayload<T>>),
+
+    /// message between peers acknowledging the receipt of critical messages sent with reliable delivery (see `reliable_delivery`);
+    /// the u32s are the sequence numbers received.
+    DeliveryAck(Vec<u32>),
 }
 
 impl<T> Display for MsgPayload<T>
@@ -131,9 +404,154 @@ where
             MsgPayload::HostToGuestPong(ping_id) => {
                 write!(f, "SimMsg::HostToGuestPong({ping_id})")
             }
-            MsgPayload::GuestToHostPongPong(ping_id) => {
-                write!(f, "SimMsg::G2h:PongPong({ping_id})")
+            MsgPayload::GuestToHostPongPong(pong_pong) => {
+                write!(
+                    f,
+                    "SimMsg::G2h:PongPong({}, guest_rtt_micros: {})",
+                    pong_pong.ping_id, pong_pong.guest_rtt_micros
+                )
+            }
+            MsgPayload::GuestToHostLegacyPongPong(ping_id) => {
+                write!(f, "SimMsg::G2h:LegacyPongPong({ping_id})")
+            }
+            MsgPayload::HostToGuestRateAdjust(skew_ppm) => {
+                write!(f, "SimMsg::HostToGuestRateAdjust({skew_ppm}ppm)")
+            }
+            MsgPayload::HostToLobbyRoundTransition(round) => {
+                write!(f, "SimMsg::H2all:RoundTransition({round})")
+            }
+            MsgPayload::GuestToHostRoundTransitionAck(round) => {
+                write!(f, "SimMsg::G2h:RoundTransitionAck({round})")
+            }
+            MsgPayload::HostToLobbyPlayerMuted(muted) => {
+                write!(
+                    f,
+                    "SimMsg::H2all:PlayerMuted(player {}; from input {})",
+                    muted.player_num.as_u8(),
+                    muted.first_ignored_input
+                )
+            }
+            MsgPayload::GuestToHostEvents(events) => {
+                write!(
+                    f,
+                    "SimMsg::G2h:Events(player {}; from {}; {} events)",
+                    events.player_num.as_u8(),
+                    events.start_seq,
+                    events.events.len()
+                )
             }
+            MsgPayload::HostToLobbyEvents(events) => {
+                write!(
+                    f,
+                    "SimMsg::H2all:Events(player {}; from {}; {} events)",
+                    events.player_num.as_u8(),
+                    events.start_seq,
+                    events.events.len()
+                )
+            }
+            MsgPayload::GuestToHostAckEvents(seen) => {
+                write!(f, "SimMsg::G2h:AckEvents({seen:?})")
+            }
+            MsgPayload::PeerInputChainHead(head) => {
+                write!(f, "SimMsg::PeerInputChainHead({head:?})")
+            }
+            MsgPayload::PeerDeterminismSample(sample) => {
+                write!(f, "SimMsg::PeerDeterminismSample({sample:?})")
+            }
+            MsgPayload::HostToLobbyRecoveryRequest(request_id) => {
+                write!(f, "SimMsg::H2all:RecoveryRequest({request_id})")
+            }
+            MsgPayload::GuestToHostRecoveryResponse(response) => {
+                write!(
+                    f,
+                    "SimMsg::G2h:RecoveryResponse(request {}, {} players)",
+                    response.request_id,
+                    response.finalized.len()
+                )
+            }
+            MsgPayload::HostToLobbyAnnotations(slice) => {
+                write!(f, "SimMsg::H2all:Annotations({slice:?})")
+            }
+            MsgPayload::GuestToHostAckAnnotations(num_seen) => {
+                write!(f, "SimMsg::G2h:AckAnnotations({num_seen})")
+            }
+            MsgPayload::HostToLobbySeed(change) => {
+                write!(f, "SimMsg::H2all:Seed({change:?})")
+            }
+            MsgPayload::GuestToHostAckSeeds(num_seen) => {
+                write!(f, "SimMsg::G2h:AckSeeds({num_seen})")
+            }
+            MsgPayload::HostToGuestPingReport(report) => {
+                write!(f, "SimMsg::HostToGuestPingReport({report:?})")
+            }
+            MsgPayload::HostToLobbyEndSession(final_tick) => {
+                write!(f, "SimMsg::H2all:EndSession({final_tick})")
+            }
+            MsgPayload::GuestToHostEndAck(final_tick) => {
+                write!(f, "SimMsg::G2h:EndAck({final_tick})")
+            }
+            MsgPayload::HostToLobbySeatTransferred(transfer) => {
+                write!(f, "SimMsg::H2all:SeatTransferred({transfer:?})")
+            }
+            MsgPayload::HostToLobbyStartProposal(proposal) => {
+                write!(f, "SimMsg::H2all:StartProposal({proposal:?})")
+            }
+            MsgPayload::GuestToHostStartAck(ack) => {
+                write!(f, "SimMsg::G2h:StartAck({ack:?})")
+            }
+            MsgPayload::HostToLobbyStartConfirmed(start) => {
+                write!(f, "SimMsg::H2all:StartConfirmed({start:?})")
+            }
+            MsgPayload::GuestToHostInputSchema(schema_id) => {
+                write!(f, "SimMsg::G2h:InputSchema({schema_id:#010x})")
+            }
+            MsgPayload::GuestToHostCapabilities(capabilities) => {
+                write!(f, "SimMsg::G2h:Capabilities({:#x})", capabilities.0)
+            }
+            MsgPayload::GuestToHostInputHashReport(report) => {
+                write!(
+                    f,
+                    "SimMsg::G2h:InputHashReport(ticks {}..{})",
+                    report.start_tick,
+                    report.end_tick()
+                )
+            }
+            MsgPayload::HostToLobbyPlayerJoined(joined) => {
+                write!(f, "SimMsg::H2all:PlayerJoined({joined:?})")
+            }
+            MsgPayload::HostToLobbyPlayerRemoved(removed) => {
+                write!(f, "SimMsg::H2all:PlayerRemoved({removed:?})")
+            }
+            MsgPayload::GuestToHostClockPing(ping) => {
+                write!(f, "SimMsg::G2h:ClockPing({ping:?})")
+            }
+            MsgPayload::HostToGuestClockPong(pong) => {
+                write!(f, "SimMsg::HostToGuestClockPong({pong:?})")
+            }
+            MsgPayload::GuestToHostBufferReset(round) => {
+                write!(f, "SimMsg::G2h:BufferReset({round})")
+            }
+            MsgPayload::GuestToHostLocalInputs(bundle) => {
+                write!(f, "SimMsg::G2h:LocalInputs(")?;
+                for (i, local) in bundle.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "player {}: {}", local.player_num.as_u8(), local.inputs)?;
+                }
+                write!(f, ")")
+            }
+            MsgPayload::Batch(msgs) => {
+                write!(f, "SimMsg::Batch(")?;
+                for (i, msg) in msgs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{msg}")?;
+                }
+                write!(f, ")")
+            }
+            MsgPayload::DeliveryAck(seqs) => write!(f, "SimMsg::DeliveryAck({seqs:?})"),
         }
     }
 }
@@ -142,75 +560,356 @@ impl<T> MsgPayload<T>
 where
     T: SimInput,
 {
-    fn variant_num(&self) -> u8 {
+    /// The variant of this message, without its contents.
+    pub fn kind(&self) -> MsgKind {
         match self {
-            MsgPayload::Empty => 0,
-            MsgPayload::Invalid => 1,
-            MsgPayload::GuestToHostAckFinalization(_) => 2,
-            MsgPayload::HostToLobbyFinalizedSlice(_) => 3,
-            MsgPayload::PeerInputs(_) => 4,
-            MsgPayload::HostToGuestPreSimSync(_) => 5,
-            MsgPayload::GuestToHostPing(_) => 6,
-            MsgPayload::HostToGuestPong(_) => 7,
-            MsgPayload::GuestToHostPongPong(_) => 8,
+            MsgPayload::Empty => MsgKind::Empty,
+            MsgPayload::Invalid => MsgKind::Invalid,
+            MsgPayload::GuestToHostAckFinalization(_) => MsgKind::GuestToHostAckFinalization,
+            MsgPayload::HostToLobbyFinalizedSlice(_) => MsgKind::HostToLobbyFinalizedSlice,
+            MsgPayload::PeerInputs(_) => MsgKind::PeerInputs,
+            MsgPayload::HostToGuestPreSimSync(_) => MsgKind::HostToGuestPreSimSync,
+            MsgPayload::GuestToHostPing(_) => MsgKind::GuestToHostPing,
+            MsgPayload::HostToGuestPong(_) => MsgKind::HostToGuestPong,
+            MsgPayload::GuestToHostPongPong(_) => MsgKind::GuestToHostPongPong,
+            MsgPayload::HostToGuestRateAdjust(_) => MsgKind::HostToGuestRateAdjust,
+            MsgPayload::HostToLobbyRoundTransition(_) => MsgKind::HostToLobbyRoundTransition,
+            MsgPayload::GuestToHostRoundTransitionAck(_) => MsgKind::GuestToHostRoundTransitionAck,
+            MsgPayload::HostToLobbyPlayerMuted(_) => MsgKind::HostToLobbyPlayerMuted,
+            MsgPayload::GuestToHostEvents(_) => MsgKind::GuestToHostEvents,
+            MsgPayload::HostToLobbyEvents(_) => MsgKind::HostToLobbyEvents,
+            MsgPayload::GuestToHostAckEvents(_) => MsgKind::GuestToHostAckEvents,
+            MsgPayload::PeerInputChainHead(_) => MsgKind::PeerInputChainHead,
+            MsgPayload::PeerDeterminismSample(_) => MsgKind::PeerDeterminismSample,
+            MsgPayload::HostToLobbyRecoveryRequest(_) => MsgKind::HostToLobbyRecoveryRequest,
+            MsgPayload::GuestToHostRecoveryResponse(_) => MsgKind::GuestToHostRecoveryResponse,
+            MsgPayload::HostToLobbyAnnotations(_) => MsgKind::HostToLobbyAnnotations,
+            MsgPayload::GuestToHostAckAnnotations(_) => MsgKind::GuestToHostAckAnnotations,
+            MsgPayload::HostToLobbySeed(_) => MsgKind::HostToLobbySeed,
+            MsgPayload::GuestToHostAckSeeds(_) => MsgKind::GuestToHostAckSeeds,
+            MsgPayload::HostToGuestPingReport(_) => MsgKind::HostToGuestPingReport,
+            MsgPayload::GuestToHostLegacyPongPong(_) => MsgKind::GuestToHostLegacyPongPong,
+            MsgPayload::HostToLobbyEndSession(_) => MsgKind::HostToLobbyEndSession,
+            MsgPayload::GuestToHostEndAck(_) => MsgKind::GuestToHostEndAck,
+            MsgPayload::HostToLobbySeatTransferred(_) => MsgKind::HostToLobbySeatTransferred,
+            MsgPayload::HostToLobbyStartProposal(_) => MsgKind::HostToLobbyStartProposal,
+            MsgPayload::GuestToHostStartAck(_) => MsgKind::GuestToHostStartAck,
+            MsgPayload::HostToLobbyStartConfirmed(_) => MsgKind::HostToLobbyStartConfirmed,
+            MsgPayload::GuestToHostInputSchema(_) => MsgKind::GuestToHostInputSchema,
+            MsgPayload::GuestToHostCapabilities(_) => MsgKind::GuestToHostCapabilities,
+            MsgPayload::GuestToHostInputHashReport(_) => MsgKind::GuestToHostInputHashReport,
+            MsgPayload::HostToLobbyPlayerJoined(_) => MsgKind::HostToLobbyPlayerJoined,
+            MsgPayload::HostToLobbyPlayerRemoved(_) => MsgKind::HostToLobbyPlayerRemoved,
+            MsgPayload::GuestToHostClockPing(_) => MsgKind::GuestToHostClockPing,
+            MsgPayload::HostToGuestClockPong(_) => MsgKind::HostToGuestClockPong,
+            MsgPayload::GuestToHostBufferReset(_) => MsgKind::GuestToHostBufferReset,
+            MsgPayload::GuestToHostLocalInputs(_) => MsgKind::GuestToHostLocalInputs,
+            MsgPayload::Batch(_) => MsgKind::Batch,
+            MsgPayload::DeliveryAck(_) => MsgKind::DeliveryAck,
         }
     }
 
+    fn variant_num(&self) -> u8 {
+        self.kind().variant_num()
+    }
+
     /// Returns true if this message is a guest reply to a host message, and thus needs to be sent to the host.
     pub fn is_guest_reply(&self) -> bool {
-        match self {
-            MsgPayload::GuestToHostAckFinalization(_) => true,
-            MsgPayload::GuestToHostPing(_) => true,
-            MsgPayload::GuestToHostPongPong(_) => true,
+        self.kind().is_guest_reply()
+    }
+
+    /// Returns true if this message is a host reply that should be broadcast to all guests.
+    pub fn is_host_reply_for_all(&self) -> bool {
+        self.kind().is_host_reply_for_all()
+    }
 
-            MsgPayload::HostToLobbyFinalizedSlice(_) => false,
-            MsgPayload::HostToGuestPreSimSync(_) => false,
+    /// Returns true if this message is a host reply that should only be sent back to the originating guest.
+    pub fn is_host_reply_for_one(&self) -> bool {
+        self.kind().is_host_reply_for_one()
+    }
+}
 
-            MsgPayload::HostToGuestPong(_) => false,
+/// The variant of a `MsgPayload` without its contents, so that routing code doesn't need to be generic over `SimInput` (see `peek_variant`).
+///
+/// Each kind's variant number in the message header (its wire id) is assigned in `variant_num`, independently of the order in which kinds are declared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MsgKind {
+    Empty,
+    Invalid,
+    GuestToHostAckFinalization,
+    HostToLobbyFinalizedSlice,
+    PeerInputs,
+    HostToGuestPreSimSync,
+    GuestToHostPing,
+    HostToGuestPong,
+    GuestToHostPongPong,
+    HostToGuestRateAdjust,
+    HostToLobbyRoundTransition,
+    GuestToHostRoundTransitionAck,
+    HostToLobbyPlayerMuted,
+    GuestToHostEvents,
+    HostToLobbyEvents,
+    GuestToHostAckEvents,
+    PeerInputChainHead,
+    PeerDeterminismSample,
+    HostToLobbyRecoveryRequest,
+    GuestToHostRecoveryResponse,
+    HostToLobbyAnnotations,
+    GuestToHostAckAnnotations,
+    HostToLobbySeed,
+    GuestToHostAckSeeds,
+    HostToGuestPingReport,
+    GuestToHostLegacyPongPong,
+    HostToLobbyEndSession,
+    GuestToHostEndAck,
+    HostToLobbySeatTransferred,
+    HostToLobbyStartProposal,
+    GuestToHostStartAck,
+    HostToLobbyStartConfirmed,
+    GuestToHostInputSchema,
+    GuestToHostCapabilities,
+    GuestToHostInputHashReport,
+    HostToLobbyPlayerJoined,
+    HostToLobbyPlayerRemoved,
+    GuestToHostClockPing,
+    HostToGuestClockPong,
+    GuestToHostBufferReset,
+    Batch,
+    DeliveryAck,
+    GuestToHostLocalInputs,
+}
 
-            MsgPayload::Empty => false,
-            MsgPayload::Invalid => false,
-            MsgPayload::PeerInputs(_) => false,
+impl MsgKind {
+    /// The kind with this variant number, if there is one.
+    pub const fn from_variant_num(variant_num: u8) -> Option<Self> {
+        match variant_num {
+            0 => Some(MsgKind::Empty),
+            1 => Some(MsgKind::Invalid),
+            2 => Some(MsgKind::GuestToHostAckFinalization),
+            3 => Some(MsgKind::HostToLobbyFinalizedSlice),
+            4 => Some(MsgKind::PeerInputs),
+            5 => Some(MsgKind::HostToGuestPreSimSync),
+            6 => Some(MsgKind::GuestToHostPing),
+            7 => Some(MsgKind::HostToGuestPong),
+            8 => Some(MsgKind::GuestToHostLegacyPongPong),
+            9 => Some(MsgKind::HostToGuestRateAdjust),
+            10 => Some(MsgKind::HostToLobbyRoundTransition),
+            11 => Some(MsgKind::GuestToHostRoundTransitionAck),
+            12 => Some(MsgKind::HostToLobbyPlayerMuted),
+            13 => Some(MsgKind::GuestToHostEvents),
+            14 => Some(MsgKind::HostToLobbyEvents),
+            15 => Some(MsgKind::GuestToHostAckEvents),
+            16 => Some(MsgKind::PeerInputChainHead),
+            17 => Some(MsgKind::PeerDeterminismSample),
+            18 => Some(MsgKind::HostToLobbyRecoveryRequest),
+            19 => Some(MsgKind::GuestToHostRecoveryResponse),
+            20 => Some(MsgKind::HostToLobbyAnnotations),
+            21 => Some(MsgKind::GuestToHostAckAnnotations),
+            22 => Some(MsgKind::HostToLobbySeed),
+            23 => Some(MsgKind::GuestToHostAckSeeds),
+            24 => Some(MsgKind::HostToGuestPingReport),
+            25 => Some(MsgKind::GuestToHostPongPong),
+            26 => Some(MsgKind::HostToLobbyEndSession),
+            27 => Some(MsgKind::GuestToHostEndAck),
+            28 => Some(MsgKind::HostToLobbySeatTransferred),
+            29 => Some(MsgKind::HostToLobbyStartProposal),
+            30 => Some(MsgKind::GuestToHostStartAck),
+            31 => Some(MsgKind::HostToLobbyStartConfirmed),
+            32 => Some(MsgKind::GuestToHostInputSchema),
+            33 => Some(MsgKind::GuestToHostCapabilities),
+            34 => Some(MsgKind::GuestToHostInputHashReport),
+            35 => Some(MsgKind::HostToLobbyPlayerJoined),
+            36 => Some(MsgKind::HostToLobbyPlayerRemoved),
+            37 => Some(MsgKind::GuestToHostClockPing),
+            38 => Some(MsgKind::HostToGuestClockPong),
+            39 => Some(MsgKind::GuestToHostBufferReset),
+            40 => Some(MsgKind::Batch),
+            41 => Some(MsgKind::DeliveryAck),
+            42 => Some(MsgKind::GuestToHostLocalInputs),
+            _ => None,
         }
     }
 
-    /// Returns true if this message is a host reply that should be broadcast to all guests.
-    pub fn is_host_reply_for_all(&self) -> bool {
+    /// The variant number of this kind in the message header: its wire id.
+    ///
+    /// Wire ids are part of the protocol, so they never change once assigned: a renamed kind keeps its id, and new kinds take new ids, below `COMPRESSED_FLAG`.
+    pub const fn variant_num(self) -> u8 {
         match self {
-            MsgPayload::HostToLobbyFinalizedSlice(_) => true,
-            MsgPayload::HostToGuestPreSimSync(_) => true,
+            MsgKind::Empty => 0,
+            MsgKind::Invalid => 1,
+            MsgKind::GuestToHostAckFinalization => 2,
+            MsgKind::HostToLobbyFinalizedSlice => 3,
+            MsgKind::PeerInputs => 4,
+            MsgKind::HostToGuestPreSimSync => 5,
+            MsgKind::GuestToHostPing => 6,
+            MsgKind::HostToGuestPong => 7,
+            // the pong-pong's payload gained the guest's RTT, so it took a new id; its old payload keeps the old one
+            MsgKind::GuestToHostLegacyPongPong => 8,
+            MsgKind::HostToGuestRateAdjust => 9,
+            MsgKind::HostToLobbyRoundTransition => 10,
+            MsgKind::GuestToHostRoundTransitionAck => 11,
+            MsgKind::HostToLobbyPlayerMuted => 12,
+            MsgKind::GuestToHostEvents => 13,
+            MsgKind::HostToLobbyEvents => 14,
+            MsgKind::GuestToHostAckEvents => 15,
+            MsgKind::PeerInputChainHead => 16,
+            MsgKind::PeerDeterminismSample => 17,
+            MsgKind::HostToLobbyRecoveryRequest => 18,
+            MsgKind::GuestToHostRecoveryResponse => 19,
+            MsgKind::HostToLobbyAnnotations => 20,
+            MsgKind::GuestToHostAckAnnotations => 21,
+            MsgKind::HostToLobbySeed => 22,
+            MsgKind::GuestToHostAckSeeds => 23,
+            MsgKind::HostToGuestPingReport => 24,
+            MsgKind::GuestToHostPongPong => 25,
+            MsgKind::HostToLobbyEndSession => 26,
+            MsgKind::GuestToHostEndAck => 27,
+            MsgKind::HostToLobbySeatTransferred => 28,
+            MsgKind::HostToLobbyStartProposal => 29,
+            MsgKind::GuestToHostStartAck => 30,
+            MsgKind::HostToLobbyStartConfirmed => 31,
+            MsgKind::GuestToHostInputSchema => 32,
+            MsgKind::GuestToHostCapabilities => 33,
+            MsgKind::GuestToHostInputHashReport => 34,
+            MsgKind::HostToLobbyPlayerJoined => 35,
+            MsgKind::HostToLobbyPlayerRemoved => 36,
+            MsgKind::GuestToHostClockPing => 37,
+            MsgKind::HostToGuestClockPong => 38,
+            MsgKind::GuestToHostBufferReset => 39,
+            MsgKind::Batch => 40,
+            MsgKind::DeliveryAck => 41,
+            MsgKind::GuestToHostLocalInputs => 42,
+        }
+    }
 
-            MsgPayload::HostToGuestPong(_) => false,
+    /// Returns true if messages of this kind are guest replies to host messages, and thus need to be sent to the host.
+    pub fn is_guest_reply(self) -> bool {
+        matches!(
+            self,
+            MsgKind::GuestToHostAckFinalization
+                | MsgKind::GuestToHostPing
+                | MsgKind::GuestToHostPongPong
+                | MsgKind::GuestToHostLegacyPongPong
+                | MsgKind::GuestToHostRoundTransitionAck
+                | MsgKind::GuestToHostAckEvents
+                | MsgKind::GuestToHostRecoveryResponse
+                | MsgKind::GuestToHostAckAnnotations
+                | MsgKind::GuestToHostAckSeeds
+                | MsgKind::GuestToHostEndAck
+                | MsgKind::GuestToHostStartAck
+                | MsgKind::GuestToHostInputSchema
+                | MsgKind::GuestToHostCapabilities
+                | MsgKind::GuestToHostClockPing
+        )
+    }
 
-            MsgPayload::GuestToHostPing(_) => false,
-            MsgPayload::GuestToHostPongPong(_) => false,
-            MsgPayload::GuestToHostAckFinalization(_) => false,
+    /// Returns true if messages of this kind are host replies that should be broadcast to all guests.
+    pub fn is_host_reply_for_all(self) -> bool {
+        matches!(
+            self,
+            MsgKind::HostToLobbyFinalizedSlice
+                | MsgKind::HostToGuestPreSimSync
+                | MsgKind::HostToLobbyRoundTransition
+                | MsgKind::HostToLobbyPlayerMuted
+                | MsgKind::HostToLobbyEvents
+                | MsgKind::HostToLobbyRecoveryRequest
+                | MsgKind::HostToLobbyAnnotations
+                | MsgKind::HostToLobbySeed
+                | MsgKind::HostToLobbyEndSession
+                | MsgKind::HostToLobbySeatTransferred
+                | MsgKind::HostToLobbyStartProposal
+                | MsgKind::HostToLobbyStartConfirmed
+                | MsgKind::HostToLobbyPlayerJoined
+                | MsgKind::HostToLobbyPlayerRemoved
+        )
+    }
 
-            MsgPayload::Empty => false,
-            MsgPayload::Invalid => false,
-            MsgPayload::PeerInputs(_) => false,
-        }
+    /// Returns true if messages of this kind are host replies that should only be sent back to the originating guest.
+    pub fn is_host_reply_for_one(self) -> bool {
+        matches!(
+            self,
+            MsgKind::HostToGuestPong
+                | MsgKind::HostToGuestRateAdjust
+                | MsgKind::HostToGuestPingReport
+                | MsgKind::HostToGuestClockPong
+        )
     }
 
-    /// Returns true if this message is a host reply that should only be sent back to the originating guest.
-    pub fn is_host_reply_for_one(&self) -> bool {
-        match self {
-            MsgPayload::HostToLobbyFinalizedSlice(_) => false,
-            MsgPayload::HostToGuestPreSimSync(_) => false,
+    /// Returns true if messages of this kind are resent until acked when reliable delivery is enabled (see `reliable_delivery`): losing one stalls finalization or the start of the sim.
+    pub fn is_critical(self) -> bool {
+        matches!(
+            self,
+            MsgKind::GuestToHostAckFinalization
+                | MsgKind::HostToLobbyFinalizedSlice
+                | MsgKind::HostToGuestPreSimSync
+        )
+    }
+}
+
+/// The names of message kinds from before they were prefixed with their direction, kept so that code written against them still compiles. Their wire ids are unchanged.
+#[allow(non_upper_case_globals)]
+impl MsgKind {
+    #[deprecated(note = "renamed to `MsgKind::GuestToHostAckFinalization`")]
+    pub const AckFinalization: MsgKind = MsgKind::GuestToHostAckFinalization;
+    #[deprecated(note = "renamed to `MsgKind::HostToLobbyFinalizedSlice`")]
+    pub const HostFinalizedSlice: MsgKind = MsgKind::HostToLobbyFinalizedSlice;
+    #[deprecated(note = "renamed to `MsgKind::HostToGuestPreSimSync`")]
+    pub const PreSimSync: MsgKind = MsgKind::HostToGuestPreSimSync;
+    #[deprecated(note = "renamed to `MsgKind::GuestToHostPing`")]
+    pub const Ping: MsgKind = MsgKind::GuestToHostPing;
+    #[deprecated(note = "renamed to `MsgKind::HostToGuestPong`")]
+    pub const Pong: MsgKind = MsgKind::HostToGuestPong;
+    #[deprecated(note = "renamed to `MsgKind::GuestToHostPongPong`")]
+    pub const PongPong: MsgKind = MsgKind::GuestToHostPongPong;
+}
 
-            MsgPayload::HostToGuestPong(_) => true,
+/// Constructors under the pre-rename variant names (see the `MsgKind` constants of the same names). Patterns must use the current names.
+#[allow(non_snake_case)]
+impl<T: SimInput> MsgPayload<T> {
+    #[deprecated(note = "renamed to `MsgPayload::GuestToHostAckFinalization`")]
+    pub fn AckFinalization(ack: PeerwiseFinalizedInputsSeen) -> Self {
+        MsgPayload::GuestToHostAckFinalization(ack)
+    }
 
-            MsgPayload::GuestToHostPing(_) => false,
-            MsgPayload::GuestToHostPongPong(_) => false,
-            MsgPayload::GuestToHostAckFinalization(_) => false,
+    #[deprecated(note = "renamed to `MsgPayload::HostToLobbyFinalizedSlice`")]
+    pub fn HostFinalizedSlice(slice: HostFinalizedSlice<T>) -> Self {
+        MsgPayload::HostToLobbyFinalizedSlice(slice)
+    }
 
-            MsgPayload::Empty => false,
-            MsgPayload::Invalid => false,
-            MsgPayload::PeerInputs(_) => false,
-        }
+    #[deprecated(note = "renamed to `MsgPayload::HostToGuestPreSimSync`")]
+    pub fn PreSimSync(sync: PreSimSync) -> Self {
+        MsgPayload::HostToGuestPreSimSync(sync)
+    }
+
+    #[deprecated(note = "renamed to `MsgPayload::GuestToHostPing`")]
+    pub fn Ping(ping_id: u32) -> Self {
+        MsgPayload::GuestToHostPing(ping_id)
+    }
+
+    #[deprecated(note = "renamed to `MsgPayload::HostToGuestPong`")]
+    pub fn Pong(ping_id: u32) -> Self {
+        MsgPayload::HostToGuestPong(ping_id)
+    }
+
+    #[deprecated(note = "renamed to `MsgPayload::GuestToHostPongPong`")]
+    pub fn PongPong(pong_pong: PongPong) -> Self {
+        MsgPayload::GuestToHostPongPong(pong_pong)
     }
 }
 
+/// Reads the kind of a serialized `MsgPayload` from its header byte, without decoding (or decompressing) the payload.
+///
+/// Like `MsgPayload::from_bytes`, an empty buffer is an empty message.
+pub fn peek_variant(bytes: &[u8]) -> Result<MsgKind, DecodeError> {
+    let Some(header) = bytes.first() else {
+        return Ok(MsgKind::Empty);
+    };
+    let variant_num = header & !COMPRESSED_FLAG;
+    MsgKind::from_variant_num(variant_num).ok_or_else(|| {
+        DecodeError::OtherString(format!("Unknown MsgPayload variant num: {variant_num}"))
+    })
+}
+
 pub fn to_bincode_bytes<T: Serialize>(value: &T) -> Vec<u8> {
     bincode::serde::encode_to_vec(value, bincode::config::standard()).unwrap()
 }
@@ -219,13 +918,86 @@ pub fn from_bincode_bytes<T: for<'a> Deserialize<'a>>(bytes: &[u8]) -> Result<T,
         .map(|(value, _)| value)
 }
 
+/// Set on the header byte of a serialized message when the payload bytes that follow it are compressed.
+///
+/// Variant numbers must therefore stay below this value.
+pub const COMPRESSED_FLAG: u8 = 0x80;
+
+// Checks at compile time that `MsgKind::from_variant_num` inverts `MsgKind::variant_num`, and that no wire id sets `COMPRESSED_FLAG`.
+const _: () = {
+    let mut variant_num = 0u8;
+    loop {
+        if let Some(kind) = MsgKind::from_variant_num(variant_num) {
+            assert!(kind.variant_num() == variant_num);
+            assert!(variant_num & COMPRESSED_FLAG == 0);
+        }
+        if variant_num == u8::MAX {
+            break;
+        }
+        variant_num += 1;
+    }
+};
+
+/// With the `compression` feature enabled, `MsgPayload::to_bytes` compresses payloads that serialize to at least this many bytes.
+pub const DEFAULT_COMPRESSION_THRESHOLD_BYTES: usize = 512;
+
+const FINALIZED_SLICE_VARIANT_NUM: u8 = MsgKind::HostToLobbyFinalizedSlice.variant_num();
+const PEER_INPUTS_VARIANT_NUM: u8 = MsgKind::PeerInputs.variant_num();
+
+/// Prepends the header byte to a serialized payload, without compressing it.
+fn frame_uncompressed(variant_num: u8, payload: Vec<u8>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(payload.len() + 1);
+    bytes.push(variant_num);
+    bytes.extend(payload);
+    bytes
+}
+
+/// Prepends the header byte to a serialized payload.
+#[cfg(not(feature = "compression"))]
+pub(crate) fn frame_msg_bytes(variant_num: u8, payload: Vec<u8>) -> Vec<u8> {
+    frame_uncompressed(variant_num, payload)
+}
+
+/// Prepends the header byte to a serialized payload, compressing payloads of at least `DEFAULT_COMPRESSION_THRESHOLD_BYTES`.
+#[cfg(feature = "compression")]
+pub(crate) fn frame_msg_bytes(variant_num: u8, payload: Vec<u8>) -> Vec<u8> {
+    frame_msg_bytes_with_compression_threshold(
+        variant_num,
+        payload,
+        DEFAULT_COMPRESSION_THRESHOLD_BYTES,
+    )
+}
+
+/// Prepends the header byte to a serialized payload, compressing it if it is at least `threshold` bytes and compression actually shrinks it.
+#[cfg(feature = "compression")]
+fn frame_msg_bytes_with_compression_threshold(
+    variant_num: u8,
+    payload: Vec<u8>,
+    threshold: usize,
+) -> Vec<u8> {
+    if payload.len() >= threshold {
+        let compressed = crate::compression::compress(&payload);
+        if compressed.len() < payload.len() {
+            let mut bytes = vec![variant_num | COMPRESSED_FLAG];
+            bytes.extend(compressed);
+            return bytes;
+        }
+    }
+    let mut bytes = Vec::with_capacity(payload.len() + 1);
+    bytes.push(variant_num);
+    bytes.extend(payload);
+    bytes
+}
+
 impl<T: SimInput> MsgPayload<T> {
-    /// The first byte of the serialized message is the variant number,
-    /// (which can be used to determine the type of message without deserializing).
-    /// The rest of the bytes are the (bincode) serialized data, if any.
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let mut bytes = vec![self.variant_num()];
-        let extension_bytes = match self {
+    /// The length of the serialized message (header byte included) before any compression.
+    pub(crate) fn uncompressed_len(&self) -> usize {
+        1 + self.payload_bytes().len()
+    }
+
+    /// The (bincode) serialized data of the message, without the header byte.
+    fn payload_bytes(&self) -> Vec<u8> {
+        match self {
             MsgPayload::Empty => vec![],
             MsgPayload::Invalid => vec![],
             MsgPayload::GuestToHostAckFinalization(ack) => to_bincode_bytes(ack),
@@ -234,13 +1006,164 @@ impl<T: SimInput> MsgPayload<T> {
             MsgPayload::HostToGuestPreSimSync(sync) => to_bincode_bytes(sync),
             MsgPayload::GuestToHostPing(ping_id) => to_bincode_bytes(ping_id),
             MsgPayload::HostToGuestPong(ping_id) => to_bincode_bytes(ping_id),
-            MsgPayload::GuestToHostPongPong(ping_id) => to_bincode_bytes(ping_id),
+            MsgPayload::GuestToHostPongPong(pong_pong) => to_bincode_bytes(pong_pong),
+            MsgPayload::HostToGuestRateAdjust(skew_ppm) => to_bincode_bytes(skew_ppm),
+            MsgPayload::HostToLobbyRoundTransition(round) => to_bincode_bytes(round),
+            MsgPayload::GuestToHostRoundTransitionAck(round) => to_bincode_bytes(round),
+            MsgPayload::HostToLobbyPlayerMuted(muted) => to_bincode_bytes(muted),
+            MsgPayload::GuestToHostEvents(events) => to_bincode_bytes(events),
+            MsgPayload::HostToLobbyEvents(events) => to_bincode_bytes(events),
+            MsgPayload::GuestToHostAckEvents(seen) => to_bincode_bytes(seen),
+            MsgPayload::PeerInputChainHead(head) => to_bincode_bytes(head),
+            MsgPayload::PeerDeterminismSample(sample) => to_bincode_bytes(sample),
+            MsgPayload::HostToLobbyRecoveryRequest(request_id) => to_bincode_bytes(request_id),
+            MsgPayload::GuestToHostRecoveryResponse(response) => to_bincode_bytes(response),
+            MsgPayload::HostToLobbyAnnotations(slice) => to_bincode_bytes(slice),
+            MsgPayload::GuestToHostAckAnnotations(num_seen) => to_bincode_bytes(num_seen),
+            MsgPayload::HostToLobbySeed(change) => to_bincode_bytes(change),
+            MsgPayload::GuestToHostAckSeeds(num_seen) => to_bincode_bytes(num_seen),
+            MsgPayload::HostToGuestPingReport(report) => to_bincode_bytes(report),
+            MsgPayload::GuestToHostLegacyPongPong(ping_id) => to_bincode_bytes(ping_id),
+            MsgPayload::HostToLobbyEndSession(final_tick) => to_bincode_bytes(final_tick),
+            MsgPayload::GuestToHostEndAck(final_tick) => to_bincode_bytes(final_tick),
+            MsgPayload::HostToLobbySeatTransferred(transfer) => to_bincode_bytes(transfer),
+            MsgPayload::HostToLobbyStartProposal(proposal) => to_bincode_bytes(proposal),
+            MsgPayload::GuestToHostStartAck(ack) => to_bincode_bytes(ack),
+            MsgPayload::HostToLobbyStartConfirmed(start) => to_bincode_bytes(start),
+            MsgPayload::GuestToHostInputSchema(schema_id) => to_bincode_bytes(schema_id),
+            MsgPayload::GuestToHostCapabilities(capabilities) => to_bincode_bytes(capabilities),
+            MsgPayload::GuestToHostInputHashReport(report) => to_bincode_bytes(report),
+            MsgPayload::HostToLobbyPlayerJoined(joined) => to_bincode_bytes(joined),
+            MsgPayload::HostToLobbyPlayerRemoved(removed) => to_bincode_bytes(removed),
+            MsgPayload::GuestToHostClockPing(ping) => to_bincode_bytes(ping),
+            MsgPayload::HostToGuestClockPong(pong) => to_bincode_bytes(pong),
+            MsgPayload::GuestToHostBufferReset(round) => to_bincode_bytes(round),
+            // each message is framed as by `to_bytes_for(Capabilities::NONE)`; the batch as a whole may be compressed
+            MsgPayload::Batch(msgs) => to_bincode_bytes(
+                &msgs
+                    .iter()
+                    .map(|msg| frame_uncompressed(msg.variant_num(), msg.payload_bytes()))
+                    .collect::<Vec<_>>(),
+            ),
+            MsgPayload::DeliveryAck(seqs) => to_bincode_bytes(seqs),
+            MsgPayload::GuestToHostLocalInputs(bundle) => to_bincode_bytes(bundle),
+        }
+    }
+
+    /// The first byte of the serialized message is the variant number,
+    /// (which can be used to determine the type of message without deserializing).
+    /// The rest of the bytes are the (bincode) serialized data, if any.
+    #[cfg(not(feature = "compression"))]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        frame_msg_bytes(self.variant_num(), self.payload_bytes())
+    }
+
+    /// The first byte of the serialized message is the variant number,
+    /// (which can be used to determine the type of message without deserializing).
+    /// The rest of the bytes are the (bincode) serialized data, if any.
+    ///
+    /// Payloads of at least `DEFAULT_COMPRESSION_THRESHOLD_BYTES` are compressed; see `to_bytes_with_compression_threshold`.
+    #[cfg(feature = "compression")]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with_compression_threshold(DEFAULT_COMPRESSION_THRESHOLD_BYTES)
+    }
+
+    /// Like `to_bytes`, but compresses the payload if it serializes to at least `threshold` bytes.
+    ///
+    /// Compressed messages have `COMPRESSED_FLAG` set on the header byte. If compression would not actually shrink the payload, it is sent uncompressed.
+    #[cfg(feature = "compression")]
+    pub fn to_bytes_with_compression_threshold(&self, threshold: usize) -> Vec<u8> {
+        frame_msg_bytes_with_compression_threshold(
+            self.variant_num(),
+            self.payload_bytes(),
+            threshold,
+        )
+    }
+
+    /// Like `to_bytes`, but only uses the optional capabilities in `capabilities`, e.g. those negotiated with the recipient (see `capabilities`).
+    ///
+    /// Without `Capabilities::COMPRESSION`, the payload is never compressed.
+    pub fn to_bytes_for(&self, capabilities: Capabilities) -> Vec<u8> {
+        if capabilities.contains(Capabilities::COMPRESSION) {
+            return self.to_bytes();
+        }
+        frame_uncompressed(self.variant_num(), self.payload_bytes())
+    }
+
+    /// Bundles messages for the same recipient into a `Batch`, leaving out empty messages. A lone message isn't wrapped, and no messages at all make an empty message.
+    pub fn batch(msgs: impl IntoIterator<Item = MsgPayload<T>>) -> Self {
+        let mut msgs: Vec<MsgPayload<T>> = msgs
+            .into_iter()
+            .filter(|msg| !matches!(msg, MsgPayload::Empty))
+            .collect();
+        match msgs.len() {
+            0 => MsgPayload::Empty,
+            1 => msgs.remove(0),
+            _ => MsgPayload::Batch(msgs),
+        }
+    }
+
+    /// Splits an input slice whose message is longer than `max_bytes` (before compression) into messages for consecutive parts of the slice, each within `max_bytes` unless it holds a single input. A batch that is too long has its slices split, and is repacked into several batches, each within `max_bytes` unless it holds a single message. Other messages, and messages that fit, are returned as they are.
+    ///
+    /// The parts are ordinary slices, so the receiver reassembles them by applying each in turn. A part that arrives after a lost one is ignored like any slice that would leave a gap, and is sent again with the rest.
+    pub fn split_to_max_bytes(self, max_bytes: usize) -> Vec<Self> {
+        if self.uncompressed_len() <= max_bytes {
+            return vec![self];
+        }
+        let (first, rest) = match self {
+            MsgPayload::Batch(msgs) => return Self::repack_batch(msgs, max_bytes),
+            MsgPayload::PeerInputs(mut slice) if slice.len() > 1 => {
+                let rest = slice.split_off(slice.start + slice.len() / 2);
+                (MsgPayload::PeerInputs(slice), MsgPayload::PeerInputs(rest))
+            }
+            MsgPayload::HostToLobbyFinalizedSlice(mut slice) if slice.inputs.len() > 1 => {
+                let mid = slice.inputs.start + slice.inputs.len() / 2;
+                let rest = HostFinalizedSlice {
+                    player_num: slice.player_num,
+                    host_tick: slice.host_tick,
+                    inputs: slice.inputs.split_off(mid),
+                };
+                (
+                    MsgPayload::HostToLobbyFinalizedSlice(slice),
+                    MsgPayload::HostToLobbyFinalizedSlice(rest),
+                )
+            }
+            msg => return vec![msg],
         };
-        bytes.extend(extension_bytes);
-        bytes
+        let mut parts = first.split_to_max_bytes(max_bytes);
+        parts.extend(rest.split_to_max_bytes(max_bytes));
+        parts
+    }
+
+    // splits each message of a batch, then packs the parts in order into as
+    // few batches within `max_bytes` as the greedy fill allows
+    fn repack_batch(msgs: Vec<Self>, max_bytes: usize) -> Vec<Self> {
+        // a batch is its header, its message count, and each message behind its length
+        let varint_len = |n: usize| to_bincode_bytes(&(n as u64)).len();
+        let mut batches = vec![];
+        let mut current: Vec<Self> = vec![];
+        let mut current_len = 0;
+        for part in msgs
+            .into_iter()
+            .flat_map(|msg| msg.split_to_max_bytes(max_bytes))
+        {
+            let part_len = part.uncompressed_len();
+            let part_len = varint_len(part_len) + part_len;
+            let batch_len = 1 + varint_len(current.len() + 1) + current_len + part_len;
+            if !current.is_empty() && batch_len > max_bytes {
+                batches.push(MsgPayload::batch(std::mem::take(&mut current)));
+                current_len = 0;
+            }
+            current_len += part_len;
+            current.push(part);
+        }
+        batches.push(MsgPayload::batch(current));
+        batches
     }
 
     /// Deserialize a `MsgPayload` from bytes.
+    ///
+    /// Compressed payloads can only be decoded with the `compression` feature enabled; otherwise they produce an error.
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError>
     where
         T: for<'a> Deserialize<'a>,
@@ -248,59 +1171,190 @@ impl<T: SimInput> MsgPayload<T> {
         if bytes.is_empty() {
             return Ok(MsgPayload::Empty);
         }
-        let variant_num = bytes[0];
-        let payload_bytes = &bytes[1..];
+        let variant_num = bytes[0] & !COMPRESSED_FLAG;
+        let payload_bytes = if bytes[0] & COMPRESSED_FLAG != 0 {
+            Cow::Owned(decompress_payload(&bytes[1..])?)
+        } else {
+            Cow::Borrowed(&bytes[1..])
+        };
+        let payload_bytes = payload_bytes.as_ref();
 
-        match variant_num {
-            0 => Ok(MsgPayload::Empty),
-            1 => Ok(MsgPayload::Invalid),
-            2 => Ok(MsgPayload::GuestToHostAckFinalization(from_bincode_bytes(
-                payload_bytes,
-            )?)),
-            3 => Ok(MsgPayload::HostToLobbyFinalizedSlice(from_bincode_bytes(
-                payload_bytes,
-            )?)),
-            4 => Ok(MsgPayload::PeerInputs(from_bincode_bytes(payload_bytes)?)),
-            5 => Ok(MsgPayload::HostToGuestPreSimSync(from_bincode_bytes(
-                payload_bytes,
-            )?)),
-            6 => Ok(MsgPayload::GuestToHostPing(from_bincode_bytes(
+        match MsgKind::from_variant_num(variant_num) {
+            Some(MsgKind::Empty) => Ok(MsgPayload::Empty),
+            Some(MsgKind::Invalid) => Ok(MsgPayload::Invalid),
+            Some(MsgKind::GuestToHostAckFinalization) => Ok(
+                MsgPayload::GuestToHostAckFinalization(from_bincode_bytes(payload_bytes)?),
+            ),
+            Some(MsgKind::HostToLobbyFinalizedSlice) => Ok(MsgPayload::HostToLobbyFinalizedSlice(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::PeerInputs) => {
+                Ok(MsgPayload::PeerInputs(from_bincode_bytes(payload_bytes)?))
+            }
+            Some(MsgKind::HostToGuestPreSimSync) => Ok(MsgPayload::HostToGuestPreSimSync(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::GuestToHostPing) => Ok(MsgPayload::GuestToHostPing(from_bincode_bytes(
                 payload_bytes,
             )?)),
-            7 => Ok(MsgPayload::HostToGuestPong(from_bincode_bytes(
+            Some(MsgKind::HostToGuestPong) => Ok(MsgPayload::HostToGuestPong(from_bincode_bytes(
                 payload_bytes,
             )?)),
-            8 => Ok(MsgPayload::GuestToHostPongPong(from_bincode_bytes(
+            Some(MsgKind::GuestToHostPongPong) => Ok(MsgPayload::GuestToHostPongPong(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::HostToGuestRateAdjust) => Ok(MsgPayload::HostToGuestRateAdjust(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::HostToLobbyRoundTransition) => Ok(
+                MsgPayload::HostToLobbyRoundTransition(from_bincode_bytes(payload_bytes)?),
+            ),
+            Some(MsgKind::GuestToHostRoundTransitionAck) => Ok(
+                MsgPayload::GuestToHostRoundTransitionAck(from_bincode_bytes(payload_bytes)?),
+            ),
+            Some(MsgKind::HostToLobbyPlayerMuted) => Ok(MsgPayload::HostToLobbyPlayerMuted(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::GuestToHostEvents) => Ok(MsgPayload::GuestToHostEvents(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::HostToLobbyEvents) => Ok(MsgPayload::HostToLobbyEvents(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::GuestToHostAckEvents) => Ok(MsgPayload::GuestToHostAckEvents(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::PeerInputChainHead) => Ok(MsgPayload::PeerInputChainHead(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::PeerDeterminismSample) => Ok(MsgPayload::PeerDeterminismSample(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::HostToLobbyRecoveryRequest) => Ok(
+                MsgPayload::HostToLobbyRecoveryRequest(from_bincode_bytes(payload_bytes)?),
+            ),
+            Some(MsgKind::GuestToHostRecoveryResponse) => Ok(
+                MsgPayload::GuestToHostRecoveryResponse(from_bincode_bytes(payload_bytes)?),
+            ),
+            Some(MsgKind::HostToLobbyAnnotations) => Ok(MsgPayload::HostToLobbyAnnotations(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::GuestToHostAckAnnotations) => Ok(MsgPayload::GuestToHostAckAnnotations(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::HostToLobbySeed) => Ok(MsgPayload::HostToLobbySeed(from_bincode_bytes(
                 payload_bytes,
             )?)),
-            x => Err(DecodeError::OtherString(format!(
-                "Unknown MsgPayload variant num: {x}"
+            Some(MsgKind::GuestToHostAckSeeds) => Ok(MsgPayload::GuestToHostAckSeeds(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::HostToGuestPingReport) => Ok(MsgPayload::HostToGuestPingReport(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::GuestToHostLegacyPongPong) => Ok(MsgPayload::GuestToHostLegacyPongPong(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::HostToLobbyEndSession) => Ok(MsgPayload::HostToLobbyEndSession(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::GuestToHostEndAck) => Ok(MsgPayload::GuestToHostEndAck(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::HostToLobbySeatTransferred) => Ok(
+                MsgPayload::HostToLobbySeatTransferred(from_bincode_bytes(payload_bytes)?),
+            ),
+            Some(MsgKind::HostToLobbyStartProposal) => Ok(MsgPayload::HostToLobbyStartProposal(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::GuestToHostStartAck) => Ok(MsgPayload::GuestToHostStartAck(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::HostToLobbyStartConfirmed) => Ok(MsgPayload::HostToLobbyStartConfirmed(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::GuestToHostInputSchema) => Ok(MsgPayload::GuestToHostInputSchema(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::GuestToHostCapabilities) => Ok(MsgPayload::GuestToHostCapabilities(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::GuestToHostInputHashReport) => Ok(
+                MsgPayload::GuestToHostInputHashReport(from_bincode_bytes(payload_bytes)?),
+            ),
+            Some(MsgKind::HostToLobbyPlayerJoined) => Ok(MsgPayload::HostToLobbyPlayerJoined(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::HostToLobbyPlayerRemoved) => Ok(MsgPayload::HostToLobbyPlayerRemoved(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::GuestToHostClockPing) => Ok(MsgPayload::GuestToHostClockPing(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::HostToGuestClockPong) => Ok(MsgPayload::HostToGuestClockPong(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::GuestToHostBufferReset) => Ok(MsgPayload::GuestToHostBufferReset(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            Some(MsgKind::Batch) => {
+                let frames: Vec<Vec<u8>> = from_bincode_bytes(payload_bytes)?;
+                let msgs = frames
+                    .iter()
+                    .map(|frame| {
+                        if peek_variant(frame)? == MsgKind::Batch {
+                            return Err(DecodeError::Other("batches can't be nested"));
+                        }
+                        Self::from_bytes(frame)
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(MsgPayload::Batch(msgs))
+            }
+            Some(MsgKind::DeliveryAck) => {
+                Ok(MsgPayload::DeliveryAck(from_bincode_bytes(payload_bytes)?))
+            }
+            Some(MsgKind::GuestToHostLocalInputs) => Ok(MsgPayload::GuestToHostLocalInputs(
+                from_bincode_bytes(payload_bytes)?,
+            )),
+            None => Err(DecodeError::OtherString(format!(
+                "Unknown MsgPayload variant num: {variant_num}"
             ))),
         }
     }
 }
 
-impl<T: SimInput> Into<MsgPayload<T>> for HostFinalizedSlice<T> {
-    fn into(self) -> MsgPayload<T> {
-        MsgPayload::HostToLobbyFinalizedSlice(self)
+#[cfg(feature = "compression")]
+pub(crate) fn decompress_payload(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    crate::compression::decompress(bytes)
+}
+
+#[cfg(not(feature = "compression"))]
+pub(crate) fn decompress_payload(_bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    Err(DecodeError::Other(
+        "received a compressed message, but the `compression` feature is not enabled",
+    ))
+}
+
+impl<T: SimInput> From<HostFinalizedSlice<T>> for MsgPayload<T> {
+    fn from(value: HostFinalizedSlice<T>) -> Self {
+        MsgPayload::HostToLobbyFinalizedSlice(value)
     }
 }
 
-impl<T: SimInput> Into<MsgPayload<T>> for PlayerInputSlice<T> {
-    fn into(self) -> MsgPayload<T> {
-        MsgPayload::PeerInputs(self)
+impl<T: SimInput> From<PlayerInputSlice<T>> for MsgPayload<T> {
+    fn from(value: PlayerInputSlice<T>) -> Self {
+        MsgPayload::PeerInputs(value)
     }
 }
 
-impl<T: SimInput> Into<MsgPayload<T>> for PeerwiseFinalizedInputsSeen {
-    fn into(self) -> MsgPayload<T> {
-        MsgPayload::GuestToHostAckFinalization(self)
+impl<T: SimInput> From<PeerwiseFinalizedInputsSeen> for MsgPayload<T> {
+    fn from(value: PeerwiseFinalizedInputsSeen) -> Self {
+        MsgPayload::GuestToHostAckFinalization(value)
     }
 }
 
-impl<T: SimInput> Into<MsgPayload<T>> for PreSimSync {
-    fn into(self) -> MsgPayload<T> {
-        MsgPayload::HostToGuestPreSimSync(self)
+impl<T: SimInput> From<PreSimSync> for MsgPayload<T> {
+    fn from(value: PreSimSync) -> Self {
+        MsgPayload::HostToGuestPreSimSync(value)
     }
 }
 
@@ -0,0 +1,30 @@
+//! Generators of input sequences for scenario tests and benchmarks.
+//!
+//! Each generator returns a closure from an input index to an input, for use with `PlayerInputSlice::from_fn` (or, in the crate's own tests, the buffers' `append_inputs_from_fn`). Since the input depends only on its index (and the generator's arguments), the same index always yields the same input, whatever order the inputs are generated in.
+
+use crate::input_trait::SimInput;
+
+/// Alternates between `pressed` for `period` inputs and `released` for the next `period` (at least 1), starting with `pressed` at index 0.
+pub fn alternating<T: SimInput>(period: u32, pressed: T, released: T) -> impl Fn(u32) -> T {
+    let period = period.max(1);
+    move |index| {
+        if (index / period).is_multiple_of(2) {
+            pressed.clone()
+        } else {
+            released.clone()
+        }
+    }
+}
+
+/// Pseudo-random inputs: `to_input` maps a random `u64`, drawn from `seed` and the input index, to an input.
+pub fn seeded_random<T: SimInput>(seed: u64, to_input: impl Fn(u64) -> T) -> impl Fn(u32) -> T {
+    move |index| to_input(splitmix64(seed ^ splitmix64(index as u64)))
+}
+
+/// The splitmix64 finalizer, which spreads nearby values (like consecutive indices) across the whole `u64` range.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,52 @@
+use crate::ewma::Ewma;
+
+/// The minimum amount of host sim time (sec) over which input arrivals are counted before they are folded into the rate estimate.
+///
+/// Guests send inputs in bursts (one packet may carry several ticks, and several packets may arrive in one frame), so sampling over a window keeps the estimate from being dominated by packet jitter.
+pub(crate) const RATE_SAMPLE_WINDOW_SEC: f32 = 0.5;
+
+/// Tracks how quickly new inputs arrive from a single guest, measured against the host's sim time.
+#[derive(Debug, Default)]
+pub(crate) struct InputArrivalRate {
+    /// the host sim time at which the current sample window started
+    window_start_time: f32,
+    /// the number of inputs received from the guest when the current sample window started
+    window_start_inputs: u32,
+    /// the end (exclusive) of the highest input tick received so far
+    inputs_received: u32,
+    /// whether the first window has been started
+    started: bool,
+    /// EWMA of inputs per second; `None` until the first window completes
+    rate: Option<Ewma>,
+}
+
+impl InputArrivalRate {
+    /// Record that inputs up to (but not including) `inputs_end` have been received by `sim_time`.
+    pub fn observe(&mut self, inputs_end: u32, sim_time: f32) {
+        self.inputs_received = self.inputs_received.max(inputs_end);
+        if !self.started {
+            self.started = true;
+            self.window_start_time = sim_time;
+            self.window_start_inputs = self.inputs_received;
+            return;
+        }
+
+        let elapsed = sim_time - self.window_start_time;
+        if elapsed < RATE_SAMPLE_WINDOW_SEC {
+            return;
+        }
+
+        let sample = (self.inputs_received - self.window_start_inputs) as f32 / elapsed;
+        match self.rate.as_mut() {
+            None => self.rate = Some(Ewma::default().with_value(sample)),
+            Some(ewma) => ewma.observe(sample),
+        }
+        self.window_start_time = sim_time;
+        self.window_start_inputs = self.inputs_received;
+    }
+
+    /// Inputs per second, or `None` if no full sample window has been observed yet.
+    pub fn rate(&self) -> Option<f32> {
+        self.rate.as_ref().map(Ewma::value)
+    }
+}
@@ -0,0 +1,55 @@
+//! Own inputs placed at specific future ticks, for scripted demos and input-injection tools.
+//!
+//! `MultiplayerInputManager::schedule_own_input` queues an input for the tick it should land on. Collection goes on as usual (`add_own_input` on guests, `add_host_input_to_fill_needed` on hosts, and their staged variants), but whenever the input being collected lands on a scheduled tick, the scheduled input is collected in place of the one passed in. A scheduled input covers only its own tick: when the host fills several ticks at once, the ticks after it get the input passed in, not a copy of the scheduled one.
+//!
+//! Ticks that are filled without collecting an input (e.g. default inputs padding a guest's transferred seat) drop anything scheduled for them. Scheduled inputs are dropped at each new round.
+
+use std::collections::BTreeMap;
+
+/// Why `schedule_own_input` refused a tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleInputError {
+    /// This node has already collected its own input for the tick; `next` is the first tick it hasn't.
+    AlreadyCollected { tick: u32, next: u32 },
+    /// The tick is at or past the end of the session (see `MultiplayerInputManager::with_max_session_ticks`).
+    PastSessionEnd { tick: u32, end_tick: u32 },
+}
+
+/// Scheduled own inputs, keyed by the index of the input they replace.
+#[derive(Debug, Clone)]
+pub(crate) struct InputSchedule<T> {
+    inputs: BTreeMap<u32, T>,
+}
+
+impl<T> Default for InputSchedule<T> {
+    fn default() -> Self {
+        Self {
+            inputs: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> InputSchedule<T> {
+    /// Schedules `input` for input index `index`, replacing any input already scheduled there.
+    pub(crate) fn insert(&mut self, index: u32, input: T) {
+        self.inputs.insert(index, input);
+    }
+
+    /// The input scheduled for `index`, if any, dropping any scheduled for earlier indices.
+    pub(crate) fn take(&mut self, index: u32) -> Option<T> {
+        while let Some(entry) = self.inputs.first_entry()
+            && *entry.key() < index
+        {
+            entry.remove();
+        }
+        self.inputs.remove(&index)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.inputs.clear();
+    }
+}
@@ -0,0 +1,20 @@
+//! Checks that every peer encodes inputs the same way.
+//!
+//! Input bytes carry no description of their layout, so a peer running another game version (or another input type) would have its inputs silently misread. Each `SimInput` names its encoding with `SimInput::SCHEMA_ID`, which peers exchange when joining:
+//!
+//! 1. The host's id is carried in the `PreSimSync`, and a guest whose own id differs rejects it with `rx_pre_sim_sync`, without adopting the host's session config.
+//! 2. A guest that accepts the `PreSimSync` replies with a `GuestToHostInputSchema` message (`get_msg_input_schema`), which the host checks with `rx_guest_input_schema`.
+//!
+//! Either side raises `InputMgrEvent::InputSchemaMismatch` for a mismatching peer. `Session` handles a host-side mismatch by muting the guest, so its inputs are never read.
+
+use crate::util_types::PlayerNum;
+
+/// A peer's input schema id differs from this node's (see `SimInput::SCHEMA_ID`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputSchemaMismatch {
+    pub peer: PlayerNum,
+    /// This node's schema id
+    pub ours: u32,
+    /// The peer's schema id
+    pub theirs: u32,
+}
@@ -0,0 +1,49 @@
+//! A local queue of raw device events, reduced into one input whenever the manager assigns inputs to ticks.
+//!
+//! Devices may deliver any number of events between ticks. Rather than pre-aggregating them into one `T` per tick, callers can push them into an `InputStagingQueue` and pass the queue to `add_host_staged_input_to_fill_needed` (host) or `add_own_staged_input` (guest). The manager drains the queue only when at least one tick needs an input, so events are never dropped while no tick is being assigned.
+
+type Reducer<E, T> = Box<dyn Fn(&[E]) -> T>;
+
+/// Raw events staged for the next input, and the reducer that aggregates them.
+pub struct InputStagingQueue<E, T> {
+    events: Vec<E>,
+    reducer: Reducer<E, T>,
+}
+
+impl<E, T> InputStagingQueue<E, T> {
+    /// `reducer` aggregates the events staged since the last drain into one input. It is also called with no events when ticks need inputs but nothing was staged.
+    pub fn new(reducer: impl Fn(&[E]) -> T + 'static) -> Self {
+        Self {
+            events: Vec::new(),
+            reducer: Box::new(reducer),
+        }
+    }
+
+    pub fn push(&mut self, event: E) {
+        self.events.push(event);
+    }
+
+    /// The number of events staged since the last drain.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Reduces the staged events into one input, and clears the queue.
+    pub(crate) fn drain_reduced(&mut self) -> T {
+        let input = (self.reducer)(&self.events);
+        self.events.clear();
+        input
+    }
+}
+
+impl<E, T> std::fmt::Debug for InputStagingQueue<E, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InputStagingQueue")
+            .field("num_events", &self.events.len())
+            .finish()
+    }
+}
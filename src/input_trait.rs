@@ -16,6 +16,12 @@ pub trait SimInput: Default + Clone + Debug + Serialize {
     fn to_bytes(&self) -> Self::Bytes;
     /// returns Self from a fixed sized byte representation of the input tick
     fn from_bytes(bytes: Self::Bytes) -> Self;
+    /// Identifies the encoding of `Self::Bytes`, e.g. a hash of its layout or a version number bumped whenever the encoding changes. Peers exchange it when joining, and a peer whose id differs from this node's is rejected rather than having its input bytes misread (see `input_schema`).
+    const SCHEMA_ID: u32 = 0;
+    /// Called on every input entering a buffer (own inputs, peer slices and finalized slices), so that games can clamp analog ranges and clear invalid button combinations in one place.
+    ///
+    /// Must be deterministic, and should be idempotent. Inputs changed by sanitizing are counted (see `MultiplayerInputManager::num_sanitized_inputs`).
+    fn sanitize(&mut self) {}
 }
 
 pub trait TestInputBytes: SimInput {
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+/// Finalization latencies above this many ticks are counted as this many ticks.
+pub const MAX_TRACKED_LATENCY_TICKS: u32 = 255;
+
+/// Percentile summary of how many ticks after their own tick a player's inputs were finalized by the host.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub num_samples: u32,
+    pub mean: f32,
+    pub p50: u32,
+    pub p90: u32,
+    pub p99: u32,
+    pub max: u32,
+}
+
+/// A histogram of finalization latencies (in ticks) for a single player.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LatencyHistogram {
+    /// The number of samples at each latency, indexed by latency
+    counts: Vec<u32>,
+    num_samples: u32,
+    total: u64,
+}
+
+impl LatencyHistogram {
+    pub(crate) fn observe(&mut self, latency_ticks: u32) {
+        let latency = latency_ticks.min(MAX_TRACKED_LATENCY_TICKS) as usize;
+        if self.counts.len() <= latency {
+            self.counts.resize(latency + 1, 0);
+        }
+        self.counts[latency] += 1;
+        self.num_samples += 1;
+        self.total += latency as u64;
+    }
+
+    /// Removes a sample previously passed to `observe`.
+    pub(crate) fn forget(&mut self, latency_ticks: u32) {
+        let latency = latency_ticks.min(MAX_TRACKED_LATENCY_TICKS) as usize;
+        debug_assert!(self.counts.get(latency).is_some_and(|c| *c > 0));
+        self.counts[latency] -= 1;
+        self.num_samples -= 1;
+        self.total -= latency as u64;
+    }
+
+    /// The smallest latency at or below which at least `percent` of the samples fall.
+    fn percentile(&self, percent: u32) -> u32 {
+        let rank = (self.num_samples as u64 * percent as u64)
+            .div_ceil(100)
+            .max(1);
+        let mut seen = 0u64;
+        for (latency, count) in self.counts.iter().enumerate() {
+            seen += *count as u64;
+            if seen >= rank {
+                return latency as u32;
+            }
+        }
+        self.counts.len().saturating_sub(1) as u32
+    }
+
+    /// `None` if no samples have been observed.
+    pub(crate) fn summary(&self) -> Option<LatencySummary> {
+        if self.num_samples == 0 {
+            return None;
+        }
+        Some(LatencySummary {
+            num_samples: self.num_samples,
+            mean: self.total as f32 / self.num_samples as f32,
+            p50: self.percentile(50),
+            p90: self.percentile(90),
+            p99: self.percentile(99),
+            max: self.percentile(100),
+        })
+    }
+}
@@ -1,26 +1,149 @@
 #![feature(duration_millis_float)]
 
+mod bandwidth_budget;
+mod buffer_diff;
+mod button_state;
+mod capabilities;
+mod change_stamps;
+mod clock_sync;
+#[cfg(feature = "compression")]
+mod compression;
+mod debug_dump;
+mod decode_stats;
+mod desync_report;
+mod determinism_probe;
+mod event_channel;
+mod events;
 mod ewma;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod finalization_watch;
+mod finalized_input_channel;
 mod finalized_observations_per_guest;
+mod full_state;
+mod health_score;
+mod host_recovery;
 mod input_buffer;
+mod input_hash_chain;
 mod input_messages;
+mod input_patterns;
+mod input_rate;
+mod input_schedule;
+mod input_schema;
+mod input_staging;
 mod input_trait;
+mod latency_stats;
+mod msg_dedup;
+mod msg_sequence;
 mod multiplayer_input_buffer;
 mod multiplayer_input_manager;
 mod multiplayer_input_manager_guest;
 mod multiplayer_input_manager_host;
+mod multiplayer_input_manager_spectator;
+mod net_stats;
+mod outgoing_queue;
 mod peerwise_finalized_input;
+mod player_metadata;
+pub mod prelude;
+#[cfg(feature = "profiling")]
+mod profiling;
+mod reliable_delivery;
+mod replay;
+mod rollback_depth;
+mod rtt;
+mod rx_outcome;
+mod seed_schedule;
+mod session;
+mod session_limit;
+#[cfg(feature = "sim_net")]
+mod sim_net;
+mod slice_throttle;
+mod start_sync;
+mod tick_consumption;
+mod tick_map;
+mod unknown_player_slices;
 mod util_types;
+mod wire_cost;
 
 pub use crate::{
-    input_buffer::InputStatus,
-    input_messages::MsgPayload,
+    bandwidth_budget::{BANDWIDTH_STARVATION_FRAMES, MsgPriority},
+    buffer_diff::{BufferDiff, PlayerBufferDiff},
+    button_state::{ButtonState, button_states, hold_duration_ticks, is_double_tap},
+    capabilities::Capabilities,
+    change_stamps::ChangeStamps,
+    clock_sync::{CLOCK_SYNC_ROUND_TRIP_SLACK_MICROS, ClockPing, ClockPong},
+    debug_dump::{DEBUG_DUMP_RECENT_TICKS, DebugDump, DumpRole, PlayerDebugDump},
+    decode_stats::{DEFAULT_MALFORMED_MSG_THRESHOLD, MALFORMED_QUARANTINE_LEN, MalformedMsg},
+    desync_report::{
+        DESYNC_REPORT_CAPACITY, DesyncReport, InputHashReport, MAX_INPUT_HASH_REPORT_TICKS,
+    },
+    determinism_probe::{
+        DETERMINISM_PROBE_CAPACITY, DeterminismCheck, DeterminismReport, DeterminismSample,
+        DivergenceKind,
+    },
+    event_channel::{EventSlice, MAX_PENDING_EVENTS_PER_PLAYER, TickEvent},
+    events::{DEFAULT_MAX_QUEUED_EVENTS, InputMgrEvent},
+    finalization_watch::FinalizationHandle,
+    finalized_input_channel::{DEFAULT_FINALIZED_INPUT_RX_DEPTH, FinalizedInputRx},
+    finalized_observations_per_guest::ObservationBlocker,
+    health_score::{HEALTH_HYSTERESIS_POINTS, HealthComponents, HealthScore, STALL_WINDOW_SEC},
+    host_recovery::RecoveryResponse,
+    input_buffer::{FinalizedSliceOutcome, IgnoredSliceReason, InputConfidence, InputStatus},
+    input_hash_chain::{ChainCheck, INPUT_CHAIN_SEED, InputChainHead},
+    input_messages::{
+        COMPRESSED_FLAG, DEFAULT_COMPRESSION_THRESHOLD_BYTES, HostFinalizedSliceRef, MsgKind,
+        MsgPayload, PongPong, peek_variant,
+    },
+    input_patterns::{alternating, seeded_random},
+    input_schedule::ScheduleInputError,
+    input_schema::InputSchemaMismatch,
+    input_staging::InputStagingQueue,
     input_trait::SimInput,
-    multiplayer_input_manager::MultiplayerInputManager,
-    multiplayer_input_manager_guest::GuestInputMgr,
-    multiplayer_input_manager_host::HostInputMgr,
-    util_types::{PlayerInputSlice, PlayerNum},
+    latency_stats::{LatencySummary, MAX_TRACKED_LATENCY_TICKS},
+    msg_sequence::{SEQUENCE_WINDOW, SequenceStats, SequencedMsg},
+    multiplayer_input_manager::{DEFAULT_MAX_INPUT_LEAD, MultiplayerInputManager},
+    multiplayer_input_manager_guest::{
+        AheadOfHostPolicy, DEFAULT_ACK_MAX_INTERVAL_SEC, DEFAULT_ACK_MIN_NEW_INPUTS,
+        DEFAULT_HOST_TICK_REGRESSION_THRESHOLD, DEFAULT_PING_INTERVAL_SEC, GuestInputMgr,
+        MICRO_TICKS_PER_TICK,
+    },
+    multiplayer_input_manager_host::{
+        HostInputMgr, InputsInFlight, LagDowngradePolicy, LobbyPeerStatus, ResendPolicy,
+    },
+    multiplayer_input_manager_spectator::SpectatorInputMgr,
+    net_stats::{MsgCounts, NET_STATS_RATE_WINDOW_SEC, NetStats, PeerNetStats},
+    outgoing_queue::{DEFAULT_OUTGOING_QUEUE_DEPTH, OutgoingQueue, OutgoingQueueSummary},
+    player_metadata::{MAX_PLAYER_METADATA_BYTES, PlayerMetadataError},
+    reliable_delivery::{DEFAULT_RESEND_AFTER_SEC, MAX_PENDING_RELIABLE_MSGS},
+    replay::{
+        REPLAY_FLAG_CHAIN_HEADS, REPLAY_FLAG_CHECKSUMS, REPLAY_FORMAT_VERSION, REPLAY_MAGIC,
+        Replay, ReplayPlayer, ReplayRecorder,
+    },
+    rtt::{
+        DEFAULT_RTT_JITTER_PADDING, DEFAULT_RTT_SMOOTHING, DEFAULT_RTT_WARM_UP_SAMPLES, PingReport,
+        RttConfig, RttSummary,
+    },
+    rx_outcome::{RxOutcome, RxRejection},
+    seed_schedule::SeedChange,
+    session::{OutgoingMsgs, Recipient, Session, SessionPhase, SessionRxError},
+    session_limit::MAX_SESSION_TICKS,
+    slice_throttle::{AdaptiveSliceConfig, SliceRedundancy},
+    start_sync::{START_MARGIN_TICKS, ScheduledStart, StartAck},
+    tick_consumption::TickConsumptionError,
+    tick_map::TickMap,
+    unknown_player_slices::MAX_HELD_SLICES_PER_PLAYER,
+    util_types::{PlayerInputSlice, PlayerInputSliceRef, PlayerNum},
+    wire_cost::{
+        REPORT_NUM_PLAYERS, REPORT_SLICE_LENS, REPORT_START_INDEX, WireCost, WireCostReport,
+        wire_cost_report,
+    },
 };
 
+#[cfg(feature = "profiling")]
+pub use crate::profiling::{CallBudget, CallProfiler, CallStats, FrameProfile, ProfileProbe};
+
+#[cfg(feature = "sim_net")]
+pub use crate::sim_net::{SimLatency, SimNet, SimNetConfig, SimNetStats};
+
 #[cfg(test)]
 pub mod tests;
@@ -0,0 +1,65 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{input_messages::MsgKind, util_types::PlayerNum};
+
+/// The most recently sent messages remembered per destination.
+pub(crate) const MSG_DEDUP_CAPACITY: usize = 32;
+
+/// Identifies a sent message: its kind, the range of inputs it carries (if any), and a hash of its content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SentMsgKey {
+    pub(crate) kind: MsgKind,
+    /// The start and length of the message's input slice
+    pub(crate) range: Option<(u32, u32)>,
+    pub(crate) hash: u64,
+}
+
+/// Recently sent messages per destination, so that identical messages regenerated within a time window can be suppressed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MsgDedupCache {
+    /// CONFIG SETTING
+    /// How long (sec) a sent message suppresses identical ones; `None` to never suppress.
+    pub(crate) window_sec: Option<f32>,
+    /// For each destination, recently sent messages with the time they were sent, oldest first
+    sent: HashMap<PlayerNum, VecDeque<(SentMsgKey, f32)>>,
+    num_suppressed: HashMap<PlayerNum, u32>,
+}
+
+impl MsgDedupCache {
+    /// Returns true if an identical message was sent to `destination` within the window before `now_sec`; otherwise records this one as sent.
+    pub(crate) fn is_duplicate(
+        &mut self,
+        destination: PlayerNum,
+        key: SentMsgKey,
+        now_sec: f32,
+    ) -> bool {
+        let Some(window_sec) = self.window_sec else {
+            return false;
+        };
+        let sent = self.sent.entry(destination).or_default();
+        sent.retain(|(_, sent_at)| now_sec - sent_at < window_sec);
+        if sent.iter().any(|(sent_key, _)| *sent_key == key) {
+            *self.num_suppressed.entry(destination).or_default() += 1;
+            return true;
+        }
+        if sent.len() == MSG_DEDUP_CAPACITY {
+            sent.pop_front();
+        }
+        sent.push_back((key, now_sec));
+        false
+    }
+
+    pub(crate) fn num_suppressed(&self, destination: PlayerNum) -> u32 {
+        self.num_suppressed.get(&destination).copied().unwrap_or(0)
+    }
+
+    /// Forgets every sent message, e.g. when sim time restarts at a new round. Suppression counts are kept.
+    pub(crate) fn clear_sent(&mut self) {
+        self.sent.clear();
+    }
+
+    /// Forgets the messages sent to `destination`, e.g. when its seat moves to a new connection that has received none of them.
+    pub(crate) fn forget_sent_to(&mut self, destination: PlayerNum) {
+        self.sent.remove(&destination);
+    }
+}
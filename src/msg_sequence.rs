@@ -0,0 +1,167 @@
+//! Sequence numbers on messages, for transports (e.g. raw UDP) that may duplicate, reorder or lose datagrams.
+//!
+//! The sender wraps each message for a peer in a `SequencedMsg` carrying the next number in its sequence for that peer (see `MultiplayerInputManager::sequence_msg`), and the receiver unwraps it with `MultiplayerInputManager::decode_sequenced_msg_from_peer`, which drops duplicates instead of re-applying them, and counts duplicates, reordered messages and gaps for each peer (see `SequenceStats`).
+//!
+//! Sequence numbers are 32 bits and don't wrap: at 100 messages per second, a sequence lasts over a year.
+
+use std::collections::HashMap;
+
+use bincode::error::DecodeError;
+use serde::Deserialize;
+
+use crate::{input_messages::MsgPayload, input_trait::SimInput, util_types::PlayerNum};
+
+/// How many sequence numbers before the newest received are remembered per peer. Messages older than this can't be told apart from duplicates, so they are dropped.
+pub const SEQUENCE_WINDOW: u32 = 64;
+
+/// A message with its sender's sequence number for the recipient.
+///
+/// On the wire, the sequence number (4 bytes, little-endian) precedes the message's bytes (see `MsgPayload::to_bytes`).
+#[derive(Debug, Clone)]
+pub struct SequencedMsg<T: SimInput> {
+    pub seq: u32,
+    pub payload: MsgPayload<T>,
+}
+
+impl<T: SimInput> SequencedMsg<T> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.seq.to_le_bytes().to_vec();
+        bytes.extend(self.payload.to_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError>
+    where
+        T: for<'a> Deserialize<'a>,
+    {
+        let Some((seq, payload)) = bytes.split_first_chunk::<4>() else {
+            return Err(DecodeError::Other("missing sequence number"));
+        };
+        Ok(Self {
+            seq: u32::from_le_bytes(*seq),
+            payload: MsgPayload::from_bytes(payload)?,
+        })
+    }
+}
+
+/// What has been received in one peer's sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SequenceStats {
+    /// Distinct messages received
+    pub num_received: u32,
+    /// Messages dropped as copies of ones already received
+    pub num_duplicates: u32,
+    /// Messages received after a later one
+    pub num_reordered: u32,
+    /// Messages dropped for arriving more than `SEQUENCE_WINDOW` behind the newest one
+    pub num_too_old: u32,
+    /// Sequence numbers skipped so far, from the first received to the newest; late messages fill them in
+    pub num_missing: u32,
+}
+
+impl SequenceStats {
+    /// The fraction of the peer's messages that haven't arrived, counting from the first received; 0 before any have.
+    pub fn estimated_loss_rate(&self) -> f32 {
+        let num_sent = self.num_received + self.num_missing;
+        if num_sent == 0 {
+            0.0
+        } else {
+            self.num_missing as f32 / num_sent as f32
+        }
+    }
+}
+
+/// How a message's sequence number relates to those already received from its sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SeqArrival {
+    /// Not received before: the message should be applied
+    New,
+    /// A copy of a message already received
+    Duplicate,
+    /// More than `SEQUENCE_WINDOW` behind the newest; it may or may not have been received before
+    TooOld,
+}
+
+/// The sequence received from one peer.
+#[derive(Debug, Clone, Default)]
+struct PeerSequence {
+    /// The lowest and highest sequence numbers received, if any
+    range: Option<(u32, u32)>,
+    /// Bit `i` is set if the message `i` before the newest has been received
+    recent: u64,
+    stats: SequenceStats,
+}
+
+impl PeerSequence {
+    /// Records the arrival of `seq`.
+    fn accept(&mut self, seq: u32) -> SeqArrival {
+        let Some((lowest, newest)) = self.range else {
+            self.range = Some((seq, seq));
+            self.recent = 1;
+            self.stats.num_received += 1;
+            return SeqArrival::New;
+        };
+        if seq > newest {
+            let shift = seq - newest;
+            self.recent = if shift >= SEQUENCE_WINDOW {
+                0
+            } else {
+                self.recent << shift
+            };
+            self.recent |= 1;
+            self.range = Some((lowest, seq));
+        } else {
+            let age = newest - seq;
+            if age >= SEQUENCE_WINDOW {
+                self.stats.num_too_old += 1;
+                return SeqArrival::TooOld;
+            }
+            if self.recent & (1 << age) != 0 {
+                self.stats.num_duplicates += 1;
+                return SeqArrival::Duplicate;
+            }
+            self.recent |= 1 << age;
+            self.stats.num_reordered += 1;
+            self.range = Some((lowest.min(seq), newest));
+        }
+        self.stats.num_received += 1;
+        SeqArrival::New
+    }
+
+    fn stats(&self) -> SequenceStats {
+        let span = self.range.map_or(0, |(lowest, newest)| newest - lowest + 1);
+        SequenceStats {
+            num_missing: span.saturating_sub(self.stats.num_received),
+            ..self.stats
+        }
+    }
+}
+
+/// The sequences sent to and received from each peer.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MsgSequences {
+    next_seq: HashMap<PlayerNum, u32>,
+    received: HashMap<PlayerNum, PeerSequence>,
+}
+
+impl MsgSequences {
+    /// The next sequence number for messages to `destination`.
+    pub(crate) fn next_seq(&mut self, destination: PlayerNum) -> u32 {
+        let next = self.next_seq.entry(destination).or_default();
+        let seq = *next;
+        *next += 1;
+        seq
+    }
+
+    /// Records the arrival of `seq` from `sender`; only a `SeqArrival::New` message should be applied.
+    pub(crate) fn accept(&mut self, sender: PlayerNum, seq: u32) -> SeqArrival {
+        self.received.entry(sender).or_default().accept(seq)
+    }
+
+    pub(crate) fn stats(&self, sender: PlayerNum) -> SequenceStats {
+        self.received
+            .get(&sender)
+            .map(PeerSequence::stats)
+            .unwrap_or_default()
+    }
+}
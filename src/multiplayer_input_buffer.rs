@@ -1,25 +1,58 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ops::Range,
+};
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
     input_messages::{from_bincode_bytes, to_bincode_bytes},
     input_trait::SimInput,
+    tick_map::TickMap,
 };
 
 use super::{
-    input_buffer::{InputStatus, PlayerInputBuffer},
+    input_buffer::{FinalizedSliceOutcome, InputConfidence, InputStatus, PlayerInputBuffer},
     peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
-    util_types::{PlayerInputSlice, PlayerNum},
+    util_types::{PlayerInputSlice, PlayerInputSliceRef, PlayerNum},
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct MultiplayerInputBuffers<T>
 where
     T: SimInput,
 {
     max_inputs_to_predict: u32,
     num_players: u8,
+    /// Translates between input indices and absolute sim ticks.
+    ///
+    /// Buffers are always indexed from 0 (as are the slices and acks exchanged between peers), from the input at the start tick.
+    tick_map: TickMap,
+    /// The number of ticks each buffer was pre-allocated for, if any.
+    ///
+    /// This is a local memory setting, so it isn't serialized.
+    #[serde(skip)]
+    preallocated_ticks: Option<u32>,
+    /// Counts changes to the buffers, carried over into the next round's buffers (see `change_stamps`).
+    ///
+    /// This is local bookkeeping, so it isn't serialized.
+    #[serde(skip)]
+    num_changes: u64,
+    /// The earliest input index whose input or prediction has changed for any player, since `take_earliest_changed_index` was last called.
+    #[serde(skip)]
+    earliest_changed_index: Option<u32>,
+    /// Whether `changed_inputs` is kept; off by default, since it grows until taken (see `set_track_changed_inputs`)
+    #[serde(skip)]
+    track_changed_inputs: bool,
+    /// Every input index whose input or prediction has changed, with its player, since `take_changed_inputs` was last called.
+    #[serde(skip)]
+    changed_inputs: BTreeSet<(u32, PlayerNum)>,
+    /// For each player, the number of held, non-final inputs whose value has been changed (see `num_rewritten_inputs`)
+    #[serde(skip)]
+    num_rewritten_inputs: HashMap<PlayerNum, u64>,
+    /// Players removed from the session, with the number of inputs each ends with (see `remove_player`)
+    removed_players: BTreeMap<PlayerNum, u32>,
     pub buffers: Vec<PlayerInputBuffer<T>>,
 }
 
@@ -34,15 +67,263 @@ impl<T: SimInput> MultiplayerInputBuffers<T> {
         Self {
             max_inputs_to_predict,
             num_players,
+            tick_map: TickMap::default(),
+            preallocated_ticks: None,
+            num_changes: 0,
+            earliest_changed_index: None,
+            track_changed_inputs: false,
+            changed_inputs: BTreeSet::new(),
+            num_rewritten_inputs: HashMap::new(),
+            removed_players: BTreeMap::new(),
             buffers: (0..num_players)
                 .map(|_| PlayerInputBuffer::default())
                 .collect(),
         }
     }
 
+    /// Creates a new, empty set of buffers with the same configuration (including any pre-allocation) as this one.
+    pub fn new_empty_like(&self) -> Self {
+        let mut buffers = Self::new(self.num_players, self.max_inputs_to_predict);
+        buffers
+            .tick_map
+            .set_sim_ticks_per_input(self.sim_ticks_per_input());
+        if let Some(num_ticks) = self.preallocated_ticks {
+            buffers.preallocate_ticks(num_ticks);
+        }
+        buffers.track_changed_inputs = self.track_changed_inputs;
+        // removed players stay removed, from the start of the new buffers
+        buffers.removed_players = self.removed_players.keys().map(|&p| (p, 0)).collect();
+        // emptying the buffers is itself a change
+        buffers.num_changes = self.num_changes + 1;
+        buffers
+    }
+
+    /// A counter that increases whenever inputs are collected, received or finalized for any player, or players are added.
+    pub fn num_changes(&self) -> u64 {
+        self.num_changes
+    }
+
+    /// Takes the earliest input index whose input or prediction has changed for any player since the last call.
+    ///
+    /// An input changes when one is collected or received that differs from what was there or predicted for it. Predictions past the new last input are made from it, so they only change if it does.
+    pub fn take_earliest_changed_index(&mut self) -> Option<u32> {
+        self.earliest_changed_index.take()
+    }
+
+    /// Sets whether every changed input is kept for `take_changed_inputs`. The set grows with each change until taken, so it's only kept when asked for.
+    pub fn set_track_changed_inputs(&mut self, track: bool) {
+        self.track_changed_inputs = track;
+        if !track {
+            self.changed_inputs.clear();
+        }
+    }
+
+    pub fn tracks_changed_inputs(&self) -> bool {
+        self.track_changed_inputs
+    }
+
+    /// Takes every input index whose input or prediction has changed since the last call, with its player, ordered by index and then player (see `take_earliest_changed_index`). Always empty unless tracking is on (see `set_track_changed_inputs`).
+    pub fn take_changed_inputs(&mut self) -> BTreeSet<(u32, PlayerNum)> {
+        std::mem::take(&mut self.changed_inputs)
+    }
+
+    /// The number of this player's inputs that were already held, but not yet finalized, when a later update changed their value, e.g. provisional inputs corrected by the finalized ones. Counted since these buffers were created.
+    pub fn num_rewritten_inputs(&self, player_num: PlayerNum) -> u64 {
+        self.num_rewritten_inputs
+            .get(&player_num)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn mark_changed(&mut self, player_num: PlayerNum, changed: &[u32]) {
+        if let Some(&first) = changed.first() {
+            self.mark_changed_from(first);
+        }
+        if !self.track_changed_inputs {
+            return;
+        }
+        self.changed_inputs
+            .extend(changed.iter().map(|&index| (index, player_num)));
+    }
+
+    fn mark_changed_from(&mut self, index: u32) {
+        self.earliest_changed_index =
+            Some(self.earliest_changed_index.map_or(index, |i| i.min(index)));
+    }
+
+    /// Allocates every player's buffer to hold `num_ticks` inputs up front, so that a match of known length never reallocates.
+    pub fn preallocate_ticks(&mut self, num_ticks: u32) {
+        self.preallocated_ticks = Some(num_ticks);
+        for buf in self.buffers.iter_mut() {
+            buf.reserve_total(num_ticks);
+        }
+    }
+
+    /// Adds empty buffers for new players up to `num_players`, with the same pre-allocation as the others. Never removes players.
+    pub fn add_players_up_to(&mut self, num_players: u8) {
+        while self.buffers.len() < usize::from(num_players) {
+            let mut buf = PlayerInputBuffer::default();
+            if let Some(num_ticks) = self.preallocated_ticks {
+                buf.reserve_total(num_ticks);
+            }
+            self.buffers.push(buf);
+            self.num_changes += 1;
+        }
+        self.num_players = self.num_players.max(num_players);
+    }
+
+    /// Removes a player for good once they have `num_inputs` inputs: their inputs from there on read as finalized defaults, and once they have that many finalized, they no longer hold back the inputs finalized across peers. Their buffer is freed as its inputs are dropped (see `drop_inputs_before`).
+    pub fn remove_player(&mut self, player_num: PlayerNum, num_inputs: u32) {
+        self.buffer_mut_by_player_num(player_num)
+            .truncate_unfinalized_from(num_inputs);
+        self.removed_players.insert(player_num, num_inputs);
+        self.num_changes += 1;
+    }
+
+    /// Checks every player's buffer (see `PlayerInputBuffer::check_invariants`), that there is a buffer for every player, and that removed players have no unfinalized inputs past their end.
+    ///
+    /// For tests and debug assertions; a failure is a bug in this crate.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.buffers.len() != usize::from(self.num_players) {
+            return Err(format!(
+                "{} buffers for {} players",
+                self.buffers.len(),
+                self.num_players
+            ));
+        }
+        for (player, buf) in self.buffers.iter().enumerate() {
+            buf.check_invariants()
+                .map_err(|e| format!("player {player}: {e}"))?;
+        }
+        for (&player_num, &end) in &self.removed_players {
+            let Some(buf) = self.buffers.get(usize::from(player_num.0)) else {
+                return Err(format!("removed player {player_num} has no buffer"));
+            };
+            if buf.num_inputs_collected() > end.max(buf.finalized_inputs()) {
+                return Err(format!(
+                    "removed player {player_num} has unfinalized inputs past their end at {end}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_removed(&self, player_num: PlayerNum) -> bool {
+        self.removed_players.contains_key(&player_num)
+    }
+
+    /// The number of inputs a removed player ends with, or `None` if they haven't been removed.
+    pub fn removed_player_end(&self, player_num: PlayerNum) -> Option<u32> {
+        self.removed_players.get(&player_num).copied()
+    }
+
+    // whether this is a removed player's input past their end, which reads as
+    // a finalized default
+    fn is_past_end(&self, player_num: PlayerNum, index: u32) -> bool {
+        self.removed_players
+            .get(&player_num)
+            .is_some_and(|&end| index >= end)
+    }
+
+    fn input_at(&self, player_num: PlayerNum, buf: &PlayerInputBuffer<T>, index: u32) -> T {
+        if self.is_past_end(player_num, index) {
+            T::default()
+        } else {
+            buf.get_input_or_prediction(index, self.max_inputs_to_predict)
+        }
+    }
+
+    fn status_at(
+        &self,
+        player_num: PlayerNum,
+        buf: &PlayerInputBuffer<T>,
+        index: u32,
+    ) -> InputStatus {
+        if self.is_past_end(player_num, index) {
+            InputStatus::Finalized {
+                num_finalized: buf.finalized_inputs(),
+            }
+        } else {
+            buf.get_input_status(index, self.max_inputs_to_predict)
+        }
+    }
+
+    /// The number of ticks every player's buffer can hold without reallocating.
+    pub fn capacity_ticks(&self) -> u32 {
+        self.buffers
+            .iter()
+            .map(|buf| buf.capacity())
+            .min()
+            .unwrap_or(0)
+    }
+
+    pub fn num_players(&self) -> u8 {
+        self.num_players
+    }
+
+    /// The translation between these buffers' input indices and absolute sim ticks.
+    pub fn tick_map(&self) -> TickMap {
+        self.tick_map
+    }
+
+    pub fn start_tick(&self) -> u32 {
+        self.tick_map.start_tick()
+    }
+
+    pub fn set_start_tick(&mut self, start_tick: u32) {
+        self.tick_map.set_start_tick(start_tick);
+    }
+
+    pub fn sim_ticks_per_input(&self) -> u32 {
+        self.tick_map.sim_ticks_per_input()
+    }
+
+    /// Sets the number of sim ticks each input covers (at least 1).
+    pub fn set_sim_ticks_per_input(&mut self, sim_ticks_per_input: u32) {
+        self.tick_map.set_sim_ticks_per_input(sim_ticks_per_input);
+    }
+
+    /// The index of the input covering this sim tick; `None` for ticks before the start tick.
+    pub fn input_index(&self, tick: u32) -> Option<u32> {
+        self.tick_map.to_relative(tick)
+    }
+
+    /// The first sim tick covered by the input at this index, which is also the sim tick reached once `index` inputs have been simulated. Saturates at `u32::MAX`.
+    pub fn tick_of_input(&self, index: u32) -> u32 {
+        self.tick_map.to_absolute(index).unwrap_or(u32::MAX)
+    }
+
+    /// The number of inputs covering the sim ticks from the start tick up to (but not including) `tick`, counting an input that is only partly before `tick`.
+    pub fn num_inputs_before_tick(&self, tick: u32) -> u32 {
+        self.tick_map.num_relative_before(tick)
+    }
+
+    /// True if no inputs have been collected for any player.
+    pub fn is_empty(&self) -> bool {
+        self.buffers
+            .iter()
+            .all(|buf| buf.num_inputs_collected() == 0)
+    }
+
+    /// Every player's finalized inputs, from the first input retained for all of them (see `first_retained_input_across_peers`).
+    pub fn finalized_input_bytes_by_player(&self) -> Vec<Vec<T::Bytes>> {
+        let first = self.first_retained_input_across_peers();
+        let num_ticks = self.get_num_finalized_inputs_across_peers();
+        self.get_peer_player_nums()
+            .into_iter()
+            .map(|player_num| {
+                (first..num_ticks)
+                    .map(|tick| self.get_input_or_prediction(player_num, tick).to_bytes())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Like `finalized_input_bytes_by_player`, indexed by tick.
     pub fn final_inputs_by_tick(&self) -> Vec<(u32, Vec<(u32, T)>)> {
         let mut final_inputs = vec![];
-        for tick in 0..self.get_num_finalized_inputs_across_peers() {
+        let first = self.first_retained_input_across_peers();
+        for tick in first..self.get_num_finalized_inputs_across_peers() {
             let mut inputs = vec![];
             for id in self.get_peer_player_nums().iter() {
                 let input = self.get_input_or_prediction(*id, tick);
@@ -55,18 +336,23 @@ impl<T: SimInput> MultiplayerInputBuffers<T> {
     }
 
     pub fn get_peer_player_nums(&self) -> Vec<PlayerNum> {
-        (0..self.num_players).map(PlayerNum).collect()
+        PlayerNum::iter(self.num_players).collect()
     }
 
+    #[cfg(test)]
     pub fn get_inputs_map_for_tick(&self, tick: u32) -> HashMap<u8, T> {
-        self.buffers
-            .iter()
-            .enumerate()
-            .map(|(player_num, buf)| {
-                let input = buf.get_input_or_prediction(tick, self.max_inputs_to_predict);
-                (player_num as u8, input)
-            })
-            .collect()
+        let mut inputs = HashMap::with_capacity(self.buffers.len());
+        self.get_inputs_map_for_tick_into(tick, &mut inputs);
+        inputs
+    }
+
+    /// Like `get_inputs_map_for_tick`, but fills `out` (cleared first) instead of allocating a new map.
+    pub fn get_inputs_map_for_tick_into(&self, tick: u32, out: &mut HashMap<u8, T>) {
+        out.clear();
+        out.extend(self.buffers.iter().enumerate().map(|(player_num, buf)| {
+            let input = self.input_at(PlayerNum(player_num as u8), buf, tick);
+            (player_num as u8, input)
+        }));
     }
 
     fn buffer_by_player_num(&self, player_num: PlayerNum) -> &PlayerInputBuffer<T> {
@@ -81,14 +367,70 @@ impl<T: SimInput> MultiplayerInputBuffers<T> {
             .unwrap_or_else(|| panic!("player_num out of bounds: {:?}", player_num))
     }
 
+    // applies `f` to the player's buffer, counting a change if it changed,
+    // collected or finalized any inputs, and marking the inputs it changed.
+    // `f` may only write the inputs in `touched`, so only those are compared,
+    // keeping the cost of an update proportional to the inputs it writes
+    fn update_player_buffer<R>(
+        &mut self,
+        player_num: PlayerNum,
+        touched: Range<u32>,
+        f: impl FnOnce(&mut PlayerInputBuffer<T>) -> R,
+    ) -> R {
+        let max_predict = self.max_inputs_to_predict;
+        let buf = self.buffer_mut_by_player_num(player_num);
+        let num_finalized = buf.finalized_inputs();
+        let num_inputs = buf.num_inputs_collected();
+        // finalized inputs never change, so only the rest need comparing
+        let start = touched.start.max(num_finalized);
+        let non_final_before: Vec<T::Bytes> = (start..touched.end.min(num_inputs))
+            .map(|i| buf.get_input_or_prediction(i, max_predict).to_bytes())
+            .collect();
+        let prediction_before = buf
+            .get_input_or_prediction(num_inputs, max_predict)
+            .to_bytes();
+        let default = T::default().to_bytes();
+        let before = |i: u32| {
+            if i < num_inputs {
+                non_final_before[(i - start) as usize]
+            } else if i < num_inputs + max_predict {
+                prediction_before
+            } else {
+                default
+            }
+        };
+
+        let result = f(buf);
+
+        let changed: Vec<u32> = (start
+            ..touched.end.min(num_inputs.max(buf.num_inputs_collected())))
+            .filter(|&i| buf.get_input_or_prediction(i, max_predict).to_bytes() != before(i))
+            .collect();
+        if !changed.is_empty()
+            || (buf.num_inputs_collected(), buf.finalized_inputs()) != (num_inputs, num_finalized)
+        {
+            self.num_changes += 1;
+        }
+        let num_rewritten = changed.iter().filter(|&&i| i < num_inputs).count() as u64;
+        if num_rewritten > 0 {
+            *self.num_rewritten_inputs.entry(player_num).or_default() += num_rewritten;
+        }
+        self.mark_changed(player_num, &changed);
+        result
+    }
+
     pub fn append_input(&mut self, player_num: PlayerNum, input: T) {
-        self.buffer_mut_by_player_num(player_num)
-            .append_input(input.to_bytes());
+        let index = self.get_num_inputs(player_num);
+        self.update_player_buffer(player_num, index..index + 1, |buf| {
+            buf.append_input(input.to_bytes())
+        });
     }
 
     pub fn append_input_finalized(&mut self, player_num: PlayerNum, input: T) {
-        self.buffer_mut_by_player_num(player_num)
-            .host_append_finalized(input.to_bytes());
+        let index = self.get_num_finalized_inputs(player_num);
+        self.update_player_buffer(player_num, index..index + 1, |buf| {
+            buf.host_append_finalized(input.to_bytes())
+        });
     }
 
     pub fn get_slice_to_end_for_peer(
@@ -99,9 +441,60 @@ impl<T: SimInput> MultiplayerInputBuffers<T> {
         self.buffer_by_player_num(player_num).slice_from(start)
     }
 
-    pub fn get_input_or_prediction(&self, player_num: PlayerNum, tick: u32) -> T {
+    /// Like `get_slice_to_end_for_peer`, but borrows the inputs rather than copying them.
+    pub fn borrow_slice_to_end_for_peer(
+        &self,
+        player_num: PlayerNum,
+        start: u32,
+    ) -> PlayerInputSliceRef<'_, T> {
+        self.buffer_by_player_num(player_num)
+            .borrow_slice_from(start)
+    }
+
+    /// Gets the finalized inputs for this peer from `start` onward, leaving out any non-final inputs.
+    pub fn get_finalized_slice_for_peer(
+        &self,
+        player_num: PlayerNum,
+        start: u32,
+    ) -> PlayerInputSlice<T> {
         self.buffer_by_player_num(player_num)
-            .get_input_or_prediction(tick, self.max_inputs_to_predict)
+            .finalized_slice_from(start)
+    }
+
+    /// Like `get_finalized_slice_for_peer`, but borrows the inputs rather than copying them.
+    pub fn borrow_finalized_slice_for_peer(
+        &self,
+        player_num: PlayerNum,
+        start: u32,
+    ) -> PlayerInputSliceRef<'_, T> {
+        self.buffer_by_player_num(player_num)
+            .borrow_finalized_slice_from(start)
+    }
+
+    /// Finalizes the oldest non-final input already collected for this player.
+    pub fn finalize_next_collected_input(&mut self, player_num: PlayerNum) {
+        let index = self.get_num_finalized_inputs(player_num);
+        self.update_player_buffer(player_num, index..index + 1, |buf| {
+            buf.finalize_next_collected()
+        });
+    }
+
+    pub fn get_input_or_prediction(&self, player_num: PlayerNum, tick: u32) -> T {
+        self.input_at(player_num, self.buffer_by_player_num(player_num), tick)
+    }
+
+    /// Like `get_input_or_prediction`, along with how the input was arrived at. A removed player's inputs past their end are finalized defaults.
+    pub fn get_input_with_confidence(
+        &self,
+        player_num: PlayerNum,
+        tick: u32,
+    ) -> (T, InputConfidence) {
+        if self.is_past_end(player_num, tick) {
+            (T::default(), InputConfidence::Finalized)
+        } else {
+            self.buffer_by_player_num(player_num)
+                .get_input_with_confidence(tick, self.max_inputs_to_predict)
+        }
     }
 
     /// ges the number of input for this peer, whether finalized or not
@@ -114,6 +507,32 @@ impl<T: SimInput> MultiplayerInputBuffers<T> {
         self.buffer_by_player_num(player_num).finalized_inputs()
     }
 
+    /// The index of this player's oldest retained input (see `drop_inputs_before`).
+    pub fn first_retained_input(&self, player_num: PlayerNum) -> u32 {
+        self.buffer_by_player_num(player_num).first_retained_input()
+    }
+
+    /// The index of the oldest input retained for every player, except removed ones (whose dropped inputs read as defaults).
+    pub fn first_retained_input_across_peers(&self) -> u32 {
+        self.buffers
+            .iter()
+            .enumerate()
+            .filter(|(player_num, _)| !self.is_removed(PlayerNum(*player_num as u8)))
+            .map(|(_, buf)| buf.first_retained_input())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Drops this player's finalized inputs before `index`, keeping every other input at the same index (see `PlayerInputBuffer::drop_inputs_before`).
+    pub fn drop_inputs_before(&mut self, player_num: PlayerNum, index: u32) {
+        let is_removed = self.is_removed(player_num);
+        let buf = self.buffer_mut_by_player_num(player_num);
+        buf.drop_inputs_before(index);
+        if is_removed {
+            buf.shrink_to_fit();
+        }
+    }
+
     // pub fn get_num_finalized_inputs_per_peer(&self) -> HashMap<PlayerNum, u32> {
     //     self.buffers
     //         .iter()
@@ -123,8 +542,10 @@ impl<T: SimInput> MultiplayerInputBuffers<T> {
     // }
 
     pub fn receive_peer_input_slice(&mut self, slice: PlayerInputSlice<T>, player_num: PlayerNum) {
-        self.buffer_mut_by_player_num(player_num)
-            .receive_peer_input_slice(slice);
+        let touched = slice.start..slice.start.saturating_add(slice.len());
+        self.update_player_buffer(player_num, touched, |buf| {
+            buf.receive_peer_input_slice(slice)
+        });
     }
 
     /// The host uses this method to directly append finalized default inputs such that the player has the desired number of final inputs in their buffer.
@@ -135,8 +556,10 @@ impl<T: SimInput> MultiplayerInputBuffers<T> {
         player_num: PlayerNum,
         target_num: u32,
     ) {
-        self.buffer_mut_by_player_num(player_num)
-            .host_append_final_default_inputs_to_target(target_num);
+        let touched = self.get_num_finalized_inputs(player_num)..target_num.saturating_add(1);
+        self.update_player_buffer(player_num, touched, |buf| {
+            buf.host_append_final_default_inputs_to_target(target_num)
+        });
     }
 
     /// This method is used by hosts *whenever* they receive inputs from a peer; the act of the host RXing inputs *is* their finalization.
@@ -146,32 +569,41 @@ impl<T: SimInput> MultiplayerInputBuffers<T> {
         &mut self,
         slice: PlayerInputSlice<T>,
         player_num: PlayerNum,
-    ) {
-        self.buffer_mut_by_player_num(player_num)
-            .receive_finalized_input_slice(slice);
+    ) -> FinalizedSliceOutcome {
+        let touched = slice.start..slice.start.saturating_add(slice.len());
+        self.update_player_buffer(player_num, touched, |buf| {
+            buf.receive_finalized_input_slice(slice)
+        })
     }
 
     /// This method builds the PeerwiseFinalizedInput mapping
     /// based on this buffer's state.
+    #[cfg(test)]
     pub fn get_peerwise_finalized_inputs(&self) -> PeerwiseFinalizedInputsSeen {
-        let ack = PeerwiseFinalizedInputsSeen::new_from_observed(
-            self.num_players,
-            &self
-                .buffers
-                .iter()
-                .map(|buf| buf.finalized_inputs())
-                .collect::<Vec<_>>(),
-        );
-        ack
+        let mut seen = PeerwiseFinalizedInputsSeen::default();
+        self.get_peerwise_finalized_inputs_into(&mut seen);
+        seen
+    }
+
+    /// Like `get_peerwise_finalized_inputs`, but overwrites `out` instead of allocating a new mapping.
+    pub fn get_peerwise_finalized_inputs_into(&self, out: &mut PeerwiseFinalizedInputsSeen) {
+        out.set_observed(self.buffers.iter().map(|buf| buf.finalized_inputs()));
     }
 
     /// Return the number of inputs that have been finalized for all players, i.e., the `min_i {f_i}` where `f_i` is the number of finalized inputs for player i.
     ///
     ///  Reminder of indexing conventions: If we have seen 0 finalized inputs for all players, we can only snapshot the initial state at tick 0; if we have seen 1 finalized input for all players, we can snapshot up to tick 1; generally, if we have seen T finalized inputs for all players, we can snapshot up to tick T; and this means that the index in the input buffer is T-1.
+    ///
+    /// Removed players (see `remove_player`) are left out once they have all their inputs finalized.
     pub fn get_num_finalized_inputs_across_peers(&self) -> u32 {
         self.buffers
             .iter()
-            .map(|buf| buf.finalized_inputs())
+            .enumerate()
+            .filter(|(player_num, buf)| {
+                self.removed_player_end(PlayerNum(*player_num as u8))
+                    .is_none_or(|end| buf.finalized_inputs() < end)
+            })
+            .map(|(_, buf)| buf.finalized_inputs())
             .min()
             .unwrap_or(0)
     }
@@ -183,8 +615,10 @@ impl<T: SimInput> MultiplayerInputBuffers<T> {
             .iter()
             .enumerate()
             .map(|(player_num, buf)| {
-                let input = buf.get_input_or_prediction(tick, self.max_inputs_to_predict);
-                (PlayerNum(player_num as u8), input, buf.is_finalized(tick))
+                let player_num = PlayerNum(player_num as u8);
+                let input = self.input_at(player_num, buf, tick);
+                let is_finalized = buf.is_finalized(tick) || self.is_past_end(player_num, tick);
+                (player_num, input, is_finalized)
             })
             .collect();
         inputs.sort_by_key(|(i, _, _)| *i);
@@ -192,12 +626,25 @@ impl<T: SimInput> MultiplayerInputBuffers<T> {
     }
 
     /// For each player, returns the InputStatus for the given input_num
+    #[cfg(test)]
     pub fn get_input_statuses(&self, input_num: u32) -> Vec<(PlayerNum, InputStatus)> {
-        self.buffers
-            .iter()
-            .enumerate()
-            .map(|(player_num, buf)| ((player_num as u8).into(), buf.get_input_status(input_num)))
-            .collect()
+        let mut statuses = Vec::with_capacity(self.buffers.len());
+        self.get_input_statuses_into(input_num, &mut statuses);
+        statuses
+    }
+
+    /// Like `get_input_statuses`, but fills `out` (cleared first) instead of allocating a new vec.
+    pub fn get_input_statuses_into(&self, input_num: u32, out: &mut Vec<(PlayerNum, InputStatus)>) {
+        out.clear();
+        out.extend(self.buffers.iter().enumerate().map(|(player_num, buf)| {
+            let player_num = PlayerNum(player_num as u8);
+            (player_num, self.status_at(player_num, buf, input_num))
+        }));
+    }
+
+    /// Returns the InputStatus of the given player's input_num
+    pub fn get_input_status(&self, player_num: PlayerNum, input_num: u32) -> InputStatus {
+        self.status_at(player_num, self.buffer_by_player_num(player_num), input_num)
     }
 
     /// Serializes the `PlayerInputBuffer<T>` for the given player number that is held in this
@@ -220,23 +667,51 @@ impl<T: SimInput> MultiplayerInputBuffers<T> {
     pub fn deserialize_player_buffer(&mut self, player_num: PlayerNum, data: &[u8]) {
         let buf = from_bincode_bytes::<PlayerInputBuffer<T>>(data).unwrap();
         let num: usize = player_num.into();
+        let num_inputs = buf.num_inputs_collected();
         self.buffers[num] = buf;
+        self.num_changes += 1;
+        self.mark_changed_from(0);
+        let replaced: Vec<u32> = (0..num_inputs).collect();
+        self.mark_changed(player_num, &replaced);
     }
 }
 
 // Test helper functions
 impl<T: SimInput> MultiplayerInputBuffers<T> {
+    /// Appends `num_inputs` inputs for this player, with the input at each index given by `f`.
+    #[cfg(test)]
+    pub(crate) fn append_inputs_from_fn(
+        &mut self,
+        player_num: PlayerNum,
+        num_inputs: u32,
+        f: impl Fn(u32) -> T,
+    ) {
+        let start = self.get_num_inputs(player_num);
+        for index in start..start + num_inputs {
+            self.append_input(player_num, f(index));
+        }
+    }
+
+    /// Like `append_inputs_from_fn`, but the inputs are appended finalized, as the host does, from the player's first non-final index.
+    #[cfg(test)]
+    pub(crate) fn append_finalized_inputs_from_fn(
+        &mut self,
+        player_num: PlayerNum,
+        num_inputs: u32,
+        f: impl Fn(u32) -> T,
+    ) {
+        let start = self.get_num_finalized_inputs(player_num);
+        for index in start..start + num_inputs {
+            self.append_input_finalized(player_num, f(index));
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn test_helper_buffer_len_per_player(&self) -> HashMap<PlayerNum, u32> {
         self.buffers
             .iter()
             .enumerate()
-            .map(|(player_num, buf)| {
-                (
-                    player_num.try_into().unwrap(),
-                    buf.num_inputs_collected() as u32,
-                )
-            })
+            .map(|(player_num, buf)| (player_num.try_into().unwrap(), buf.num_inputs_collected()))
             .collect()
     }
 }
@@ -1,18 +1,68 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use crate::{input_buffer::InputStatus, input_trait::SimInput};
+use serde::Deserialize;
 
-use super::{multiplayer_input_buffer::MultiplayerInputBuffers, util_types::PlayerNum};
+use crate::{
+    bandwidth_budget::BandwidthBudget,
+    buffer_diff::{BufferDiff, diff_buffers},
+    capabilities::Capabilities,
+    change_stamps::{ChangeCounters, ChangeStamps},
+    decode_stats::{DecodeStats, MalformedMsg},
+    determinism_probe::{
+        DeterminismCheck, DeterminismProbe, DeterminismReport, DeterminismSample, DivergenceKind,
+        tick_input_digest,
+    },
+    event_channel::{EventChannel, TickEvent},
+    events::{EventQueue, InputMgrEvent},
+    finalization_watch::{FinalizationHandle, FinalizationWatchers},
+    finalized_input_channel::{FinalizedInputRx, FinalizedInputTxs},
+    host_recovery::HostRecovery,
+    input_buffer::{InputConfidence, InputStatus},
+    input_hash_chain::{ChainCheck, InputChainHead, InputHashChains},
+    input_messages::MsgPayload,
+    input_schedule::{InputSchedule, ScheduleInputError},
+    input_schema::InputSchemaMismatch,
+    input_trait::SimInput,
+    latency_stats::LatencySummary,
+    msg_sequence::{MsgSequences, SeqArrival, SequenceStats, SequencedMsg},
+    net_stats::NetStats,
+    player_metadata::{MAX_PLAYER_METADATA_BYTES, PlayerMetadataError},
+    reliable_delivery::ReliableDelivery,
+    replay::Replay,
+    rollback_depth::RollbackDepthTracker,
+    rtt::RttConfig,
+    rx_outcome::{RxOutcome, RxRejection},
+    seed_schedule::SeedSchedule,
+    session::OutgoingMsgs,
+    session_limit::{MAX_SESSION_TICKS, SessionLimit},
+    tick_consumption::{TickConsumption, TickConsumptionError},
+    tick_map::TickMap,
+    unknown_player_slices::UnknownPlayerSlices,
+};
+
+#[cfg(feature = "profiling")]
+use crate::profiling::{CallProfiler, FrameProfile, ProfileProbe};
+
+use super::{
+    multiplayer_input_buffer::MultiplayerInputBuffers,
+    multiplayer_input_manager_host::HOST_PLAYER_NUM,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+/// The default max input lead (see `MultiplayerInputManager::with_max_input_lead`): 10 seconds of inputs at 60hz.
+pub const DEFAULT_MAX_INPUT_LEAD: u32 = 600;
 
 /// A node that manages input buffers.
 /// This is also the source of truth regarding timing for the client.
 ///
 /// Timing works as follows:
 /// - we start with finalized world state S_0
+///
 /// Then by induction:
 /// - at time T, we have world state S_T
 /// - we collect inputs I_T for time T
 /// - we can compute S_{T+1} from S_T and I_T
+///
 /// Therefore, if S_T and all the inputs in I_T are finalized,
 /// we can compute S_{T+1} and finalize + snapshot it.
 ///     
@@ -38,6 +88,85 @@ where
     pub(super) ticks_per_sec: u32,
     /// specialized data for the a given role (either host or guest)
     pub(super) inner: R,
+    /// The index of the current round; 0 for the first round of the session.
+    pub(super) round: u32,
+    /// The buffers of each completed round, indexed by round.
+    pub(super) archived_rounds: Vec<MultiplayerInputBuffers<T>>,
+    /// Statistics about messages passed to `decode_msg_from_peer`
+    pub(super) decode_stats: DecodeStats,
+    /// The sequence numbers sent to and received from each peer (see `sequence_msg`)
+    pub(super) msg_sequences: MsgSequences,
+    /// Critical messages awaiting acks, and acks owed to peers (see `with_reliable_delivery`)
+    pub(super) reliable_delivery: ReliableDelivery<T>,
+    /// Messages and bytes sent to and received from each peer (see `get_net_stats`)
+    pub(super) net_stats: NetStats,
+    /// Events queued since the last call to `drain_events`, up to a cap (see `with_max_queued_events`)
+    pub(super) events: EventQueue,
+    /// Players whose inputs are ignored, keyed to the index of the first ignored input (see `mute_player`).
+    pub(super) muted_players: HashMap<PlayerNum, u32>,
+    /// The connection controlling each seat that has been moved off its own player num (see `transfer_seat`).
+    pub(super) seat_connections: HashMap<PlayerNum, PlayerNum>,
+    /// Each player's low-rate, tick-stamped events (see `event_channel`)
+    pub(super) event_channel: EventChannel,
+    /// The host's authoritative annotations, kept as a single-player event channel (see `add_annotation`)
+    pub(super) annotations: EventChannel,
+    /// The lobby's RNG seed and the host's changes to it (see `seed_for_tick`)
+    pub(super) seeds: SeedSchedule,
+    /// The ticks the sim has marked consumed this round (see `mark_tick_consumed`)
+    pub(super) tick_consumption: TickConsumption,
+    /// Own inputs scheduled for future ticks (see `schedule_own_input`)
+    pub(super) scheduled_inputs: InputSchedule<T>,
+    /// CONFIG SETTING
+    /// The optional protocol capabilities this node has enabled (see `with_capabilities`)
+    pub(super) capabilities: Capabilities,
+    /// The capabilities negotiated with each peer; peers without an entry use the baseline protocol (see `capabilities`)
+    pub(super) negotiated_capabilities: HashMap<PlayerNum, Capabilities>,
+    /// The display metadata registered for each seat (see `player_metadata`)
+    pub(super) player_metadata: BTreeMap<PlayerNum, Vec<u8>>,
+    /// GUEST ONLY: finalized slices for players beyond `num_players`, held until they are registered (see `unknown_player_slices`)
+    pub(super) unknown_player_slices: UnknownPlayerSlices<T>,
+    /// Recent rollback depths, and the configured cap (see `max_rollback_depth`)
+    pub(super) rollback_depth: RollbackDepthTracker,
+    /// Handles from `notify_when_finalized` that haven't resolved yet
+    pub(super) finalization_watchers: FinalizationWatchers,
+    /// CONFIG SETTING
+    /// The depth of the channels opened with `finalized_input_rx` (see `with_finalized_input_rx_depth`)
+    pub(super) finalized_input_rx_depth: u32,
+    /// The sending ends of the channels opened with `finalized_input_rx`
+    pub(super) finalized_input_txs: FinalizedInputTxs<T>,
+    /// For each player, the number of inputs changed by `SimInput::sanitize`
+    pub(super) num_sanitized: HashMap<PlayerNum, u32>,
+    /// CONFIG SETTING
+    /// How RTT estimates are bootstrapped and smoothed
+    pub(super) rtt_config: RttConfig,
+    /// For each player, the number of finalized slices ignored because they would have left a gap (see `num_ignored_slices`)
+    pub(super) num_ignored_slices: HashMap<PlayerNum, u32>,
+    /// CONFIG SETTING
+    /// How far past the inputs finalized for every player a received peer slice may reach (see `with_max_input_lead`)
+    pub(super) max_input_lead: u32,
+    /// For each player, the number of received inputs dropped for reaching past the max input lead (see `num_inputs_beyond_lead`)
+    pub(super) num_inputs_beyond_lead: HashMap<PlayerNum, u32>,
+    /// CONFIG SETTING
+    /// How many of each player's most recent inputs are kept once older ones can be dropped; `None` keeps every input (see `with_max_retained_inputs`)
+    pub(super) max_retained_inputs: Option<u32>,
+    /// CONFIG SETTING
+    /// The longest input slice message to send before splitting it; `None` never splits (see `with_max_payload_bytes`)
+    pub(super) max_payload_bytes: Option<usize>,
+    /// Chained hashes of each player's finalized inputs; `None` unless enabled with `with_input_hash_chains`
+    pub(super) input_chains: Option<InputHashChains>,
+    /// The sim tick at which the session ends (see `with_max_session_ticks`)
+    pub(super) session_limit: SessionLimit,
+    /// Recent determinism samples for this node and its peers (see `record_state_digest`)
+    pub(super) determinism_probe: DeterminismProbe,
+    /// The outgoing bandwidth budget (see `set_outgoing_budget_bytes_per_sec`)
+    pub(super) bandwidth_budget: BandwidthBudget,
+    /// HOST ONLY: the guests' responses collected while recovering from a restart (see `begin_recovery`); `None` when not recovering
+    pub(super) host_recovery: Option<HostRecovery<T>>,
+    /// Change counters for the state the buffers don't count themselves (see `change_stamps`)
+    pub(super) change_counters: ChangeCounters,
+    /// Measures the calls `Session` makes on this manager; `None` unless attached with `with_profiler`
+    #[cfg(feature = "profiling")]
+    pub(super) profiler: Option<CallProfiler<Box<dyn ProfileProbe>>>,
 }
 
 impl<T: SimInput, Buf> MultiplayerInputManager<T, Buf> {
@@ -45,8 +174,66 @@ impl<T: SimInput, Buf> MultiplayerInputManager<T, Buf> {
         self.own_player_num.into()
     }
 
+    /// Each finalized input, with the first sim tick it covers.
     pub fn get_final_inputs_by_tick(&self) -> Vec<(u32, Vec<(u32, T)>)> {
-        self.buffers.final_inputs_by_tick()
+        self.buffers
+            .final_inputs_by_tick()
+            .into_iter()
+            .map(|(index, inputs)| (self.buffers.tick_of_input(index), inputs))
+            .collect()
+    }
+
+    /// Pre-allocates every player's buffer to hold `num_ticks` inputs, for matches of known length (e.g. 3 minutes at 60hz is 10,800 ticks).
+    ///
+    /// This avoids reallocation hitches mid-match and makes memory usage predictable: roughly `num_players * num_ticks * size_of::<T::Bytes>()` bytes per round. Buffers still grow if a match runs long. The allocation is repeated for each new round.
+    pub fn with_preallocated_ticks(mut self, num_ticks: u32) -> Self {
+        self.buffers.preallocate_ticks(num_ticks);
+        self
+    }
+
+    /// The number of ticks every player's buffer can hold before it needs to reallocate.
+    pub fn capacity_ticks(&self) -> u32 {
+        self.buffers.capacity_ticks()
+    }
+
+    /// The sim tick at which this session's input timeline starts; 0 unless the session was started (or resumed) at a later tick (see `PreSimSync::start_tick`).
+    ///
+    /// Tick arguments and return values of this manager are sim ticks, while input counts (e.g. `get_own_num_inputs`) and the slices and acks exchanged between peers count from the start tick.
+    pub fn start_tick(&self) -> u32 {
+        self.buffers.start_tick()
+    }
+
+    /// The number of sim ticks each input covers; 1 unless set with `with_sim_ticks_per_input` (adopted by guests from the `PreSimSync`).
+    ///
+    /// Tick arguments are mapped to the input covering them, so every sim tick an input covers sees the same input, and annotations, seed changes and mutes take effect from the first sim tick of their input. Input counts, the host's and guests' pacing, and rollback depths count inputs rather than sim ticks.
+    pub fn sim_ticks_per_input(&self) -> u32 {
+        self.buffers.sim_ticks_per_input()
+    }
+
+    /// The translation between absolute sim ticks (as used by this manager's API) and the input indices used by the buffers, slices and acks.
+    pub fn tick_map(&self) -> TickMap {
+        self.buffers.tick_map()
+    }
+
+    /// The rate at which inputs are collected: `ticks_per_sec` divided by `sim_ticks_per_input`.
+    pub(super) fn inputs_per_sec(&self) -> f32 {
+        self.ticks_per_sec as f32 / self.sim_ticks_per_input() as f32
+    }
+
+    /// Converts a sim tick into the index of the input covering it; `None` for ticks before the start tick.
+    pub(super) fn input_index(&self, tick: u32) -> Option<u32> {
+        self.buffers.input_index(tick)
+    }
+
+    /// Like `input_index`, but only for the first sim tick an input covers, so that per-input data (events and annotations) is reported once.
+    pub(super) fn input_index_starting_at(&self, tick: u32) -> Option<u32> {
+        self.input_index(tick)
+            .filter(|index| self.buffers.tick_of_input(*index) == tick)
+    }
+
+    /// The number of players, including any added mid-session (see `add_player`).
+    pub fn get_num_players(&self) -> u8 {
+        self.buffers.num_players()
     }
 
     pub fn get_peer_player_nums(&self) -> Vec<u8> {
@@ -61,18 +248,62 @@ impl<T: SimInput, Buf> MultiplayerInputManager<T, Buf> {
         self.buffers.get_num_finalized_inputs_across_peers()
     }
 
-    /// For each player, returns the inputs for the given tick and whether the inputs have been finalized.
+    /// For each player, returns the inputs for the given absolute sim tick and whether the inputs have been finalized.
+    ///
+    /// Ticks before the start tick are treated as finalized default inputs.
     pub fn get_inputs_and_finalization_status(&self, tick: u32) -> Vec<(PlayerNum, T, bool)> {
-        self.buffers.get_inputs_and_finalization_status(tick)
+        match self.input_index(tick) {
+            Some(index) => self.buffers.get_inputs_and_finalization_status(index),
+            None => self
+                .buffers
+                .get_peer_player_nums()
+                .into_iter()
+                .map(|player_num| (player_num, T::default(), true))
+                .collect(),
+        }
     }
 
+    /// Each player's input (or prediction) for the given absolute sim tick, keyed by player num; default inputs for ticks before the start tick.
     pub fn get_inputs_map_for_tick(&self, tick: u32) -> HashMap<u8, T> {
-        self.buffers.get_inputs_map_for_tick(tick)
+        let mut inputs = HashMap::with_capacity(self.buffers.num_players() as usize);
+        self.get_inputs_map_for_tick_into(tick, &mut inputs);
+        inputs
     }
 
+    /// Like `get_inputs_map_for_tick`, but fills `out` (cleared first) instead of allocating a new map, for per-frame use: once `out` has held every player, this doesn't allocate.
+    pub fn get_inputs_map_for_tick_into(&self, tick: u32, out: &mut HashMap<u8, T>) {
+        match self.input_index(tick) {
+            Some(index) => self.buffers.get_inputs_map_for_tick_into(index, out),
+            None => {
+                out.clear();
+                out.extend(
+                    PlayerNum::iter(self.buffers.num_players())
+                        .map(|player_num| (player_num.0, T::default())),
+                );
+            }
+        }
+    }
+
+    /// A player's input (or prediction) for the given absolute sim tick; the default input for ticks before the start tick.
     pub fn get_peer_input_for_tick(&self, player_num: PlayerNum, tick: u32) -> T {
-        self.buffers
-            .get_input_or_prediction(player_num.into(), tick)
+        match self.input_index(tick) {
+            Some(index) => self.buffers.get_input_or_prediction(player_num, index),
+            None => T::default(),
+        }
+    }
+
+    /// Like `get_peer_input_for_tick`, along with how far the input can be trusted: finalized, received but not finalized, a prediction carrying the player's last input forward, or the default input. Ticks before the start tick are finalized defaults.
+    ///
+    /// E.g. a game can draw remote players as ghosts while their inputs are predicted.
+    pub fn get_input_with_confidence(
+        &self,
+        player_num: PlayerNum,
+        tick: u32,
+    ) -> (T, InputConfidence) {
+        match self.input_index(tick) {
+            Some(index) => self.buffers.get_input_with_confidence(player_num, index),
+            None => (T::default(), InputConfidence::Finalized),
+        }
     }
 
     /// returns the newest input tick for this peer, whether finalized or not
@@ -100,12 +331,462 @@ impl<T: SimInput, Buf> MultiplayerInputManager<T, Buf> {
     /// And likewise for all N: if the number of finalized ticks
     /// that have been observed for all peers is N, then we can seen inputs_{N-1}
     /// for all peers, and can snapshot up to tick N.
+    ///
+    /// This is an absolute sim tick: if the session doesn't start at tick 0, it is offset by the start tick.
     pub fn get_snapshottable_sim_tick(&self) -> u32 {
+        self.buffers
+            .tick_of_input(self.buffers.get_num_finalized_inputs_across_peers())
+    }
+
+    /// Takes the earliest sim tick whose input (or prediction) for any player has changed since the last call, e.g. because a peer's input arrived that differs from the prediction for it, so a rollback sim knows how far back to re-simulate from.
+    ///
+    /// Inputs change when collected, received or finalized (including defaults the host injects for lagging guests), and never once finalized, so the result is never before the snapshottable tick as of the previous call. Ticks past a player's last input are predicted from it, so they only change if it does. A new round starts with nothing changed.
+    pub fn take_earliest_changed_tick(&mut self) -> Option<u32> {
+        let index = self.buffers.take_earliest_changed_index()?;
+        Some(self.buffers.tick_of_input(index))
+    }
+
+    /// Attaches a profiler, with which `Session` measures its `add_own_input`, `rx_msg` and `get_outgoing_msgs_for_frame` calls (see `profiling`).
+    #[cfg(feature = "profiling")]
+    pub fn with_profiler(mut self, profiler: CallProfiler<Box<dyn ProfileProbe>>) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    /// Ends the attached profiler's frame, returning its profile; `None` without a profiler.
+    #[cfg(feature = "profiling")]
+    pub fn end_profile_frame(&mut self) -> Option<FrameProfile> {
+        self.profiler.as_mut().map(CallProfiler::end_frame)
+    }
+
+    /// Keeps every (player, tick) whose input changes, for `take_changed_ticks_since`. Off by default: the changes pile up until taken, so only turn this on if they'll be taken every frame.
+    pub fn with_changed_tick_tracking(mut self) -> Self {
+        self.buffers.set_track_changed_inputs(true);
+        self
+    }
+
+    /// Takes the (player, sim tick) entries whose input (or prediction) has changed since the last call, at or after `tick`, ordered by tick and then player, e.g. so a rollback sim that has simulated from `tick` on re-simulates only the ticks whose inputs were rewritten by late or corrected slices, rather than every tick back to the snapshottable tick.
+    ///
+    /// Only changes made with tracking on are kept (see `with_changed_tick_tracking`); without it, this is always empty. Changes are counted as for `take_earliest_changed_tick`, but the two are taken independently. Changes before `tick` are dropped along with the rest. A new round starts with nothing changed.
+    pub fn take_changed_ticks_since(&mut self, tick: u32) -> Vec<(PlayerNum, u32)> {
+        let changed = self.buffers.take_changed_inputs();
+        changed
+            .into_iter()
+            .map(|(index, player_num)| (player_num, self.buffers.tick_of_input(index)))
+            .filter(|(_, changed_tick)| *changed_tick >= tick)
+            .collect()
+    }
+
+    /// Compares this manager's input buffers with another's, e.g. to assert that a host and guest have converged (see `buffer_diff`).
+    pub fn diff_against<R>(&self, other: &MultiplayerInputManager<T, R>) -> BufferDiff {
+        diff_buffers(&self.buffers, &other.buffers)
+    }
+
+    /// Checks a peer's input schema id against this node's (see `input_schema`), raising `InputMgrEvent::InputSchemaMismatch` if they differ.
+    pub(super) fn check_input_schema(
+        &mut self,
+        peer: PlayerNum,
+        theirs: u32,
+    ) -> Result<(), InputSchemaMismatch> {
+        if theirs == T::SCHEMA_ID {
+            return Ok(());
+        }
+        self.events.push(InputMgrEvent::InputSchemaMismatch {
+            peer,
+            ours: T::SCHEMA_ID,
+            theirs,
+        });
+        Err(InputSchemaMismatch {
+            peer,
+            ours: T::SCHEMA_ID,
+            theirs,
+        })
+    }
+
+    /// Returns a handle that resolves once the inputs for `tick` are finalized for all players, i.e. once `get_snapshottable_sim_tick` passes `tick`.
+    ///
+    /// Useful for synchronized events, e.g. "match point starts at tick 5000". If the tick is already finalized, the handle is resolved immediately.
+    pub fn notify_when_finalized(&mut self, tick: u32) -> FinalizationHandle {
+        let snapshottable_tick = self.get_snapshottable_sim_tick();
+        self.finalization_watchers.watch(tick, snapshottable_tick)
+    }
+
+    /// The number of handles from `notify_when_finalized` that haven't resolved yet.
+    pub fn num_pending_finalization_handles(&self) -> usize {
+        self.finalization_watchers.num_pending()
+    }
+
+    /// Sets the most inputs queued at once for each channel opened with `finalized_input_rx` (default `DEFAULT_FINALIZED_INPUT_RX_DEPTH`).
+    pub fn with_finalized_input_rx_depth(mut self, depth: u32) -> Self {
+        self.finalized_input_rx_depth = depth.max(1);
+        self
+    }
+
+    pub fn finalized_input_rx_depth(&self) -> u32 {
+        self.finalized_input_rx_depth
+    }
+
+    /// Opens a channel of this player's finalized inputs for the current round, which yields `(tick, input)` for each in order, exactly once (see `finalized_input_channel`).
+    ///
+    /// The inputs already finalized are queued straight away, up to the channel's depth.
+    pub fn finalized_input_rx(&mut self, player_num: PlayerNum) -> FinalizedInputRx<T> {
+        let rx = self
+            .finalized_input_txs
+            .open(player_num, self.finalized_input_rx_depth);
+        self.feed_finalized_input_rxs();
+        rx
+    }
+
+    /// The number of channels from `finalized_input_rx` still being fed; channels are dropped once their receiver is.
+    pub fn num_open_finalized_input_rxs(&self) -> usize {
+        self.finalized_input_txs.num_open()
+    }
+
+    /// Queues newly finalized inputs to every channel from `finalized_input_rx`, up to its depth.
+    ///
+    /// This happens whenever inputs are finalized, so it only needs calling directly to catch a lagging channel up once no more inputs are being finalized, e.g. after the session has ended.
+    pub fn feed_finalized_input_rxs(&mut self) {
+        for (player_num, num_lagging) in self.finalized_input_txs.feed(&self.buffers) {
+            self.events.push(InputMgrEvent::FinalizedInputRxLagging {
+                player_num,
+                num_lagging,
+            });
+        }
+    }
+
+    /// Updates everything that follows finalized inputs: resolves the handles whose ticks are now finalized, extends the input chains, and feeds the finalized input channels.
+    pub(super) fn after_inputs_finalized(&mut self) {
+        let snapshottable_tick = self.get_snapshottable_sim_tick();
+        self.finalization_watchers
+            .resolve_before(snapshottable_tick);
+        self.extend_input_chains();
+        self.feed_finalized_input_rxs();
+    }
+
+    // Input chains //////////////////////////////
+
+    /// Keeps a chained hash of each player's finalized inputs (see `input_hash_chain`), so that peers can exchange chain heads to detect input histories that have diverged. The hash isn't cryptographic, so this catches bugs and corruption, not deliberate tampering.
+    pub fn with_input_hash_chains(mut self) -> Self {
+        self.input_chains = Some(InputHashChains::new(self.buffers.num_players()));
+        self.extend_input_chains();
+        self
+    }
+
+    fn extend_input_chains(&mut self) {
+        let Some(chains) = self.input_chains.as_mut() else {
+            return;
+        };
+        for player_num in PlayerNum::iter(self.buffers.num_players()) {
+            let start = chains.num_inputs(player_num);
+            let new_inputs = self
+                .buffers
+                .borrow_finalized_slice_for_peer(player_num, start);
+            chains.extend::<T>(player_num, new_inputs.inputs);
+        }
+    }
+
+    /// The head of this player's input chain over all of their finalized inputs; `None` if input chains aren't enabled.
+    pub fn input_chain_head(&self, player_num: PlayerNum) -> Option<InputChainHead> {
+        let chains = self.input_chains.as_ref()?;
+        let num_inputs = chains.num_inputs(player_num);
+        Some(InputChainHead {
+            player_num,
+            num_inputs,
+            head: chains.head_at(player_num, num_inputs)?,
+        })
+    }
+
+    /// A message carrying this player's chain head, for peers to check against their own (see `rx_input_chain_head`); empty if input chains aren't enabled.
+    pub fn get_msg_input_chain_head(&self, player_num: PlayerNum) -> MsgPayload<T> {
+        self.input_chain_head(player_num)
+            .map_or(MsgPayload::Empty, MsgPayload::PeerInputChainHead)
+    }
+
+    /// Checks a chain head received from `sender` against this node's own chain. A mismatch also raises an `InputMgrEvent::InputChainMismatch`.
+    pub fn rx_input_chain_head(&mut self, sender: PlayerNum, msg: MsgPayload<T>) -> ChainCheck {
+        let MsgPayload::PeerInputChainHead(theirs) = msg else {
+            return ChainCheck::Unknown;
+        };
+        if usize::from(theirs.player_num) >= self.buffers.num_players() as usize {
+            return ChainCheck::Unknown;
+        }
+        let Some(ours) = self
+            .input_chains
+            .as_ref()
+            .and_then(|chains| chains.head_at(theirs.player_num, theirs.num_inputs))
+        else {
+            return ChainCheck::Unknown;
+        };
+        if ours == theirs.head {
+            ChainCheck::Match
+        } else {
+            self.events.push(InputMgrEvent::InputChainMismatch {
+                sender,
+                player_num: theirs.player_num,
+                num_inputs: theirs.num_inputs,
+            });
+            ChainCheck::Mismatch
+        }
+    }
+
+    // Bandwidth budget //////////////////////////////
+
+    /// Limits the bytes `select_outgoing_within_budget` lets through per second, e.g. when the transport reports congestion; `None` removes the limit. Can be changed at any time.
+    pub fn set_outgoing_budget_bytes_per_sec(&mut self, bytes_per_sec: Option<u32>) {
+        self.bandwidth_budget.set_bytes_per_sec(bytes_per_sec);
+    }
+
+    pub fn outgoing_budget_bytes_per_sec(&self) -> Option<u32> {
+        self.bandwidth_budget.bytes_per_sec
+    }
+
+    /// Selects which of this frame's outgoing messages to send within the budget, in priority order (see `bandwidth_budget`); `delta` is the time (sec) since the last call. Without a budget, every message is returned.
+    ///
+    /// Call this once per frame with all of the frame's messages.
+    pub fn select_outgoing_within_budget(
+        &mut self,
+        outgoing: OutgoingMsgs<T>,
+        delta: f32,
+    ) -> OutgoingMsgs<T> {
+        let num_peers = self.buffers.num_players().saturating_sub(1) as u32;
+        self.bandwidth_budget.select(outgoing, num_peers, delta)
+    }
+
+    /// The number of messages `select_outgoing_within_budget` has dropped.
+    pub fn num_msgs_dropped_for_budget(&self) -> u32 {
+        self.bandwidth_budget.num_dropped
+    }
+
+    /// The number of input slices `select_outgoing_within_budget` has sent with fewer inputs to fit the budget.
+    pub fn num_input_slices_trimmed_for_budget(&self) -> u32 {
+        self.bandwidth_budget.num_trimmed
+    }
+
+    // Determinism probe //////////////////////////////
+
+    /// Records the game's digest of its state after simulating `tick`, alongside a digest of every player's finalized inputs for the tick (see `determinism_probe`). Any peer samples already received for the tick are checked against it.
+    ///
+    /// The tick's inputs must be finalized for all players, i.e. `tick` must be before `get_snapshottable_sim_tick`.
+    pub fn record_state_digest(&mut self, tick: u32, state_digest: u64) -> Result<(), String> {
+        let snapshottable_tick = self.get_snapshottable_sim_tick();
+        if tick < self.start_tick() || tick >= snapshottable_tick {
+            return Err(format!(
+                "inputs for tick {tick} are not finalized (snapshottable tick is {snapshottable_tick})"
+            ));
+        }
+        let sample = DeterminismSample {
+            tick,
+            input_digest: self.tick_input_digest(tick),
+            state_digest,
+        };
+        for (peer, kind) in self.determinism_probe.record_own(sample) {
+            self.report_divergence(peer, tick, kind);
+        }
+        Ok(())
+    }
+
+    /// The digest of every player's inputs for `tick`, in player order (see `determinism_probe`).
+    pub(super) fn tick_input_digest(&self, tick: u32) -> u64 {
+        let inputs: Vec<T::Bytes> = self
+            .get_inputs_and_finalization_status(tick)
+            .into_iter()
+            .map(|(_, input, _)| input.to_bytes())
+            .collect();
+        tick_input_digest::<T>(&inputs)
+    }
+
+    /// A message carrying this node's sample for `tick`, for peers to check against their own (see `rx_determinism_sample`); empty if no state digest has been recorded for the tick.
+    pub fn get_msg_determinism_sample(&self, tick: u32) -> MsgPayload<T> {
+        self.determinism_probe
+            .own_sample(tick)
+            .map_or(MsgPayload::Empty, MsgPayload::PeerDeterminismSample)
+    }
+
+    /// Records a sample received from `sender` and checks it against this node's own. A divergence also raises an `InputMgrEvent::DeterminismDivergence`.
+    pub fn rx_determinism_sample(
+        &mut self,
+        sender: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> DeterminismCheck {
+        let MsgPayload::PeerDeterminismSample(sample) = msg else {
+            return DeterminismCheck::Unknown;
+        };
+        if sender == self.own_player_num
+            || usize::from(sender) >= self.buffers.num_players() as usize
+        {
+            return DeterminismCheck::Unknown;
+        }
+        let check = self.determinism_probe.record_peer(sender, sample);
+        if let DeterminismCheck::Diverged(kind) = check {
+            self.report_divergence(sender, sample.tick, kind);
+        }
+        check
+    }
+
+    fn report_divergence(&mut self, peer: PlayerNum, tick: u32, kind: DivergenceKind) {
+        if self.determinism_probe.mark_reported(peer) {
+            self.events
+                .push(InputMgrEvent::DeterminismDivergence { peer, tick, kind });
+        }
+    }
+
+    /// Compares this node's recent samples against `peer`'s, over the ticks both have sampled (at most `DETERMINISM_PROBE_CAPACITY`).
+    pub fn determinism_report(&self, peer: PlayerNum) -> DeterminismReport {
+        self.determinism_probe.report(peer)
+    }
+
+    /// The worst-case number of ticks the game may need to re-simulate right now: own tick minus the snapshottable tick.
+    pub fn max_rollback_depth(&self) -> u32 {
+        self.get_own_num_inputs()
+            .saturating_sub(self.buffers.get_num_finalized_inputs_across_peers())
+    }
+
+    /// Sets the rollback depth at which an `InputMgrEvent::RollbackDepthCapReached` is raised.
+    ///
+    /// Guests also enforce the cap: `num_inputs_needed` won't ask for inputs that would take the depth past it. The host's own inputs are its clock, so the host only raises the event; use `max_guest_ticks_behind` to bound the host's depth.
+    pub fn with_max_rollback_depth_cap(mut self, cap: u32) -> Self {
+        self.rollback_depth.cap = Some(cap);
+        self
+    }
+
+    pub fn max_rollback_depth_cap(&self) -> Option<u32> {
+        self.rollback_depth.cap
+    }
+
+    /// Sets how many of the most recent rollback depth samples `rollback_depth_summary` covers.
+    pub fn with_rollback_depth_window(mut self, num_samples: u32) -> Self {
+        self.rollback_depth.set_window(num_samples);
+        self
+    }
+
+    /// Percentiles of the rollback depth over recent own inputs (one sample per input); `None` until an own input has been added.
+    pub fn rollback_depth_summary(&self) -> Option<LatencySummary> {
+        self.rollback_depth.summary()
+    }
+
+    /// Samples the rollback depth, raising an event if it has just reached the cap.
+    pub(super) fn observe_rollback_depth(&mut self) {
+        let depth = self.max_rollback_depth();
+        if self.rollback_depth.observe(depth) {
+            self.events
+                .push(InputMgrEvent::RollbackDepthCapReached { depth });
+        }
+    }
+
+    // Session length //////////////////////////////
+
+    /// Ends the session at sim tick `max_ticks` rather than at `MAX_SESSION_TICKS` (larger values are clamped to it).
+    ///
+    /// Once this node's own inputs reach that tick, no more are collected, inputs in received slices past it are dropped, and an `InputMgrEvent::SessionEnded` is raised. All nodes in a session should use the same limit.
+    pub fn with_max_session_ticks(mut self, max_ticks: u32) -> Self {
+        self.session_limit.max_ticks = max_ticks.min(MAX_SESSION_TICKS);
+        self
+    }
+
+    pub fn max_session_ticks(&self) -> u32 {
+        self.session_limit.max_ticks
+    }
+
+    /// The number of own inputs that can still be collected before the session ends.
+    pub fn remaining_session_ticks(&self) -> u32 {
+        self.buffers
+            .num_inputs_before_tick(self.session_limit.max_ticks)
+            .saturating_sub(self.get_own_num_inputs())
+    }
+
+    /// True once this node's own inputs have reached the end of the session (see `with_max_session_ticks`).
+    pub fn is_session_ended(&self) -> bool {
+        self.remaining_session_ticks() == 0
+    }
+
+    /// True once the host has ended the session early with `end_session_at`; on a guest, once that end has arrived from the host.
+    pub fn is_session_end_requested(&self) -> bool {
+        self.session_limit.end_requested
+    }
+
+    /// True once every player's inputs are finalized up to the end of the session.
+    pub fn is_session_end_finalized(&self) -> bool {
         self.buffers.get_num_finalized_inputs_across_peers()
+            >= self
+                .buffers
+                .num_inputs_before_tick(self.session_limit.max_ticks)
+    }
+
+    /// Raises `InputMgrEvent::SessionEnded` if the session has just ended. After a graceful end (see `end_session_at`), the session only ends once every player's inputs are finalized up to the final tick.
+    pub(super) fn observe_session_end(&mut self) {
+        let ended = if self.session_limit.end_requested {
+            self.is_session_end_finalized()
+        } else {
+            self.is_session_ended()
+        };
+        if ended && !self.session_limit.end_reported {
+            self.session_limit.end_reported = true;
+            self.events.push(InputMgrEvent::SessionEnded {
+                end_tick: self.session_limit.max_ticks,
+            });
+        }
+    }
+
+    /// Drops a received slice's inputs for ticks at or past the end of the session. Returns false if none are left.
+    pub(super) fn drop_inputs_past_session_end(
+        &self,
+        input_slice: &mut PlayerInputSlice<T>,
+    ) -> bool {
+        let end = self
+            .buffers
+            .num_inputs_before_tick(self.session_limit.max_ticks);
+        input_slice.truncate_before(end);
+        !input_slice.is_empty()
+    }
+
+    /// For each player, returns the status of the input for the given absolute sim tick.
+    ///
+    /// Ticks before the start tick are treated as finalized.
+    pub fn get_input_statuses(&self, tick: u32) -> Vec<(PlayerNum, InputStatus)> {
+        let mut statuses = Vec::with_capacity(self.buffers.num_players() as usize);
+        self.get_input_statuses_into(tick, &mut statuses);
+        statuses
     }
 
-    pub fn get_input_statuses(&self, input_num: u32) -> Vec<(PlayerNum, InputStatus)> {
-        self.buffers.get_input_statuses(input_num)
+    /// Like `get_input_statuses`, but fills `out` (cleared first) instead of allocating a new vec, for per-frame use: once `out` has held every player, this doesn't allocate.
+    pub fn get_input_statuses_into(&self, tick: u32, out: &mut Vec<(PlayerNum, InputStatus)>) {
+        match self.input_index(tick) {
+            Some(index) => self.buffers.get_input_statuses_into(index, out),
+            None => {
+                out.clear();
+                out.extend(
+                    PlayerNum::iter(self.buffers.num_players())
+                        .map(|player_num| (player_num, self.status_before_start(player_num))),
+                );
+            }
+        }
+    }
+
+    /// For each player, the status of the input for each absolute sim tick in `start..end`, e.g. to draw a row of ticks per player in a UI overlay.
+    ///
+    /// Ticks before the start tick are treated as finalized.
+    pub fn get_input_statuses_range(
+        &self,
+        start: u32,
+        end: u32,
+    ) -> Vec<(PlayerNum, Vec<InputStatus>)> {
+        PlayerNum::iter(self.buffers.num_players())
+            .map(|player_num| {
+                let statuses = (start..end)
+                    .map(|tick| match self.input_index(tick) {
+                        Some(index) => self.buffers.get_input_status(player_num, index),
+                        None => self.status_before_start(player_num),
+                    })
+                    .collect();
+                (player_num, statuses)
+            })
+            .collect()
+    }
+
+    fn status_before_start(&self, player_num: PlayerNum) -> InputStatus {
+        InputStatus::Finalized {
+            num_finalized: self.buffers.get_num_finalized_inputs(player_num),
+        }
     }
 
     /// Serializes the `PlayerInputBuffer<T>` for the given player number that is held in this
@@ -123,6 +804,788 @@ impl<T: SimInput, Buf> MultiplayerInputManager<T, Buf> {
     }
 
     pub fn deserialize_player_buffer(&mut self, player_num: PlayerNum, data: &[u8]) {
-        self.buffers.deserialize_player_buffer(player_num, data)
+        self.buffers.deserialize_player_buffer(player_num, data);
+        self.after_inputs_finalized();
+    }
+
+    // Rounds //////////////////////////////
+
+    /// The index of the current round; 0 for the first round of the session.
+    pub fn current_round(&self) -> u32 {
+        self.round
+    }
+
+    /// The number of completed rounds whose buffers have been archived.
+    pub fn num_archived_rounds(&self) -> u32 {
+        self.archived_rounds.len() as u32
+    }
+
+    /// The finalized inputs of a completed round, in the same format as `get_final_inputs_by_tick`.
+    ///
+    /// Returns `None` if the round has not been archived (e.g. it is the current round).
+    #[allow(clippy::type_complexity)]
+    pub fn get_archived_final_inputs_by_tick(
+        &self,
+        round: u32,
+    ) -> Option<Vec<(u32, Vec<(u32, T)>)>> {
+        self.archived_rounds
+            .get(round as usize)
+            .map(MultiplayerInputBuffers::final_inputs_by_tick)
+    }
+
+    /// The current round's inputs up to the snapshottable tick, for export in the documented replay format (see `Replay::to_bytes`).
+    pub fn export_replay(&self) -> Replay<T> {
+        Self::replay_from_buffers(&self.buffers, self.ticks_per_sec)
+    }
+
+    /// Like `export_replay`, for a completed round; `None` if the round has not been archived.
+    pub fn export_archived_replay(&self, round: u32) -> Option<Replay<T>> {
+        self.archived_rounds
+            .get(round as usize)
+            .map(|buffers| Self::replay_from_buffers(buffers, self.ticks_per_sec))
+    }
+
+    fn replay_from_buffers(buffers: &MultiplayerInputBuffers<T>, ticks_per_sec: u32) -> Replay<T> {
+        Replay {
+            ticks_per_sec,
+            // dropped inputs (see `with_max_retained_inputs`) can't be replayed
+            start_tick: buffers.tick_of_input(buffers.first_retained_input_across_peers()),
+            inputs_by_player: buffers.finalized_input_bytes_by_player(),
+        }
+    }
+
+    /// The state of a button for this player over the last `last_n_ticks` ticks up to the local tick (`get_own_num_inputs`), oldest first.
+    ///
+    /// `is_down` extracts the button from an input. Ticks without a received input use the same prediction as `get_peer_input_for_tick`, and ticks before the start of the round are skipped. Pass the result to the helpers in `button_state` to get per-tick `ButtonState`s, hold durations, or double-taps.
+    pub fn button_history<'a>(
+        &'a self,
+        player_num: PlayerNum,
+        is_down: impl Fn(&T) -> bool + 'a,
+        last_n_ticks: u32,
+    ) -> impl Iterator<Item = bool> + 'a {
+        let end = self.buffers.tick_of_input(self.get_own_num_inputs());
+        (end.saturating_sub(last_n_ticks).max(self.start_tick())..end)
+            .map(move |tick| is_down(&self.get_peer_input_for_tick(player_num, tick)))
+    }
+
+    // Events //////////////////////////////
+
+    /// The number of this player's events that are final: confirmed by the host, at ticks for which the player's inputs are finalized.
+    pub fn get_num_finalized_events(&self, player_num: PlayerNum) -> u32 {
+        self.event_channel.num_finalized(
+            player_num,
+            self.buffers.get_num_finalized_inputs(player_num),
+        )
+    }
+
+    /// This player's final events, in the order the host accepted them. Event ticks are input indices, counted from the start tick.
+    pub fn get_finalized_events(&self, player_num: PlayerNum) -> &[TickEvent] {
+        self.event_channel
+            .events(player_num, self.get_num_finalized_events(player_num))
+    }
+
+    /// The final events that take effect at this sim tick, for all players.
+    pub fn get_finalized_events_for_tick(&self, tick: u32) -> Vec<(PlayerNum, &TickEvent)> {
+        let Some(index) = self.input_index_starting_at(tick) else {
+            return vec![];
+        };
+        PlayerNum::iter(self.buffers.num_players())
+            .flat_map(|player_num| {
+                self.get_finalized_events(player_num)
+                    .iter()
+                    .filter(move |e| e.tick == index)
+                    .map(move |e| (player_num, e))
+            })
+            .collect()
+    }
+
+    // Annotations //////////////////////////////
+
+    /// The number of the host's annotations this node has: every annotation on the host, and those received so far on a guest.
+    pub fn get_num_annotations(&self) -> u32 {
+        self.annotations.num_confirmed(HOST_PLAYER_NUM)
+    }
+
+    /// The host's annotations for this sim tick (see `add_annotation`), in the order the host added them.
+    ///
+    /// Annotations are authoritative as soon as they arrive, but like inputs they may arrive after the game first simulates their tick, so games should treat a newly arrived annotation for a past tick like a late input.
+    pub fn annotations_for_tick(&self, tick: u32) -> Vec<&TickEvent> {
+        let Some(index) = self.input_index_starting_at(tick) else {
+            return vec![];
+        };
+        self.annotations
+            .events(HOST_PLAYER_NUM, self.get_num_annotations())
+            .iter()
+            .filter(|annotation| annotation.tick == index)
+            .collect()
+    }
+
+    // Seeds //////////////////////////////
+
+    /// The lobby's RNG seed in effect at this sim tick: the seed of the host's latest change at or before the tick (see `set_seed`), or else the initial seed. Ticks before the start tick get the initial seed.
+    pub fn seed_for_tick(&self, tick: u32) -> u64 {
+        self.input_index(tick)
+            .map_or(self.seeds.initial, |index| self.seeds.seed_at(index))
+    }
+
+    /// The number of the host's seed changes this node has: every change on the host, and those received so far on a guest.
+    pub fn get_num_seed_changes(&self) -> u32 {
+        self.seeds.num_changes()
+    }
+
+    // Tick consumption //////////////////////////////
+
+    /// Records that the sim has advanced past `tick` (see `tick_consumption`).
+    ///
+    /// Errors, leaving consumption unchanged, if the tick has already been consumed, if it would skip unconsumed ticks, or if this node hasn't collected its own input for it yet.
+    pub fn mark_tick_consumed(&mut self, tick: u32) -> Result<(), TickConsumptionError> {
+        let end = self
+            .buffers
+            .tick_of_input(self.buffers.get_num_inputs(self.own_player_num));
+        self.tick_consumption.consume(tick, self.start_tick(), end)
+    }
+
+    /// The next tick the sim should consume: the start tick until a tick has been marked consumed this round.
+    pub fn next_unconsumed_tick(&self) -> u32 {
+        self.tick_consumption.next_tick(self.start_tick())
+    }
+
+    // Scheduled inputs //////////////////////////////
+
+    /// Schedules `input` as this node's own input for the absolute sim tick `tick`, to be collected in place of the input passed to `add_own_input` (guest) or `add_host_input_to_fill_needed` (host) when collection reaches that tick (see `input_schedule`). An input scheduled earlier for the same tick is replaced.
+    ///
+    /// With several sim ticks per input, the input covering `tick` is replaced. Errors if the own input for `tick` has already been collected, or if `tick` is past the end of the session.
+    pub fn schedule_own_input(&mut self, tick: u32, input: T) -> Result<(), ScheduleInputError> {
+        let next = self
+            .buffers
+            .tick_of_input(self.buffers.get_num_inputs(self.own_player_num));
+        let end_tick = self.session_limit.max_ticks;
+        if tick >= end_tick {
+            return Err(ScheduleInputError::PastSessionEnd { tick, end_tick });
+        }
+        match self.input_index(tick) {
+            Some(index) if index >= self.buffers.get_num_inputs(self.own_player_num) => {
+                self.scheduled_inputs.insert(index, input);
+                Ok(())
+            }
+            _ => Err(ScheduleInputError::AlreadyCollected { tick, next }),
+        }
+    }
+
+    /// The number of own inputs scheduled and not yet collected.
+    pub fn num_scheduled_own_inputs(&self) -> usize {
+        self.scheduled_inputs.len()
+    }
+
+    /// Drops every scheduled own input.
+    pub fn clear_scheduled_own_inputs(&mut self) {
+        self.scheduled_inputs.clear();
+    }
+
+    /// The input to collect as this node's next own input: the one scheduled for it, if any, or else `input`.
+    pub(super) fn scheduled_or(&mut self, input: T) -> T {
+        let index = self.buffers.get_num_inputs(self.own_player_num);
+        self.scheduled_inputs.take(index).unwrap_or(input)
+    }
+
+    // Capabilities //////////////////////////////
+
+    /// Restricts the optional protocol capabilities this node offers its peers (see `capabilities`); capabilities not compiled into this build are ignored. By default, every supported capability is enabled.
+    ///
+    /// Must be set before the capabilities are negotiated, i.e. before the host builds its `PreSimSync`, or before a guest receives it.
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities.intersection(Capabilities::supported());
+        self
+    }
+
+    /// The optional protocol capabilities this node has enabled.
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// The capabilities negotiated with `peer`; `Capabilities::NONE` (the baseline protocol) until the peer has joined.
+    pub fn negotiated_capabilities(&self, peer: PlayerNum) -> Capabilities {
+        self.negotiated_capabilities
+            .get(&peer)
+            .copied()
+            .unwrap_or(Capabilities::NONE)
+    }
+
+    /// The capabilities negotiated with every peer, which are the only ones a broadcast can use.
+    ///
+    /// Guests only negotiate with the host, so this is always the baseline on a guest with other peers.
+    pub fn lobby_capabilities(&self) -> Capabilities {
+        PlayerNum::iter(self.buffers.num_players())
+            .filter(|&peer| peer != self.own_player_num)
+            .fold(self.capabilities, |capabilities, peer| {
+                capabilities.intersection(self.negotiated_capabilities(peer))
+            })
+    }
+
+    /// Serializes `msg` for `peer`, using only the capabilities negotiated with it (see `MsgPayload::to_bytes_for`).
+    pub fn msg_bytes_for_peer(&self, peer: PlayerNum, msg: &MsgPayload<T>) -> Vec<u8> {
+        msg.to_bytes_for(self.negotiated_capabilities(peer))
+    }
+
+    /// Serializes `msg` for a broadcast, using only the capabilities negotiated with every peer (see `lobby_capabilities`).
+    pub fn msg_bytes_for_all_peers(&self, msg: &MsgPayload<T>) -> Vec<u8> {
+        msg.to_bytes_for(self.lobby_capabilities())
+    }
+
+    /// Stores the capabilities `peer` has in common with this node as the set negotiated with it, returning that set.
+    pub(super) fn negotiate_capabilities(
+        &mut self,
+        peer: PlayerNum,
+        theirs: Capabilities,
+    ) -> Capabilities {
+        let negotiated = self.capabilities.intersection(theirs);
+        self.negotiated_capabilities.insert(peer, negotiated);
+        negotiated
+    }
+
+    // Player metadata //////////////////////////////
+
+    /// The display metadata registered for this seat (see `player_metadata`), or `None` if there is none.
+    ///
+    /// On a guest, this is the metadata from the host's latest `PreSimSync`.
+    pub fn player_metadata(&self, player_num: PlayerNum) -> Option<&[u8]> {
+        self.player_metadata.get(&player_num).map(Vec::as_slice)
+    }
+
+    /// Checks that `metadata` can be registered for `player_num`.
+    pub(super) fn validate_player_metadata(
+        &self,
+        player_num: PlayerNum,
+        metadata: &[u8],
+    ) -> Result<(), PlayerMetadataError> {
+        if u8::from(player_num) >= self.buffers.num_players() {
+            return Err(PlayerMetadataError::NoSuchPlayer(player_num));
+        }
+        if metadata.len() > MAX_PLAYER_METADATA_BYTES {
+            return Err(PlayerMetadataError::TooLong {
+                len: metadata.len(),
+                max: MAX_PLAYER_METADATA_BYTES,
+            });
+        }
+        Ok(())
+    }
+
+    // Muting //////////////////////////////
+
+    /// The sim tick from which this player's own inputs are ignored, or `None` if the player isn't muted.
+    ///
+    /// Muted players keep their seat; the host finalizes default inputs for them instead.
+    pub fn muted_from_tick(&self, player_num: PlayerNum) -> Option<u32> {
+        self.muted_players
+            .get(&player_num)
+            .map(|first_ignored| self.buffers.tick_of_input(*first_ignored))
+    }
+
+    /// Drops the inputs in this slice that fall after the player was muted.
+    ///
+    /// Returns false if nothing is left of the slice.
+    pub(super) fn drop_muted_inputs(
+        &self,
+        player_num: PlayerNum,
+        input_slice: &mut PlayerInputSlice<T>,
+    ) -> bool {
+        if let Some(&first_ignored) = self.muted_players.get(&player_num) {
+            input_slice.truncate_before(first_ignored);
+            !input_slice.is_empty()
+        } else {
+            true
+        }
+    }
+
+    // Seats //////////////////////////////
+
+    /// The player num by which the transport addresses the connection controlling this seat. Each seat is its own connection until it is moved with `transfer_seat`.
+    pub fn connection_for_seat(&self, seat: PlayerNum) -> PlayerNum {
+        self.seat_connections.get(&seat).copied().unwrap_or(seat)
+    }
+
+    /// The seat controlled by this connection, or `None` if it controls none, e.g. a device whose seat has been moved to another.
+    ///
+    /// Messages received from a connection should be handled as coming from its seat (`Session` does this). A connection controlling several seats (see `add_local_seat`) is handled as its own seat if it still controls it, and otherwise as the lowest of its seats.
+    pub fn seat_for_connection(&self, connection: PlayerNum) -> Option<PlayerNum> {
+        let is_own_seat = u8::from(connection) < self.buffers.num_players()
+            && !self.seat_connections.contains_key(&connection);
+        if is_own_seat {
+            return Some(connection);
+        }
+        self.seat_connections
+            .iter()
+            .filter(|(_, seat_connection)| **seat_connection == connection)
+            .map(|(seat, _)| *seat)
+            .min()
+    }
+
+    /// The seats controlled by the same connection as `seat`, `seat` included, in ascending order. A guest with several local players controls more than one (see `add_local_seat`).
+    pub fn seats_sharing_connection(&self, seat: PlayerNum) -> Vec<PlayerNum> {
+        let connection = self.connection_for_seat(seat);
+        PlayerNum::iter(self.buffers.num_players())
+            .filter(|other| self.connection_for_seat(*other) == connection)
+            .collect()
+    }
+
+    /// Records that `seat` is now controlled by `connection`.
+    pub(super) fn move_seat(&mut self, seat: PlayerNum, connection: PlayerNum) {
+        if seat == connection {
+            self.seat_connections.remove(&seat);
+        } else {
+            self.seat_connections.insert(seat, connection);
+        }
+        self.events
+            .push(InputMgrEvent::SeatTransferred { seat, connection });
+    }
+
+    // Sanitizing //////////////////////////////
+
+    /// The number of this player's inputs that were changed by `SimInput::sanitize` when entering a buffer.
+    ///
+    /// Inputs in received slices are counted once, the first time they are received; resent copies aren't counted again.
+    pub fn num_sanitized_inputs(&self, player_num: PlayerNum) -> u32 {
+        self.num_sanitized.get(&player_num).copied().unwrap_or(0)
+    }
+
+    /// Sanitizes an input entering this player's buffer, counting it if it changed.
+    pub(super) fn sanitize_input(&mut self, player_num: PlayerNum, mut input: T) -> T {
+        let before = input.to_bytes();
+        input.sanitize();
+        if input.to_bytes() != before {
+            *self.num_sanitized.entry(player_num).or_default() += 1;
+        }
+        input
+    }
+
+    /// Sanitizes every input of a received slice, counting the changed inputs that this player's buffer doesn't have yet.
+    pub(super) fn sanitize_slice(
+        &mut self,
+        player_num: PlayerNum,
+        slice: &mut PlayerInputSlice<T>,
+    ) {
+        let num_inputs = self.buffers.get_num_inputs(player_num);
+        let mut num_new_sanitized = 0;
+        for (index, bytes) in (slice.start..).zip(slice.inputs.iter_mut()) {
+            let mut input = T::from_bytes(*bytes);
+            input.sanitize();
+            let sanitized = input.to_bytes();
+            if sanitized != *bytes {
+                *bytes = sanitized;
+                if index >= num_inputs {
+                    num_new_sanitized += 1;
+                }
+            }
+        }
+        if num_new_sanitized > 0 {
+            *self.num_sanitized.entry(player_num).or_default() += num_new_sanitized;
+        }
+    }
+
+    // Receiving //////////////////////////////
+
+    /// The number of this player's finalized slices that were ignored because they started past the next input needing finalization.
+    ///
+    /// An occasional gap is expected when messages arrive out of order; a count that keeps climbing points at a sender that never resends from the right place.
+    pub fn num_ignored_slices(&self, player_num: PlayerNum) -> u32 {
+        self.num_ignored_slices
+            .get(&player_num)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Sets how many inputs past the inputs finalized for every player a received peer slice may reach (default `DEFAULT_MAX_INPUT_LEAD`). On the host, disconnected and muted guests don't count: their seats are filled with default inputs instead, so a guest that drops doesn't hold the others back until then.
+    ///
+    /// Peers only run ahead of the finalized inputs by about a round trip, so a slice reaching much further is from a buggy or hostile peer; its inputs past the lead are dropped rather than stored, and counted (see `num_inputs_beyond_lead`). The lead must cover the longest stall a guest can recover from, since its inputs keep coming while the others' are delayed.
+    pub fn with_max_input_lead(mut self, max_input_lead: u32) -> Self {
+        self.max_input_lead = max_input_lead;
+        self
+    }
+
+    pub fn max_input_lead(&self) -> u32 {
+        self.max_input_lead
+    }
+
+    /// The number of this player's received inputs dropped for reaching past the max input lead (see `with_max_input_lead`).
+    pub fn num_inputs_beyond_lead(&self, player_num: PlayerNum) -> u32 {
+        self.num_inputs_beyond_lead
+            .get(&player_num)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Drops the inputs of a received peer slice that reach more than the max input lead past `lead_from`, the inputs finalized for every player the role counts, counting them and raising `InputMgrEvent::InputsBeyondLead`. Returns false if no inputs are left.
+    pub(super) fn drop_inputs_beyond_lead(
+        &mut self,
+        player_num: PlayerNum,
+        input_slice: &mut PlayerInputSlice<T>,
+        lead_from: u32,
+    ) -> bool {
+        let end = lead_from.saturating_add(self.max_input_lead);
+        let num_dropped =
+            input_slice.len() - end.saturating_sub(input_slice.start).min(input_slice.len());
+        if num_dropped > 0 {
+            input_slice.truncate_before(end);
+            *self.num_inputs_beyond_lead.entry(player_num).or_default() += num_dropped;
+            self.events.push(InputMgrEvent::InputsBeyondLead {
+                player_num,
+                num_dropped,
+            });
+        }
+        !input_slice.is_empty()
+    }
+
+    /// Bounds the memory used by each player's inputs in long sessions: once an input is finalized for every player and no longer needed, only the most recent `max_retained_inputs` of the player's inputs are kept (default: every input is kept). At least 1 input is kept, so predictions can always repeat the last known input.
+    ///
+    /// Inputs stay indexed by tick, so reads, slices and finalization counts are unaffected for the retained inputs. An input is only dropped once it is before the snapshottable tick and has been queued to every finalized input channel, and, on the host, once every guest has acked it. Inputs are dropped in batches, once `max_retained_inputs` more can be, so each player holds at most about twice that many, plus the inputs not yet finalized.
+    ///
+    /// Dropped inputs read as the default input, and replays, archived rounds, recovery responses and slices for reconnecting guests only cover the retained inputs; a guest that falls behind them needs a state transfer rather than slices.
+    pub fn with_max_retained_inputs(mut self, max_retained_inputs: u32) -> Self {
+        self.max_retained_inputs = Some(max_retained_inputs.max(1));
+        self
+    }
+
+    pub fn max_retained_inputs(&self) -> Option<u32> {
+        self.max_retained_inputs
+    }
+
+    /// Splits input slices whose messages would be longer than `max_bytes` (before compression) into several messages, e.g. to keep each within the transport's MTU after a long stall (default: slices are never split).
+    ///
+    /// Splitting applies to the `get_msgs_*` slice getters and the slices a `Session` or `handle_msg` returns; see `MsgPayload::split_to_max_bytes`.
+    pub fn with_max_payload_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_payload_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn max_payload_bytes(&self) -> Option<usize> {
+        self.max_payload_bytes
+    }
+
+    /// Resends critical messages (see `MsgKind::is_critical`) wrapped with `sequence_msg` every `resend_after_sec` until the peer acks them, for transports that may lose messages (default: messages are sent once). Peers must enable it too, since only they send the acks.
+    ///
+    /// Resends and acks are returned by `update_reliable_delivery`; see `reliable_delivery`.
+    pub fn with_reliable_delivery(mut self, resend_after_sec: f32) -> Self {
+        self.reliable_delivery.resend_after_sec = Some(resend_after_sec);
+        self
+    }
+
+    pub fn reliable_delivery_resend_after_sec(&self) -> Option<f32> {
+        self.reliable_delivery.resend_after_sec
+    }
+
+    /// Splits a slice message to the max payload size, if one is set.
+    pub(crate) fn split_to_max_payload(&self, msg: MsgPayload<T>) -> Vec<MsgPayload<T>> {
+        match self.max_payload_bytes {
+            Some(max_bytes) => msg.split_to_max_bytes(max_bytes),
+            None => vec![msg],
+        }
+    }
+
+    /// Checks the internal consistency of the input buffers: for every player, only finalized inputs are dropped, only collected inputs are finalized, and removed players have no unfinalized inputs past their end.
+    ///
+    /// For tests and debug assertions (e.g. after every step of a fuzzed session); a failure is a bug in this crate.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        self.buffers.check_invariants()
+    }
+
+    /// The index of this player's oldest retained input; earlier inputs have been dropped (see `with_max_retained_inputs`).
+    pub fn first_retained_input(&self, player_num: PlayerNum) -> u32 {
+        self.buffers.first_retained_input(player_num)
+    }
+
+    /// Drops each player's inputs that are no longer needed, keeping the most recent `max_retained_inputs` (see `with_max_retained_inputs`), or none for a removed player (see `remove_player`). `keep_from` is the first input of each player still needed by the role, e.g. the fewest acked by any guest.
+    pub(super) fn drop_unretained_inputs(&mut self, keep_from: impl Fn(PlayerNum) -> u32) {
+        let num_finalized_across_peers = self.buffers.get_num_finalized_inputs_across_peers();
+        for player_num in PlayerNum::iter(self.buffers.num_players()) {
+            let max_retained = if self.buffers.is_removed(player_num) {
+                0
+            } else if let Some(max_retained) = self.max_retained_inputs {
+                max_retained
+            } else {
+                continue;
+            };
+            let mut first_needed = num_finalized_across_peers
+                .min(
+                    self.buffers
+                        .get_num_inputs(player_num)
+                        .saturating_sub(max_retained),
+                )
+                .min(keep_from(player_num));
+            if let Some(next_queued) = self.finalized_input_txs.next_index(player_num) {
+                first_needed = first_needed.min(next_queued);
+            }
+            if let Some(chains) = &self.input_chains {
+                first_needed = first_needed.min(chains.num_inputs(player_num));
+            }
+            // drop in batches, so that the buffer isn't shifted for every input
+            if first_needed >= self.buffers.first_retained_input(player_num) + max_retained.max(1) {
+                self.buffers.drop_inputs_before(player_num, first_needed);
+            }
+        }
+    }
+
+    /// Rejects a finalized slice for this player that would leave a gap, counting it.
+    pub(super) fn reject_gap_before_slice(&mut self, player_num: PlayerNum) -> RxOutcome {
+        *self.num_ignored_slices.entry(player_num).or_default() += 1;
+        let num_finalized = self.buffers.get_num_finalized_inputs(player_num);
+        RxOutcome::rejected(RxRejection::GapAtTick(
+            self.buffers.tick_of_input(num_finalized),
+        ))
+    }
+
+    /// Applies `receive` to the buffers, and summarizes how it changed this player's buffer.
+    pub(super) fn rx_and_summarize<U>(
+        &mut self,
+        player_num: PlayerNum,
+        receive: impl FnOnce(&mut MultiplayerInputBuffers<T>) -> U,
+    ) -> RxOutcome {
+        let inputs_before = self.buffers.get_num_inputs(player_num);
+        let finalized_before = self.buffers.get_num_finalized_inputs(player_num);
+        let rewritten_before = self.buffers.num_rewritten_inputs(player_num);
+        receive(&mut self.buffers);
+        let rewritten_inputs = self.buffers.num_rewritten_inputs(player_num) - rewritten_before;
+        self.after_inputs_finalized();
+        RxOutcome {
+            new_inputs: self.buffers.get_num_inputs(player_num) - inputs_before,
+            newly_finalized: self.buffers.get_num_finalized_inputs(player_num) - finalized_before,
+            rewritten_inputs: rewritten_inputs as u32,
+            rejected: None,
+        }
+    }
+
+    /// Sets how RTT estimates are bootstrapped and smoothed.
+    ///
+    /// Estimates already under way keep the smoothing they started with.
+    pub fn with_rtt_config(mut self, config: RttConfig) -> Self {
+        self.rtt_config = config;
+        self
+    }
+
+    pub fn rtt_config(&self) -> RttConfig {
+        self.rtt_config
+    }
+
+    /// Sets the number of malformed messages from a single peer at which an `InputMgrEvent::MalformedMsgThresholdExceeded` is raised.
+    pub fn with_malformed_msg_threshold(mut self, threshold: u32) -> Self {
+        self.decode_stats.malformed_msg_threshold = threshold;
+        self
+    }
+
+    /// Decodes a message received from `player_num`, keeping per-peer and per-variant decode statistics.
+    ///
+    /// Malformed messages produce `None`, and are kept for inspection (see `malformed_msgs`). When a peer's malformed message count reaches the configured threshold, an `InputMgrEvent::MalformedMsgThresholdExceeded` is queued.
+    pub fn decode_msg_from_peer(
+        &mut self,
+        player_num: PlayerNum,
+        bytes: &[u8],
+    ) -> Option<MsgPayload<T>>
+    where
+        T: for<'a> Deserialize<'a>,
+    {
+        match MsgPayload::from_bytes(bytes) {
+            Ok(msg) => {
+                self.decode_stats.record_decoded(bytes);
+                self.net_stats
+                    .record_received(player_num, msg.kind(), bytes.len());
+                Some(msg)
+            }
+            Err(e) => {
+                self.record_malformed_msg(player_num, bytes, e.to_string());
+                None
+            }
+        }
+    }
+
+    fn record_malformed_msg(&mut self, player_num: PlayerNum, bytes: &[u8], error: String) {
+        if self.decode_stats.record_malformed(player_num, bytes, error) {
+            self.events
+                .push(InputMgrEvent::MalformedMsgThresholdExceeded {
+                    player_num,
+                    num_malformed: self.decode_stats.num_malformed_from_peer(player_num),
+                });
+        }
+    }
+
+    /// Wraps a message for `destination` with the next number in this node's sequence for that peer (see `msg_sequence`). A message for all peers must be wrapped separately for each.
+    ///
+    /// With reliable delivery enabled, critical messages are held to be resent until acked (see `update_reliable_delivery`).
+    pub fn sequence_msg(&mut self, destination: PlayerNum, msg: MsgPayload<T>) -> SequencedMsg<T> {
+        let seq = self.msg_sequences.next_seq(destination);
+        self.reliable_delivery.track_sent(destination, seq, &msg);
+        SequencedMsg { seq, payload: msg }
+    }
+
+    /// Like `decode_msg_from_peer`, for a `SequencedMsg`'s bytes: a duplicate of a message already received from `player_num`, or one too old to tell, decodes to `MsgPayload::Empty` rather than being applied again. Duplicates, reordering and gaps are counted (see `sequence_stats`).
+    ///
+    /// With reliable delivery enabled, critical messages are acked, duplicates included, and a `MsgPayload::DeliveryAck` is consumed here, decoding to `MsgPayload::Empty`. A message too old to tell from a duplicate is neither applied nor acked.
+    pub fn decode_sequenced_msg_from_peer(
+        &mut self,
+        player_num: PlayerNum,
+        bytes: &[u8],
+    ) -> Option<MsgPayload<T>>
+    where
+        T: for<'a> Deserialize<'a>,
+    {
+        match SequencedMsg::from_bytes(bytes) {
+            Ok(SequencedMsg { seq, payload }) => {
+                self.decode_stats.record_decoded(&bytes[4..]);
+                self.net_stats
+                    .record_received(player_num, payload.kind(), bytes.len());
+                match self.msg_sequences.accept(player_num, seq) {
+                    // never ack a message that wasn't applied, so the sender keeps resending it
+                    SeqArrival::TooOld => Some(MsgPayload::Empty),
+                    SeqArrival::Duplicate => {
+                        self.reliable_delivery
+                            .track_received(player_num, seq, &payload);
+                        Some(MsgPayload::Empty)
+                    }
+                    SeqArrival::New => {
+                        self.reliable_delivery
+                            .track_received(player_num, seq, &payload);
+                        if let MsgPayload::DeliveryAck(seqs) = &payload {
+                            self.reliable_delivery.rx_ack(player_num, seqs);
+                            Some(MsgPayload::Empty)
+                        } else {
+                            Some(payload)
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                // attribute the failure to the message's variant, after the sequence number
+                let payload = bytes.get(4..).unwrap_or(bytes);
+                self.record_malformed_msg(player_num, payload, e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Advances reliable delivery's resend timers by `delta` (sec), returning the messages to send now, by destination: acks owed to peers for the critical messages they sent, then critical messages unacked for too long, resent with their original sequence numbers. Returns nothing unless reliable delivery is enabled (see `with_reliable_delivery`).
+    ///
+    /// Call it every frame, like `add_own_input`, and send the messages as they are.
+    pub fn update_reliable_delivery(&mut self, delta: f32) -> Vec<(PlayerNum, SequencedMsg<T>)> {
+        let mut msgs: Vec<_> = self
+            .reliable_delivery
+            .take_acks_due()
+            .into_iter()
+            .map(|(sender, seqs)| {
+                (
+                    sender,
+                    self.sequence_msg(sender, MsgPayload::DeliveryAck(seqs)),
+                )
+            })
+            .collect();
+        msgs.extend(self.reliable_delivery.poll_resends(delta));
+        msgs
+    }
+
+    /// The number of critical messages sent to `destination` that it has yet to ack.
+    pub fn num_pending_reliable_msgs(&self, destination: PlayerNum) -> usize {
+        self.reliable_delivery.num_pending(destination)
+    }
+
+    /// The number of times critical messages to `destination` have been resent.
+    pub fn num_resent_msgs(&self, destination: PlayerNum) -> u32 {
+        self.reliable_delivery.num_resent(destination)
+    }
+
+    // Net stats //////////////////////////////
+
+    /// The messages and bytes sent to and received from each peer, by message kind, with rolling per-second rates (see `net_stats`).
+    pub fn get_net_stats(&self) -> &NetStats {
+        &self.net_stats
+    }
+
+    /// Counts a message sent to `destination` as `num_bytes` on the wire, for games that serialize messages themselves (e.g. with `msg_bytes_for_peer`, or a transport's own framing). A message for all peers is recorded once for each.
+    pub fn record_msg_bytes_sent(
+        &mut self,
+        destination: PlayerNum,
+        msg: &MsgPayload<T>,
+        num_bytes: usize,
+    ) {
+        self.net_stats
+            .record_sent(destination, msg.kind(), num_bytes);
+    }
+
+    /// Advances the clock of the rolling rates in `get_net_stats` by `delta` (sec); call it every frame.
+    pub fn update_net_stats(&mut self, delta: f32) {
+        self.net_stats.advance(delta);
+    }
+
+    /// What has been received in `player_num`'s sequence of messages to this node.
+    pub fn sequence_stats(&self, player_num: PlayerNum) -> SequenceStats {
+        self.msg_sequences.stats(player_num)
+    }
+
+    /// The number of messages of this variant successfully decoded by `decode_msg_from_peer`.
+    pub fn num_decoded_msgs(&self, variant_num: u8) -> u32 {
+        self.decode_stats.num_decoded(variant_num)
+    }
+
+    /// The number of messages with this variant number in their header that failed to decode.
+    pub fn num_malformed_msgs_for_variant(&self, variant_num: u8) -> u32 {
+        self.decode_stats.num_malformed_for_variant(variant_num)
+    }
+
+    /// The number of messages from this peer that failed to decode.
+    pub fn num_malformed_msgs_from_peer(&self, player_num: PlayerNum) -> u32 {
+        self.decode_stats.num_malformed_from_peer(player_num)
+    }
+
+    /// The most recent malformed messages (up to `MALFORMED_QUARANTINE_LEN`), oldest first.
+    pub fn malformed_msgs(&self) -> impl Iterator<Item = &MalformedMsg> {
+        self.decode_stats.quarantine()
+    }
+
+    /// Takes all events queued since the last call.
+    pub fn drain_events(&mut self) -> Vec<InputMgrEvent> {
+        self.change_counters.events_drained += self.events.len() as u64;
+        self.events.take()
+    }
+
+    /// Sets how many events are held until `drain_events` is called (`DEFAULT_MAX_QUEUED_EVENTS` by default). Past that, the oldest event is dropped for each new one (see `num_dropped_events`).
+    pub fn with_max_queued_events(mut self, max_queued_events: usize) -> Self {
+        self.events.max_len = max_queued_events;
+        self
+    }
+
+    /// The number of events dropped because the queue was full when they were raised.
+    pub fn num_dropped_events(&self) -> u64 {
+        self.events.num_dropped()
+    }
+
+    /// Counters that increase whenever the buffers, the finalization acks, the RTT estimates or the queued events change, for UIs to poll cheaply (see `change_stamps`).
+    pub fn change_stamps(&self) -> ChangeStamps {
+        ChangeStamps {
+            buffers: self.buffers.num_changes(),
+            observations: self.change_counters.observations,
+            rtt: self.change_counters.rtt,
+            events: self.change_counters.events_drained
+                + self.events.len() as u64
+                + self.events.num_dropped(),
+        }
+    }
+
+    /// Moves the current buffers into the archive and starts the next round with empty buffers.
+    pub(super) fn archive_round(&mut self) {
+        self.finalized_input_txs.close_all();
+        let fresh = self.buffers.new_empty_like();
+        let finished = std::mem::replace(&mut self.buffers, fresh);
+        self.archived_rounds.push(finished);
+        self.round += 1;
+        self.event_channel = EventChannel::new(self.buffers.num_players());
+        self.annotations = EventChannel::new(1);
+        self.seeds.clear_changes();
+        self.tick_consumption = TickConsumption::default();
+        self.scheduled_inputs.clear();
+        self.session_limit.end_reported = false;
+        self.determinism_probe.clear();
+        if self.input_chains.is_some() {
+            self.input_chains = Some(InputHashChains::new(self.buffers.num_players()));
+        }
+        // muted players stay muted for the whole of later rounds
+        for first_ignored in self.muted_players.values_mut() {
+            *first_ignored = 0;
+        }
     }
 }
@@ -1,18 +1,83 @@
 use core::f32;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use crate::{ewma::Ewma, input_trait::SimInput};
+use crate::{
+    bandwidth_budget::BandwidthBudget,
+    capabilities::Capabilities,
+    change_stamps::ChangeCounters,
+    clock_sync::{ClockPing, ClockSample, ClockSync},
+    debug_dump::{DebugDump, DumpRole},
+    decode_stats::DecodeStats,
+    desync_report::{InputHashReport, MAX_INPUT_HASH_REPORT_TICKS},
+    determinism_probe::DeterminismProbe,
+    event_channel::{EventChannel, TickEvent},
+    events::{EventQueue, InputMgrEvent},
+    finalization_watch::FinalizationWatchers,
+    finalized_input_channel::{DEFAULT_FINALIZED_INPUT_RX_DEPTH, FinalizedInputTxs},
+    full_state::{FullState, RoleState},
+    host_recovery::RecoveryResponse,
+    input_schedule::InputSchedule,
+    input_schema::InputSchemaMismatch,
+    input_staging::InputStagingQueue,
+    input_trait::SimInput,
+    msg_sequence::MsgSequences,
+    net_stats::NetStats,
+    reliable_delivery::ReliableDelivery,
+    rollback_depth::RollbackDepthTracker,
+    rtt::{PingReport, RttConfig, RttEstimate, RttSummary, ms_to_micros},
+    rx_outcome::{RxOutcome, RxRejection},
+    seed_schedule::SeedSchedule,
+    session::{
+        OutgoingMsgs, Recipient, SessionPhase, SessionRxError, push_replies, without_empty_msgs,
+    },
+    session_limit::{MAX_SESSION_TICKS, SessionLimit, signed_host_tick},
+    start_sync::{GuestStartCountdown, StartAck},
+    tick_consumption::TickConsumption,
+    unknown_player_slices::UnknownPlayerSlices,
+};
 
 use super::{
-    input_messages::{HostFinalizedSlice, MsgPayload, PreSimSync},
+    input_messages::{
+        HostFinalizedSlice, LocalPlayerInputs, MsgPayload, PlayerJoined, PlayerMuted,
+        PlayerRemoved, PongPong, PreSimSync, SeatTransfer,
+    },
     multiplayer_input_buffer::MultiplayerInputBuffers,
-    multiplayer_input_manager::MultiplayerInputManager,
-    util_types::PlayerNum,
+    multiplayer_input_manager::{DEFAULT_MAX_INPUT_LEAD, MultiplayerInputManager},
+    multiplayer_input_manager_host::HOST_PLAYER_NUM,
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    util_types::{PlayerInputSlice, PlayerNum},
 };
 
 pub(crate) const DEFAULT_MAX_CATCHUP_INPUTS: u32 = 5;
 
-/// get the time since the program started in microseconds as a u64
+/// Guest pacing is computed in fixed point: durations in whole microseconds and tick counts in millionths of a tick, so that pacing decisions are bit-for-bit identical on every platform. Floats appear only where an RTT is observed and where pacing is reported.
+pub const MICRO_TICKS_PER_TICK: i64 = 1_000_000;
+
+const MICROS_PER_SEC: i64 = 1_000_000;
+
+/// How many ticks the host's tick must go backwards by before a guest reports a likely host restart (see `with_host_tick_regression_threshold`).
+pub const DEFAULT_HOST_TICK_REGRESSION_THRESHOLD: u32 = 60;
+
+/// How many inputs must be newly finalized (summed over all players) before `maybe_get_msg_ack` sends an ack (see `with_ack_min_new_inputs`).
+pub const DEFAULT_ACK_MIN_NEW_INPUTS: u32 = 1;
+
+/// The longest `maybe_get_msg_ack` goes without sending an ack, in seconds (see `with_ack_max_interval_sec`).
+pub const DEFAULT_ACK_MAX_INTERVAL_SEC: f32 = 0.25;
+
+/// How often `get_outgoing_msgs_for_frame` includes a ping, in seconds (see `with_ping_interval_sec`).
+pub const DEFAULT_PING_INTERVAL_SEC: f32 = 0.5;
+
+/// What a guest does when its local tick has run more than a tick ahead of its estimate of the host's current tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AheadOfHostPolicy {
+    /// Collect no inputs until the host catches up, pausing the local sim for as many frames as needed.
+    #[default]
+    Stall,
+    /// Keep collecting one input per frame, and rely on the game slowing its frame loop by `recommended_frame_delay_ms()`.
+    ///
+    /// This stretches the surplus ticks over extra wall-clock time rather than freezing the sim outright.
+    Throttle,
+}
 
 /// A struct to keep track of the times at which pings were sent
 struct PingSendTimes {
@@ -37,13 +102,10 @@ impl PingSendTimes {
         ping_id
     }
 
-    fn observe_pong(&mut self, ping_id: u32) -> f32 {
-        let sent_instant = self
-            .pings
-            .remove(&ping_id)
-            .expect(format!("No ping with id {}", ping_id).as_str());
-
-        sent_instant.elapsed().as_millis_f32()
+    /// The RTT (ms) of the ping with this id; `None` if no such ping is outstanding.
+    fn observe_pong(&mut self, ping_id: u32) -> Option<f32> {
+        let sent_instant = self.pings.remove(&ping_id)?;
+        Some(sent_instant.elapsed().as_millis_f32())
     }
 }
 
@@ -52,35 +114,107 @@ impl PingSendTimes {
 ///
 /// Timing works as follows:
 /// - we start with finalized world state S_0
+///
 /// Then by induction:
 /// - at time T, we have world state S_T
 /// - we collect inputs I_T for time T
 /// - we can compute S_{T+1} from S_T and I_T
+///
 /// Therefore, if S_T and all the inputs in I_T are finalized,
 /// we can compute S_{T+1} and finalize + snapshot it.
 pub struct GuestInputMgr {
     /// the most recent collected input tick
     /// that the host has sent to this peer
     ///
-    /// can be negative in the pre-sim sync phase
-    host_tick: i32,
+    /// can be negative in the pre-sim sync phase;
+    /// `None` until the first PreSimSync or finalized slice arrives
+    host_tick: Option<i32>,
 
     /// the number of ms it takes for a finalization to
     /// make it from the host to this peer.
     ///
-    /// `None` if no RTT samples have been observed yet
-    rtt_ms_to_host: Option<Ewma>,
+    /// Has no value until an RTT sample has been observed
+    rtt_ms_to_host: RttEstimate,
 
     pings: PingSendTimes,
+    /// The host's latest comparison of its RTT and this guest's; `None` until the host has completed a ping cycle.
+    ping_report: Option<PingReport>,
+    /// The local time (whole micros) last passed to `observe_local_time_ms`; `None` until then.
+    local_time_micros: Option<i64>,
+    /// The offset between this guest's local clock and the host's sim clock, from clock pings.
+    clock_sync: ClockSync,
+
+    /// CONFIG SETTING
+    /// How `num_inputs_needed` behaves when this guest is ahead of the host.
+    ahead_of_host_policy: AheadOfHostPolicy,
+
+    /// The most recent clock skew (ppm) the host has reported for this guest.
+    host_reported_skew_ppm: i32,
+
+    /// CONFIG SETTING
+    /// How many ticks the host's tick must go backwards by before `InputMgrEvent::HostTickRegressed` is raised.
+    host_tick_regression_threshold: u32,
+    /// The number of host ticks received that were lower than the newest one seen.
+    num_host_tick_regressions: u32,
+    /// True once `InputMgrEvent::HostTickRegressed` has been raised, until the reset is accepted.
+    host_tick_regression_reported: bool,
+
+    /// CONFIG SETTING
+    /// How many inputs must be newly finalized since the last ack before `maybe_get_msg_ack` sends another.
+    ack_min_new_inputs: u32,
+    /// CONFIG SETTING
+    /// The longest (sec) `maybe_get_msg_ack` goes without sending an ack.
+    ack_max_interval_sec: f32,
+    /// The finalized counts carried by the last ack sent.
+    last_acked_finalized: PeerwiseFinalizedInputsSeen,
+    /// The time (sec) accumulated by `maybe_get_msg_ack` since the last ack was sent.
+    time_since_ack_sec: f32,
+    /// CONFIG SETTING
+    /// How often (sec) `get_outgoing_msgs_for_frame` includes a ping.
+    ping_interval_sec: f32,
+    /// The time (sec) accumulated by `get_outgoing_msgs_for_frame` since it last included a ping; `None` before the first.
+    time_since_ping_sec: Option<f32>,
+    /// The first tick not yet covered by an input hash report (see `get_msg_input_hash_report`).
+    next_input_hash_tick: u32,
+
+    /// The countdown to the start proposed or confirmed by the host (see `start_sync`); `None` until a proposal arrives.
+    start_countdown: Option<GuestStartCountdown>,
+
+    /// CONFIG SETTING
+    /// The seats whose inputs this guest collects besides its own, e.g. for split-screen (see `new_with_local_players`).
+    extra_local_players: Vec<PlayerNum>,
+}
+
+impl Default for GuestInputMgr {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GuestInputMgr {
     // CONSTRUCTORS ///////////////////////////////////////////
     pub fn new() -> Self {
         Self {
-            host_tick: i32::MIN,
-            rtt_ms_to_host: None,
+            host_tick: None,
+            rtt_ms_to_host: RttEstimate::default(),
             pings: PingSendTimes::new(),
+            ping_report: None,
+            local_time_micros: None,
+            clock_sync: ClockSync::default(),
+            ahead_of_host_policy: AheadOfHostPolicy::default(),
+            host_reported_skew_ppm: 0,
+            host_tick_regression_threshold: DEFAULT_HOST_TICK_REGRESSION_THRESHOLD,
+            num_host_tick_regressions: 0,
+            host_tick_regression_reported: false,
+            ack_min_new_inputs: DEFAULT_ACK_MIN_NEW_INPUTS,
+            ack_max_interval_sec: DEFAULT_ACK_MAX_INTERVAL_SEC,
+            last_acked_finalized: PeerwiseFinalizedInputsSeen::default(),
+            time_since_ack_sec: 0.0,
+            ping_interval_sec: DEFAULT_PING_INTERVAL_SEC,
+            time_since_ping_sec: None,
+            next_input_hash_tick: 0,
+            start_countdown: None,
+            extra_local_players: vec![],
         }
     }
 }
@@ -91,67 +225,412 @@ impl<T: SimInput> MultiplayerInputManager<T, GuestInputMgr> {
             ticks_per_sec,
             buffers: MultiplayerInputBuffers::new(num_players, DEFAULT_MAX_CATCHUP_INPUTS),
             inner: GuestInputMgr::new(),
-            own_player_num: own_player_num,
+            own_player_num,
+            round: 0,
+            archived_rounds: Vec::default(),
+            decode_stats: DecodeStats::default(),
+            msg_sequences: MsgSequences::default(),
+            reliable_delivery: ReliableDelivery::default(),
+            net_stats: NetStats::default(),
+            events: EventQueue::default(),
+            muted_players: HashMap::default(),
+            seat_connections: HashMap::default(),
+            event_channel: EventChannel::new(num_players),
+            annotations: EventChannel::new(1),
+            seeds: SeedSchedule::default(),
+            tick_consumption: TickConsumption::default(),
+            scheduled_inputs: InputSchedule::default(),
+            capabilities: Capabilities::supported(),
+            negotiated_capabilities: HashMap::default(),
+            player_metadata: BTreeMap::default(),
+            unknown_player_slices: UnknownPlayerSlices::default(),
+            rollback_depth: RollbackDepthTracker::default(),
+            max_payload_bytes: None,
+            finalization_watchers: FinalizationWatchers::default(),
+            finalized_input_rx_depth: DEFAULT_FINALIZED_INPUT_RX_DEPTH,
+            finalized_input_txs: FinalizedInputTxs::default(),
+            num_sanitized: HashMap::default(),
+            rtt_config: RttConfig::default(),
+            num_ignored_slices: HashMap::default(),
+            max_input_lead: DEFAULT_MAX_INPUT_LEAD,
+            num_inputs_beyond_lead: HashMap::default(),
+            max_retained_inputs: None,
+            input_chains: None,
+            session_limit: SessionLimit::default(),
+            determinism_probe: DeterminismProbe::default(),
+            bandwidth_budget: BandwidthBudget::default(),
+            host_recovery: None,
+            change_counters: ChangeCounters::default(),
+            #[cfg(feature = "profiling")]
+            profiler: None,
         }
     }
 
+    /// A guest collecting the inputs of several local players on one device, e.g. for split-screen or couch co-op alongside online players. `own_player_nums` are the local players' seats; the first is this guest's own seat, whose connection reaches the host, and the host must let that connection control the others with `add_local_seat`.
+    ///
+    /// Inputs for each local player are added with `add_own_input_for`, and `get_msg_own_input_slice` bundles every local player's inputs into one message. Pacing (see `num_inputs_needed`) follows the own seat, so the game should add an input for every local player each time it adds one for the own seat.
+    pub fn new_with_local_players(
+        num_players: u8,
+        own_player_nums: Vec<PlayerNum>,
+        ticks_per_sec: u32,
+    ) -> Self {
+        let (&own_player_num, extra_local_players) = own_player_nums
+            .split_first()
+            .expect("a guest needs at least one local player");
+        for (i, player_num) in own_player_nums.iter().enumerate() {
+            assert!(
+                player_num.is_guest() && u8::from(*player_num) < num_players,
+                "{player_num:?} is not a guest seat of a {num_players} player session"
+            );
+            assert!(
+                !own_player_nums[..i].contains(player_num),
+                "{player_num:?} is listed twice"
+            );
+        }
+        let mut mgr = Self::new(num_players, own_player_num, ticks_per_sec);
+        mgr.inner.extra_local_players = extra_local_players.to_vec();
+        mgr
+    }
+
+    /// The seats whose inputs this guest collects, its own seat first (see `new_with_local_players`).
+    pub fn local_player_nums(&self) -> Vec<PlayerNum> {
+        let mut local_player_nums = vec![self.own_player_num];
+        local_player_nums.extend(&self.inner.extra_local_players);
+        local_player_nums
+    }
+
+    fn is_local_player(&self, player_num: PlayerNum) -> bool {
+        player_num == self.own_player_num || self.inner.extra_local_players.contains(&player_num)
+    }
+
+    /// Sets how many ticks the host's tick must go backwards by before `InputMgrEvent::HostTickRegressed` is raised.
+    pub fn with_host_tick_regression_threshold(mut self, ticks: u32) -> Self {
+        self.inner.host_tick_regression_threshold = ticks;
+        self
+    }
+
+    /// Sets how many inputs (summed over all players) must be newly finalized since the last ack before `maybe_get_msg_ack` sends another (at least 1).
+    pub fn with_ack_min_new_inputs(mut self, num_inputs: u32) -> Self {
+        self.inner.ack_min_new_inputs = num_inputs.max(1);
+        self
+    }
+
+    /// Sets the longest (sec) `maybe_get_msg_ack` goes without sending an ack, even if nothing new has been finalized.
+    pub fn with_ack_max_interval_sec(mut self, interval_sec: f32) -> Self {
+        self.inner.ack_max_interval_sec = interval_sec;
+        self
+    }
+
+    /// Sets how often (sec) `get_outgoing_msgs_for_frame` includes a ping.
+    pub fn with_ping_interval_sec(mut self, interval_sec: f32) -> Self {
+        self.inner.ping_interval_sec = interval_sec;
+        self
+    }
+
+    /// Sets how `num_inputs_needed` behaves when this guest is ahead of the host.
+    pub fn with_ahead_of_host_policy(mut self, policy: AheadOfHostPolicy) -> Self {
+        self.inner.ahead_of_host_policy = policy;
+        self
+    }
+
+    pub fn ahead_of_host_policy(&self) -> AheadOfHostPolicy {
+        self.inner.ahead_of_host_policy
+    }
+
     /// the number of finalized inputs that the host has
     /// seen from this peer and acked back to the peer
     pub fn num_final_inputs_seen_by_host(&self) -> u32 {
         self.buffers.get_num_finalized_inputs(self.own_player_num)
     }
 
+    /// The own inputs this guest has collected (and so sent, since every own input slice runs to the newest input) that the host hasn't yet finalized. Together with the host's `inputs_in_flight`, this tells whether inputs are held up on the way to the host or waiting to be finalized there.
+    pub fn num_own_inputs_in_flight(&self) -> u32 {
+        self.get_own_num_inputs()
+            .saturating_sub(self.num_final_inputs_seen_by_host())
+    }
+
     pub fn observe_rtt_ms_to_host(&mut self, rtt: f32) {
         assert!(
             rtt >= 0.01,
             "RTT must be in units of ms; got {} (less than 10 micros)",
             rtt
         );
-        if self.inner.rtt_ms_to_host.is_none() {
-            self.inner.rtt_ms_to_host = Some(Ewma::default().with_value(rtt));
+        let was_synced = self.is_synced();
+        self.inner.rtt_ms_to_host.observe(rtt, &self.rtt_config);
+        self.change_counters.rtt += 1;
+        self.raise_event_if_newly_synced(was_synced);
+    }
+
+    /// The smoothed RTT (ms) to the host; `None` until an RTT sample has been observed.
+    pub fn get_rtt_ms_to_host(&self) -> Option<f32> {
+        self.inner.rtt_ms_to_host.value()
+    }
+
+    /// The smoothed deviation (ms) of RTT samples to the host from the smoothed RTT; `None` until two RTT samples have been observed.
+    pub fn get_rtt_jitter_ms_to_host(&self) -> Option<f32> {
+        self.inner.rtt_ms_to_host.jitter()
+    }
+
+    /// The lowest RTT (ms) to the host observed so far; `None` until an RTT sample has been observed.
+    pub fn get_min_rtt_ms_to_host(&self) -> Option<f32> {
+        self.inner.rtt_ms_to_host.min()
+    }
+
+    /// The smoothed RTT (ms) to the host with its sample count, and whether it has warmed up (see `RttConfig::warm_up_samples`); `None` until an RTT sample has been observed.
+    pub fn rtt_to_host_summary(&self) -> Option<RttSummary> {
+        self.inner.rtt_ms_to_host.summary(&self.rtt_config)
+    }
+
+    /// Half the RTT to the host, in ticks; `None` until an RTT sample has been observed.
+    ///
+    /// This reports `one_way_in_micro_ticks`, which is what pacing uses.
+    pub fn one_way_in_ticks(&self) -> Option<f32> {
+        Some(self.one_way_in_micro_ticks()? as f32 / MICRO_TICKS_PER_TICK as f32)
+    }
+
+    /// The one-way latency from the host, in millionths of a tick (see `MICRO_TICKS_PER_TICK`); `None` until an RTT sample has been observed.
+    ///
+    /// This is half the RTT to the host, less the turnaround gap in the host's latest `PingReport` (if any): the guest's RTT includes the host's turnaround time, which does not delay the host ticks this guest receives. It is never negative.
+    ///
+    /// The smoothed RTT is rounded to the nearest microsecond, and the result rounded down to a whole micro-tick.
+    pub fn one_way_in_micro_ticks(&self) -> Option<i64> {
+        let rtt_micros = ms_to_micros(self.get_rtt_ms_to_host()?);
+        let turnaround_gap_micros = self
+            .inner
+            .ping_report
+            .map_or(0, |report| report.turnaround_gap_micros as i64);
+        let round_trip_micros = (rtt_micros - turnaround_gap_micros).max(0);
+        // micros * (ticks / sec) / (micros / sec) gives ticks, and the
+        // micro-tick scale cancels the micros per sec.
+        Some(
+            round_trip_micros * self.ticks_per_sec as i64 / (2 * self.sim_ticks_per_input() as i64),
+        )
+    }
+
+    /// How far (micro-ticks) `num_inputs_needed` pads the expected current host tick for jitter: the configured multiple (see `RttConfig::jitter_padding`) of half the RTT jitter, or 0 until the jitter is known.
+    fn jitter_padding_in_micro_ticks(&self) -> i64 {
+        let Some(jitter_ms) = self.get_rtt_jitter_ms_to_host() else {
+            return 0;
+        };
+        let padding_micros = ms_to_micros(jitter_ms * self.rtt_config.jitter_padding).max(0);
+        padding_micros * self.ticks_per_sec as i64 / (2 * self.sim_ticks_per_input() as i64)
+    }
+
+    /// Records this guest's local time (ms), from any monotonic clock; call it each frame before `num_inputs_needed`. Clock pings are stamped with it (see `get_msg_clock_ping`), and it is what `estimated_host_tick_now` projects from.
+    pub fn observe_local_time_ms(&mut self, now_ms: f64) {
+        self.inner.local_time_micros = Some((now_ms * 1000.0).round() as i64);
+    }
+
+    /// How far (ms) the host's sim clock is ahead of this guest's local clock; `None` until a clock pong has been accepted.
+    pub fn host_clock_offset_ms(&self) -> Option<f64> {
+        Some(self.inner.clock_sync.offset_micros()? as f64 / 1000.0)
+    }
+
+    /// The number of clock pongs accepted into `host_clock_offset_ms`.
+    pub fn num_clock_sync_samples(&self) -> u32 {
+        self.inner.clock_sync.num_samples()
+    }
+
+    /// The host's tick (fractional) at this guest's last observed local time (see `observe_local_time_ms`), projected through the host clock offset; `None` until a clock pong has been accepted.
+    ///
+    /// Unlike the host tick plus the one-way latency, this doesn't assume the host turns messages around as fast as this guest does. Between clock pongs, it is corrected for the clock skew the host last reported. Once it is known, `num_inputs_needed` paces against it.
+    pub fn estimated_host_tick_now(&self) -> Option<f32> {
+        Some(self.estimated_host_micro_ticks_now()? as f32 / MICRO_TICKS_PER_TICK as f32)
+    }
+
+    fn estimated_host_micro_ticks_now(&self) -> Option<i64> {
+        let local_micros = self.inner.local_time_micros?;
+        let host_micros = local_micros as i128
+            + self.inner.clock_sync.offset_micros()? as i128
+            + self.skew_correction_micros(local_micros) as i128;
+        // widened, since micros * micro-ticks overflows an i64 within hours
+        Some(
+            (host_micros * MICRO_TICKS_PER_TICK as i128 * self.ticks_per_sec as i128
+                / (MICROS_PER_SEC as i128 * self.sim_ticks_per_input() as i128)) as i64,
+        )
+    }
+
+    /// How far (micros) the host's clock has drifted from this guest's since the last accepted clock pong, going by the skew the host last reported (see `get_host_reported_skew_ppm`). A guest reported slow has fallen behind the host, so this is positive.
+    fn skew_correction_micros(&self, local_micros: i64) -> i64 {
+        let Some(synced_at) = self.inner.clock_sync.last_sample_recv_micros() else {
+            return 0;
+        };
+        let elapsed_micros = (local_micros - synced_at).max(0) as i128;
+        (-elapsed_micros * self.inner.host_reported_skew_ppm as i128 / 1_000_000) as i64
+    }
+
+    /// The host's latest comparison of its RTT to this guest with this guest's RTT to the host; `None` until the host has completed a ping cycle with this guest.
+    pub fn ping_report(&self) -> Option<PingReport> {
+        self.inner.ping_report
+    }
+
+    /// The duration of one tick (i.e. one input) in whole microseconds, rounded down.
+    fn micros_per_tick(&self) -> i64 {
+        MICROS_PER_SEC * self.sim_ticks_per_input() as i64 / self.ticks_per_sec.max(1) as i64
+    }
+
+    /// The most recent host tick this guest has heard of; negative during the PreSimSync countdown, and `None` until the countdown or a finalized slice arrives.
+    pub fn get_host_tick(&self) -> Option<i32> {
+        self.inner.host_tick
+    }
+
+    /// Where this guest is in the session's lifecycle: in the lobby until it hears the host's tick, counting down while the host's tick is negative, and running once the host's tick or this guest's own inputs have started.
+    pub fn session_phase(&self) -> SessionPhase {
+        if self.is_session_ended() {
+            SessionPhase::Ended
+        } else if self.round > 0
+            || self.get_own_num_inputs() > 0
+            || self.inner.host_tick.is_some_and(|tick| tick >= 0)
+        {
+            SessionPhase::Running
+        } else if self.inner.host_tick.is_some() {
+            SessionPhase::Countdown
         } else {
-            self.inner.rtt_ms_to_host.as_mut().unwrap().observe(rtt);
+            SessionPhase::Lobby
         }
     }
 
-    pub fn get_rtt_ms_to_host(&self) -> f32 {
-        self.inner
-            .rtt_ms_to_host
-            .as_ref()
-            .map_or(f32::NAN, |ewma| ewma.value())
+    /// True once this guest knows both the host's tick and its RTT to the host, and so can pace its inputs against the host.
+    ///
+    /// Until then, the guest is unsynced: `num_inputs_needed` asks for exactly one input per call, and `ticks_ahead` and `recommended_frame_delay_ms` are 0. An `InputMgrEvent::GuestSynced` is raised when the guest becomes synced.
+    pub fn is_synced(&self) -> bool {
+        self.inner.host_tick.is_some() && self.inner.rtt_ms_to_host.value().is_some()
+    }
+
+    /// Raises `InputMgrEvent::GuestSynced` if this guest has just become synced.
+    fn raise_event_if_newly_synced(&mut self, was_synced: bool) {
+        if let Some(host_tick) = self.inner.host_tick
+            && !was_synced
+            && self.is_synced()
+        {
+            self.events.push(InputMgrEvent::GuestSynced { host_tick });
+        }
+    }
+
+    /// Records a host tick, keeping the newest one seen.
+    ///
+    /// Lower host ticks are counted as regressions; a regression past the threshold raises `InputMgrEvent::HostTickRegressed`.
+    fn observe_host_tick(&mut self, host_tick: i32) {
+        let was_synced = self.is_synced();
+        match self.inner.host_tick {
+            Some(current) if host_tick < current => {
+                self.observe_host_tick_regression(current, host_tick)
+            }
+            Some(current) if host_tick == current => {}
+            _ => self.inner.host_tick = Some(host_tick),
+        }
+        self.raise_event_if_newly_synced(was_synced);
+    }
+
+    fn observe_host_tick_regression(&mut self, newest_host_tick: i32, host_tick: i32) {
+        self.inner.num_host_tick_regressions += 1;
+        let regression = newest_host_tick.abs_diff(host_tick);
+        if regression > self.inner.host_tick_regression_threshold
+            && !self.inner.host_tick_regression_reported
+        {
+            self.inner.host_tick_regression_reported = true;
+            self.events.push(InputMgrEvent::HostTickRegressed {
+                newest_host_tick,
+                host_tick,
+            });
+        }
     }
 
-    pub fn one_way_in_ticks(&self) -> f32 {
-        let rtt_sec = self.inner.rtt_ms_to_host.as_ref().unwrap().value() / 1000.0;
-        0.5 * rtt_sec * self.ticks_per_sec as f32
+    /// The number of host ticks received that were lower than the newest one seen.
+    ///
+    /// Small regressions are expected when finalized slices arrive out of order; large ones point at a host restart or a host bug.
+    pub fn num_host_tick_regressions(&self) -> u32 {
+        self.inner.num_host_tick_regressions
+    }
+
+    /// Deliberately adopts a host tick lower than the newest one seen, e.g. after reconnecting to a restarted or migrated host. Lower host ticks are otherwise ignored.
+    ///
+    /// This also re-arms `InputMgrEvent::HostTickRegressed`.
+    pub fn accept_host_tick_reset(&mut self, host_tick: i32) {
+        self.inner.host_tick = Some(host_tick);
+        self.inner.host_tick_regression_reported = false;
     }
 
+    /// The number of micro-ticks by which the local tick trails the expected current host tick (negative if this guest is ahead).
+    ///
+    /// `None` until this guest is synced (see `is_synced`).
+    fn micro_ticks_behind_host(&self) -> Option<i64> {
+        let host_tick = self.inner.host_tick? as i64 * MICRO_TICKS_PER_TICK;
+        let expected_current_host_tick = match self.estimated_host_micro_ticks_now() {
+            Some(estimated) => estimated,
+            None => host_tick + self.one_way_in_micro_ticks()?,
+        };
+        let local_tick = self.get_own_num_inputs() as i64 * MICRO_TICKS_PER_TICK;
+
+        Some(expected_current_host_tick - local_tick)
+    }
+
+    /// The number of micro-ticks by which this guest's local tick is ahead of the expected current host tick, or 0 if it is not ahead (or this guest is not synced yet).
+    fn micro_ticks_ahead(&self) -> i64 {
+        self.micro_ticks_behind_host()
+            .map_or(0, |behind| (-behind).max(0))
+    }
+
+    /// The number of ticks by which this guest's local tick is ahead of the expected current host tick, or 0 if it is not ahead (or no RTT has been observed yet).
+    pub fn ticks_ahead(&self) -> f32 {
+        self.micro_ticks_ahead() as f32 / MICRO_TICKS_PER_TICK as f32
+    }
+
+    /// The extra time (ms) the game should wait before running its next frame so the host can catch up, or 0 if this guest is within a tick of the host.
+    ///
+    /// At most one tick's duration is recommended per frame, so that the local loop slows down smoothly instead of freezing. The delay is computed in whole microseconds (rounded down) before being reported in ms.
+    pub fn recommended_frame_delay_ms(&self) -> f32 {
+        let micro_ticks_over_tolerance = self.micro_ticks_ahead() - MICRO_TICKS_PER_TICK;
+        if micro_ticks_over_tolerance <= 0 {
+            return 0.0;
+        }
+        let delay_micros = micro_ticks_over_tolerance.min(MICRO_TICKS_PER_TICK)
+            * self.micros_per_tick()
+            / MICRO_TICKS_PER_TICK;
+        (delay_micros as f64 / 1000.0) as f32
+    }
+
+    /// The number of inputs the game should collect this frame to keep pace with the host.
+    ///
+    /// Before this guest is synced (see `is_synced`), this is always 1. The expected current host tick is padded for RTT jitter (see `RttConfig::jitter_padding`).
+    ///
+    /// If a rollback depth cap is set (see `with_max_rollback_depth_cap`), this never asks for inputs that would take the depth past the cap. Once the session has ended (see `with_max_session_ticks`), this is 0.
     pub fn num_inputs_needed(&self) -> u32 {
+        let needed = self
+            .num_inputs_needed_to_keep_pace()
+            .min(self.remaining_session_ticks());
+        match self.rollback_depth.cap {
+            Some(cap) => needed.min(cap.saturating_sub(self.max_rollback_depth())),
+            None => needed,
+        }
+    }
+
+    fn num_inputs_needed_to_keep_pace(&self) -> u32 {
         // if we're in the start up phase and we haven't
         // observed the rtt yet or a host tick, just
         // collect a single input
-        if self.inner.rtt_ms_to_host.is_none() {
+        let Some(micro_ticks_behind) = self.micro_ticks_behind_host() else {
             return 1;
-        }
+        };
+        let micro_ticks_behind = micro_ticks_behind + self.jitter_padding_in_micro_ticks();
 
-        let host_tick = self.inner.host_tick as f32;
-
-        let expected_current_host_tick = host_tick + self.one_way_in_ticks();
-
-        let local_tick = self.get_own_num_inputs() as f32;
-
-        let ticks_behind = expected_current_host_tick - local_tick;
         // if we're within a tick of expected_current_host_tick,
         // just collect a single input;
         // if we're *ahead* of the host, by more than 1 tick,
-        // collect 0 inputs if we're more than a tick behind,
+        // defer to the configured policy;
+        // if we're more than a tick behind,
         // collect the difference, up to a max of 5 inputs
-        if ticks_behind.abs() < 1.0 {
+        // (a partial tick behind is rounded down)
+        if micro_ticks_behind.abs() < MICRO_TICKS_PER_TICK {
             1
-        } else if ticks_behind < -1.0 {
-            0
+        } else if micro_ticks_behind < -MICRO_TICKS_PER_TICK {
+            match self.inner.ahead_of_host_policy {
+                AheadOfHostPolicy::Stall => 0,
+                AheadOfHostPolicy::Throttle => 1,
+            }
         } else {
-            (ticks_behind as u32).min(5)
+            (micro_ticks_behind / MICRO_TICKS_PER_TICK).min(DEFAULT_MAX_CATCHUP_INPUTS as i64)
+                as u32
         }
     }
 
@@ -161,9 +640,165 @@ impl<T: SimInput> MultiplayerInputManager<T, GuestInputMgr> {
     /// Note that if an input tick has been skipped due to
     /// client time syncing, the client will fill in the missing
     /// inputs with a last-observation-carried-forward approach.
-
+    ///
+    /// Once the session has ended (see `with_max_session_ticks`), inputs are ignored. An input scheduled for the tick (see `schedule_own_input`) is collected in place of `input`.
     pub fn add_own_input(&mut self, input: T) {
-        self.buffers.append_input(self.own_player_num, input.into());
+        if self.is_session_ended() {
+            return;
+        }
+        let input = self.scheduled_or(input);
+        let input = self.sanitize_input(self.own_player_num, input);
+        self.buffers.append_input(self.own_player_num, input);
+        self.observe_rollback_depth();
+        self.observe_session_end();
+    }
+
+    /// Adds `num_inputs_needed` copies of the input reduced from the events staged in `queue`. Returns the number of inputs added.
+    ///
+    /// The queue is only drained if at least one input is needed; otherwise its events carry over to the next call.
+    pub fn add_own_staged_input<E>(&mut self, queue: &mut InputStagingQueue<E, T>) -> u32 {
+        let num_inputs_needed = self.num_inputs_needed();
+        if num_inputs_needed > 0 {
+            let input = queue.drain_reduced();
+            for _ in 0..num_inputs_needed {
+                self.add_own_input(input.clone());
+            }
+        }
+        num_inputs_needed
+    }
+
+    /// Adds an input for one of this guest's local players (see `new_with_local_players`), as `add_own_input` does for the own seat. Inputs for other local players aren't scheduled (see `schedule_own_input`).
+    ///
+    /// Returns an error if `player_num` isn't a local player.
+    pub fn add_own_input_for(&mut self, player_num: PlayerNum, input: T) -> Result<(), String> {
+        if player_num == self.own_player_num {
+            self.add_own_input(input);
+            return Ok(());
+        }
+        if !self.is_local_player(player_num) {
+            return Err(format!(
+                "{player_num:?} is not a local player of this guest"
+            ));
+        }
+        if self.is_session_ended() {
+            return Ok(());
+        }
+        let input = self.sanitize_input(player_num, input);
+        self.buffers.append_input(player_num, input);
+        Ok(())
+    }
+
+    // Dispatch //////////////////////////////
+
+    /// Passes a message received from the peer in seat `player_num` to the matching `rx_*` method, returning the replies to send, all addressed to the host. The messages of a batch are handled in turn, and replies superseded by a later reply are dropped.
+    pub fn handle_msg(
+        &mut self,
+        player_num: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> Result<OutgoingMsgs<T>, SessionRxError> {
+        let sender = player_num;
+        if let MsgPayload::Batch(msgs) = msg {
+            let mut replies = vec![];
+            for msg in msgs {
+                if let Ok(outgoing) = self.handle_msg(sender, msg) {
+                    push_replies(&mut replies, outgoing);
+                }
+            }
+            return Ok(replies);
+        }
+        let host = Recipient::Player(HOST_PLAYER_NUM);
+        let outgoing = match msg {
+            MsgPayload::HostToLobbyFinalizedSlice(_) => {
+                self.rx_final_peer_input_slice_from_host(msg);
+                vec![(host, self.get_msg_ack_finalization())]
+            }
+            MsgPayload::PeerInputs(_) => {
+                self.rx_peer_input_slice(sender, msg);
+                vec![]
+            }
+            MsgPayload::HostToGuestPreSimSync(_) => {
+                self.rx_pre_sim_sync(msg)
+                    .map_err(SessionRxError::InputSchemaMismatch)?;
+                vec![
+                    (host, self.get_msg_input_schema()),
+                    (host, self.get_msg_capabilities()),
+                ]
+            }
+            MsgPayload::HostToGuestPong(_) => vec![(
+                host,
+                self.rx_host_pong_and_reply(msg)
+                    .map_err(SessionRxError::Rejected)?,
+            )],
+            MsgPayload::HostToGuestRateAdjust(_) => {
+                self.rx_host_rate_adjust(msg);
+                vec![]
+            }
+            MsgPayload::HostToGuestPingReport(_) => {
+                self.rx_ping_report(msg);
+                vec![]
+            }
+            MsgPayload::HostToGuestClockPong(_) => {
+                self.rx_clock_pong(msg);
+                vec![]
+            }
+            MsgPayload::HostToLobbyRoundTransition(_) => {
+                vec![(host, self.rx_round_transition_and_reply(msg))]
+            }
+            MsgPayload::HostToLobbyPlayerMuted(_) => {
+                self.rx_player_muted(msg);
+                vec![]
+            }
+            MsgPayload::HostToLobbyEvents(_) => match self.rx_events_from_host(msg) {
+                Ok(_) => vec![(host, self.get_msg_ack_events())],
+                Err(_) => vec![],
+            },
+            MsgPayload::PeerInputChainHead(_) => {
+                self.rx_input_chain_head(sender, msg);
+                vec![]
+            }
+            MsgPayload::PeerDeterminismSample(_) => {
+                self.rx_determinism_sample(sender, msg);
+                vec![]
+            }
+            MsgPayload::HostToLobbyAnnotations(_) => {
+                self.rx_annotations_from_host(msg);
+                vec![(host, self.get_msg_ack_annotations())]
+            }
+            MsgPayload::HostToLobbyRecoveryRequest(_) => {
+                vec![(host, self.rx_recovery_request_and_reply(msg))]
+            }
+            MsgPayload::HostToLobbySeed(_) => {
+                self.rx_seed_from_host(msg);
+                vec![(host, self.get_msg_ack_seeds())]
+            }
+            MsgPayload::HostToLobbyEndSession(_) => {
+                self.rx_end_session(msg);
+                vec![(host, self.get_msg_end_ack())]
+            }
+            MsgPayload::HostToLobbySeatTransferred(_) => {
+                self.rx_seat_transfer(msg);
+                vec![]
+            }
+            MsgPayload::HostToLobbyStartProposal(_) => {
+                vec![(host, self.rx_start_proposal_and_reply(msg))]
+            }
+            MsgPayload::HostToLobbyStartConfirmed(_) => {
+                self.rx_start_confirmed(msg);
+                vec![]
+            }
+            MsgPayload::HostToLobbyPlayerJoined(_) => {
+                self.rx_player_joined(msg);
+                vec![(host, self.get_msg_ack_finalization())]
+            }
+            MsgPayload::HostToLobbyPlayerRemoved(_) => {
+                self.rx_player_removed(msg);
+                vec![]
+            }
+            // acks are consumed by `decode_sequenced_msg_from_peer`
+            MsgPayload::Empty | MsgPayload::DeliveryAck(_) => vec![],
+            _ => return Err(SessionRxError::WrongRole),
+        };
+        Ok(without_empty_msgs(outgoing))
     }
 
     // PeerInputs //////////////////////////////
@@ -173,7 +808,23 @@ impl<T: SimInput> MultiplayerInputManager<T, GuestInputMgr> {
     ///
     /// Note that if the server has seen N inputs from the peer, the next
     /// input slice sent by the peer should start at index N
+    ///
+    /// A guest with several local players (see `new_with_local_players`) instead bundles a slice for each of them into one `GuestToHostLocalInputs`, each starting at that player's first input the host hasn't finalized.
     pub fn get_msg_own_input_slice(&self) -> MsgPayload<T> {
+        if !self.inner.extra_local_players.is_empty() {
+            return MsgPayload::GuestToHostLocalInputs(
+                self.local_player_nums()
+                    .into_iter()
+                    .map(|player_num| LocalPlayerInputs {
+                        player_num,
+                        inputs: self.buffers.get_slice_to_end_for_peer(
+                            player_num,
+                            self.buffers.get_num_finalized_inputs(player_num),
+                        ),
+                    })
+                    .collect(),
+            );
+        }
         let slice_start = self.num_final_inputs_seen_by_host();
         let slice = self
             .buffers
@@ -181,72 +832,738 @@ impl<T: SimInput> MultiplayerInputManager<T, GuestInputMgr> {
         slice.into()
     }
 
+    /// Like `get_msg_own_input_slice`, but split into several messages if it would be longer than the max payload size (see `with_max_payload_bytes`). Bundles of several local players' inputs aren't split.
+    pub fn get_msgs_own_input_slice(&self) -> Vec<MsgPayload<T>> {
+        self.split_to_max_payload(self.get_msg_own_input_slice())
+    }
+
+    /// Like `get_msg_own_input_slice`, but serializes the message straight from the buffer (as by `MsgPayload::to_bytes`), without first copying the inputs into an owned message.
+    pub fn get_msg_bytes_own_input_slice(&self) -> Vec<u8> {
+        if !self.inner.extra_local_players.is_empty() {
+            return self.get_msg_own_input_slice().to_bytes();
+        }
+        self.buffers
+            .borrow_slice_to_end_for_peer(self.own_player_num, self.num_final_inputs_seen_by_host())
+            .to_msg_bytes()
+    }
+
     /// Add a slice of inputs to the input buffer for the player
     /// with the given player_num. This is used when receiving input
     /// slice directly from a peer
-
-    pub fn rx_peer_input_slice(&mut self, player_num: PlayerNum, msg: MsgPayload<T>) {
-        if let Ok(input_slice) = msg.try_into() {
-            self.buffers
-                .receive_peer_input_slice(input_slice, player_num);
+    pub fn rx_peer_input_slice(&mut self, player_num: PlayerNum, msg: MsgPayload<T>) -> RxOutcome {
+        let Ok(mut input_slice) = msg.try_into() else {
+            return RxOutcome::rejected(RxRejection::UnexpectedMsg);
+        };
+        if !self.is_known_player(player_num) {
+            return RxOutcome::rejected(RxRejection::UnknownPlayer);
+        }
+        if self.buffers.is_removed(player_num) {
+            return RxOutcome::rejected(RxRejection::PlayerRemoved);
+        }
+        if !self.drop_muted_inputs(player_num, &mut input_slice) {
+            return RxOutcome::rejected(RxRejection::PlayerMuted);
+        }
+        if !self.drop_inputs_past_session_end(&mut input_slice) {
+            return RxOutcome::rejected(RxRejection::PastSessionEnd);
         }
+        let lead_from = self.buffers.get_num_finalized_inputs_across_peers();
+        if !self.drop_inputs_beyond_lead(player_num, &mut input_slice, lead_from) {
+            return RxOutcome::rejected(RxRejection::BeyondInputLead);
+        }
+        self.sanitize_slice(player_num, &mut input_slice);
+        self.rx_and_summarize(player_num, |buffers| {
+            buffers.receive_peer_input_slice(input_slice, player_num)
+        })
     }
 
-    pub fn rx_final_peer_input_slice_from_host(&mut self, msg: MsgPayload<T>) {
-        if let Ok(HostFinalizedSlice {
+    /// A slice for a player beyond this guest's `num_players` is held until the player is registered, raising `InputMgrEvent::UnknownPlayer` (see `unknown_player_slices`).
+    pub fn rx_final_peer_input_slice_from_host(&mut self, msg: MsgPayload<T>) -> RxOutcome {
+        let Ok(HostFinalizedSlice {
             player_num,
             host_tick,
             inputs,
         }) = msg.try_into()
-        {
-            // update the host tick if it is greater than the current host tick
-            if host_tick as i32 > self.inner.host_tick {
-                self.inner.host_tick = host_tick as i32;
+        else {
+            return RxOutcome::rejected(RxRejection::UnexpectedMsg);
+        };
+        // update the host tick if it is greater than the current host tick
+        self.observe_host_tick(signed_host_tick(host_tick));
+
+        if !self.is_known_player(player_num) {
+            if self.unknown_player_slices.hold(player_num, inputs) {
+                self.events
+                    .push(InputMgrEvent::UnknownPlayer { player_num });
+            }
+            return RxOutcome::rejected(RxRejection::UnknownPlayer);
+        }
+        self.rx_final_slice(player_num, inputs)
+    }
+
+    /// Adds players up to `num_players` (e.g. after processing a mid-session join), then applies the finalized slices held for them while they were unknown, returning the outcome of each in the order they were applied.
+    ///
+    /// Does nothing if the guest already has at least this many players.
+    pub fn register_players(&mut self, num_players: u8) -> Vec<(PlayerNum, RxOutcome)> {
+        if num_players <= self.buffers.num_players() {
+            return Vec::new();
+        }
+        self.buffers.add_players_up_to(num_players);
+        self.event_channel.add_players_up_to(num_players);
+        if let Some(chains) = self.input_chains.as_mut() {
+            chains.add_players_up_to(num_players);
+        }
+        self.unknown_player_slices
+            .take_known(num_players)
+            .into_iter()
+            .map(|(player_num, inputs)| (player_num, self.rx_final_slice(player_num, inputs)))
+            .collect()
+    }
+
+    /// Adds players up to and including `player_num` (see `register_players`).
+    pub fn add_player(&mut self, player_num: PlayerNum) -> Vec<(PlayerNum, RxOutcome)> {
+        self.register_players(u8::from(player_num).saturating_add(1))
+    }
+
+    /// Handles the host's announcement of a player added mid-session (see `add_player` on the host): adds the player, and finalizes default inputs for them up to the join, as the host did. Raises `InputMgrEvent::PlayerJoined` if this guest didn't know about the player yet.
+    ///
+    /// If this guest is the new player, its own inputs are padded with default inputs up to the join, so that the ones it collects start there.
+    pub fn rx_player_joined(&mut self, msg: MsgPayload<T>) {
+        let MsgPayload::HostToLobbyPlayerJoined(PlayerJoined {
+            player_num,
+            first_input,
+        }) = msg
+        else {
+            return;
+        };
+        let is_new = !self.is_known_player(player_num);
+        self.add_player(player_num);
+        if player_num == self.own_player_num {
+            while self.get_own_num_inputs() < first_input {
+                self.buffers.append_input(player_num, T::default());
+            }
+        }
+        let num_finalized = self.buffers.get_num_finalized_inputs(player_num);
+        if num_finalized < first_input {
+            let defaults = PlayerInputSlice {
+                start: num_finalized,
+                inputs: vec![T::default().to_bytes(); (first_input - num_finalized) as usize],
+            };
+            self.rx_final_slice(player_num, defaults);
+        }
+        if is_new {
+            self.events.push(InputMgrEvent::PlayerJoined {
+                player_num,
+                first_tick: self.buffers.tick_of_input(first_input),
+            });
+        }
+    }
+
+    /// Handles the host's removal of a player for good (see `remove_player` on the host). Once this guest has finalized the player's last inputs, it no longer waits on them, and it drops their inputs as they fall behind the snapshottable tick; their direct inputs are no longer accepted. Raises `InputMgrEvent::PlayerRemoved`, once per player.
+    pub fn rx_player_removed(&mut self, msg: MsgPayload<T>) {
+        let MsgPayload::HostToLobbyPlayerRemoved(PlayerRemoved {
+            player_num,
+            num_inputs,
+        }) = msg
+        else {
+            return;
+        };
+        if self.buffers.is_removed(player_num) {
+            return;
+        }
+        self.add_player(player_num);
+        self.buffers.remove_player(player_num, num_inputs);
+        self.after_inputs_finalized();
+        self.drop_unretained_inputs(|_| u32::MAX);
+        self.events.push(InputMgrEvent::PlayerRemoved {
+            player_num,
+            from_tick: self.buffers.tick_of_input(num_inputs),
+        });
+    }
+
+    /// The number of finalized slices held for players this guest doesn't know about yet (see `register_players`).
+    pub fn num_unknown_player_slices(&self) -> usize {
+        self.unknown_player_slices.len()
+    }
+
+    fn is_known_player(&self, player_num: PlayerNum) -> bool {
+        u8::from(player_num) < self.buffers.num_players()
+    }
+
+    fn rx_final_slice(
+        &mut self,
+        player_num: PlayerNum,
+        mut inputs: PlayerInputSlice<T>,
+    ) -> RxOutcome {
+        if !self.drop_inputs_past_session_end(&mut inputs) {
+            return RxOutcome::rejected(RxRejection::PastSessionEnd);
+        }
+        if inputs.start > self.buffers.get_num_finalized_inputs(player_num) {
+            return self.reject_gap_before_slice(player_num);
+        }
+        self.sanitize_slice(player_num, &mut inputs);
+        let outcome = self.rx_and_summarize(player_num, |buffers| {
+            buffers.receive_finalized_input_slice_for_player(inputs, player_num)
+        });
+        self.observe_session_end();
+        self.drop_unretained_inputs(|_| u32::MAX);
+        outcome
+    }
+
+    // Recovery //////////////////////////////
+
+    /// Replies to a restarted host's recovery request with this guest's finalized inputs for every player (see `host_recovery`). The reply must be sent to the host.
+    pub fn rx_recovery_request_and_reply(&self, msg: MsgPayload<T>) -> MsgPayload<T> {
+        let MsgPayload::HostToLobbyRecoveryRequest(request_id) = msg else {
+            return MsgPayload::Empty;
+        };
+        let finalized = PlayerNum::iter(self.buffers.num_players())
+            .map(|player_num| self.buffers.get_finalized_slice_for_peer(player_num, 0))
+            .collect();
+        MsgPayload::GuestToHostRecoveryResponse(RecoveryResponse {
+            request_id,
+            finalized,
+        })
+    }
+
+    // Full state //////////////////////////////
+
+    /// Saves everything needed to resume this session in a new guest process: every player's inputs and finalization count, the round, the last host tick seen, and the finalized inputs last acked to the host (see `full_state`).
+    pub fn serialize_full_state(&self) -> Result<Vec<u8>, String> {
+        FullState {
+            own_player_num: self.own_player_num,
+            ticks_per_sec: self.ticks_per_sec,
+            round: self.round,
+            buffers: self.buffers.clone(),
+            role: RoleState::Guest {
+                host_tick: self.inner.host_tick,
+                last_acked_finalized: self.inner.last_acked_finalized.clone(),
+            },
+        }
+        .to_bytes()
+    }
+
+    /// Restores a state saved by `serialize_full_state` into a freshly constructed guest, which must have the same player num and tick rate as the one that saved it.
+    pub fn deserialize_full_state(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let state = FullState::<T>::from_bytes(bytes, self.own_player_num, self.ticks_per_sec)?;
+        let RoleState::Guest {
+            host_tick,
+            last_acked_finalized,
+        } = state.role
+        else {
+            return Err(format!(
+                "full state was saved by a {}, not a guest",
+                state.role.name()
+            ));
+        };
+        let track_changed_inputs = self.buffers.tracks_changed_inputs();
+        self.buffers = state.buffers;
+        self.buffers.set_track_changed_inputs(track_changed_inputs);
+        self.round = state.round;
+        self.inner.host_tick = host_tick;
+        self.inner.last_acked_finalized = last_acked_finalized;
+        Ok(())
+    }
+
+    // Events //////////////////////////////
+
+    /// Adds one of this guest's own events, taking effect at the tick of the next input this guest collects.
+    ///
+    /// The host may move the event to a later tick if it arrives after that tick has been finalized.
+    pub fn add_own_event(&mut self, kind: u16, data: Vec<u8>) {
+        let event = TickEvent {
+            tick: self.get_own_num_inputs(),
+            kind,
+            data,
+        };
+        self.event_channel
+            .push(self.own_player_num, event, 0, false)
+            .expect("a guest's own seat is always in the lobby");
+    }
+
+    /// Gets this guest's events that the host hasn't confirmed yet, or an empty message if there are none.
+    ///
+    /// This should be sent to the host regularly (e.g. along with the guest's inputs) until the events are confirmed.
+    pub fn get_msg_own_events(&self) -> MsgPayload<T> {
+        let own = self.own_player_num;
+        let start = self.event_channel.num_confirmed(own);
+        let end = self.event_channel.num_events(own);
+        if start >= end {
+            return MsgPayload::Empty;
+        }
+        MsgPayload::GuestToHostEvents(self.event_channel.slice(own, start, end))
+    }
+
+    /// Stores events confirmed by the host, and returns how many were new to this guest.
+    ///
+    /// Events for a player beyond this guest's `num_players` are rejected as `RxRejection::UnknownPlayer`.
+    pub fn rx_events_from_host(&mut self, msg: MsgPayload<T>) -> Result<u32, RxRejection> {
+        let MsgPayload::HostToLobbyEvents(slice) = msg else {
+            return Err(RxRejection::UnexpectedMsg);
+        };
+        self.event_channel.receive_confirmed(slice)
+    }
+
+    /// Gets the ack msg that guests send to the host upon receiving events.
+    pub fn get_msg_ack_events(&self) -> MsgPayload<T> {
+        MsgPayload::GuestToHostAckEvents(
+            PlayerNum::iter(self.buffers.num_players())
+                .map(|player_num| self.event_channel.num_confirmed(player_num))
+                .collect(),
+        )
+    }
+
+    /// Stores annotations from the host, and returns how many were new to this guest.
+    pub fn rx_annotations_from_host(&mut self, msg: MsgPayload<T>) -> u32 {
+        match msg {
+            MsgPayload::HostToLobbyAnnotations(slice) if !slice.player_num.is_guest() => {
+                self.annotations.receive_confirmed(slice).unwrap_or(0)
             }
+            _ => 0,
+        }
+    }
 
-            self.buffers
-                .receive_finalized_input_slice_for_player(inputs, player_num);
+    /// Gets the ack msg that guests send to the host upon receiving annotations.
+    pub fn get_msg_ack_annotations(&self) -> MsgPayload<T> {
+        MsgPayload::GuestToHostAckAnnotations(self.get_num_annotations())
+    }
+
+    /// Stores a seed change from the host, returning whether it was new to this guest.
+    ///
+    /// Changes are stored in the order the host made them; a change that arrives before the one preceding it is ignored, and picked up when the host resends it.
+    pub fn rx_seed_from_host(&mut self, msg: MsgPayload<T>) -> bool {
+        match msg {
+            MsgPayload::HostToLobbySeed(change) => self.seeds.receive(change),
+            _ => false,
         }
     }
 
-    pub fn rx_pre_sim_sync(&mut self, msg: MsgPayload<T>) {
+    /// Gets the ack msg that guests send to the host upon receiving a seed change.
+    pub fn get_msg_ack_seeds(&self) -> MsgPayload<T> {
+        MsgPayload::GuestToHostAckSeeds(self.get_num_seed_changes())
+    }
+
+    // Session end //////////////////////////////
+
+    /// Adopts the host's final tick for the session (see `end_session_at` on the host), returning whether it was new to this guest.
+    ///
+    /// No more own inputs are collected for ticks at or past the final tick, and `InputMgrEvent::SessionEnded` is raised once every player's inputs are finalized up to it. Resent messages are ignored.
+    pub fn rx_end_session(&mut self, msg: MsgPayload<T>) -> bool {
+        let MsgPayload::HostToLobbyEndSession(final_tick) = msg else {
+            return false;
+        };
+        if self.session_limit.end_requested {
+            return false;
+        }
+        self.session_limit.max_ticks = final_tick.min(MAX_SESSION_TICKS);
+        self.session_limit.end_requested = true;
+        self.observe_session_end();
+        true
+    }
+
+    /// Gets the ack msg that guests send to the host upon receiving the end of the session, or an empty message if it hasn't arrived.
+    pub fn get_msg_end_ack(&self) -> MsgPayload<T> {
+        if self.session_limit.end_requested {
+            MsgPayload::GuestToHostEndAck(self.session_limit.max_ticks)
+        } else {
+            MsgPayload::Empty
+        }
+    }
+
+    /// Counts down `ticks_until_start`; `delta` is the time (sec) since the last call.
+    pub fn advance_lobby_time(&mut self, delta: f32) {
+        if let Some(countdown) = &mut self.inner.start_countdown {
+            countdown.remaining_sec -= delta;
+        }
+    }
+
+    /// The seconds a message from the host is estimated to spend in transit: half the RTT, or 0 until an RTT has been observed.
+    fn one_way_sec_from_host(&self) -> f32 {
+        self.inner.rtt_ms_to_host.value().unwrap_or(0.0) / 2000.0
+    }
+
+    /// Handles the host's proposal of a start tick (see `start_sync`), returning the ack to send back to the host with this guest's RTT-adjusted estimate of the ticks left until it.
+    ///
+    /// Proposals are ignored (producing an empty message) once a start has been confirmed.
+    pub fn rx_start_proposal_and_reply(&mut self, msg: MsgPayload<T>) -> MsgPayload<T> {
+        let MsgPayload::HostToLobbyStartProposal(proposal) = msg else {
+            return MsgPayload::Empty;
+        };
+        if self
+            .inner
+            .start_countdown
+            .is_some_and(|countdown| countdown.confirmed)
+        {
+            return MsgPayload::Empty;
+        }
+        let countdown = GuestStartCountdown {
+            proposal_num: proposal.proposal_num,
+            remaining_sec: proposal
+                .remaining_sec_on_arrival(self.inputs_per_sec(), self.one_way_sec_from_host()),
+            confirmed: false,
+        };
+        self.inner.start_countdown = Some(countdown);
+        MsgPayload::GuestToHostStartAck(StartAck {
+            proposal_num: countdown.proposal_num,
+            ticks_until_start: countdown.ticks_until_start(self.inputs_per_sec()),
+        })
+    }
+
+    /// Handles the host's confirmation of the start tick (see `start_sync`), re-estimating the time left until it from this confirmation's lobby tick.
+    ///
+    /// Until inputs are collected, the countdown is also adopted as this guest's (negative) host tick, moving it to `SessionPhase::Countdown`.
+    pub fn rx_start_confirmed(&mut self, msg: MsgPayload<T>) {
+        let MsgPayload::HostToLobbyStartConfirmed(start) = msg else {
+            return;
+        };
+        let countdown = GuestStartCountdown {
+            proposal_num: start.proposal_num,
+            remaining_sec: start
+                .remaining_sec_on_arrival(self.inputs_per_sec(), self.one_way_sec_from_host()),
+            confirmed: true,
+        };
+        self.inner.start_countdown = Some(countdown);
+        if self.buffers.is_empty() {
+            let ticks_until_start = countdown.ticks_until_start(self.inputs_per_sec());
+            self.observe_host_tick(-ticks_until_start.max(0));
+        }
+    }
+
+    /// The ticks left until the start confirmed by the host, estimated from its confirmation and counted down by `advance_lobby_time`; negative once it has passed, and `None` until a confirmation arrives.
+    pub fn ticks_until_start(&self) -> Option<i32> {
+        self.inner
+            .start_countdown
+            .filter(|countdown| countdown.confirmed)
+            .map(|countdown| countdown.ticks_until_start(self.inputs_per_sec()))
+    }
+
+    /// Handles the host's countdown to the start of the sim.
+    ///
+    /// The host's session config (start tick, initial seed, sim ticks per input, tick rate and session length) is adopted as long as no inputs have been collected yet; once the timeline has started, it can't be changed. The seats' display metadata (see `player_metadata`) is always replaced by the host's.
+    ///
+    /// A countdown from a host whose input schema id differs from this guest's is rejected (see `input_schema`): neither the countdown nor the config is adopted. Otherwise, the capabilities the host advertises are negotiated (see `capabilities`), and the guest should reply with `get_msg_input_schema` and `get_msg_capabilities`.
+    pub fn rx_pre_sim_sync(&mut self, msg: MsgPayload<T>) -> Result<(), InputSchemaMismatch> {
         if let Ok(PreSimSync {
             host_tick_countdown,
+            start_tick,
+            initial_seed,
+            sim_ticks_per_input,
+            ticks_per_sec,
+            max_session_ticks,
+            input_schema_id,
+            capabilities,
+            player_metadata,
             ..
         }) = msg.try_into()
         {
-            self.inner.host_tick = -(host_tick_countdown as i32);
+            self.check_input_schema(HOST_PLAYER_NUM, input_schema_id)?;
+            self.negotiate_capabilities(HOST_PLAYER_NUM, capabilities);
+            self.player_metadata = player_metadata
+                .into_iter()
+                .filter(|(player_num, metadata)| {
+                    self.validate_player_metadata(*player_num, metadata).is_ok()
+                })
+                .collect();
+            let was_synced = self.is_synced();
+            self.inner.host_tick = Some(-(host_tick_countdown as i32));
+            self.raise_event_if_newly_synced(was_synced);
+            if self.buffers.is_empty() {
+                self.buffers.set_start_tick(start_tick);
+                self.seeds.initial = initial_seed;
+                self.buffers.set_sim_ticks_per_input(sim_ticks_per_input);
+                self.ticks_per_sec = ticks_per_sec.max(1);
+                self.session_limit.max_ticks = max_session_ticks.min(MAX_SESSION_TICKS);
+            }
         }
+        Ok(())
     }
 
-    pub fn rx_host_pong_and_reply(&mut self, msg: MsgPayload<T>) -> MsgPayload<T> {
-        if let MsgPayload::HostToGuestPong(ping_id) = msg {
-            let rtt = self.inner.pings.observe_pong(ping_id);
-            self.observe_rtt_ms_to_host(rtt);
-            MsgPayload::GuestToHostPongPong(ping_id)
-        } else {
-            panic!("Expected HostPong");
+    /// Gets this guest's input schema id for the host to check (see `input_schema`).
+    ///
+    /// This message should be sent to the host in reply to the `PreSimSync`.
+    pub fn get_msg_input_schema(&self) -> MsgPayload<T> {
+        MsgPayload::GuestToHostInputSchema(T::SCHEMA_ID)
+    }
+
+    /// Gets the capabilities this guest negotiated with the host from its `PreSimSync`, for the host to store (see `capabilities`).
+    ///
+    /// This message should be sent to the host in reply to the `PreSimSync`.
+    pub fn get_msg_capabilities(&self) -> MsgPayload<T> {
+        MsgPayload::GuestToHostCapabilities(self.negotiated_capabilities(HOST_PLAYER_NUM))
+    }
+
+    /// Gets hashes of every player's finalized inputs for the ticks finalized since the last report (at most `MAX_INPUT_HASH_REPORT_TICKS`), for the host to check against its own (see `desync_report`). Empty if no tick has been finalized since.
+    ///
+    /// This message should be sent to the host periodically, e.g. alongside acks.
+    pub fn get_msg_input_hash_report(&mut self) -> MsgPayload<T> {
+        let start_tick = self.inner.next_input_hash_tick.max(self.start_tick());
+        let end_tick = self
+            .get_snapshottable_sim_tick()
+            .min(start_tick + MAX_INPUT_HASH_REPORT_TICKS);
+        if end_tick <= start_tick {
+            return MsgPayload::Empty;
         }
+        self.inner.next_input_hash_tick = end_tick;
+        MsgPayload::GuestToHostInputHashReport(InputHashReport {
+            start_tick,
+            hashes: (start_tick..end_tick)
+                .map(|tick| self.tick_input_digest(tick))
+                .collect(),
+        })
+    }
+
+    /// Measures the RTT to the host from a pong, returning the pong-pong that completes the host's ping cycle.
+    ///
+    /// Samples are floored at the 10us minimum `observe_rtt_ms_to_host` accepts, since a pong over a local link can arrive sooner.
+    ///
+    /// Returns an error for anything but a `HostToGuestPong`, or for a pong to a ping this guest has no record of (e.g. a duplicate).
+    pub fn rx_host_pong_and_reply(&mut self, msg: MsgPayload<T>) -> Result<MsgPayload<T>, String> {
+        let MsgPayload::HostToGuestPong(ping_id) = msg else {
+            return Err("fn rx_host_pong_and_reply can only handle HostToGuestPong message".into());
+        };
+        let Some(rtt) = self.inner.pings.observe_pong(ping_id) else {
+            return Err(format!(
+                "rx_host_pong_and_reply ping id {ping_id} not found"
+            ));
+        };
+        self.observe_rtt_ms_to_host(rtt.max(0.01));
+        let guest_rtt_ms = self.get_rtt_ms_to_host().expect("an RTT was just observed");
+        Ok(MsgPayload::GuestToHostPongPong(PongPong {
+            ping_id,
+            guest_rtt_micros: ms_to_micros(guest_rtt_ms) as u32,
+        }))
+    }
+
+    /// Folds the host's reply to a clock ping into the host clock offset, taking this guest's last observed local time as the time it arrived. Returns false if the pong was discarded: if no local time has been observed, or if its round trip was negative or much slower than the fastest seen (see `CLOCK_SYNC_ROUND_TRIP_SLACK_MICROS`).
+    pub fn rx_clock_pong(&mut self, msg: MsgPayload<T>) -> bool {
+        let MsgPayload::HostToGuestClockPong(pong) = msg else {
+            return false;
+        };
+        let Some(guest_recv_micros) = self.inner.local_time_micros else {
+            return false;
+        };
+        let sample = ClockSample {
+            guest_send_micros: pong.guest_send_micros,
+            host_micros: pong.host_micros,
+            guest_recv_micros,
+        };
+        let accepted = self
+            .inner
+            .clock_sync
+            .observe(sample, self.rtt_config.smoothing);
+        if accepted {
+            self.change_counters.rtt += 1;
+        }
+        accepted
+    }
+
+    /// Records the host's ping report, which adjusts this guest's one-way latency estimate (see `one_way_in_micro_ticks`).
+    pub fn rx_ping_report(&mut self, msg: MsgPayload<T>) {
+        if let MsgPayload::HostToGuestPingReport(report) = msg {
+            self.inner.ping_report = Some(report);
+            self.change_counters.rtt += 1;
+        }
+    }
+
+    /// Archives the current round's buffers and starts a new round, with the host tick reset to 0. RTT and ping state carry over, but the host clock offset restarts with the host's sim clock.
+    ///
+    /// This is normally triggered by `rx_round_transition_and_reply`; the returned ack must be sent to the host.
+    pub fn start_new_round(&mut self) -> MsgPayload<T> {
+        self.archive_round();
+        self.inner.host_tick = Some(0);
+        // finalized counts restart with the round
+        self.inner.last_acked_finalized = PeerwiseFinalizedInputsSeen::default();
+        self.inner.next_input_hash_tick = 0;
+        self.inner.clock_sync = ClockSync::default();
+        self.change_counters.observations += 1;
+        MsgPayload::GuestToHostRoundTransitionAck(self.round)
+    }
+
+    /// Reinitializes this guest's buffers mid-round, e.g. after they were lost or corrupted, keeping the start tick, players and removals. Finalized and own inputs are dropped, and the finalized inputs acked to the host restart from zero.
+    ///
+    /// The returned message must be sent to the host, which then forgets this guest's acks and resends every finalized input it still retains. Without it the host, which never lets acks go backwards, would keep sending only inputs past the old acks.
+    pub fn reset_buffers(&mut self) -> MsgPayload<T> {
+        let mut fresh = self.buffers.new_empty_like();
+        fresh.set_start_tick(self.buffers.start_tick());
+        for player_num in PlayerNum::iter(self.buffers.num_players()) {
+            if let Some(end) = self.buffers.removed_player_end(player_num) {
+                fresh.remove_player(player_num, end);
+            }
+        }
+        self.buffers = fresh;
+        self.inner.last_acked_finalized = PeerwiseFinalizedInputsSeen::default();
+        self.change_counters.observations += 1;
+        MsgPayload::GuestToHostBufferReset(self.round)
+    }
+
+    /// Handles the host's announcement of a new round, starting it locally and returning the ack to send back to the host.
+    ///
+    /// Announcements for rounds this guest has already started are ignored, and produce an empty message.
+    pub fn rx_round_transition_and_reply(&mut self, msg: MsgPayload<T>) -> MsgPayload<T> {
+        match msg {
+            MsgPayload::HostToLobbyRoundTransition(round) if round > self.round => {
+                // archive an empty round for any rounds we missed entirely,
+                // so that archive indices keep matching round indices
+                while self.round + 1 < round {
+                    self.archive_round();
+                }
+                self.start_new_round()
+            }
+            _ => MsgPayload::Empty,
+        }
+    }
+
+    /// Handles the host's announcement that a player has been muted, after which that player's inputs sent directly to this guest are ignored beyond the tick the host muted them at.
+    ///
+    /// Finalized slices from the host are still accepted for the muted player, since they carry the default inputs the host fills their seat with.
+    pub fn rx_player_muted(&mut self, msg: MsgPayload<T>) {
+        if let MsgPayload::HostToLobbyPlayerMuted(PlayerMuted {
+            player_num,
+            first_ignored_input,
+        }) = msg
+        {
+            let previous = self.muted_players.insert(player_num, first_ignored_input);
+            if previous != Some(first_ignored_input) {
+                self.events.push(InputMgrEvent::PlayerMuted {
+                    player_num,
+                    from_tick: self.buffers.tick_of_input(first_ignored_input),
+                });
+            }
+        }
+    }
+
+    /// Handles the host's announcement that a seat has moved to a new connection (see `transfer_seat` on the host), after which that seat's inputs are accepted directly from the new connection only.
+    ///
+    /// If the seat is one of this guest's local players (its own seat, or one added with `new_with_local_players`), its inputs are padded with default inputs up to the transfer's first input, so that the ones it collects continue the seat's history; the host's finalized slices then fill in the seat's real inputs.
+    pub fn rx_seat_transfer(&mut self, msg: MsgPayload<T>) {
+        let MsgPayload::HostToLobbySeatTransferred(SeatTransfer {
+            seat,
+            connection,
+            first_input,
+        }) = msg
+        else {
+            return;
+        };
+        if self.connection_for_seat(seat) == connection {
+            return;
+        }
+        self.move_seat(seat, connection);
+        if self.is_local_player(seat) {
+            while self.buffers.get_num_inputs(seat) < first_input {
+                self.buffers.append_input(seat, T::default());
+            }
+        }
+    }
+
+    /// Records the clock skew the host has measured for this guest.
+    pub fn rx_host_rate_adjust(&mut self, msg: MsgPayload<T>) {
+        if let MsgPayload::HostToGuestRateAdjust(skew_ppm) = msg {
+            self.inner.host_reported_skew_ppm = skew_ppm;
+        }
+    }
+
+    /// The most recent clock skew (ppm) reported by the host; 0 until one arrives.
+    pub fn get_host_reported_skew_ppm(&self) -> i32 {
+        self.inner.host_reported_skew_ppm
+    }
+
+    /// The tick rate the game should run its input loop at to cancel out the skew reported by the host.
+    ///
+    /// E.g. if the host reports the guest running 1000ppm slow, this is 0.1% faster than `ticks_per_sec`. Once a clock pong has been accepted, `num_inputs_needed` already paces at this rate, by correcting the host clock estimate for the skew.
+    pub fn paced_ticks_per_sec(&self) -> f32 {
+        self.ticks_per_sec as f32 * (1.0 - self.inner.host_reported_skew_ppm as f32 / 1_000_000.0)
     }
 
     /// Gets the ack msg that guests send to the host upon receiving
     /// a finalized input slice.
     pub fn get_msg_ack_finalization(&mut self) -> MsgPayload<T> {
-        let finalized_ticks = self.buffers.get_peerwise_finalized_inputs();
-        MsgPayload::GuestToHostAckFinalization(finalized_ticks).into()
+        self.buffers
+            .get_peerwise_finalized_inputs_into(&mut self.inner.last_acked_finalized);
+        self.inner.time_since_ack_sec = 0.0;
+        self.change_counters.observations += 1;
+        MsgPayload::GuestToHostAckFinalization(self.inner.last_acked_finalized.clone())
+    }
+
+    /// Gets the finalization ack if one is due, or an empty message otherwise, so that ack traffic scales with how much the host is finalizing rather than with the frame rate.
+    ///
+    /// `delta` is the time (sec) since the last call. An ack is due once `ack_min_new_inputs` inputs have been finalized (summed over all players) since the last ack was sent, or once `ack_max_interval_sec` has passed since it, so that a lost ack is eventually repeated.
+    pub fn maybe_get_msg_ack(&mut self, delta: f32) -> MsgPayload<T> {
+        self.inner.time_since_ack_sec += delta;
+        let num_new_inputs: u32 = PlayerNum::iter(self.buffers.num_players())
+            .map(|player_num| {
+                self.buffers
+                    .get_num_finalized_inputs(player_num)
+                    .saturating_sub(self.inner.last_acked_finalized.get(player_num))
+            })
+            .sum();
+        if num_new_inputs >= self.inner.ack_min_new_inputs
+            || self.inner.time_since_ack_sec >= self.inner.ack_max_interval_sec
+        {
+            self.get_msg_ack_finalization()
+        } else {
+            MsgPayload::Empty
+        }
+    }
+
+    /// A clock ping stamped with this guest's local time (see `observe_local_time_ms`); `MsgPayload::Empty` until a local time has been observed.
+    pub fn get_msg_clock_ping(&self) -> MsgPayload<T> {
+        match self.inner.local_time_micros {
+            Some(guest_send_micros) => {
+                MsgPayload::GuestToHostClockPing(ClockPing { guest_send_micros })
+            }
+            None => MsgPayload::Empty,
+        }
     }
 
     pub fn get_msg_guest_ping(&mut self) -> MsgPayload<T> {
         let ping_id = self.inner.pings.send_next_ping();
-        MsgPayload::GuestToHostPing(ping_id).into()
+        MsgPayload::GuestToHostPing(ping_id)
+    }
+
+    /// Bundles everything this guest sends the host in a frame into one message (see `MsgPayload::batch`): its own input slice, the finalization ack if one is due (see `maybe_get_msg_ack`), and a ping and clock ping every `ping_interval_sec`, starting with the first call.
+    ///
+    /// `delta` is the time (sec) since the last call. Use this instead of the separate `get_msg_*` calls, not alongside them.
+    pub fn get_outgoing_msgs_for_frame(&mut self, delta: f32) -> MsgPayload<T> {
+        let mut msgs = vec![
+            self.get_msg_own_input_slice(),
+            self.maybe_get_msg_ack(delta),
+        ];
+        let since_ping = self
+            .inner
+            .time_since_ping_sec
+            .map_or(f32::INFINITY, |since| since + delta);
+        if since_ping >= self.inner.ping_interval_sec {
+            msgs.push(self.get_msg_guest_ping());
+            msgs.push(self.get_msg_clock_ping());
+            self.inner.time_since_ping_sec = Some(0.0);
+        } else {
+            self.inner.time_since_ping_sec = Some(since_ping);
+        }
+        MsgPayload::batch(msgs)
+    }
+
+    /// Like `get_outgoing_msgs_for_frame`, but as several batches if one would be longer than the max payload size (see `with_max_payload_bytes`), with an oversized input slice split.
+    pub fn get_outgoing_batches_for_frame(&mut self, delta: f32) -> Vec<MsgPayload<T>> {
+        let batch = self.get_outgoing_msgs_for_frame(delta);
+        self.split_to_max_payload(batch)
+    }
+
+    // info and debug //////////////////////////////
+
+    /// Builds a status-level summary of this guest's buffers.
+    pub fn debug_dump(&self) -> DebugDump {
+        DebugDump::new_from_buffers(DumpRole::Guest, self.own_player_num, &self.buffers, vec![])
+    }
+
+    /// A multi-line, human-readable summary of this guest's state, suitable for pasting into bug reports.
+    ///
+    /// The text can be parsed back into a `DebugDump`.
+    pub fn dump_debug_text(&self) -> String {
+        self.debug_dump().to_string()
     }
 }
 
 #[cfg(test)]
 impl<T: SimInput> MultiplayerInputManager<T, GuestInputMgr> {
     pub(crate) fn test_advance_host_tick(&mut self, host_tick: i32) {
-        if host_tick > self.inner.host_tick {
-            self.inner.host_tick = host_tick;
-        }
+        self.observe_host_tick(host_tick);
     }
 }
@@ -1,19 +1,120 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 
 use crate::{
-    ewma::Ewma, finalized_observations_per_guest::FinalizedObservationsPerGuest,
+    bandwidth_budget::BandwidthBudget,
+    capabilities::Capabilities,
+    change_stamps::ChangeCounters,
+    clock_sync::ClockPong,
+    debug_dump::{DebugDump, DumpRole},
+    decode_stats::DecodeStats,
+    desync_report::{DesyncReport, DesyncTracker},
+    determinism_probe::DeterminismProbe,
+    event_channel::{EventChannel, MAX_PENDING_EVENTS_PER_PLAYER, TickEvent},
+    events::{EventQueue, InputMgrEvent},
+    finalization_watch::FinalizationWatchers,
+    finalized_input_channel::{DEFAULT_FINALIZED_INPUT_RX_DEPTH, FinalizedInputTxs},
+    finalized_observations_per_guest::{FinalizedObservationsPerGuest, ObservationBlocker},
+    full_state::{FullState, RoleState},
+    health_score::{HealthComponents, HealthScore, PeerHealth},
+    host_recovery::HostRecovery,
+    input_hash_chain::{INPUT_CHAIN_SEED, fnv1a_64},
+    input_rate::InputArrivalRate,
+    input_schedule::InputSchedule,
+    input_schema::InputSchemaMismatch,
+    input_staging::InputStagingQueue,
     input_trait::SimInput,
+    latency_stats::{LatencyHistogram, LatencySummary},
+    msg_dedup::{MsgDedupCache, SentMsgKey},
+    msg_sequence::MsgSequences,
+    net_stats::NetStats,
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    player_metadata::PlayerMetadataError,
+    reliable_delivery::ReliableDelivery,
+    rollback_depth::RollbackDepthTracker,
+    rtt::{PingReport, RttConfig, RttEstimate, RttSummary, ms_to_micros},
+    rx_outcome::{RxOutcome, RxRejection},
+    seed_schedule::SeedSchedule,
+    session::{
+        OutgoingMsgs, Recipient, SessionPhase, SessionRxError, push_replies, without_empty_msgs,
+    },
+    session_limit::SessionLimit,
+    slice_throttle::{AdaptiveSliceConfig, SliceRedundancy, SliceThrottle},
+    start_sync::StartAgreement,
+    tick_consumption::TickConsumption,
+    unknown_player_slices::UnknownPlayerSlices,
 };
 
 use super::{
-    input_messages::{HostFinalizedSlice, MsgPayload},
+    input_messages::{
+        HostFinalizedSlice, HostFinalizedSliceRef, MsgPayload, PlayerJoined, PlayerMuted,
+        PlayerRemoved, PreSimSync, SeatTransfer, to_bincode_bytes,
+    },
     multiplayer_input_buffer::MultiplayerInputBuffers,
-    multiplayer_input_manager::MultiplayerInputManager,
-    util_types::PlayerNum,
+    multiplayer_input_manager::{DEFAULT_MAX_INPUT_LEAD, MultiplayerInputManager},
+    util_types::{PlayerInputSlice, PlayerNum},
 };
 
 pub(super) const HOST_PLAYER_NUM: PlayerNum = PlayerNum(0);
 
+/// Guests whose estimated clock skew is smaller than this (in ppm) are not sent rate adjustments by default.
+pub(crate) const DEFAULT_RATE_ADJUST_THRESHOLD_PPM: u32 = 2_000;
+
+/// The fraction of an input by which the host's elapsed sim time may exceed a whole number of inputs without another input being needed.
+const SIM_TIME_TOLERANCE_INPUTS: f64 = 1e-4;
+
+/// By default, each message in a `sync_plan_for` carries at most this many inputs, keeping messages of small inputs within a typical MTU.
+pub(crate) const DEFAULT_MAX_INPUTS_PER_SYNC_MSG: u32 = 256;
+
+/// By default, `poll_catch_up` checks each guest this often (sec of host time).
+pub(crate) const DEFAULT_CATCH_UP_CHECK_INTERVAL_SEC: f32 = 0.1;
+
+/// By default, `poll_catch_up` only catches up a guest once it has fallen at least this many ticks past `max_guest_ticks_behind`.
+pub(crate) const DEFAULT_CATCH_UP_HYSTERESIS_TICKS: u32 = 3;
+
+/// By default, `recommended_countdown_ticks` adds this many ticks on top of the worst guest RTT.
+pub(crate) const DEFAULT_COUNTDOWN_MARGIN_TICKS: u32 = 3;
+
+/// A guest's lobby status, for deciding when to start the pre-sim countdown (see `lobby_readiness`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LobbyPeerStatus {
+    /// The smoothed RTT (ms) to this guest; `None` until a ping round trip has completed.
+    pub rtt_ms: Option<f32>,
+    /// The number of completed ping round trips (ping, pong, pong-pong) with this guest.
+    pub pings_exchanged: u32,
+    /// The lobby time (ms) since this guest's last ping or pong-pong arrived; `None` if nothing has arrived yet.
+    pub last_seen_ms: Option<f32>,
+}
+
+/// The inputs in flight between the host and one guest, for telling whether latency is on the upstream or downstream path (see `inputs_in_flight_by_guest`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InputsInFlight {
+    /// Inputs the host has received from this guest but not yet finalized, i.e. those held in the review window (see `with_finalization_delay_ticks`). Inputs still on the wire are only known to the guest; see `num_own_inputs_in_flight` on the guest.
+    pub upstream: u32,
+    /// Finalized inputs, summed over every peer (including the host), that the host has broadcast but this guest hasn't acked.
+    pub downstream: u32,
+}
+
+/// When the host gives up on a chronically lagging guest (see `with_lag_downgrade_policy`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LagDowngradePolicy {
+    /// A guest is lagging while the host's tick is more than this many sim ticks past the inputs received from it.
+    pub max_ticks_behind: u32,
+    /// A guest that keeps lagging for this long (sec of host time) is downgraded.
+    pub sustained_sec: f32,
+}
+
+/// Which version of a guest input held for review the host keeps when the guest resends it with a different value (see `with_resend_policy`).
+///
+/// Either way, guests only ever see the version the host finalizes, so every peer converges on the same finalized inputs; the policy decides which version that is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResendPolicy {
+    /// Keep the first version received. The version finalized depends on which slice arrives first.
+    #[default]
+    FirstWriteWins,
+    /// Keep the version from the newest slice, ordering slices by their end (the guest's input count when it sent them, which only grows). The version finalized doesn't depend on arrival order, unless two slices with the same end disagree, in which case the first received is kept.
+    LastWriteWins,
+}
+
 #[derive(Default)]
 /// A struct to keep track of the times at which pongs are sent and replies are received.
 struct PongSendTimes {
@@ -52,7 +153,18 @@ pub struct HostInputMgr {
     pub(super) max_guest_ticks_behind: u32,
 
     pong_send_times: HashMap<PlayerNum, PongSendTimes>,
-    rtts: HashMap<PlayerNum, Ewma>,
+    rtts: HashMap<PlayerNum, RttEstimate>,
+    /// Each guest's own smoothed RTT (whole microseconds), as reported in its last pong-pong.
+    guest_reported_rtts: HashMap<PlayerNum, u32>,
+
+    /// The time (sec) passed to `advance_lobby_time` so far.
+    lobby_time: f32,
+    /// The lobby time (sec) at which each guest's last ping or pong-pong arrived.
+    last_seen_lobby_times: HashMap<PlayerNum, f32>,
+
+    /// CONFIG SETTING
+    /// The number of ticks `recommended_countdown_ticks` adds on top of the worst guest RTT.
+    countdown_margin_ticks: u32,
 
     /// A list of players that have disconnected.
     ///
@@ -60,7 +172,107 @@ pub struct HostInputMgr {
     disconnected_players: Vec<PlayerNum>,
 
     /// The time since the simulation started, in seconds.
-    sim_time: f32,
+    ///
+    /// This is accumulated in f64: summing a 60hz frame time into an f32 drifts by several seconds over a long session.
+    sim_time: f64,
+
+    /// How quickly new inputs arrive from each guest, measured against `sim_time`.
+    input_rates: HashMap<PlayerNum, InputArrivalRate>,
+
+    /// CONFIG SETTING
+    /// The minimum estimated clock skew (ppm) for which the host will send a guest a rate adjustment.
+    rate_adjust_threshold_ppm: u32,
+
+    /// CONFIG SETTING
+    /// How often (sec) `poll_catch_up` checks whether each guest needs catching up.
+    catch_up_check_interval_sec: f32,
+
+    /// CONFIG SETTING
+    /// How many ticks past `max_guest_ticks_behind` a connected guest must fall before `poll_catch_up` catches it up, so that a guest hovering right at the threshold isn't sent a tiny catch-up slice on every check.
+    catch_up_hysteresis_ticks: u32,
+
+    /// The time (sec) since `poll_catch_up` last checked each guest.
+    catch_up_timers: HashMap<PlayerNum, f32>,
+
+    /// CONFIG SETTING
+    /// How long (sec of sim time) a guest can go without acking finalized inputs before its acks stop holding back the start of broadcast slices; `None` (the default) never times out.
+    observation_staleness_timeout_sec: Option<f32>,
+    /// The sim time at which each guest last acked finalized inputs; a guest that never acked counts as acking at 0.
+    last_ack_sim_times: HashMap<PlayerNum, f64>,
+
+    /// CONFIG SETTING
+    /// When `poll_lagging_guests` downgrades a guest; `None` (the default) never downgrades.
+    lag_downgrade_policy: Option<LagDowngradePolicy>,
+    /// The time (sec) each guest has been lagging without a break, for `poll_lagging_guests`.
+    lagging_times: HashMap<PlayerNum, f32>,
+
+    /// Each guest's ping loss, stalls and last reported health score (see `poll_health_scores`).
+    health: HashMap<PlayerNum, PeerHealth>,
+
+    /// CONFIG SETTING
+    /// The number of ticks between the host collecting one of its own inputs and that input taking effect.
+    ///
+    /// Inputs collected within the delay are held in the host's buffer as non-final inputs, and can be shared with guests early via `get_msg_provisional_own_inputs`.
+    input_delay_ticks: u32,
+
+    /// CONFIG SETTING
+    /// The maximum number of inputs in each message of a `sync_plan_for`.
+    max_inputs_per_sync_msg: u32,
+
+    /// CONFIG SETTING
+    /// The maximum number of finalized ticks the host sends a guest past what it has acked, per player (see `get_msgs_windowed_finalized_slices`); `None` for no limit.
+    send_window_ticks: Option<u32>,
+
+    /// For each guest, the number of confirmed events it has received for each player (indexed by player num).
+    guests_events_seen: HashMap<PlayerNum, Vec<u32>>,
+    /// For each guest, the number of the host's annotations it has received.
+    guests_annotations_seen: HashMap<PlayerNum, u32>,
+    /// For each guest, the number of the host's seed changes it has received.
+    guests_seeds_seen: HashMap<PlayerNum, u32>,
+    /// Guests that have acked the end of the session (see `end_session_at`).
+    guests_acked_end: Vec<PlayerNum>,
+
+    /// For each player, how many ticks after their own tick the host finalized their inputs.
+    ///
+    /// These are kept for the whole match, across rounds.
+    finalization_latencies: HashMap<PlayerNum, LatencyHistogram>,
+
+    /// Guests that have not yet acked the current round.
+    ///
+    /// Inputs and acks from these guests are ignored, since they may still refer to the previous round.
+    guests_pending_round_ack: Vec<PlayerNum>,
+
+    /// True once the `PreSimSync` has been built, starting the countdown (see `session_phase`).
+    pre_sim_sync_sent: bool,
+    /// The host's side of the agreement on the lobby tick at which the sim starts (see `start_sync`).
+    start_agreement: StartAgreement,
+
+    /// CONFIG SETTING
+    /// The number of host ticks a guest's inputs are held as provisional before the host finalizes them (see `with_finalization_delay_ticks`).
+    finalization_delay_ticks: u32,
+    /// For each guest, the inputs held for review: the host tick at which they are due to be finalized, and the number of the guest's inputs finalized then, oldest first.
+    pending_review: HashMap<PlayerNum, VecDeque<(u32, u32)>>,
+    /// CONFIG SETTING
+    /// Which version of a resent input held for review is kept (see `with_resend_policy`)
+    resend_policy: ResendPolicy,
+    /// For each guest, the end of the newest slice held for review (see `ResendPolicy::LastWriteWins`)
+    newest_slice_ends: HashMap<PlayerNum, u32>,
+    /// For each guest, the indices of the inputs held for review that the host has replaced (see `replace_input_pending_review`)
+    replaced_pending_review: HashMap<PlayerNum, BTreeSet<u32>>,
+
+    /// Messages recently sent to each guest, for suppressing duplicates (see `with_msg_dedup_window_sec`)
+    msg_dedup: MsgDedupCache,
+
+    /// For each guest, the end of the furthest finalized slice of each player recorded as sent to it, indexed by player num (see `record_msg_sent`); `None` for a player none of whose slices have been recorded, whose acks are then not checked
+    finalized_sent: HashMap<PlayerNum, Vec<Option<u32>>>,
+    /// Each guest's redundant finalized inputs, and the pacing of its adaptive slices (see `with_adaptive_finalized_slices`)
+    slice_throttle: SliceThrottle,
+
+    /// The ticks at which each guest's input hashes disagreed with the host's (see `rx_input_hash_report`)
+    desyncs: DesyncTracker,
+
+    /// The finalized inputs each spectator connection has acked, kept apart from the guests' observations so that spectators never hold back finalization (see `add_spectator`)
+    spectators: BTreeMap<PlayerNum, PeerwiseFinalizedInputsSeen>,
 }
 
 impl HostInputMgr {
@@ -70,8 +282,43 @@ impl HostInputMgr {
             max_guest_ticks_behind,
             pong_send_times: HashMap::default(),
             rtts: HashMap::default(),
+            guest_reported_rtts: HashMap::default(),
+            lobby_time: 0.0,
+            last_seen_lobby_times: HashMap::default(),
+            countdown_margin_ticks: DEFAULT_COUNTDOWN_MARGIN_TICKS,
             disconnected_players: Vec::default(),
             sim_time: 0.0,
+            input_rates: HashMap::default(),
+            rate_adjust_threshold_ppm: DEFAULT_RATE_ADJUST_THRESHOLD_PPM,
+            guests_pending_round_ack: Vec::default(),
+            pre_sim_sync_sent: false,
+            start_agreement: StartAgreement::default(),
+            input_delay_ticks: 0,
+            catch_up_check_interval_sec: DEFAULT_CATCH_UP_CHECK_INTERVAL_SEC,
+            catch_up_hysteresis_ticks: DEFAULT_CATCH_UP_HYSTERESIS_TICKS,
+            catch_up_timers: HashMap::default(),
+            observation_staleness_timeout_sec: None,
+            last_ack_sim_times: HashMap::default(),
+            lag_downgrade_policy: None,
+            lagging_times: HashMap::default(),
+            health: HashMap::default(),
+            max_inputs_per_sync_msg: DEFAULT_MAX_INPUTS_PER_SYNC_MSG,
+            send_window_ticks: None,
+            guests_events_seen: HashMap::default(),
+            guests_annotations_seen: HashMap::default(),
+            guests_seeds_seen: HashMap::default(),
+            guests_acked_end: Vec::default(),
+            finalization_latencies: HashMap::default(),
+            finalization_delay_ticks: 0,
+            pending_review: HashMap::default(),
+            resend_policy: ResendPolicy::default(),
+            newest_slice_ends: HashMap::default(),
+            replaced_pending_review: HashMap::default(),
+            msg_dedup: MsgDedupCache::default(),
+            finalized_sent: HashMap::default(),
+            slice_throttle: SliceThrottle::default(),
+            desyncs: DesyncTracker::default(),
+            spectators: BTreeMap::default(),
         }
     }
 }
@@ -89,206 +336,2587 @@ impl<T: SimInput> MultiplayerInputManager<T, HostInputMgr> {
             inner: HostInputMgr::new(max_guest_ticks_behind, num_players),
             own_player_num: HOST_PLAYER_NUM,
             ticks_per_sec,
+            round: 0,
+            archived_rounds: Vec::default(),
+            decode_stats: DecodeStats::default(),
+            msg_sequences: MsgSequences::default(),
+            reliable_delivery: ReliableDelivery::default(),
+            net_stats: NetStats::default(),
+            events: EventQueue::default(),
+            muted_players: HashMap::default(),
+            seat_connections: HashMap::default(),
+            event_channel: EventChannel::new(num_players),
+            annotations: EventChannel::new(1),
+            seeds: SeedSchedule::default(),
+            tick_consumption: TickConsumption::default(),
+            scheduled_inputs: InputSchedule::default(),
+            capabilities: Capabilities::supported(),
+            negotiated_capabilities: HashMap::default(),
+            player_metadata: BTreeMap::default(),
+            unknown_player_slices: UnknownPlayerSlices::default(),
+            rollback_depth: RollbackDepthTracker::default(),
+            max_payload_bytes: None,
+            finalization_watchers: FinalizationWatchers::default(),
+            finalized_input_rx_depth: DEFAULT_FINALIZED_INPUT_RX_DEPTH,
+            finalized_input_txs: FinalizedInputTxs::default(),
+            num_sanitized: HashMap::default(),
+            rtt_config: RttConfig::default(),
+            num_ignored_slices: HashMap::default(),
+            max_input_lead: DEFAULT_MAX_INPUT_LEAD,
+            num_inputs_beyond_lead: HashMap::default(),
+            max_retained_inputs: None,
+            input_chains: None,
+            session_limit: SessionLimit::default(),
+            determinism_probe: DeterminismProbe::default(),
+            bandwidth_budget: BandwidthBudget::default(),
+            host_recovery: None,
+            change_counters: ChangeCounters::default(),
+            #[cfg(feature = "profiling")]
+            profiler: None,
         }
     }
 
+    /// Sets the minimum estimated clock skew (ppm) for which `get_msg_rate_adjust_for_guest` will produce a message.
+    pub fn with_rate_adjust_threshold_ppm(mut self, threshold_ppm: u32) -> Self {
+        self.inner.rate_adjust_threshold_ppm = threshold_ppm;
+        self
+    }
+
+    /// Sets the number of ticks between the host collecting one of its own inputs and that input taking effect.
+    pub fn with_input_delay_ticks(mut self, input_delay_ticks: u32) -> Self {
+        self.inner.input_delay_ticks = input_delay_ticks;
+        self
+    }
+
+    /// Starts the synchronized input timeline at `start_tick` rather than 0, e.g. when resuming a saved match. Guests adopt it from `get_msg_pre_sim_sync`.
+    pub fn with_start_tick(mut self, start_tick: u32) -> Self {
+        self.buffers.set_start_tick(start_tick);
+        self
+    }
+
+    /// Collects one input per `sim_ticks_per_input` sim ticks (e.g. 2 to collect inputs at 25hz for a 50hz sim), with each input covering that many sim ticks. This cuts the bandwidth of games that don't need per-tick input granularity. Guests adopt it from `get_msg_pre_sim_sync`.
+    ///
+    /// `ticks_per_sec` remains the sim's tick rate; the host collects inputs at `ticks_per_sec / sim_ticks_per_input` per second.
+    pub fn with_sim_ticks_per_input(mut self, sim_ticks_per_input: u32) -> Self {
+        self.buffers.set_sim_ticks_per_input(sim_ticks_per_input);
+        self
+    }
+
+    /// Sets the lobby's RNG seed from the start tick (see `seed_schedule`). Guests adopt it from `get_msg_pre_sim_sync`.
+    pub fn with_initial_seed(mut self, seed: u64) -> Self {
+        self.seeds.initial = seed;
+        self
+    }
+
+    /// Sets how often (sec) `poll_catch_up` checks whether each guest needs catching up.
+    pub fn with_catch_up_check_interval_sec(mut self, interval_sec: f32) -> Self {
+        self.inner.catch_up_check_interval_sec = interval_sec;
+        self
+    }
+
+    /// Sets how many ticks past `max_guest_ticks_behind` a connected guest must fall before `poll_catch_up` catches it up.
+    pub fn with_catch_up_hysteresis_ticks(mut self, hysteresis_ticks: u32) -> Self {
+        self.inner.catch_up_hysteresis_ticks = hysteresis_ticks;
+        self
+    }
+
+    /// Opts in to downgrading chronically lagging guests (see `poll_lagging_guests`).
+    pub fn with_lag_downgrade_policy(mut self, policy: LagDowngradePolicy) -> Self {
+        self.inner.lag_downgrade_policy = Some(policy);
+        self
+    }
+
+    /// Sets the maximum number of inputs in each message of a `sync_plan_for`, e.g. to keep messages within the transport's MTU.
+    pub fn with_max_inputs_per_sync_msg(mut self, max_inputs: u32) -> Self {
+        self.inner.max_inputs_per_sync_msg = max_inputs.max(1);
+        self
+    }
+
+    /// Sets the number of ticks `recommended_countdown_ticks` adds on top of the worst guest RTT.
+    pub fn with_countdown_margin_ticks(mut self, margin_ticks: u32) -> Self {
+        self.inner.countdown_margin_ticks = margin_ticks;
+        self
+    }
+
+    /// Limits the finalized ticks sent to each guest by `get_msgs_windowed_finalized_slices` to this many past what the guest has acked, so a guest on a slow link isn't sent ever-growing slices.
+    ///
+    /// With a window, `get_outgoing_msgs_for_frame` leaves out the broadcast finalized slices, and `Session` sends each guest its windowed slices instead.
+    pub fn with_send_window_ticks(mut self, window_ticks: u32) -> Self {
+        self.inner.send_window_ticks = Some(window_ticks.max(1));
+        self
+    }
+
+    pub fn send_window_ticks(&self) -> Option<u32> {
+        self.inner.send_window_ticks
+    }
+
+    /// Opts in to sending each guest its own finalized slices, resending unacked inputs at an interval that adapts to how many of them the guest already had (see `poll_adaptive_finalized_slices`).
+    pub fn with_adaptive_finalized_slices(mut self, config: AdaptiveSliceConfig) -> Self {
+        self.inner.slice_throttle.config = Some(config);
+        self
+    }
+
+    pub fn adaptive_finalized_slices(&self) -> Option<AdaptiveSliceConfig> {
+        self.inner.slice_throttle.config
+    }
+
+    /// Stops a guest's acks from holding back the start of broadcast finalized slices once it has gone this long (sec of sim time) without acking.
+    ///
+    /// Without a timeout, one silent guest pins every broadcast slice to start at the last tick it acked, so the slices grow without bound during an outage. A stale guest is left to the catch-up path (`get_msg_finalized_late_inputs_for_guest`, `poll_catch_up`) instead, and counts again as soon as it acks.
+    pub fn with_observation_staleness_timeout_sec(mut self, timeout_sec: f32) -> Self {
+        self.inner.observation_staleness_timeout_sec = Some(timeout_sec.max(0.0));
+        self
+    }
+
+    pub fn observation_staleness_timeout_sec(&self) -> Option<f32> {
+        self.inner.observation_staleness_timeout_sec
+    }
+
+    /// Holds each guest input as provisional for `delay_ticks` host ticks after it arrives, before finalizing it, so the game can review it first (e.g. for anti-cheat).
+    ///
+    /// During the review window the input can be inspected with `get_inputs_pending_review`, replaced with `replace_input_pending_review`, or finalized early with `approve_inputs_pending_review`. A guest resending an input with a different value is resolved by the resend policy (see `with_resend_policy`), and replacements are never undone by the guest resending its slice. With a delay of 0 (the default), guest inputs are finalized as soon as they arrive.
+    pub fn with_finalization_delay_ticks(mut self, delay_ticks: u32) -> Self {
+        self.inner.finalization_delay_ticks = delay_ticks;
+        self
+    }
+
+    pub fn finalization_delay_ticks(&self) -> u32 {
+        self.inner.finalization_delay_ticks
+    }
+
+    /// Sets which version of a guest input held for review is kept when the guest resends it with a different value, e.g. after a client-side rollback changed it (default `ResendPolicy::FirstWriteWins`).
+    ///
+    /// Inputs are only held for review with a finalization delay (see `with_finalization_delay_ticks`); otherwise the first version received is finalized on arrival.
+    pub fn with_resend_policy(mut self, policy: ResendPolicy) -> Self {
+        self.inner.resend_policy = policy;
+        self
+    }
+
+    pub fn resend_policy(&self) -> ResendPolicy {
+        self.inner.resend_policy
+    }
+
+    /// Enables `suppress_duplicate_msg_for_guest`: a message is suppressed if an identical one was sent to the same guest within the last `window_sec` seconds of sim time.
+    pub fn with_msg_dedup_window_sec(mut self, window_sec: f32) -> Self {
+        self.inner.msg_dedup.window_sec = Some(window_sec);
+        self
+    }
+
+    pub fn input_delay_ticks(&self) -> u32 {
+        self.inner.input_delay_ticks
+    }
+
+    /// The number of ticks the host has finalized its own inputs for, which is the host's clock.
+    ///
+    /// Unlike `get_own_num_inputs`, this excludes inputs held back by input delay.
+    fn host_tick(&self) -> u32 {
+        self.get_peer_num_final_inputs(HOST_PLAYER_NUM)
+    }
+
     /// The input manager functions as the master clock and coordinator for simulation and multiplayer timing.
     ///
     /// On the host (including solo-mode self hosts), this means that the host input buffer tracks the elapsed time since it started collecting inputs (`sim_time`). Whenever a simulation rollout needs to be triggered, the host adds inputs into its buffer sufficient to be able to simulate up to the total target time, where the target time is found by adding the delta time (sec, f32) to the stored elapsed `sim_time`.
     ///
     /// This number of inputs to add is calculated based on the configured `ticks_per_sec` rate, and the current number of inputs in the host's own input buffer.
     pub(crate) fn update_time_and_get_num_inputs_needed(&mut self, delta: f32) -> u32 {
-        self.inner.sim_time += delta;
-        let expected_num_inputs = (self.inner.sim_time * self.ticks_per_sec as f32).ceil() as u32;
-        let current_num_inputs = self.get_own_num_inputs();
-        if expected_num_inputs > current_num_inputs {
-            expected_num_inputs - current_num_inputs
-        } else {
-            0
+        // sim time resumes from the recovered history once recovery finishes
+        if self.is_recovering() {
+            return 0;
         }
+        self.inner.sim_time += delta as f64;
+        // frame times carry f32 rounding error (1/60 as an f32 is slightly
+        // over 1/60), which mustn't tip the count into an extra input
+        let expected_num_inputs = (self.inner.sim_time * self.inputs_per_sec() as f64
+            - SIM_TIME_TOLERANCE_INPUTS)
+            .ceil() as u32;
+        expected_num_inputs
+            .saturating_sub(self.host_tick())
+            .min(self.remaining_session_ticks())
     }
 
     /// Adds finalized copies of the most recently collected input to the host's own input buffer to fill up to the needed number of inputs based on the given delta time (in seconds as f32) since the last input was collected.
+    ///
+    /// Ticks with a scheduled input (see `schedule_own_input`) get that input instead; the ticks filled after one get `input` again.
     pub fn add_host_input_to_fill_needed(&mut self, input: T, delta: f32) {
         let num_inputs_needed = self.update_time_and_get_num_inputs_needed(delta);
         for _ in 0..num_inputs_needed {
-            self.add_host_input_directly(input.clone());
+            self.add_host_input_with_delay(input.clone());
+        }
+    }
+
+    /// Like `add_host_input_to_fill_needed`, but the input is reduced from the events staged in `queue`. Returns the number of ticks filled.
+    ///
+    /// The queue is only drained if at least one input is needed; otherwise its events carry over to the next call.
+    pub fn add_host_staged_input_to_fill_needed<E>(
+        &mut self,
+        queue: &mut InputStagingQueue<E, T>,
+        delta: f32,
+    ) -> u32 {
+        let num_inputs_needed = self.update_time_and_get_num_inputs_needed(delta);
+        if num_inputs_needed > 0 {
+            let input = queue.drain_reduced();
+            for _ in 0..num_inputs_needed {
+                self.add_host_input_with_delay(input.clone());
+            }
+        }
+        num_inputs_needed
+    }
+
+    /// Adds one of the host's own inputs, honoring the configured input delay.
+    ///
+    /// Without delay, the input is finalized immediately. With a delay of D ticks, it is appended as a non-final input, and the input collected D ticks earlier is finalized in its place.
+    fn add_host_input_with_delay(&mut self, input: T) {
+        if self.is_session_ended() || self.is_recovering() {
+            return;
+        }
+        let delay = self.inner.input_delay_ticks;
+        if delay == 0 {
+            self.add_host_input_directly(input);
+            return;
         }
+        // nothing was collected for the first D ticks of a round,
+        // so hold default inputs for them
+        while self.buffers.get_num_inputs(HOST_PLAYER_NUM) < self.host_tick() + delay {
+            self.buffers.append_input(HOST_PLAYER_NUM, T::default());
+        }
+        let input = self.scheduled_or(input);
+        let input = self.sanitize_input(HOST_PLAYER_NUM, input);
+        self.buffers.append_input(HOST_PLAYER_NUM, input);
+        self.record_finalization_latencies(HOST_PLAYER_NUM, self.host_tick(), 1);
+        self.buffers.finalize_next_collected_input(HOST_PLAYER_NUM);
+        self.after_inputs_finalized();
+        self.finalize_reviewed_guest_inputs();
+        self.observe_rollback_depth();
+        self.finalize_to_session_end();
+        self.observe_session_end();
     }
 
     /// Add a finalized input to the hosts own input buffer
     pub(crate) fn add_host_input_directly(&mut self, input: T) {
+        if self.is_session_ended() || self.is_recovering() {
+            return;
+        }
+        let input = self.scheduled_or(input);
+        let input = self.sanitize_input(HOST_PLAYER_NUM, input);
+        self.record_finalization_latencies(HOST_PLAYER_NUM, self.host_tick(), 1);
         self.buffers.append_input_finalized(HOST_PLAYER_NUM, input);
+        self.after_inputs_finalized();
+        self.finalize_reviewed_guest_inputs();
+        self.observe_rollback_depth();
+        self.finalize_to_session_end();
+        self.observe_session_end();
+    }
+
+    // Dispatch //////////////////////////////
+
+    /// Passes a message received from the guest in seat `player_num` to the matching `rx_*` method, returning the replies to send, each addressed to that guest or to all peers. The messages of a batch are handled in turn, and replies superseded by a later reply to the same recipient are dropped.
+    ///
+    /// Replies are addressed to seats, and aren't recorded as sent (see `record_msg_sent`); `Session::rx_msg` does both for the connections it manages.
+    pub fn handle_msg(
+        &mut self,
+        player_num: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> Result<OutgoingMsgs<T>, SessionRxError> {
+        let sender = player_num;
+        if !sender.is_guest() {
+            return Err(SessionRxError::InvalidSender);
+        }
+        if let MsgPayload::Batch(msgs) = msg {
+            let mut replies = vec![];
+            for msg in msgs {
+                if let Ok(outgoing) = self.handle_msg(sender, msg) {
+                    push_replies(&mut replies, outgoing);
+                }
+            }
+            return Ok(replies);
+        }
+        let outgoing = match msg {
+            MsgPayload::PeerInputs(_) => {
+                self.rx_guest_input_slice(sender, msg);
+                self.get_msgs_finalized_slice(sender)
+                    .into_iter()
+                    .map(|msg| (Recipient::AllPeers, msg))
+                    .collect()
+            }
+            MsgPayload::GuestToHostLocalInputs(_) => self
+                .rx_guest_local_inputs(sender, msg)
+                .into_iter()
+                .filter(|(_, outcome)| outcome.rejected.is_none())
+                .flat_map(|(player_num, _)| self.get_msgs_finalized_slice(player_num))
+                .map(|msg| (Recipient::AllPeers, msg))
+                .collect(),
+            MsgPayload::GuestToHostAckFinalization(_) => {
+                self.rx_finalized_ticks_observations(sender, msg);
+                vec![]
+            }
+            MsgPayload::GuestToHostPing(_) => {
+                vec![(
+                    Recipient::Player(sender),
+                    self.rx_guest_ping_and_reply(sender, msg),
+                )]
+            }
+            MsgPayload::GuestToHostClockPing(_) => {
+                vec![(
+                    Recipient::Player(sender),
+                    self.rx_clock_ping_and_reply(sender, msg),
+                )]
+            }
+            MsgPayload::GuestToHostPongPong(_) | MsgPayload::GuestToHostLegacyPongPong(_) => {
+                let ping_report = self
+                    .rx_guest_pong_pong(sender, msg)
+                    .map_err(SessionRxError::Rejected)?;
+                vec![
+                    (Recipient::Player(sender), ping_report),
+                    (
+                        Recipient::Player(sender),
+                        self.get_msg_rate_adjust_for_guest(sender),
+                    ),
+                ]
+            }
+            MsgPayload::GuestToHostRoundTransitionAck(_) => {
+                self.rx_round_transition_ack(sender, msg);
+                vec![]
+            }
+            MsgPayload::GuestToHostBufferReset(_) => {
+                self.rx_guest_buffer_reset(sender, msg);
+                vec![]
+            }
+            MsgPayload::GuestToHostEvents(_) => match self.rx_guest_events(sender, msg) {
+                Ok(_) => vec![(Recipient::AllPeers, self.get_msg_events(sender))],
+                Err(_) => vec![],
+            },
+            MsgPayload::GuestToHostAckEvents(_) => {
+                self.rx_guest_events_ack(sender, msg);
+                vec![]
+            }
+            MsgPayload::PeerInputChainHead(_) => {
+                self.rx_input_chain_head(sender, msg);
+                vec![]
+            }
+            MsgPayload::PeerDeterminismSample(_) => {
+                self.rx_determinism_sample(sender, msg);
+                vec![]
+            }
+            MsgPayload::GuestToHostAckAnnotations(_) => {
+                self.rx_guest_annotations_ack(sender, msg);
+                vec![]
+            }
+            MsgPayload::GuestToHostRecoveryResponse(_) => {
+                self.rx_recovery_response(sender, msg);
+                vec![]
+            }
+            MsgPayload::GuestToHostAckSeeds(_) => {
+                self.rx_guest_seeds_ack(sender, msg);
+                vec![]
+            }
+            MsgPayload::GuestToHostEndAck(_) => {
+                self.rx_guest_end_ack(sender, msg);
+                vec![]
+            }
+            MsgPayload::GuestToHostInputSchema(_) => {
+                match self.rx_guest_input_schema(sender, msg) {
+                    Ok(()) => vec![],
+                    Err(_) => vec![(Recipient::AllPeers, self.mute_player(sender, 0))],
+                }
+            }
+            MsgPayload::GuestToHostCapabilities(_) => {
+                self.rx_guest_capabilities(sender, msg);
+                vec![]
+            }
+            MsgPayload::GuestToHostInputHashReport(_) => {
+                self.rx_input_hash_report(sender, msg);
+                vec![]
+            }
+            MsgPayload::GuestToHostStartAck(_) => {
+                self.rx_start_ack(sender, msg);
+                vec![(Recipient::AllPeers, self.get_msg_start_confirmed())]
+            }
+            // acks are consumed by `decode_sequenced_msg_from_peer`
+            MsgPayload::Empty | MsgPayload::DeliveryAck(_) => vec![],
+            _ => return Err(SessionRxError::WrongRole),
+        };
+        Ok(without_empty_msgs(outgoing))
     }
 
     // PeerInputs //////////////////////////////
 
     /// Finalize a slice of inputs to the input buffer for
     /// the player with the given player_num.
-    pub fn rx_guest_input_slice(&mut self, player_num: PlayerNum, msg: MsgPayload<T>) {
+    pub fn rx_guest_input_slice(&mut self, player_num: PlayerNum, msg: MsgPayload<T>) -> RxOutcome {
         #[cfg(debug_assertions)]
         assert!(player_num != HOST_PLAYER_NUM);
+        if self.inner.guests_pending_round_ack.contains(&player_num) {
+            return RxOutcome::rejected(RxRejection::AwaitingRoundAck);
+        }
+        if self.is_recovering() {
+            return RxOutcome::rejected(RxRejection::HostRecovering);
+        }
+        if self.buffers.is_removed(player_num) {
+            return RxOutcome::rejected(RxRejection::PlayerRemoved);
+        }
         // self.add_input_observations_if_needed(player_num.into());
-        if let Ok(input_slice) = msg.try_into() {
-            self.buffers
-                .receive_finalized_input_slice_for_player(input_slice, player_num);
+        let MsgPayload::PeerInputs(mut input_slice) = msg else {
+            return RxOutcome::rejected(RxRejection::UnexpectedMsg);
+        };
+        if !self.drop_muted_inputs(player_num, &mut input_slice) {
+            return RxOutcome::rejected(RxRejection::PlayerMuted);
         }
-    }
-
-    // AckFinalization //////////////////////////////
-
-    /// The host input manager should add input observations for each guest
-    /// as soon it becomes aware of them.
-    // fn add_input_observations_if_needed(&mut self, player_num: PlayerNum) {
-    //     #[cfg(debug_assertions)]
-    //     assert!(player_num != HOST_PLAYER_NUM);
-    //     self.inner
-    //         .guests_finalized_observations
-    //         .entry(player_num)
-    //         .or_insert_with(PeerwiseFinalizedInputsSeen::default);
-    // }
-
-    pub fn rx_finalized_ticks_observations(&mut self, player_num: PlayerNum, msg: MsgPayload<T>) {
-        if let MsgPayload::GuestToHostAckFinalization(new_ack) = msg {
-            self.inner
-                .guests_finalized_observations
-                .update_guest_observation(player_num, new_ack);
+        if !self.drop_inputs_past_session_end(&mut input_slice) {
+            return RxOutcome::rejected(RxRejection::PastSessionEnd);
+        }
+        let lead_from = self.num_finalized_across_live_players();
+        if !self.drop_inputs_beyond_lead(player_num, &mut input_slice, lead_from) {
+            return RxOutcome::rejected(RxRejection::BeyondInputLead);
+        }
+        self.inner
+            .input_rates
+            .entry(player_num)
+            .or_default()
+            .observe(
+                input_slice.start + input_slice.len(),
+                self.inner.sim_time as f32,
+            );
+        let finalized_before = self.buffers.get_num_finalized_inputs(player_num);
+        if input_slice.start > finalized_before {
+            return self.reject_gap_before_slice(player_num);
         }
+        if self.inner.finalization_delay_ticks > 0 {
+            return self.rx_guest_input_slice_for_review(player_num, input_slice);
+        }
+        self.sanitize_slice(player_num, &mut input_slice);
+        let outcome = self.rx_and_summarize(player_num, |buffers| {
+            buffers.receive_finalized_input_slice_for_player(input_slice, player_num)
+        });
+        self.record_finalization_latencies(player_num, finalized_before, outcome.newly_finalized);
+        outcome
     }
 
-    // Pings and Pongs //////////////////////////////
-
-    pub fn rx_guest_ping_and_reply(
+    /// Finalizes each slice of a guest's bundled `GuestToHostLocalInputs` as if received with `rx_guest_input_slice`, returning the outcome for each player in the bundle.
+    ///
+    /// `player_num` is the sender's seat; slices for seats its connection doesn't control (see `add_local_seat`) are rejected as `RxRejection::NotLocalSeat`.
+    pub fn rx_guest_local_inputs(
         &mut self,
         player_num: PlayerNum,
         msg: MsgPayload<T>,
-    ) -> MsgPayload<T> {
-        if let MsgPayload::GuestToHostPing(id) = msg {
-            self.inner
-                .pong_send_times
-                .entry(player_num)
-                .or_insert(PongSendTimes::default())
-                .record_pong_send(id);
-
-            MsgPayload::HostToGuestPong(id)
-        } else {
-            panic!("fn rx_guest_ping can only handle GuestPing message")
-        }
+    ) -> Vec<(PlayerNum, RxOutcome)> {
+        let MsgPayload::GuestToHostLocalInputs(bundle) = msg else {
+            return vec![(player_num, RxOutcome::rejected(RxRejection::UnexpectedMsg))];
+        };
+        let local_seats = self.seats_sharing_connection(player_num);
+        bundle
+            .into_iter()
+            .map(|local| {
+                let outcome = if local_seats.contains(&local.player_num) {
+                    self.rx_guest_input_slice(local.player_num, local.inputs.into())
+                } else {
+                    RxOutcome::rejected(RxRejection::NotLocalSeat)
+                };
+                (local.player_num, outcome)
+            })
+            .collect()
     }
 
-    pub fn rx_guest_pong_pong(
+    // Review window //////////////////////////////
+
+    /// Stores a guest's new inputs as provisional, to be finalized once the review window has passed.
+    fn rx_guest_input_slice_for_review(
         &mut self,
         player_num: PlayerNum,
-        msg: MsgPayload<T>,
-    ) -> Result<MsgPayload<T>, String> {
-        if let MsgPayload::GuestToHostPongPong(id) = msg {
-            let rtt = self
+        mut input_slice: PlayerInputSlice<T>,
+    ) -> RxOutcome {
+        let num_held = self.buffers.get_num_inputs(player_num);
+        let slice_end = input_slice.start + input_slice.len();
+        let newest_end = self.inner.newest_slice_ends.entry(player_num).or_default();
+        if self.inner.resend_policy == ResendPolicy::LastWriteWins && slice_end > *newest_end {
+            *newest_end = slice_end;
+            // the newest slice replaces the held inputs, except for those
+            // the host replaced itself
+            input_slice.drop_before(self.buffers.get_num_finalized_inputs(player_num));
+            for &index in self
                 .inner
-                .pong_send_times
-                .get_mut(&player_num)
-                .unwrap()
-                .observe_pong_reply(id);
-
-            if rtt.is_err() {
-                return Err(format!(
-                    "rx_guest_pong_pong msg id not found for player {:?}; msg payload: {:?}",
-                    player_num, msg
-                ));
+                .replaced_pending_review
+                .get(&player_num)
+                .into_iter()
+                .flatten()
+            {
+                if let Some(bytes) = input_slice
+                    .inputs
+                    .get_mut(index.wrapping_sub(input_slice.start) as usize)
+                {
+                    *bytes = self
+                        .buffers
+                        .get_input_or_prediction(player_num, index)
+                        .to_bytes();
+                }
             }
-
-            self.inner
-                .rtts
-                .entry(player_num)
-                .or_insert(Ewma::default())
-                .observe(rtt.unwrap());
-
-            Ok(MsgPayload::Empty)
         } else {
-            Err("fn rx_guest_pong can only handle GuestPong message".into())
+            *newest_end = (*newest_end).max(slice_end);
+            input_slice.drop_before(num_held);
+        }
+        if input_slice.is_empty() {
+            return RxOutcome::default();
         }
+        self.sanitize_slice(player_num, &mut input_slice);
+        let num_inputs = input_slice.start + input_slice.len();
+        let outcome = self.rx_and_summarize(player_num, |buffers| {
+            buffers.receive_peer_input_slice(input_slice, player_num)
+        });
+        let due_tick = self.host_tick() + self.inner.finalization_delay_ticks;
+        self.inner
+            .pending_review
+            .entry(player_num)
+            .or_default()
+            .push_back((due_tick, num_inputs));
+        outcome
     }
 
-    // HostFinalizedSlice //////////////////////////////
-
-    /// Gets the finalized input slice for this peer
-    /// needed by guests
-    pub fn get_msg_finalized_slice(&self, player_num: PlayerNum) -> MsgPayload<T> {
-        // get the earliest tick that has been finalized across all peers
-        let start = self
+    /// Finalizes the guest inputs whose review window has passed.
+    fn finalize_reviewed_guest_inputs(&mut self) {
+        let host_tick = self.host_tick();
+        let due: Vec<(PlayerNum, u32)> = self
             .inner
-            .guests_finalized_observations
-            .get_earliest_num_observed_final_for_peer(player_num.into());
-
-        let slice = self
-            .buffers
-            .get_slice_to_end_for_peer(player_num.into(), start);
+            .pending_review
+            .iter()
+            .filter_map(|(&player_num, pending)| {
+                pending
+                    .iter()
+                    .take_while(|(due_tick, _)| *due_tick <= host_tick)
+                    .map(|(_, num_inputs)| *num_inputs)
+                    .max()
+                    .map(|num_inputs| (player_num, num_inputs))
+            })
+            .collect();
+        for (player_num, num_inputs) in due {
+            self.finalize_guest_inputs_pending_review(player_num, num_inputs);
+        }
+    }
 
-        HostFinalizedSlice {
-            player_num: player_num,
-            host_tick: self.get_peer_num_final_inputs(HOST_PLAYER_NUM.into()),
-            inputs: slice,
+    /// Finalizes the guest's provisional inputs up to `num_inputs`, and stops tracking the review windows they cover.
+    fn finalize_guest_inputs_pending_review(
+        &mut self,
+        player_num: PlayerNum,
+        num_inputs: u32,
+    ) -> RxOutcome {
+        let finalized_before = self.buffers.get_num_finalized_inputs(player_num);
+        let num_inputs = num_inputs.min(self.buffers.get_num_inputs(player_num));
+        if let Some(pending) = self.inner.pending_review.get_mut(&player_num) {
+            pending.retain(|(_, pending_num_inputs)| *pending_num_inputs > num_inputs);
         }
-        .into()
+        if let Some(replaced) = self.inner.replaced_pending_review.get_mut(&player_num) {
+            replaced.retain(|index| *index >= num_inputs);
+        }
+        if num_inputs <= finalized_before {
+            return RxOutcome::default();
+        }
+        let mut input_slice = self
+            .buffers
+            .get_slice_to_end_for_peer(player_num, finalized_before);
+        input_slice.truncate_before(num_inputs);
+        let outcome = self.rx_and_summarize(player_num, |buffers| {
+            buffers.receive_finalized_input_slice_for_player(input_slice, player_num)
+        });
+        self.record_finalization_latencies(player_num, finalized_before, outcome.newly_finalized);
+        outcome
     }
 
-    // // Catch Up //////////////////////////////
+    /// The guest's inputs that are held for review (see `with_finalization_delay_ticks`), by sim tick.
+    pub fn get_inputs_pending_review(&self, player_num: PlayerNum) -> Vec<(u32, T)> {
+        let finalized = self.buffers.get_num_finalized_inputs(player_num);
+        let input_slice = self
+            .buffers
+            .get_slice_to_end_for_peer(player_num, finalized);
+        input_slice
+            .inputs
+            .into_iter()
+            .enumerate()
+            .map(|(offset, bytes)| {
+                let index = input_slice.start + offset as u32;
+                (self.buffers.tick_of_input(index), T::from_bytes(bytes))
+            })
+            .collect()
+    }
 
-    /// Checks whether the newest input tick seen by the host is more than
-    /// max_guest_ticks_behind ticks behind the host's local tick.
-    /// If so, the host will add finalized inputs up the target tick,
-    /// and send them to the guest.
+    /// Replaces a guest's input for `tick` while it is held for review (see `with_finalization_delay_ticks`); the replacement is what will be finalized and sent to guests.
     ///
-    /// Also, if the player is disconnected, the host will add finalized inputs up to the hosts own input and send those.
-    ///
-    /// If not, this function returns an empty message.
-    pub fn get_msg_finalized_late_inputs_for_guest(
+    /// Returns an error if the input isn't held for review, i.e. it hasn't arrived yet or has already been finalized.
+    pub fn replace_input_pending_review(
         &mut self,
         player_num: PlayerNum,
-    ) -> MsgPayload<T> {
-        let target_num_final_inputs = if self.inner.disconnected_players.contains(&player_num) {
-            self.get_own_num_inputs()
-        } else {
-            (self.get_own_num_inputs() as i32 - self.inner.max_guest_ticks_behind as i32).max(0)
-                as u32
-        };
-
-        let peer_num_final_inputs = self.buffers.get_num_finalized_inputs(player_num);
-        // check if the peer is behind the target tick
-        if peer_num_final_inputs < target_num_final_inputs {
-            self.buffers
-                .append_final_default_inputs_to_target(player_num, target_num_final_inputs);
+        tick: u32,
+        mut input: T,
+    ) -> Result<(), String> {
+        let index = self
+            .input_index(tick)
+            .filter(|index| {
+                *index >= self.buffers.get_num_finalized_inputs(player_num)
+                    && *index < self.buffers.get_num_inputs(player_num)
+            })
+            .ok_or_else(|| {
+                format!("Player {player_num}'s input for tick {tick} is not held for review")
+            })?;
+        input.sanitize();
+        self.buffers.receive_peer_input_slice(
+            PlayerInputSlice {
+                start: index,
+                inputs: vec![input.to_bytes()],
+            },
+            player_num,
+        );
+        self.inner
+            .replaced_pending_review
+            .entry(player_num)
+            .or_default()
+            .insert(index);
+        Ok(())
+    }
 
-            let start = self
-                .inner
-                .guests_finalized_observations
-                .get_earliest_num_observed_final_for_peer(player_num);
+    /// Finalizes the guest's inputs held for review for ticks before `end_tick`, without waiting for the rest of the review window.
+    pub fn approve_inputs_pending_review(
+        &mut self,
+        player_num: PlayerNum,
+        end_tick: u32,
+    ) -> RxOutcome {
+        let num_inputs = self.buffers.num_inputs_before_tick(end_tick);
+        self.finalize_guest_inputs_pending_review(player_num, num_inputs)
+    }
 
-            let slice = self.buffers.get_slice_to_end_for_peer(player_num, start);
+    // Deduplication //////////////////////////////
 
-            HostFinalizedSlice {
-                player_num,
-                host_tick: self.get_own_num_inputs(),
-                inputs: slice,
-            }
+    /// Returns `msg` unchanged, or `MsgPayload::Empty` if an identical message was already sent to `guest` within the dedup window (see `with_msg_dedup_window_sec`). Call this just before sending a message to a single guest, e.g. the repeated catch-up slices regenerated during a reconnect storm.
+    ///
+    /// Messages are identified by their kind, the range of inputs they carry, and a hash of their content; sent times are measured in sim time. A finalized slice's content leaves out the host tick it is stamped with, so the same inputs regenerated on a later tick are still duplicates. Without a dedup window, nothing is suppressed.
+    pub fn suppress_duplicate_msg_for_guest(
+        &mut self,
+        guest: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> MsgPayload<T> {
+        if matches!(msg, MsgPayload::Empty) {
+            return msg;
+        }
+        let (range, content) = match &msg {
+            MsgPayload::HostToLobbyFinalizedSlice(slice) => (
+                Some((slice.inputs.start, slice.inputs.len())),
+                to_bincode_bytes(&(slice.player_num, &slice.inputs)),
+            ),
+            MsgPayload::PeerInputs(slice) => (Some((slice.start, slice.len())), msg.to_bytes()),
+            _ => (None, msg.to_bytes()),
+        };
+        let key = SentMsgKey {
+            kind: msg.kind(),
+            range,
+            hash: fnv1a_64(INPUT_CHAIN_SEED, &content),
+        };
+        if self
+            .inner
+            .msg_dedup
+            .is_duplicate(guest, key, self.inner.sim_time as f32)
+        {
+            MsgPayload::Empty
+        } else {
+            msg
+        }
+    }
+
+    /// The number of messages to this guest suppressed as duplicates by `suppress_duplicate_msg_for_guest`, over the whole match.
+    pub fn num_suppressed_duplicate_msgs(&self, guest: PlayerNum) -> u32 {
+        self.inner.msg_dedup.num_suppressed(guest)
+    }
+
+    // AckFinalization //////////////////////////////
+
+    // The host input manager should add input observations for each guest
+    // as soon it becomes aware of them.
+    // fn add_input_observations_if_needed(&mut self, player_num: PlayerNum) {
+    //     #[cfg(debug_assertions)]
+    //     assert!(player_num != HOST_PLAYER_NUM);
+    //     self.inner
+    //         .guests_finalized_observations
+    //         .entry(player_num)
+    //         .or_insert_with(PeerwiseFinalizedInputsSeen::default);
+    // }
+
+    /// Records which finalized inputs a guest has seen. The ack covers every seat the guest's connection controls (see `add_local_seat`).
+    ///
+    /// Acks are checked against what the host could have sent: no more than it has finalized for each player and, once finalized slices sent to this guest are recorded (see `record_msg_sent`), no more than those reached. An ack past that is clamped to it, raising `InputMgrEvent::AckBeyondSent`, so a misbehaving guest can't move the broadcast start past inputs it never received.
+    ///
+    /// This never changes the input buffers, so the outcome only reports whether the ack was rejected.
+    pub fn rx_finalized_ticks_observations(
+        &mut self,
+        player_num: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> RxOutcome {
+        if self.inner.guests_pending_round_ack.contains(&player_num) {
+            return RxOutcome::rejected(RxRejection::AwaitingRoundAck);
+        }
+        if self.buffers.is_removed(player_num) {
+            return RxOutcome::rejected(RxRejection::PlayerRemoved);
+        }
+        let MsgPayload::GuestToHostAckFinalization(new_ack) = msg else {
+            return RxOutcome::rejected(RxRejection::UnexpectedMsg);
+        };
+        let new_ack = self.clamp_ack_to_sent(player_num, &new_ack);
+        for seat in self.seats_sharing_connection(player_num) {
+            if self.buffers.is_removed(seat) {
+                continue;
+            }
+            self.inner
+                .guests_finalized_observations
+                .update_guest_observation(seat, new_ack.clone());
+            self.inner
+                .last_ack_sim_times
+                .insert(seat, self.inner.sim_time);
+        }
+        self.change_counters.observations += 1;
+        self.drop_unacked_inputs();
+        RxOutcome::default()
+    }
+
+    /// Handles a guest's announcement that it has reinitialized its buffers (see `MultiplayerInputManager::reset_buffers`): the host forgets the guest's acks, so its next finalized slices start from the first input the host still retains.
+    ///
+    /// A reset stamped with another round is rejected as unexpected.
+    pub fn rx_guest_buffer_reset(
+        &mut self,
+        player_num: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> RxOutcome {
+        if self.inner.guests_pending_round_ack.contains(&player_num) {
+            return RxOutcome::rejected(RxRejection::AwaitingRoundAck);
+        }
+        if self.buffers.is_removed(player_num) {
+            return RxOutcome::rejected(RxRejection::PlayerRemoved);
+        }
+        let MsgPayload::GuestToHostBufferReset(round) = msg else {
+            return RxOutcome::rejected(RxRejection::UnexpectedMsg);
+        };
+        if round != self.round {
+            return RxOutcome::rejected(RxRejection::UnexpectedMsg);
+        }
+        let num_players = self.buffers.num_players();
+        for seat in self.seats_sharing_connection(player_num) {
+            self.inner
+                .guests_finalized_observations
+                .reset_guest_observation(seat, num_players);
+            self.inner.finalized_sent.remove(&seat);
+        }
+        self.change_counters.observations += 1;
+        RxOutcome::default()
+    }
+
+    // drops the inputs no longer needed (see `drop_unretained_inputs`),
+    // keeping those a connected guest hasn't acked, which may still need
+    // sending to it. A disconnected guest's acks don't hold inputs back.
+    fn drop_unacked_inputs(&mut self) {
+        let disconnected = &self.inner.disconnected_players;
+        let acked_by_all: Vec<u32> = PlayerNum::iter(self.buffers.num_players())
+            .map(|player| {
+                self.inner
+                    .guests_finalized_observations
+                    .get_earliest_num_observed_final_for_peer_among(player, |guest| {
+                        !disconnected.contains(&guest)
+                    })
+                    .unwrap_or(u32::MAX)
+            })
+            .collect();
+        self.drop_unretained_inputs(|player| acked_by_all[usize::from(player)]);
+    }
+
+    /// Records that a message was sent, so that acks can be checked against the finalized slices each guest was actually sent (see `rx_finalized_ticks_observations`). Only finalized slices are recorded; other messages are ignored.
+    ///
+    /// `Session` records the messages it returns. When driving the manager directly, record every finalized slice sent; an unrecorded one only makes the host resend inputs the guest already has, and flag its acks for them.
+    pub fn record_msg_sent(&mut self, recipient: Recipient, msg: &MsgPayload<T>) {
+        if let MsgPayload::Batch(msgs) = msg {
+            for msg in msgs {
+                self.record_msg_sent(recipient, msg);
+            }
+            return;
+        }
+        let MsgPayload::HostToLobbyFinalizedSlice(slice) = msg else {
+            return;
+        };
+        let num_players = self.buffers.num_players();
+        let player = usize::from(slice.player_num);
+        if player >= usize::from(num_players) {
+            return;
+        }
+        let end = slice.inputs.start + slice.inputs.len();
+        let guests: Vec<PlayerNum> = match recipient {
+            Recipient::AllPeers => PlayerNum::iter_guests(num_players).collect(),
+            Recipient::Player(seat) if seat.is_guest() => vec![seat],
+            Recipient::Player(_) => vec![],
+        };
+        for guest in guests {
+            let start = slice.inputs.start;
+            let acked = self
+                .inner
+                .guests_finalized_observations
+                .get_guest_observation(guest, slice.player_num);
+            let sent_before = self
+                .inner
+                .finalized_sent
+                .get(&guest)
+                .and_then(|sent| sent[player])
+                .unwrap_or(0);
+            let num_acked = acked.clamp(start, end) - start;
+            let num_resent = sent_before.min(end).saturating_sub(start.max(acked));
+            self.inner
+                .slice_throttle
+                .record_sent(guest, slice.inputs.len(), num_acked, num_resent);
+            let sent = self
+                .inner
+                .finalized_sent
+                .entry(guest)
+                .or_insert_with(|| vec![None; usize::from(num_players)]);
+            sent[player] = Some(sent[player].map_or(end, |sent| sent.max(end)));
+        }
+    }
+
+    /// The most finalized inputs of `player_num` this guest can have received: all the host has finalized, or less if the slices of that player sent to the guest are recorded.
+    fn max_ackable(&self, guest: PlayerNum, player_num: PlayerNum) -> u32 {
+        let num_finalized = self.buffers.get_num_finalized_inputs(player_num);
+        self.inner
+            .finalized_sent
+            .get(&guest)
+            .and_then(|sent| sent.get(usize::from(player_num)).copied().flatten())
+            .map_or(num_finalized, |sent| sent.min(num_finalized))
+    }
+
+    // clamps each count in the guest's ack to what it can have received,
+    // raising an event for each count that went past it
+    fn clamp_ack_to_sent(
+        &mut self,
+        guest: PlayerNum,
+        ack: &PeerwiseFinalizedInputsSeen,
+    ) -> PeerwiseFinalizedInputsSeen {
+        let num_players = self.buffers.num_players();
+        let clamped: Vec<u32> = PlayerNum::iter(num_players)
+            .map(|player_num| {
+                let acked = ack.get(player_num);
+                let max_sent = self.max_ackable(guest, player_num);
+                if acked <= max_sent {
+                    return acked;
+                }
+                self.events.push(InputMgrEvent::AckBeyondSent {
+                    guest,
+                    player_num,
+                    acked,
+                    max_sent,
+                });
+                max_sent
+            })
+            .collect();
+        PeerwiseFinalizedInputsSeen::new_from_observed(num_players, &clamped)
+    }
+
+    // Events //////////////////////////////
+
+    /// Adds one of the host's own events, taking effect at the tick of the next input the host collects.
+    pub fn add_host_event(&mut self, kind: u16, data: Vec<u8>) {
+        let event = TickEvent {
+            tick: self.get_own_num_inputs(),
+            kind,
+            data,
+        };
+        self.event_channel
+            .push(HOST_PLAYER_NUM, event, 0, true)
+            .expect("the host's seat is always in the lobby");
+    }
+
+    /// Accepts a guest's events that the host hasn't seen yet, and returns how many were accepted.
+    ///
+    /// An event stamped at a tick for which the guest's inputs are already finalized (i.e. it arrived too late) is moved to the guest's next unfinalized tick, so events are never dropped and never rewrite finalized history. Slices that would leave a gap in the event sequence are ignored; the guest resends them. So are events past `MAX_PENDING_EVENTS_PER_PLAYER` of the guest's events that aren't final yet.
+    ///
+    /// Events from a seat beyond the host's `num_players` are rejected as `RxRejection::UnknownPlayer`.
+    pub fn rx_guest_events(
+        &mut self,
+        player_num: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> Result<u32, RxRejection> {
+        if self.inner.guests_pending_round_ack.contains(&player_num) {
+            return Err(RxRejection::AwaitingRoundAck);
+        }
+        let MsgPayload::GuestToHostEvents(slice) = msg else {
+            return Err(RxRejection::UnexpectedMsg);
+        };
+        if slice.player_num != player_num {
+            return Err(RxRejection::UnexpectedMsg);
+        }
+        if u8::from(player_num) >= self.buffers.num_players() {
+            return Err(RxRejection::UnknownPlayer);
+        }
+        let num_events = self.event_channel.num_events(player_num);
+        if slice.start_seq > num_events {
+            return Ok(0);
+        }
+        let min_tick = self.buffers.get_num_finalized_inputs(player_num);
+        let num_pending = num_events - self.event_channel.num_finalized(player_num, min_tick);
+        let new_events = slice
+            .events
+            .into_iter()
+            .skip((num_events - slice.start_seq) as usize)
+            .take(MAX_PENDING_EVENTS_PER_PLAYER.saturating_sub(num_pending) as usize);
+        let mut num_accepted = 0;
+        for event in new_events {
+            self.event_channel.push(player_num, event, min_tick, true)?;
+            num_accepted += 1;
+        }
+        Ok(num_accepted)
+    }
+
+    /// Records how many confirmed events a guest has received for each player.
+    pub fn rx_guest_events_ack(&mut self, player_num: PlayerNum, msg: MsgPayload<T>) {
+        if self.inner.guests_pending_round_ack.contains(&player_num) {
+            return;
+        }
+        if let MsgPayload::GuestToHostAckEvents(seen) = msg {
+            self.inner.guests_events_seen.insert(player_num, seen);
+        }
+    }
+
+    /// Gets the accepted events for this player that at least one live guest hasn't acked yet, or an empty message if every live guest is current. Disconnected and muted guests aren't waited for.
+    ///
+    /// This message should be broadcast to all guests.
+    pub fn get_msg_events(&self, player_num: PlayerNum) -> MsgPayload<T> {
+        let start = self
+            .live_guests()
+            .map(|guest| {
+                self.inner
+                    .guests_events_seen
+                    .get(&guest)
+                    .and_then(|seen| seen.get(usize::from(player_num)).copied())
+                    .unwrap_or(0)
+            })
+            .min()
+            .unwrap_or(0);
+        let end = self.event_channel.num_events(player_num);
+        if start >= end {
+            return MsgPayload::Empty;
+        }
+        MsgPayload::HostToLobbyEvents(self.event_channel.slice(player_num, start, end))
+    }
+
+    // Annotations //////////////////////////////
+
+    /// Adds an authoritative annotation for a sim tick (e.g. a round start, or an item spawn seed), which reaches guests on the same reliable, acked channel as events (see `get_msg_annotations`).
+    ///
+    /// Finalized history can't be rewritten, so the tick must be one for which the host's own input isn't finalized yet, and annotations must be added in tick order.
+    pub fn add_annotation(&mut self, tick: u32, kind: u16, data: Vec<u8>) -> Result<(), String> {
+        let Some(index) = self.input_index(tick) else {
+            return Err(format!("tick {tick} is before the start tick"));
+        };
+        let num_final = self.buffers.get_num_finalized_inputs(HOST_PLAYER_NUM);
+        if index < num_final {
+            return Err(format!(
+                "tick {tick} is already finalized (next unfinalized tick is {})",
+                self.buffers.tick_of_input(num_final)
+            ));
+        }
+        let num_annotations = self.get_num_annotations();
+        if let Some(last) = self
+            .annotations
+            .events(HOST_PLAYER_NUM, num_annotations)
+            .last()
+            .filter(|last| last.tick > index)
+        {
+            return Err(format!(
+                "tick {tick} is before the last annotation's tick {}",
+                self.buffers.tick_of_input(last.tick)
+            ));
+        }
+        let annotation = TickEvent {
+            tick: index,
+            kind,
+            data,
+        };
+        self.annotations
+            .push(HOST_PLAYER_NUM, annotation, index, true)
+            .expect("the host's annotations are always in the channel");
+        Ok(())
+    }
+
+    /// Records how many of the host's annotations a guest has received.
+    pub fn rx_guest_annotations_ack(&mut self, player_num: PlayerNum, msg: MsgPayload<T>) {
+        if self.inner.guests_pending_round_ack.contains(&player_num) {
+            return;
+        }
+        if let MsgPayload::GuestToHostAckAnnotations(num_seen) = msg {
+            self.inner
+                .guests_annotations_seen
+                .insert(player_num, num_seen);
+        }
+    }
+
+    /// Gets the annotations that at least one live guest hasn't acked yet, or an empty message if every live guest is current. Disconnected and muted guests aren't waited for.
+    ///
+    /// This message should be broadcast to all guests, e.g. alongside the host's finalized slices.
+    pub fn get_msg_annotations(&self) -> MsgPayload<T> {
+        let start = self
+            .live_guests()
+            .map(|guest| {
+                self.inner
+                    .guests_annotations_seen
+                    .get(&guest)
+                    .copied()
+                    .unwrap_or(0)
+            })
+            .min()
+            .unwrap_or(0);
+        let end = self.get_num_annotations();
+        if start >= end {
+            return MsgPayload::Empty;
+        }
+        MsgPayload::HostToLobbyAnnotations(self.annotations.slice(HOST_PLAYER_NUM, start, end))
+    }
+
+    // Seeds //////////////////////////////
+
+    /// Changes the lobby's RNG seed from this sim tick on (see `seed_schedule`). The change reaches guests via `get_msg_seed`.
+    ///
+    /// Like annotations, the tick must be one for which the host's own input isn't finalized yet, and changes must be made in tick order.
+    pub fn set_seed(&mut self, tick: u32, seed: u64) -> Result<(), String> {
+        let Some(index) = self.input_index(tick) else {
+            return Err(format!("tick {tick} is before the start tick"));
+        };
+        let num_final = self.buffers.get_num_finalized_inputs(HOST_PLAYER_NUM);
+        if index < num_final {
+            return Err(format!(
+                "tick {tick} is already finalized (next unfinalized tick is {})",
+                self.buffers.tick_of_input(num_final)
+            ));
+        }
+        if let Some(last) = self.seeds.last_change().filter(|last| last.tick > index) {
+            return Err(format!(
+                "tick {tick} is before the last seed change's tick {}",
+                self.buffers.tick_of_input(last.tick)
+            ));
+        }
+        self.seeds.push(index, seed);
+        Ok(())
+    }
+
+    /// Records how many of the host's seed changes a guest has received.
+    pub fn rx_guest_seeds_ack(&mut self, player_num: PlayerNum, msg: MsgPayload<T>) {
+        if self.inner.guests_pending_round_ack.contains(&player_num) {
+            return;
+        }
+        if let MsgPayload::GuestToHostAckSeeds(num_seen) = msg {
+            self.inner.guests_seeds_seen.insert(player_num, num_seen);
+        }
+    }
+
+    /// Gets the oldest seed change that at least one live guest hasn't acked yet, or an empty message if every live guest is current. Disconnected and muted guests aren't waited for.
+    ///
+    /// This message should be broadcast to all guests, e.g. alongside the host's finalized slices.
+    pub fn get_msg_seed(&self) -> MsgPayload<T> {
+        let num_seen = self
+            .live_guests()
+            .map(|guest| {
+                self.inner
+                    .guests_seeds_seen
+                    .get(&guest)
+                    .copied()
+                    .unwrap_or(0)
+            })
+            .min()
+            .unwrap_or(0);
+        match self.seeds.change(num_seen) {
+            Some(change) => MsgPayload::HostToLobbySeed(change),
+            None => MsgPayload::Empty,
+        }
+    }
+
+    // Session end //////////////////////////////
+
+    /// Ends the session gracefully at sim tick `final_tick` (see `session_limit`), returning the final tick actually used.
+    ///
+    /// No inputs for ticks at or past the final tick are accepted. Once the host's own inputs reach it, every player's inputs are finalized up to exactly that tick, with default inputs for any a guest never sent; inputs still held for review are finalized as they are. The end reaches guests via `get_msg_end_session`.
+    ///
+    /// The final tick can't be before inputs that are already collected (the host's) or finalized (the guests'), so earlier ticks are raised to the earliest possible one, and it can't be past the session's configured length. Once set, the end can't be changed.
+    pub fn end_session_at(&mut self, final_tick: u32) -> u32 {
+        if self.session_limit.end_requested {
+            return self.session_limit.max_ticks;
+        }
+        let earliest_end = PlayerNum::iter(self.buffers.num_players())
+            .map(|player_num| {
+                if player_num == HOST_PLAYER_NUM {
+                    self.buffers.get_num_inputs(player_num)
+                } else {
+                    self.buffers.get_num_finalized_inputs(player_num)
+                }
+            })
+            .max()
+            .unwrap_or(0);
+        self.session_limit.max_ticks = final_tick
+            .max(self.buffers.tick_of_input(earliest_end))
+            .min(self.session_limit.max_ticks);
+        self.session_limit.end_requested = true;
+        self.finalize_to_session_end();
+        self.observe_session_end();
+        self.session_limit.max_ticks
+    }
+
+    /// Once the host's own inputs have reached a requested end (see `end_session_at`), finalizes every player's inputs up to it.
+    fn finalize_to_session_end(&mut self) {
+        if !self.session_limit.end_requested || !self.is_session_ended() {
+            return;
+        }
+        let end = self
+            .buffers
+            .num_inputs_before_tick(self.session_limit.max_ticks);
+        for player_num in PlayerNum::iter(self.buffers.num_players()) {
+            let num_collected = self.buffers.get_num_inputs(player_num).min(end);
+            while self.buffers.get_num_finalized_inputs(player_num) < num_collected {
+                self.buffers.finalize_next_collected_input(player_num);
+            }
+            if self.buffers.get_num_finalized_inputs(player_num) < end {
+                self.buffers
+                    .append_final_default_inputs_to_target(player_num, end - 1);
+            }
+        }
+        self.inner.pending_review.clear();
+        self.inner.newest_slice_ends.clear();
+        self.inner.replaced_pending_review.clear();
+        self.after_inputs_finalized();
+    }
+
+    /// Records a guest's ack of the end of the session.
+    pub fn rx_guest_end_ack(&mut self, player_num: PlayerNum, msg: MsgPayload<T>) {
+        if let MsgPayload::GuestToHostEndAck(final_tick) = msg
+            && self.session_limit.end_requested
+            && final_tick == self.session_limit.max_ticks
+            && !self.inner.guests_acked_end.contains(&player_num)
+        {
+            self.inner.guests_acked_end.push(player_num);
+        }
+    }
+
+    /// True once every connected guest has acked the end of the session (see `end_session_at`). Removed and disconnected guests aren't waited for.
+    pub fn all_guests_acked_end(&self) -> bool {
+        self.session_limit.end_requested
+            && self
+                .connected_guests()
+                .all(|guest| self.inner.guests_acked_end.contains(&guest))
+    }
+
+    /// Gets the end of the session if at least one guest hasn't acked it yet, or an empty message if the session hasn't been ended with `end_session_at` or every guest has acked it.
+    ///
+    /// This message should be broadcast to all guests, e.g. alongside the host's finalized slices.
+    pub fn get_msg_end_session(&self) -> MsgPayload<T> {
+        if !self.session_limit.end_requested || self.all_guests_acked_end() {
+            return MsgPayload::Empty;
+        }
+        MsgPayload::HostToLobbyEndSession(self.session_limit.max_ticks)
+    }
+
+    // Pings and Pongs //////////////////////////////
+
+    pub fn rx_guest_ping_and_reply(
+        &mut self,
+        player_num: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> MsgPayload<T> {
+        if let MsgPayload::GuestToHostPing(id) = msg {
+            self.mark_seen_in_lobby(player_num);
+            self.inner
+                .health
+                .entry(player_num)
+                .or_default()
+                .observe_ping(id);
+            self.inner
+                .pong_send_times
+                .entry(player_num)
+                .or_default()
+                .record_pong_send(id);
+
+            MsgPayload::HostToGuestPong(id)
+        } else {
+            panic!("fn rx_guest_ping can only handle GuestPing message")
+        }
+    }
+
+    /// Completes a ping cycle with a guest, returning the `PingReport` to send back to that guest.
+    ///
+    /// Also accepts a `GuestToHostLegacyPongPong` from an older guest, which carries no guest RTT, so no report is returned for it unless the guest reported one before.
+    pub fn rx_guest_pong_pong(
+        &mut self,
+        player_num: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> Result<MsgPayload<T>, String> {
+        let (ping_id, guest_rtt_micros) = match &msg {
+            MsgPayload::GuestToHostPongPong(pong_pong) => {
+                (pong_pong.ping_id, Some(pong_pong.guest_rtt_micros))
+            }
+            MsgPayload::GuestToHostLegacyPongPong(ping_id) => (*ping_id, None),
+            _ => return Err("fn rx_guest_pong can only handle GuestPong message".into()),
+        };
+        let rtt = self
+            .inner
+            .pong_send_times
+            .get_mut(&player_num)
+            .unwrap()
+            .observe_pong_reply(ping_id);
+
+        if rtt.is_err() {
+            return Err(format!(
+                "rx_guest_pong_pong msg id not found for player {:?}; msg payload: {:?}",
+                player_num, msg
+            ));
+        }
+
+        self.observe_guest_rtt_ms(player_num, rtt.unwrap());
+        self.mark_seen_in_lobby(player_num);
+        if let Some(guest_rtt_micros) = guest_rtt_micros {
+            self.inner
+                .guest_reported_rtts
+                .insert(player_num, guest_rtt_micros);
+        }
+
+        Ok(self
+            .ping_report(player_num)
+            .map_or(MsgPayload::Empty, MsgPayload::HostToGuestPingReport))
+    }
+
+    /// Replies to a guest's clock ping with the host's sim time, for the guest to estimate how far the host's clock is ahead of its own (see `estimated_host_tick_now` on the guest).
+    ///
+    /// The host's clock only runs while it collects its own inputs, so before its first input, while recovering, and for anything but a clock ping, the reply is `MsgPayload::Empty`.
+    pub fn rx_clock_ping_and_reply(
+        &mut self,
+        player_num: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> MsgPayload<T> {
+        let MsgPayload::GuestToHostClockPing(ping) = msg else {
+            return MsgPayload::Empty;
+        };
+        self.mark_seen_in_lobby(player_num);
+        if self.host_tick() == 0 || self.is_recovering() {
+            return MsgPayload::Empty;
+        }
+        MsgPayload::HostToGuestClockPong(ClockPong {
+            guest_send_micros: ping.guest_send_micros,
+            host_micros: (self.inner.sim_time * 1_000_000.0).round() as i64,
+        })
+    }
+
+    /// This guest's RTT measurement compared with the host's; `None` until a ping cycle with the guest has completed.
+    pub fn ping_report(&self, player_num: PlayerNum) -> Option<PingReport> {
+        let guest_rtt_micros = *self.inner.guest_reported_rtts.get(&player_num)?;
+        let host_rtt_ms = self.inner.rtts.get(&player_num)?.value()?;
+        Some(PingReport::new(
+            guest_rtt_micros,
+            ms_to_micros(host_rtt_ms) as u32,
+        ))
+    }
+
+    /// Folds an RTT sample into this guest's smoothed RTT.
+    pub(super) fn observe_guest_rtt_ms(&mut self, player_num: PlayerNum, rtt_ms: f32) {
+        self.inner
+            .rtts
+            .entry(player_num)
+            .or_default()
+            .observe(rtt_ms, &self.rtt_config);
+        self.change_counters.rtt += 1;
+    }
+
+    // HostFinalizedSlice //////////////////////////////
+
+    /// True if this guest has gone longer than `with_observation_staleness_timeout_sec` without acking finalized inputs, so its acks no longer hold back the start of broadcast slices. Always false without a timeout.
+    pub fn is_observation_stale(&self, guest: PlayerNum) -> bool {
+        let Some(timeout_sec) = self.inner.observation_staleness_timeout_sec else {
+            return false;
+        };
+        let last_ack = self
+            .inner
+            .last_ack_sim_times
+            .get(&guest)
+            .copied()
+            .unwrap_or(0.0);
+        self.inner.sim_time - last_ack > f64::from(timeout_sec)
+    }
+
+    /// The guests whose observations are stale (see `is_observation_stale`).
+    pub fn guests_with_stale_observations(&self) -> Vec<PlayerNum> {
+        self.active_guests()
+            .filter(|guest| self.is_observation_stale(*guest))
+            .collect()
+    }
+
+    /// Gets the finalized input slice for this peer
+    /// needed by guests
+    pub fn get_msg_finalized_slice(&self, player_num: PlayerNum) -> MsgPayload<T> {
+        // get the earliest tick that has been finalized across all peers
+        let start = self.broadcast_start(player_num);
+
+        let slice = self.buffers.get_finalized_slice_for_peer(player_num, start);
+
+        HostFinalizedSlice {
+            player_num,
+            host_tick: self.host_tick(),
+            inputs: slice,
+        }
+        .into()
+    }
+
+    /// Like `get_msg_finalized_slice`, but split into several messages if it would be longer than the max payload size (see `with_max_payload_bytes`).
+    pub fn get_msgs_finalized_slice(&self, player_num: PlayerNum) -> Vec<MsgPayload<T>> {
+        self.split_to_max_payload(self.get_msg_finalized_slice(player_num))
+    }
+
+    /// Like `get_msg_finalized_slice`, but serializes the message straight from the buffer (as by `MsgPayload::to_bytes`), without first copying the inputs into an owned message.
+    ///
+    /// Prefer this when broadcasting every frame.
+    pub fn get_msg_bytes_finalized_slice(&self, player_num: PlayerNum) -> Vec<u8> {
+        let start = self.broadcast_start(player_num);
+
+        HostFinalizedSliceRef {
+            player_num,
+            host_tick: self.host_tick(),
+            inputs: self
+                .buffers
+                .borrow_finalized_slice_for_peer(player_num, start),
+        }
+        .to_msg_bytes()
+    }
+
+    /// Like `get_msg_bytes_finalized_slice`, but split into several messages if it would be longer than the max payload size (see `with_max_payload_bytes`).
+    pub fn get_msgs_bytes_finalized_slice(&self, player_num: PlayerNum) -> Vec<Vec<u8>> {
+        let start = self.broadcast_start(player_num);
+        let slice = HostFinalizedSliceRef {
+            player_num,
+            host_tick: self.host_tick(),
+            inputs: self
+                .buffers
+                .borrow_finalized_slice_for_peer(player_num, start),
+        };
+        match self.max_payload_bytes {
+            Some(max_bytes) => slice.to_msg_bytes_split(max_bytes),
+            None => vec![slice.to_msg_bytes()],
+        }
+    }
+
+    /// Bundles everything the host broadcasts in a frame into one message (see `MsgPayload::batch`): every player's finalized slice, the host's provisional inputs, and any catch-up slices (see `poll_catch_up`). With a send window, the finalized slices are left out, to be sent to each guest on its own (see `get_msgs_windowed_finalized_slices_for_guests`).
+    ///
+    /// `delta` is the time (sec) since the last call, for `poll_catch_up`. The returned message must be broadcast to all guests.
+    pub fn get_outgoing_msgs_for_frame(&mut self, delta: f32) -> MsgPayload<T> {
+        let mut msgs: Vec<MsgPayload<T>> = if self.inner.send_window_ticks.is_some() {
+            vec![]
+        } else {
+            PlayerNum::iter(self.buffers.num_players())
+                .map(|player_num| self.get_msg_finalized_slice(player_num))
+                .collect()
+        };
+        msgs.push(self.get_msg_provisional_own_inputs());
+        msgs.extend(self.poll_catch_up(delta).into_iter().map(|(_, msg)| msg));
+        MsgPayload::batch(msgs)
+    }
+
+    /// Like `get_outgoing_msgs_for_frame`, but as several batches if one would be longer than the max payload size (see `with_max_payload_bytes`), with oversized slices split.
+    pub fn get_outgoing_batches_for_frame(&mut self, delta: f32) -> Vec<MsgPayload<T>> {
+        let batch = self.get_outgoing_msgs_for_frame(delta);
+        self.split_to_max_payload(batch)
+    }
+
+    /// The finalized slices for this guest alone, each starting from what this guest has acked for that player, and truncated to the send window (see `with_send_window_ticks`).
+    ///
+    /// Players whose inputs this guest is already current on are skipped. Unlike `get_msg_finalized_slice`, these messages are meant for this guest only, and should not be broadcast; use them instead of the broadcast when links differ widely in speed. Each slice is split to the max payload size (see `with_max_payload_bytes`).
+    pub fn get_msgs_windowed_finalized_slices(&self, guest: PlayerNum) -> Vec<MsgPayload<T>> {
+        PlayerNum::iter(self.buffers.num_players())
+            .filter_map(|peer| self.windowed_finalized_slice_for_guest(peer, guest))
+            .flat_map(|slice| self.split_to_max_payload(slice.into()))
+            .collect()
+    }
+
+    /// Every active guest's windowed finalized slices of the `subjects`' inputs (see `get_msgs_windowed_finalized_slices`), each addressed to its guest.
+    ///
+    /// With a send window, `Session` sends these in place of the broadcast finalized slices, so each guest is sent at most a window past its own acks, rather than the history back to the slowest guest's.
+    pub fn get_msgs_windowed_finalized_slices_for_guests(
+        &self,
+        subjects: &[PlayerNum],
+    ) -> OutgoingMsgs<T> {
+        self.active_guests()
+            .flat_map(|guest| {
+                subjects
+                    .iter()
+                    .filter_map(move |&subject| {
+                        self.windowed_finalized_slice_for_guest(subject, guest)
+                    })
+                    .flat_map(move |slice| {
+                        self.split_to_max_payload(slice.into())
+                            .into_iter()
+                            .map(move |part| (Recipient::Player(guest), part))
+                    })
+            })
+            .collect()
+    }
+
+    // `finalized_slice_for_guest`, truncated to the send window
+    fn windowed_finalized_slice_for_guest(
+        &self,
+        subject: PlayerNum,
+        recipient: PlayerNum,
+    ) -> Option<HostFinalizedSlice<T>> {
+        let mut slice = self.finalized_slice_for_guest(subject, recipient)?;
+        if let Some(window) = self.inner.send_window_ticks {
+            let end = slice.inputs.start.saturating_add(window);
+            slice.inputs.truncate_before(end);
+        }
+        Some(slice)
+    }
+
+    /// The finalized slice of `subject`'s inputs for `recipient` alone, starting from what the recipient has acked for `subject` rather than from the fewest acked by any guest (see `get_msg_finalized_slice`). Returns an empty message if the recipient is already current on `subject`.
+    ///
+    /// The message is meant for `recipient` only, and should not be broadcast; see `get_msgs_finalized_slices_for_guests` for every guest's slices, addressed.
+    pub fn get_msg_finalized_slice_for_guest(
+        &self,
+        subject: PlayerNum,
+        recipient: PlayerNum,
+    ) -> MsgPayload<T> {
+        self.finalized_slice_for_guest(subject, recipient)
+            .map_or(MsgPayload::Empty, MsgPayload::from)
+    }
+
+    /// Every guest's finalized slices (see `get_msg_finalized_slice_for_guest`), each addressed to its guest, for sending personalized slices in place of the broadcast ones. Slices of players a guest is current on are skipped, as are removed guests. Each slice is split to the max payload size (see `with_max_payload_bytes`).
+    ///
+    /// The slices sent should be recorded (see `record_msg_sent`), as `Session::get_finalized_slices_for_guests` does.
+    pub fn get_msgs_finalized_slices_for_guests(&self) -> OutgoingMsgs<T> {
+        let num_players = self.buffers.num_players();
+        self.active_guests()
+            .flat_map(|guest| {
+                PlayerNum::iter(num_players)
+                    .filter_map(move |subject| self.finalized_slice_for_guest(subject, guest))
+                    .flat_map(move |slice| {
+                        self.split_to_max_payload(slice.into())
+                            .into_iter()
+                            .map(move |part| (Recipient::Player(guest), part))
+                    })
+            })
+            .collect()
+    }
+
+    // the finalized inputs of `subject` past those `recipient` has acked, if any
+    fn finalized_slice_for_guest(
+        &self,
+        subject: PlayerNum,
+        recipient: PlayerNum,
+    ) -> Option<HostFinalizedSlice<T>> {
+        let acked = self
+            .inner
+            .guests_finalized_observations
+            .get_guest_observation(recipient, subject);
+        if acked >= self.buffers.get_num_finalized_inputs(subject) {
+            return None;
+        }
+        Some(HostFinalizedSlice {
+            player_num: subject,
+            host_tick: self.host_tick(),
+            inputs: self.buffers.get_finalized_slice_for_peer(subject, acked),
+        })
+    }
+
+    // Spectators //////////////////////////////
+
+    /// Registers `connection` as a spectator (see `SpectatorInputMgr`): a connection that controls no seat, and follows the session through finalized slices sent to it alone (see `get_msgs_finalized_slices_for_spectators`). Its acks are kept apart from the guests', so it never holds back broadcasts, retention or any other finalization gating.
+    ///
+    /// Errors if `connection` controls a seat. Registering a spectator again keeps its acks.
+    pub fn add_spectator(&mut self, connection: PlayerNum) -> Result<(), String> {
+        if self.seat_for_connection(connection).is_some() {
+            return Err(format!(
+                "connection {connection:?} controls a seat, so it can't spectate"
+            ));
+        }
+        let num_players = self.buffers.num_players();
+        self.inner
+            .spectators
+            .entry(connection)
+            .or_insert_with(|| PeerwiseFinalizedInputsSeen::new(num_players));
+        Ok(())
+    }
+
+    /// Forgets a spectator, returning false if `connection` wasn't one.
+    pub fn remove_spectator(&mut self, connection: PlayerNum) -> bool {
+        self.inner.spectators.remove(&connection).is_some()
+    }
+
+    pub fn is_spectator(&self, connection: PlayerNum) -> bool {
+        self.inner.spectators.contains_key(&connection)
+    }
+
+    /// The connections registered as spectators, in order.
+    pub fn spectators(&self) -> Vec<PlayerNum> {
+        self.inner.spectators.keys().copied().collect()
+    }
+
+    /// The number of `player_num`'s finalized inputs this spectator has acked; 0 for connections that aren't spectators.
+    pub fn spectator_num_acked(&self, connection: PlayerNum, player_num: PlayerNum) -> u32 {
+        self.inner
+            .spectators
+            .get(&connection)
+            .map_or(0, |acked| acked.get(player_num))
+    }
+
+    /// Records a spectator's finalization ack, clamped to the inputs the host has finalized. Unlike a guest's ack (see `rx_finalized_ticks_observations`), it only moves where the spectator's next slices start.
+    pub fn rx_spectator_ack(&mut self, connection: PlayerNum, msg: MsgPayload<T>) -> RxOutcome {
+        let MsgPayload::GuestToHostAckFinalization(ack) = msg else {
+            return RxOutcome::rejected(RxRejection::UnexpectedMsg);
+        };
+        let num_players = self.buffers.num_players();
+        let clamped: Vec<u32> = PlayerNum::iter(num_players)
+            .map(|player_num| {
+                ack.get(player_num)
+                    .min(self.buffers.get_num_finalized_inputs(player_num))
+            })
+            .collect();
+        let Some(acked) = self.inner.spectators.get_mut(&connection) else {
+            return RxOutcome::rejected(RxRejection::UnknownSpectator);
+        };
+        *acked = PeerwiseFinalizedInputsSeen::new_from_observed(num_players, &clamped);
+        RxOutcome::default()
+    }
+
+    /// Handles a message from a spectator's connection (see `add_spectator`), returning the replies to send: only finalization acks are accepted from spectators.
+    pub fn handle_spectator_msg(
+        &mut self,
+        connection: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> Result<OutgoingMsgs<T>, SessionRxError> {
+        if !self.is_spectator(connection) {
+            return Err(SessionRxError::InvalidSender);
+        }
+        match msg {
+            MsgPayload::Batch(msgs) => {
+                for msg in msgs {
+                    // like `handle_msg`, a rejected message of a batch is skipped
+                    let _ = self.handle_spectator_msg(connection, msg);
+                }
+            }
+            MsgPayload::GuestToHostAckFinalization(_) => {
+                self.rx_spectator_ack(connection, msg);
+            }
+            // acks are consumed by `decode_sequenced_msg_from_peer`
+            MsgPayload::Empty | MsgPayload::DeliveryAck(_) => {}
+            _ => return Err(SessionRxError::WrongRole),
+        }
+        Ok(vec![])
+    }
+
+    /// Every spectator's finalized slices: for each player, the finalized inputs past those the spectator has acked, addressed to its connection. Slices of players a spectator is current on are skipped.
+    ///
+    /// Spectators aren't seats, so they get none of the broadcast messages; send them these instead, e.g. each frame. Slices start no earlier than the oldest input the host retains (see `with_max_retained_inputs`), which spectators don't hold back.
+    pub fn get_msgs_finalized_slices_for_spectators(&self) -> OutgoingMsgs<T> {
+        let num_players = self.buffers.num_players();
+        self.inner
+            .spectators
+            .iter()
+            .flat_map(|(&connection, acked)| {
+                PlayerNum::iter(num_players).filter_map(move |subject| {
+                    let start = acked.get(subject);
+                    if start >= self.buffers.get_num_finalized_inputs(subject) {
+                        return None;
+                    }
+                    let slice = HostFinalizedSlice {
+                        player_num: subject,
+                        host_tick: self.host_tick(),
+                        inputs: self.buffers.get_finalized_slice_for_peer(subject, start),
+                    };
+                    Some((Recipient::Player(connection), slice.into()))
+                })
+            })
+            .collect()
+    }
+
+    /// Each guest's own finalized slices, if adaptive slices are enabled (see `slice_throttle`), paired with the guest each is for; `delta` is the time (sec) since the last call.
+    ///
+    /// For each player, a guest is sent the finalized inputs past those recorded as sent to it (see `record_msg_sent`), or, once per resend interval, past those it has acked, truncated to the send window (see `with_send_window_ticks`). The messages must be sent to their guest only, and recorded as sent with `record_msg_sent`, which is what lets the next call skip them; use them instead of the broadcast finalized slices. Guests that haven't acked the current round are skipped.
+    pub fn poll_adaptive_finalized_slices(
+        &mut self,
+        delta: f32,
+    ) -> Vec<(PlayerNum, MsgPayload<T>)> {
+        if self.inner.slice_throttle.config.is_none() {
+            return vec![];
+        }
+        let mut msgs = vec![];
+        let guests: Vec<PlayerNum> = self.active_guests().collect();
+        for guest in guests {
+            if self.inner.guests_pending_round_ack.contains(&guest) {
+                continue;
+            }
+            let num_players = self.buffers.num_players();
+            let acked: Vec<u32> = PlayerNum::iter(num_players)
+                .map(|peer| {
+                    self.inner
+                        .guests_finalized_observations
+                        .get_guest_observation(guest, peer)
+                })
+                .collect();
+            // only recorded slices count as sent here, unlike in `max_ackable`
+            let sent: Vec<u32> = self
+                .inner
+                .finalized_sent
+                .get(&guest)
+                .map(|sent| sent.iter().map(|sent| sent.unwrap_or(0)).collect())
+                .unwrap_or_else(|| vec![0; usize::from(num_players)]);
+            let resend = self
+                .inner
+                .slice_throttle
+                .poll_resend_due(guest, delta, &acked, &sent);
+            for peer in PlayerNum::iter(num_players) {
+                let acked = acked[usize::from(peer)];
+                let start = if resend {
+                    acked
+                } else {
+                    acked.max(sent[usize::from(peer)])
+                };
+                let mut inputs = self.buffers.get_finalized_slice_for_peer(peer, start);
+                if let Some(window) = self.inner.send_window_ticks {
+                    inputs.truncate_before(acked.saturating_add(window));
+                }
+                if inputs.is_empty() {
+                    continue;
+                }
+                msgs.push((
+                    guest,
+                    HostFinalizedSlice {
+                        player_num: peer,
+                        host_tick: self.host_tick(),
+                        inputs,
+                    }
+                    .into(),
+                ));
+            }
+        }
+        msgs
+    }
+
+    /// The finalized inputs sent to this guest, and how many of them it had already acked when they were sent; only messages recorded as sent are counted (see `record_msg_sent`).
+    pub fn slice_redundancy(&self, guest: PlayerNum) -> SliceRedundancy {
+        self.inner.slice_throttle.redundancy(guest)
+    }
+
+    /// The current interval (sec) between resends of this guest's unacked inputs by `poll_adaptive_finalized_slices`; `None` unless adaptive slices are enabled.
+    pub fn adaptive_resend_interval_sec(&self, guest: PlayerNum) -> Option<f32> {
+        self.inner.slice_throttle.resend_interval_sec(guest)
+    }
+
+    /// The number of finalized ticks this guest hasn't acked yet, for the player it is furthest behind on.
+    ///
+    /// With a send window, at most `send_window_ticks` of these are in flight at once; an occupancy at or above the window means the link is applying backpressure.
+    pub fn send_window_occupancy(&self, guest: PlayerNum) -> u32 {
+        PlayerNum::iter(self.buffers.num_players())
+            .map(|peer| {
+                let acked = self
+                    .inner
+                    .guests_finalized_observations
+                    .get_guest_observation(guest, peer);
+                self.buffers
+                    .get_num_finalized_inputs(peer)
+                    .saturating_sub(acked)
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Gets the host's own inputs that have been collected under input delay but are not yet finalized.
+    ///
+    /// Guests store these as non-final inputs (see `rx_peer_input_slice`), so they are used instead of a last-observation-carried-forward prediction for the host until the finalized inputs arrive. Returns an empty message if no such inputs are held.
+    ///
+    /// This message should be broadcast to all guests.
+    pub fn get_msg_provisional_own_inputs(&self) -> MsgPayload<T> {
+        let start = self.host_tick();
+        if self.get_own_num_inputs() <= start {
+            return MsgPayload::Empty;
+        }
+        self.buffers
+            .get_slice_to_end_for_peer(HOST_PLAYER_NUM, start)
+            .into()
+    }
+
+    // Lobby //////////////////////////////
+
+    /// Advances the lobby clock used for `LobbyPeerStatus::last_seen_ms` and `ticks_until_start`; `delta` is the time (sec) since the last call.
+    pub fn advance_lobby_time(&mut self, delta: f32) {
+        self.inner.lobby_time += delta;
+    }
+
+    /// The lobby clock in ticks at the session's input rate, counting from 0 (see `start_sync`).
+    pub fn lobby_tick(&self) -> u32 {
+        (self.inner.lobby_time * self.inputs_per_sec()) as u32
+    }
+
+    fn mark_seen_in_lobby(&mut self, player_num: PlayerNum) {
+        self.inner
+            .last_seen_lobby_times
+            .insert(player_num, self.inner.lobby_time);
+    }
+
+    /// Each guest's RTT, ping count and time since last heard from, for deciding when to start the pre-sim countdown.
+    pub fn lobby_readiness(&self) -> Vec<(PlayerNum, LobbyPeerStatus)> {
+        self.active_guests()
+            .map(|guest| {
+                let status = LobbyPeerStatus {
+                    rtt_ms: self.inner.rtts.get(&guest).and_then(RttEstimate::value),
+                    pings_exchanged: self
+                        .inner
+                        .rtts
+                        .get(&guest)
+                        .map_or(0, RttEstimate::num_samples),
+                    last_seen_ms: self
+                        .inner
+                        .last_seen_lobby_times
+                        .get(&guest)
+                        .map(|seen| (self.inner.lobby_time - seen) * 1000.0),
+                };
+                (guest, status)
+            })
+            .collect()
+    }
+
+    /// A countdown long enough for the `PreSimSync` to reach the slowest guest before the sim starts: the worst guest RTT in ticks, plus `countdown_margin_ticks`.
+    ///
+    /// `None` until an RTT has been measured for every guest.
+    pub fn recommended_countdown_ticks(&self) -> Option<u8> {
+        let worst_rtt_ms = self
+            .lobby_readiness()
+            .into_iter()
+            .map(|(_, status)| status.rtt_ms)
+            .try_fold(0.0f32, |worst, rtt| Some(worst.max(rtt?)))?;
+        let rtt_ticks = (worst_rtt_ms / 1000.0 * self.inputs_per_sec()).ceil() as u32;
+        let countdown = rtt_ticks + self.inner.countdown_margin_ticks;
+        Some(countdown.min(u8::MAX as u32) as u8)
+    }
+
+    /// Like `get_msg_pre_sim_sync`, with the countdown from `recommended_countdown_ticks`.
+    ///
+    /// `None` until an RTT has been measured for every guest.
+    pub fn get_msg_pre_sim_sync_with_recommended_countdown(&mut self) -> Option<MsgPayload<T>> {
+        Some(self.get_msg_pre_sim_sync(self.recommended_countdown_ticks()?))
+    }
+
+    // Start agreement //////////////////////////////
+
+    /// Proposes starting the sim `countdown_ticks` lobby ticks from now, replacing any earlier proposal (see `start_sync`). The proposal should be broadcast to all guests, which reply with a `GuestToHostStartAck`.
+    ///
+    /// Once a start has been confirmed it can't be moved, and the confirmation is returned instead.
+    pub fn propose_start(&mut self, countdown_ticks: u32) -> MsgPayload<T> {
+        if self.inner.start_agreement.confirmed_start().is_some() {
+            return self.get_msg_start_confirmed();
+        }
+        let lobby_tick = self.lobby_tick();
+        MsgPayload::HostToLobbyStartProposal(
+            self.inner
+                .start_agreement
+                .propose(lobby_tick, countdown_ticks),
+        )
+    }
+
+    /// Handles a guest's ack of a start proposal. Acks of earlier proposals are ignored.
+    pub fn rx_start_ack(&mut self, player_num: PlayerNum, msg: MsgPayload<T>) {
+        if let MsgPayload::GuestToHostStartAck(ack) = msg
+            && player_num.is_guest()
+        {
+            self.inner.start_agreement.receive_ack(player_num, ack);
+        }
+    }
+
+    /// Gets the confirmation of the start tick once every connected guest has acked the latest proposal, or an empty message until then; guests that disconnect before acking aren't waited for. The first confirmation fixes the start; later ones repeat it with the current lobby tick, and should be sent until the sim starts.
+    ///
+    /// This message should be broadcast to all guests.
+    pub fn get_msg_start_confirmed(&mut self) -> MsgPayload<T> {
+        let lobby_tick = self.lobby_tick();
+        let guests: Vec<PlayerNum> = self.connected_guests().collect();
+        match self.inner.start_agreement.confirm(lobby_tick, guests) {
+            Some(start) => MsgPayload::HostToLobbyStartConfirmed(start),
+            None => MsgPayload::Empty,
+        }
+    }
+
+    /// The lobby ticks left until the confirmed start, negative once it has passed; `None` until the start has been confirmed.
+    pub fn ticks_until_start(&self) -> Option<i32> {
+        let start = self.inner.start_agreement.confirmed_start()?;
+        Some((start as i64 - self.lobby_tick() as i64) as i32)
+    }
+
+    // PreSimSync //////////////////////////////
+
+    /// Gets the countdown message sent to guests before the sim starts. It carries the session's player list (every seat, including the host's) and the session config guests adopt: start tick, initial seed, sim ticks per input, tick rate and session length.
+    ///
+    /// Building it moves the host from `SessionPhase::Lobby` to `SessionPhase::Countdown`. This message should be broadcast to all guests.
+    pub fn get_msg_pre_sim_sync(&mut self, host_tick_countdown: u8) -> MsgPayload<T> {
+        self.inner.pre_sim_sync_sent = true;
+        PreSimSync {
+            host_tick_countdown,
+            peers: self
+                .buffers
+                .get_peer_player_nums()
+                .into_iter()
+                .map(u32::from)
+                .collect(),
+            start_tick: self.start_tick(),
+            initial_seed: self.seeds.initial,
+            sim_ticks_per_input: self.sim_ticks_per_input(),
+            ticks_per_sec: self.ticks_per_sec,
+            max_session_ticks: self.session_limit.max_ticks,
+            input_schema_id: T::SCHEMA_ID,
+            capabilities: self.capabilities,
+            player_metadata: self
+                .player_metadata
+                .iter()
+                .map(|(player_num, metadata)| (*player_num, metadata.clone()))
+                .collect(),
+        }
+        .into()
+    }
+
+    /// Registers display metadata for a seat, e.g. the player's name (see `player_metadata`), replacing any registered before.
+    ///
+    /// Guests receive it in the `PreSimSync`, so it should be registered before building that message; later changes reach guests with the next one.
+    pub fn register_player_metadata(
+        &mut self,
+        player_num: PlayerNum,
+        metadata: impl Into<Vec<u8>>,
+    ) -> Result<(), PlayerMetadataError> {
+        let metadata = metadata.into();
+        self.validate_player_metadata(player_num, &metadata)?;
+        self.player_metadata.insert(player_num, metadata);
+        Ok(())
+    }
+
+    /// Checks a guest's input schema id against the host's (see `input_schema`).
+    ///
+    /// A guest whose schema differs should be rejected, e.g. by muting it with `mute_player` (as `Session` does) or disconnecting it, since its input bytes can't be read.
+    pub fn rx_guest_input_schema(
+        &mut self,
+        player_num: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> Result<(), InputSchemaMismatch> {
+        match msg {
+            MsgPayload::GuestToHostInputSchema(schema_id) => {
+                self.check_input_schema(player_num, schema_id)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Stores the capabilities a guest confirmed from the `PreSimSync` as the set negotiated with it (see `capabilities`), returning that set.
+    ///
+    /// Capabilities the host hasn't enabled are dropped, so the negotiated set is always one both sides support.
+    pub fn rx_guest_capabilities(
+        &mut self,
+        player_num: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> Capabilities {
+        match msg {
+            MsgPayload::GuestToHostCapabilities(capabilities) => {
+                self.negotiate_capabilities(player_num, capabilities)
+            }
+            _ => self.negotiated_capabilities(player_num),
+        }
+    }
+
+    /// Checks a guest's input hashes against the host's own for each tick it has finalized (see `desync_report`), returning the ticks that disagree. The first desynced tick found for a guest also raises an `InputMgrEvent::InputDesync`.
+    ///
+    /// Ticks the host hasn't finalized for all players aren't checked, and reports from guests that haven't acked the current round are ignored.
+    pub fn rx_input_hash_report(&mut self, player_num: PlayerNum, msg: MsgPayload<T>) -> Vec<u32> {
+        let MsgPayload::GuestToHostInputHashReport(report) = msg else {
+            return vec![];
+        };
+        if !player_num.is_guest()
+            || u8::from(player_num) >= self.buffers.num_players()
+            || self.inner.guests_pending_round_ack.contains(&player_num)
+        {
+            return vec![];
+        }
+        let checked_ticks = self.start_tick()..self.get_snapshottable_sim_tick();
+        let desynced: Vec<u32> = report
+            .iter()
+            .filter(|(tick, hash)| {
+                checked_ticks.contains(tick) && *hash != self.tick_input_digest(*tick)
+            })
+            .map(|(tick, _)| tick)
+            .collect();
+        for &tick in &desynced {
+            if self.inner.desyncs.record(player_num, tick) {
+                self.events.push(InputMgrEvent::InputDesync {
+                    peer: player_num,
+                    tick,
+                });
+            }
+        }
+        desynced
+    }
+
+    /// The ticks at which each guest's input hashes have disagreed with the host's in the current round, so that the game can resync those guests rather than let their sims diverge (see `desync_report`).
+    pub fn get_desync_report(&self) -> DesyncReport {
+        self.inner.desyncs.report()
+    }
+
+    /// Where the host is in the session's lifecycle: in the lobby until the `PreSimSync` is built or a start is proposed, then counting down until its first input is collected.
+    pub fn session_phase(&self) -> SessionPhase {
+        if self.is_session_ended() {
+            SessionPhase::Ended
+        } else if self.round > 0 || self.get_own_num_inputs() > 0 {
+            SessionPhase::Running
+        } else if self.inner.pre_sim_sync_sent || self.inner.start_agreement.is_proposed() {
+            SessionPhase::Countdown
+        } else {
+            SessionPhase::Lobby
+        }
+    }
+
+    // Pacing //////////////////////////////
+
+    /// The rate (inputs per second of host sim time) at which new inputs have been arriving from this guest.
+    ///
+    /// `None` until enough inputs have arrived to form an estimate.
+    pub fn guest_input_rate(&self, player_num: PlayerNum) -> Option<f32> {
+        self.inner.input_rates.get(&player_num)?.rate()
+    }
+
+    /// The estimated clock skew of this guest relative to the host in parts per million, derived from `guest_input_rate` and `ticks_per_sec`.
+    ///
+    /// Negative values mean the guest's inputs arrive slower than the tick rate (e.g. a slow clock or dropped frames).
+    pub fn guest_clock_skew_ppm(&self, player_num: PlayerNum) -> Option<f32> {
+        let rate = self.guest_input_rate(player_num)?;
+        Some((rate / self.inputs_per_sec() - 1.0) * 1_000_000.0)
+    }
+
+    /// Gets a message telling this guest its estimated clock skew, so it can nudge its pacing.
+    ///
+    /// Returns an empty message if there is no estimate yet, or if the skew is within the configured threshold.
+    pub fn get_msg_rate_adjust_for_guest(&self, player_num: PlayerNum) -> MsgPayload<T> {
+        match self.guest_clock_skew_ppm(player_num) {
+            Some(skew_ppm) if skew_ppm.abs() >= self.inner.rate_adjust_threshold_ppm as f32 => {
+                MsgPayload::HostToGuestRateAdjust(skew_ppm.round() as i32)
+            }
+            _ => MsgPayload::Empty,
+        }
+    }
+
+    // // Catch Up //////////////////////////////
+
+    /// Checks whether the newest input tick seen by the host is more than
+    /// max_guest_ticks_behind ticks behind the host's local tick.
+    /// If so, the host will add finalized inputs up the target tick,
+    /// and send them to the guest.
+    ///
+    /// Also, if the player is disconnected, the host will add finalized inputs up to the hosts own input and send those.
+    ///
+    /// If not, this function returns an empty message.
+    pub fn get_msg_finalized_late_inputs_for_guest(
+        &mut self,
+        player_num: PlayerNum,
+    ) -> MsgPayload<T> {
+        let target_num_final_inputs = self.catch_up_target(player_num);
+
+        let peer_num_final_inputs = self.buffers.get_num_finalized_inputs(player_num);
+        // check if the peer is behind the target tick
+        if peer_num_final_inputs < target_num_final_inputs {
+            self.buffers
+                .append_final_default_inputs_to_target(player_num, target_num_final_inputs);
+            self.after_inputs_finalized();
+
+            let start = self.broadcast_start(player_num);
+
+            let slice = self.buffers.get_finalized_slice_for_peer(player_num, start);
+
+            HostFinalizedSlice {
+                player_num,
+                host_tick: self.host_tick(),
+                inputs: slice,
+            }
             .into()
         } else {
             MsgPayload::Empty
         }
     }
 
+    /// The number of finalized inputs a guest must have to not need catching up.
+    fn catch_up_target(&self, player_num: PlayerNum) -> u32 {
+        if self.is_filled_with_defaults(player_num) {
+            self.host_tick()
+        } else {
+            self.host_tick()
+                .saturating_sub(self.inner.max_guest_ticks_behind)
+        }
+    }
+
+    /// Checks each guest on its own timer, and returns the catch-up slices (see `get_msg_finalized_late_inputs_for_guest`) for guests that have fallen too far behind, paired with the guest each slice is for.
+    ///
+    /// `delta` is the time (sec) since the last call. Connected guests are only caught up once they are at least `catch_up_hysteresis_ticks` behind the catch-up target; disconnected guests are always filled up to the host's tick. Guests that haven't acked the current round are skipped.
+    ///
+    /// Like all finalized slices, the returned messages must be broadcast to all guests. Each slice is split to the max payload size (see `with_max_payload_bytes`).
+    pub fn poll_catch_up(&mut self, delta: f32) -> Vec<(PlayerNum, MsgPayload<T>)> {
+        let mut msgs = vec![];
+        let guests: Vec<PlayerNum> = self.active_guests().collect();
+        for guest in guests {
+            let elapsed = self.inner.catch_up_timers.entry(guest).or_default();
+            *elapsed += delta;
+            if *elapsed < self.inner.catch_up_check_interval_sec
+                || self.inner.guests_pending_round_ack.contains(&guest)
+            {
+                continue;
+            }
+            *elapsed = 0.0;
+
+            let deficit = self
+                .catch_up_target(guest)
+                .saturating_sub(self.buffers.get_num_finalized_inputs(guest));
+            let min_deficit = if self.is_filled_with_defaults(guest) {
+                1
+            } else {
+                self.inner.catch_up_hysteresis_ticks.max(1)
+            };
+            if deficit < min_deficit {
+                continue;
+            }
+
+            let msg = self.get_msg_finalized_late_inputs_for_guest(guest);
+            if !matches!(msg, MsgPayload::Empty) {
+                if !self.is_filled_with_defaults(guest) {
+                    let sim_time = self.inner.sim_time;
+                    self.inner
+                        .health
+                        .entry(guest)
+                        .or_default()
+                        .observe_stall(sim_time);
+                }
+                msgs.extend(
+                    self.split_to_max_payload(msg)
+                        .into_iter()
+                        .map(|part| (guest, part)),
+                );
+            }
+        }
+        msgs
+    }
+
+    /// Checks each guest against the lag downgrade policy, and downgrades those that have lagged for the policy's sustained period, returning their `mute_player` messages. Does nothing unless opted in with `with_lag_downgrade_policy`.
+    ///
+    /// `delta` is the time (sec) since the last call. A downgraded guest's seat is filled with default inputs from the host's tick on, as if they had disconnected, so one bad connection can't stall the match; the guest stays connected and keeps receiving finalized slices, so the game can move them to a spectator view. An `InputMgrEvent::GuestDowngraded` is raised for each guest downgraded. Disconnected and muted guests, and guests that haven't acked the current round, aren't checked.
+    ///
+    /// The returned messages must be broadcast to all guests.
+    pub fn poll_lagging_guests(&mut self, delta: f32) -> Vec<MsgPayload<T>> {
+        let Some(policy) = self.inner.lag_downgrade_policy else {
+            return vec![];
+        };
+        let mut msgs = vec![];
+        let guests: Vec<PlayerNum> = self.live_guests().collect();
+        for guest in guests {
+            if self.inner.guests_pending_round_ack.contains(&guest) {
+                continue;
+            }
+            let ticks_behind = self.buffers.tick_of_input(self.host_tick()).saturating_sub(
+                self.buffers
+                    .tick_of_input(self.buffers.get_num_inputs(guest)),
+            );
+            if ticks_behind <= policy.max_ticks_behind {
+                self.inner.lagging_times.remove(&guest);
+                continue;
+            }
+            let lagging_time = self.inner.lagging_times.entry(guest).or_default();
+            *lagging_time += delta;
+            if *lagging_time < policy.sustained_sec {
+                continue;
+            }
+            self.inner.lagging_times.remove(&guest);
+            let from_tick = self.buffers.tick_of_input(self.host_tick());
+            msgs.push(self.mute_player(guest, from_tick));
+            self.events.push(InputMgrEvent::GuestDowngraded {
+                player_num: guest,
+                ticks_behind,
+            });
+        }
+        msgs
+    }
+
+    /// The ordered, minimal sequence of finalized slices that brings this guest from the inputs it has acked up to the current snapshottable tick, for reconnect and spectator-join flows.
+    ///
+    /// Each message carries at most `max_inputs_per_sync_msg` inputs. Messages are ordered by tick range first and player second, so that the guest's snapshottable tick advances with each full pass over the players, even if later messages are lost.
+    ///
+    /// Unlike other finalized slices, these messages are meant for this guest only, and should not be broadcast.
+    pub fn sync_plan_for(&self, player_num: PlayerNum) -> Vec<MsgPayload<T>> {
+        let target = self.buffers.get_num_finalized_inputs_across_peers();
+        let page_len = self.inner.max_inputs_per_sync_msg;
+        let starts: Vec<(PlayerNum, u32)> = PlayerNum::iter(self.buffers.num_players())
+            .map(|peer| {
+                let acked = self
+                    .inner
+                    .guests_finalized_observations
+                    .get_guest_observation(player_num, peer);
+                (peer, acked)
+            })
+            .collect();
+
+        let mut msgs = vec![];
+        let mut page_start = starts.iter().map(|(_, s)| *s).min().unwrap_or(target);
+        while page_start < target {
+            let page_end = page_start.saturating_add(page_len).min(target);
+            for &(peer, acked) in &starts {
+                let start = acked.max(page_start);
+                if start >= page_end {
+                    continue;
+                }
+                let mut inputs = self.buffers.get_finalized_slice_for_peer(peer, start);
+                inputs.truncate_before(page_end);
+                msgs.push(
+                    HostFinalizedSlice {
+                        player_num: peer,
+                        host_tick: self.host_tick(),
+                        inputs,
+                    }
+                    .into(),
+                );
+            }
+            page_start = page_end;
+        }
+        msgs
+    }
+
+    // Recovery //////////////////////////////
+
+    /// Puts a freshly restarted host into recovery mode (see `host_recovery`), returning the request to broadcast to all guests. `request_id` should differ from that of any earlier recovery in the match (e.g. a count of host restarts), so that late responses to an earlier request are ignored.
+    ///
+    /// Until recovery finishes, the host collects none of its own inputs and rejects guest input slices. Fails if the host already has inputs, since it can't then have restarted.
+    pub fn begin_recovery(&mut self, request_id: u32) -> Result<MsgPayload<T>, String> {
+        let num_inputs: u32 = PlayerNum::iter(self.buffers.num_players())
+            .map(|player_num| self.buffers.get_num_inputs(player_num))
+            .sum();
+        if num_inputs > 0 {
+            return Err(format!(
+                "the host already has {num_inputs} inputs, so can't recover from a restart"
+            ));
+        }
+        self.host_recovery = Some(HostRecovery::new(request_id));
+        Ok(MsgPayload::HostToLobbyRecoveryRequest(request_id))
+    }
+
+    pub fn is_recovering(&self) -> bool {
+        self.host_recovery.is_some()
+    }
+
+    /// Collects a guest's response to the recovery request. Once every connected guest has responded, recovery finishes (see `finish_recovery`), and the outcome counts the inputs finalized across all players.
+    pub fn rx_recovery_response(&mut self, player_num: PlayerNum, msg: MsgPayload<T>) -> RxOutcome {
+        let MsgPayload::GuestToHostRecoveryResponse(response) = msg else {
+            return RxOutcome::rejected(RxRejection::UnexpectedMsg);
+        };
+        let Some(recovery) = self.host_recovery.as_mut() else {
+            return RxOutcome::rejected(RxRejection::UnexpectedMsg);
+        };
+        if !player_num.is_guest() || response.request_id != recovery.request_id {
+            return RxOutcome::rejected(RxRejection::UnexpectedMsg);
+        }
+        recovery.responses.insert(player_num, response.finalized);
+
+        let all_responded = PlayerNum::iter_guests(self.buffers.num_players())
+            .filter(|guest| {
+                !self.buffers.is_removed(*guest) && !self.inner.disconnected_players.contains(guest)
+            })
+            .all(|guest| recovery.responses.contains_key(&guest));
+        if !all_responded {
+            return RxOutcome::default();
+        }
+        let num_finalized = self.finish_recovery().unwrap_or_default();
+        let newly_finalized = num_finalized.into_iter().sum();
+        RxOutcome {
+            new_inputs: newly_finalized,
+            newly_finalized,
+            rewritten_inputs: 0,
+            rejected: None,
+        }
+    }
+
+    /// Finishes recovery with the responses collected so far, e.g. once a guest that hasn't responded has timed out: for each player, finalizes the longest prefix of inputs on which all responding guests agree, and resumes the host's clock from the end of its own recovered inputs. Returns the number of inputs recovered for each player (indexed by player num), which are also reported in an `InputMgrEvent::HostRecovered`.
+    pub fn finish_recovery(&mut self) -> Result<Vec<u32>, String> {
+        let Some(recovery) = self.host_recovery.take() else {
+            return Err("the host isn't recovering".to_string());
+        };
+        let num_players = self.buffers.num_players();
+        for (player_num, inputs) in
+            PlayerNum::iter(num_players).zip(recovery.reconcile(num_players))
+        {
+            self.buffers.receive_finalized_input_slice_for_player(
+                PlayerInputSlice { start: 0, inputs },
+                player_num,
+            );
+        }
+        let num_finalized: Vec<u32> = PlayerNum::iter(num_players)
+            .map(|player_num| self.buffers.get_num_finalized_inputs(player_num))
+            .collect();
+
+        // each guest has seen what it sent, as far as the host kept it
+        for (guest, finalized) in recovery.responses {
+            let observed: Vec<u32> = PlayerNum::iter(num_players)
+                .map(|player_num| {
+                    let seen = finalized
+                        .get(usize::from(player_num))
+                        .map_or(0, |slice| slice.start + slice.len());
+                    seen.min(num_finalized[usize::from(player_num)])
+                })
+                .collect();
+            self.inner
+                .guests_finalized_observations
+                .update_guest_observation(
+                    guest,
+                    PeerwiseFinalizedInputsSeen::new_from_observed(num_players, &observed),
+                );
+            self.change_counters.observations += 1;
+        }
+
+        self.inner.sim_time = self.host_tick() as f64 / self.inputs_per_sec() as f64;
+        self.after_inputs_finalized();
+        self.observe_session_end();
+        self.events.push(InputMgrEvent::HostRecovered {
+            num_finalized: num_finalized.clone(),
+        });
+        Ok(num_finalized)
+    }
+
+    // Full state //////////////////////////////
+
+    /// Saves everything needed to resume this session in a new host process: every player's inputs and finalization count, the round, the host's sim time, and how many of each player's finalized inputs each guest has acked and been sent (see `full_state`).
+    pub fn serialize_full_state(&self) -> Result<Vec<u8>, String> {
+        FullState {
+            own_player_num: self.own_player_num,
+            ticks_per_sec: self.ticks_per_sec,
+            round: self.round,
+            buffers: self.buffers.clone(),
+            role: RoleState::Host {
+                sim_time: self.inner.sim_time,
+                guests_finalized_observations: self.inner.guests_finalized_observations.clone(),
+                finalized_sent: self.inner.finalized_sent.clone(),
+            },
+        }
+        .to_bytes()
+    }
+
+    /// Restores a state saved by `serialize_full_state` into a freshly constructed host, which must have the same tick rate as the one that saved it.
+    ///
+    /// Since the host keeps each guest's acks, guests don't need to reset anything: the host resumes sending each of them only the finalized inputs it hasn't acked.
+    pub fn deserialize_full_state(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let state = FullState::<T>::from_bytes(bytes, self.own_player_num, self.ticks_per_sec)?;
+        let RoleState::Host {
+            sim_time,
+            guests_finalized_observations,
+            finalized_sent,
+        } = state.role
+        else {
+            return Err(format!(
+                "full state was saved by a {}, not a host",
+                state.role.name()
+            ));
+        };
+        let track_changed_inputs = self.buffers.tracks_changed_inputs();
+        self.buffers = state.buffers;
+        self.buffers.set_track_changed_inputs(track_changed_inputs);
+        self.round = state.round;
+        self.inner.sim_time = sim_time;
+        self.inner.guests_finalized_observations = guests_finalized_observations;
+        self.inner.finalized_sent = finalized_sent;
+        self.change_counters.observations += 1;
+        Ok(())
+    }
+
+    // Rounds //////////////////////////////
+
+    /// Archives the current round's buffers and starts a new round from tick 0.
+    ///
+    /// Finalization observations and the host's sim time are reset, while RTT and ping state carry over. Inputs and acks from each guest are ignored until that guest acks the new round, so stale messages from the previous round can't leak into this one.
+    ///
+    /// The returned message must be broadcast to all guests.
+    pub fn start_new_round(&mut self) -> MsgPayload<T> {
+        self.archive_round();
+        let num_players = self.buffers.num_players();
+        self.inner.guests_finalized_observations = FinalizedObservationsPerGuest::new(num_players);
+        self.change_counters.observations += 1;
+        self.inner.sim_time = 0.0;
+        self.inner.input_rates.clear();
+        self.inner.catch_up_timers.clear();
+        self.inner.last_ack_sim_times.clear();
+        self.inner.finalized_sent.clear();
+        self.inner.lagging_times.clear();
+        for health in self.inner.health.values_mut() {
+            health.clear_recent_stalls();
+        }
+        self.inner.guests_events_seen.clear();
+        self.inner.guests_annotations_seen.clear();
+        self.inner.guests_seeds_seen.clear();
+        self.inner.pending_review.clear();
+        self.inner.newest_slice_ends.clear();
+        self.inner.replaced_pending_review.clear();
+        self.inner.msg_dedup.clear_sent();
+        self.inner.desyncs.clear();
+        for player_num in PlayerNum::iter_guests(num_players) {
+            if self.buffers.is_removed(player_num) {
+                self.inner
+                    .guests_finalized_observations
+                    .remove_guest(player_num);
+            }
+        }
+        // disconnected and muted guests can't ack, and their seats are filled
+        // with defaults regardless
+        self.inner.guests_pending_round_ack = self.live_guests().collect();
+        MsgPayload::HostToLobbyRoundTransition(self.round)
+    }
+
+    /// Handles a guest's confirmation that it has started the current round, after which its inputs and acks are accepted again.
+    pub fn rx_round_transition_ack(&mut self, player_num: PlayerNum, msg: MsgPayload<T>) {
+        if let MsgPayload::GuestToHostRoundTransitionAck(round) = msg
+            && round == self.round
+        {
+            let seats = self.seats_sharing_connection(player_num);
+            self.inner
+                .guests_pending_round_ack
+                .retain(|p| *p != player_num && !seats.contains(p));
+        }
+    }
+
     /// Marks a player as disconnected.
     ///
+    /// A disconnected guest no longer has to ack the current round, so that catch-up fills its seat with default inputs right away.
     pub fn player_disconnected(&mut self, player_num: PlayerNum) {
         self.inner.disconnected_players.push(player_num);
+        self.inner
+            .guests_pending_round_ack
+            .retain(|p| *p != player_num);
     }
 
-    // private helper functions //////////////////////////////
+    /// Soft-kicks a player: from `from_tick` on, their inputs are ignored, and the host finalizes default inputs for their seat (via `poll_catch_up`) as if they had disconnected. The player keeps their seat, so the lobby's player numbering is unchanged.
+    ///
+    /// Inputs that are already finalized can't be undone, so muting takes effect no earlier than the player's next unfinalized input. Muted players stay muted in later rounds, and don't have to ack them.
+    ///
+    /// The returned message must be broadcast to all guests, so that they stop accepting the player's inputs directly as well.
+    pub fn mute_player(&mut self, player_num: PlayerNum, from_tick: u32) -> MsgPayload<T> {
+        #[cfg(debug_assertions)]
+        assert!(player_num != HOST_PLAYER_NUM);
+        let first_ignored_input = self
+            .input_index(from_tick)
+            .unwrap_or(0)
+            .max(self.buffers.get_num_finalized_inputs(player_num));
+        self.muted_players.insert(player_num, first_ignored_input);
+        self.inner
+            .guests_pending_round_ack
+            .retain(|p| *p != player_num);
+        MsgPayload::HostToLobbyPlayerMuted(PlayerMuted {
+            player_num,
+            first_ignored_input,
+        })
+    }
+
+    /// Moves a guest's seat to a new connection mid-match, e.g. when the player swaps to a controller on another device, which the transport addresses as `new_connection`.
+    ///
+    /// The seat keeps its input history: inputs already finalized stay, and the new connection's inputs are accepted from the seat's next unfinalized input on, while slices from the old connection are no longer accepted (see `seat_for_connection`). The host forgets the seat's acks (of inputs, events, annotations and seeds), RTT and disconnection, since they describe the old connection, so the new one is sent the full finalized history.
+    ///
+    /// The returned message must be broadcast to all guests (including the new connection), so that they map the seat's direct inputs to the new connection as well.
+    pub fn transfer_seat(
+        &mut self,
+        seat: PlayerNum,
+        new_connection: PlayerNum,
+    ) -> Result<MsgPayload<T>, String> {
+        if !seat.is_guest() || u8::from(seat) >= self.buffers.num_players() {
+            return Err(format!("{seat:?} is not a guest seat"));
+        }
+        if let Some(other_seat) = self.seat_for_connection(new_connection) {
+            return Err(format!(
+                "connection {new_connection:?} already controls seat {other_seat:?}"
+            ));
+        }
+        Ok(self.move_seat_and_forget_link(seat, new_connection))
+    }
+
+    /// Lets the connection controlling `guest`'s seat control `seat` as well, for a guest with several local players on one device, e.g. split-screen (see `new_with_local_players` on the guest).
+    ///
+    /// As with `transfer_seat`, the seat keeps its input history, and the host forgets its acks and RTT. From then on the seat's inputs are accepted from the guest's bundled `GuestToHostLocalInputs` (see `rx_guest_local_inputs`), and the guest's acks and round acks count for the seat too, so it doesn't hold back the finalized broadcasts.
+    ///
+    /// The returned message must be broadcast to all guests (including `guest`), so that they map the seat's direct inputs to the guest's connection as well.
+    pub fn add_local_seat(
+        &mut self,
+        seat: PlayerNum,
+        guest: PlayerNum,
+    ) -> Result<MsgPayload<T>, String> {
+        for seat in [seat, guest] {
+            if !seat.is_guest() || u8::from(seat) >= self.buffers.num_players() {
+                return Err(format!("{seat:?} is not a guest seat"));
+            }
+        }
+        let connection = self.connection_for_seat(guest);
+        if self.connection_for_seat(seat) == connection {
+            return Err(format!(
+                "{seat:?} is already controlled by connection {connection:?}"
+            ));
+        }
+        Ok(self.move_seat_and_forget_link(seat, connection))
+    }
 
-    /// for the target peer, gets the earliest input whose
-    /// finalization has not been acked by at least one other peer.
+    // moves a seat to a connection, forgetting what the host knew about the
+    // seat's old link, and returns the transfer to broadcast
+    fn move_seat_and_forget_link(
+        &mut self,
+        seat: PlayerNum,
+        connection: PlayerNum,
+    ) -> MsgPayload<T> {
+        let num_players = self.buffers.num_players();
+        self.inner
+            .guests_finalized_observations
+            .reset_guest_observation(seat, num_players);
+        self.inner.rtts.remove(&seat);
+        self.inner.guest_reported_rtts.remove(&seat);
+        self.inner.pong_send_times.remove(&seat);
+        self.inner.last_seen_lobby_times.remove(&seat);
+        self.change_counters.observations += 1;
+        self.change_counters.rtt += 1;
+        self.inner.disconnected_players.retain(|p| *p != seat);
+        self.inner.input_rates.remove(&seat);
+        self.inner.catch_up_timers.remove(&seat);
+        self.inner.lagging_times.remove(&seat);
+        self.inner.last_ack_sim_times.remove(&seat);
+        self.inner.finalized_sent.remove(&seat);
+        self.inner.slice_throttle.remove(seat);
+        self.inner.health.remove(&seat);
+        self.inner.desyncs.remove(seat);
+        // the new connection has received none of the host's events, annotations or seeds
+        self.inner.guests_events_seen.remove(&seat);
+        self.inner.guests_annotations_seen.remove(&seat);
+        self.inner.guests_seeds_seen.remove(&seat);
+        self.inner.guests_acked_end.retain(|p| *p != seat);
+        self.inner.msg_dedup.forget_sent_to(seat);
+        self.negotiated_capabilities.remove(&seat);
+        self.move_seat(seat, connection);
+        MsgPayload::HostToLobbySeatTransferred(SeatTransfer {
+            seat,
+            connection,
+            first_input: self.buffers.get_num_finalized_inputs(seat),
+        })
+    }
+
+    /// Adds a player mid-session in the next free seat (`player_num` must equal the current number of players), e.g. for a guest joining a match in progress.
+    ///
+    /// The new player's inputs are finalized as defaults up to the host's tick, so that no one waits on inputs for ticks that have already been simulated, and their own inputs are accepted from there on. No guest has acked any of the new player's inputs, so the defaults go out with the player's finalized slices as usual. The new guest has acked nothing at all, so it is sent the full finalized history (see `sync_plan_for`); the game must still give it the sim state to start from.
+    ///
+    /// The returned message must be broadcast to all guests (including the new one), so that they add the player as well.
+    pub fn add_player(&mut self, player_num: PlayerNum) -> Result<MsgPayload<T>, String> {
+        let num_players = self.buffers.num_players();
+        let new_num_players = num_players
+            .checked_add(1)
+            .filter(|_| u8::from(player_num) == num_players)
+            .ok_or_else(|| format!("{player_num:?} is not the next free seat"))?;
+        self.buffers.add_players_up_to(new_num_players);
+        self.event_channel.add_players_up_to(new_num_players);
+        if let Some(chains) = self.input_chains.as_mut() {
+            chains.add_players_up_to(new_num_players);
+        }
+        self.inner
+            .guests_finalized_observations
+            .add_guests_up_to(new_num_players);
+        for sent in self.inner.finalized_sent.values_mut() {
+            sent.resize(usize::from(new_num_players), None);
+        }
+        self.change_counters.observations += 1;
+        let first_input = self.host_tick();
+        if first_input > 0 {
+            self.buffers
+                .append_final_default_inputs_to_target(player_num, first_input - 1);
+        }
+        self.after_inputs_finalized();
+        Ok(MsgPayload::HostToLobbyPlayerJoined(PlayerJoined {
+            player_num,
+            first_input,
+        }))
+    }
+
+    /// Removes a guest for good, e.g. once they have permanently disconnected, so that they no longer hold back the rest of the session.
     ///
-    /// I.e., this is the latest finalized input for this peer that can be sent
-    /// which will leave no gap in finalization for any other peer.
+    /// A disconnected player's seat still needs default inputs finalized for every tick (see `poll_catch_up`). A removed player's inputs instead end at the host's tick (finalizing defaults up to it, as for a disconnected player), and every later input reads as a finalized default, so the player no longer holds back the snapshottable tick and nothing more is finalized or sent for them. Their own acks are no longer tracked, and their inputs are dropped to free their buffer once every other guest has acked them and they are behind the snapshottable tick; dropped inputs read as defaults too, e.g. in a replay exported later. The seat keeps its number, so the other players' numbering is unchanged, and it stays removed in later rounds.
+    ///
+    /// The returned message must be broadcast to all guests, so that they stop waiting on the player as well.
+    pub fn remove_player(&mut self, player_num: PlayerNum) -> Result<MsgPayload<T>, String> {
+        if !player_num.is_guest() || u8::from(player_num) >= self.buffers.num_players() {
+            return Err(format!("{player_num:?} is not a guest seat"));
+        }
+        if self.buffers.is_removed(player_num) {
+            return Err(format!("{player_num:?} has already been removed"));
+        }
+        let num_inputs = self
+            .buffers
+            .get_num_finalized_inputs(player_num)
+            .max(self.host_tick());
+        if num_inputs > 0 {
+            self.buffers
+                .append_final_default_inputs_to_target(player_num, num_inputs - 1);
+        }
+        self.buffers.remove_player(player_num, num_inputs);
+        self.inner
+            .guests_finalized_observations
+            .remove_guest(player_num);
+        self.change_counters.observations += 1;
+        self.inner.pending_review.remove(&player_num);
+        self.inner.newest_slice_ends.remove(&player_num);
+        self.inner.replaced_pending_review.remove(&player_num);
+        self.inner.input_rates.remove(&player_num);
+        self.inner.catch_up_timers.remove(&player_num);
+        self.inner.lagging_times.remove(&player_num);
+        self.inner.last_ack_sim_times.remove(&player_num);
+        self.inner.finalized_sent.remove(&player_num);
+        self.inner.slice_throttle.remove(player_num);
+        self.inner.health.remove(&player_num);
+        self.inner.desyncs.remove(player_num);
+        self.inner
+            .guests_pending_round_ack
+            .retain(|p| *p != player_num);
+        self.after_inputs_finalized();
+        self.drop_unacked_inputs();
+        Ok(MsgPayload::HostToLobbyPlayerRemoved(PlayerRemoved {
+            player_num,
+            num_inputs,
+        }))
+    }
+
+    // private helper functions //////////////////////////////
+
+    /// The guests still in the session, i.e. not removed with `remove_player`.
+    fn active_guests(&self) -> impl Iterator<Item = PlayerNum> + '_ {
+        PlayerNum::iter_guests(self.buffers.num_players())
+            .filter(|guest| !self.buffers.is_removed(*guest))
+    }
+
+    /// The active guests that haven't disconnected.
+    fn connected_guests(&self) -> impl Iterator<Item = PlayerNum> + '_ {
+        self.active_guests()
+            .filter(|guest| !self.inner.disconnected_players.contains(guest))
+    }
+
+    /// The active guests still sending their own inputs, i.e. neither disconnected nor muted.
+    fn live_guests(&self) -> impl Iterator<Item = PlayerNum> + '_ {
+        self.active_guests()
+            .filter(|guest| !self.is_filled_with_defaults(*guest))
+    }
+
+    // the fewest inputs finalized for the host or any live guest; disconnected
+    // and muted guests are left out, since their seats get default inputs
+    fn num_finalized_across_live_players(&self) -> u32 {
+        std::iter::once(HOST_PLAYER_NUM)
+            .chain(self.live_guests())
+            .map(|player| self.buffers.get_num_finalized_inputs(player))
+            .min()
+            .unwrap_or(0)
+    }
+
+    // the first finalized input of this peer to broadcast: the fewest acked
+    // by any guest whose observations aren't stale. If every guest is stale,
+    // nothing already finalized is re-sent.
+    fn broadcast_start(&self, player_num: PlayerNum) -> u32 {
+        let observations = &self.inner.guests_finalized_observations;
+        if self.inner.observation_staleness_timeout_sec.is_none() {
+            return observations.get_earliest_num_observed_final_for_peer(player_num);
+        }
+        observations
+            .get_earliest_num_observed_final_for_peer_among(player_num, |guest| {
+                !self.is_observation_stale(guest)
+            })
+            .unwrap_or_else(|| self.buffers.get_num_finalized_inputs(player_num))
+    }
+
+    // for the target peer, gets the earliest input whose
+    // finalization has not been acked by at least one other peer.
+    //
+    // I.e., this is the latest finalized input for this peer that can be sent
+    // which will leave no gap in finalization for any other peer.
     // pub(super) fn get_earliest_num_observed_final_for_peer(&self, player_num: PlayerNum) -> u32 {
     //     self.inner
     //         .guests_finalized_observations
@@ -298,15 +2926,186 @@ impl<T: SimInput> MultiplayerInputManager<T, HostInputMgr> {
     //         .unwrap_or(0)
     // }
 
+    /// Disconnected and muted players have their seats filled with default inputs up to the host's tick.
+    fn is_filled_with_defaults(&self, player_num: PlayerNum) -> bool {
+        self.inner.disconnected_players.contains(&player_num)
+            || self.muted_players.contains_key(&player_num)
+    }
+
+    /// Records that `num_inputs` of this player's inputs, starting at `first_input`, were finalized at the host's current tick.
+    fn record_finalization_latencies(
+        &mut self,
+        player_num: PlayerNum,
+        first_input: u32,
+        num_inputs: u32,
+    ) {
+        let host_tick = self.host_tick();
+        let histogram = self
+            .inner
+            .finalization_latencies
+            .entry(player_num)
+            .or_default();
+        for input in first_input..first_input + num_inputs {
+            histogram.observe(host_tick.saturating_sub(input));
+        }
+    }
+
+    // Fairness //////////////////////////////
+
+    /// Percentiles of how many ticks after their own tick this player's inputs were finalized by the host, over the whole match; `None` if none have been finalized yet.
+    ///
+    /// The host's own inputs are finalized on their own tick. Default inputs the host fills in for a lagging or disconnected guest are not counted, since the guest never sent them.
+    pub fn finalization_latency_summary(&self, player_num: PlayerNum) -> Option<LatencySummary> {
+        self.inner
+            .finalization_latencies
+            .get(&player_num)?
+            .summary()
+    }
+
+    /// The finalization latency summary of every player with at least one finalized input, sorted by player num.
+    pub fn fairness_report(&self) -> Vec<(PlayerNum, LatencySummary)> {
+        PlayerNum::iter(self.buffers.num_players())
+            .filter_map(|p| Some((p, self.finalization_latency_summary(p)?)))
+            .collect()
+    }
+
+    /// The spread (ticks) between the highest and lowest median finalization latency across players, as a single measure of how unevenly the match treated its players; `None` until any inputs have been finalized.
+    ///
+    /// A large spread means some players systematically had their inputs finalized later than others.
+    pub fn fairness_spread_ticks(&self) -> Option<u32> {
+        let medians = self.fairness_report().into_iter().map(|(_, s)| s.p50);
+        let (min, max) = medians.fold(None, |acc: Option<(u32, u32)>, m| {
+            Some(acc.map_or((m, m), |(lo, hi)| (lo.min(m), hi.max(m))))
+        })?;
+        Some(max - min)
+    }
+
+    /// Scores each guest's connection quality from 0 to 100 (see `health_score`), with the signals behind each score, sorted by player num.
+    ///
+    /// Call this at most once per frame: the hysteresis that keeps a score from flickering compares each call's raw score with the score reported by the previous call.
+    pub fn poll_health_scores(&mut self) -> Vec<(PlayerNum, HealthScore)> {
+        let sim_time = self.inner.sim_time;
+        let guests: Vec<PlayerNum> = self.active_guests().collect();
+        guests
+            .into_iter()
+            .map(|guest| {
+                let ticks_behind = self
+                    .host_tick()
+                    .saturating_sub(self.buffers.get_num_inputs(guest));
+                let rtt = self.inner.rtts.get(&guest);
+                let health = self.inner.health.entry(guest).or_default();
+                let (recent_stalls, num_stalls) = health.stalls(sim_time);
+                let components = HealthComponents {
+                    rtt_ms: rtt.and_then(RttEstimate::value),
+                    jitter_ms: rtt.and_then(RttEstimate::jitter),
+                    loss: health.loss(),
+                    ticks_behind,
+                    recent_stalls,
+                    num_stalls,
+                };
+                (guest, health.score(components))
+            })
+            .collect()
+    }
+
     // info and debug //////////////////////////////
     pub fn rtts_by_player(&self) -> Vec<(u8, f32)> {
-        self.inner
+        let mut rtts = Vec::with_capacity(self.inner.rtts.len());
+        self.rtts_by_player_into(&mut rtts);
+        rtts
+    }
+
+    /// Like `rtts_by_player`, but fills `out` (cleared first) instead of allocating a new vec, for per-frame use: once `out` has held every guest, this doesn't allocate.
+    pub fn rtts_by_player_into(&self, out: &mut Vec<(u8, f32)>) {
+        out.clear();
+        out.extend(
+            self.inner
+                .rtts
+                .iter()
+                .filter_map(|(k, v)| Some(((*k).into(), v.value()?))),
+        );
+    }
+
+    /// The smoothed deviation (ms) of RTT samples to a guest from the smoothed RTT; `None` until two RTT samples have been observed.
+    pub fn get_guest_rtt_jitter_ms(&self, player_num: PlayerNum) -> Option<f32> {
+        self.inner.rtts.get(&player_num)?.jitter()
+    }
+
+    /// The lowest RTT (ms) to a guest observed so far; `None` until an RTT sample has been observed.
+    pub fn get_guest_min_rtt_ms(&self, player_num: PlayerNum) -> Option<f32> {
+        self.inner.rtts.get(&player_num)?.min()
+    }
+
+    /// Each guest's smoothed RTT with its sample count, and whether it has warmed up (see `RttConfig::warm_up_samples`), sorted by player num; guests without RTT samples are omitted.
+    pub fn rtt_summaries_by_player(&self) -> Vec<(PlayerNum, RttSummary)> {
+        let mut summaries: Vec<_> = self
+            .inner
             .rtts
             .iter()
-            .map(|(k, v)| ((*k).into(), v.value()))
+            .filter_map(|(player_num, rtt)| Some((*player_num, rtt.summary(&self.rtt_config)?)))
+            .collect();
+        summaries.sort_by_key(|(player_num, _)| *player_num);
+        summaries
+    }
+
+    /// For each guest, the number of finalized inputs it has acked for every peer (including the host), sorted by player num.
+    pub fn observation_matrix(&self) -> Vec<(PlayerNum, Vec<(PlayerNum, u32)>)> {
+        self.inner
+            .guests_finalized_observations
+            .observations_by_guest()
+    }
+
+    /// For each peer, which guests have acked the fewest of its finalized inputs, and so hold back the finalized slices broadcast for it. Useful for diagnosing stalls.
+    pub fn observation_blockers(&self) -> Vec<ObservationBlocker> {
+        self.inner
+            .guests_finalized_observations
+            .blockers(self.buffers.num_players())
+    }
+
+    /// The inputs in flight between the host and this guest (see `InputsInFlight`).
+    pub fn inputs_in_flight(&self, guest: PlayerNum) -> InputsInFlight {
+        let observations = &self.inner.guests_finalized_observations;
+        InputsInFlight {
+            upstream: self
+                .buffers
+                .get_num_inputs(guest)
+                .saturating_sub(self.buffers.get_num_finalized_inputs(guest)),
+            downstream: PlayerNum::iter(self.buffers.num_players())
+                .map(|peer| {
+                    self.buffers
+                        .get_num_finalized_inputs(peer)
+                        .saturating_sub(observations.get_guest_observation(guest, peer))
+                })
+                .sum(),
+        }
+    }
+
+    /// The inputs in flight between the host and each guest, sorted by player num.
+    pub fn inputs_in_flight_by_guest(&self) -> Vec<(PlayerNum, InputsInFlight)> {
+        self.active_guests()
+            .map(|guest| (guest, self.inputs_in_flight(guest)))
             .collect()
     }
 
+    /// Builds a status-level summary of this host's buffers, including the observation matrix of what each guest has acked.
+    pub fn debug_dump(&self) -> DebugDump {
+        DebugDump::new_from_buffers(
+            DumpRole::Host,
+            self.own_player_num,
+            &self.buffers,
+            self.inner
+                .guests_finalized_observations
+                .observations_by_guest(),
+        )
+    }
+
+    /// A multi-line, human-readable summary of this host's state, suitable for pasting into bug reports.
+    ///
+    /// The text can be parsed back into a `DebugDump`.
+    pub fn dump_debug_text(&self) -> String {
+        self.debug_dump().to_string()
+    }
+
     #[cfg(test)]
     pub(super) fn test_get_earliest_num_observed_final_for_peer(
         &self,
@@ -0,0 +1,279 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    bandwidth_budget::BandwidthBudget,
+    capabilities::Capabilities,
+    change_stamps::ChangeCounters,
+    decode_stats::DecodeStats,
+    determinism_probe::DeterminismProbe,
+    event_channel::EventChannel,
+    events::{EventQueue, InputMgrEvent},
+    finalization_watch::FinalizationWatchers,
+    finalized_input_channel::{DEFAULT_FINALIZED_INPUT_RX_DEPTH, FinalizedInputTxs},
+    input_schedule::InputSchedule,
+    input_schema::InputSchemaMismatch,
+    input_trait::SimInput,
+    msg_sequence::MsgSequences,
+    net_stats::NetStats,
+    reliable_delivery::ReliableDelivery,
+    rollback_depth::RollbackDepthTracker,
+    rtt::RttConfig,
+    rx_outcome::{RxOutcome, RxRejection},
+    seed_schedule::SeedSchedule,
+    session::{OutgoingMsgs, Recipient, SessionRxError, push_replies, without_empty_msgs},
+    session_limit::{MAX_SESSION_TICKS, SessionLimit, signed_host_tick},
+    tick_consumption::TickConsumption,
+    unknown_player_slices::UnknownPlayerSlices,
+};
+
+use super::{
+    input_messages::{HostFinalizedSlice, MsgPayload, PlayerRemoved, PreSimSync},
+    multiplayer_input_buffer::MultiplayerInputBuffers,
+    multiplayer_input_manager::{DEFAULT_MAX_INPUT_LEAD, MultiplayerInputManager},
+    multiplayer_input_manager_guest::DEFAULT_MAX_CATCHUP_INPUTS,
+    multiplayer_input_manager_host::HOST_PLAYER_NUM,
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    util_types::PlayerNum,
+};
+
+/// A node that follows a session without playing in it: it receives the host's finalized slices for every player, but holds no seat and never collects inputs of its own.
+///
+/// A spectator connects to the host on a connection that controls no seat (a player num at or past the session's `num_players`), which the host registers with `add_spectator`. Its finalization acks only tell the host which finalized inputs to send it next (see `get_msgs_finalized_slices_for_spectators` on the host); unlike a guest's, they never hold back the host's broadcasts or the inputs it retains, so a slow spectator can't stall the match.
+///
+/// Methods about this node's own inputs (e.g. `get_own_num_inputs`) don't apply to a spectator, which has none.
+pub struct SpectatorInputMgr {
+    /// the most recent collected input tick
+    /// that the host has sent to this spectator
+    ///
+    /// can be negative in the pre-sim sync phase;
+    /// `None` until the first PreSimSync or finalized slice arrives
+    host_tick: Option<i32>,
+    /// The finalized counts carried by the last ack sent.
+    last_acked_finalized: PeerwiseFinalizedInputsSeen,
+}
+
+impl Default for SpectatorInputMgr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpectatorInputMgr {
+    // CONSTRUCTORS ///////////////////////////////////////////
+    pub fn new() -> Self {
+        Self {
+            host_tick: None,
+            last_acked_finalized: PeerwiseFinalizedInputsSeen::default(),
+        }
+    }
+}
+
+impl<T: SimInput> MultiplayerInputManager<T, SpectatorInputMgr> {
+    /// `connection` is this spectator's connection to the host, which must not be a seat, i.e. must be at least `num_players`.
+    pub fn new(num_players: u8, connection: PlayerNum, ticks_per_sec: u32) -> Self {
+        assert!(
+            u8::from(connection) >= num_players,
+            "spectator connection {connection:?} is a seat of a {num_players} player session"
+        );
+        Self {
+            ticks_per_sec,
+            buffers: MultiplayerInputBuffers::new(num_players, DEFAULT_MAX_CATCHUP_INPUTS),
+            inner: SpectatorInputMgr::new(),
+            own_player_num: connection,
+            round: 0,
+            archived_rounds: Vec::default(),
+            decode_stats: DecodeStats::default(),
+            msg_sequences: MsgSequences::default(),
+            reliable_delivery: ReliableDelivery::default(),
+            net_stats: NetStats::default(),
+            events: EventQueue::default(),
+            muted_players: HashMap::default(),
+            seat_connections: HashMap::default(),
+            event_channel: EventChannel::new(num_players),
+            annotations: EventChannel::new(1),
+            seeds: SeedSchedule::default(),
+            tick_consumption: TickConsumption::default(),
+            scheduled_inputs: InputSchedule::default(),
+            capabilities: Capabilities::NONE,
+            negotiated_capabilities: HashMap::default(),
+            player_metadata: BTreeMap::default(),
+            unknown_player_slices: UnknownPlayerSlices::default(),
+            rollback_depth: RollbackDepthTracker::default(),
+            max_payload_bytes: None,
+            finalization_watchers: FinalizationWatchers::default(),
+            finalized_input_rx_depth: DEFAULT_FINALIZED_INPUT_RX_DEPTH,
+            finalized_input_txs: FinalizedInputTxs::default(),
+            num_sanitized: HashMap::default(),
+            rtt_config: RttConfig::default(),
+            num_ignored_slices: HashMap::default(),
+            max_input_lead: DEFAULT_MAX_INPUT_LEAD,
+            num_inputs_beyond_lead: HashMap::default(),
+            max_retained_inputs: None,
+            input_chains: None,
+            session_limit: SessionLimit::default(),
+            determinism_probe: DeterminismProbe::default(),
+            bandwidth_budget: BandwidthBudget::default(),
+            host_recovery: None,
+            change_counters: ChangeCounters::default(),
+            #[cfg(feature = "profiling")]
+            profiler: None,
+        }
+    }
+
+    /// The connection this spectator uses to reach the host.
+    pub fn connection(&self) -> PlayerNum {
+        self.own_player_num
+    }
+
+    /// The newest host tick received; `None` until the first PreSimSync or finalized slice arrives.
+    pub fn get_host_tick(&self) -> Option<i32> {
+        self.inner.host_tick
+    }
+
+    fn observe_host_tick(&mut self, host_tick: i32) {
+        self.inner.host_tick = Some(self.inner.host_tick.map_or(host_tick, |t| t.max(host_tick)));
+    }
+
+    // Receiving //////////////////////////////
+
+    /// Passes a message received from the host to the matching `rx_*` method, returning the replies to send, all addressed to the host. The messages of a batch are handled in turn, and replies superseded by a later reply are dropped.
+    ///
+    /// Spectators only follow the finalized inputs: messages about collecting inputs or pacing them are rejected as for the wrong role.
+    pub fn handle_msg(&mut self, msg: MsgPayload<T>) -> Result<OutgoingMsgs<T>, SessionRxError> {
+        if let MsgPayload::Batch(msgs) = msg {
+            let mut replies = vec![];
+            for msg in msgs {
+                if let Ok(outgoing) = self.handle_msg(msg) {
+                    push_replies(&mut replies, outgoing);
+                }
+            }
+            return Ok(replies);
+        }
+        let host = Recipient::Player(HOST_PLAYER_NUM);
+        let outgoing = match msg {
+            MsgPayload::HostToLobbyFinalizedSlice(_) => {
+                self.rx_final_peer_input_slice_from_host(msg);
+                vec![(host, self.get_msg_ack_finalization())]
+            }
+            MsgPayload::HostToGuestPreSimSync(_) => {
+                self.rx_pre_sim_sync(msg)
+                    .map_err(SessionRxError::InputSchemaMismatch)?;
+                vec![]
+            }
+            MsgPayload::HostToLobbyPlayerRemoved(_) => {
+                self.rx_player_removed(msg);
+                vec![]
+            }
+            // acks are consumed by `decode_sequenced_msg_from_peer`
+            MsgPayload::Empty | MsgPayload::DeliveryAck(_) => vec![],
+            _ => return Err(SessionRxError::WrongRole),
+        };
+        Ok(without_empty_msgs(outgoing))
+    }
+
+    /// Adopts the session config from the host's `PreSimSync`, as a guest does (see `rx_pre_sim_sync` on the guest). Spectators talk to the host with the baseline protocol, so no capabilities are negotiated, and they send no reply.
+    pub fn rx_pre_sim_sync(&mut self, msg: MsgPayload<T>) -> Result<(), InputSchemaMismatch> {
+        if let Ok(PreSimSync {
+            host_tick_countdown,
+            start_tick,
+            initial_seed,
+            sim_ticks_per_input,
+            ticks_per_sec,
+            max_session_ticks,
+            input_schema_id,
+            player_metadata,
+            ..
+        }) = msg.try_into()
+        {
+            self.check_input_schema(HOST_PLAYER_NUM, input_schema_id)?;
+            self.player_metadata = player_metadata
+                .into_iter()
+                .filter(|(player_num, metadata)| {
+                    self.validate_player_metadata(*player_num, metadata).is_ok()
+                })
+                .collect();
+            self.inner.host_tick = Some(-(host_tick_countdown as i32));
+            if self.buffers.is_empty() {
+                self.buffers.set_start_tick(start_tick);
+                self.seeds.initial = initial_seed;
+                self.buffers.set_sim_ticks_per_input(sim_ticks_per_input);
+                self.ticks_per_sec = ticks_per_sec.max(1);
+                self.session_limit.max_ticks = max_session_ticks.min(MAX_SESSION_TICKS);
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies a finalized slice from the host. A slice for a player this spectator doesn't know yet (e.g. one added mid-session) adds players up to them, since the host's slices start from the inputs it finalized for the player before they joined.
+    pub fn rx_final_peer_input_slice_from_host(&mut self, msg: MsgPayload<T>) -> RxOutcome {
+        let Ok(HostFinalizedSlice {
+            player_num,
+            host_tick,
+            mut inputs,
+        }) = msg.try_into()
+        else {
+            return RxOutcome::rejected(RxRejection::UnexpectedMsg);
+        };
+        self.observe_host_tick(signed_host_tick(host_tick));
+        self.add_players_up_to(player_num);
+
+        if !self.drop_inputs_past_session_end(&mut inputs) {
+            return RxOutcome::rejected(RxRejection::PastSessionEnd);
+        }
+        if inputs.start > self.buffers.get_num_finalized_inputs(player_num) {
+            return self.reject_gap_before_slice(player_num);
+        }
+        self.sanitize_slice(player_num, &mut inputs);
+        let outcome = self.rx_and_summarize(player_num, |buffers| {
+            buffers.receive_finalized_input_slice_for_player(inputs, player_num)
+        });
+        self.observe_session_end_finalized();
+        outcome
+    }
+
+    /// Like `observe_session_end`, but a spectator has no inputs of its own to reach the end with, so its session ends once every player's inputs are finalized up to the end.
+    fn observe_session_end_finalized(&mut self) {
+        if self.is_session_end_finalized() && !self.session_limit.end_reported {
+            self.session_limit.end_reported = true;
+            self.events.push(InputMgrEvent::SessionEnded {
+                end_tick: self.session_limit.max_ticks,
+            });
+        }
+    }
+
+    /// Handles the host's removal of a player for good (see `remove_player` on the host), so that this spectator no longer waits on the player's inputs once it has finalized their last ones.
+    pub fn rx_player_removed(&mut self, msg: MsgPayload<T>) {
+        let MsgPayload::HostToLobbyPlayerRemoved(PlayerRemoved {
+            player_num,
+            num_inputs,
+        }) = msg
+        else {
+            return;
+        };
+        if self.buffers.is_removed(player_num) {
+            return;
+        }
+        self.add_players_up_to(player_num);
+        self.buffers.remove_player(player_num, num_inputs);
+        self.after_inputs_finalized();
+    }
+
+    fn add_players_up_to(&mut self, player_num: PlayerNum) {
+        let num_players = u8::from(player_num).saturating_add(1);
+        if num_players <= self.buffers.num_players() {
+            return;
+        }
+        self.buffers.add_players_up_to(num_players);
+        self.event_channel.add_players_up_to(num_players);
+    }
+
+    // Acks //////////////////////////////
+
+    /// Gets the ack to send the host upon receiving a finalized slice, telling it which finalized inputs to send this spectator next.
+    pub fn get_msg_ack_finalization(&mut self) -> MsgPayload<T> {
+        self.buffers
+            .get_peerwise_finalized_inputs_into(&mut self.inner.last_acked_finalized);
+        self.change_counters.observations += 1;
+        MsgPayload::GuestToHostAckFinalization(self.inner.last_acked_finalized.clone())
+    }
+}
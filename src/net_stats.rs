@@ -0,0 +1,186 @@
+//! Bandwidth accounting: the messages and bytes sent to and received from each peer, by message kind, with the bytes of the last second as a rolling rate, for tuning tick rates and slice sizes (see `MultiplayerInputManager::get_net_stats`).
+//!
+//! Received messages are counted as they are decoded (see `decode_msg_from_peer` and `decode_sequenced_msg_from_peer`), at their size on the wire. Messages sent are counted when serialized by a `Session`; games that serialize messages themselves report them with `record_msg_bytes_sent`, since only they know what reached the wire (e.g. after compression). The rolling rates advance with `update_net_stats`.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::{input_messages::MsgKind, util_types::PlayerNum};
+
+/// The span (sec) over which the rolling rates are counted.
+pub const NET_STATS_RATE_WINDOW_SEC: f64 = 1.0;
+
+/// A count of messages and their bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MsgCounts {
+    pub num_msgs: u64,
+    pub num_bytes: u64,
+}
+
+impl MsgCounts {
+    fn add(&mut self, num_bytes: usize) {
+        self.num_msgs += 1;
+        self.num_bytes += num_bytes as u64;
+    }
+}
+
+impl std::ops::Add for MsgCounts {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            num_msgs: self.num_msgs + other.num_msgs,
+            num_bytes: self.num_bytes + other.num_bytes,
+        }
+    }
+}
+
+/// The traffic with one peer.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PeerNetStats {
+    /// Messages sent to the peer, by kind; a batch counts as one `MsgKind::Batch`
+    pub sent: BTreeMap<MsgKind, MsgCounts>,
+    /// Messages received from the peer, by kind, duplicates included
+    pub received: BTreeMap<MsgKind, MsgCounts>,
+    /// Bytes sent to the peer in the last `NET_STATS_RATE_WINDOW_SEC`
+    pub sent_bytes_per_sec: u64,
+    /// Bytes received from the peer in the last `NET_STATS_RATE_WINDOW_SEC`
+    pub received_bytes_per_sec: u64,
+}
+
+impl PeerNetStats {
+    pub fn total_sent(&self) -> MsgCounts {
+        self.sent
+            .values()
+            .copied()
+            .fold(MsgCounts::default(), |a, b| a + b)
+    }
+
+    pub fn total_received(&self) -> MsgCounts {
+        self.received
+            .values()
+            .copied()
+            .fold(MsgCounts::default(), |a, b| a + b)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Sent,
+    Received,
+}
+
+/// Bytes counted in the rolling rates until they fall out of the window.
+#[derive(Debug, Clone)]
+struct RecentBytes {
+    at_sec: f64,
+    peer: PlayerNum,
+    direction: Direction,
+    num_bytes: u64,
+}
+
+/// The traffic with every peer (see the module docs).
+#[derive(Debug, Clone, Default)]
+pub struct NetStats {
+    peers: BTreeMap<PlayerNum, PeerNetStats>,
+    /// The time (sec) passed to `advance` so far
+    now_sec: f64,
+    /// Traffic within the rate window, oldest first
+    recent: VecDeque<RecentBytes>,
+}
+
+impl NetStats {
+    /// The traffic with `peer`; empty if there has been none.
+    pub fn peer(&self, peer: PlayerNum) -> PeerNetStats {
+        self.peers.get(&peer).cloned().unwrap_or_default()
+    }
+
+    /// The traffic with each peer there has been any with, in player order.
+    pub fn peers(&self) -> impl Iterator<Item = (PlayerNum, &PeerNetStats)> {
+        self.peers.iter().map(|(peer, stats)| (*peer, stats))
+    }
+
+    /// Messages sent to all peers.
+    pub fn total_sent(&self) -> MsgCounts {
+        self.peers
+            .values()
+            .map(PeerNetStats::total_sent)
+            .fold(MsgCounts::default(), |a, b| a + b)
+    }
+
+    /// Messages received from all peers.
+    pub fn total_received(&self) -> MsgCounts {
+        self.peers
+            .values()
+            .map(PeerNetStats::total_received)
+            .fold(MsgCounts::default(), |a, b| a + b)
+    }
+
+    /// Bytes sent to all peers in the last `NET_STATS_RATE_WINDOW_SEC`.
+    pub fn sent_bytes_per_sec(&self) -> u64 {
+        self.peers
+            .values()
+            .map(|peer| peer.sent_bytes_per_sec)
+            .sum()
+    }
+
+    /// Bytes received from all peers in the last `NET_STATS_RATE_WINDOW_SEC`.
+    pub fn received_bytes_per_sec(&self) -> u64 {
+        self.peers
+            .values()
+            .map(|peer| peer.received_bytes_per_sec)
+            .sum()
+    }
+
+    pub(crate) fn record_sent(&mut self, peer: PlayerNum, kind: MsgKind, num_bytes: usize) {
+        self.record(peer, kind, num_bytes, Direction::Sent);
+    }
+
+    pub(crate) fn record_received(&mut self, peer: PlayerNum, kind: MsgKind, num_bytes: usize) {
+        self.record(peer, kind, num_bytes, Direction::Received);
+    }
+
+    fn record(&mut self, peer: PlayerNum, kind: MsgKind, num_bytes: usize, direction: Direction) {
+        let stats = self.peers.entry(peer).or_default();
+        let (counts, rate) = match direction {
+            Direction::Sent => (&mut stats.sent, &mut stats.sent_bytes_per_sec),
+            Direction::Received => (&mut stats.received, &mut stats.received_bytes_per_sec),
+        };
+        counts.entry(kind).or_default().add(num_bytes);
+        *rate += num_bytes as u64;
+        // traffic between two calls to `advance` shares a time, so it is kept
+        // as one entry per peer and direction
+        let same_time = self
+            .recent
+            .iter_mut()
+            .rev()
+            .take_while(|recent| recent.at_sec == self.now_sec)
+            .find(|recent| recent.peer == peer && recent.direction == direction);
+        if let Some(recent) = same_time {
+            recent.num_bytes += num_bytes as u64;
+            return;
+        }
+        self.recent.push_back(RecentBytes {
+            at_sec: self.now_sec,
+            peer,
+            direction,
+            num_bytes: num_bytes as u64,
+        });
+    }
+
+    /// Advances the clock by `delta` (sec), dropping traffic older than the rate window from the rates.
+    pub(crate) fn advance(&mut self, delta: f32) {
+        self.now_sec += delta as f64;
+        while let Some(oldest) = self.recent.front() {
+            if self.now_sec - oldest.at_sec < NET_STATS_RATE_WINDOW_SEC {
+                break;
+            }
+            let stats = self.peers.entry(oldest.peer).or_default();
+            let rate = match oldest.direction {
+                Direction::Sent => &mut stats.sent_bytes_per_sec,
+                Direction::Received => &mut stats.received_bytes_per_sec,
+            };
+            *rate -= oldest.num_bytes;
+            self.recent.pop_front();
+        }
+    }
+}
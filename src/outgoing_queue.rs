@@ -0,0 +1,202 @@
+//! A capped queue of outgoing messages for peers the transport can't currently reach.
+//!
+//! The managers produce messages every frame whether or not a peer is reachable. Rather than sending them into a dead transport (or piling them up without bound), callers can pass each frame's `OutgoingMsgs` through an `OutgoingQueue`: messages for reachable peers go straight out, while messages for unreachable peers wait in a queue per peer until `set_reachable` marks the peer reachable again.
+//!
+//! Queued messages are collapsed as they arrive, since most of what the managers send is superseded by newer messages of the same kind: a slice is subsumed by a newer one covering the same inputs, and cumulative acks, rate adjustments, ping reports and countdowns by newer copies. Once a queue holds `max_depth` messages, the oldest are dropped. Both are counted per peer (see `summary`).
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{
+    input_messages::MsgPayload,
+    input_trait::SimInput,
+    session::{OutgoingMsgs, Recipient},
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+/// The default number of messages queued for each unreachable peer.
+pub const DEFAULT_OUTGOING_QUEUE_DEPTH: usize = 64;
+
+/// What has happened to the messages queued for a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OutgoingQueueSummary {
+    /// Messages waiting for the peer to become reachable
+    pub num_queued: u32,
+    /// Queued messages replaced by a newer message that supersedes them
+    pub num_collapsed: u32,
+    /// Queued messages dropped because the queue was full
+    pub num_dropped: u32,
+}
+
+/// True if `newer` covers every input `older` carries.
+fn slice_covers<T: SimInput>(newer: &PlayerInputSlice<T>, older: &PlayerInputSlice<T>) -> bool {
+    newer.start <= older.start && newer.start + newer.len() >= older.start + older.len()
+}
+
+/// True if sending `newer` makes sending `older` pointless.
+pub(crate) fn supersedes<T: SimInput>(newer: &MsgPayload<T>, older: &MsgPayload<T>) -> bool {
+    match (newer, older) {
+        (
+            MsgPayload::HostToLobbyFinalizedSlice(newer),
+            MsgPayload::HostToLobbyFinalizedSlice(older),
+        ) => newer.player_num == older.player_num && slice_covers(&newer.inputs, &older.inputs),
+        (MsgPayload::PeerInputs(newer), MsgPayload::PeerInputs(older)) => {
+            slice_covers(newer, older)
+        }
+        (MsgPayload::GuestToHostLocalInputs(newer), MsgPayload::GuestToHostLocalInputs(older)) => {
+            older.iter().all(|older| {
+                newer.iter().any(|newer| {
+                    newer.player_num == older.player_num
+                        && slice_covers(&newer.inputs, &older.inputs)
+                })
+            })
+        }
+        (MsgPayload::GuestToHostAckFinalization(_), MsgPayload::GuestToHostAckFinalization(_))
+        | (MsgPayload::GuestToHostAckEvents(_), MsgPayload::GuestToHostAckEvents(_))
+        | (MsgPayload::GuestToHostAckAnnotations(_), MsgPayload::GuestToHostAckAnnotations(_))
+        | (MsgPayload::GuestToHostAckSeeds(_), MsgPayload::GuestToHostAckSeeds(_))
+        | (MsgPayload::HostToGuestRateAdjust(_), MsgPayload::HostToGuestRateAdjust(_))
+        | (MsgPayload::HostToGuestPingReport(_), MsgPayload::HostToGuestPingReport(_))
+        | (MsgPayload::GuestToHostClockPing(_), MsgPayload::GuestToHostClockPing(_))
+        | (MsgPayload::HostToLobbyEndSession(_), MsgPayload::HostToLobbyEndSession(_))
+        | (MsgPayload::GuestToHostEndAck(_), MsgPayload::GuestToHostEndAck(_))
+        | (MsgPayload::HostToLobbyStartProposal(_), MsgPayload::HostToLobbyStartProposal(_))
+        | (MsgPayload::GuestToHostStartAck(_), MsgPayload::GuestToHostStartAck(_))
+        | (MsgPayload::HostToLobbyStartConfirmed(_), MsgPayload::HostToLobbyStartConfirmed(_))
+        | (MsgPayload::GuestToHostInputSchema(_), MsgPayload::GuestToHostInputSchema(_))
+        | (MsgPayload::GuestToHostCapabilities(_), MsgPayload::GuestToHostCapabilities(_))
+        | (MsgPayload::HostToGuestPreSimSync(_), MsgPayload::HostToGuestPreSimSync(_)) => true,
+        _ => false,
+    }
+}
+
+#[derive(Debug, Default)]
+struct PeerQueue<T: SimInput> {
+    msgs: VecDeque<MsgPayload<T>>,
+    summary: OutgoingQueueSummary,
+}
+
+/// Holds outgoing messages for unreachable peers (see the module docs).
+#[derive(Debug)]
+pub struct OutgoingQueue<T: SimInput> {
+    own_player_num: PlayerNum,
+    num_players: u8,
+    max_depth: usize,
+    unreachable: HashSet<PlayerNum>,
+    queues: HashMap<PlayerNum, PeerQueue<T>>,
+}
+
+impl<T: SimInput> OutgoingQueue<T> {
+    /// A queue for the messages this player sends to the other players in a session of `num_players`. Every peer starts out reachable.
+    pub fn new(num_players: u8, own_player_num: PlayerNum) -> Self {
+        Self {
+            own_player_num,
+            num_players,
+            max_depth: DEFAULT_OUTGOING_QUEUE_DEPTH,
+            unreachable: HashSet::default(),
+            queues: HashMap::default(),
+        }
+    }
+
+    /// Sets the number of messages queued for each unreachable peer before the oldest are dropped (at least 1).
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth.max(1);
+        self
+    }
+
+    /// Marks a peer reachable or not, as reported by the transport. Messages queued for a peer that becomes reachable go out with the next `route`.
+    pub fn set_reachable(&mut self, peer: PlayerNum, reachable: bool) {
+        if reachable {
+            self.unreachable.remove(&peer);
+        } else {
+            self.unreachable.insert(peer);
+        }
+    }
+
+    pub fn is_reachable(&self, peer: PlayerNum) -> bool {
+        !self.unreachable.contains(&peer)
+    }
+
+    /// Queues this frame's messages for unreachable peers, and returns the messages to send now: those queued for peers that are reachable again (oldest first), then this frame's messages for reachable peers.
+    ///
+    /// A message for all peers is returned as is if every peer is reachable, and otherwise split into one message per peer.
+    pub fn route(&mut self, outgoing: OutgoingMsgs<T>) -> OutgoingMsgs<T> {
+        let mut to_send = self.take_reachable();
+        let peers: Vec<PlayerNum> = PlayerNum::iter(self.num_players)
+            .filter(|peer| *peer != self.own_player_num)
+            .collect();
+        for (recipient, msg) in outgoing {
+            if matches!(msg, MsgPayload::Empty) {
+                continue;
+            }
+            match recipient {
+                Recipient::AllPeers if self.unreachable.is_empty() => {
+                    to_send.push((recipient, msg));
+                }
+                Recipient::AllPeers => {
+                    for peer in &peers {
+                        self.send_or_queue(*peer, msg.clone(), &mut to_send);
+                    }
+                }
+                Recipient::Player(peer) => self.send_or_queue(peer, msg, &mut to_send),
+            }
+        }
+        to_send
+    }
+
+    fn send_or_queue(
+        &mut self,
+        peer: PlayerNum,
+        msg: MsgPayload<T>,
+        to_send: &mut OutgoingMsgs<T>,
+    ) {
+        if self.is_reachable(peer) {
+            to_send.push((Recipient::Player(peer), msg));
+        } else {
+            self.enqueue(peer, msg);
+        }
+    }
+
+    fn enqueue(&mut self, peer: PlayerNum, msg: MsgPayload<T>) {
+        let max_depth = self.max_depth;
+        let queue = self.queues.entry(peer).or_default();
+        let len_before = queue.msgs.len();
+        queue.msgs.retain(|older| !supersedes(&msg, older));
+        queue.summary.num_collapsed += (len_before - queue.msgs.len()) as u32;
+        queue.msgs.push_back(msg);
+        while queue.msgs.len() > max_depth {
+            queue.msgs.pop_front();
+            queue.summary.num_dropped += 1;
+        }
+        queue.summary.num_queued = queue.msgs.len() as u32;
+    }
+
+    /// Takes the messages queued for peers that are reachable again.
+    fn take_reachable(&mut self) -> OutgoingMsgs<T> {
+        let mut peers: Vec<PlayerNum> = self
+            .queues
+            .iter()
+            .filter(|(peer, queue)| !queue.msgs.is_empty() && !self.unreachable.contains(peer))
+            .map(|(peer, _)| *peer)
+            .collect();
+        peers.sort();
+        let mut to_send = Vec::new();
+        for peer in peers {
+            let queue = self.queues.get_mut(&peer).expect("queue exists");
+            to_send.extend(
+                queue
+                    .msgs
+                    .drain(..)
+                    .map(|msg| (Recipient::Player(peer), msg)),
+            );
+            queue.summary.num_queued = 0;
+        }
+        to_send
+    }
+
+    /// The messages queued, collapsed and dropped for this peer.
+    pub fn summary(&self, peer: PlayerNum) -> OutgoingQueueSummary {
+        self.queues
+            .get(&peer)
+            .map_or_else(OutgoingQueueSummary::default, |queue| queue.summary)
+    }
+}
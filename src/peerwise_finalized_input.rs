@@ -26,7 +26,7 @@ impl Display for PeerwiseFinalizedInputsSeen {
 impl PeerwiseFinalizedInputsSeen {
     pub fn new(num_players: u8) -> Self {
         Self(HashMap::from_iter(
-            (0..num_players).map(|i| (PlayerNum(i), 0)),
+            PlayerNum::iter(num_players).map(|p| (p, 0)),
         ))
     }
 
@@ -39,6 +39,14 @@ impl PeerwiseFinalizedInputsSeen {
         Self(map)
     }
 
+    /// Replaces the counts with `observed`, indexed by player num, reusing the map's storage.
+    pub(crate) fn set_observed(&mut self, observed: impl Iterator<Item = u32>) {
+        self.0.clear();
+        for (i, count) in observed.enumerate() {
+            self.0.insert(PlayerNum(i as u8), count);
+        }
+    }
+
     #[cfg(test)]
     pub fn new_test(map: HashMap<PlayerNum, u32>) -> Self {
         Self(map)
@@ -55,16 +63,7 @@ impl PeerwiseFinalizedInputsSeen {
     /// Update the ack with the ticks from another ack
     /// if the other ack has a newer tick for the same player_num.
     ///
-    /// FIXME: was encountering a bug where the input buffer was being re-initialized on guests, which caused the clients PeerwiseFinalizedInputsSeen to be reset to zeroes. This meant that when the host rxed the observation from the guest, because we only merge NEWER ticks, the host would ignore the guest's observation entirely, since all the ticks were zeroes. And thus, there would be a gap between what the host thought the guest had finalized and what the guest thought it had finalized. And this gap would never be filled, b/c the host would never send inputs prior to what it thought the guest had finalized.
-    /// To work around this, for now we just always update to the other ack's ticks regardless of whether they are newer or older.
-    pub fn merge_needs_to_be_fixed(&mut self, other: PeerwiseFinalizedInputsSeen) {
-        *self = other;
-    }
-
-    /// Update the ack with the ticks from another ack
-    /// if the other ack has a newer tick for the same player_num.
-    ///
-    /// FIXME: use this version!!
+    /// Acks never go backwards: a guest that reinitializes its buffers must say so explicitly (see `MsgPayload::GuestToHostBufferReset`), rather than sending an ack of zeroes.
     pub fn merge(&mut self, other: PeerwiseFinalizedInputsSeen) {
         for (player_num, tick) in other.0.iter() {
             if let Some(existing_tick) = self.0.get(player_num) {
@@ -0,0 +1,19 @@
+//! Display metadata for each seat, e.g. a player's name or account id.
+//!
+//! The host registers a small opaque blob per seat with `MultiplayerInputManager::register_player_metadata` (a string's bytes work too), and the registered metadata travels to guests in the `PreSimSync`, so every node can look a seat up with `player_metadata` instead of keeping its own map from player nums to players. Metadata registered after the `PreSimSync` was built reaches guests with the next one.
+//!
+//! Metadata belongs to the seat, so it stays put when a seat moves to another connection.
+
+use crate::util_types::PlayerNum;
+
+/// The largest metadata blob that can be registered for a seat.
+pub const MAX_PLAYER_METADATA_BYTES: usize = 64;
+
+/// Why `register_player_metadata` refused a blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerMetadataError {
+    /// The session has no such seat.
+    NoSuchPlayer(PlayerNum),
+    /// The blob is longer than `MAX_PLAYER_METADATA_BYTES`.
+    TooLong { len: usize, max: usize },
+}
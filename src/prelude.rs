@@ -0,0 +1,17 @@
+//! The types most game code needs, for a single glob import: `use temporal_input_buffer::prelude::*;`
+
+pub use crate::{
+    events::InputMgrEvent,
+    input_buffer::{InputConfidence, InputStatus},
+    input_messages::{MsgKind, MsgPayload, peek_variant},
+    input_schema::InputSchemaMismatch,
+    input_trait::SimInput,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    multiplayer_input_manager_spectator::SpectatorInputMgr,
+    rx_outcome::{RxOutcome, RxRejection},
+    session::{OutgoingMsgs, Recipient, Session, SessionRxError},
+    tick_consumption::TickConsumptionError,
+    util_types::PlayerNum,
+};
@@ -0,0 +1,172 @@
+//! Opt-in profiling of manager calls, for catching pathological cases (e.g. giant catch-up slices) during development.
+//!
+//! A `CallProfiler` wraps calls made through `measure`, recording the time each took and the bytes it allocated, aggregated per call name over each frame (see `end_frame`). Budgets can be set per call; calls over budget are counted, and with `with_budget_assertions` they panic in debug builds.
+//!
+//! A profiler can also be attached to a manager with `with_profiler`, in which case `Session` measures its own `add_own_input`, `rx_msg` and `get_outgoing_msgs_for_frame` calls under those names.
+//!
+//! The profiler never reads a clock or the allocator itself: both come from a `ProfileProbe` supplied by the game (typically `std::time::Instant` and a counting global allocator), so the crate stays deterministic and the profiler can be tested with a scripted probe.
+
+use std::collections::BTreeMap;
+
+/// The game's source of timestamps and allocation counts for a `CallProfiler`.
+pub trait ProfileProbe {
+    /// A monotonic timestamp, in nanoseconds.
+    fn now_nanos(&self) -> u64;
+    /// The total bytes allocated so far.
+    fn bytes_allocated(&self) -> u64;
+}
+
+impl<P: ProfileProbe + ?Sized> ProfileProbe for Box<P> {
+    fn now_nanos(&self) -> u64 {
+        (**self).now_nanos()
+    }
+
+    fn bytes_allocated(&self) -> u64 {
+        (**self).bytes_allocated()
+    }
+}
+
+/// The most a single call may take; `None` leaves that dimension unbounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CallBudget {
+    pub max_nanos: Option<u64>,
+    pub max_bytes: Option<u64>,
+}
+
+impl CallBudget {
+    /// True if a call that took `nanos` and allocated `bytes` goes over this budget.
+    pub fn is_exceeded_by(&self, nanos: u64, bytes: u64) -> bool {
+        self.max_nanos.is_some_and(|max| nanos > max)
+            || self.max_bytes.is_some_and(|max| bytes > max)
+    }
+}
+
+/// The calls to one method over a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CallStats {
+    pub calls: u32,
+    pub total_nanos: u64,
+    /// The longest single call
+    pub max_nanos: u64,
+    pub bytes_allocated: u64,
+    /// The most allocated by a single call
+    pub max_bytes: u64,
+    /// The calls that went over the method's budget
+    pub over_budget: u32,
+}
+
+impl CallStats {
+    fn record(&mut self, nanos: u64, bytes: u64, over_budget: bool) {
+        self.calls += 1;
+        self.total_nanos += nanos;
+        self.max_nanos = self.max_nanos.max(nanos);
+        self.bytes_allocated += bytes;
+        self.max_bytes = self.max_bytes.max(bytes);
+        self.over_budget += u32::from(over_budget);
+    }
+}
+
+/// The calls measured over one frame, by call name.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FrameProfile {
+    /// The number of frames ended before this one
+    pub frame: u64,
+    pub calls: BTreeMap<&'static str, CallStats>,
+}
+
+impl FrameProfile {
+    pub fn total_nanos(&self) -> u64 {
+        self.calls.values().map(|stats| stats.total_nanos).sum()
+    }
+
+    pub fn bytes_allocated(&self) -> u64 {
+        self.calls.values().map(|stats| stats.bytes_allocated).sum()
+    }
+
+    /// The names of the calls that went over budget at least once this frame.
+    pub fn calls_over_budget(&self) -> Vec<&'static str> {
+        self.calls
+            .iter()
+            .filter(|(_, stats)| stats.over_budget > 0)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+}
+
+/// Measures the time and allocations of the calls made through it, per frame.
+#[derive(Debug)]
+pub struct CallProfiler<P: ProfileProbe> {
+    probe: P,
+    /// CONFIG SETTING
+    /// The budget of each call name; calls without one are never over budget.
+    budgets: BTreeMap<&'static str, CallBudget>,
+    /// CONFIG SETTING
+    /// Whether a call over budget panics (in debug builds only).
+    assert_budgets: bool,
+    frame: FrameProfile,
+}
+
+impl<P: ProfileProbe> CallProfiler<P> {
+    pub fn new(probe: P) -> Self {
+        Self {
+            probe,
+            budgets: BTreeMap::default(),
+            assert_budgets: false,
+            frame: FrameProfile::default(),
+        }
+    }
+
+    /// Sets the budget of each call measured under this name.
+    pub fn with_budget(mut self, call: &'static str, budget: CallBudget) -> Self {
+        self.budgets.insert(call, budget);
+        self
+    }
+
+    /// Makes a call over its budget panic in debug builds, rather than only being counted. Release builds only count it.
+    pub fn with_budget_assertions(mut self, assert_budgets: bool) -> Self {
+        self.assert_budgets = assert_budgets;
+        self
+    }
+
+    pub fn probe(&self) -> &P {
+        &self.probe
+    }
+
+    /// Runs `f`, recording its time and allocations under `call` (by convention, the name of the manager method it calls).
+    pub fn measure<R>(&mut self, call: &'static str, f: impl FnOnce() -> R) -> R {
+        let start_nanos = self.probe.now_nanos();
+        let start_bytes = self.probe.bytes_allocated();
+        let result = f();
+        let nanos = self.probe.now_nanos().saturating_sub(start_nanos);
+        let bytes = self.probe.bytes_allocated().saturating_sub(start_bytes);
+
+        let budget = self.budgets.get(call);
+        let over_budget = budget.is_some_and(|budget| budget.is_exceeded_by(nanos, bytes));
+        self.frame
+            .calls
+            .entry(call)
+            .or_default()
+            .record(nanos, bytes, over_budget);
+        if over_budget && self.assert_budgets && cfg!(debug_assertions) {
+            panic!(
+                "`{call}` went over its budget of {:?}: took {nanos} ns and allocated {bytes} bytes",
+                budget.unwrap()
+            );
+        }
+        result
+    }
+
+    /// The calls measured so far this frame.
+    pub fn current_frame(&self) -> &FrameProfile {
+        &self.frame
+    }
+
+    /// Ends the frame, returning its profile and starting the next one empty.
+    pub fn end_frame(&mut self) -> FrameProfile {
+        let next = FrameProfile {
+            frame: self.frame.frame + 1,
+            calls: BTreeMap::default(),
+        };
+        std::mem::replace(&mut self.frame, next)
+    }
+}
@@ -0,0 +1,128 @@
+//! Optional reliable delivery of critical messages, over transports that may lose them (e.g. raw UDP), on top of sequence numbers (see `msg_sequence`).
+//!
+//! With reliable delivery enabled (see `MultiplayerInputManager::with_reliable_delivery`), each critical message (see `MsgKind::is_critical`) wrapped with `sequence_msg` is held until the peer acks its sequence number with a `MsgPayload::DeliveryAck`; `update_reliable_delivery` returns it again, with the same sequence number, each time the resend interval passes without an ack. A held message is dropped once a newer message to the same peer supersedes it (e.g. a longer finalized slice or a newer ack), since resending it would be redundant.
+//!
+//! Receivers ack every critical message decoded with `decode_sequenced_msg_from_peer`, duplicates included, since the first ack may have been lost. A message arriving more than `SEQUENCE_WINDOW` behind the newest can't be told from a duplicate, so it is neither applied nor acked. Both peers must enable reliable delivery: a peer without it never acks.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::{
+    input_messages::MsgPayload, input_trait::SimInput, msg_sequence::SequencedMsg,
+    outgoing_queue::supersedes, util_types::PlayerNum,
+};
+
+/// By default, critical messages are resent this long (sec) after they were last sent, if still unacked.
+pub const DEFAULT_RESEND_AFTER_SEC: f32 = 0.2;
+/// The most unacked messages held per peer; once over, the oldest is given up on.
+pub const MAX_PENDING_RELIABLE_MSGS: usize = 64;
+
+#[derive(Debug, Clone)]
+struct PendingMsg<T: SimInput> {
+    seq: u32,
+    msg: MsgPayload<T>,
+    /// The time (sec) since the message was last sent
+    since_sent_sec: f32,
+}
+
+/// Messages that need resending until acked, and acks owed to peers.
+#[derive(Debug, Clone)]
+pub(crate) struct ReliableDelivery<T: SimInput> {
+    /// CONFIG SETTING
+    /// How long (sec) to wait for an ack before resending; `None` disables reliable delivery.
+    pub(crate) resend_after_sec: Option<f32>,
+    /// For each peer, the critical messages sent to it and not yet acked, oldest first
+    pending: BTreeMap<PlayerNum, VecDeque<PendingMsg<T>>>,
+    /// For each peer, the sequence numbers of critical messages received from it and not yet acked
+    acks_due: BTreeMap<PlayerNum, Vec<u32>>,
+    num_resent: BTreeMap<PlayerNum, u32>,
+}
+
+impl<T: SimInput> Default for ReliableDelivery<T> {
+    fn default() -> Self {
+        Self {
+            resend_after_sec: None,
+            pending: BTreeMap::new(),
+            acks_due: BTreeMap::new(),
+            num_resent: BTreeMap::new(),
+        }
+    }
+}
+
+/// True if a message should be resent until acked: a critical message, or a batch holding one.
+fn is_critical<T: SimInput>(msg: &MsgPayload<T>) -> bool {
+    match msg {
+        MsgPayload::Batch(msgs) => msgs.iter().any(is_critical),
+        msg => msg.kind().is_critical(),
+    }
+}
+
+impl<T: SimInput> ReliableDelivery<T> {
+    /// Holds a critical message sent to `destination` until it is acked, dropping any held message it supersedes.
+    pub(crate) fn track_sent(&mut self, destination: PlayerNum, seq: u32, msg: &MsgPayload<T>) {
+        if self.resend_after_sec.is_none() || !is_critical(msg) {
+            return;
+        }
+        let pending = self.pending.entry(destination).or_default();
+        pending.retain(|older| !supersedes(msg, &older.msg));
+        if pending.len() == MAX_PENDING_RELIABLE_MSGS {
+            pending.pop_front();
+        }
+        pending.push_back(PendingMsg {
+            seq,
+            msg: msg.clone(),
+            since_sent_sec: 0.0,
+        });
+    }
+
+    /// Owes `sender` an ack if the message it sent with `seq` is critical.
+    pub(crate) fn track_received(&mut self, sender: PlayerNum, seq: u32, msg: &MsgPayload<T>) {
+        if self.resend_after_sec.is_some() && is_critical(msg) {
+            self.acks_due.entry(sender).or_default().push(seq);
+        }
+    }
+
+    /// Stops resending the messages `sender` has acked.
+    pub(crate) fn rx_ack(&mut self, sender: PlayerNum, seqs: &[u32]) {
+        if let Some(pending) = self.pending.get_mut(&sender) {
+            pending.retain(|msg| !seqs.contains(&msg.seq));
+        }
+    }
+
+    /// Takes the acks owed to each peer.
+    pub(crate) fn take_acks_due(&mut self) -> BTreeMap<PlayerNum, Vec<u32>> {
+        std::mem::take(&mut self.acks_due)
+    }
+
+    /// Advances the resend timers by `delta` (sec), returning the messages due to be resent, by peer.
+    pub(crate) fn poll_resends(&mut self, delta: f32) -> Vec<(PlayerNum, SequencedMsg<T>)> {
+        let Some(resend_after_sec) = self.resend_after_sec else {
+            return vec![];
+        };
+        let mut resends = vec![];
+        for (&destination, pending) in self.pending.iter_mut() {
+            for msg in pending.iter_mut() {
+                msg.since_sent_sec += delta;
+                if msg.since_sent_sec >= resend_after_sec {
+                    msg.since_sent_sec = 0.0;
+                    *self.num_resent.entry(destination).or_default() += 1;
+                    resends.push((
+                        destination,
+                        SequencedMsg {
+                            seq: msg.seq,
+                            payload: msg.msg.clone(),
+                        },
+                    ));
+                }
+            }
+        }
+        resends
+    }
+
+    pub(crate) fn num_pending(&self, destination: PlayerNum) -> usize {
+        self.pending.get(&destination).map_or(0, VecDeque::len)
+    }
+
+    pub(crate) fn num_resent(&self, destination: PlayerNum) -> u32 {
+        self.num_resent.get(&destination).copied().unwrap_or(0)
+    }
+}
@@ -0,0 +1,492 @@
+//! A documented, versioned container for exporting a match's finalized inputs, so that tools that don't link this crate (replay viewers, analytics pipelines in other languages) can read them.
+//!
+//! # Format (version 1)
+//!
+//! All integers are little-endian.
+//!
+//! Header (23 bytes):
+//!
+//! | offset | size | field                                                  |
+//! |--------|------|--------------------------------------------------------|
+//! | 0      | 4    | magic, the ASCII bytes `TIBR`                          |
+//! | 4      | 2    | format version (`u16`), currently 1                    |
+//! | 6      | 2    | flags (`u16`); see below                               |
+//! | 8      | 4    | ticks per second (`u32`)                               |
+//! | 12     | 4    | start tick (`u32`), the sim tick of the first input    |
+//! | 16     | 4    | number of ticks (`u32`)                                |
+//! | 20     | 1    | number of players (`u8`)                               |
+//! | 21     | 2    | input width in bytes (`u16`)                           |
+//!
+//! Then one chunk per player, in player order:
+//!
+//! | size                     | field                                       |
+//! |--------------------------|---------------------------------------------|
+//! | 1                        | player num (`u8`)                           |
+//! | 4                        | chunk length in bytes (`u32`)               |
+//! | number of ticks * width  | the player's inputs, one per tick           |
+//!
+//! Each input is the player's `SimInput::Bytes`, encoded with bincode's fixed-int encoding, so every input has the same width. How those bytes map to buttons and axes is up to the game.
+//!
+//! If flag bit 0 is set, the player chunks are followed by one `u32` checksum per tick: the 32-bit FNV-1a hash of that tick's inputs for all players, concatenated in player order.
+//!
+//! If flag bit 1 is set, these are followed by one `u64` per player, in player order: the head of the player's input chain over the replay's inputs (see `input_hash_chain`). A replay of a whole round can be checked against the chain heads peers exchanged during the match.
+//!
+//! Readers must reject unknown versions and flags, and trailing bytes.
+//!
+//! A `ReplayRecorder` builds a `Replay` as a match is played, and a `ReplayPlayer` feeds one back into a manager, so the game's sim can read replayed inputs through the same API as live ones.
+
+use crate::{
+    input_hash_chain::chain_head,
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    input_trait::SimInput,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+/// The magic bytes at the start of every replay.
+pub const REPLAY_MAGIC: [u8; 4] = *b"TIBR";
+
+/// The replay format version written by `Replay::to_bytes`.
+pub const REPLAY_FORMAT_VERSION: u16 = 1;
+
+/// Header flag set when per-tick checksums follow the player chunks.
+pub const REPLAY_FLAG_CHECKSUMS: u16 = 1 << 0;
+
+/// Header flag set when each player's input chain head follows the checksums.
+pub const REPLAY_FLAG_CHAIN_HEADS: u16 = 1 << 1;
+
+const KNOWN_FLAGS: u16 = REPLAY_FLAG_CHECKSUMS | REPLAY_FLAG_CHAIN_HEADS;
+
+const HEADER_LEN: usize = 23;
+
+fn input_encoding_config() -> impl bincode::config::Config {
+    bincode::config::standard().with_fixed_int_encoding()
+}
+
+/// The 32-bit FNV-1a hash used for per-tick checksums.
+fn fnv1a_32(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, b| {
+        (hash ^ *b as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// The finalized inputs of a match, in a form that can be exported to (and read back from) the replay format described in this module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay<T: SimInput> {
+    pub ticks_per_sec: u32,
+    /// The sim tick of the first input
+    pub start_tick: u32,
+    /// Each player's inputs, indexed by player num; all players have the same number of inputs.
+    pub inputs_by_player: Vec<Vec<T::Bytes>>,
+}
+
+impl<T: SimInput> Replay<T> {
+    /// The number of ticks covered by this replay.
+    pub fn num_ticks(&self) -> u32 {
+        self.inputs_by_player.first().map_or(0, |i| i.len() as u32)
+    }
+
+    pub fn num_players(&self) -> u8 {
+        self.inputs_by_player.len() as u8
+    }
+
+    /// This player's input at the given sim tick, or `None` if the tick is outside the replay.
+    pub fn input(&self, player_num: PlayerNum, tick: u32) -> Option<T> {
+        let index = tick.checked_sub(self.start_tick)?;
+        self.inputs_by_player
+            .get(usize::from(player_num))?
+            .get(index as usize)
+            .map(|bytes| T::from_bytes(*bytes))
+    }
+
+    /// The head of each player's input chain over this replay's inputs, indexed by player num.
+    pub fn chain_heads(&self) -> Vec<u64> {
+        self.inputs_by_player
+            .iter()
+            .map(|inputs| chain_head::<T>(inputs))
+            .collect()
+    }
+
+    /// Writes this replay in the documented format, optionally with per-tick checksums.
+    ///
+    /// Fails if the inputs don't all encode to the same width, or if the replay is too large for the header's fields.
+    pub fn to_bytes(&self, with_checksums: bool) -> Result<Vec<u8>, String> {
+        let flags = if with_checksums {
+            REPLAY_FLAG_CHECKSUMS
+        } else {
+            0
+        };
+        self.to_bytes_with_flags(flags)
+    }
+
+    /// Like `to_bytes`, with the optional sections chosen by `REPLAY_FLAG_*` bits.
+    pub fn to_bytes_with_flags(&self, flags: u16) -> Result<Vec<u8>, String> {
+        if flags & !KNOWN_FLAGS != 0 {
+            return Err(format!("unsupported replay flags {flags:#06x}"));
+        }
+        let num_ticks = self.num_ticks();
+        let num_players = u8::try_from(self.inputs_by_player.len())
+            .map_err(|_| "too many players for a replay".to_string())?;
+
+        // encode every player's inputs up front, to learn the input width
+        let width = encode_input::<T>(&T::Bytes::default())?.len();
+        let mut chunks = Vec::with_capacity(num_players as usize);
+        for inputs in &self.inputs_by_player {
+            if inputs.len() as u32 != num_ticks {
+                return Err("all players must have the same number of inputs".into());
+            }
+            let mut chunk = Vec::with_capacity(inputs.len() * width);
+            for input in inputs {
+                let encoded = encode_input::<T>(input)?;
+                if encoded.len() != width {
+                    return Err(format!(
+                        "inputs must encode to a fixed width of {width} bytes, got {}",
+                        encoded.len()
+                    ));
+                }
+                chunk.extend(encoded);
+            }
+            chunks.push(chunk);
+        }
+        let width = u16::try_from(width).map_err(|_| "input width is too large".to_string())?;
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN);
+        bytes.extend(REPLAY_MAGIC);
+        bytes.extend(REPLAY_FORMAT_VERSION.to_le_bytes());
+        bytes.extend(flags.to_le_bytes());
+        bytes.extend(self.ticks_per_sec.to_le_bytes());
+        bytes.extend(self.start_tick.to_le_bytes());
+        bytes.extend(num_ticks.to_le_bytes());
+        bytes.push(num_players);
+        bytes.extend(width.to_le_bytes());
+
+        for (player_num, chunk) in chunks.iter().enumerate() {
+            let chunk_len =
+                u32::try_from(chunk.len()).map_err(|_| "player chunk is too large".to_string())?;
+            bytes.push(player_num as u8);
+            bytes.extend(chunk_len.to_le_bytes());
+            bytes.extend(chunk);
+        }
+
+        if flags & REPLAY_FLAG_CHECKSUMS != 0 {
+            for checksum in tick_checksums(&chunks, width as usize, num_ticks) {
+                bytes.extend(checksum.to_le_bytes());
+            }
+        }
+        if flags & REPLAY_FLAG_CHAIN_HEADS != 0 {
+            for head in self.chain_heads() {
+                bytes.extend(head.to_le_bytes());
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Reads a replay written by `to_bytes`, verifying its checksums if it has any.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = ByteReader { bytes, pos: 0 };
+        if reader.take(4)? != REPLAY_MAGIC {
+            return Err("missing replay magic bytes".into());
+        }
+        let version = reader.u16()?;
+        if version != REPLAY_FORMAT_VERSION {
+            return Err(format!("unsupported replay format version {version}"));
+        }
+        let flags = reader.u16()?;
+        if flags & !KNOWN_FLAGS != 0 {
+            return Err(format!("unsupported replay flags {flags:#06x}"));
+        }
+        let ticks_per_sec = reader.u32()?;
+        let start_tick = reader.u32()?;
+        let num_ticks = reader.u32()? as usize;
+        let num_players = reader.u8()?;
+        let width = reader.u16()? as usize;
+        if width != encode_input::<T>(&T::Bytes::default())?.len() {
+            return Err(format!(
+                "replay input width {width} doesn't match this input type"
+            ));
+        }
+
+        let mut chunks = Vec::with_capacity(num_players as usize);
+        for expected_player in 0..num_players {
+            let player_num = reader.u8()?;
+            if player_num != expected_player {
+                return Err(format!(
+                    "expected the chunk for player {expected_player}, got player {player_num}"
+                ));
+            }
+            let chunk_len = reader.u32()? as usize;
+            if Some(chunk_len) != num_ticks.checked_mul(width) {
+                return Err(format!(
+                    "chunk for player {player_num} has length {chunk_len}, expected {num_ticks} inputs of {width} bytes"
+                ));
+            }
+            chunks.push(reader.take(chunk_len)?);
+        }
+
+        if flags & REPLAY_FLAG_CHECKSUMS != 0 {
+            let expected = tick_checksums(&chunks, width, num_ticks as u32);
+            for (tick_index, expected) in expected.into_iter().enumerate() {
+                if reader.u32()? != expected {
+                    return Err(format!(
+                        "checksum mismatch at tick {}",
+                        start_tick as usize + tick_index
+                    ));
+                }
+            }
+        }
+        let chain_heads = if flags & REPLAY_FLAG_CHAIN_HEADS != 0 {
+            (0..num_players)
+                .map(|_| reader.u64())
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            vec![]
+        };
+        if reader.pos != bytes.len() {
+            return Err("unexpected trailing bytes after replay".into());
+        }
+
+        let inputs_by_player = chunks
+            .iter()
+            .map(|chunk| {
+                (0..num_ticks)
+                    .map(|i| decode_input::<T>(&chunk[i * width..(i + 1) * width]))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let replay = Self {
+            ticks_per_sec,
+            start_tick,
+            inputs_by_player,
+        };
+        if !chain_heads.is_empty() {
+            for (player_num, (expected, head)) in replay
+                .chain_heads()
+                .into_iter()
+                .zip(chain_heads)
+                .enumerate()
+            {
+                if head != expected {
+                    return Err(format!("input chain mismatch for player {player_num}"));
+                }
+            }
+        }
+        Ok(replay)
+    }
+}
+
+fn encode_input<T: SimInput>(input: &T::Bytes) -> Result<Vec<u8>, String> {
+    bincode::serde::encode_to_vec(input, input_encoding_config())
+        .map_err(|e| format!("failed to encode input: {e}"))
+}
+
+/// Builds a `Replay` of a manager's current round as its inputs are finalized.
+///
+/// Unlike `MultiplayerInputManager::export_replay`, which can only cover the inputs still retained, a recorder keeps every input it has recorded, so a whole match can be replayed even with `with_max_retained_inputs` set, as long as `record` is called before inputs are dropped (e.g. once per frame).
+#[derive(Debug, Clone)]
+pub struct ReplayRecorder<T: SimInput> {
+    replay: Replay<T>,
+    /// The round being recorded
+    round: u32,
+    /// The input index of the replay's first input
+    first_input: u32,
+}
+
+impl<T: SimInput> ReplayRecorder<T> {
+    /// Starts recording the manager's current round from its oldest retained input.
+    pub fn new<Buf>(mgr: &MultiplayerInputManager<T, Buf>) -> Self {
+        let first_input = mgr.buffers.first_retained_input_across_peers();
+        Self {
+            replay: Replay {
+                ticks_per_sec: mgr.ticks_per_sec,
+                start_tick: mgr.buffers.tick_of_input(first_input),
+                inputs_by_player: vec![Vec::new(); mgr.buffers.num_players() as usize],
+            },
+            round: mgr.current_round(),
+            first_input,
+        }
+    }
+
+    /// Appends the inputs finalized for every player since the last call, returning the number of ticks appended. Players added since the last call are given default inputs for the ticks already recorded, as the host finalizes for them.
+    ///
+    /// Fails, appending nothing, if the manager has moved on to another round, or if inputs that haven't been recorded yet have already been dropped (see `with_max_retained_inputs`).
+    pub fn record<Buf>(&mut self, mgr: &MultiplayerInputManager<T, Buf>) -> Result<u32, String> {
+        if mgr.current_round() != self.round {
+            return Err(format!(
+                "recording round {} but the manager is in round {}",
+                self.round,
+                mgr.current_round()
+            ));
+        }
+        let buffers = &mgr.buffers;
+        let next = self.first_input + self.replay.num_ticks();
+        let end = buffers.get_num_finalized_inputs_across_peers().max(next);
+        // removed players' inputs past their end read as defaults, dropped or not
+        let dropped = PlayerNum::iter(buffers.num_players()).find(|player_num| {
+            buffers.first_retained_input(*player_num) > next
+                && buffers
+                    .removed_player_end(*player_num)
+                    .is_none_or(|removed_end| next < removed_end)
+        });
+        if let Some(player_num) = dropped.filter(|_| end > next) {
+            return Err(format!(
+                "{player_num:?}'s input {next} was dropped before it was recorded"
+            ));
+        }
+        let num_recorded = self.replay.num_ticks() as usize;
+        self.replay.inputs_by_player.resize(
+            buffers.num_players() as usize,
+            vec![T::Bytes::default(); num_recorded],
+        );
+        for (player, inputs) in self.replay.inputs_by_player.iter_mut().enumerate() {
+            let player_num = PlayerNum(player as u8);
+            inputs.extend((next..end).map(|index| {
+                buffers
+                    .get_input_or_prediction(player_num, index)
+                    .to_bytes()
+            }));
+        }
+        Ok(end - next)
+    }
+
+    /// The replay recorded so far.
+    pub fn replay(&self) -> &Replay<T> {
+        &self.replay
+    }
+
+    pub fn into_replay(self) -> Replay<T> {
+        self.replay
+    }
+}
+
+/// Plays a `Replay` back into a guest manager, one tick at a time, as finalized slices like those the host sends.
+///
+/// The manager's snapshottable tick advances with each tick played, so a game can drive its sim from a replay with the same code it uses for live matches.
+#[derive(Debug, Clone)]
+pub struct ReplayPlayer<T: SimInput> {
+    replay: Replay<T>,
+    /// The number of ticks played so far
+    num_played: u32,
+}
+
+impl<T: SimInput> ReplayPlayer<T> {
+    pub fn new(replay: Replay<T>) -> Self {
+        Self {
+            replay,
+            num_played: 0,
+        }
+    }
+
+    /// A guest manager to play the replay into, with a seat for each of the replay's players, starting at the replay's start tick.
+    ///
+    /// Its own seat is the last player's, but it collects no inputs of its own: every player's inputs come from the replay.
+    pub fn new_manager(&self) -> MultiplayerInputManager<T, GuestInputMgr> {
+        let num_players = self.replay.num_players();
+        let own_player_num = PlayerNum(num_players.saturating_sub(1));
+        let mut mgr = MultiplayerInputManager::<T, GuestInputMgr>::new(
+            num_players,
+            own_player_num,
+            self.replay.ticks_per_sec,
+        );
+        mgr.buffers.set_start_tick(self.replay.start_tick);
+        mgr
+    }
+
+    /// The finalized slice of each player's input for the next tick, or nothing once the replay has been played to the end.
+    pub fn next_tick_msgs(&mut self) -> Vec<MsgPayload<T>> {
+        if self.is_finished() {
+            return Vec::new();
+        }
+        let index = self.num_played;
+        self.num_played += 1;
+        self.replay
+            .inputs_by_player
+            .iter()
+            .enumerate()
+            .map(|(player, inputs)| {
+                MsgPayload::HostToLobbyFinalizedSlice(HostFinalizedSlice {
+                    player_num: PlayerNum(player as u8),
+                    host_tick: self.num_played,
+                    inputs: PlayerInputSlice {
+                        start: index,
+                        inputs: vec![inputs[index as usize]],
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Plays the next tick into `mgr` (see `new_manager`). Returns false, changing nothing, once the replay has been played to the end.
+    pub fn step(&mut self, mgr: &mut MultiplayerInputManager<T, GuestInputMgr>) -> bool {
+        let msgs = self.next_tick_msgs();
+        let played = !msgs.is_empty();
+        for msg in msgs {
+            mgr.rx_final_peer_input_slice_from_host(msg);
+        }
+        played
+    }
+
+    pub fn num_ticks_played(&self) -> u32 {
+        self.num_played
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.num_played >= self.replay.num_ticks()
+    }
+}
+
+fn decode_input<T: SimInput>(bytes: &[u8]) -> Result<T::Bytes, String> {
+    bincode::serde::decode_from_slice(bytes, input_encoding_config())
+        .map(|(input, _)| input)
+        .map_err(|e| format!("failed to decode input: {e}"))
+}
+
+/// The checksum of each tick's inputs across all players' encoded chunks.
+fn tick_checksums<C: AsRef<[u8]>>(chunks: &[C], width: usize, num_ticks: u32) -> Vec<u32> {
+    (0..num_ticks as usize)
+        .map(|tick_index| {
+            let tick_bytes: Vec<u8> = chunks
+                .iter()
+                .flat_map(|chunk| &chunk.as_ref()[tick_index * width..(tick_index + 1) * width])
+                .copied()
+                .collect();
+            fnv1a_32(&tick_bytes)
+        })
+        .collect()
+}
+
+/// Reads little-endian fields from the front of a byte slice.
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| format!("replay truncated at byte {}", self.pos))?;
+        let taken = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
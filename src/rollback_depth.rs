@@ -0,0 +1,64 @@
+use std::collections::VecDeque;
+
+use crate::latency_stats::{LatencyHistogram, LatencySummary};
+
+/// By default, the rollback depth histogram covers this many of the most recent samples.
+pub(crate) const DEFAULT_ROLLBACK_DEPTH_WINDOW: u32 = 600;
+
+/// Tracks recent rollback depths (own tick minus snapshottable tick), and whether the configured cap has been reached.
+#[derive(Debug, Clone)]
+pub(crate) struct RollbackDepthTracker {
+    /// CONFIG SETTING
+    /// The rollback depth at which `InputMgrEvent::RollbackDepthCapReached` is raised, and at which guests stop collecting new inputs.
+    pub(crate) cap: Option<u32>,
+    /// CONFIG SETTING
+    /// The number of most recent samples covered by the histogram.
+    window: u32,
+    /// The most recent samples, oldest first
+    samples: VecDeque<u32>,
+    histogram: LatencyHistogram,
+    /// True while the depth is at or above the cap, so that the event is raised once each time the cap is reached.
+    at_cap: bool,
+}
+
+impl Default for RollbackDepthTracker {
+    fn default() -> Self {
+        Self {
+            cap: None,
+            window: DEFAULT_ROLLBACK_DEPTH_WINDOW,
+            samples: VecDeque::default(),
+            histogram: LatencyHistogram::default(),
+            at_cap: false,
+        }
+    }
+}
+
+impl RollbackDepthTracker {
+    pub(crate) fn set_window(&mut self, window: u32) {
+        self.window = window.max(1);
+        self.trim();
+    }
+
+    /// Records a depth sample. Returns true if the depth has just reached the cap.
+    pub(crate) fn observe(&mut self, depth: u32) -> bool {
+        self.samples.push_back(depth);
+        self.histogram.observe(depth);
+        self.trim();
+
+        let was_at_cap = self.at_cap;
+        self.at_cap = self.cap.is_some_and(|cap| depth >= cap);
+        self.at_cap && !was_at_cap
+    }
+
+    fn trim(&mut self) {
+        while self.samples.len() > self.window as usize {
+            let oldest = self.samples.pop_front().unwrap();
+            self.histogram.forget(oldest);
+        }
+    }
+
+    /// `None` if no samples have been observed.
+    pub(crate) fn summary(&self) -> Option<LatencySummary> {
+        self.histogram.summary()
+    }
+}
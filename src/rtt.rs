@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ewma::Ewma;
+
+/// By default, each new RTT sample has this weight in the smoothed RTT.
+pub const DEFAULT_RTT_SMOOTHING: f32 = 0.1;
+
+/// By default, an RTT estimate is reported as reliable once it has this many samples.
+pub const DEFAULT_RTT_WARM_UP_SAMPLES: u32 = 3;
+
+/// By default, guests pad their estimate of the host's current tick by this many times the RTT jitter.
+pub const DEFAULT_RTT_JITTER_PADDING: f32 = 1.0;
+
+/// How RTT estimates are bootstrapped and smoothed, on both hosts and guests.
+///
+/// Every estimate starts from its first sample, and is then smoothed with an EWMA. The jitter (the smoothed deviation of samples from the smoothed RTT) uses the same smoothing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RttConfig {
+    /// The weight (0 to 1) of each new sample in the smoothed RTT
+    pub smoothing: f32,
+    /// The number of samples an estimate needs before it is reported as reliable
+    pub warm_up_samples: u32,
+    /// The multiple of the one-way jitter a guest adds to its estimate of the host's current tick in `num_inputs_needed`, so that it keeps collecting inputs ahead of late messages; 0 disables the padding
+    pub jitter_padding: f32,
+}
+
+impl Default for RttConfig {
+    fn default() -> Self {
+        Self {
+            smoothing: DEFAULT_RTT_SMOOTHING,
+            warm_up_samples: DEFAULT_RTT_WARM_UP_SAMPLES,
+            jitter_padding: DEFAULT_RTT_JITTER_PADDING,
+        }
+    }
+}
+
+/// A smoothed RTT, with the number of samples behind it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RttSummary {
+    pub rtt_ms: f32,
+    pub num_samples: u32,
+    /// True once `num_samples` has reached the configured warm-up count
+    pub is_reliable: bool,
+}
+
+/// The guest's and the host's RTT measurements for their link, assembled by the host when a ping cycle completes and sent on to the guest.
+///
+/// The guest's round trip (ping, pong) includes the host's turnaround time, and the host's round trip (pong, pong-pong) the guest's, so the difference between the two RTTs is how much longer the host takes to turn messages around than the guest does. This says nothing about the two directions of the link, which RTTs alone can't tell apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PingReport {
+    /// The guest's smoothed RTT (whole microseconds), as reported in its last pong-pong
+    pub guest_rtt_micros: u32,
+    /// The host's smoothed RTT (whole microseconds) to the guest
+    pub host_rtt_micros: u32,
+    /// `guest_rtt_micros - host_rtt_micros`: positive when the host is slower to turn messages around than the guest
+    pub turnaround_gap_micros: i32,
+}
+
+impl PingReport {
+    pub fn new(guest_rtt_micros: u32, host_rtt_micros: u32) -> Self {
+        Self {
+            guest_rtt_micros,
+            host_rtt_micros,
+            turnaround_gap_micros: (guest_rtt_micros as i64 - host_rtt_micros as i64) as i32,
+        }
+    }
+}
+
+/// Converts a duration in ms to whole microseconds, rounding to the nearest microsecond (halves away from zero).
+pub(crate) fn ms_to_micros(ms: f32) -> i64 {
+    (ms as f64 * 1000.0).round() as i64
+}
+
+/// A smoothed RTT estimate for one link.
+#[derive(Debug, Default)]
+pub(crate) struct RttEstimate {
+    /// `None` until the first sample
+    ewma: Option<Ewma>,
+    /// The smoothed deviation of samples from the smoothed RTT; `None` until the second sample
+    jitter: Option<Ewma>,
+    /// The lowest sample; `None` until the first sample
+    min: Option<f32>,
+    num_samples: u32,
+}
+
+impl RttEstimate {
+    pub(crate) fn observe(&mut self, rtt_ms: f32, config: &RttConfig) {
+        match self.ewma.as_mut() {
+            None => self.ewma = Some(Ewma::new_with_value(config.smoothing, rtt_ms)),
+            Some(ewma) => {
+                let deviation = (rtt_ms - ewma.value()).abs();
+                match self.jitter.as_mut() {
+                    None => self.jitter = Some(Ewma::new_with_value(config.smoothing, deviation)),
+                    Some(jitter) => jitter.observe(deviation),
+                }
+                ewma.observe(rtt_ms);
+            }
+        }
+        self.min = Some(self.min.map_or(rtt_ms, |min| min.min(rtt_ms)));
+        self.num_samples += 1;
+    }
+
+    /// `None` until the first sample.
+    pub(crate) fn value(&self) -> Option<f32> {
+        self.ewma.as_ref().map(Ewma::value)
+    }
+
+    /// `None` until the second sample.
+    pub(crate) fn jitter(&self) -> Option<f32> {
+        self.jitter.as_ref().map(Ewma::value)
+    }
+
+    /// `None` until the first sample.
+    pub(crate) fn min(&self) -> Option<f32> {
+        self.min
+    }
+
+    pub(crate) fn num_samples(&self) -> u32 {
+        self.num_samples
+    }
+
+    /// `None` until the first sample.
+    pub(crate) fn summary(&self, config: &RttConfig) -> Option<RttSummary> {
+        Some(RttSummary {
+            rtt_ms: self.value()?,
+            num_samples: self.num_samples,
+            is_reliable: self.num_samples >= config.warm_up_samples,
+        })
+    }
+}
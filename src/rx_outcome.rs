@@ -0,0 +1,54 @@
+/// Why an `rx_*` call left the buffers unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RxRejection {
+    /// The message was not of the variant this `rx_*` method handles.
+    UnexpectedMsg,
+    /// The sender hasn't acked the current round yet, so its message may belong to the previous round.
+    AwaitingRoundAck,
+    /// The finalized slice starts after the next input that needs finalizing, and would leave a gap; holds the sim tick of that input.
+    GapAtTick(u32),
+    /// All of the slice's inputs fall after its player was muted.
+    PlayerMuted,
+    /// All of the slice's inputs fall at or past the end of the session (see `MultiplayerInputManager::with_max_session_ticks`).
+    PastSessionEnd,
+    /// The slice is for a player beyond this node's `num_players`. A guest holds such finalized slices until the player is registered (see `MultiplayerInputManager::register_players`).
+    UnknownPlayer,
+    /// All of the slice's inputs reach past the max input lead (see `MultiplayerInputManager::with_max_input_lead`).
+    BeyondInputLead,
+    /// The host is recovering from a restart (see `MultiplayerInputManager::begin_recovery`), and accepts no inputs until it has rebuilt its history.
+    HostRecovering,
+    /// The player has been removed from the session (see `MultiplayerInputManager::remove_player`).
+    PlayerRemoved,
+    /// The sender is not a registered spectator (see `MultiplayerInputManager::add_spectator`).
+    UnknownSpectator,
+    /// The bundled slice is for a seat that the sender's connection doesn't control (see `MultiplayerInputManager::add_local_seat`).
+    NotLocalSeat,
+}
+
+/// A summary of what a single `rx_*` call changed in the buffers.
+///
+/// Callers can use this to react immediately (e.g. trigger a rollback when new inputs arrive) rather than re-querying buffer counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RxOutcome {
+    /// The number of inputs added to the end of the buffer
+    pub new_inputs: u32,
+    /// The number of inputs that became finalized
+    pub newly_finalized: u32,
+    /// The number of inputs already held, but not finalized, whose value changed, e.g. provisional inputs corrected by a later slice. A rollback sim must re-simulate from these as well as from new inputs.
+    pub rewritten_inputs: u32,
+    pub rejected: Option<RxRejection>,
+}
+
+impl RxOutcome {
+    pub fn rejected(reason: RxRejection) -> Self {
+        Self {
+            rejected: Some(reason),
+            ..Default::default()
+        }
+    }
+
+    /// True if the call added, rewrote or finalized any inputs.
+    pub fn changed(&self) -> bool {
+        self.new_inputs > 0 || self.rewritten_inputs > 0 || self.newly_finalized > 0
+    }
+}
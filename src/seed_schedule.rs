@@ -0,0 +1,70 @@
+//! A lobby-wide RNG seed, coordinated with the input timeline.
+//!
+//! Lockstep sims need every node to draw from the same RNG seed at the same tick. The host sets the session's initial seed with `MultiplayerInputManager::with_initial_seed`, which reaches guests in the `PreSimSync`, and may change the seed from a later tick with `set_seed`. Each change is broadcast as a `HostToLobbySeed` message and resent until every guest has acked it, so seeds travel the same reliable path as finalized inputs rather than an ad-hoc side channel.
+//!
+//! Games look up the seed in effect for a tick with `seed_for_tick`. Like inputs, a seed change may arrive after a guest has already predicted its tick, so games should treat a newly arrived change for a past tick like a late input.
+
+use serde::{Deserialize, Serialize};
+
+/// A change of the lobby's seed, taking effect from a tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedChange {
+    /// The position of this change in the host's schedule, 0 for the first change after the initial seed
+    pub change_num: u32,
+    /// The index (counted from the session's start tick) of the first input to which the seed applies
+    pub tick: u32,
+    pub seed: u64,
+}
+
+/// The initial seed, and the changes made to it since, in tick order.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SeedSchedule {
+    pub(crate) initial: u64,
+    changes: Vec<SeedChange>,
+}
+
+impl SeedSchedule {
+    pub(crate) fn num_changes(&self) -> u32 {
+        self.changes.len() as u32
+    }
+
+    pub(crate) fn last_change(&self) -> Option<&SeedChange> {
+        self.changes.last()
+    }
+
+    pub(crate) fn change(&self, change_num: u32) -> Option<SeedChange> {
+        self.changes.get(change_num as usize).copied()
+    }
+
+    /// The seed in effect at this input index: that of the latest change at or before it, or else the initial seed.
+    pub(crate) fn seed_at(&self, index: u32) -> u64 {
+        self.changes
+            .iter()
+            .rev()
+            .find(|change| change.tick <= index)
+            .map_or(self.initial, |change| change.seed)
+    }
+
+    /// Appends a change; the caller checks that it is in tick order.
+    pub(crate) fn push(&mut self, tick: u32, seed: u64) {
+        self.changes.push(SeedChange {
+            change_num: self.num_changes(),
+            tick,
+            seed,
+        });
+    }
+
+    /// Stores a change received from the host if it is the next one in the schedule, returning whether it was stored. Resent and out-of-order changes are ignored.
+    pub(crate) fn receive(&mut self, change: SeedChange) -> bool {
+        if change.change_num != self.num_changes() {
+            return false;
+        }
+        self.changes.push(change);
+        true
+    }
+
+    /// Forgets the changes (but not the initial seed), e.g. when ticks restart at a new round.
+    pub(crate) fn clear_changes(&mut self) {
+        self.changes.clear();
+    }
+}
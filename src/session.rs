@@ -0,0 +1,544 @@
+//! A role-agnostic wrapper over the host and guest input managers.
+//!
+//! Game code that doesn't care whether it is hosting can hold a `Session<T>` and use its shared operations (adding own inputs, receiving messages, reading inputs and ticks), which route each message to the right `rx_*` method and return the replies to send. Role-specific calls remain available through `as_host_mut`/`as_guest_mut`.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::{
+    capabilities::Capabilities,
+    change_stamps::ChangeStamps,
+    events::InputMgrEvent,
+    input_buffer::InputStatus,
+    input_messages::MsgPayload,
+    input_schema::InputSchemaMismatch,
+    input_trait::SimInput,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    net_stats::NetStats,
+    outgoing_queue::supersedes,
+    util_types::PlayerNum,
+};
+
+/// Who an outgoing message should be sent to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recipient {
+    Player(PlayerNum),
+    /// Every other player in the session
+    AllPeers,
+}
+
+/// Messages to send, paired with their recipient.
+pub type OutgoingMsgs<T> = Vec<(Recipient, MsgPayload<T>)>;
+
+/// Why a `Session` couldn't handle a received message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionRxError {
+    /// The message bytes couldn't be decoded (see `MultiplayerInputManager::decode_msg_from_peer`).
+    Decode,
+    /// The sender can't send messages to this node, e.g. a host receiving a message from itself.
+    InvalidSender,
+    /// The message is not one this node's role receives, e.g. a host receiving a finalized slice.
+    WrongRole,
+    /// The manager rejected the message.
+    Rejected(String),
+    /// The host encodes inputs with a different schema than this guest (see `input_schema`), so the guest can't join its session.
+    InputSchemaMismatch(InputSchemaMismatch),
+}
+
+/// Where a node is in the session's lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionPhase {
+    /// Players are gathering: the host hasn't built its `PreSimSync` yet, or a guest hasn't received it.
+    Lobby,
+    /// The `PreSimSync` countdown to the start of the sim is under way.
+    Countdown,
+    /// Inputs are being collected.
+    Running,
+    /// This node's inputs have reached the end of the session (see `MultiplayerInputManager::with_max_session_ticks`).
+    Ended,
+}
+
+/// A host or guest input manager.
+pub enum Session<T: SimInput> {
+    Host(Box<MultiplayerInputManager<T, HostInputMgr>>),
+    Guest(Box<MultiplayerInputManager<T, GuestInputMgr>>),
+}
+
+/// Runs the same expression against whichever manager a `Session` holds.
+macro_rules! either_role {
+    ($session:expr, $mgr:ident => $body:expr) => {
+        match $session {
+            Session::Host($mgr) => $body,
+            Session::Guest($mgr) => $body,
+        }
+    };
+}
+
+impl<T: SimInput> From<MultiplayerInputManager<T, HostInputMgr>> for Session<T> {
+    fn from(host: MultiplayerInputManager<T, HostInputMgr>) -> Self {
+        Session::Host(Box::new(host))
+    }
+}
+
+impl<T: SimInput> From<MultiplayerInputManager<T, GuestInputMgr>> for Session<T> {
+    fn from(guest: MultiplayerInputManager<T, GuestInputMgr>) -> Self {
+        Session::Guest(Box::new(guest))
+    }
+}
+
+impl<T: SimInput> Session<T> {
+    pub fn is_host(&self) -> bool {
+        matches!(self, Session::Host(_))
+    }
+
+    pub fn as_host(&self) -> Option<&MultiplayerInputManager<T, HostInputMgr>> {
+        match self {
+            Session::Host(host) => Some(host),
+            Session::Guest(_) => None,
+        }
+    }
+
+    pub fn as_host_mut(&mut self) -> Option<&mut MultiplayerInputManager<T, HostInputMgr>> {
+        match self {
+            Session::Host(host) => Some(host),
+            Session::Guest(_) => None,
+        }
+    }
+
+    pub fn as_guest(&self) -> Option<&MultiplayerInputManager<T, GuestInputMgr>> {
+        match self {
+            Session::Guest(guest) => Some(guest),
+            Session::Host(_) => None,
+        }
+    }
+
+    pub fn as_guest_mut(&mut self) -> Option<&mut MultiplayerInputManager<T, GuestInputMgr>> {
+        match self {
+            Session::Guest(guest) => Some(guest),
+            Session::Host(_) => None,
+        }
+    }
+
+    pub fn own_player_num(&self) -> PlayerNum {
+        either_role!(self, mgr => mgr.own_player_num)
+    }
+
+    // Ticks and inputs //////////////////////////////
+
+    pub fn start_tick(&self) -> u32 {
+        either_role!(self, mgr => mgr.start_tick())
+    }
+
+    pub fn session_phase(&self) -> SessionPhase {
+        either_role!(self, mgr => mgr.session_phase())
+    }
+
+    pub fn current_round(&self) -> u32 {
+        either_role!(self, mgr => mgr.current_round())
+    }
+
+    pub fn get_own_num_inputs(&self) -> u32 {
+        either_role!(self, mgr => mgr.get_own_num_inputs())
+    }
+
+    pub fn get_peer_num_inputs(&self, player_num: PlayerNum) -> u32 {
+        either_role!(self, mgr => mgr.get_peer_num_inputs(player_num))
+    }
+
+    pub fn get_peer_num_final_inputs(&self, player_num: PlayerNum) -> u32 {
+        either_role!(self, mgr => mgr.get_peer_num_final_inputs(player_num))
+    }
+
+    /// See `MultiplayerInputManager::get_snapshottable_sim_tick`.
+    pub fn get_snapshottable_sim_tick(&self) -> u32 {
+        either_role!(self, mgr => mgr.get_snapshottable_sim_tick())
+    }
+
+    pub fn get_peer_input_for_tick(&self, player_num: PlayerNum, tick: u32) -> T {
+        either_role!(self, mgr => mgr.get_peer_input_for_tick(player_num, tick))
+    }
+
+    pub fn get_inputs_map_for_tick(&self, tick: u32) -> HashMap<u8, T> {
+        either_role!(self, mgr => mgr.get_inputs_map_for_tick(tick))
+    }
+
+    /// See `MultiplayerInputManager::get_inputs_map_for_tick_into`.
+    pub fn get_inputs_map_for_tick_into(&self, tick: u32, out: &mut HashMap<u8, T>) {
+        either_role!(self, mgr => mgr.get_inputs_map_for_tick_into(tick, out))
+    }
+
+    pub fn get_input_statuses(&self, tick: u32) -> Vec<(PlayerNum, InputStatus)> {
+        either_role!(self, mgr => mgr.get_input_statuses(tick))
+    }
+
+    /// See `MultiplayerInputManager::get_input_statuses_into`.
+    pub fn get_input_statuses_into(&self, tick: u32, out: &mut Vec<(PlayerNum, InputStatus)>) {
+        either_role!(self, mgr => mgr.get_input_statuses_into(tick, out))
+    }
+
+    /// See `MultiplayerInputManager::get_input_statuses_range`.
+    pub fn get_input_statuses_range(
+        &self,
+        start: u32,
+        end: u32,
+    ) -> Vec<(PlayerNum, Vec<InputStatus>)> {
+        either_role!(self, mgr => mgr.get_input_statuses_range(start, end))
+    }
+
+    /// Serializes `msg` for `recipient`, using only the capabilities negotiated with it (see `capabilities`); connections that control no seat get the baseline protocol.
+    pub fn msg_bytes(&self, recipient: Recipient, msg: &MsgPayload<T>) -> Vec<u8> {
+        either_role!(self, mgr => match recipient {
+            Recipient::Player(connection) => match mgr.seat_for_connection(connection) {
+                Some(seat) => mgr.msg_bytes_for_peer(seat, msg),
+                None => msg.to_bytes_for(Capabilities::NONE),
+            },
+            Recipient::AllPeers => mgr.msg_bytes_for_all_peers(msg),
+        })
+    }
+
+    /// See `MultiplayerInputManager::player_metadata`.
+    pub fn player_metadata(&self, player_num: PlayerNum) -> Option<&[u8]> {
+        either_role!(self, mgr => mgr.player_metadata(player_num))
+    }
+
+    pub fn drain_events(&mut self) -> Vec<InputMgrEvent> {
+        either_role!(self, mgr => mgr.drain_events())
+    }
+
+    pub fn take_earliest_changed_tick(&mut self) -> Option<u32> {
+        either_role!(self, mgr => mgr.take_earliest_changed_tick())
+    }
+
+    pub fn take_changed_ticks_since(&mut self, tick: u32) -> Vec<(PlayerNum, u32)> {
+        either_role!(self, mgr => mgr.take_changed_ticks_since(tick))
+    }
+
+    pub fn change_stamps(&self) -> ChangeStamps {
+        either_role!(self, mgr => mgr.change_stamps())
+    }
+
+    // Bandwidth //////////////////////////////
+
+    /// See `MultiplayerInputManager::set_outgoing_budget_bytes_per_sec`.
+    pub fn set_outgoing_budget_bytes_per_sec(&mut self, bytes_per_sec: Option<u32>) {
+        either_role!(self, mgr => mgr.set_outgoing_budget_bytes_per_sec(bytes_per_sec))
+    }
+
+    /// Selects which of this frame's outgoing messages to send within the outgoing budget (see `MultiplayerInputManager::select_outgoing_within_budget`).
+    pub fn select_outgoing_within_budget(
+        &mut self,
+        outgoing: OutgoingMsgs<T>,
+        delta: f32,
+    ) -> OutgoingMsgs<T> {
+        either_role!(self, mgr => mgr.select_outgoing_within_budget(outgoing, delta))
+    }
+
+    // Own inputs //////////////////////////////
+
+    /// Adds a local input, returning the messages that share it.
+    ///
+    /// On the host, `delta` is the time (sec) since the last call, and the host's buffer is filled up to the elapsed sim time (see `add_host_input_to_fill_needed`); the host's finalized inputs, provisional inputs, unacked annotations, seed changes and session end, any lag downgrades (see `poll_lagging_guests`), and any catch-up slices are returned. On a guest, `delta` is ignored and exactly one input is added (use `num_inputs_needed` on the guest to decide how many to add); the guest's input slice for the host is returned.
+    pub fn add_own_input(&mut self, input: T, delta: f32) -> OutgoingMsgs<T> {
+        self.profiled("add_own_input", |session| {
+            session.add_own_input_unprofiled(input, delta)
+        })
+    }
+
+    fn add_own_input_unprofiled(&mut self, input: T, delta: f32) -> OutgoingMsgs<T> {
+        let outgoing = match self {
+            Session::Host(host) => {
+                host.add_host_input_to_fill_needed(input, delta);
+                let mut outgoing: OutgoingMsgs<T> = if host.send_window_ticks().is_some() {
+                    host.get_msgs_windowed_finalized_slices_for_guests(&[HOST_PLAYER_NUM])
+                } else {
+                    host.get_msgs_finalized_slice(HOST_PLAYER_NUM)
+                        .into_iter()
+                        .map(|msg| (Recipient::AllPeers, msg))
+                        .collect()
+                };
+                outgoing.extend([
+                    (Recipient::AllPeers, host.get_msg_provisional_own_inputs()),
+                    (Recipient::AllPeers, host.get_msg_annotations()),
+                    (Recipient::AllPeers, host.get_msg_seed()),
+                    (Recipient::AllPeers, host.get_msg_end_session()),
+                ]);
+                for msg in host.poll_lagging_guests(delta) {
+                    outgoing.push((Recipient::AllPeers, msg));
+                }
+                for (_, msg) in host.poll_catch_up(delta) {
+                    outgoing.push((Recipient::AllPeers, msg));
+                }
+                outgoing
+            }
+            Session::Guest(guest) => {
+                guest.add_own_input(input);
+                guest
+                    .get_msgs_own_input_slice()
+                    .into_iter()
+                    .map(|msg| (Recipient::Player(HOST_PLAYER_NUM), msg))
+                    .collect()
+            }
+        };
+        let outgoing = without_empty_msgs(self.split_to_max_payload(outgoing));
+        self.record_sent(&outgoing);
+        self.to_connections(outgoing)
+    }
+
+    // Receiving //////////////////////////////
+
+    /// Decodes a message received from `sender` and handles it (see `rx_msg`).
+    pub fn rx_bytes(
+        &mut self,
+        sender: PlayerNum,
+        bytes: &[u8],
+    ) -> Result<OutgoingMsgs<T>, SessionRxError>
+    where
+        T: for<'a> Deserialize<'a>,
+    {
+        let msg = either_role!(self, mgr => mgr.decode_msg_from_peer(sender, bytes))
+            .ok_or(SessionRxError::Decode)?;
+        self.rx_msg(sender, msg)
+    }
+
+    /// Like `rx_bytes`, for a `SequencedMsg`'s bytes (see `MultiplayerInputManager::decode_sequenced_msg_from_peer`): duplicates are dropped rather than handled again.
+    pub fn rx_sequenced_bytes(
+        &mut self,
+        sender: PlayerNum,
+        bytes: &[u8],
+    ) -> Result<OutgoingMsgs<T>, SessionRxError>
+    where
+        T: for<'a> Deserialize<'a>,
+    {
+        let msg = either_role!(self, mgr => mgr.decode_sequenced_msg_from_peer(sender, bytes))
+            .ok_or(SessionRxError::Decode)?;
+        self.rx_msg(sender, msg)
+    }
+
+    /// Serializes outgoing messages as `SequencedMsg`s, one per connection: a message for all peers is sent to every other seat's connection, each with its own sequence number.
+    pub fn to_sequenced_bytes(&mut self, outgoing: OutgoingMsgs<T>) -> Vec<(PlayerNum, Vec<u8>)> {
+        let mut sequenced = vec![];
+        for (recipient, msg) in outgoing {
+            let connections: Vec<PlayerNum> = match recipient {
+                Recipient::Player(connection) => vec![connection],
+                // a connection controlling several seats (see `add_local_seat`) is sent one copy
+                Recipient::AllPeers => either_role!(self, mgr => {
+                    let own_connection = mgr.connection_for_seat(mgr.own_player_num);
+                    let mut connections: Vec<PlayerNum> = vec![];
+                    for seat in PlayerNum::iter(mgr.get_num_players()) {
+                        let connection = mgr.connection_for_seat(seat);
+                        if connection != own_connection && !connections.contains(&connection) {
+                            connections.push(connection);
+                        }
+                    }
+                    connections
+                }),
+            };
+            for connection in connections {
+                let bytes = either_role!(self, mgr => {
+                    let bytes = mgr.sequence_msg(connection, msg.clone()).to_bytes();
+                    mgr.record_msg_bytes_sent(connection, &msg, bytes.len());
+                    bytes
+                });
+                sequenced.push((connection, bytes));
+            }
+        }
+        sequenced
+    }
+
+    /// Advances reliable delivery by `delta` (sec), returning the serialized acks and resends to send now, by connection (see `MultiplayerInputManager::update_reliable_delivery`).
+    pub fn update_reliable_delivery(&mut self, delta: f32) -> Vec<(PlayerNum, Vec<u8>)> {
+        either_role!(self, mgr => {
+            mgr.update_reliable_delivery(delta)
+                .into_iter()
+                .map(|(connection, msg)| {
+                    let bytes = msg.to_bytes();
+                    mgr.record_msg_bytes_sent(connection, &msg.payload, bytes.len());
+                    (connection, bytes)
+                })
+                .collect()
+        })
+    }
+
+    /// See `MultiplayerInputManager::get_net_stats`.
+    pub fn get_net_stats(&self) -> &NetStats {
+        either_role!(self, mgr => mgr.get_net_stats())
+    }
+
+    /// See `MultiplayerInputManager::record_msg_bytes_sent`.
+    pub fn record_msg_bytes_sent(
+        &mut self,
+        destination: PlayerNum,
+        msg: &MsgPayload<T>,
+        num_bytes: usize,
+    ) {
+        either_role!(self, mgr => mgr.record_msg_bytes_sent(destination, msg, num_bytes))
+    }
+
+    /// See `MultiplayerInputManager::update_net_stats`.
+    pub fn update_net_stats(&mut self, delta: f32) {
+        either_role!(self, mgr => mgr.update_net_stats(delta))
+    }
+
+    /// Passes a message received from `sender` to this node's manager (see `MultiplayerInputManager::handle_msg`), returning the replies to send.
+    ///
+    /// `sender` is the connection the message arrived from, and replies are addressed to connections, which differ from seats once a seat has been moved (see `MultiplayerInputManager::transfer_seat`). Messages from a connection that controls no seat are rejected, except on a host from its spectators (see `MultiplayerInputManager::handle_spectator_msg`).
+    ///
+    /// Peers' input schemas are checked when joining (see `input_schema`): a guest rejects a `PreSimSync` from a host with another schema, and replies to one with its own schema; a host mutes a guest with another schema. The optional capabilities the host advertises in the `PreSimSync` are negotiated alongside (see `capabilities`).
+    pub fn rx_msg(
+        &mut self,
+        connection: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> Result<OutgoingMsgs<T>, SessionRxError> {
+        self.profiled("rx_msg", |session| {
+            session.rx_msg_unprofiled(connection, msg)
+        })
+    }
+
+    fn rx_msg_unprofiled(
+        &mut self,
+        connection: PlayerNum,
+        msg: MsgPayload<T>,
+    ) -> Result<OutgoingMsgs<T>, SessionRxError> {
+        if let Session::Host(host) = self
+            && host.is_spectator(connection)
+        {
+            return host.handle_spectator_msg(connection, msg);
+        }
+        let sender = either_role!(self, mgr => mgr.seat_for_connection(connection))
+            .ok_or(SessionRxError::InvalidSender)?;
+        let outgoing = either_role!(self, mgr => mgr.handle_msg(sender, msg))?;
+        let outgoing = without_empty_msgs(outgoing);
+        self.record_sent(&outgoing);
+        Ok(self.to_connections(outgoing))
+    }
+
+    /// Handles each message of a batch in turn (see `rx_msg`), returning all of their replies, less those superseded by a later reply to the same recipient (e.g. all but the last finalization ack).
+    ///
+    /// A message that is rejected is skipped, like a rejected message on its own, so the rest of the batch still applies; only a sender that controls no seat rejects the whole batch.
+    pub fn rx_batch(
+        &mut self,
+        connection: PlayerNum,
+        msgs: Vec<MsgPayload<T>>,
+    ) -> Result<OutgoingMsgs<T>, SessionRxError> {
+        self.rx_msg(connection, MsgPayload::Batch(msgs))
+    }
+
+    /// Bundles this frame's outgoing messages into a single batch (see `MultiplayerInputManager::get_outgoing_msgs_for_frame`): on the host for all peers, on a guest for the host. With a max payload size, the batch is split into several within it (see `MultiplayerInputManager::get_outgoing_batches_for_frame`). With a send window on the host, each guest's windowed finalized slices are added, addressed to its connection (see `MultiplayerInputManager::get_msgs_windowed_finalized_slices_for_guests`).
+    pub fn get_outgoing_msgs_for_frame(&mut self, delta: f32) -> OutgoingMsgs<T> {
+        self.profiled("get_outgoing_msgs_for_frame", |session| {
+            session.get_outgoing_msgs_for_frame_unprofiled(delta)
+        })
+    }
+
+    fn get_outgoing_msgs_for_frame_unprofiled(&mut self, delta: f32) -> OutgoingMsgs<T> {
+        let outgoing = match self {
+            Session::Host(host) => {
+                let mut outgoing: OutgoingMsgs<T> = host
+                    .get_outgoing_batches_for_frame(delta)
+                    .into_iter()
+                    .map(|msg| (Recipient::AllPeers, msg))
+                    .collect();
+                if host.send_window_ticks().is_some() {
+                    let players: Vec<PlayerNum> =
+                        PlayerNum::iter(host.buffers.num_players()).collect();
+                    outgoing.extend(host.get_msgs_windowed_finalized_slices_for_guests(&players));
+                }
+                outgoing
+            }
+            Session::Guest(guest) => guest
+                .get_outgoing_batches_for_frame(delta)
+                .into_iter()
+                .map(|msg| (Recipient::Player(HOST_PLAYER_NUM), msg))
+                .collect(),
+        };
+        let outgoing = without_empty_msgs(outgoing);
+        self.record_sent(&outgoing);
+        self.to_connections(outgoing)
+    }
+
+    /// On the host, every guest's own finalized slices, each starting from what that guest has acked, addressed to the guest's connection (see `MultiplayerInputManager::get_msgs_finalized_slices_for_guests`); nothing on a guest.
+    ///
+    /// Send these in place of the broadcast finalized slices when guests' links differ widely.
+    pub fn get_finalized_slices_for_guests(&mut self) -> OutgoingMsgs<T> {
+        let outgoing = match self {
+            Session::Host(host) => host.get_msgs_finalized_slices_for_guests(),
+            Session::Guest(_) => vec![],
+        };
+        self.record_sent(&outgoing);
+        self.to_connections(outgoing)
+    }
+
+    /// On the host, every spectator's finalized slices, addressed to its connection (see `MultiplayerInputManager::get_msgs_finalized_slices_for_spectators`); nothing on a guest.
+    pub fn get_finalized_slices_for_spectators(&self) -> OutgoingMsgs<T> {
+        match self {
+            Session::Host(host) => host.get_msgs_finalized_slices_for_spectators(),
+            Session::Guest(_) => vec![],
+        }
+    }
+
+    // runs `f` under the manager's profiler, if one is attached (see
+    // `MultiplayerInputManager::with_profiler`)
+    #[cfg_attr(not(feature = "profiling"), allow(unused_variables))]
+    fn profiled<R>(&mut self, call: &'static str, f: impl FnOnce(&mut Self) -> R) -> R {
+        #[cfg(feature = "profiling")]
+        if let Some(mut profiler) = either_role!(self, mgr => mgr.profiler.take()) {
+            let result = profiler.measure(call, || f(self));
+            either_role!(self, mgr => mgr.profiler = Some(profiler));
+            return result;
+        }
+        f(self)
+    }
+
+    /// On the host, records the finalized slices among messages for seats (see `MultiplayerInputManager::record_msg_sent`).
+    fn record_sent(&mut self, outgoing: &OutgoingMsgs<T>) {
+        if let Session::Host(host) = self {
+            for (recipient, msg) in outgoing {
+                host.record_msg_sent(*recipient, msg);
+            }
+        }
+    }
+
+    /// Splits oversized slices and batches to the max payload size (see `MultiplayerInputManager::with_max_payload_bytes`).
+    fn split_to_max_payload(&self, outgoing: OutgoingMsgs<T>) -> OutgoingMsgs<T> {
+        outgoing
+            .into_iter()
+            .flat_map(|(recipient, msg)| {
+                either_role!(self, mgr => mgr.split_to_max_payload(msg))
+                    .into_iter()
+                    .map(move |part| (recipient, part))
+            })
+            .collect()
+    }
+
+    /// Addresses messages for single seats to the connections controlling them.
+    fn to_connections(&self, outgoing: OutgoingMsgs<T>) -> OutgoingMsgs<T> {
+        outgoing
+            .into_iter()
+            .map(|(recipient, msg)| match recipient {
+                Recipient::Player(seat) => (
+                    Recipient::Player(either_role!(self, mgr => mgr.connection_for_seat(seat))),
+                    msg,
+                ),
+                Recipient::AllPeers => (recipient, msg),
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn without_empty_msgs<T: SimInput>(mut outgoing: OutgoingMsgs<T>) -> OutgoingMsgs<T> {
+    outgoing.retain(|(_, msg)| !matches!(msg, MsgPayload::Empty));
+    outgoing
+}
+
+/// Adds replies to those already collected, dropping any earlier reply to the same recipient that a new one supersedes.
+pub(crate) fn push_replies<T: SimInput>(replies: &mut OutgoingMsgs<T>, outgoing: OutgoingMsgs<T>) {
+    for (recipient, reply) in outgoing {
+        replies.retain(|(older_recipient, older)| {
+            *older_recipient != recipient || !supersedes(&reply, older)
+        });
+        replies.push((recipient, reply));
+    }
+}
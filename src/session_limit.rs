@@ -0,0 +1,37 @@
+//! The maximum length of a session, and what happens when it is reached.
+//!
+//! Tick counts are never wrapped around. Guests track the host's tick as an `i32` (negative during the PreSimSync countdown), so every sim tick in a session must fit in an `i32`: `MAX_SESSION_TICKS` is `i32::MAX`, about 414 days at 60 ticks per second. A session can also be given a shorter limit with `MultiplayerInputManager::with_max_session_ticks`.
+//!
+//! Once the limit is reached, the session saturates: no more own inputs are collected, inputs in received slices past the limit are dropped, and an `InputMgrEvent::SessionEnded` is raised once.
+//!
+//! The host can also end a session gracefully while it is running, with `MultiplayerInputManager::end_session_at`. This lowers the limit to a final tick on every node: the host broadcasts a `HostToLobbyEndSession` message, resent until each guest acks it with a `GuestToHostEndAck`. Once the host's own inputs reach the final tick, it finalizes every player up to exactly that tick (with default inputs for any a player never sent), so all nodes finish with the same history. Nodes raise `InputMgrEvent::SessionEnded` once every player's inputs are finalized up to the final tick.
+
+/// The largest sim tick a session can reach (see the module docs).
+pub const MAX_SESSION_TICKS: u32 = i32::MAX as u32;
+
+/// Converts a host tick to the signed tick guests track, saturating at `i32::MAX` (which `MAX_SESSION_TICKS` keeps sessions from reaching).
+pub(crate) fn signed_host_tick(host_tick: u32) -> i32 {
+    i32::try_from(host_tick).unwrap_or(i32::MAX)
+}
+
+/// The configured session length, and whether its end has been reported.
+#[derive(Debug, Clone)]
+pub(crate) struct SessionLimit {
+    /// CONFIG SETTING
+    /// The sim tick at which the session ends; at most `MAX_SESSION_TICKS`.
+    pub(crate) max_ticks: u32,
+    /// True once `InputMgrEvent::SessionEnded` has been raised for the current round.
+    pub(crate) end_reported: bool,
+    /// True once the host has ended the session with `end_session_at` (on guests, once its end has arrived), so that `max_ticks` is the final tick to which every player is finalized.
+    pub(crate) end_requested: bool,
+}
+
+impl Default for SessionLimit {
+    fn default() -> Self {
+        Self {
+            max_ticks: MAX_SESSION_TICKS,
+            end_reported: false,
+            end_requested: false,
+        }
+    }
+}
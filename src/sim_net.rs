@@ -0,0 +1,351 @@
+//! A simulated network for end-to-end tests (behind the `sim_net` feature): a host and its guests, each wrapped in a `Session`, stepped one tick at a time, with every message serialized and passed through a lossy, latent channel.
+//!
+//! The network is deterministic: losses and delays come from a seeded generator, and time only advances with `step`, so a failing run can be replayed exactly from its seed.
+
+use serde::Deserialize;
+
+use crate::{
+    events::InputMgrEvent,
+    input_trait::SimInput,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    session::{OutgoingMsgs, Recipient, Session},
+    util_types::PlayerNum,
+};
+
+/// How long each message takes to arrive, in ticks of the network's clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimLatency {
+    /// Every message takes the same time.
+    Constant(u32),
+    /// Delays are spread evenly from `min` to `max`, inclusive.
+    Uniform { min: u32, max: u32 },
+    /// Messages usually take `base` ticks, but 1 in `spike_one_in` take `spike` ticks, as on a link with occasional stalls.
+    Spiky {
+        base: u32,
+        spike: u32,
+        spike_one_in: u64,
+    },
+}
+
+impl SimLatency {
+    /// The average delay, in ticks.
+    pub fn mean_ticks(&self) -> f32 {
+        match *self {
+            SimLatency::Constant(ticks) => ticks as f32,
+            SimLatency::Uniform { min, max } => (min + max) as f32 / 2.0,
+            SimLatency::Spiky {
+                base,
+                spike,
+                spike_one_in,
+            } => {
+                let spike_odds = 1.0 / spike_one_in.max(1) as f32;
+                base as f32 * (1.0 - spike_odds) + spike as f32 * spike_odds
+            }
+        }
+    }
+}
+
+/// The behavior of a `SimNet`'s channels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimNetConfig {
+    /// Seeds the generator behind losses and delays
+    pub seed: u64,
+    pub latency: SimLatency,
+    /// Each message is lost with probability 1 in `loss_one_in`; `None` loses nothing
+    pub loss_one_in: Option<u64>,
+    /// If true, messages on the same link may overtake each other when their delays differ; if false, each link delivers in the order sent, holding back messages behind slower ones
+    pub reorder: bool,
+}
+
+impl Default for SimNetConfig {
+    /// A reliable, in-order network with a 2 tick delay.
+    fn default() -> Self {
+        Self {
+            seed: 0x9e37_79b9_7f4a_7c15,
+            latency: SimLatency::Constant(2),
+            loss_one_in: None,
+            reorder: false,
+        }
+    }
+}
+
+/// Counts of what a `SimNet` has done with the messages sent through it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SimNetStats {
+    /// Messages sent, counting a broadcast once per recipient
+    pub num_sent: u64,
+    pub num_lost: u64,
+    pub num_delivered: u64,
+    /// Delivered messages that the recipient couldn't handle (see `SessionRxError`)
+    pub num_rejected: u64,
+}
+
+/// A deterministic xorshift generator.
+struct SimRng(u64);
+
+impl SimRng {
+    /// Spreads the seed with a splitmix64 step, since xorshift never leaves a zero state; the one seed that still maps to zero is nudged off it.
+    fn new(seed: u64) -> Self {
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Self((z ^ (z >> 31)).max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// True with probability 1 in `one_in`.
+    fn one_in(&mut self, one_in: u64) -> bool {
+        self.next().is_multiple_of(one_in.max(1))
+    }
+}
+
+struct InFlight {
+    deliver_at: u32,
+    from: PlayerNum,
+    to: PlayerNum,
+    bytes: Vec<u8>,
+}
+
+/// A host and its guests, connected by a simulated network (see the module docs).
+pub struct SimNet<T: SimInput> {
+    /// Every node, indexed by player num
+    nodes: Vec<Session<T>>,
+    config: SimNetConfig,
+    rng: SimRng,
+    in_flight: Vec<InFlight>,
+    /// The network's clock, in ticks
+    now: u32,
+    /// The host's frame time (sec), passed to every node each step
+    delta: f32,
+    stats: SimNetStats,
+}
+
+impl<T> SimNet<T>
+where
+    T: SimInput + for<'a> Deserialize<'a>,
+{
+    /// Connects a host to its guests, which must fill seats 1 to `num_players - 1` in order.
+    ///
+    /// Guests time their pings with the wall clock, which doesn't advance in a simulation, so each guest is given the network's mean round trip time as its RTT estimate.
+    pub fn new(
+        host: MultiplayerInputManager<T, HostInputMgr>,
+        guests: Vec<MultiplayerInputManager<T, GuestInputMgr>>,
+        config: SimNetConfig,
+    ) -> Result<Self, String> {
+        let num_players = host.get_num_players();
+        if guests.len() + 1 != num_players as usize {
+            return Err(format!(
+                "a {num_players} player session needs {} guests, got {}",
+                num_players - 1,
+                guests.len()
+            ));
+        }
+        let ticks_per_sec = host.ticks_per_sec;
+        let rtt_ms = 2.0 * config.latency.mean_ticks() * 1000.0 / ticks_per_sec as f32;
+        let mut nodes: Vec<Session<T>> = vec![host.into()];
+        for (seat, mut guest) in PlayerNum::iter_guests(num_players).zip(guests) {
+            if guest.own_player_num != seat {
+                return Err(format!(
+                    "expected the guest in seat {seat}, got player {}",
+                    guest.own_player_num
+                ));
+            }
+            guest.observe_rtt_ms_to_host(rtt_ms);
+            nodes.push(guest.into());
+        }
+        Ok(Self {
+            nodes,
+            config,
+            rng: SimRng::new(config.seed),
+            in_flight: Vec::new(),
+            now: 0,
+            delta: 1.0 / ticks_per_sec as f32,
+            stats: SimNetStats::default(),
+        })
+    }
+
+    /// Advances the network by one tick: each node adds its inputs for the tick (one on the host, `num_inputs_needed` on a guest), taking them from `input_for`, which is given the player and the index of the input; then the messages due are delivered, and their replies sent.
+    ///
+    /// Returns the events every node queued during the step, by player.
+    pub fn step(
+        &mut self,
+        mut input_for: impl FnMut(PlayerNum, u32) -> T,
+    ) -> Vec<(PlayerNum, InputMgrEvent)> {
+        let mut events = vec![];
+        for player in 0..self.nodes.len() {
+            let player_num = PlayerNum(player as u8);
+            let num_inputs = match &mut self.nodes[player] {
+                Session::Host(_) => 1,
+                Session::Guest(guest) => guest.num_inputs_needed(),
+            };
+            for _ in 0..num_inputs {
+                let node = &mut self.nodes[player];
+                let input = input_for(player_num, node.get_own_num_inputs());
+                let outgoing = node.add_own_input(input, self.delta);
+                self.send(player_num, outgoing);
+            }
+        }
+        self.deliver_due();
+        for (player, node) in self.nodes.iter_mut().enumerate() {
+            let player_num = PlayerNum(player as u8);
+            node.update_net_stats(self.delta);
+            events.extend(node.drain_events().into_iter().map(|e| (player_num, e)));
+        }
+        self.now += 1;
+        events
+    }
+
+    /// Steps the network `num_ticks` times (see `step`), discarding events.
+    pub fn run(&mut self, num_ticks: u32, mut input_for: impl FnMut(PlayerNum, u32) -> T) {
+        for _ in 0..num_ticks {
+            self.step(&mut input_for);
+        }
+    }
+
+    /// Sends messages from `from` through the network, e.g. messages a test built itself. Messages to all peers are sent separately to each, and may be lost or delayed independently.
+    pub fn send(&mut self, from: PlayerNum, outgoing: OutgoingMsgs<T>) {
+        let num_players = self.nodes.len() as u8;
+        for (recipient, msg) in outgoing {
+            let bytes = msg.to_bytes();
+            let recipients: Vec<PlayerNum> = match recipient {
+                Recipient::Player(to) => vec![to],
+                Recipient::AllPeers => PlayerNum::iter(num_players)
+                    .filter(|to| *to != from)
+                    .collect(),
+            };
+            for to in recipients {
+                self.stats.num_sent += 1;
+                self.nodes[from.0 as usize].record_msg_bytes_sent(to, &msg, bytes.len());
+                if self
+                    .config
+                    .loss_one_in
+                    .is_some_and(|loss_one_in| self.rng.one_in(loss_one_in))
+                {
+                    self.stats.num_lost += 1;
+                    continue;
+                }
+                let mut deliver_at = self.now + self.sample_delay();
+                if !self.config.reorder {
+                    let last_on_link = self
+                        .in_flight
+                        .iter()
+                        .filter(|msg| msg.from == from && msg.to == to)
+                        .map(|msg| msg.deliver_at)
+                        .max();
+                    deliver_at = deliver_at.max(last_on_link.unwrap_or(0));
+                }
+                self.in_flight.push(InFlight {
+                    deliver_at,
+                    from,
+                    to,
+                    bytes: bytes.clone(),
+                });
+            }
+        }
+    }
+
+    fn sample_delay(&mut self) -> u32 {
+        match self.config.latency {
+            SimLatency::Constant(ticks) => ticks,
+            SimLatency::Uniform { min, max } => {
+                let spread = max.saturating_sub(min) as u64 + 1;
+                min + (self.rng.next() % spread) as u32
+            }
+            SimLatency::Spiky {
+                base,
+                spike,
+                spike_one_in,
+            } => {
+                if self.rng.one_in(spike_one_in) {
+                    spike
+                } else {
+                    base
+                }
+            }
+        }
+    }
+
+    /// Delivers the messages due by now, earliest first and otherwise in the order they were sent, and sends their replies. Replies are sent at the current tick, so they are never delivered in the same step.
+    fn deliver_due(&mut self) {
+        let now = self.now;
+        let (mut due, in_flight): (Vec<_>, Vec<_>) = std::mem::take(&mut self.in_flight)
+            .into_iter()
+            .partition(|msg| msg.deliver_at <= now);
+        self.in_flight = in_flight;
+        due.sort_by_key(|msg| msg.deliver_at);
+        for msg in due {
+            self.stats.num_delivered += 1;
+            match self.nodes[msg.to.0 as usize].rx_bytes(msg.from, &msg.bytes) {
+                Ok(replies) => self.send(msg.to, replies),
+                Err(_) => self.stats.num_rejected += 1,
+            }
+        }
+    }
+}
+
+impl<T: SimInput> SimNet<T> {
+    /// The node in seat `player_num`.
+    pub fn node(&self, player_num: PlayerNum) -> &Session<T> {
+        &self.nodes[player_num.0 as usize]
+    }
+
+    pub fn node_mut(&mut self, player_num: PlayerNum) -> &mut Session<T> {
+        &mut self.nodes[player_num.0 as usize]
+    }
+
+    pub fn host(&self) -> &MultiplayerInputManager<T, HostInputMgr> {
+        self.nodes[HOST_PLAYER_NUM.0 as usize]
+            .as_host()
+            .expect("seat 0 is the host")
+    }
+
+    pub fn nodes(&self) -> &[Session<T>] {
+        &self.nodes
+    }
+
+    /// The network's clock: the number of steps taken.
+    pub fn now(&self) -> u32 {
+        self.now
+    }
+
+    pub fn stats(&self) -> SimNetStats {
+        self.stats
+    }
+
+    /// The number of messages still in flight.
+    pub fn num_in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// The lowest snapshottable tick across all nodes: every node has finalized every player's inputs before it.
+    pub fn min_snapshottable_tick(&self) -> u32 {
+        self.nodes
+            .iter()
+            .map(|node| node.get_snapshottable_sim_tick())
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// True if every node has the same inputs for every player on every tick before `min_snapshottable_tick`.
+    pub fn finalized_inputs_agree(&self) -> bool {
+        let num_players = self.nodes.len() as u8;
+        (0..self.min_snapshottable_tick()).all(|tick| {
+            PlayerNum::iter(num_players).all(|player_num| {
+                let expected = self.nodes[0]
+                    .get_peer_input_for_tick(player_num, tick)
+                    .to_bytes();
+                self.nodes[1..].iter().all(|node| {
+                    node.get_peer_input_for_tick(player_num, tick).to_bytes() == expected
+                })
+            })
+        })
+    }
+}
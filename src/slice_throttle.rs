@@ -0,0 +1,141 @@
+//! Adaptive throttling of the finalized slices the host sends each guest.
+//!
+//! Broadcast finalized slices start at the earliest tick any guest has acked, so every guest is resent whatever the slowest guest lacks, and even a lone guest is resent everything sent within its last round trip. The host counts, per guest, the finalized inputs it sends that the guest had already acked, and those it had already been sent (see `MultiplayerInputManager::slice_redundancy`).
+//!
+//! With adaptive slices enabled (see `MultiplayerInputManager::with_adaptive_finalized_slices`), `poll_adaptive_finalized_slices` sends each guest its own slices instead: on most calls, only the inputs past those it was last sent; once per resend interval, everything past what it has acked, to recover lost slices. Each guest's interval doubles (up to the max) while it has acked everything it was sent by its previous resend, since resends then only repeat inputs still in flight, and halves (down to the min) once it hasn't, since an input has likely been lost.
+
+use std::collections::HashMap;
+
+use crate::util_types::PlayerNum;
+
+/// How `poll_adaptive_finalized_slices` paces each guest's resends (see `slice_throttle`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveSliceConfig {
+    /// The shortest time (sec) between resends of a guest's unacked inputs, and the interval each guest starts at
+    pub min_resend_interval_sec: f32,
+    /// The longest time (sec) between resends of a guest's unacked inputs
+    pub max_resend_interval_sec: f32,
+}
+
+impl Default for AdaptiveSliceConfig {
+    fn default() -> Self {
+        Self {
+            min_resend_interval_sec: 0.1,
+            max_resend_interval_sec: 1.6,
+        }
+    }
+}
+
+/// The finalized inputs sent to a guest, and how many of them were sent again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SliceRedundancy {
+    pub num_inputs_sent: u64,
+    /// Inputs sent that the guest had already acked when they were sent
+    pub num_acked_inputs: u64,
+    /// Inputs sent that the guest hadn't acked, but had already been sent
+    pub num_resent_inputs: u64,
+}
+
+impl SliceRedundancy {
+    /// The fraction of inputs sent that the guest had already acked or been sent; 0 before any are sent.
+    pub fn redundant_fraction(&self) -> f32 {
+        if self.num_inputs_sent == 0 {
+            0.0
+        } else {
+            (self.num_acked_inputs + self.num_resent_inputs) as f32 / self.num_inputs_sent as f32
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct GuestResends {
+    interval_sec: f32,
+    /// The time (sec) since the guest's last resend
+    since_resend_sec: f32,
+    /// For each player, the end of the inputs sent to the guest as of its last resend
+    sent_at_last_resend: Option<Vec<u32>>,
+}
+
+/// Redundancy counts for every guest, and the resend pacing of each (see the module docs).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SliceThrottle {
+    /// CONFIG SETTING
+    /// `None` (the default) disables adaptive slices.
+    pub(crate) config: Option<AdaptiveSliceConfig>,
+    redundancy: HashMap<PlayerNum, SliceRedundancy>,
+    resends: HashMap<PlayerNum, GuestResends>,
+}
+
+impl SliceThrottle {
+    /// Counts finalized inputs sent to `guest`, of which it had already acked `num_acked`, and been sent `num_resent` more.
+    pub(crate) fn record_sent(
+        &mut self,
+        guest: PlayerNum,
+        num_inputs: u32,
+        num_acked: u32,
+        num_resent: u32,
+    ) {
+        let redundancy = self.redundancy.entry(guest).or_default();
+        redundancy.num_inputs_sent += u64::from(num_inputs);
+        redundancy.num_acked_inputs += u64::from(num_acked);
+        redundancy.num_resent_inputs += u64::from(num_resent);
+    }
+
+    pub(crate) fn redundancy(&self, guest: PlayerNum) -> SliceRedundancy {
+        self.redundancy.get(&guest).copied().unwrap_or_default()
+    }
+
+    pub(crate) fn resend_interval_sec(&self, guest: PlayerNum) -> Option<f32> {
+        let config = self.config?;
+        Some(
+            self.resends
+                .get(&guest)
+                .map_or(config.min_resend_interval_sec, |resends| {
+                    resends.interval_sec
+                }),
+        )
+    }
+
+    /// Advances `guest`'s resend timer by `delta` (sec), returning true if its unacked inputs are due to be resent. `acked` and `sent` are, for each player, the guest's acked inputs and the end of the inputs sent to it; the interval adapts to them before each resend.
+    pub(crate) fn poll_resend_due(
+        &mut self,
+        guest: PlayerNum,
+        delta: f32,
+        acked: &[u32],
+        sent: &[u32],
+    ) -> bool {
+        let Some(config) = self.config else {
+            return false;
+        };
+        let resends = self.resends.entry(guest).or_insert_with(|| GuestResends {
+            interval_sec: config.min_resend_interval_sec,
+            // a guest's first slices start from its ack
+            since_resend_sec: config.min_resend_interval_sec,
+            sent_at_last_resend: None,
+        });
+        resends.since_resend_sec += delta;
+        if resends.since_resend_sec < resends.interval_sec {
+            return false;
+        }
+        resends.since_resend_sec = 0.0;
+        if let Some(sent_at_last_resend) = &resends.sent_at_last_resend {
+            let caught_up = sent_at_last_resend
+                .iter()
+                .zip(acked)
+                .all(|(sent, acked)| acked >= sent);
+            resends.interval_sec = if caught_up {
+                (resends.interval_sec * 2.0).min(config.max_resend_interval_sec)
+            } else {
+                (resends.interval_sec / 2.0).max(config.min_resend_interval_sec)
+            };
+        }
+        resends.sent_at_last_resend = Some(sent.to_vec());
+        true
+    }
+
+    /// Forgets a guest whose seat is vacated or moved to another connection.
+    pub(crate) fn remove(&mut self, guest: PlayerNum) {
+        self.redundancy.remove(&guest);
+        self.resends.remove(&guest);
+    }
+}
@@ -0,0 +1,131 @@
+//! Agreement on the lobby tick at which the sim starts.
+//!
+//! A bare `PreSimSync` countdown leaves each guest to guess how much of it was spent in transit. Instead, the host can schedule the start in two phases, counting in lobby ticks (the host's `advance_lobby_time`, at the session's input rate):
+//!
+//! 1. The host proposes a start tick with `propose_start`, broadcast as a `HostToLobbyStartProposal` carrying the host's lobby tick at sending.
+//! 2. Each guest replies with a `GuestToHostStartAck` carrying its estimate of the ticks left until the proposed start, adjusted by half its RTT to the host.
+//! 3. Once every guest has acked, the host confirms a final start tick with `get_msg_start_confirmed`: the proposed one, or a later one if a guest's ack shows the confirmation couldn't reach it in time. The confirmation is resent on request, carrying the host's current lobby tick so late arrivals can adjust.
+//!
+//! Both sides then count down with `ticks_until_start`, and start collecting inputs when it reaches 0. A guest adopts the countdown as its (negative) host tick, so its pacing starts from the agreed start rather than from when a message happened to arrive.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::util_types::PlayerNum;
+
+/// The number of ticks by which a confirmed start must follow a guest's estimated receipt of the confirmation.
+pub const START_MARGIN_TICKS: u32 = 1;
+
+/// A start tick proposed or confirmed by the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledStart {
+    /// Which of the host's proposals this is, counting from 0; a confirmation carries the number of the proposal it confirms
+    pub proposal_num: u32,
+    /// The host's lobby tick when it sent this message
+    pub host_lobby_tick: u32,
+    /// The host lobby tick at which the sim starts
+    pub start_lobby_tick: u32,
+}
+
+impl ScheduledStart {
+    /// The seconds left until the start when this message arrives, for a message that spent `one_way_sec` in transit.
+    pub(crate) fn remaining_sec_on_arrival(&self, inputs_per_sec: f32, one_way_sec: f32) -> f32 {
+        let countdown_ticks = self.start_lobby_tick as f32 - self.host_lobby_tick as f32;
+        countdown_ticks / inputs_per_sec - one_way_sec
+    }
+}
+
+/// A guest's reply to a `ScheduledStart` proposal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StartAck {
+    pub proposal_num: u32,
+    /// The guest's RTT-adjusted estimate of the ticks left until the proposed start when the proposal arrived; negative if it arrived too late
+    pub ticks_until_start: i32,
+}
+
+/// The host's side of the start agreement.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StartAgreement {
+    /// The latest proposal, if any
+    proposal: Option<ScheduledStart>,
+    /// The guests' acks of the latest proposal
+    acks: HashMap<PlayerNum, i32>,
+    /// The confirmed start lobby tick, once every guest has acked
+    confirmed_start: Option<u32>,
+}
+
+impl StartAgreement {
+    pub(crate) fn is_proposed(&self) -> bool {
+        self.proposal.is_some()
+    }
+
+    pub(crate) fn confirmed_start(&self) -> Option<u32> {
+        self.confirmed_start
+    }
+
+    /// Replaces any earlier proposal with one starting `countdown_ticks` after `host_lobby_tick`, forgetting the acks of the old one.
+    pub(crate) fn propose(&mut self, host_lobby_tick: u32, countdown_ticks: u32) -> ScheduledStart {
+        let proposal = ScheduledStart {
+            proposal_num: self.proposal.map_or(0, |old| old.proposal_num + 1),
+            host_lobby_tick,
+            start_lobby_tick: host_lobby_tick.saturating_add(countdown_ticks),
+        };
+        self.proposal = Some(proposal);
+        self.acks.clear();
+        proposal
+    }
+
+    /// Stores a guest's ack if it is for the latest proposal.
+    pub(crate) fn receive_ack(&mut self, guest: PlayerNum, ack: StartAck) {
+        if self
+            .proposal
+            .is_some_and(|proposal| proposal.proposal_num == ack.proposal_num)
+        {
+            self.acks.insert(guest, ack.ticks_until_start);
+        }
+    }
+
+    /// Confirms the start once every guest has acked the latest proposal, returning the confirmation to send at `host_lobby_tick`; `None` until then.
+    ///
+    /// The confirmed start is the proposed one, pushed back if needed so that it follows each guest's estimated receipt of the confirmation by `START_MARGIN_TICKS`. A guest's transit time is inferred from its ack: the proposed countdown less the ticks the guest had left. Once confirmed, the start doesn't change.
+    pub(crate) fn confirm(
+        &mut self,
+        host_lobby_tick: u32,
+        guests: impl IntoIterator<Item = PlayerNum>,
+    ) -> Option<ScheduledStart> {
+        let proposal = self.proposal?;
+        if self.confirmed_start.is_none() {
+            let countdown_ticks = (proposal.start_lobby_tick - proposal.host_lobby_tick) as i64;
+            let mut start = proposal.start_lobby_tick as i64;
+            for guest in guests {
+                let ticks_until_start = *self.acks.get(&guest)?;
+                let transit_ticks = (countdown_ticks - ticks_until_start as i64).max(0);
+                start =
+                    start.max(host_lobby_tick as i64 + transit_ticks + START_MARGIN_TICKS as i64);
+            }
+            self.confirmed_start = Some(start.min(u32::MAX as i64) as u32);
+        }
+        Some(ScheduledStart {
+            proposal_num: proposal.proposal_num,
+            host_lobby_tick,
+            start_lobby_tick: self.confirmed_start?,
+        })
+    }
+}
+
+/// A guest's countdown to a scheduled start.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct GuestStartCountdown {
+    pub(crate) proposal_num: u32,
+    /// The estimated seconds left until the start
+    pub(crate) remaining_sec: f32,
+    /// True once the start has been confirmed by the host
+    pub(crate) confirmed: bool,
+}
+
+impl GuestStartCountdown {
+    pub(crate) fn ticks_until_start(&self, inputs_per_sec: f32) -> i32 {
+        (self.remaining_sec * inputs_per_sec).ceil() as i32
+    }
+}
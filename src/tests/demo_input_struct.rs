@@ -56,7 +56,7 @@ impl PlayerInputBinary {
     }
 
     pub fn from_input(input: PlayerInput) -> PlayerInputBinary {
-        let mut flags = 0 as u8;
+        let mut flags = 0u8;
         if input.dash {
             flags |= input_flag_bits::DASH
         }
@@ -0,0 +1,122 @@
+//! A deterministic, simulated network between the nodes of a lobby, for scenario tests that step whole sessions.
+
+use std::collections::HashSet;
+
+use crate::{
+    session::{OutgoingMsgs, Recipient, Session},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+/// A deterministic xorshift generator, so that every run sees the same losses and delays.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+struct InFlight {
+    deliver_at: u32,
+    from: PlayerNum,
+    to: PlayerNum,
+    bytes: Vec<u8>,
+}
+
+/// A lossy, jittery network between the nodes of a lobby.
+///
+/// Each message is lost with probability 1 in `loss_one_in` (if set), and otherwise delayed by `base_delay_ticks` plus up to `jitter_ticks`, so messages may arrive out of order. Players that are cut off neither send nor receive anything, including messages already in flight.
+pub struct Network {
+    num_players: u8,
+    rng: Rng,
+    loss_one_in: Option<u64>,
+    base_delay_ticks: u32,
+    jitter_ticks: u32,
+    in_flight: Vec<InFlight>,
+    cut_off: HashSet<PlayerNum>,
+    pub num_sent: u64,
+    pub num_lost: u64,
+}
+
+impl Network {
+    pub fn new(
+        num_players: u8,
+        seed: u64,
+        loss_one_in: Option<u64>,
+        base_delay_ticks: u32,
+        jitter_ticks: u32,
+    ) -> Self {
+        Self {
+            num_players,
+            rng: Rng(seed),
+            loss_one_in,
+            base_delay_ticks,
+            jitter_ticks,
+            in_flight: Vec::new(),
+            cut_off: HashSet::new(),
+            num_sent: 0,
+            num_lost: 0,
+        }
+    }
+
+    /// Cuts `player` off from the network, or reconnects it.
+    pub fn set_cut_off(&mut self, player: PlayerNum, cut_off: bool) {
+        if cut_off {
+            self.cut_off.insert(player);
+        } else {
+            self.cut_off.remove(&player);
+        }
+    }
+
+    pub fn send(&mut self, now: u32, from: PlayerNum, outgoing: OutgoingMsgs<PlayerInput>) {
+        if self.cut_off.contains(&from) {
+            return;
+        }
+        for (recipient, msg) in outgoing {
+            let bytes = msg.to_bytes();
+            let recipients: Vec<PlayerNum> = match recipient {
+                Recipient::Player(to) => vec![to],
+                Recipient::AllPeers => PlayerNum::iter(self.num_players)
+                    .filter(|to| *to != from)
+                    .collect(),
+            };
+            for to in recipients {
+                self.num_sent += 1;
+                if self
+                    .loss_one_in
+                    .is_some_and(|loss_one_in| self.rng.next().is_multiple_of(loss_one_in))
+                {
+                    self.num_lost += 1;
+                    continue;
+                }
+                let jitter = (self.rng.next() % (self.jitter_ticks as u64 + 1)) as u32;
+                self.in_flight.push(InFlight {
+                    deliver_at: now + self.base_delay_ticks + jitter,
+                    from,
+                    to,
+                    bytes: bytes.clone(),
+                });
+            }
+        }
+    }
+
+    /// Delivers the messages due by `now` to `nodes` (indexed by player num) in the order they were sent, and sends their replies.
+    pub fn deliver_due(&mut self, now: u32, nodes: &mut [Session<PlayerInput>]) {
+        let (due, in_flight): (Vec<_>, Vec<_>) = std::mem::take(&mut self.in_flight)
+            .into_iter()
+            .partition(|msg| msg.deliver_at <= now);
+        self.in_flight = in_flight;
+        for msg in due {
+            if self.cut_off.contains(&msg.to) {
+                continue;
+            }
+            let node = &mut nodes[msg.to.0 as usize];
+            let replies = node.rx_bytes(msg.from, &msg.bytes).unwrap();
+            self.send(now, msg.to, replies);
+        }
+    }
+}
@@ -1,7 +1,83 @@
 pub mod demo_input_struct;
+pub mod lobby_sim;
+pub mod test_ack_triggers;
+pub mod test_annotations;
+pub mod test_bandwidth_budget;
+pub mod test_batch;
+pub mod test_buffer_diff;
+pub mod test_button_state;
+pub mod test_capabilities;
+pub mod test_catch_up_scenario;
+pub mod test_change_stamps;
+pub mod test_changed_ticks;
+pub mod test_clock_sync;
+pub mod test_convergence_fuzz;
+pub mod test_debug_dump;
+pub mod test_decode_stats;
+pub mod test_desync_report;
+pub mod test_determinism_probe;
+pub mod test_earliest_changed_tick;
+pub mod test_end_session;
+pub mod test_event_channel;
+#[cfg(feature = "ffi")]
+pub mod test_ffi;
+pub mod test_finalization_watch;
+pub mod test_finalized_input_channel;
+pub mod test_full_state;
+pub mod test_guest_buffer_reset;
+pub mod test_guest_sync;
+pub mod test_handle_msg;
+pub mod test_hot_path;
+pub mod test_input_hash_chain;
+pub mod test_input_lead;
+pub mod test_input_messages;
+pub mod test_input_patterns;
+pub mod test_input_retention;
+pub mod test_input_schedule;
+pub mod test_input_schema;
+pub mod test_input_staging;
+pub mod test_input_statuses;
+pub mod test_inputs_in_flight;
+pub mod test_late_join;
+pub mod test_latency_stats;
+pub mod test_max_payload;
+pub mod test_msg_sequence;
 pub mod test_multiplayer_input_buffer;
 pub mod test_multiplayer_input_manager;
 pub mod test_multiplayer_input_manager_host;
+pub mod test_net_stats;
+pub mod test_outgoing_queue;
+pub mod test_ping_report;
 pub mod test_player_input_buffer;
+pub mod test_player_metadata;
+pub mod test_player_removal;
 pub mod test_playernum;
-pub mod test_input_messages;
+pub mod test_preallocation;
+#[cfg(feature = "profiling")]
+pub mod test_profiling;
+pub mod test_reliable_delivery;
+pub mod test_replay;
+pub mod test_rollback_depth;
+mod test_rounds;
+pub mod test_rtt;
+pub mod test_rx_outcome;
+pub mod test_sanitize;
+pub mod test_seat_transfer;
+pub mod test_seed_schedule;
+pub mod test_session;
+pub mod test_session_limit;
+pub mod test_session_phase;
+#[cfg(feature = "sim_net")]
+pub mod test_sim_net;
+pub mod test_sim_ticks_per_input;
+#[cfg(feature = "soak")]
+pub mod test_soak;
+pub mod test_spectator;
+pub mod test_split_screen;
+pub mod test_start_sync;
+pub mod test_start_tick;
+pub mod test_tick_consumption;
+pub mod test_tick_map;
+pub mod test_unknown_player_slices;
+pub mod test_wire_cost;
+pub mod test_zero_copy_slices;
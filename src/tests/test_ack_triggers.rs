@@ -0,0 +1,137 @@
+use test_case::test_case;
+
+use crate::{
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::{DEFAULT_ACK_MAX_INTERVAL_SEC, GuestInputMgr},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const FRAME_SEC: f32 = 1.0 / 60.0;
+
+/// Has the guest receive `len` finalized host inputs starting at `start`.
+fn rx_host_slice(guest: &mut Guest, start: u32, len: u32) {
+    guest.rx_final_peer_input_slice_from_host(MsgPayload::HostToLobbyFinalizedSlice(
+        HostFinalizedSlice {
+            player_num: PlayerNum(0),
+            host_tick: start + len,
+            inputs: PlayerInputSlice::new_test(start, len),
+        },
+    ));
+}
+
+fn is_ack(msg: &MsgPayload<PlayerInput>) -> bool {
+    matches!(msg, MsgPayload::GuestToHostAckFinalization(_))
+}
+
+#[test]
+fn test_no_ack_while_nothing_is_finalized() {
+    // With nothing newly finalized and the max interval not yet elapsed, no
+    // ack is sent however many frames pass.
+    let mut guest = Guest::new(2, PlayerNum(1), 60);
+    for _ in 0..10 {
+        assert!(matches!(
+            guest.maybe_get_msg_ack(FRAME_SEC),
+            MsgPayload::Empty
+        ));
+    }
+}
+
+#[test]
+fn test_ack_sent_once_per_newly_finalized_batch() {
+    // A batch of newly finalized inputs triggers one ack carrying the new
+    // counts; later frames with nothing new send nothing more.
+    let mut guest = Guest::new(2, PlayerNum(1), 60);
+    rx_host_slice(&mut guest, 0, 3);
+
+    match guest.maybe_get_msg_ack(FRAME_SEC) {
+        MsgPayload::GuestToHostAckFinalization(acked) => {
+            assert_eq!(acked.get(PlayerNum(0)), 3)
+        }
+        msg => panic!("expected an ack, got {msg:?}"),
+    }
+    assert!(matches!(
+        guest.maybe_get_msg_ack(FRAME_SEC),
+        MsgPayload::Empty
+    ));
+}
+
+#[test_case(1, 1, true; "one new input meets a threshold of one")]
+#[test_case(4, 3, false; "three new inputs fall short of four")]
+#[test_case(4, 4, true; "four new inputs meet a threshold of four")]
+fn test_count_threshold(min_new_inputs: u32, num_finalized: u32, expect_ack: bool) {
+    // An ack is sent only once at least the configured number of inputs have
+    // been finalized since the last one.
+    let mut guest = Guest::new(2, PlayerNum(1), 60).with_ack_min_new_inputs(min_new_inputs);
+    rx_host_slice(&mut guest, 0, num_finalized);
+    assert_eq!(is_ack(&guest.maybe_get_msg_ack(FRAME_SEC)), expect_ack);
+}
+
+#[test]
+fn test_new_inputs_accumulate_across_slices_toward_threshold() {
+    // Inputs finalized by several small slices add up toward the count
+    // threshold.
+    let mut guest = Guest::new(2, PlayerNum(1), 60).with_ack_min_new_inputs(4);
+    rx_host_slice(&mut guest, 0, 2);
+    assert!(matches!(
+        guest.maybe_get_msg_ack(FRAME_SEC),
+        MsgPayload::Empty
+    ));
+    rx_host_slice(&mut guest, 2, 2);
+    assert!(is_ack(&guest.maybe_get_msg_ack(FRAME_SEC)));
+}
+
+#[test]
+fn test_ack_repeated_after_max_interval() {
+    // With nothing new finalized, an ack is still repeated once the max
+    // interval has passed since the last one, in case it was lost.
+    let mut guest = Guest::new(2, PlayerNum(1), 60).with_ack_max_interval_sec(0.1);
+    rx_host_slice(&mut guest, 0, 1);
+    assert!(is_ack(&guest.maybe_get_msg_ack(FRAME_SEC)));
+
+    assert!(matches!(guest.maybe_get_msg_ack(0.05), MsgPayload::Empty));
+    assert!(is_ack(&guest.maybe_get_msg_ack(0.05)));
+    assert!(matches!(guest.maybe_get_msg_ack(0.05), MsgPayload::Empty));
+}
+
+#[test]
+fn test_default_max_interval_bounds_silence() {
+    // By default, a guest with nothing to ack still sends one ack per
+    // default max interval.
+    let mut guest = Guest::new(2, PlayerNum(1), 60);
+    let num_frames = 600;
+    let num_acks = (0..num_frames)
+        .filter(|_| is_ack(&guest.maybe_get_msg_ack(FRAME_SEC)))
+        .count();
+    let expected = (num_frames as f32 * FRAME_SEC / DEFAULT_ACK_MAX_INTERVAL_SEC) as usize;
+    assert!(num_acks.abs_diff(expected) <= 1, "{num_acks} vs {expected}");
+}
+
+#[test]
+fn test_explicit_ack_resets_triggers() {
+    // An ack taken with `get_msg_ack_finalization` counts as the last ack, so
+    // `maybe_get_msg_ack` doesn't repeat it straight away.
+    let mut guest = Guest::new(2, PlayerNum(1), 60);
+    rx_host_slice(&mut guest, 0, 3);
+    assert!(is_ack(&guest.get_msg_ack_finalization()));
+    assert!(matches!(
+        guest.maybe_get_msg_ack(FRAME_SEC),
+        MsgPayload::Empty
+    ));
+}
+
+#[test]
+fn test_new_round_acks_inputs_finalized_in_it() {
+    // Finalized counts restart at a new round, so the first inputs finalized
+    // in it trigger an ack even though the old round had acked more.
+    let mut guest = Guest::new(2, PlayerNum(1), 60);
+    rx_host_slice(&mut guest, 0, 5);
+    assert!(is_ack(&guest.maybe_get_msg_ack(FRAME_SEC)));
+
+    guest.rx_round_transition_and_reply(MsgPayload::HostToLobbyRoundTransition(1));
+    rx_host_slice(&mut guest, 0, 1);
+    assert!(is_ack(&guest.maybe_get_msg_ack(FRAME_SEC)));
+}
@@ -0,0 +1,128 @@
+use test_case::test_case;
+
+use crate::{
+    event_channel::TickEvent, input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr, multiplayer_input_manager_host::HostInputMgr,
+    session::Session, tests::demo_input_struct::PlayerInput, util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+/// A 2 player host whose session starts at tick 100, with 3 of its own inputs finalized.
+fn host() -> Host {
+    let mut host = Host::new(2, 50, 5, 60).with_start_tick(100);
+    for _ in 0..3 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host
+}
+
+fn kinds(annotations: Vec<&TickEvent>) -> Vec<u16> {
+    annotations
+        .iter()
+        .map(|annotation| annotation.kind)
+        .collect()
+}
+
+#[test]
+fn test_annotations_reach_guests_until_acked() {
+    // Annotations are resent until the guest acks them, and are then
+    // available on the guest at the same sim ticks as on the host.
+    let mut host = host();
+    let mut guest = Guest::new(2, GUEST, 60);
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+    host.add_annotation(103, 1, vec![7]).unwrap();
+    host.add_annotation(103, 2, vec![]).unwrap();
+    host.add_annotation(110, 3, vec![]).unwrap();
+
+    assert_eq!(
+        guest.rx_annotations_from_host(host.get_msg_annotations()),
+        3
+    );
+    assert_eq!(
+        guest.rx_annotations_from_host(host.get_msg_annotations()),
+        0
+    );
+    host.rx_guest_annotations_ack(GUEST, guest.get_msg_ack_annotations());
+    assert!(matches!(host.get_msg_annotations(), MsgPayload::Empty));
+
+    for node_annotations in [
+        host.annotations_for_tick(103),
+        guest.annotations_for_tick(103),
+    ] {
+        assert_eq!(kinds(node_annotations), vec![1, 2]);
+    }
+    assert_eq!(kinds(guest.annotations_for_tick(110)), vec![3]);
+    assert!(guest.annotations_for_tick(104).is_empty());
+    assert_eq!(guest.annotations_for_tick(103)[0].data, vec![7]);
+}
+
+#[test_case(false; "disconnected guest")]
+#[test_case(true; "muted guest")]
+fn test_annotations_stop_once_live_guests_ack(muted: bool) {
+    // In a 3 player session, once the live guest has acked every annotation,
+    // the host stops resending them, even though a guest that disconnected or
+    // was muted never acked them.
+    let mut host = Host::new(3, 50, 5, 60).with_start_tick(100);
+    host.add_annotation(100, 1, vec![]).unwrap();
+    if muted {
+        host.mute_player(PlayerNum(2), 0);
+    } else {
+        host.player_disconnected(PlayerNum(2));
+    }
+
+    host.rx_guest_annotations_ack(GUEST, MsgPayload::GuestToHostAckAnnotations(1));
+
+    assert!(matches!(host.get_msg_annotations(), MsgPayload::Empty));
+}
+
+#[test_case(102; "already finalized")]
+#[test_case(99; "before start tick")]
+#[test_case(104; "before last annotation")]
+fn test_add_annotation_rejects_invalid_ticks(tick: u32) {
+    // Annotations can't rewrite finalized ticks, and must be added in tick
+    // order.
+    let mut host = host();
+    host.add_annotation(105, 1, vec![]).unwrap();
+    assert!(host.add_annotation(tick, 2, vec![]).is_err());
+    assert_eq!(host.get_num_annotations(), 1);
+}
+
+#[test]
+fn test_session_delivers_annotations_with_inputs() {
+    // A host driven through `Session` sends its annotations along with its
+    // inputs, and the guest's session replies with an ack.
+    let mut host = Session::from(Host::new(2, 50, 5, 60));
+    let mut guest = Session::from(Guest::new(2, GUEST, 60));
+    host.as_host_mut()
+        .unwrap()
+        .add_annotation(2, 9, vec![])
+        .unwrap();
+
+    for (_, msg) in host.add_own_input(PlayerInput::default(), 0.1) {
+        for (_, reply) in guest.rx_msg(PlayerNum(0), msg).unwrap() {
+            host.rx_msg(GUEST, reply).unwrap();
+        }
+    }
+    assert_eq!(
+        kinds(guest.as_guest().unwrap().annotations_for_tick(2)),
+        vec![9]
+    );
+    assert!(matches!(
+        host.as_host().unwrap().get_msg_annotations(),
+        MsgPayload::Empty
+    ));
+}
+
+#[test]
+fn test_new_round_clears_annotations() {
+    // Annotations belong to a round, like inputs.
+    let mut host = host();
+    host.add_annotation(105, 1, vec![]).unwrap();
+    host.start_new_round();
+    assert_eq!(host.get_num_annotations(), 0);
+}
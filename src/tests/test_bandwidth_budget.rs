@@ -0,0 +1,118 @@
+use crate::{
+    bandwidth_budget::BANDWIDTH_STARVATION_FRAMES,
+    input_messages::{HostFinalizedSlice, MsgKind, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    session::{OutgoingMsgs, Recipient},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const TO_HOST: Recipient = Recipient::Player(HOST_PLAYER_NUM);
+
+fn finalized_slice() -> MsgPayload<PlayerInput> {
+    MsgPayload::HostToLobbyFinalizedSlice(HostFinalizedSlice::new_test(PlayerNum(0), 20, 0, 20))
+}
+
+fn own_inputs(num_inputs: u32) -> MsgPayload<PlayerInput> {
+    MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, num_inputs))
+}
+
+fn size(msg: &MsgPayload<PlayerInput>) -> u32 {
+    msg.to_bytes().len() as u32
+}
+
+fn kinds(outgoing: &OutgoingMsgs<PlayerInput>) -> Vec<MsgKind> {
+    outgoing.iter().map(|(_, msg)| msg.kind()).collect()
+}
+
+/// A frame's worth of messages, deliberately out of priority order.
+fn frame() -> OutgoingMsgs<PlayerInput> {
+    vec![
+        (TO_HOST, MsgPayload::GuestToHostPing(7)),
+        (TO_HOST, own_inputs(20)),
+        (TO_HOST, finalized_slice()),
+    ]
+}
+
+#[test]
+fn test_no_budget_sends_everything() {
+    // Without a budget, every message is returned as it is, in its original
+    // order.
+    let mut guest = Guest::new(2, PlayerNum(1), 60);
+    let selected = guest.select_outgoing_within_budget(frame(), 1.0);
+    assert_eq!(kinds(&selected), kinds(&frame()));
+    assert_eq!(guest.num_msgs_dropped_for_budget(), 0);
+}
+
+#[test]
+fn test_budget_sends_in_priority_order() {
+    // With room for the finalized slice and own inputs but not the ping, the
+    // ping is dropped and the rest are sent highest priority first.
+    let mut guest = Guest::new(2, PlayerNum(1), 60);
+    guest.set_outgoing_budget_bytes_per_sec(Some(size(&finalized_slice()) + size(&own_inputs(20))));
+    let selected = guest.select_outgoing_within_budget(frame(), 1.0);
+    assert_eq!(
+        kinds(&selected),
+        vec![MsgKind::HostToLobbyFinalizedSlice, MsgKind::PeerInputs]
+    );
+    assert_eq!(guest.num_msgs_dropped_for_budget(), 1);
+}
+
+#[test]
+fn test_own_inputs_are_trimmed_to_their_oldest_inputs() {
+    // An own input slice that doesn't fit is cut down to its oldest inputs,
+    // keeping the same start so that the receiver is left no gap.
+    let mut guest = Guest::new(2, PlayerNum(1), 60);
+    guest.set_outgoing_budget_bytes_per_sec(Some(size(&own_inputs(6))));
+    let selected = guest.select_outgoing_within_budget(vec![(TO_HOST, own_inputs(20))], 1.0);
+
+    let [(_, MsgPayload::PeerInputs(slice))] = selected.as_slice() else {
+        panic!("expected a single input slice");
+    };
+    assert_eq!(slice.start, 0);
+    assert_eq!(slice.len(), 5);
+    assert_eq!(guest.num_input_slices_trimmed_for_budget(), 1);
+}
+
+#[test]
+fn test_starved_pings_are_eventually_sent() {
+    // When higher priorities use up the whole budget every frame, a ping is
+    // still sent after BANDWIDTH_STARVATION_FRAMES frames without one.
+    let mut guest = Guest::new(2, PlayerNum(1), 60);
+    guest.set_outgoing_budget_bytes_per_sec(Some(size(&finalized_slice())));
+    let frame = || {
+        vec![
+            (TO_HOST, finalized_slice()),
+            (TO_HOST, MsgPayload::GuestToHostPing(7)),
+        ]
+    };
+
+    for _ in 0..BANDWIDTH_STARVATION_FRAMES {
+        let selected = guest.select_outgoing_within_budget(frame(), 1.0);
+        assert_eq!(kinds(&selected), vec![MsgKind::HostToLobbyFinalizedSlice]);
+    }
+    let selected = guest.select_outgoing_within_budget(frame(), 1.0);
+    assert_eq!(
+        kinds(&selected),
+        vec![MsgKind::HostToLobbyFinalizedSlice, MsgKind::GuestToHostPing]
+    );
+}
+
+#[test]
+fn test_broadcasts_cost_once_per_peer() {
+    // A message for all peers costs its size for each peer, so a host with
+    // two guests can't broadcast a slice that fits the budget only once.
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(3, 50, 5, 60);
+    host.set_outgoing_budget_bytes_per_sec(Some(size(&finalized_slice()) * 3 / 2));
+    let outgoing = vec![
+        (Recipient::AllPeers, finalized_slice()),
+        (Recipient::Player(PlayerNum(1)), finalized_slice()),
+    ];
+    let selected = host.select_outgoing_within_budget(outgoing, 1.0);
+    assert_eq!(selected.len(), 1);
+    assert_eq!(selected[0].0, Recipient::Player(PlayerNum(1)));
+}
@@ -0,0 +1,179 @@
+use test_case::test_case;
+
+use crate::{
+    input_messages::{MsgKind, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    session::{OutgoingMsgs, Recipient, Session},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+fn kinds(msg: &MsgPayload<PlayerInput>) -> Vec<MsgKind> {
+    match msg {
+        MsgPayload::Batch(msgs) => msgs.iter().map(|msg| msg.kind()).collect(),
+        msg => vec![msg.kind()],
+    }
+}
+
+#[test_case(vec![], vec![MsgKind::Empty]; "nothing makes an empty message")]
+#[test_case(vec![MsgPayload::Empty, MsgPayload::GuestToHostPing(1)], vec![MsgKind::GuestToHostPing]; "lone message isn't wrapped")]
+#[test_case(vec![MsgPayload::GuestToHostPing(1), MsgPayload::Empty, MsgPayload::GuestToHostEndAck(2)], vec![MsgKind::GuestToHostPing, MsgKind::GuestToHostEndAck]; "empty messages are left out")]
+fn test_batch_leaves_out_empty_messages(
+    msgs: Vec<MsgPayload<PlayerInput>>,
+    expected: Vec<MsgKind>,
+) {
+    // Bundling drops empty messages, and only wraps two or more in a batch.
+    let batch = MsgPayload::batch(msgs);
+
+    assert_eq!(kinds(&batch), expected);
+    assert_eq!(matches!(batch, MsgPayload::Batch(_)), expected.len() > 1);
+}
+
+#[test]
+fn test_nested_batches_are_rejected_on_decode() {
+    // A batch inside a batch doesn't decode, so a hostile peer can't nest
+    // them deeply.
+    let inner = MsgPayload::<PlayerInput>::Batch(vec![
+        MsgPayload::GuestToHostPing(1),
+        MsgPayload::GuestToHostPing(2),
+    ]);
+    let outer = MsgPayload::Batch(vec![inner, MsgPayload::GuestToHostPing(3)]);
+
+    assert!(MsgPayload::<PlayerInput>::from_bytes(&outer.to_bytes()).is_err());
+}
+
+#[test]
+fn test_guest_frame_pings_every_interval() {
+    // The first frame carries the guest's inputs and a ping; until the ping
+    // interval has passed, later frames carry only the inputs. (Acks are held
+    // back, as nothing is finalized.)
+    let mut guest = Guest::new(2, GUEST, 60)
+        .with_ping_interval_sec(0.5)
+        .with_ack_max_interval_sec(10.0);
+    guest.add_own_input(PlayerInput::new_test_simple(1));
+
+    let first = guest.get_outgoing_msgs_for_frame(0.1);
+    let second = guest.get_outgoing_msgs_for_frame(0.1);
+    for _ in 0..3 {
+        guest.get_outgoing_msgs_for_frame(0.1);
+    }
+    let after_interval = guest.get_outgoing_msgs_for_frame(0.1);
+
+    assert_eq!(
+        kinds(&first),
+        vec![MsgKind::PeerInputs, MsgKind::GuestToHostPing]
+    );
+    assert_eq!(kinds(&second), vec![MsgKind::PeerInputs]);
+    assert_eq!(
+        kinds(&after_interval),
+        vec![MsgKind::PeerInputs, MsgKind::GuestToHostPing]
+    );
+}
+
+#[test]
+fn test_guest_frame_includes_due_ack_and_clock_ping() {
+    // Once inputs are finalized the ack is due, and once the guest has a
+    // local time its pings come with a clock ping.
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    host.add_host_input_directly(PlayerInput::new_test_simple(1));
+    guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(HOST_PLAYER_NUM));
+    guest.observe_local_time_ms(1_000.0);
+
+    let frame = guest.get_outgoing_msgs_for_frame(0.1);
+
+    assert_eq!(
+        kinds(&frame),
+        vec![
+            MsgKind::PeerInputs,
+            MsgKind::GuestToHostAckFinalization,
+            MsgKind::GuestToHostPing,
+            MsgKind::GuestToHostClockPing,
+        ]
+    );
+}
+
+#[test]
+fn test_host_frame_has_every_players_finalized_slice() {
+    // The host's frame broadcasts the finalized slice of every player.
+    let mut host = Host::new(3, 50, 5, 60);
+    host.add_host_input_directly(PlayerInput::new_test_simple(1));
+
+    let frame = host.get_outgoing_msgs_for_frame(0.1);
+
+    assert_eq!(kinds(&frame), vec![MsgKind::HostToLobbyFinalizedSlice; 3]);
+}
+
+#[test]
+fn test_host_records_finalized_slices_sent_in_a_batch() {
+    // Slices sent inside a batch count as sent, so acks up to them aren't
+    // flagged as beyond what was sent.
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    for x in 0..4 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(x));
+    }
+    let frame = host.get_outgoing_msgs_for_frame(0.1);
+    host.record_msg_sent(Recipient::AllPeers, &frame);
+    let MsgPayload::Batch(msgs) = frame else {
+        panic!("expected a batch");
+    };
+    for msg in msgs {
+        guest.rx_final_peer_input_slice_from_host(msg);
+    }
+
+    host.rx_finalized_ticks_observations(GUEST, guest.get_msg_ack_finalization());
+
+    assert!(host.drain_events().is_empty());
+    assert_eq!(host.observation_matrix()[0].1[0], (HOST_PLAYER_NUM, 4));
+}
+
+/// Delivers each message to `to` as bytes, returning its replies.
+fn deliver(
+    from: PlayerNum,
+    to: &mut Session<PlayerInput>,
+    outgoing: OutgoingMsgs<PlayerInput>,
+) -> OutgoingMsgs<PlayerInput> {
+    outgoing
+        .into_iter()
+        .flat_map(|(_, msg)| to.rx_bytes(from, &msg.to_bytes()).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_sessions_exchange_frames_as_batches() {
+    // Sessions that send one batch per frame finalize each other's inputs,
+    // and a guest replies to a batch of slices with a single ack.
+    let mut host: Session<PlayerInput> = Host::new(2, 50, 5, 10).into();
+    let mut guest: Session<PlayerInput> = Guest::new(2, GUEST, 10).into();
+
+    for x in 0..3 {
+        host.add_own_input(PlayerInput::new_test_simple(x), 0.1);
+        guest.add_own_input(PlayerInput::new_test_simple(x + 10), 0.1);
+        let from_guest = guest.get_outgoing_msgs_for_frame(0.1);
+        deliver(GUEST, &mut host, from_guest);
+        let from_host = host.get_outgoing_msgs_for_frame(0.1);
+        let replies = deliver(HOST_PLAYER_NUM, &mut guest, from_host);
+
+        let acks = replies
+            .iter()
+            .filter(|(_, msg)| matches!(msg, MsgPayload::GuestToHostAckFinalization(_)))
+            .count();
+        assert_eq!(acks, 1);
+        deliver(GUEST, &mut host, replies);
+    }
+
+    assert_eq!(host.get_snapshottable_sim_tick(), 3);
+    assert_eq!(guest.get_snapshottable_sim_tick(), 3);
+    assert_eq!(
+        host.get_peer_input_for_tick(GUEST, 2),
+        PlayerInput::new_test_simple(12)
+    );
+}
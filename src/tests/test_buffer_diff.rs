@@ -0,0 +1,98 @@
+use crate::{
+    input_messages::MsgPayload,
+    input_trait::SimInput,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+/// A 2 player host and guest that have exchanged 4 inputs each, all finalized on both.
+fn converged_host_and_guest() -> (Host, Guest) {
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    for x in 0..4 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(x));
+        guest.add_own_input(PlayerInput::new_test_simple(10 + x));
+    }
+    host.rx_guest_input_slice(GUEST, guest.get_msg_own_input_slice());
+    guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(HOST_PLAYER_NUM));
+    guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(GUEST));
+    (host, guest)
+}
+
+#[test]
+fn test_converged_managers() {
+    // A host and guest that have finalized the same inputs are converged,
+    // with matching counts for every player.
+    let (host, guest) = converged_host_and_guest();
+    let diff = host.diff_against(&guest);
+
+    assert!(diff.is_converged(), "{diff:?}");
+    assert_eq!(diff.first_divergent_tick(), None);
+    let host_diff = diff.player(HOST_PLAYER_NUM).unwrap();
+    assert_eq!(host_diff.num_finalized, (4, 4));
+    assert_eq!(host_diff.num_inputs, (4, 4));
+}
+
+#[test]
+fn test_unequal_finalized_counts_are_not_converged() {
+    // A node that has finalized fewer inputs for a player isn't converged,
+    // even though the inputs both have finalized agree.
+    let (mut host, guest) = converged_host_and_guest();
+    host.add_host_input_directly(PlayerInput::new_test_simple(4));
+    let diff = host.diff_against(&guest);
+
+    assert!(!diff.is_converged());
+    assert_eq!(diff.first_divergent_tick(), None);
+    assert_eq!(diff.player(HOST_PLAYER_NUM).unwrap().num_finalized, (5, 4));
+    assert!(diff.player(GUEST).unwrap().is_converged());
+}
+
+#[test]
+fn test_unfinalized_inputs_do_not_affect_convergence() {
+    // Total counts are reported, but a guest's own inputs that the host
+    // hasn't finalized yet don't break convergence.
+    let (host, mut guest) = converged_host_and_guest();
+    guest.add_own_input(PlayerInput::new_test_simple(14));
+    let diff = guest.diff_against(&host);
+
+    assert!(diff.is_converged());
+    assert_eq!(diff.player(GUEST).unwrap().num_inputs, (5, 4));
+}
+
+#[test]
+fn test_first_divergent_tick_per_player() {
+    // Finalized inputs that differ are reported at the first sim tick where
+    // they do, for each player separately.
+    let (host, _) = converged_host_and_guest();
+    let mut other_guest = Guest::new(2, GUEST, 60);
+    // The guest's inputs as the host has them, except at tick 2
+    let mut inputs = PlayerInputSlice::<PlayerInput>::new_test(10, 4);
+    inputs.start = 0;
+    inputs.inputs[2] = PlayerInput::new_test_simple(99).to_bytes();
+    other_guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(HOST_PLAYER_NUM));
+    other_guest.rx_final_peer_input_slice_from_host(MsgPayload::HostToLobbyFinalizedSlice(
+        crate::input_messages::HostFinalizedSlice {
+            player_num: GUEST,
+            host_tick: 4,
+            inputs,
+        },
+    ));
+
+    let diff = host.diff_against(&other_guest);
+
+    assert!(!diff.is_converged());
+    assert_eq!(
+        diff.player(HOST_PLAYER_NUM).unwrap().first_divergent_tick,
+        None
+    );
+    assert_eq!(diff.player(GUEST).unwrap().first_divergent_tick, Some(2));
+    assert_eq!(diff.first_divergent_tick(), Some(2));
+}
@@ -0,0 +1,65 @@
+use test_case::test_case;
+
+use crate::{
+    button_state::{ButtonState, button_states, hold_duration_ticks, is_double_tap},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+/// Builds a history from a string of `#` (down) and `.` (up), oldest first.
+fn history(s: &str) -> Vec<bool> {
+    s.chars().map(|c| c == '#').collect()
+}
+
+#[test]
+fn test_button_states_from_history() {
+    // Each tick's state depends on that tick and the previous one, with the
+    // button taken to be up before the history starts.
+    use ButtonState::*;
+    let states: Vec<_> = button_states(history("##..#")).collect();
+    assert_eq!(states, vec![Pressed, Held, Released, Up, Pressed]);
+}
+
+#[test_case("", 0; "empty history")]
+#[test_case("...", 0; "never pressed")]
+#[test_case("###.", 0; "released on last tick")]
+#[test_case("#..###", 3; "trailing hold")]
+#[test_case("####", 4; "held throughout")]
+fn test_hold_duration(s: &str, expected: u32) {
+    // The hold duration counts only the trailing run of ticks the button is down.
+    assert_eq!(hold_duration_ticks(history(s)), expected);
+}
+
+#[test_case("#..#", 3, true; "second press within gap")]
+#[test_case("#...#", 3, false; "second press too late")]
+#[test_case("#.#.", 3, false; "double tap not on last tick")]
+#[test_case("##", 3, false; "held is not a tap")]
+#[test_case("#", 3, false; "single press")]
+fn test_double_tap(s: &str, max_gap_ticks: u32, expected: bool) {
+    // A double tap is reported only on the tick of the second press, and only if
+    // it follows the previous press closely enough.
+    assert_eq!(is_double_tap(history(s), max_gap_ticks), expected);
+}
+
+#[test]
+fn test_button_history_from_buffer() {
+    // The history is read from the buffer up to the local tick, with
+    // predictions for peer ticks that haven't been received.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, 1.into(), 60);
+    for x in [1, 0, 1, 1] {
+        guest.add_own_input(PlayerInput::new_test_simple(x));
+    }
+
+    let jump = |input: &PlayerInput| input.jump;
+    let own: Vec<_> = guest.button_history(PlayerNum(1), jump, 3).collect();
+    assert_eq!(own, vec![false, true, true]);
+
+    // asking for more ticks than have been collected stops at tick 0
+    assert_eq!(guest.button_history(PlayerNum(1), jump, 10).count(), 4);
+
+    // nothing has been received from the host, so its inputs are predicted
+    let host: Vec<_> = guest.button_history(PlayerNum(0), jump, 3).collect();
+    assert_eq!(host, vec![false; 3]);
+}
@@ -0,0 +1,202 @@
+use test_case::test_case;
+
+use crate::{
+    capabilities::Capabilities,
+    input_messages::{COMPRESSED_FLAG, MsgPayload, PreSimSync},
+    prelude::*,
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerInputSlice,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+/// A message large enough to be compressed when compression is negotiated.
+fn large_msg() -> MsgPayload<PlayerInput> {
+    MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 400))
+}
+
+fn pre_sim_sync_with_capabilities(capabilities: Capabilities) -> MsgPayload<PlayerInput> {
+    PreSimSync {
+        host_tick_countdown: 3,
+        capabilities,
+        ..Default::default()
+    }
+    .into()
+}
+
+#[test_case(Capabilities(0b011), Capabilities(0b110), Capabilities(0b010); "overlapping")]
+#[test_case(Capabilities(0b001), Capabilities(0b010), Capabilities::NONE; "disjoint")]
+#[test_case(Capabilities(0b111), Capabilities::NONE, Capabilities::NONE; "baseline")]
+fn test_intersection(ours: Capabilities, theirs: Capabilities, expected: Capabilities) {
+    // The intersection keeps only the capabilities in both sets, and is
+    // contained in each of them.
+    let common = ours.intersection(theirs);
+    assert_eq!(common, expected);
+    assert!(ours.contains(common));
+    assert!(theirs.contains(common));
+    assert_eq!(ours.union(theirs).intersection(ours), ours);
+}
+
+#[test]
+fn test_with_capabilities_drops_unsupported_bits() {
+    // A node can only enable capabilities compiled into this build, and
+    // enables all of them by default.
+    let host = Host::new(2, 50, 5, 60);
+    assert_eq!(host.capabilities(), Capabilities::supported());
+
+    let host = host.with_capabilities(Capabilities(u32::MAX));
+    assert_eq!(host.capabilities(), Capabilities::supported());
+
+    let host = host.with_capabilities(Capabilities::NONE);
+    assert_eq!(host.capabilities(), Capabilities::NONE);
+}
+
+#[test]
+fn test_handshake_negotiates_the_common_subset() {
+    // The host advertises its capabilities in the PreSimSync, the guest keeps
+    // those it also has and confirms them, and the host stores the confirmed
+    // set for that guest.
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    assert_eq!(host.negotiated_capabilities(GUEST), Capabilities::NONE);
+
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+    let supported = Capabilities::supported();
+    assert_eq!(guest.negotiated_capabilities(PlayerNum(0)), supported);
+
+    let negotiated = host.rx_guest_capabilities(GUEST, guest.get_msg_capabilities());
+    assert_eq!(negotiated, supported);
+    assert_eq!(host.negotiated_capabilities(GUEST), supported);
+    assert_eq!(host.lobby_capabilities(), supported);
+}
+
+#[test]
+fn test_guest_without_capabilities_falls_back_to_baseline() {
+    // A guest that has disabled every capability negotiates the baseline
+    // protocol, whatever the host advertises.
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60).with_capabilities(Capabilities::NONE);
+
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+    assert_eq!(
+        guest.negotiated_capabilities(PlayerNum(0)),
+        Capabilities::NONE
+    );
+
+    host.rx_guest_capabilities(GUEST, guest.get_msg_capabilities());
+    assert_eq!(host.negotiated_capabilities(GUEST), Capabilities::NONE);
+    assert_eq!(host.lobby_capabilities(), Capabilities::NONE);
+}
+
+#[test]
+fn test_unknown_capabilities_are_dropped() {
+    // Capabilities a peer advertises or confirms that this build doesn't know
+    // are never negotiated.
+    let mut guest = Guest::new(2, GUEST, 60);
+    guest
+        .rx_pre_sim_sync(pre_sim_sync_with_capabilities(Capabilities(u32::MAX)))
+        .unwrap();
+    assert_eq!(
+        guest.negotiated_capabilities(PlayerNum(0)),
+        Capabilities::supported()
+    );
+
+    let mut host = Host::new(2, 50, 5, 60);
+    let negotiated = host.rx_guest_capabilities(
+        GUEST,
+        MsgPayload::GuestToHostCapabilities(Capabilities(u32::MAX)),
+    );
+    assert_eq!(negotiated, Capabilities::supported());
+}
+
+#[test]
+fn test_peers_without_negotiation_get_baseline_bytes() {
+    // Until a peer has confirmed its capabilities, messages to it are never
+    // compressed, and still decode to the same message.
+    let host = Host::new(2, 50, 5, 60);
+    let msg = large_msg();
+
+    for bytes in [
+        host.msg_bytes_for_peer(GUEST, &msg),
+        host.msg_bytes_for_all_peers(&msg),
+    ] {
+        assert_eq!(bytes[0] & COMPRESSED_FLAG, 0);
+        assert_eq!(bytes, msg.to_bytes_for(Capabilities::NONE));
+        let decoded = MsgPayload::<PlayerInput>::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes_for(Capabilities::NONE), bytes);
+    }
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_mixed_lobby_only_compresses_for_capable_peers() {
+    // In a lobby where one guest has compression and the other doesn't,
+    // messages to the capable guest are compressed, while messages to the
+    // other guest and broadcasts fall back to the baseline.
+    let mut host = Host::new(3, 50, 5, 60);
+    let sync = host.get_msg_pre_sim_sync(3);
+    let mut capable = Guest::new(3, PlayerNum(1), 60);
+    let mut baseline = Guest::new(3, PlayerNum(2), 60).with_capabilities(Capabilities::NONE);
+    capable.rx_pre_sim_sync(sync.clone()).unwrap();
+    baseline.rx_pre_sim_sync(sync).unwrap();
+    host.rx_guest_capabilities(PlayerNum(1), capable.get_msg_capabilities());
+    host.rx_guest_capabilities(PlayerNum(2), baseline.get_msg_capabilities());
+
+    let msg = large_msg();
+    let compressed = host.msg_bytes_for_peer(PlayerNum(1), &msg);
+    assert_ne!(compressed[0] & COMPRESSED_FLAG, 0);
+    assert_eq!(compressed, msg.to_bytes());
+    assert_eq!(
+        host.msg_bytes_for_peer(PlayerNum(2), &msg)[0] & COMPRESSED_FLAG,
+        0
+    );
+    assert_eq!(host.lobby_capabilities(), Capabilities::NONE);
+    assert_eq!(host.msg_bytes_for_all_peers(&msg)[0] & COMPRESSED_FLAG, 0);
+
+    // The capable guest compresses what it sends to the host.
+    assert_ne!(
+        capable.msg_bytes_for_peer(PlayerNum(0), &msg)[0] & COMPRESSED_FLAG,
+        0
+    );
+}
+
+#[test]
+fn test_session_negotiates_through_rx_msg() {
+    // A guest session confirms its capabilities in reply to the PreSimSync,
+    // and a host session stores them for that guest.
+    let mut host: Session<PlayerInput> = Host::new(2, 50, 5, 60).into();
+    let mut guest: Session<PlayerInput> = Guest::new(2, GUEST, 60).into();
+
+    let sync = host.as_host_mut().unwrap().get_msg_pre_sim_sync(3);
+    let replies = guest.rx_msg(PlayerNum(0), sync).unwrap();
+    for (_, reply) in replies {
+        host.rx_msg(GUEST, reply).unwrap();
+    }
+
+    assert_eq!(
+        host.as_host().unwrap().negotiated_capabilities(GUEST),
+        Capabilities::supported()
+    );
+    let msg = large_msg();
+    assert_eq!(
+        host.msg_bytes(Recipient::Player(GUEST), &msg),
+        msg.to_bytes_for(Capabilities::supported())
+    );
+}
+
+#[test]
+fn test_transferred_seat_falls_back_to_baseline() {
+    // Moving a seat to a new connection forgets the capabilities negotiated
+    // with the old one.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.rx_guest_capabilities(
+        GUEST,
+        MsgPayload::GuestToHostCapabilities(Capabilities::supported()),
+    );
+
+    host.transfer_seat(GUEST, PlayerNum(5)).unwrap();
+    assert_eq!(host.negotiated_capabilities(GUEST), Capabilities::NONE);
+}
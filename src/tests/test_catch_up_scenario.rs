@@ -0,0 +1,173 @@
+//! Scenarios that step a whole lobby through a guest's prolonged stall, the host's catch-up of that guest with default inputs, and the guest's return.
+
+use std::collections::HashMap;
+
+use test_case::test_case;
+
+use crate::{
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    session::Session,
+    tests::{demo_input_struct::PlayerInput, lobby_sim::Network},
+    util_types::PlayerNum,
+};
+
+const TICKS_PER_SEC: u32 = 60;
+const NUM_PLAYERS: u8 = 3;
+const MAX_GUEST_TICKS_BEHIND: u32 = 10;
+const STALLED_GUEST: PlayerNum = PlayerNum(2);
+const STALL_START: u32 = 60;
+const BASE_DELAY_TICKS: u32 = 2;
+/// Ticks stepped after the stall ends, for the lobby to settle.
+const SETTLE_TICKS: u32 = 240;
+
+/// Each node's own input for a tick; never the default input, so that defaults injected by the host can be told apart.
+fn real_input(player: PlayerNum, now: u32) -> PlayerInput {
+    PlayerInput::new_test_simple(1 + ((now + 7 * player.0 as u32) % 100) as u8)
+}
+
+/// The finalized inputs observed so far, keyed by player and tick, across every node.
+#[derive(Default)]
+struct FinalizedObservations {
+    inputs: HashMap<(PlayerNum, u32), PlayerInput>,
+    /// The number of each player's finalized inputs already checked on each node, keyed by node and player
+    num_checked: HashMap<(usize, PlayerNum), u32>,
+}
+
+impl FinalizedObservations {
+    /// Checks every node's newly finalized inputs against those observed before, on any node, and records them. With `recheck_all`, the inputs checked before are checked again, to catch any that have changed since.
+    fn observe(&mut self, now: u32, nodes: &[Session<PlayerInput>], recheck_all: bool) {
+        for (node_num, node) in nodes.iter().enumerate() {
+            for player in PlayerNum::iter(NUM_PLAYERS) {
+                let num_final = match node {
+                    Session::Host(host) => host.get_peer_num_final_inputs(player),
+                    Session::Guest(guest) => guest.get_peer_num_final_inputs(player),
+                };
+                let num_checked = self.num_checked.entry((node_num, player)).or_default();
+                assert!(num_final >= *num_checked);
+                let first_unchecked = if recheck_all { 0 } else { *num_checked };
+                *num_checked = num_final;
+                for tick in first_unchecked..num_final {
+                    let input = node.get_peer_input_for_tick(player, tick);
+                    let first = *self.inputs.entry((player, tick)).or_insert(input);
+                    assert_eq!(
+                        input, first,
+                        "at {now}, node {node_num} changed or disagrees on {player:?}'s finalized input for tick {tick}"
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn run_stall_scenario(stall_ticks: u32, jitter_ticks: u32) {
+    let mut nodes: Vec<Session<PlayerInput>> = vec![
+        MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(
+            NUM_PLAYERS,
+            MAX_GUEST_TICKS_BEHIND,
+            5,
+            TICKS_PER_SEC,
+        )
+        .into(),
+    ];
+    let mean_rtt_ms =
+        2.0 * (BASE_DELAY_TICKS as f32 + jitter_ticks as f32 / 2.0) * 1000.0 / TICKS_PER_SEC as f32;
+    for guest_num in PlayerNum::iter_guests(NUM_PLAYERS) {
+        let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(
+            NUM_PLAYERS,
+            guest_num,
+            TICKS_PER_SEC,
+        );
+        // pings are timed with the wall clock, so the test supplies the RTT
+        guest.observe_rtt_ms_to_host(mean_rtt_ms);
+        nodes.push(guest.into());
+    }
+    let mut network = Network::new(
+        NUM_PLAYERS,
+        0x5eed_cafe,
+        None,
+        BASE_DELAY_TICKS,
+        jitter_ticks,
+    );
+    let mut observations = FinalizedObservations::default();
+    let stall_end = STALL_START + stall_ticks;
+    let delta = 1.0 / TICKS_PER_SEC as f32;
+
+    let end = stall_end + SETTLE_TICKS;
+    for now in 0..end {
+        let stalled = (STALL_START..stall_end).contains(&now);
+        network.set_cut_off(STALLED_GUEST, stalled);
+        for (player, node) in nodes.iter_mut().enumerate() {
+            let player_num = PlayerNum(player as u8);
+            if stalled && player_num == STALLED_GUEST {
+                continue;
+            }
+            let num_inputs = match node {
+                Session::Host(_) => 1,
+                Session::Guest(guest) => guest.num_inputs_needed(),
+            };
+            for _ in 0..num_inputs {
+                let outgoing = node.add_own_input(real_input(player_num, now), delta);
+                network.send(now, player_num, outgoing);
+            }
+            node.drain_events();
+        }
+        network.deliver_due(now, &mut nodes);
+        observations.observe(now, &nodes, now % TICKS_PER_SEC == 0 || now + 1 == end);
+    }
+
+    let host = nodes[0].as_host().unwrap();
+    let stalled_guest = nodes[STALLED_GUEST.0 as usize].as_guest().unwrap();
+    let other_guest = nodes[1].as_guest().unwrap();
+
+    // The host injected defaults for the stalled guest: every default in its
+    // seat was injected, since the guest's own inputs never are.
+    let injected: Vec<u32> = (0..host.get_peer_num_final_inputs(STALLED_GUEST))
+        .filter(|&tick| host.get_peer_input_for_tick(STALLED_GUEST, tick) == PlayerInput::default())
+        .collect();
+    assert!(
+        injected.len() as u32 >= stall_ticks - 2 * MAX_GUEST_TICKS_BEHIND,
+        "only {} defaults injected for a {stall_ticks} tick stall",
+        injected.len()
+    );
+    assert!(
+        injected
+            .iter()
+            .all(|tick| (STALL_START..stall_end + MAX_GUEST_TICKS_BEHIND).contains(tick))
+    );
+
+    // The guests end up with exactly the injected defaults, so the stalled
+    // guest's own late inputs for those ticks were discarded everywhere.
+    for guest in [stalled_guest, other_guest] {
+        assert!(guest.get_peer_num_final_inputs(STALLED_GUEST) > *injected.last().unwrap());
+        for &tick in &injected {
+            assert_eq!(
+                guest.get_peer_input_for_tick(STALLED_GUEST, tick),
+                PlayerInput::default()
+            );
+        }
+        assert_eq!(guest.diff_against(host).first_divergent_tick(), None);
+    }
+
+    // After the stall, the guest's real inputs are finalized again.
+    let last_final = host.get_peer_num_final_inputs(STALLED_GUEST) - 1;
+    assert!(last_final > *injected.last().unwrap());
+    assert_ne!(
+        host.get_peer_input_for_tick(STALLED_GUEST, last_final),
+        PlayerInput::default()
+    );
+}
+
+#[test_case(120, 0; "short stall")]
+#[test_case(600, 0; "ten second stall")]
+#[test_case(300, 3; "stall with jittery delivery")]
+fn test_stalled_guest_is_caught_up_with_defaults(stall_ticks: u32, jitter_ticks: u32) {
+    // A 3 player lobby where one guest stalls (neither stepping nor sending
+    // nor receiving) and then resumes. Throughout, no node's finalized input
+    // ever changes, and all nodes agree on every finalized input. Afterwards,
+    // the defaults the host injected for the stalled guest are exactly what
+    // every guest has for those ticks, the stalled guest's own late inputs
+    // for them are discarded, and its later inputs are finalized as usual.
+    run_stall_scenario(stall_ticks, jitter_ticks);
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use crate::{
+    ChangeStamps, InputMgrEvent, Session,
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+/// Which stamps differ between `before` and `after`, as (buffers, observations, rtt, events).
+fn changed(before: ChangeStamps, after: ChangeStamps) -> (bool, bool, bool, bool) {
+    (
+        after.buffers != before.buffers,
+        after.observations != before.observations,
+        after.rtt != before.rtt,
+        after.events != before.events,
+    )
+}
+
+#[test]
+fn test_receiving_inputs_only_bumps_buffers() {
+    // A guest slice arriving at the host changes the buffers, and nothing
+    // else.
+    let mut host = Host::new(2, 50, 5, 60);
+    let before = host.change_stamps();
+    host.rx_guest_input_slice(
+        PlayerNum(1),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 5)),
+    );
+
+    assert_eq!(
+        changed(before, host.change_stamps()),
+        (true, false, false, false)
+    );
+}
+
+#[test]
+fn test_duplicate_slice_leaves_stamps_alone() {
+    // Receiving a slice that adds nothing new leaves every stamp as it was,
+    // so UIs polling the stamps don't re-render.
+    let mut host = Host::new(2, 50, 5, 60);
+    let slice = MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 5));
+    host.rx_guest_input_slice(PlayerNum(1), slice.clone());
+    let before = host.change_stamps();
+    host.rx_guest_input_slice(PlayerNum(1), slice);
+
+    assert!(!host.change_stamps().changed_since(&before));
+}
+
+#[test]
+fn test_guest_acks_bump_observations_on_host() {
+    // An ack from a guest changes the host's observations stamp.
+    let mut host = Host::new(2, 50, 5, 60);
+    for _ in 0..5 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    let before = host.change_stamps();
+    host.rx_finalized_ticks_observations(
+        PlayerNum(1),
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
+            HashMap::from([(HOST_PLAYER_NUM, 3)]),
+        )),
+    );
+
+    assert_eq!(
+        changed(before, host.change_stamps()),
+        (false, true, false, false)
+    );
+}
+
+#[test]
+fn test_rtt_sample_bumps_rtt() {
+    // A new RTT sample on a guest changes its RTT stamp (the guest doesn't
+    // know the host tick yet, so it doesn't become synced and queue an event).
+    let mut guest = Guest::new(2, PlayerNum(1), 60);
+    let before = guest.change_stamps();
+    guest.observe_rtt_ms_to_host(40.0);
+
+    assert_eq!(
+        changed(before, guest.change_stamps()),
+        (false, false, true, false)
+    );
+}
+
+#[test]
+fn test_events_stamp_counts_queued_and_drained_events() {
+    // Queuing an event bumps the events stamp, and draining it doesn't move
+    // the stamp back, so it never repeats an earlier value.
+    let mut guest = Guest::new(2, PlayerNum(1), 60);
+    guest.rx_final_peer_input_slice_from_host(
+        HostFinalizedSlice::new_test(PlayerNum(2), 10, 0, 5).into(),
+    );
+    let queued = guest.change_stamps();
+    assert_eq!(
+        guest.drain_events(),
+        vec![InputMgrEvent::UnknownPlayer {
+            player_num: PlayerNum(2)
+        }]
+    );
+
+    assert_eq!(queued.events, 1);
+    assert_eq!(guest.change_stamps().events, 1);
+}
+
+#[test]
+fn test_stamps_keep_increasing_across_rounds() {
+    // Starting a new round empties the buffers, which counts as a change
+    // rather than resetting the buffers stamp.
+    let mut host = Host::new(2, 50, 5, 60);
+    for _ in 0..5 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    let before = host.change_stamps();
+    host.start_new_round();
+
+    let after = host.change_stamps();
+    assert!(after.buffers > before.buffers);
+    assert!(after.observations > before.observations);
+}
+
+#[test]
+fn test_session_forwards_change_stamps() {
+    // A session reports the stamps of the manager it wraps.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.add_host_input_directly(PlayerInput::default());
+    let stamps = host.change_stamps();
+
+    assert_eq!(Session::from(host).change_stamps(), stamps);
+}
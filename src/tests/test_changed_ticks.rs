@@ -0,0 +1,190 @@
+use crate::{
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    input_trait::SimInput,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+fn input(x: u8) -> PlayerInput {
+    PlayerInput::new_test_simple(x)
+}
+
+fn slice(start: u32, inputs: &[PlayerInput]) -> MsgPayload<PlayerInput> {
+    MsgPayload::PeerInputs(PlayerInputSlice {
+        start,
+        inputs: inputs.iter().map(SimInput::to_bytes).collect(),
+    })
+}
+
+#[test]
+fn test_nothing_changed_on_a_new_manager() {
+    // A manager that has collected and received nothing reports no changed
+    // ticks.
+    let mut host = Host::new(2, 50, 5, 60).with_changed_tick_tracking();
+
+    assert_eq!(host.take_changed_ticks_since(0), vec![]);
+}
+
+#[test]
+fn test_only_mispredicted_ticks_are_reported() {
+    // Of a peer's inputs, only those differing from what was predicted for
+    // them (a repeat of its last input) are reported, rather than every tick
+    // from the earliest change.
+    let mut host = Host::new(2, 50, 5, 60).with_changed_tick_tracking();
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, &[input(7); 3]));
+    host.take_changed_ticks_since(0);
+
+    host.rx_guest_input_slice(
+        PlayerNum(1),
+        slice(3, &[input(7), input(8), input(7), input(9)]),
+    );
+
+    assert_eq!(
+        host.take_changed_ticks_since(0),
+        vec![(PlayerNum(1), 4), (PlayerNum(1), 6)]
+    );
+}
+
+#[test]
+fn test_late_finalized_slice_rewrites_predicted_ticks() {
+    // On a guest, a finalized slice from the host reports each tick whose
+    // input differs from the host input predicted for it.
+    let mut guest = Guest::new(2, PlayerNum(1), 60).with_changed_tick_tracking();
+    let finalized = |start, inputs: &[PlayerInput]| {
+        MsgPayload::HostToLobbyFinalizedSlice(HostFinalizedSlice {
+            player_num: PlayerNum(0),
+            host_tick: start + inputs.len() as u32,
+            inputs: PlayerInputSlice {
+                start,
+                inputs: inputs.iter().map(SimInput::to_bytes).collect(),
+            },
+        })
+    };
+    guest.rx_final_peer_input_slice_from_host(finalized(0, &[input(3); 2]));
+    guest.take_changed_ticks_since(0);
+
+    guest.rx_final_peer_input_slice_from_host(finalized(2, &[input(3), input(4), input(5)]));
+
+    assert_eq!(
+        guest.take_changed_ticks_since(0),
+        vec![(PlayerNum(0), 3), (PlayerNum(0), 4)]
+    );
+}
+
+#[test]
+fn test_changes_are_reported_once() {
+    // A changed tick is only reported by the first call after it changed.
+    let mut host = Host::new(2, 50, 5, 60).with_changed_tick_tracking();
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, &[input(7)]));
+
+    assert_eq!(host.take_changed_ticks_since(0), vec![(PlayerNum(1), 0)]);
+    assert_eq!(host.take_changed_ticks_since(0), vec![]);
+}
+
+#[test]
+fn test_changes_before_the_given_tick_are_dropped() {
+    // Changes before `tick` aren't reported, and aren't kept for later calls
+    // either.
+    let mut host = Host::new(2, 50, 5, 60).with_changed_tick_tracking();
+    host.rx_guest_input_slice(
+        PlayerNum(1),
+        slice(0, &[input(1), input(2), input(3), input(4)]),
+    );
+
+    assert_eq!(
+        host.take_changed_ticks_since(2),
+        vec![(PlayerNum(1), 2), (PlayerNum(1), 3)]
+    );
+    assert_eq!(host.take_changed_ticks_since(0), vec![]);
+}
+
+#[test]
+fn test_changes_are_ordered_by_tick_then_player() {
+    // Changes for several players are interleaved in tick order.
+    let mut host = Host::new(3, 50, 5, 60).with_changed_tick_tracking();
+    host.rx_guest_input_slice(PlayerNum(2), slice(0, &[input(1), input(2)]));
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, &[input(1), input(2)]));
+    host.add_host_input_directly(input(5));
+
+    assert_eq!(
+        host.take_changed_ticks_since(0),
+        vec![
+            (PlayerNum(0), 0),
+            (PlayerNum(1), 0),
+            (PlayerNum(2), 0),
+            (PlayerNum(1), 1),
+            (PlayerNum(2), 1),
+        ]
+    );
+}
+
+#[test]
+fn test_taken_independently_of_earliest_changed_tick() {
+    // Taking the earliest changed tick leaves the changed ticks to be taken,
+    // and vice versa.
+    let mut host = Host::new(2, 50, 5, 60).with_changed_tick_tracking();
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, &[input(7)]));
+    assert_eq!(host.take_earliest_changed_tick(), Some(0));
+    assert_eq!(host.take_changed_ticks_since(0), vec![(PlayerNum(1), 0)]);
+
+    host.rx_guest_input_slice(PlayerNum(1), slice(1, &[input(8)]));
+    assert_eq!(host.take_changed_ticks_since(0), vec![(PlayerNum(1), 1)]);
+    assert_eq!(host.take_earliest_changed_tick(), Some(1));
+}
+
+#[test]
+fn test_changed_ticks_account_for_sim_ticks_per_input() {
+    // With each input covering several sim ticks, each change is reported at
+    // the first sim tick its input covers.
+    let mut host = Host::new(2, 50, 5, 60)
+        .with_sim_ticks_per_input(2)
+        .with_changed_tick_tracking();
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, &[input(7); 3]));
+    host.take_changed_ticks_since(0);
+
+    host.rx_guest_input_slice(PlayerNum(1), slice(3, &[input(8)]));
+
+    assert_eq!(host.take_changed_ticks_since(0), vec![(PlayerNum(1), 6)]);
+}
+
+#[test]
+fn test_new_round_starts_with_nothing_changed() {
+    // Changes from a round that has ended aren't reported in the next one.
+    let mut host = Host::new(2, 50, 5, 60).with_changed_tick_tracking();
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, &[input(7); 3]));
+
+    host.start_new_round();
+
+    assert_eq!(host.take_changed_ticks_since(0), vec![]);
+}
+
+#[test]
+fn test_changes_arent_kept_without_tracking() {
+    // Changed ticks are opt-in: without tracking, a mispredicted input is
+    // still reported as the earliest change, but no (player, tick) entries
+    // are kept.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, &[input(7); 3]));
+
+    assert_eq!(host.take_changed_ticks_since(0), vec![]);
+    assert_eq!(host.take_earliest_changed_tick(), Some(0));
+}
+
+#[test]
+fn test_tracking_carries_into_the_next_round() {
+    // Tracking stays on across rounds, so changes in a new round are
+    // reported.
+    let mut host = Host::new(2, 50, 5, 60).with_changed_tick_tracking();
+    host.start_new_round();
+    host.rx_round_transition_ack(PlayerNum(1), MsgPayload::GuestToHostRoundTransitionAck(1));
+
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, &[input(7)]));
+
+    assert_eq!(host.take_changed_ticks_since(0), vec![(PlayerNum(1), 0)]);
+}
@@ -0,0 +1,164 @@
+use test_case::test_case;
+
+use crate::{
+    clock_sync::{ClockPong, ClockSample},
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+/// A host whose sim clock has run for half a second (30 ticks at 60 ticks per sec).
+fn running_host() -> Host {
+    let mut host = Host::new(2, 50, 5, 60);
+    host.add_host_input_to_fill_needed(PlayerInput::default(), 0.5);
+    host
+}
+
+/// Sends a clock ping from the guest at local time `send_ms`, and delivers the host's pong at `recv_ms`.
+fn clock_exchange(host: &mut Host, guest: &mut Guest, send_ms: f64, recv_ms: f64) -> bool {
+    guest.observe_local_time_ms(send_ms);
+    let pong = host.rx_clock_ping_and_reply(GUEST, guest.get_msg_clock_ping());
+    guest.observe_local_time_ms(recv_ms);
+    guest.rx_clock_pong(pong)
+}
+
+#[test_case(1_000, 5_000, 1_040, 3_980; "host ahead")]
+#[test_case(9_000, 5_000, 9_100, -4_050; "host behind")]
+#[test_case(1_000, 1_000, 1_000, 0; "same clock, no delay")]
+fn test_sample_offset_is_host_time_less_midpoint(send: i64, host: i64, recv: i64, offset: i64) {
+    // As in NTP, the host's reply time is compared with the midpoint of the
+    // guest's send and receive times.
+    let sample = ClockSample {
+        guest_send_micros: send,
+        host_micros: host,
+        guest_recv_micros: recv,
+    };
+
+    assert_eq!(sample.offset_micros(), offset);
+}
+
+#[test]
+fn test_guest_estimates_host_tick_from_clock_pong() {
+    // The guest's clock reads 10s when the host's reads 0.5s (less half the
+    // 40ms round trip), so 100ms later the host is estimated 80ms past 0.5s,
+    // i.e. at tick 34.8.
+    let mut host = running_host();
+    let mut guest = Guest::new(2, GUEST, 60);
+
+    assert!(clock_exchange(&mut host, &mut guest, 10_000.0, 10_040.0));
+    guest.observe_local_time_ms(10_100.0);
+
+    assert_eq!(guest.host_clock_offset_ms(), Some(-9_520.0));
+    assert_eq!(guest.estimated_host_tick_now(), Some(34.8));
+}
+
+#[test_case(0, 91.2; "no skew")]
+#[test_case(-10_000, 91.8; "slow guest")]
+#[test_case(10_000, 90.6; "fast guest")]
+fn test_host_tick_estimate_corrects_reported_skew(skew_ppm: i32, host_tick: f32) {
+    // A second after the clock pong, the host is estimated 1.52s into its sim
+    // clock, adjusted by the skew it has reported for this guest.
+    let mut host = running_host();
+    let mut guest = Guest::new(2, GUEST, 60);
+    assert!(clock_exchange(&mut host, &mut guest, 10_000.0, 10_040.0));
+
+    guest.rx_host_rate_adjust(MsgPayload::HostToGuestRateAdjust(skew_ppm));
+    guest.observe_local_time_ms(11_040.0);
+
+    assert_eq!(guest.estimated_host_tick_now(), Some(host_tick));
+}
+
+#[test]
+fn test_host_replies_only_once_its_clock_runs() {
+    // Before the host collects its first input its sim clock is stopped, so
+    // clock pings get no reply.
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    guest.observe_local_time_ms(1_000.0);
+
+    let reply = host.rx_clock_ping_and_reply(GUEST, guest.get_msg_clock_ping());
+
+    assert!(matches!(reply, MsgPayload::Empty));
+}
+
+#[test]
+fn test_guest_sends_no_clock_ping_without_local_time() {
+    // A ping can only be stamped once the guest has observed its local time.
+    let guest = Guest::new(2, GUEST, 60);
+
+    assert!(matches!(guest.get_msg_clock_ping(), MsgPayload::Empty));
+    assert_eq!(guest.estimated_host_tick_now(), None);
+}
+
+#[test_case(-10.0; "negative round trip")]
+#[test_case(200.0; "round trip much slower than the fastest")]
+fn test_guest_discards_unreliable_clock_pongs(round_trip_ms: f64) {
+    // After a 40ms round trip, pongs that come back before they were sent, or
+    // that took far longer, are discarded and leave the offset unchanged.
+    let mut host = running_host();
+    let mut guest = Guest::new(2, GUEST, 60);
+    clock_exchange(&mut host, &mut guest, 10_000.0, 10_040.0);
+
+    let accepted = clock_exchange(&mut host, &mut guest, 20_000.0, 20_000.0 + round_trip_ms);
+
+    assert!(!accepted);
+    assert_eq!(guest.num_clock_sync_samples(), 1);
+    assert_eq!(guest.host_clock_offset_ms(), Some(-9_520.0));
+}
+
+#[test]
+fn test_pacing_follows_the_clock_estimate() {
+    // With the host tick at 30 and a 40ms RTT, a guest with 30 inputs expects
+    // the host at tick 31.2 and collects one input; once the clock estimate
+    // puts the host at tick 34.8, it collects 4.
+    let mut host = running_host();
+    let mut guest = Guest::new(2, GUEST, 60);
+    guest.test_advance_host_tick(30);
+    guest.observe_rtt_ms_to_host(40.0);
+    for _ in 0..30 {
+        guest.add_own_input(PlayerInput::default());
+    }
+    assert_eq!(guest.num_inputs_needed(), 1);
+
+    clock_exchange(&mut host, &mut guest, 10_000.0, 10_040.0);
+    guest.observe_local_time_ms(10_100.0);
+
+    assert_eq!(guest.num_inputs_needed(), 4);
+}
+
+#[test]
+fn test_clock_offset_restarts_with_a_new_round() {
+    // The host's sim clock restarts each round, so the guest drops its offset.
+    let mut host = running_host();
+    let mut guest = Guest::new(2, GUEST, 60);
+    clock_exchange(&mut host, &mut guest, 10_000.0, 10_040.0);
+
+    guest.start_new_round();
+
+    assert_eq!(guest.host_clock_offset_ms(), None);
+    assert_eq!(guest.num_clock_sync_samples(), 0);
+}
+
+#[test]
+fn test_clock_offset_is_smoothed() {
+    // Later samples move the offset by the configured RTT smoothing (0.1 by
+    // default) rather than replacing it.
+    let mut guest = Guest::new(2, GUEST, 60);
+    for (send_micros, host_micros) in [(0, 1_000_000), (1_000_000, 2_100_000)] {
+        guest.observe_local_time_ms(send_micros as f64 / 1000.0);
+        guest.rx_clock_pong(MsgPayload::HostToGuestClockPong(ClockPong {
+            guest_send_micros: send_micros,
+            host_micros,
+        }));
+    }
+
+    assert_eq!(guest.host_clock_offset_ms(), Some(1_010.0));
+}
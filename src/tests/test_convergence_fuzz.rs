@@ -0,0 +1,310 @@
+//! Property-based tests of input convergence: arbitrary interleavings of slices, acks, losses and duplicates, checking the buffers' invariants after every step.
+
+use proptest::{prelude::*, test_runner::TestCaseError};
+
+use crate::{
+    input_buffer::PlayerInputBuffer,
+    input_trait::SimInput,
+    multiplayer_input_buffer::MultiplayerInputBuffers,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    session::{OutgoingMsgs, Recipient, Session},
+    tests::demo_input_struct::{PlayerInput, PlayerInputBinary},
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+const NUM_PLAYERS: u8 = 3;
+const TICKS_PER_SEC: u32 = 60;
+
+fn config(cases: u32) -> ProptestConfig {
+    ProptestConfig {
+        cases,
+        failure_persistence: None,
+        ..ProptestConfig::default()
+    }
+}
+
+// A single player's buffer //////////////////////////////
+
+/// The input every peer agrees the player made at `index`.
+fn truth(index: u32) -> PlayerInputBinary {
+    PlayerInput::new_test_simple(index as u8).to_bytes()
+}
+
+fn truth_slice(start: u32, len: u32) -> PlayerInputSlice<PlayerInput> {
+    PlayerInputSlice {
+        start,
+        inputs: (start..start + len).map(truth).collect(),
+    }
+}
+
+#[derive(Debug, Clone)]
+enum BufferOp {
+    /// A finalized slice from the host, delivered `copies` times
+    Finalized { start: u32, len: u32, copies: u8 },
+    /// A provisional slice from the player, delivered `copies` times
+    Provisional { start: u32, len: u32, copies: u8 },
+    /// Drops inputs to bound memory
+    DropBefore(u32),
+}
+
+fn buffer_op() -> impl Strategy<Value = BufferOp> {
+    prop_oneof![
+        (0..60u32, 1..12u32, 1..=2u8).prop_map(|(start, len, copies)| BufferOp::Finalized {
+            start,
+            len,
+            copies
+        }),
+        (0..60u32, 1..12u32, 1..=2u8).prop_map(|(start, len, copies)| BufferOp::Provisional {
+            start,
+            len,
+            copies
+        }),
+        (0..60u32).prop_map(BufferOp::DropBefore),
+    ]
+}
+
+proptest! {
+    #![proptest_config(config(256))]
+
+    #[test]
+    fn test_fuzz_player_buffer(ops in prop::collection::vec(buffer_op(), 0..80)) {
+        // Whatever slices arrive, in whatever order and however often, the
+        // buffer stays consistent, its finalized count never decreases, and
+        // every input it holds is the one made at that index.
+        let mut buf = PlayerInputBuffer::<PlayerInput>::default();
+        for op in ops {
+            let finalized_before = buf.finalized_inputs();
+            match op {
+                BufferOp::Finalized { start, len, copies } => {
+                    for _ in 0..copies {
+                        buf.receive_finalized_input_slice(truth_slice(start, len));
+                    }
+                }
+                BufferOp::Provisional { start, len, copies } => {
+                    for _ in 0..copies {
+                        buf.receive_peer_input_slice(truth_slice(start, len));
+                    }
+                }
+                BufferOp::DropBefore(index) => buf.drop_inputs_before(index),
+            }
+
+            prop_assert_eq!(buf.check_invariants(), Ok(()));
+            prop_assert!(buf.finalized_inputs() >= finalized_before);
+            for index in buf.first_retained_input()..buf.num_inputs_collected() {
+                prop_assert_eq!(buf.test_helper_get_input(index as usize), truth(index));
+            }
+        }
+    }
+}
+
+// A whole lobby //////////////////////////////
+
+fn input_for(player_num: PlayerNum, index: u32) -> PlayerInput {
+    PlayerInput::new_test_simple((index as u8).wrapping_mul(3).wrapping_add(player_num.0))
+}
+
+fn buffers(node: &Session<PlayerInput>) -> &MultiplayerInputBuffers<PlayerInput> {
+    match node {
+        Session::Host(host) => &host.buffers,
+        Session::Guest(guest) => &guest.buffers,
+    }
+}
+
+#[derive(Debug, Clone)]
+enum NetOp {
+    /// The player adds an input and sends the resulting messages
+    AddInput(u8),
+    /// The in-flight message at this index (modulo the number in flight) is delivered, and kept in flight again if `duplicate`
+    Deliver { pick: usize, duplicate: bool },
+    /// The in-flight message at this index (modulo the number in flight) is lost
+    Lose(usize),
+}
+
+fn net_op() -> impl Strategy<Value = NetOp> {
+    prop_oneof![
+        3 => (0..NUM_PLAYERS).prop_map(NetOp::AddInput),
+        4 => (any::<usize>(), any::<bool>())
+            .prop_map(|(pick, duplicate)| NetOp::Deliver { pick, duplicate }),
+        1 => any::<usize>().prop_map(NetOp::Lose),
+    ]
+}
+
+/// A host and its guests, with the messages between them in flight until a `NetOp` delivers or loses them.
+struct Lobby {
+    nodes: Vec<Session<PlayerInput>>,
+    in_flight: Vec<(PlayerNum, PlayerNum, Vec<u8>)>,
+}
+
+impl Lobby {
+    fn new() -> Self {
+        let mut nodes: Vec<Session<PlayerInput>> = vec![
+            MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(
+                NUM_PLAYERS,
+                TICKS_PER_SEC,
+                5,
+                TICKS_PER_SEC,
+            )
+            .into(),
+        ];
+        for seat in PlayerNum::iter_guests(NUM_PLAYERS) {
+            nodes.push(
+                MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(
+                    NUM_PLAYERS,
+                    seat,
+                    TICKS_PER_SEC,
+                )
+                .into(),
+            );
+        }
+        Self {
+            nodes,
+            in_flight: vec![],
+        }
+    }
+
+    fn send(&mut self, from: PlayerNum, outgoing: OutgoingMsgs<PlayerInput>) {
+        for (recipient, msg) in outgoing {
+            let bytes = msg.to_bytes();
+            match recipient {
+                Recipient::Player(to) => self.in_flight.push((from, to, bytes)),
+                Recipient::AllPeers => {
+                    for to in PlayerNum::iter(NUM_PLAYERS).filter(|to| *to != from) {
+                        self.in_flight.push((from, to, bytes.clone()));
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_input(&mut self, player: u8) {
+        let player_num = PlayerNum(player);
+        let node = &mut self.nodes[usize::from(player)];
+        let input = input_for(player_num, node.get_own_num_inputs());
+        let outgoing = node.add_own_input(input, 1.0 / TICKS_PER_SEC as f32);
+        self.send(player_num, outgoing);
+    }
+
+    fn deliver(&mut self, index: usize, duplicate: bool) {
+        let (from, to, bytes) = if duplicate {
+            self.in_flight[index].clone()
+        } else {
+            self.in_flight.remove(index)
+        };
+        if let Ok(replies) = self.nodes[usize::from(to.0)].rx_bytes(from, &bytes) {
+            self.send(to, replies);
+        }
+    }
+
+    fn apply(&mut self, op: NetOp) {
+        match op {
+            NetOp::AddInput(player) => self.add_input(player),
+            NetOp::Deliver { pick, duplicate } if !self.in_flight.is_empty() => {
+                self.deliver(pick % self.in_flight.len(), duplicate)
+            }
+            NetOp::Lose(pick) if !self.in_flight.is_empty() => {
+                self.in_flight.remove(pick % self.in_flight.len());
+            }
+            _ => {}
+        }
+    }
+
+    /// Delivers every message in flight, in order, along with their replies.
+    fn flush(&mut self) {
+        while !self.in_flight.is_empty() {
+            self.deliver(0, false);
+        }
+    }
+
+    /// Each node's finalized input count for each player.
+    fn finalized_counts(&self) -> Vec<Vec<u32>> {
+        self.nodes
+            .iter()
+            .map(|node| {
+                PlayerNum::iter(NUM_PLAYERS)
+                    .map(|player_num| buffers(node).get_num_finalized_inputs(player_num))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Checks every node's invariants, that no finalized count went down since `counts_before`, that every guest's finalized inputs match the host's, and that every provisional input a guest holds for the host is the host's input at that index.
+    fn check(&self, counts_before: &[Vec<u32>]) -> Result<(), TestCaseError> {
+        let host = buffers(&self.nodes[0]);
+        for (node, (counts, before)) in self
+            .nodes
+            .iter()
+            .zip(self.finalized_counts().iter().zip(counts_before))
+        {
+            let bufs = buffers(node);
+            prop_assert_eq!(bufs.check_invariants(), Ok(()));
+            for (count, before) in counts.iter().zip(before) {
+                prop_assert!(count >= before);
+            }
+            for (player, (buf, host_buf)) in bufs.buffers.iter().zip(&host.buffers).enumerate() {
+                let common = buf.finalized_inputs().min(host_buf.finalized_inputs());
+                for index in 0..common as usize {
+                    prop_assert_eq!(
+                        buf.test_helper_get_input(index),
+                        host_buf.test_helper_get_input(index),
+                        "player {} input {} differs from the host's",
+                        player,
+                        index
+                    );
+                }
+            }
+            let (guest_view, host_own) = (
+                &bufs.buffers[usize::from(HOST_PLAYER_NUM.0)],
+                &host.buffers[usize::from(HOST_PLAYER_NUM.0)],
+            );
+            let provisional = guest_view.finalized_inputs()
+                ..guest_view
+                    .num_inputs_collected()
+                    .min(host_own.num_inputs_collected());
+            for index in provisional {
+                prop_assert_eq!(
+                    guest_view.test_helper_get_input(index as usize),
+                    host_own.test_helper_get_input(index as usize),
+                    "provisional host input {} is misplaced",
+                    index
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+proptest! {
+    #![proptest_config(config(64))]
+
+    #[test]
+    fn test_fuzz_lobby_converges(ops in prop::collection::vec(net_op(), 0..200)) {
+        // Whatever order messages are delivered in, however many are lost or
+        // duplicated, every node stays consistent and agrees with the host on
+        // what is finalized; once the network heals, every node converges to
+        // the same finalized inputs.
+        let mut lobby = Lobby::new();
+        for op in ops {
+            let counts_before = lobby.finalized_counts();
+            lobby.apply(op);
+            lobby.check(&counts_before)?;
+        }
+
+        for _ in 0..10 {
+            for player in 0..NUM_PLAYERS {
+                lobby.add_input(player);
+            }
+            lobby.flush();
+        }
+        let counts_before = lobby.finalized_counts();
+        lobby.flush();
+        lobby.check(&counts_before)?;
+
+        let counts = lobby.finalized_counts();
+        for node_counts in &counts[1..] {
+            prop_assert_eq!(node_counts, &counts[0]);
+        }
+        prop_assert!(counts[0].iter().all(|count| *count > 0), "{:?}", counts);
+    }
+}
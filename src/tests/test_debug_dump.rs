@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use crate::{
+    debug_dump::{DebugDump, DumpRole},
+    input_buffer::InputStatus,
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+fn host_with_some_state() -> MultiplayerInputManager<PlayerInput, HostInputMgr> {
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(3, 5, 5, 60);
+    for _ in 0..20 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host.rx_guest_input_slice(
+        PlayerNum(1),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 12)),
+    );
+    host.rx_finalized_ticks_observations(
+        PlayerNum(2),
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
+            HashMap::from([(PlayerNum(0), 7), (PlayerNum(1), 3), (PlayerNum(2), 0)]),
+        )),
+    );
+    host
+}
+
+#[test]
+fn test_host_dump_round_trips_through_text() {
+    // The text form of a host dump should parse back into an identical DebugDump,
+    // including the observation matrix.
+    let host = host_with_some_state();
+    let dump = host.debug_dump();
+    let parsed: DebugDump = host.dump_debug_text().parse().unwrap();
+    assert_eq!(parsed, dump);
+}
+
+#[test]
+fn test_host_dump_contents() {
+    // The dump window should end at the longest buffer, and each player's
+    // statuses and the observation matrix should reflect the host's state.
+    let dump = host_with_some_state().debug_dump();
+
+    assert_eq!(dump.role, DumpRole::Host);
+    assert_eq!(dump.window_start, 4);
+    assert_eq!(dump.snapshottable_tick, 0);
+
+    let guest_1 = &dump.players[1];
+    assert_eq!((guest_1.finalized, guest_1.total), (12, 12));
+    let mut expected = vec![InputStatus::Finalized { num_finalized: 12 }; 8];
+    let predicted = |predicted| InputStatus::NotReceived {
+        num_finalized: 12,
+        predicted,
+    };
+    expected.extend([predicted(true); 5]);
+    expected.extend([predicted(false); 3]);
+    assert_eq!(guest_1.recent, expected);
+
+    assert_eq!(
+        dump.observations[1],
+        (
+            PlayerNum(2),
+            vec![(PlayerNum(0), 7), (PlayerNum(1), 3), (PlayerNum(2), 0)]
+        )
+    );
+}
+
+#[test]
+fn test_guest_dump_text_format() {
+    // A guest dump renders finalized, non-final, predicted, and missing ticks
+    // with F/N/~/- symbols, and has no observation lines.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, 1.into(), 60);
+    for _ in 0..4 {
+        guest.add_own_input(PlayerInput::default());
+    }
+    guest.rx_final_peer_input_slice_from_host(MsgPayload::HostToLobbyFinalizedSlice(
+        HostFinalizedSlice::new_test(1.into(), 0, 0, 2),
+    ));
+
+    let expected = "temporal_input_buffer dump v2\n\
+        role: guest; own_player: 1; snapshottable: 0; window_start: 0\n\
+        player 0: finalized 0; total 0; recent ----------------\n\
+        player 1: finalized 2; total 4; recent FFNN~~~~~-------\n";
+    assert_eq!(guest.dump_debug_text(), expected);
+}
+
+#[test]
+fn test_dump_reconstructs_buffer_counts() {
+    // Buffers rebuilt from a parsed dump should have the same status-level state
+    // as the buffers the dump was taken from.
+    let host = host_with_some_state();
+    let dump: DebugDump = host.dump_debug_text().parse().unwrap();
+    let rebuilt = dump.to_buffers::<PlayerInput>(5);
+
+    for player in &dump.players {
+        assert_eq!(
+            rebuilt.get_num_finalized_inputs(player.player_num),
+            host.get_peer_num_final_inputs(player.player_num)
+        );
+        assert_eq!(
+            rebuilt.get_num_inputs(player.player_num),
+            host.get_peer_num_inputs(player.player_num)
+        );
+    }
+}
+
+#[test]
+fn test_parse_rejects_malformed_dumps() {
+    // Missing headers and unknown status symbols are reported as errors.
+    assert!(
+        "player 0: finalized 0; total 0; recent ----"
+            .parse::<DebugDump>()
+            .is_err()
+    );
+
+    let bad_symbol = "temporal_input_buffer dump v1\n\
+        role: host; own_player: 0; snapshottable: 0; window_start: 0\n\
+        player 0: finalized 0; total 0; recent --X-\n";
+    assert!(bad_symbol.parse::<DebugDump>().is_err());
+}
+
+#[test]
+fn test_parse_accepts_v1_dumps() {
+    // v1 dumps, from before the `~` symbol, still parse, since they only use
+    // symbols v2 also has.
+    let v1 = "temporal_input_buffer dump v1\n\
+        role: guest; own_player: 1; snapshottable: 0; window_start: 0\n\
+        player 0: finalized 0; total 0; recent ----\n\
+        player 1: finalized 2; total 4; recent FFNN\n";
+
+    let dump: DebugDump = v1.parse().unwrap();
+
+    assert_eq!(
+        dump.players[1].recent[2],
+        InputStatus::NonFinal { num_finalized: 2 }
+    );
+}
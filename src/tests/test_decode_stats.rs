@@ -0,0 +1,119 @@
+use crate::{
+    decode_stats::MALFORMED_QUARANTINE_LEN,
+    events::InputMgrEvent,
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+fn new_host() -> MultiplayerInputManager<PlayerInput, HostInputMgr> {
+    MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(3, 5, 5, 60)
+}
+
+#[test]
+fn test_decoded_msgs_are_counted_by_variant() {
+    // Successfully decoded messages are counted under their variant number,
+    // and are returned unchanged.
+    let mut host = new_host();
+    let slice = MsgPayload::PeerInputs(PlayerInputSlice::<PlayerInput>::new_test(0, 4));
+    let bytes = slice.to_bytes();
+
+    let decoded = host.decode_msg_from_peer(PlayerNum(1), &bytes);
+    assert!(matches!(decoded, Some(MsgPayload::PeerInputs(_))));
+    host.decode_msg_from_peer(PlayerNum(2), &bytes);
+
+    assert_eq!(host.num_decoded_msgs(4), 2);
+    assert_eq!(host.num_malformed_msgs_for_variant(4), 0);
+    assert_eq!(host.malformed_msgs().count(), 0);
+}
+
+#[test]
+fn test_malformed_msgs_are_counted_and_quarantined() {
+    // Malformed messages are counted per peer and per header variant, and
+    // their raw bytes are kept for inspection.
+    let mut host = new_host();
+    let truncated = [4u8, 0xff];
+    let unknown = [0x7fu8];
+
+    assert!(
+        host.decode_msg_from_peer(PlayerNum(1), &truncated)
+            .is_none()
+    );
+    assert!(host.decode_msg_from_peer(PlayerNum(1), &unknown).is_none());
+    assert!(
+        host.decode_msg_from_peer(PlayerNum(2), &truncated)
+            .is_none()
+    );
+
+    assert_eq!(host.num_malformed_msgs_from_peer(PlayerNum(1)), 2);
+    assert_eq!(host.num_malformed_msgs_from_peer(PlayerNum(2)), 1);
+    assert_eq!(host.num_malformed_msgs_for_variant(4), 2);
+    assert_eq!(host.num_malformed_msgs_for_variant(0x7f), 1);
+    assert_eq!(host.num_decoded_msgs(4), 0);
+
+    let quarantined: Vec<_> = host.malformed_msgs().collect();
+    assert_eq!(quarantined.len(), 3);
+    assert_eq!(quarantined[1].player_num, PlayerNum(1));
+    assert_eq!(quarantined[1].variant_num, Some(0x7f));
+    assert_eq!(quarantined[1].bytes, unknown.to_vec());
+}
+
+#[test]
+fn test_quarantine_is_bounded() {
+    // Only the most recent malformed messages are kept.
+    let mut host = new_host();
+    for i in 0..(MALFORMED_QUARANTINE_LEN as u8 + 3) {
+        host.decode_msg_from_peer(PlayerNum(1), &[0x7f, i]);
+    }
+    let quarantined: Vec<_> = host.malformed_msgs().collect();
+    assert_eq!(quarantined.len(), MALFORMED_QUARANTINE_LEN);
+    assert_eq!(quarantined[0].bytes, vec![0x7f, 3]);
+}
+
+#[test]
+fn test_threshold_event_raised_once_per_peer() {
+    // Reaching the malformed message threshold raises a single event for that
+    // peer; further malformed messages don't raise it again.
+    let mut host = new_host().with_malformed_msg_threshold(2);
+
+    host.decode_msg_from_peer(PlayerNum(1), &[0x7f]);
+    assert!(host.drain_events().is_empty());
+
+    host.decode_msg_from_peer(PlayerNum(1), &[0x7f]);
+    host.decode_msg_from_peer(PlayerNum(1), &[0x7f]);
+    host.decode_msg_from_peer(PlayerNum(2), &[0x7f]);
+    assert_eq!(
+        host.drain_events(),
+        vec![InputMgrEvent::MalformedMsgThresholdExceeded {
+            player_num: PlayerNum(1),
+            num_malformed: 2
+        }]
+    );
+    assert!(host.drain_events().is_empty());
+}
+
+#[test]
+fn test_full_event_queue_drops_oldest() {
+    // With room for 2 undrained events, the third peer to reach the threshold
+    // pushes out the first peer's event, which is counted as dropped.
+    let mut host = new_host()
+        .with_malformed_msg_threshold(1)
+        .with_max_queued_events(2);
+
+    for player_num in 1..=3 {
+        host.decode_msg_from_peer(PlayerNum(player_num), &[0x7f]);
+    }
+
+    let peers: Vec<_> = host
+        .drain_events()
+        .into_iter()
+        .map(|event| match event {
+            InputMgrEvent::MalformedMsgThresholdExceeded { player_num, .. } => player_num,
+            other => panic!("unexpected event {other:?}"),
+        })
+        .collect();
+    assert_eq!(peers, vec![PlayerNum(2), PlayerNum(3)]);
+    assert_eq!(host.num_dropped_events(), 1);
+}
@@ -0,0 +1,191 @@
+use test_case::test_case;
+
+use crate::{
+    desync_report::{InputHashReport, MAX_INPUT_HASH_REPORT_TICKS},
+    events::InputMgrEvent,
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    session::{Session, SessionRxError},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+/// A 2 player host and guest which have both finalized `num_inputs` inputs for each player.
+fn host_and_guest(num_inputs: u32) -> (Host, Guest) {
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    finalize_more_inputs(&mut host, &mut guest, num_inputs);
+    (host, guest)
+}
+
+/// Collects `num_inputs` more inputs on each side and finalizes them on both.
+fn finalize_more_inputs(host: &mut Host, guest: &mut Guest, num_inputs: u32) {
+    for x in 0..num_inputs {
+        host.add_host_input_directly(PlayerInput::new_test_simple((x % 100) as u8));
+        guest.add_own_input(PlayerInput::new_test_simple((x % 100) as u8 + 1));
+    }
+    host.rx_guest_input_slice(GUEST, guest.get_msg_own_input_slice());
+    for player in [PlayerNum(0), GUEST] {
+        guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(player));
+    }
+}
+
+fn hash_report(msg: MsgPayload<PlayerInput>) -> InputHashReport {
+    match msg {
+        MsgPayload::GuestToHostInputHashReport(report) => report,
+        other => panic!("expected an input hash report, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_identical_inputs_are_synced() {
+    // A guest that finalized the same inputs as the host reports matching
+    // hashes for every finalized tick, so no desync is found or raised.
+    let (mut host, mut guest) = host_and_guest(6);
+    let report = hash_report(guest.get_msg_input_hash_report());
+    assert_eq!(report.start_tick, 0);
+    assert_eq!(report.end_tick(), guest.get_snapshottable_sim_tick());
+    assert_eq!(
+        host.rx_input_hash_report(GUEST, MsgPayload::GuestToHostInputHashReport(report)),
+        Vec::<u32>::new()
+    );
+    assert!(host.get_desync_report().is_synced());
+    assert_eq!(host.drain_events(), vec![]);
+}
+
+#[test]
+fn test_reports_cover_each_tick_once() {
+    // Each report starts where the last one ended, and there is nothing to
+    // report until more ticks are finalized.
+    let (mut host, mut guest) = host_and_guest(6);
+    let first = hash_report(guest.get_msg_input_hash_report());
+    assert!(matches!(
+        guest.get_msg_input_hash_report(),
+        MsgPayload::Empty
+    ));
+    finalize_more_inputs(&mut host, &mut guest, 3);
+    let second = hash_report(guest.get_msg_input_hash_report());
+    assert_eq!(second.start_tick, first.end_tick());
+    assert_eq!(second.end_tick(), guest.get_snapshottable_sim_tick());
+    assert_eq!(second.hashes.len(), 3);
+    assert_eq!(
+        host.rx_input_hash_report(GUEST, MsgPayload::GuestToHostInputHashReport(second)),
+        Vec::<u32>::new()
+    );
+}
+
+#[test]
+fn test_reports_are_capped() {
+    // A guest with more finalized ticks than fit in one report sends the rest
+    // in its next report.
+    let (_, mut guest) = host_and_guest(MAX_INPUT_HASH_REPORT_TICKS + 40);
+    let first = hash_report(guest.get_msg_input_hash_report());
+    assert_eq!(first.hashes.len() as u32, MAX_INPUT_HASH_REPORT_TICKS);
+    let second = hash_report(guest.get_msg_input_hash_report());
+    assert_eq!(second.start_tick, first.end_tick());
+    assert_eq!(second.end_tick(), guest.get_snapshottable_sim_tick());
+}
+
+#[test]
+fn test_mismatched_hashes_are_reported() {
+    // Ticks whose hash disagrees with the host's are listed in the desync
+    // report, and the first one found raises a single event.
+    let (mut host, mut guest) = host_and_guest(6);
+    let mut report = hash_report(guest.get_msg_input_hash_report());
+    report.hashes[2] ^= 1;
+    report.hashes[4] ^= 1;
+    assert_eq!(
+        host.rx_input_hash_report(GUEST, MsgPayload::GuestToHostInputHashReport(report)),
+        vec![2, 4]
+    );
+    let desyncs = host.get_desync_report();
+    assert!(!desyncs.is_synced());
+    assert_eq!(desyncs.desynced_ticks(GUEST), &[2, 4]);
+    assert_eq!(desyncs.first_desync_tick(GUEST), Some(2));
+    assert_eq!(desyncs.desynced_peers().collect::<Vec<_>>(), vec![GUEST]);
+    assert_eq!(
+        host.drain_events(),
+        vec![InputMgrEvent::InputDesync {
+            peer: GUEST,
+            tick: 2,
+        }]
+    );
+}
+
+#[test_case(0, 3; "from the start of the round")]
+#[test_case(3, 10; "reaching past the host's finalized ticks")]
+#[test_case(50, 5; "entirely past the host's finalized ticks")]
+fn test_only_finalized_ticks_are_checked(start_tick: u32, num_ticks: u32) {
+    // Hashes for ticks the host hasn't finalized for all players can't be
+    // checked, so they are never reported as desynced.
+    let (mut host, _) = host_and_guest(6);
+    let snapshottable_tick = host.get_snapshottable_sim_tick();
+    let report = InputHashReport {
+        start_tick,
+        hashes: vec![0; num_ticks as usize],
+    };
+    let expected: Vec<u32> = (start_tick..start_tick + num_ticks)
+        .filter(|tick| *tick < snapshottable_tick)
+        .collect();
+    assert_eq!(
+        host.rx_input_hash_report(GUEST, MsgPayload::GuestToHostInputHashReport(report)),
+        expected
+    );
+}
+
+#[test]
+fn test_desyncs_are_forgotten_at_new_round() {
+    // Ticks restart at a new round, so the previous round's desyncs are
+    // cleared, and reports from a guest that hasn't acked the round are
+    // ignored.
+    let (mut host, mut guest) = host_and_guest(6);
+    let mut report = hash_report(guest.get_msg_input_hash_report());
+    report.hashes[0] ^= 1;
+    let msg = MsgPayload::GuestToHostInputHashReport(report);
+    host.rx_input_hash_report(GUEST, msg.clone());
+    host.start_new_round();
+    assert!(host.get_desync_report().is_synced());
+    assert_eq!(host.rx_input_hash_report(GUEST, msg), Vec::<u32>::new());
+}
+
+#[test]
+fn test_seat_transfer_forgets_desyncs() {
+    // A seat moved to a new connection starts with no desyncs.
+    let (mut host, mut guest) = host_and_guest(6);
+    let mut report = hash_report(guest.get_msg_input_hash_report());
+    report.hashes[1] ^= 1;
+    host.rx_input_hash_report(GUEST, MsgPayload::GuestToHostInputHashReport(report));
+    host.transfer_seat(GUEST, PlayerNum(5)).unwrap();
+    assert!(host.get_desync_report().is_synced());
+}
+
+#[test]
+fn test_session_routes_reports_to_host() {
+    // A session passes a guest's report to the host's check, while a guest
+    // session rejects it.
+    let (host, mut guest) = host_and_guest(6);
+    let mut report = hash_report(guest.get_msg_input_hash_report());
+    report.hashes[3] ^= 1;
+    let msg = MsgPayload::GuestToHostInputHashReport(report);
+    let mut host = Session::from(host);
+    assert!(host.rx_msg(GUEST, msg.clone()).unwrap().is_empty());
+    assert_eq!(
+        host.as_host()
+            .unwrap()
+            .get_desync_report()
+            .first_desync_tick(GUEST),
+        Some(3)
+    );
+    let mut guest = Session::from(guest);
+    assert_eq!(
+        guest.rx_msg(PlayerNum(0), msg).err(),
+        Some(SessionRxError::WrongRole)
+    );
+}
@@ -0,0 +1,133 @@
+use test_case::test_case;
+
+use crate::{
+    determinism_probe::{DeterminismCheck, DeterminismSample, DivergenceKind},
+    events::InputMgrEvent,
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+/// A 2 player host and guest which have both finalized 6 inputs for each player.
+fn host_and_guest() -> (Host, Guest) {
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, 1.into(), 60);
+    for x in 0..6 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(x));
+        guest.add_own_input(PlayerInput::new_test_simple(x + 1));
+    }
+    host.rx_guest_input_slice(PlayerNum(1), guest.get_msg_own_input_slice());
+    for player in [PlayerNum(0), PlayerNum(1)] {
+        guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(player));
+    }
+    (host, guest)
+}
+
+#[test]
+fn test_identical_samples_match() {
+    // With the same inputs and the same state digests, every compared tick
+    // matches and no event is raised.
+    let (mut host, mut guest) = host_and_guest();
+    for tick in 0..6 {
+        host.record_state_digest(tick, 100 + tick as u64).unwrap();
+        guest.record_state_digest(tick, 100 + tick as u64).unwrap();
+        let msg = guest.get_msg_determinism_sample(tick);
+        assert_eq!(
+            host.rx_determinism_sample(PlayerNum(1), msg),
+            DeterminismCheck::Match
+        );
+    }
+    let report = host.determinism_report(PlayerNum(1));
+    assert_eq!(report.num_ticks_compared, 6);
+    assert!(report.is_consistent());
+    assert_eq!(host.drain_events(), vec![]);
+}
+
+#[test]
+fn test_state_divergence_with_identical_inputs() {
+    // Differing state digests with matching input digests are reported as a
+    // state divergence, pointing at the simulation rather than the inputs.
+    let (mut host, mut guest) = host_and_guest();
+    for tick in 0..6 {
+        host.record_state_digest(tick, 7).unwrap();
+        let state_digest = if tick < 3 { 7 } else { 8 };
+        guest.record_state_digest(tick, state_digest).unwrap();
+        host.rx_determinism_sample(PlayerNum(1), guest.get_msg_determinism_sample(tick));
+    }
+    let report = host.determinism_report(PlayerNum(1));
+    assert_eq!(report.num_matching, 3);
+    assert_eq!(report.first_input_divergence, None);
+    assert_eq!(report.first_state_divergence, Some(3));
+    assert_eq!(
+        host.drain_events(),
+        vec![InputMgrEvent::DeterminismDivergence {
+            peer: PlayerNum(1),
+            tick: 3,
+            kind: DivergenceKind::State,
+        }]
+    );
+}
+
+#[test]
+fn test_input_divergence_is_distinguished() {
+    // A peer whose inputs for a tick differ is reported as an input
+    // divergence, even if its state digest happens to match.
+    let (mut host, mut guest) = host_and_guest();
+    host.record_state_digest(2, 7).unwrap();
+    guest.record_state_digest(2, 7).unwrap();
+    let MsgPayload::PeerDeterminismSample(sample) = guest.get_msg_determinism_sample(2) else {
+        panic!("expected a sample");
+    };
+    let tampered = DeterminismSample {
+        input_digest: sample.input_digest ^ 1,
+        ..sample
+    };
+    assert_eq!(
+        host.rx_determinism_sample(PlayerNum(1), MsgPayload::PeerDeterminismSample(tampered)),
+        DeterminismCheck::Diverged(DivergenceKind::Inputs)
+    );
+    assert_eq!(
+        host.determinism_report(PlayerNum(1)).first_input_divergence,
+        Some(2)
+    );
+}
+
+#[test]
+fn test_peer_sample_before_own_is_checked_when_recorded() {
+    // A peer's sample that arrives before this node's own can't be checked
+    // yet, but is checked once this node records its sample for the tick.
+    let (mut host, mut guest) = host_and_guest();
+    guest.record_state_digest(4, 1).unwrap();
+    assert_eq!(
+        host.rx_determinism_sample(PlayerNum(1), guest.get_msg_determinism_sample(4)),
+        DeterminismCheck::Unknown
+    );
+    host.record_state_digest(4, 2).unwrap();
+    assert_eq!(
+        host.drain_events(),
+        vec![InputMgrEvent::DeterminismDivergence {
+            peer: PlayerNum(1),
+            tick: 4,
+            kind: DivergenceKind::State,
+        }]
+    );
+}
+
+#[test_case(6; "not yet finalized")]
+#[test_case(100; "far future")]
+fn test_state_digest_requires_finalized_inputs(tick: u32) {
+    // A state digest can only be recorded for ticks whose inputs are
+    // finalized for all players, and no sample is sent for it.
+    let (mut host, _) = host_and_guest();
+    assert!(host.record_state_digest(tick, 1).is_err());
+    assert!(matches!(
+        host.get_msg_determinism_sample(tick),
+        MsgPayload::Empty
+    ));
+}
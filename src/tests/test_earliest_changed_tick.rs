@@ -0,0 +1,119 @@
+use crate::{
+    input_messages::MsgPayload,
+    input_trait::SimInput,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+
+fn input(x: u8) -> PlayerInput {
+    PlayerInput::new_test_simple(x)
+}
+
+fn slice(start: u32, inputs: &[PlayerInput]) -> MsgPayload<PlayerInput> {
+    MsgPayload::PeerInputs(PlayerInputSlice {
+        start,
+        inputs: inputs.iter().map(SimInput::to_bytes).collect(),
+    })
+}
+
+#[test]
+fn test_nothing_changed_on_a_new_manager() {
+    // A manager that has collected and received nothing reports no change.
+    let mut host = Host::new(2, 50, 5, 60);
+
+    assert_eq!(host.take_earliest_changed_tick(), None);
+}
+
+#[test]
+fn test_mispredicted_peer_input_marks_its_tick() {
+    // A peer's input that differs from the prediction (a repeat of its last
+    // input) marks its tick, but the inputs before it that matched their
+    // predictions don't.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, &[input(7); 3]));
+    assert_eq!(host.take_earliest_changed_tick(), Some(0));
+
+    host.rx_guest_input_slice(PlayerNum(1), slice(3, &[input(7), input(8)]));
+
+    assert_eq!(host.take_earliest_changed_tick(), Some(4));
+}
+
+#[test]
+fn test_input_matching_prediction_marks_nothing() {
+    // Inputs equal to what was predicted for them need no rollback, so
+    // nothing is marked.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, &[input(7)]));
+    host.take_earliest_changed_tick();
+
+    host.rx_guest_input_slice(PlayerNum(1), slice(1, &[input(7); 2]));
+
+    assert_eq!(host.take_earliest_changed_tick(), None);
+}
+
+#[test]
+fn test_take_clears_the_mark() {
+    // A change is only reported once.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, &[input(7); 3]));
+
+    assert_eq!(host.take_earliest_changed_tick(), Some(0));
+    assert_eq!(host.take_earliest_changed_tick(), None);
+}
+
+#[test]
+fn test_earliest_change_across_players_is_kept() {
+    // With changes for several players between calls, the earliest tick
+    // changed for any of them is reported.
+    let mut host = Host::new(3, 50, 5, 60);
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, &[input(7); 5]));
+    host.take_earliest_changed_tick();
+
+    host.rx_guest_input_slice(PlayerNum(1), slice(5, &[input(8)]));
+    host.rx_guest_input_slice(PlayerNum(2), slice(0, &[input(9)]));
+
+    assert_eq!(host.take_earliest_changed_tick(), Some(0));
+}
+
+#[test]
+fn test_own_input_marks_its_tick() {
+    // Collecting an own input that differs from the prediction marks its
+    // tick too.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.add_host_input_directly(input(7));
+    host.take_earliest_changed_tick();
+    host.add_host_input_directly(input(7));
+    assert_eq!(host.take_earliest_changed_tick(), None);
+
+    host.add_host_input_directly(input(8));
+
+    assert_eq!(host.take_earliest_changed_tick(), Some(2));
+}
+
+#[test]
+fn test_changed_tick_accounts_for_sim_ticks_per_input() {
+    // With each input covering several sim ticks, the tick reported is the
+    // first sim tick the changed input covers.
+    let mut host = Host::new(2, 50, 5, 60).with_sim_ticks_per_input(2);
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, &[input(7); 3]));
+    host.take_earliest_changed_tick();
+
+    host.rx_guest_input_slice(PlayerNum(1), slice(3, &[input(8)]));
+
+    assert_eq!(host.take_earliest_changed_tick(), Some(6));
+}
+
+#[test]
+fn test_new_round_starts_with_nothing_changed() {
+    // Changes from a round that has ended aren't reported in the next one.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, &[input(7); 3]));
+
+    host.start_new_round();
+
+    assert_eq!(host.take_earliest_changed_tick(), None);
+}
@@ -0,0 +1,211 @@
+use test_case::test_case;
+
+use crate::{
+    events::InputMgrEvent,
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    session::{OutgoingMsgs, Recipient, Session},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+fn session_ended_events(events: Vec<InputMgrEvent>) -> Vec<u32> {
+    events
+        .into_iter()
+        .filter_map(|event| match event {
+            InputMgrEvent::SessionEnded { end_tick } => Some(end_tick),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A 2 player host with `num_host_inputs` of its own inputs and `num_guest_inputs` from the guest.
+fn host_with_inputs(num_host_inputs: u32, num_guest_inputs: u32) -> Host {
+    let mut host = Host::new(2, 50, 5, 60);
+    for _ in 0..num_host_inputs {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host.rx_guest_input_slice(
+        GUEST,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, num_guest_inputs)),
+    );
+    host
+}
+
+#[test]
+fn test_host_finalizes_every_player_to_the_final_tick() {
+    // Once the host's own inputs reach the final tick, it stops collecting,
+    // finalizes the guest up to exactly that tick with defaults for the
+    // inputs the guest never sent, and raises a single SessionEnded event.
+    let mut host = host_with_inputs(4, 3);
+    assert_eq!(host.end_session_at(6), 6);
+    assert!(host.is_session_end_requested());
+    assert!(!host.is_session_ended());
+    assert!(session_ended_events(host.drain_events()).is_empty());
+
+    for _ in 0..4 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+
+    assert_eq!(host.get_own_num_inputs(), 6);
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 6);
+    assert_eq!(host.get_snapshottable_sim_tick(), 6);
+    assert_eq!(session_ended_events(host.drain_events()), vec![6]);
+}
+
+#[test]
+fn test_host_drops_guest_inputs_past_the_final_tick() {
+    // Guest inputs at or past the final tick are dropped.
+    let mut host = host_with_inputs(4, 3);
+    host.end_session_at(5);
+    host.rx_guest_input_slice(
+        GUEST,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(3, 4)),
+    );
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 5);
+}
+
+#[test]
+fn test_final_tick_cant_precede_collected_inputs() {
+    // A final tick before inputs the host already has is raised to the
+    // earliest possible one, and the session ends right away; later calls
+    // don't move the end.
+    let mut host = host_with_inputs(4, 7);
+    assert_eq!(host.end_session_at(2), 7);
+    assert_eq!(host.end_session_at(20), 7);
+
+    let mut host = host_with_inputs(4, 2);
+    assert_eq!(host.end_session_at(2), 4);
+    assert!(host.is_session_ended());
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 4);
+    assert_eq!(session_ended_events(host.drain_events()), vec![4]);
+}
+
+#[test]
+fn test_final_tick_cant_pass_the_session_limit() {
+    // The end can't be moved past the configured session length.
+    let mut host = Host::new(2, 50, 5, 60).with_max_session_ticks(10);
+    assert_eq!(host.end_session_at(30), 10);
+}
+
+#[test]
+fn test_host_resends_end_until_every_guest_acks() {
+    // The end is sent until every guest has acked it; acks for another final
+    // tick don't count.
+    let mut host = Host::new(3, 50, 5, 60);
+    assert!(matches!(host.get_msg_end_session(), MsgPayload::Empty));
+    host.end_session_at(30);
+
+    host.rx_guest_end_ack(PlayerNum(1), MsgPayload::GuestToHostEndAck(30));
+    host.rx_guest_end_ack(PlayerNum(2), MsgPayload::GuestToHostEndAck(29));
+    assert!(!host.all_guests_acked_end());
+    assert!(matches!(
+        host.get_msg_end_session(),
+        MsgPayload::HostToLobbyEndSession(30)
+    ));
+
+    host.rx_guest_end_ack(PlayerNum(2), MsgPayload::GuestToHostEndAck(30));
+    assert!(host.all_guests_acked_end());
+    assert!(matches!(host.get_msg_end_session(), MsgPayload::Empty));
+}
+
+#[test_case(false; "disconnected guest")]
+#[test_case(true; "removed guest")]
+fn test_host_doesnt_wait_on_end_ack_from_departed_guest(removed: bool) {
+    // A guest that disconnects or is removed before acking the end isn't
+    // waited for once the remaining guest has acked.
+    let mut host = Host::new(3, 50, 5, 60);
+    host.end_session_at(30);
+    host.rx_guest_end_ack(PlayerNum(1), MsgPayload::GuestToHostEndAck(30));
+
+    if removed {
+        host.remove_player(PlayerNum(2)).unwrap();
+    } else {
+        host.player_disconnected(PlayerNum(2));
+    }
+
+    assert!(host.all_guests_acked_end());
+    assert!(matches!(host.get_msg_end_session(), MsgPayload::Empty));
+}
+
+#[test]
+fn test_guest_adopts_the_first_end_it_receives() {
+    // A guest stops collecting own inputs at the final tick, and ignores
+    // resent ends.
+    let mut guest = Guest::new(2, GUEST, 60);
+    assert!(matches!(guest.get_msg_end_ack(), MsgPayload::Empty));
+    assert!(guest.rx_end_session(MsgPayload::HostToLobbyEndSession(3)));
+    assert!(!guest.rx_end_session(MsgPayload::HostToLobbyEndSession(8)));
+    assert!(matches!(
+        guest.get_msg_end_ack(),
+        MsgPayload::GuestToHostEndAck(3)
+    ));
+
+    for _ in 0..5 {
+        guest.add_own_input(PlayerInput::default());
+    }
+    assert_eq!(guest.get_own_num_inputs(), 3);
+    assert_eq!(guest.num_inputs_needed(), 0);
+}
+
+/// Delivers each message to `to` as bytes, returning its replies.
+fn deliver(
+    from: PlayerNum,
+    to: &mut Session<PlayerInput>,
+    outgoing: OutgoingMsgs<PlayerInput>,
+) -> OutgoingMsgs<PlayerInput> {
+    let mut replies = Vec::new();
+    for (_, msg) in outgoing {
+        replies.extend(to.rx_bytes(from, &msg.to_bytes()).unwrap());
+    }
+    replies
+}
+
+#[test]
+fn test_end_session_handshake_via_sessions() {
+    // Through `Session`s, the guest acks the end as soon as it arrives, but
+    // only raises SessionEnded once the host's finalized slices have brought
+    // every player up to the final tick; the host stops sending the end once
+    // it is acked.
+    let mut host: Session<PlayerInput> = Host::new(2, 50, 5, 10).into();
+    let mut guest: Session<PlayerInput> = Guest::new(2, GUEST, 10).into();
+    for _ in 0..2 {
+        let outgoing = guest.add_own_input(PlayerInput::default(), 0.1);
+        deliver(GUEST, &mut host, outgoing);
+    }
+    host.as_host_mut().unwrap().end_session_at(4);
+
+    let outgoing = host.add_own_input(PlayerInput::default(), 0.1);
+    assert!(
+        outgoing
+            .iter()
+            .any(|(_, msg)| matches!(msg, MsgPayload::HostToLobbyEndSession(4)))
+    );
+    let acks = deliver(HOST_PLAYER_NUM, &mut guest, outgoing);
+    assert!(guest.as_guest().unwrap().is_session_end_requested());
+    assert!(session_ended_events(guest.drain_events()).is_empty());
+    deliver(GUEST, &mut host, acks);
+    assert!(host.as_host().unwrap().all_guests_acked_end());
+
+    // the host reaches the final tick and fills in the guest's missing inputs
+    let mut outgoing = host.add_own_input(PlayerInput::default(), 1.0);
+    assert!(
+        !outgoing
+            .iter()
+            .any(|(_, msg)| matches!(msg, MsgPayload::HostToLobbyEndSession(_)))
+    );
+    assert_eq!(session_ended_events(host.drain_events()), vec![4]);
+    let finalized_guest_slice = host.as_host().unwrap().get_msg_finalized_slice(GUEST);
+    outgoing.push((Recipient::AllPeers, finalized_guest_slice));
+    deliver(HOST_PLAYER_NUM, &mut guest, outgoing);
+
+    assert_eq!(guest.get_snapshottable_sim_tick(), 4);
+    assert_eq!(session_ended_events(guest.drain_events()), vec![4]);
+}
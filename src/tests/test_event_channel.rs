@@ -0,0 +1,227 @@
+use crate::{
+    event_channel::{EventSlice, MAX_PENDING_EVENTS_PER_PLAYER, TickEvent},
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    rx_outcome::RxRejection,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+const GUEST: PlayerNum = PlayerNum(1);
+const EMOTE: u16 = 3;
+
+fn new_host() -> MultiplayerInputManager<PlayerInput, HostInputMgr> {
+    MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(3, 5, 5, 60)
+}
+
+fn new_guest() -> MultiplayerInputManager<PlayerInput, GuestInputMgr> {
+    MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(3, GUEST, 60)
+}
+
+fn guest_inputs(start: u32, num_inputs: u32) -> MsgPayload<PlayerInput> {
+    MsgPayload::PeerInputs(PlayerInputSlice::new_test(start, num_inputs))
+}
+
+fn event(tick: u32) -> TickEvent {
+    TickEvent {
+        tick,
+        kind: EMOTE,
+        data: vec![tick as u8],
+    }
+}
+
+#[test]
+fn test_host_event_final_with_host_inputs() {
+    // A host event takes effect at the host's next input, and is final once
+    // that input is finalized.
+    let mut host = new_host();
+    for _ in 0..4 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host.add_host_event(EMOTE, vec![9]);
+    assert_eq!(host.get_num_finalized_events(PlayerNum(0)), 0);
+
+    host.add_host_input_directly(PlayerInput::default());
+    let events = host.get_finalized_events_for_tick(4);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].0, PlayerNum(0));
+    assert_eq!(events[0].1.data, vec![9]);
+}
+
+#[test]
+fn test_guest_event_round_trip() {
+    // A guest's event is sent to the host until confirmed: the host accepts it
+    // once, and after the host's broadcast the guest stops resending it.
+    let mut host = new_host();
+    let mut guest = new_guest();
+    guest.add_own_event(EMOTE, vec![1]);
+
+    let msg = guest.get_msg_own_events();
+    assert_eq!(host.rx_guest_events(GUEST, msg.clone()), Ok(1));
+    assert_eq!(host.rx_guest_events(GUEST, msg), Ok(0));
+
+    assert_eq!(guest.rx_events_from_host(host.get_msg_events(GUEST)), Ok(1));
+    assert!(matches!(guest.get_msg_own_events(), MsgPayload::Empty));
+}
+
+#[test]
+fn test_late_guest_event_moves_to_unfinalized_tick() {
+    // An event stamped at a tick the host has already finalized for that guest
+    // is moved to the guest's next unfinalized tick, and the guest adopts the
+    // host's tick when it is confirmed.
+    let mut host = new_host();
+    let mut guest = new_guest();
+    guest.add_own_event(EMOTE, vec![1]);
+    host.rx_guest_input_slice(GUEST, guest_inputs(0, 10));
+
+    host.rx_guest_events(GUEST, guest.get_msg_own_events())
+        .unwrap();
+    guest
+        .rx_events_from_host(host.get_msg_events(GUEST))
+        .unwrap();
+
+    let MsgPayload::HostToLobbyEvents(slice) = host.get_msg_events(GUEST) else {
+        panic!("expected events from the host");
+    };
+    assert_eq!(slice.events[0].tick, 10);
+}
+
+#[test]
+fn test_confirmed_event_waits_for_finalized_inputs() {
+    // A confirmed event is only final once the player's inputs are finalized
+    // through the event's tick.
+    let mut guest = new_guest();
+    guest
+        .rx_events_from_host(MsgPayload::HostToLobbyEvents(EventSlice {
+            player_num: PlayerNum(2),
+            start_seq: 0,
+            events: vec![event(5)],
+        }))
+        .unwrap();
+    assert_eq!(guest.get_num_finalized_events(PlayerNum(2)), 0);
+
+    guest.rx_final_peer_input_slice_from_host(MsgPayload::HostToLobbyFinalizedSlice(
+        HostFinalizedSlice::new_test(PlayerNum(2), 6, 0, 6),
+    ));
+    assert_eq!(guest.get_finalized_events(PlayerNum(2)), &[event(5)]);
+}
+
+#[test]
+fn test_host_resends_events_until_every_guest_acks() {
+    // The host's event broadcast starts at the earliest event any guest hasn't
+    // acked, and is empty once every guest has acked everything.
+    let mut host = new_host();
+    host.add_host_event(EMOTE, vec![]);
+    host.add_host_event(EMOTE, vec![]);
+
+    host.rx_guest_events_ack(
+        PlayerNum(1),
+        MsgPayload::GuestToHostAckEvents(vec![2, 0, 0]),
+    );
+    host.rx_guest_events_ack(
+        PlayerNum(2),
+        MsgPayload::GuestToHostAckEvents(vec![1, 0, 0]),
+    );
+    let MsgPayload::HostToLobbyEvents(slice) = host.get_msg_events(PlayerNum(0)) else {
+        panic!("expected events from the host");
+    };
+    assert_eq!((slice.start_seq, slice.events.len()), (1, 1));
+
+    host.rx_guest_events_ack(
+        PlayerNum(2),
+        MsgPayload::GuestToHostAckEvents(vec![2, 0, 0]),
+    );
+    assert!(matches!(
+        host.get_msg_events(PlayerNum(0)),
+        MsgPayload::Empty
+    ));
+}
+
+#[test]
+fn test_host_doesnt_resend_events_for_a_disconnected_guest() {
+    // Once the connected guest has acked the host's events, the broadcast is
+    // empty even though a disconnected guest never acked them.
+    let mut host = new_host();
+    host.add_host_event(EMOTE, vec![]);
+    host.player_disconnected(PlayerNum(2));
+
+    host.rx_guest_events_ack(
+        PlayerNum(1),
+        MsgPayload::GuestToHostAckEvents(vec![1, 0, 0]),
+    );
+
+    assert!(matches!(
+        host.get_msg_events(PlayerNum(0)),
+        MsgPayload::Empty
+    ));
+}
+
+#[test]
+fn test_event_slices_with_gaps_are_ignored() {
+    // Slices that start past the next expected event are ignored by both the
+    // host and guests, so the event sequence never has holes.
+    let mut host = new_host();
+    let mut guest = new_guest();
+    let gapped = EventSlice {
+        player_num: GUEST,
+        start_seq: 1,
+        events: vec![event(0)],
+    };
+
+    assert_eq!(
+        host.rx_guest_events(GUEST, MsgPayload::GuestToHostEvents(gapped.clone())),
+        Ok(0)
+    );
+    assert_eq!(
+        guest.rx_events_from_host(MsgPayload::HostToLobbyEvents(gapped)),
+        Ok(0)
+    );
+}
+
+#[test]
+fn test_events_for_unknown_players_are_rejected() {
+    // Events for a seat beyond the lobby are rejected rather than indexing
+    // past the channel, whether a guest receives them or the host does.
+    let mut host = new_host();
+    let mut guest = new_guest();
+    let unknown = EventSlice {
+        player_num: PlayerNum(5),
+        start_seq: 0,
+        events: vec![event(0)],
+    };
+
+    assert_eq!(
+        host.rx_guest_events(PlayerNum(5), MsgPayload::GuestToHostEvents(unknown.clone())),
+        Err(RxRejection::UnknownPlayer)
+    );
+    assert_eq!(
+        guest.rx_events_from_host(MsgPayload::HostToLobbyEvents(unknown)),
+        Err(RxRejection::UnknownPlayer)
+    );
+}
+
+#[test]
+fn test_host_caps_pending_events_per_guest() {
+    // The host accepts no more than the cap of a guest's events that aren't
+    // final yet; more are accepted once earlier ones become final.
+    let mut host = new_host();
+    let flood = EventSlice {
+        player_num: GUEST,
+        start_seq: 0,
+        events: (0..MAX_PENDING_EVENTS_PER_PLAYER + 10)
+            .map(|_| event(0))
+            .collect(),
+    };
+    let msg = MsgPayload::GuestToHostEvents(flood);
+
+    assert_eq!(
+        host.rx_guest_events(GUEST, msg.clone()),
+        Ok(MAX_PENDING_EVENTS_PER_PLAYER)
+    );
+    assert_eq!(host.rx_guest_events(GUEST, msg.clone()), Ok(0));
+
+    host.rx_guest_input_slice(GUEST, guest_inputs(0, 1));
+    assert_eq!(host.rx_guest_events(GUEST, msg), Ok(10));
+}
@@ -0,0 +1,174 @@
+use std::ptr;
+
+use crate::ffi::*;
+
+/// Drains the handle's outbox, returning each message's recipient and bytes.
+fn poll_all(handle: *mut TibManager) -> Vec<(u8, Vec<u8>)> {
+    let mut msgs = vec![];
+    loop {
+        let mut buf = [0u8; 1024];
+        let (mut recipient, mut len) = (0u8, 0usize);
+        let status = unsafe {
+            tib_poll_outgoing(
+                handle,
+                &mut recipient,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut len,
+            )
+        };
+        if status == TIB_NO_MESSAGE {
+            return msgs;
+        }
+        assert_eq!(status, TIB_OK);
+        msgs.push((recipient, buf[..len].to_vec()));
+    }
+}
+
+fn push_input(handle: *mut TibManager, value: u8, delta: f32) -> i32 {
+    let input = [value; TIB_INPUT_BYTES];
+    unsafe { tib_push_local_input(handle, input.as_ptr(), input.len(), delta) }
+}
+
+#[test]
+fn test_guest_inputs_reach_host_and_come_back_finalized() {
+    // A guest's input, routed through the host via the C ABI, is finalized on
+    // the host, broadcast back, and finalized on the guest.
+    let host = tib_host_new(2, 5, 5, 60);
+    let guest = tib_guest_new(2, 1, 60);
+    assert!(!host.is_null() && !guest.is_null());
+
+    assert_eq!(push_input(guest, 7, 0.0), TIB_OK);
+    for (recipient, msg) in poll_all(guest) {
+        assert_eq!(recipient, 0);
+        assert_eq!(
+            unsafe { tib_push_message(host, 1, msg.as_ptr(), msg.len()) },
+            TIB_OK
+        );
+    }
+
+    let (mut total, mut finalized) = (0, 0);
+    assert_eq!(
+        unsafe { tib_num_inputs(host, 1, &mut total, &mut finalized) },
+        TIB_OK
+    );
+    assert_eq!((total, finalized), (1, 1));
+
+    for (recipient, msg) in poll_all(host) {
+        assert_eq!(recipient, TIB_BROADCAST);
+        assert_eq!(
+            unsafe { tib_push_message(guest, 0, msg.as_ptr(), msg.len()) },
+            TIB_OK
+        );
+    }
+
+    let mut input = [0u8; TIB_INPUT_BYTES];
+    let mut is_final = false;
+    assert_eq!(
+        unsafe { tib_get_input(guest, 1, 0, input.as_mut_ptr(), input.len(), &mut is_final) },
+        TIB_OK
+    );
+    assert_eq!(input, [7; TIB_INPUT_BYTES]);
+    assert!(is_final);
+
+    // the guest acks the finalized slice back to the host
+    let acks = poll_all(guest);
+    assert_eq!(acks.len(), 1);
+    assert_eq!(acks[0].0, 0);
+
+    unsafe {
+        tib_manager_free(host);
+        tib_manager_free(guest);
+    }
+}
+
+#[test]
+fn test_host_fills_inputs_for_elapsed_time() {
+    // The host fills its buffer up to the elapsed sim time and queues a
+    // broadcast of its finalized inputs.
+    let host = tib_host_new(2, 100, 5, 60);
+    assert_eq!(push_input(host, 1, 5.0 / 60.0), TIB_OK);
+
+    let mut snapshottable = u32::MAX;
+    let (mut total, mut finalized) = (0, 0);
+    unsafe {
+        assert_eq!(tib_num_inputs(host, 0, &mut total, &mut finalized), TIB_OK);
+        assert_eq!(tib_snapshottable_tick(host, &mut snapshottable), TIB_OK);
+    }
+    assert_eq!((total, finalized), (5, 5));
+    assert_eq!(snapshottable, 0);
+    assert_eq!(poll_all(host).len(), 1);
+
+    unsafe { tib_manager_free(host) };
+}
+
+#[test]
+fn test_poll_reports_required_size_when_buffer_too_small() {
+    // A too-small output buffer reports the required length and leaves the
+    // message queued.
+    let guest = tib_guest_new(2, 1, 60);
+    push_input(guest, 3, 0.0);
+
+    let mut buf = [0u8; 1];
+    let mut len = 0;
+    let status = unsafe {
+        tib_poll_outgoing(
+            guest,
+            ptr::null_mut(),
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut len,
+        )
+    };
+    assert_eq!(status, TIB_ERR_BUFFER_TOO_SMALL);
+    assert!(len > 1);
+    assert_eq!(poll_all(guest).len(), 1);
+
+    unsafe { tib_manager_free(guest) };
+}
+
+#[test]
+fn test_error_codes() {
+    // Invalid handles, arguments, roles, and message bytes each map to their
+    // own status code.
+    assert!(tib_guest_new(2, 0, 60).is_null());
+    assert!(tib_guest_new(2, 2, 60).is_null());
+    assert!(tib_host_new(0, 5, 5, 60).is_null());
+
+    assert_eq!(push_input(ptr::null_mut(), 0, 0.0), TIB_ERR_NULL_POINTER);
+
+    let host = tib_host_new(2, 5, 5, 60);
+    let short = [0u8; 3];
+    unsafe {
+        assert_eq!(
+            tib_push_local_input(host, short.as_ptr(), short.len(), 0.0),
+            TIB_ERR_INVALID_ARGUMENT
+        );
+        let unknown_variant = [0x7f];
+        assert_eq!(
+            tib_push_message(host, 1, unknown_variant.as_ptr(), unknown_variant.len()),
+            TIB_ERR_DECODE
+        );
+        assert_eq!(
+            tib_push_message(host, 5, short.as_ptr(), short.len()),
+            TIB_ERR_INVALID_ARGUMENT
+        );
+        assert_eq!(
+            tib_guest_num_inputs_needed(host, &mut 0),
+            TIB_ERR_WRONG_ROLE
+        );
+        assert_eq!(tib_guest_send_ping(host), TIB_ERR_WRONG_ROLE);
+        // pong-pong for a ping that was never sent
+        let pong_pong = crate::MsgPayload::<FfiInput>::GuestToHostPongPong(crate::PongPong {
+            ping_id: 9,
+            guest_rtt_micros: 50_000,
+        })
+        .to_bytes();
+        assert_eq!(
+            tib_push_message(host, 1, pong_pong.as_ptr(), pong_pong.len()),
+            TIB_ERR_INTERNAL
+        );
+        tib_manager_free(host);
+        tib_manager_free(ptr::null_mut());
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+
+fn host_with_inputs(num_host_inputs: u32) -> Host {
+    let mut host = Host::new(2, 50, 5, 60);
+    for _ in 0..num_host_inputs {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host
+}
+
+#[test]
+fn test_handle_resolves_when_all_players_pass_the_tick() {
+    // The handle for tick 4 stays pending until the inputs for tick 4 are
+    // finalized for every player, not just the host.
+    let mut host = host_with_inputs(10);
+    let handle = host.notify_when_finalized(4);
+    assert_eq!(handle.tick(), 4);
+    assert!(!handle.is_finalized());
+
+    host.rx_guest_input_slice(
+        PlayerNum(1),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 4)),
+    );
+    assert!(!handle.is_finalized());
+
+    host.rx_guest_input_slice(
+        PlayerNum(1),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(4, 1)),
+    );
+    assert!(handle.is_finalized());
+    assert_eq!(host.num_pending_finalization_handles(), 0);
+}
+
+#[test]
+fn test_handle_for_already_finalized_tick_is_resolved() {
+    // Watching a tick that is already finalized resolves immediately,
+    // without registering a pending handle.
+    let mut host = host_with_inputs(3);
+    host.rx_guest_input_slice(
+        PlayerNum(1),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 3)),
+    );
+
+    let handle = host.notify_when_finalized(2);
+    assert!(handle.is_finalized());
+    assert_eq!(host.num_pending_finalization_handles(), 0);
+}
+
+#[test]
+fn test_handle_respects_start_tick() {
+    // With a start tick of 100, tick 101 is the second input.
+    let mut host = Host::new(2, 50, 5, 60).with_start_tick(100);
+    let handle = host.notify_when_finalized(101);
+    host.rx_guest_input_slice(
+        PlayerNum(1),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 2)),
+    );
+    host.add_host_input_directly(PlayerInput::default());
+    assert!(!handle.is_finalized());
+    host.add_host_input_directly(PlayerInput::default());
+    assert!(handle.is_finalized());
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_handle_can_be_awaited() {
+    // Awaiting a pending handle registers a waker, which is woken when the
+    // tick is finalized; the future then resolves to the tick.
+    use std::{
+        future::Future,
+        pin::Pin,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        task::{Context, Poll, Wake, Waker},
+    };
+
+    struct FlagWaker(AtomicBool);
+    impl Wake for FlagWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let flag = Arc::new(FlagWaker(AtomicBool::new(false)));
+    let waker = Waker::from(flag.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let mut host = host_with_inputs(1);
+    let mut handle = host.notify_when_finalized(0);
+    assert_eq!(Pin::new(&mut handle).poll(&mut cx), Poll::Pending);
+
+    host.rx_guest_input_slice(
+        PlayerNum(1),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 1)),
+    );
+    assert!(flag.0.load(Ordering::SeqCst));
+    assert_eq!(Pin::new(&mut handle).poll(&mut cx), Poll::Ready(0));
+}
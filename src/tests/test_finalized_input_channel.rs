@@ -0,0 +1,251 @@
+use crate::{
+    FinalizedInputRx, InputMgrEvent,
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+fn rx_guest_inputs(host: &mut Host, start: u32, num_inputs: u32) {
+    host.rx_guest_input_slice(
+        GUEST,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(start, num_inputs)),
+    );
+}
+
+/// The (tick, input) a host with start tick 0 finalizes for the guest's `index`th input from `PlayerInputSlice::new_test`.
+fn expected(index: u32) -> (u32, PlayerInput) {
+    (index, PlayerInput::new_test_simple(index as u8))
+}
+
+fn recv_all(rx: &FinalizedInputRx<PlayerInput>) -> Vec<(u32, PlayerInput)> {
+    std::iter::from_fn(|| rx.try_recv()).collect()
+}
+
+#[test]
+fn test_yields_finalized_inputs_in_order() {
+    // Each finalized input is yielded once, in order, with its sim tick.
+    let mut host = Host::new(2, 50, 5, 60).with_start_tick(100);
+    let rx = host.finalized_input_rx(GUEST);
+    assert_eq!(rx.try_recv(), None);
+
+    rx_guest_inputs(&mut host, 0, 3);
+
+    assert_eq!(
+        recv_all(&rx),
+        (0..3)
+            .map(|index| (100 + index, PlayerInput::new_test_simple(index as u8)))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_inputs_are_only_yielded_once() {
+    // Inputs already received aren't yielded again when more are finalized,
+    // even if the guest resends them.
+    let mut host = Host::new(2, 50, 5, 60);
+    let rx = host.finalized_input_rx(GUEST);
+    rx_guest_inputs(&mut host, 0, 3);
+    recv_all(&rx);
+
+    rx_guest_inputs(&mut host, 0, 5);
+
+    assert_eq!(recv_all(&rx), vec![expected(3), expected(4)]);
+}
+
+#[test]
+fn test_rx_opened_late_starts_from_the_round_start() {
+    // A channel opened mid-round first yields the inputs finalized before it
+    // was opened.
+    let mut host = Host::new(2, 50, 5, 60);
+    rx_guest_inputs(&mut host, 0, 3);
+
+    let rx = host.finalized_input_rx(GUEST);
+
+    assert_eq!(recv_all(&rx), (0..3).map(expected).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_provisional_inputs_are_not_yielded() {
+    // Inputs held for review aren't final yet, so they aren't yielded.
+    let mut host = Host::new(2, 50, 5, 60).with_finalization_delay_ticks(10);
+    let rx = host.finalized_input_rx(GUEST);
+
+    rx_guest_inputs(&mut host, 0, 3);
+
+    assert_eq!(rx.num_queued(), 0);
+}
+
+#[test]
+fn test_lagging_consumer_loses_nothing() {
+    // A consumer that falls behind by more than the depth gets at most the
+    // depth queued, with the rest reported as lagging and queued as it
+    // catches up, so every input is still yielded once and in order.
+    let mut host = Host::new(2, 50, 5, 60).with_finalized_input_rx_depth(4);
+    let rx = host.finalized_input_rx(GUEST);
+
+    rx_guest_inputs(&mut host, 0, 10);
+    assert_eq!(rx.num_queued(), 4);
+    assert_eq!(rx.num_lagging(), 6);
+
+    let mut received = recv_all(&rx);
+    rx_guest_inputs(&mut host, 10, 2);
+    received.extend(recv_all(&rx));
+    assert_eq!(rx.num_lagging(), 4);
+    host.feed_finalized_input_rxs();
+    received.extend(recv_all(&rx));
+    host.feed_finalized_input_rxs();
+    received.extend(recv_all(&rx));
+
+    assert_eq!(received, (0..12).map(expected).collect::<Vec<_>>());
+    assert_eq!(rx.num_lagging(), 0);
+}
+
+#[test]
+fn test_lagging_event_is_raised_once_per_fall_behind() {
+    // The lagging event is raised when the consumer falls behind, not again
+    // while it stays behind, and again if it falls behind after catching up.
+    let mut host = Host::new(2, 50, 5, 60).with_finalized_input_rx_depth(4);
+    let rx = host.finalized_input_rx(GUEST);
+    let lagging_events = |host: &mut Host| {
+        host.drain_events()
+            .into_iter()
+            .filter(|event| matches!(event, InputMgrEvent::FinalizedInputRxLagging { .. }))
+            .collect::<Vec<_>>()
+    };
+
+    rx_guest_inputs(&mut host, 0, 6);
+    assert_eq!(
+        lagging_events(&mut host),
+        vec![InputMgrEvent::FinalizedInputRxLagging {
+            player_num: GUEST,
+            num_lagging: 2
+        }]
+    );
+    rx_guest_inputs(&mut host, 6, 1);
+    assert_eq!(lagging_events(&mut host), vec![]);
+
+    recv_all(&rx);
+    host.feed_finalized_input_rxs();
+    assert_eq!(rx.num_lagging(), 0);
+    rx_guest_inputs(&mut host, 7, 1);
+    recv_all(&rx);
+    rx_guest_inputs(&mut host, 8, 5);
+
+    assert_eq!(lagging_events(&mut host).len(), 1);
+}
+
+#[test]
+fn test_new_round_closes_the_channel() {
+    // Starting a new round closes the previous round's channels, but inputs
+    // already queued can still be received.
+    let mut host = Host::new(2, 50, 5, 60);
+    let rx = host.finalized_input_rx(GUEST);
+    rx_guest_inputs(&mut host, 0, 2);
+
+    host.start_new_round();
+
+    assert!(rx.is_closed());
+    assert_eq!(recv_all(&rx), vec![expected(0), expected(1)]);
+    assert_eq!(host.num_open_finalized_input_rxs(), 0);
+}
+
+#[test]
+fn test_dropping_the_manager_closes_the_channel() {
+    // A receiver outliving its manager sees its channel closed.
+    let mut host = Host::new(2, 50, 5, 60);
+    let rx = host.finalized_input_rx(GUEST);
+
+    drop(host);
+
+    assert!(rx.is_closed());
+}
+
+#[test]
+fn test_dropped_rx_is_no_longer_fed() {
+    // The manager stops feeding a channel once its receiver is dropped.
+    let mut host = Host::new(2, 50, 5, 60);
+    let rx = host.finalized_input_rx(GUEST);
+    assert_eq!(host.num_open_finalized_input_rxs(), 1);
+
+    drop(rx);
+    rx_guest_inputs(&mut host, 0, 2);
+
+    assert_eq!(host.num_open_finalized_input_rxs(), 0);
+}
+
+#[test]
+fn test_rx_consumed_on_another_thread() {
+    // A receiver moved to another thread yields every input once and in
+    // order while the manager keeps finalizing, until the manager is dropped.
+    let mut host = Host::new(2, 50, 5, 60);
+    let rx = host.finalized_input_rx(GUEST);
+    let consumer = std::thread::spawn(move || {
+        let mut received = Vec::new();
+        loop {
+            let closed = rx.is_closed();
+            received.extend(recv_all(&rx));
+            if closed {
+                return received;
+            }
+            std::thread::yield_now();
+        }
+    });
+
+    for start in (0..100).step_by(5) {
+        rx_guest_inputs(&mut host, start, 5);
+    }
+    drop(host);
+
+    assert_eq!(
+        consumer.join().unwrap(),
+        (0..100).map(expected).collect::<Vec<_>>()
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_rx_can_be_polled_as_a_stream() {
+    // Polling an empty receiver registers a waker, which is woken when inputs
+    // are queued; the stream ends once the channel is closed and drained.
+    use std::{
+        pin::Pin,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        task::{Context, Poll, Wake, Waker},
+    };
+
+    use futures_core::Stream;
+
+    struct FlagWaker(AtomicBool);
+    impl Wake for FlagWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let flag = Arc::new(FlagWaker(AtomicBool::new(false)));
+    let waker = Waker::from(flag.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut rx = host.finalized_input_rx(GUEST);
+    assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Pending);
+
+    rx_guest_inputs(&mut host, 0, 1);
+    assert!(flag.0.load(Ordering::SeqCst));
+    assert_eq!(
+        Pin::new(&mut rx).poll_next(&mut cx),
+        Poll::Ready(Some(expected(0)))
+    );
+
+    host.start_new_round();
+    assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Ready(None));
+}
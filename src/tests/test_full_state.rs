@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use test_case::test_case;
+
+use crate::{
+    InputMgrEvent, Recipient,
+    input_messages::{
+        COMPRESSED_FLAG, DEFAULT_COMPRESSION_THRESHOLD_BYTES, HostFinalizedSlice, MsgPayload,
+    },
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+fn input(x: u32) -> PlayerInput {
+    PlayerInput::new_test_simple((x % 100) as u8 + 1)
+}
+
+/// A host and guest that have run half a second (30 ticks at 60 ticks per
+/// sec), exchanging inputs, finalized slices and acks.
+fn running_session() -> (Host, Guest) {
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    host.add_host_input_to_fill_needed(input(0), 0.5);
+    for frame in 0..30 {
+        guest.add_own_input(input(frame));
+    }
+    host.rx_guest_input_slice(GUEST, guest.get_msg_own_input_slice());
+    for player in PlayerNum::iter(2) {
+        guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(player));
+    }
+    host.rx_finalized_ticks_observations(GUEST, guest.get_msg_ack_finalization());
+    (host, guest)
+}
+
+fn finalized_slice_start(msg: MsgPayload<PlayerInput>) -> u32 {
+    let MsgPayload::HostToLobbyFinalizedSlice(HostFinalizedSlice { inputs, .. }) = msg else {
+        panic!("expected a finalized slice, got {msg:?}");
+    };
+    inputs.start
+}
+
+fn acked(msg: MsgPayload<PlayerInput>) -> HashMap<PlayerNum, u32> {
+    let MsgPayload::GuestToHostAckFinalization(seen) = msg else {
+        panic!("expected an ack, got {msg:?}");
+    };
+    seen.inner()
+}
+
+#[test]
+fn test_resumed_host_keeps_guest_acks() {
+    // A host resumed from a saved state only sends the guest the inputs it
+    // hasn't acked, where a host recovered from scratch would resend them all.
+    let (host, _) = running_session();
+    let mut resumed = Host::new(2, 50, 5, 60);
+
+    resumed
+        .deserialize_full_state(&host.serialize_full_state().unwrap())
+        .unwrap();
+
+    assert_eq!(
+        finalized_slice_start(resumed.get_msg_finalized_slice(GUEST)),
+        30
+    );
+    assert_eq!(resumed.observation_matrix(), host.observation_matrix());
+}
+
+#[test]
+fn test_resumed_host_keeps_inputs_and_sim_time() {
+    // The resumed host has every finalized input, and its sim clock carries on
+    // from half a second, so another 0.1s collects 6 more inputs.
+    let (host, _) = running_session();
+    let mut resumed = Host::new(2, 50, 5, 60);
+    resumed
+        .deserialize_full_state(&host.serialize_full_state().unwrap())
+        .unwrap();
+
+    resumed.add_host_input_to_fill_needed(input(1), 0.1);
+
+    assert_eq!(resumed.get_peer_num_final_inputs(GUEST), 30);
+    assert_eq!(resumed.get_peer_input_for_tick(GUEST, 29), input(29));
+    assert_eq!(resumed.get_peer_num_final_inputs(PlayerNum(0)), 36);
+}
+
+#[test]
+fn test_resumed_guest_keeps_inputs_and_acks() {
+    // A guest resumed from a saved state has the same inputs, and acks the
+    // same finalized inputs to the host.
+    let (_, mut guest) = running_session();
+    let mut resumed = Guest::new(2, GUEST, 60);
+
+    resumed
+        .deserialize_full_state(&guest.serialize_full_state().unwrap())
+        .unwrap();
+
+    assert_eq!(resumed.get_snapshottable_sim_tick(), 30);
+    assert_eq!(resumed.get_peer_input_for_tick(GUEST, 12), input(12));
+    assert_eq!(
+        acked(resumed.get_msg_ack_finalization()),
+        acked(guest.get_msg_ack_finalization())
+    );
+}
+
+#[test]
+fn test_resumed_host_keeps_recorded_sends() {
+    // The resumed host still bounds a guest's acks by the slices recorded as
+    // sent to it, so an ack past them is clamped and flagged.
+    let (mut host, _) = running_session();
+    host.record_msg_sent(
+        Recipient::Player(GUEST),
+        &HostFinalizedSlice::new_test(PlayerNum(0), 0, 0, 20).into(),
+    );
+    let mut resumed = Host::new(2, 50, 5, 60);
+    resumed
+        .deserialize_full_state(&host.serialize_full_state().unwrap())
+        .unwrap();
+
+    resumed.rx_finalized_ticks_observations(
+        GUEST,
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
+            HashMap::from([(PlayerNum(0), 25)]),
+        )),
+    );
+
+    assert_eq!(
+        resumed.drain_events(),
+        vec![InputMgrEvent::AckBeyondSent {
+            guest: GUEST,
+            player_num: PlayerNum(0),
+            acked: 25,
+            max_sent: 20,
+        }]
+    );
+}
+
+#[test]
+fn test_full_state_keeps_the_round() {
+    // A state saved after a round transition resumes in that round.
+    let (mut host, _) = running_session();
+    host.start_new_round();
+    let mut resumed = Host::new(2, 50, 5, 60);
+
+    resumed
+        .deserialize_full_state(&host.serialize_full_state().unwrap())
+        .unwrap();
+
+    assert_eq!(resumed.current_round(), 1);
+}
+
+#[test]
+fn test_host_rejects_guest_full_state() {
+    // A state saved by a guest can't be restored into a host.
+    let (_, guest) = running_session();
+    let mut host = Host::new(2, 50, 5, 60);
+
+    assert!(
+        host.deserialize_full_state(&guest.serialize_full_state().unwrap())
+            .is_err()
+    );
+}
+
+#[test_case(PlayerNum(2), 60; "other player num")]
+#[test_case(GUEST, 30; "other tick rate")]
+fn test_guest_rejects_mismatched_full_state(own_player_num: PlayerNum, ticks_per_sec: u32) {
+    // The restoring guest must be configured like the one that saved the state.
+    let (_, guest) = running_session();
+    let mut resumed = Guest::new(3, own_player_num, ticks_per_sec);
+
+    assert!(
+        resumed
+            .deserialize_full_state(&guest.serialize_full_state().unwrap())
+            .is_err()
+    );
+}
+
+#[test_case(|bytes| bytes.truncate(10); "truncated")]
+#[test_case(|bytes| bytes[0] = b'X'; "bad magic")]
+#[test_case(|bytes| bytes[4] += 1; "unknown version")]
+#[test_case(|bytes| bytes.push(0); "trailing bytes")]
+fn test_host_rejects_corrupt_full_state(corrupt: fn(&mut Vec<u8>)) {
+    // Corrupt saved states are reported as errors, leaving the host as it was.
+    let (host, _) = running_session();
+    let mut bytes = host.serialize_full_state().unwrap();
+    corrupt(&mut bytes);
+    let mut resumed = Host::new(2, 50, 5, 60);
+
+    assert!(resumed.deserialize_full_state(&bytes).is_err());
+    assert_eq!(resumed.get_peer_num_final_inputs(GUEST), 0);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_large_full_state_is_compressed_and_round_trips() {
+    // A saved state past the compression threshold is compressed (flag set on
+    // the version byte, shorter than uncompressed), and restores the same inputs.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.add_host_input_to_fill_needed(input(0), 30.0);
+    let bytes = host.serialize_full_state().unwrap();
+    let mut resumed = Host::new(2, 50, 5, 60);
+
+    resumed.deserialize_full_state(&bytes).unwrap();
+
+    assert_ne!(bytes[4] & COMPRESSED_FLAG, 0);
+    assert!(bytes.len() < DEFAULT_COMPRESSION_THRESHOLD_BYTES);
+    assert_eq!(resumed.get_peer_num_final_inputs(PlayerNum(0)), 1800);
+    assert_eq!(
+        resumed.get_peer_input_for_tick(PlayerNum(0), 1799),
+        input(0)
+    );
+}
+
+#[cfg(not(feature = "compression"))]
+#[test]
+fn test_full_state_is_uncompressed_without_feature() {
+    // Without the `compression` feature, saved states are never compressed.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.add_host_input_to_fill_needed(input(0), 30.0);
+
+    let bytes = host.serialize_full_state().unwrap();
+
+    assert_eq!(bytes[4] & COMPRESSED_FLAG, 0);
+    assert!(bytes.len() > DEFAULT_COMPRESSION_THRESHOLD_BYTES);
+}
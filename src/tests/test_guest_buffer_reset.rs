@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use crate::{
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    rx_outcome::RxRejection,
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+fn input(x: u32) -> PlayerInput {
+    PlayerInput::new_test_simple((x % 100) as u8 + 1)
+}
+
+/// A host and guest that have each collected 10 inputs, with the guest
+/// holding and acking every finalized input.
+fn acked_session() -> (Host, Guest) {
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    for frame in 0..10 {
+        host.add_host_input_directly(input(frame));
+        guest.add_own_input(input(frame + 50));
+    }
+    exchange(&mut host, &mut guest);
+    (host, guest)
+}
+
+/// Sends the guest's inputs to the host, then every player's finalized slice back to it, and the guest's ack to the host.
+fn exchange(host: &mut Host, guest: &mut Guest) {
+    host.rx_guest_input_slice(GUEST, guest.get_msg_own_input_slice());
+    for player in PlayerNum::iter(2) {
+        guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(player));
+    }
+    host.rx_finalized_ticks_observations(GUEST, guest.get_msg_ack_finalization());
+}
+
+fn ack_host_inputs(host: &mut Host, num_acked: u32) {
+    host.rx_finalized_ticks_observations(
+        GUEST,
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
+            HashMap::from([(HOST_PLAYER_NUM, num_acked)]),
+        )),
+    );
+}
+
+fn finalized_slice_start(msg: MsgPayload<PlayerInput>) -> u32 {
+    let MsgPayload::HostToLobbyFinalizedSlice(HostFinalizedSlice { inputs, .. }) = msg else {
+        panic!("expected a finalized slice, got {msg:?}");
+    };
+    inputs.start
+}
+
+#[test]
+fn test_host_ignores_acks_that_go_backwards() {
+    // An ack older than one already received, e.g. one delivered out of
+    // order, doesn't move the guest's acks back.
+    let mut host = Host::new(2, 50, 5, 60);
+    for frame in 0..10 {
+        host.add_host_input_directly(input(frame));
+    }
+
+    ack_host_inputs(&mut host, 8);
+    ack_host_inputs(&mut host, 5);
+
+    assert_eq!(host.observation_matrix()[0].1[0], (HOST_PLAYER_NUM, 8));
+}
+
+#[test]
+fn test_guest_reset_drops_inputs_and_acks() {
+    // Resetting a guest's buffers drops every input, restarts its acks from
+    // zero, and returns the reset for the current round.
+    let (_, mut guest) = acked_session();
+
+    let msg = guest.reset_buffers();
+
+    assert!(matches!(msg, MsgPayload::GuestToHostBufferReset(0)));
+    assert_eq!(guest.get_snapshottable_sim_tick(), 0);
+    assert_eq!(guest.get_peer_num_final_inputs(HOST_PLAYER_NUM), 0);
+    let MsgPayload::GuestToHostAckFinalization(acked) = guest.get_msg_ack_finalization() else {
+        panic!("expected an ack");
+    };
+    assert_eq!(acked.get(HOST_PLAYER_NUM), 0);
+}
+
+#[test]
+fn test_host_resends_everything_after_guest_reset() {
+    // Once the host handles the reset, it forgets the guest's acks, so the
+    // next finalized slices start from the first input and the guest catches
+    // back up.
+    let (mut host, mut guest) = acked_session();
+    assert_eq!(
+        finalized_slice_start(host.get_msg_finalized_slice(HOST_PLAYER_NUM)),
+        10
+    );
+
+    let outcome = host.rx_guest_buffer_reset(GUEST, guest.reset_buffers());
+    assert_eq!(outcome.rejected, None);
+    assert_eq!(
+        finalized_slice_start(host.get_msg_finalized_slice(HOST_PLAYER_NUM)),
+        0
+    );
+
+    exchange(&mut host, &mut guest);
+    assert_eq!(guest.get_snapshottable_sim_tick(), 10);
+    assert_eq!(guest.get_peer_input_for_tick(GUEST, 3), input(53));
+}
+
+#[test]
+fn test_host_rejects_reset_from_another_round() {
+    // A reset stamped with a round the host isn't in leaves the guest's acks
+    // as they were.
+    let (mut host, _) = acked_session();
+
+    let outcome = host.rx_guest_buffer_reset(GUEST, MsgPayload::GuestToHostBufferReset(3));
+
+    assert_eq!(outcome.rejected, Some(RxRejection::UnexpectedMsg));
+    assert_eq!(
+        finalized_slice_start(host.get_msg_finalized_slice(HOST_PLAYER_NUM)),
+        10
+    );
+}
+
+#[test]
+fn test_host_rejects_reset_while_awaiting_round_ack() {
+    // Until the guest acks a new round, its reset may belong to the previous
+    // one, so it's rejected.
+    let (mut host, _) = acked_session();
+    host.start_new_round();
+
+    let outcome = host.rx_guest_buffer_reset(GUEST, MsgPayload::GuestToHostBufferReset(1));
+
+    assert_eq!(outcome.rejected, Some(RxRejection::AwaitingRoundAck));
+}
@@ -0,0 +1,120 @@
+use crate::{
+    events::InputMgrEvent,
+    input_messages::{HostFinalizedSlice, MsgPayload, PreSimSync},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::{AheadOfHostPolicy, GuestInputMgr},
+    tests::demo_input_struct::PlayerInput,
+};
+
+fn new_guest() -> MultiplayerInputManager<PlayerInput, GuestInputMgr> {
+    MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, 1.into(), 60)
+}
+
+fn pre_sim_sync(countdown: u8) -> MsgPayload<PlayerInput> {
+    PreSimSync {
+        host_tick_countdown: countdown,
+        ..Default::default()
+    }
+    .into()
+}
+
+#[test]
+fn test_unsynced_getters_do_not_panic() {
+    // Before any sync, timing getters report unknown values or neutral
+    // defaults rather than panicking.
+    let guest = new_guest();
+
+    assert!(!guest.is_synced());
+    assert_eq!(guest.get_host_tick(), None);
+    assert_eq!(guest.get_rtt_ms_to_host(), None);
+    assert_eq!(guest.one_way_in_ticks(), None);
+    assert_eq!(guest.ticks_ahead(), 0.0);
+    assert_eq!(guest.recommended_frame_delay_ms(), 0.0);
+}
+
+#[test]
+fn test_rtt_without_host_tick_collects_one_input() {
+    // Knowing the RTT but not the host tick is still unsynced, so even a
+    // stalling guest keeps collecting one input per frame.
+    let mut guest = new_guest().with_ahead_of_host_policy(AheadOfHostPolicy::Stall);
+    guest.observe_rtt_ms_to_host(100.0);
+    for _ in 0..10 {
+        guest.add_own_input(PlayerInput::default());
+    }
+
+    assert!(!guest.is_synced());
+    assert_eq!(guest.num_inputs_needed(), 1);
+}
+
+#[test]
+fn test_synced_event_after_pre_sim_sync_and_rtt() {
+    // The guest becomes synced once both the PreSimSync countdown and an RTT
+    // sample have arrived, raising a single GuestSynced event.
+    let mut guest = new_guest();
+    guest.rx_pre_sim_sync(pre_sim_sync(3)).unwrap();
+    assert!(guest.drain_events().is_empty());
+
+    guest.observe_rtt_ms_to_host(100.0);
+    guest.observe_rtt_ms_to_host(120.0);
+    assert!(guest.is_synced());
+    assert_eq!(
+        guest.drain_events(),
+        vec![InputMgrEvent::GuestSynced { host_tick: -3 }]
+    );
+}
+
+#[test]
+fn test_synced_event_after_finalized_slice() {
+    // A finalized slice from the host also provides the host tick, so a guest
+    // that already knows its RTT becomes synced when one arrives.
+    let mut guest = new_guest();
+    guest.observe_rtt_ms_to_host(100.0);
+    guest.rx_final_peer_input_slice_from_host(MsgPayload::HostToLobbyFinalizedSlice(
+        HostFinalizedSlice::new_test(0.into(), 7, 0, 3),
+    ));
+
+    assert_eq!(guest.get_host_tick(), Some(7));
+    assert_eq!(
+        guest.drain_events(),
+        vec![InputMgrEvent::GuestSynced { host_tick: 7 }]
+    );
+}
+
+#[test]
+fn test_small_host_tick_regressions_are_counted_silently() {
+    // Out-of-order host ticks are counted, but are ignored without an event
+    // while within the threshold.
+    let mut guest = new_guest().with_host_tick_regression_threshold(10);
+    guest.test_advance_host_tick(100);
+    guest.test_advance_host_tick(95);
+    guest.test_advance_host_tick(90);
+    assert_eq!(guest.num_host_tick_regressions(), 2);
+    assert_eq!(guest.get_host_tick(), Some(100));
+    assert_eq!(guest.drain_events(), vec![]);
+}
+
+#[test]
+fn test_large_host_tick_regression_raises_event_once() {
+    // A regression past the threshold (e.g. a restarted host) raises one
+    // event, and the lower tick is ignored until the reset is accepted.
+    let mut guest = new_guest().with_host_tick_regression_threshold(10);
+    guest.test_advance_host_tick(100);
+    guest.test_advance_host_tick(3);
+    guest.test_advance_host_tick(4);
+    assert_eq!(
+        guest.drain_events(),
+        vec![InputMgrEvent::HostTickRegressed {
+            newest_host_tick: 100,
+            host_tick: 3,
+        }]
+    );
+    assert_eq!(guest.get_host_tick(), Some(100));
+
+    guest.accept_host_tick_reset(4);
+    assert_eq!(guest.get_host_tick(), Some(4));
+    guest.test_advance_host_tick(5);
+    assert_eq!(guest.get_host_tick(), Some(5));
+    guest.test_advance_host_tick(100);
+    guest.test_advance_host_tick(0);
+    assert_eq!(guest.drain_events().len(), 1);
+}
@@ -0,0 +1,133 @@
+use test_case::test_case;
+
+use crate::{
+    input_messages::{MsgKind, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    session::{OutgoingMsgs, Recipient, SessionRxError},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+fn routed_kinds(outgoing: &OutgoingMsgs<PlayerInput>) -> Vec<(Recipient, MsgKind)> {
+    outgoing
+        .iter()
+        .map(|(recipient, msg)| (*recipient, msg.kind()))
+        .collect()
+}
+
+#[test]
+fn test_host_broadcasts_slice_finalized_from_guest_inputs() {
+    // A guest's inputs are finalized on receipt, and the resulting slice is
+    // addressed to every peer.
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    guest.add_own_input(PlayerInput::new_test_simple(1));
+
+    let outgoing = host
+        .handle_msg(GUEST, guest.get_msg_own_input_slice())
+        .unwrap();
+
+    assert_eq!(
+        routed_kinds(&outgoing),
+        vec![(Recipient::AllPeers, MsgKind::HostToLobbyFinalizedSlice)]
+    );
+    assert_eq!(
+        host.get_peer_input_for_tick(GUEST, 0),
+        PlayerInput::new_test_simple(1)
+    );
+}
+
+#[test]
+fn test_host_replies_to_ping_only_to_its_sender() {
+    // A pong goes back to the guest that pinged.
+    let mut host = Host::new(3, 50, 5, 60);
+
+    let outgoing = host
+        .handle_msg(PlayerNum(2), MsgPayload::GuestToHostPing(7))
+        .unwrap();
+
+    assert_eq!(
+        routed_kinds(&outgoing),
+        vec![(Recipient::Player(PlayerNum(2)), MsgKind::HostToGuestPong)]
+    );
+}
+
+#[test_case(HOST_PLAYER_NUM, MsgPayload::GuestToHostPing(1), SessionRxError::InvalidSender; "message from the host's own seat")]
+#[test_case(GUEST, MsgPayload::HostToGuestPong(1), SessionRxError::WrongRole; "message only guests receive")]
+fn test_host_rejects_message(
+    sender: PlayerNum,
+    msg: MsgPayload<PlayerInput>,
+    expected: SessionRxError,
+) {
+    // The host only handles messages that guests send, from guest seats.
+    let mut host = Host::new(2, 50, 5, 60);
+
+    assert_eq!(host.handle_msg(sender, msg).err(), Some(expected));
+}
+
+#[test]
+fn test_guest_acks_finalized_slice_to_host() {
+    // A finalized slice from the host is acked back to the host.
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    host.add_host_input_directly(PlayerInput::new_test_simple(1));
+
+    let outgoing = guest
+        .handle_msg(
+            HOST_PLAYER_NUM,
+            host.get_msg_finalized_slice(HOST_PLAYER_NUM),
+        )
+        .unwrap();
+
+    assert_eq!(
+        routed_kinds(&outgoing),
+        vec![(
+            Recipient::Player(HOST_PLAYER_NUM),
+            MsgKind::GuestToHostAckFinalization
+        )]
+    );
+}
+
+#[test]
+fn test_guest_acks_a_batch_of_slices_once() {
+    // Each slice of a batch is applied, but only the last ack is kept, as it
+    // supersedes the earlier ones.
+    let mut host = Host::new(3, 50, 5, 60);
+    let mut guest = Guest::new(3, GUEST, 60);
+    host.add_host_input_directly(PlayerInput::new_test_simple(1));
+    let batch = host.get_outgoing_msgs_for_frame(0.1);
+
+    let outgoing = guest.handle_msg(HOST_PLAYER_NUM, batch).unwrap();
+
+    assert_eq!(
+        routed_kinds(&outgoing),
+        vec![(
+            Recipient::Player(HOST_PLAYER_NUM),
+            MsgKind::GuestToHostAckFinalization
+        )]
+    );
+    assert_eq!(
+        guest.get_peer_input_for_tick(HOST_PLAYER_NUM, 0),
+        PlayerInput::new_test_simple(1)
+    );
+}
+
+#[test]
+fn test_guest_rejects_message_only_host_receives() {
+    // A guest doesn't handle messages addressed to the host.
+    let mut guest = Guest::new(2, GUEST, 60);
+
+    assert_eq!(
+        guest
+            .handle_msg(HOST_PLAYER_NUM, MsgPayload::GuestToHostPing(1))
+            .err(),
+        Some(SessionRxError::WrongRole)
+    );
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use crate::{
+    input_buffer::InputStatus,
+    input_messages::{MsgPayload, PongPong},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+/// A 3 player guest with 4 of its own inputs, 2 of them finalized by the host's slice.
+fn guest_with_inputs() -> Guest {
+    let mut host = Host::new(3, 50, 5, 60).with_start_tick(10);
+    let mut guest = Guest::new(3, GUEST, 60);
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+    for x in 0..4 {
+        guest.add_own_input(PlayerInput::new_test_simple(x));
+    }
+    for x in 0..2 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(10 + x));
+    }
+    guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(HOST_PLAYER_NUM));
+    guest
+}
+
+#[test]
+fn test_inputs_map_into_matches_allocating_version() {
+    // Filling a reused map gives the same inputs as building a new one, on
+    // ticks before and after the start tick, and drops stale entries.
+    let guest = guest_with_inputs();
+    let mut inputs = HashMap::from([(9, PlayerInput::new_test_simple(9))]);
+
+    for tick in [0, 9, 10, 11, 13, 20] {
+        guest.get_inputs_map_for_tick_into(tick, &mut inputs);
+        assert_eq!(inputs, guest.get_inputs_map_for_tick(tick), "tick {tick}");
+    }
+}
+
+#[test]
+fn test_input_statuses_into_matches_allocating_version() {
+    // Filling a reused vec gives the same statuses as building a new one, on
+    // ticks before and after the start tick, and drops stale entries.
+    let guest = guest_with_inputs();
+    let mut statuses = vec![
+        (
+            PlayerNum(9),
+            InputStatus::NotReceived {
+                num_finalized: 0,
+                predicted: false
+            }
+        );
+        5
+    ];
+
+    for tick in [0, 9, 10, 11, 13, 20] {
+        guest.get_input_statuses_into(tick, &mut statuses);
+        assert_eq!(statuses, guest.get_input_statuses(tick), "tick {tick}");
+    }
+    guest.get_input_statuses_into(11, &mut statuses);
+    assert_eq!(
+        statuses,
+        vec![
+            (HOST_PLAYER_NUM, InputStatus::Finalized { num_finalized: 2 }),
+            (GUEST, InputStatus::NonFinal { num_finalized: 0 }),
+            (
+                PlayerNum(2),
+                InputStatus::NotReceived {
+                    num_finalized: 0,
+                    predicted: false
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_into_variants_reuse_their_buffers() {
+    // Once a buffer has held every player, refilling it each frame keeps its
+    // storage rather than reallocating.
+    let guest = guest_with_inputs();
+    let mut inputs = HashMap::new();
+    let mut statuses = Vec::new();
+    guest.get_inputs_map_for_tick_into(10, &mut inputs);
+    guest.get_input_statuses_into(10, &mut statuses);
+    let map_capacity = inputs.capacity();
+    let statuses_ptr = statuses.as_ptr();
+
+    for tick in 10..100 {
+        guest.get_inputs_map_for_tick_into(tick, &mut inputs);
+        guest.get_input_statuses_into(tick, &mut statuses);
+        assert_eq!(inputs.capacity(), map_capacity);
+        assert_eq!(statuses.as_ptr(), statuses_ptr);
+    }
+}
+
+#[test]
+fn test_rtts_by_player_into_matches_allocating_version() {
+    // The host's RTTs fill a reused vec the same as a new one, dropping stale
+    // entries.
+    let mut host = Host::new(3, 50, 5, 60);
+    host.rx_guest_ping_and_reply(GUEST, MsgPayload::GuestToHostPing(0));
+    host.rx_guest_pong_pong(
+        GUEST,
+        MsgPayload::GuestToHostPongPong(PongPong {
+            ping_id: 0,
+            guest_rtt_micros: 40_000,
+        }),
+    )
+    .unwrap();
+    let mut rtts = vec![(7, 1.0), (8, 2.0)];
+
+    host.rtts_by_player_into(&mut rtts);
+
+    assert_eq!(rtts, host.rtts_by_player());
+    assert_eq!(rtts.len(), 1);
+    assert_eq!(rtts[0].0, GUEST.0);
+}
@@ -0,0 +1,160 @@
+use crate::{
+    events::InputMgrEvent,
+    input_hash_chain::{ChainCheck, INPUT_CHAIN_SEED, InputChainHead},
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    replay::{REPLAY_FLAG_CHAIN_HEADS, REPLAY_FLAG_CHECKSUMS, Replay},
+    tests::demo_input_struct::{PlayerInput, PlayerInputBinary},
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+/// A 2 player host and guest with input chains, which have both finalized 6 inputs for each player.
+fn host_and_guest() -> (Host, Guest) {
+    let mut host = Host::new(2, 50, 5, 60).with_input_hash_chains();
+    let mut guest = Guest::new(2, 1.into(), 60).with_input_hash_chains();
+    for x in 0..6 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(x));
+        guest.add_own_input(PlayerInput::new_test_simple(x + 1));
+    }
+    host.rx_guest_input_slice(PlayerNum(1), guest.get_msg_own_input_slice());
+    for player in [PlayerNum(0), PlayerNum(1)] {
+        guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(player));
+    }
+    (host, guest)
+}
+
+#[test]
+fn test_host_and_guest_chains_agree() {
+    // Having finalized the same inputs, the host and guest compute the same
+    // chain heads, and each accepts the other's.
+    let (mut host, mut guest) = host_and_guest();
+    for player in [PlayerNum(0), PlayerNum(1)] {
+        let head = host.input_chain_head(player).unwrap();
+        assert_eq!(head.num_inputs, 6);
+        assert_eq!(Some(head), guest.input_chain_head(player));
+
+        let msg = guest.get_msg_input_chain_head(player);
+        assert_eq!(
+            host.rx_input_chain_head(PlayerNum(1), msg),
+            ChainCheck::Match
+        );
+        let msg = host.get_msg_input_chain_head(player);
+        assert_eq!(
+            guest.rx_input_chain_head(PlayerNum(0), msg),
+            ChainCheck::Match
+        );
+    }
+    assert_eq!(host.drain_events(), vec![]);
+}
+
+#[test]
+fn test_earlier_heads_can_be_checked() {
+    // A head for fewer inputs than this node has finalized is checked
+    // against the chain at that point; a head for more can't be checked yet.
+    let (mut host, guest) = host_and_guest();
+    let mut short_guest = Guest::new(2, 1.into(), 60).with_input_hash_chains();
+    short_guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(PlayerNum(0)));
+    let short_head = short_guest.get_msg_input_chain_head(PlayerNum(0));
+    assert_eq!(
+        host.rx_input_chain_head(PlayerNum(1), short_head),
+        ChainCheck::Match
+    );
+
+    let ahead = MsgPayload::PeerInputChainHead(InputChainHead {
+        num_inputs: 7,
+        ..guest.input_chain_head(PlayerNum(0)).unwrap()
+    });
+    assert_eq!(
+        host.rx_input_chain_head(PlayerNum(1), ahead),
+        ChainCheck::Unknown
+    );
+}
+
+#[test]
+fn test_modified_history_is_a_mismatch() {
+    // A node that finalized a different input for a player produces a
+    // different head, which is reported as a mismatch with an event.
+    let (mut host, _) = host_and_guest();
+    let mut tampered = Guest::new(2, 1.into(), 60).with_input_hash_chains();
+    let MsgPayload::HostToLobbyFinalizedSlice(mut slice) =
+        host.get_msg_finalized_slice(PlayerNum(1))
+    else {
+        panic!("expected a finalized slice");
+    };
+    slice.inputs.inputs[2] = PlayerInputBinary::new_test_simple(9);
+    tampered.rx_final_peer_input_slice_from_host(MsgPayload::HostToLobbyFinalizedSlice(slice));
+
+    let msg = tampered.get_msg_input_chain_head(PlayerNum(1));
+    assert_eq!(
+        host.rx_input_chain_head(PlayerNum(1), msg),
+        ChainCheck::Mismatch
+    );
+    assert_eq!(
+        host.drain_events(),
+        vec![InputMgrEvent::InputChainMismatch {
+            sender: PlayerNum(1),
+            player_num: PlayerNum(1),
+            num_inputs: 6,
+        }]
+    );
+}
+
+#[test]
+fn test_chains_are_opt_in() {
+    // Without input chains, there is no head to send, and received heads
+    // can't be checked.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.add_host_input_directly(PlayerInput::default());
+    assert_eq!(host.input_chain_head(PlayerNum(0)), None);
+    assert!(matches!(
+        host.get_msg_input_chain_head(PlayerNum(0)),
+        MsgPayload::Empty
+    ));
+    let msg = MsgPayload::PeerInputChainHead(InputChainHead {
+        player_num: PlayerNum(0),
+        num_inputs: 0,
+        head: INPUT_CHAIN_SEED,
+    });
+    assert_eq!(
+        host.rx_input_chain_head(PlayerNum(1), msg),
+        ChainCheck::Unknown
+    );
+}
+
+#[test]
+fn test_replay_chain_heads_match_live_heads() {
+    // A replay of the round carries the same chain heads the peers computed
+    // live, and survives a round trip with them.
+    let (host, _) = host_and_guest();
+    let replay = host.export_replay();
+    let live_heads: Vec<u64> = [PlayerNum(0), PlayerNum(1)]
+        .map(|p| host.input_chain_head(p).unwrap().head)
+        .to_vec();
+    assert_eq!(replay.chain_heads(), live_heads);
+
+    let bytes = replay
+        .to_bytes_with_flags(REPLAY_FLAG_CHECKSUMS | REPLAY_FLAG_CHAIN_HEADS)
+        .unwrap();
+    assert_eq!(Replay::<PlayerInput>::from_bytes(&bytes), Ok(replay));
+}
+
+#[test]
+fn test_replay_with_wrong_chain_head_is_rejected() {
+    // Changing a stored chain head makes the replay fail to load.
+    let (host, _) = host_and_guest();
+    let mut bytes = host
+        .export_replay()
+        .to_bytes_with_flags(REPLAY_FLAG_CHAIN_HEADS)
+        .unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    assert_eq!(
+        Replay::<PlayerInput>::from_bytes(&bytes),
+        Err("input chain mismatch for player 1".to_string())
+    );
+}
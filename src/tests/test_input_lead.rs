@@ -0,0 +1,158 @@
+use test_case::test_case;
+
+use crate::{
+    DEFAULT_MAX_INPUT_LEAD, InputMgrEvent, RxOutcome, RxRejection,
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+fn slice(start: u32, num_inputs: u32) -> MsgPayload<PlayerInput> {
+    MsgPayload::PeerInputs(PlayerInputSlice::new_test(start, num_inputs))
+}
+
+#[test]
+fn test_default_max_input_lead() {
+    // Managers allow the default lead unless configured otherwise.
+    assert_eq!(
+        Host::new(2, 50, 5, 60).max_input_lead(),
+        DEFAULT_MAX_INPUT_LEAD
+    );
+    assert_eq!(
+        Guest::new(3, PlayerNum(1), 60).max_input_lead(),
+        DEFAULT_MAX_INPUT_LEAD
+    );
+}
+
+#[test]
+fn test_host_drops_guest_inputs_past_the_lead() {
+    // A hostile guest slice reaching far past the finalized inputs only has
+    // its inputs within the lead finalized; the rest are dropped, counted and
+    // reported.
+    let mut host = Host::new(2, 50, 5, 60).with_max_input_lead(20);
+
+    let outcome = host.rx_guest_input_slice(PlayerNum(1), slice(0, 5000));
+
+    assert_eq!(outcome.newly_finalized, 20);
+    assert_eq!(host.buffers.get_num_inputs(PlayerNum(1)), 20);
+    assert_eq!(host.num_inputs_beyond_lead(PlayerNum(1)), 4980);
+    assert!(
+        host.drain_events()
+            .contains(&InputMgrEvent::InputsBeyondLead {
+                player_num: PlayerNum(1),
+                num_dropped: 4980
+            })
+    );
+}
+
+#[test]
+fn test_guest_drops_peer_inputs_past_the_lead() {
+    // A guest receiving another guest's provisional inputs stores only those
+    // within the lead.
+    let mut guest = Guest::new(3, PlayerNum(1), 60).with_max_input_lead(20);
+
+    let outcome = guest.rx_peer_input_slice(PlayerNum(2), slice(0, 5000));
+
+    assert_eq!(outcome.new_inputs, 20);
+    assert_eq!(guest.buffers.get_num_inputs(PlayerNum(2)), 20);
+    assert_eq!(guest.num_inputs_beyond_lead(PlayerNum(2)), 4980);
+    assert!(
+        guest
+            .drain_events()
+            .contains(&InputMgrEvent::InputsBeyondLead {
+                player_num: PlayerNum(2),
+                num_dropped: 4980
+            })
+    );
+}
+
+#[test_case(true ; "host")]
+#[test_case(false ; "guest")]
+fn test_slice_entirely_past_the_lead_is_rejected(is_host: bool) {
+    // A slice starting thousands of inputs ahead is rejected outright, and
+    // none of its inputs are stored.
+    let hostile = slice(10_000, 50);
+    let (outcome, num_inputs, num_beyond_lead) = if is_host {
+        let mut host = Host::new(3, 50, 5, 60);
+        let outcome = host.rx_guest_input_slice(PlayerNum(2), hostile);
+        (
+            outcome,
+            host.buffers.get_num_inputs(PlayerNum(2)),
+            host.num_inputs_beyond_lead(PlayerNum(2)),
+        )
+    } else {
+        let mut guest = Guest::new(3, PlayerNum(1), 60);
+        let outcome = guest.rx_peer_input_slice(PlayerNum(2), hostile);
+        (
+            outcome,
+            guest.buffers.get_num_inputs(PlayerNum(2)),
+            guest.num_inputs_beyond_lead(PlayerNum(2)),
+        )
+    };
+
+    assert_eq!(outcome, RxOutcome::rejected(RxRejection::BeyondInputLead));
+    assert_eq!(num_inputs, 0);
+    assert_eq!(num_beyond_lead, 50);
+}
+
+#[test]
+fn test_slice_within_the_lead_is_untouched() {
+    // A slice within the lead is accepted whole, without counting or raising
+    // anything.
+    let mut guest = Guest::new(3, PlayerNum(1), 60).with_max_input_lead(20);
+
+    let outcome = guest.rx_peer_input_slice(PlayerNum(2), slice(0, 20));
+
+    assert_eq!(outcome.new_inputs, 20);
+    assert_eq!(guest.num_inputs_beyond_lead(PlayerNum(2)), 0);
+    assert!(guest.drain_events().is_empty());
+}
+
+#[test]
+fn test_lead_moves_with_finalization() {
+    // The window is measured from the inputs finalized for every player, so
+    // it only lets a guest further ahead once the other players catch up.
+    let mut host = Host::new(3, 50, 5, 60).with_max_input_lead(10);
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, 10));
+    assert_eq!(
+        host.rx_guest_input_slice(PlayerNum(1), slice(10, 5)),
+        RxOutcome::rejected(RxRejection::BeyondInputLead)
+    );
+
+    for _ in 0..5 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host.rx_guest_input_slice(PlayerNum(2), slice(0, 5));
+    let outcome = host.rx_guest_input_slice(PlayerNum(1), slice(10, 5));
+
+    assert_eq!(outcome.newly_finalized, 5);
+    assert_eq!(host.buffers.get_num_finalized_inputs(PlayerNum(1)), 15);
+}
+
+#[test_case(false; "disconnected guest")]
+#[test_case(true; "muted guest")]
+fn test_departed_guest_doesnt_hold_back_the_lead(muted: bool) {
+    // On the host, a guest that stopped sending inputs because it
+    // disconnected or was muted doesn't pin the lead window for the others.
+    let mut host = Host::new(3, 50, 5, 60).with_max_input_lead(10);
+    if muted {
+        host.mute_player(PlayerNum(2), 0);
+    } else {
+        host.player_disconnected(PlayerNum(2));
+    }
+    for _ in 0..20 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host.rx_guest_input_slice(PlayerNum(1), slice(0, 10));
+
+    let outcome = host.rx_guest_input_slice(PlayerNum(1), slice(10, 10));
+
+    assert_eq!(outcome.new_inputs, 10);
+    assert_eq!(host.num_inputs_beyond_lead(PlayerNum(1)), 0);
+}
@@ -3,8 +3,21 @@ use std::collections::HashMap;
 use test_case::test_case;
 
 use crate::{
-    input_messages::{HostFinalizedSlice, MsgPayload, PreSimSync},
+    capabilities::Capabilities,
+    clock_sync::{ClockPing, ClockPong},
+    desync_report::InputHashReport,
+    determinism_probe::DeterminismSample,
+    event_channel::{EventSlice, TickEvent},
+    host_recovery::RecoveryResponse,
+    input_hash_chain::InputChainHead,
+    input_messages::{
+        COMPRESSED_FLAG, HostFinalizedSlice, MsgKind, MsgPayload, PlayerJoined, PlayerMuted,
+        PlayerRemoved, PongPong, PreSimSync, SeatTransfer, peek_variant,
+    },
     peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    rtt::PingReport,
+    seed_schedule::SeedChange,
+    start_sync::{ScheduledStart, StartAck},
     tests::demo_input_struct::PlayerInput,
     util_types::{PlayerInputSlice, PlayerNum},
 };
@@ -23,10 +36,119 @@ use crate::{
 #[test_case(MsgPayload::<PlayerInput>::HostToGuestPreSimSync(PreSimSync {
     host_tick_countdown: 4,
     peers: vec![0, 1, 2],
+    start_tick: 72_000,
+    initial_seed: 0x1234_5678_9abc_def0,
+    sim_ticks_per_input: 2,
+    ticks_per_sec: 50,
+    max_session_ticks: 180_000,
+    input_schema_id: 0xdead_beef,
+    capabilities: Capabilities(0b101),
+    player_metadata: vec![(PlayerNum(0), b"host".to_vec()), (PlayerNum(2), vec![])],
 }); "pre sim sync")]
 #[test_case(MsgPayload::<PlayerInput>::GuestToHostPing(42); "guest ping")]
 #[test_case(MsgPayload::<PlayerInput>::HostToGuestPong(43); "host pong")]
-#[test_case(MsgPayload::<PlayerInput>::GuestToHostPongPong(44); "guest pong pong")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostPongPong(PongPong {
+    ping_id: 44,
+    guest_rtt_micros: 52_500,
+}); "guest pong pong")]
+#[test_case(MsgPayload::<PlayerInput>::HostToGuestRateAdjust(-1500); "host rate adjust")]
+#[test_case(MsgPayload::<PlayerInput>::HostToLobbyRoundTransition(2); "host round transition")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostRoundTransitionAck(2); "guest round transition ack")]
+#[test_case(MsgPayload::<PlayerInput>::HostToLobbyPlayerMuted(PlayerMuted {
+    player_num: PlayerNum(2),
+    first_ignored_input: 30,
+}); "host player muted")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostEvents(EventSlice {
+    player_num: PlayerNum(1),
+    start_seq: 3,
+    events: vec![TickEvent { tick: 40, kind: 7, data: vec![1, 2, 3] }],
+}); "guest events")]
+#[test_case(MsgPayload::<PlayerInput>::HostToLobbyEvents(EventSlice {
+    player_num: PlayerNum(0),
+    start_seq: 0,
+    events: vec![],
+}); "host events")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostAckEvents(vec![2, 0, 5]); "guest ack events")]
+#[test_case(MsgPayload::<PlayerInput>::PeerInputChainHead(InputChainHead {
+    player_num: PlayerNum(2),
+    num_inputs: 600,
+    head: 0x0123_4567_89ab_cdef,
+}); "peer input chain head")]
+#[test_case(MsgPayload::<PlayerInput>::PeerDeterminismSample(DeterminismSample {
+    tick: 1200,
+    input_digest: 0x0123_4567_89ab_cdef,
+    state_digest: 0xfedc_ba98_7654_3210,
+}); "peer determinism sample")]
+#[test_case(MsgPayload::<PlayerInput>::HostToLobbyRecoveryRequest(3); "host recovery request")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostRecoveryResponse(RecoveryResponse {
+    request_id: 3,
+    finalized: vec![PlayerInputSlice::new_test(0, 4), PlayerInputSlice::new_test(0, 2)],
+}); "guest recovery response")]
+#[test_case(MsgPayload::<PlayerInput>::HostToLobbyAnnotations(EventSlice {
+    player_num: PlayerNum(0),
+    start_seq: 2,
+    events: vec![TickEvent {
+        tick: 300,
+        kind: 4,
+        data: vec![0xde, 0xad],
+    }],
+}); "host annotations")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostAckAnnotations(3); "guest ack annotations")]
+#[test_case(MsgPayload::<PlayerInput>::HostToLobbySeed(SeedChange {
+    change_num: 2,
+    tick: 300,
+    seed: u64::MAX - 7,
+}); "host seed")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostAckSeeds(3); "guest ack seeds")]
+#[test_case(MsgPayload::<PlayerInput>::HostToGuestPingReport(PingReport::new(52_500, 48_000)); "host ping report")]
+#[test_case(MsgPayload::<PlayerInput>::HostToLobbyEndSession(3600); "host end session")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostEndAck(3600); "guest end ack")]
+#[test_case(MsgPayload::<PlayerInput>::HostToLobbySeatTransferred(SeatTransfer {
+    seat: PlayerNum(2),
+    connection: PlayerNum(5),
+    first_input: 900,
+}); "host seat transferred")]
+#[test_case(MsgPayload::<PlayerInput>::HostToLobbyStartProposal(ScheduledStart {
+    proposal_num: 1,
+    host_lobby_tick: 300,
+    start_lobby_tick: 360,
+}); "host start proposal")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostStartAck(StartAck {
+    proposal_num: 1,
+    ticks_until_start: -4,
+}); "guest start ack")]
+#[test_case(MsgPayload::<PlayerInput>::HostToLobbyStartConfirmed(ScheduledStart {
+    proposal_num: 1,
+    host_lobby_tick: 310,
+    start_lobby_tick: 364,
+}); "host start confirmed")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostInputSchema(0xdead_beef); "guest input schema")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostCapabilities(Capabilities::COMPRESSION); "guest capabilities")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostInputHashReport(InputHashReport {
+    start_tick: 120,
+    hashes: vec![0x0123_4567_89ab_cdef, u64::MAX, 0],
+}); "guest input hash report")]
+#[test_case(MsgPayload::<PlayerInput>::HostToLobbyPlayerJoined(PlayerJoined {
+    player_num: PlayerNum(3),
+    first_input: 1200,
+}); "host player joined")]
+#[test_case(MsgPayload::<PlayerInput>::HostToLobbyPlayerRemoved(PlayerRemoved {
+    player_num: PlayerNum(2),
+    num_inputs: 4800,
+}); "host player removed")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostClockPing(ClockPing {
+    guest_send_micros: -12_345_678_901,
+}); "guest clock ping")]
+#[test_case(MsgPayload::<PlayerInput>::HostToGuestClockPong(ClockPong {
+    guest_send_micros: 12_345_678_901,
+    host_micros: 500_000,
+}); "host clock pong")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostBufferReset(2); "guest buffer reset")]
+#[test_case(MsgPayload::<PlayerInput>::Batch(vec![
+    MsgPayload::PeerInputs(PlayerInputSlice::new_test(3, 5)),
+    MsgPayload::GuestToHostPing(7),
+]); "batch")]
+#[test_case(MsgPayload::<PlayerInput>::DeliveryAck(vec![0, 7, 4_000_000_000]); "delivery ack")]
 fn test_msg_payload_round_trip(payload: MsgPayload<PlayerInput>) {
     // Ensure every MsgPayload variant survives a to_bytes/from_bytes round trip.
     let bytes = payload.to_bytes();
@@ -54,16 +176,131 @@ fn test_msg_payload_round_trip(payload: MsgPayload<PlayerInput>) {
         (MsgPayload::HostToGuestPreSimSync(ps1), MsgPayload::HostToGuestPreSimSync(ps2)) => {
             assert_eq!(ps1.host_tick_countdown, ps2.host_tick_countdown);
             assert_eq!(ps1.peers, ps2.peers);
+            assert_eq!(ps1.start_tick, ps2.start_tick);
+            assert_eq!(ps1.initial_seed, ps2.initial_seed);
+            assert_eq!(ps1.sim_ticks_per_input, ps2.sim_ticks_per_input);
+            assert_eq!(ps1.ticks_per_sec, ps2.ticks_per_sec);
+            assert_eq!(ps1.max_session_ticks, ps2.max_session_ticks);
+            assert_eq!(ps1.input_schema_id, ps2.input_schema_id);
+            assert_eq!(ps1.capabilities, ps2.capabilities);
+            assert_eq!(ps1.player_metadata, ps2.player_metadata);
         }
         (MsgPayload::GuestToHostPing(p1), MsgPayload::GuestToHostPing(p2)) => assert_eq!(p1, p2),
         (MsgPayload::HostToGuestPong(p1), MsgPayload::HostToGuestPong(p2)) => assert_eq!(p1, p2),
         (MsgPayload::GuestToHostPongPong(p1), MsgPayload::GuestToHostPongPong(p2)) => {
             assert_eq!(p1, p2)
         }
+        (MsgPayload::HostToGuestRateAdjust(r1), MsgPayload::HostToGuestRateAdjust(r2)) => {
+            assert_eq!(r1, r2)
+        }
+        (
+            MsgPayload::HostToLobbyRoundTransition(r1),
+            MsgPayload::HostToLobbyRoundTransition(r2),
+        ) => assert_eq!(r1, r2),
+        (
+            MsgPayload::GuestToHostRoundTransitionAck(r1),
+            MsgPayload::GuestToHostRoundTransitionAck(r2),
+        )
+        | (MsgPayload::GuestToHostBufferReset(r1), MsgPayload::GuestToHostBufferReset(r2)) => {
+            assert_eq!(r1, r2)
+        }
+        (MsgPayload::HostToLobbyPlayerMuted(m1), MsgPayload::HostToLobbyPlayerMuted(m2)) => {
+            assert_eq!(m1, m2)
+        }
+        (MsgPayload::GuestToHostEvents(e1), MsgPayload::GuestToHostEvents(e2)) => {
+            assert_eq!(e1, e2)
+        }
+        (MsgPayload::HostToLobbyEvents(e1), MsgPayload::HostToLobbyEvents(e2)) => {
+            assert_eq!(e1, e2)
+        }
+        (MsgPayload::GuestToHostAckEvents(a1), MsgPayload::GuestToHostAckEvents(a2)) => {
+            assert_eq!(a1, a2)
+        }
+        (MsgPayload::PeerInputChainHead(h1), MsgPayload::PeerInputChainHead(h2)) => {
+            assert_eq!(h1, h2)
+        }
+        (MsgPayload::PeerDeterminismSample(s1), MsgPayload::PeerDeterminismSample(s2)) => {
+            assert_eq!(s1, s2)
+        }
+        (
+            MsgPayload::HostToLobbyRecoveryRequest(id1),
+            MsgPayload::HostToLobbyRecoveryRequest(id2),
+        ) => assert_eq!(id1, id2),
+        (MsgPayload::HostToLobbyAnnotations(a1), MsgPayload::HostToLobbyAnnotations(a2)) => {
+            assert_eq!(a1, a2)
+        }
+        (MsgPayload::GuestToHostAckAnnotations(n1), MsgPayload::GuestToHostAckAnnotations(n2)) => {
+            assert_eq!(n1, n2)
+        }
+        (MsgPayload::HostToLobbySeed(c1), MsgPayload::HostToLobbySeed(c2)) => assert_eq!(c1, c2),
+        (MsgPayload::HostToGuestPingReport(r1), MsgPayload::HostToGuestPingReport(r2)) => {
+            assert_eq!(r1, r2)
+        }
+        (MsgPayload::GuestToHostAckSeeds(n1), MsgPayload::GuestToHostAckSeeds(n2)) => {
+            assert_eq!(n1, n2)
+        }
+        (MsgPayload::HostToLobbyEndSession(t1), MsgPayload::HostToLobbyEndSession(t2)) => {
+            assert_eq!(t1, t2)
+        }
+        (MsgPayload::GuestToHostEndAck(t1), MsgPayload::GuestToHostEndAck(t2)) => {
+            assert_eq!(t1, t2)
+        }
+        (MsgPayload::GuestToHostInputSchema(s1), MsgPayload::GuestToHostInputSchema(s2)) => {
+            assert_eq!(s1, s2)
+        }
+        (MsgPayload::GuestToHostCapabilities(c1), MsgPayload::GuestToHostCapabilities(c2)) => {
+            assert_eq!(c1, c2)
+        }
+        (
+            MsgPayload::GuestToHostInputHashReport(r1),
+            MsgPayload::GuestToHostInputHashReport(r2),
+        ) => {
+            assert_eq!(r1, r2)
+        }
+        (MsgPayload::HostToLobbyPlayerJoined(j1), MsgPayload::HostToLobbyPlayerJoined(j2)) => {
+            assert_eq!(j1, j2)
+        }
+        (MsgPayload::HostToLobbyPlayerRemoved(r1), MsgPayload::HostToLobbyPlayerRemoved(r2)) => {
+            assert_eq!(r1, r2)
+        }
+        (MsgPayload::GuestToHostClockPing(p1), MsgPayload::GuestToHostClockPing(p2)) => {
+            assert_eq!(p1, p2)
+        }
+        (MsgPayload::HostToGuestClockPong(p1), MsgPayload::HostToGuestClockPong(p2)) => {
+            assert_eq!(p1, p2)
+        }
+        (
+            MsgPayload::HostToLobbySeatTransferred(t1),
+            MsgPayload::HostToLobbySeatTransferred(t2),
+        ) => {
+            assert_eq!(t1, t2)
+        }
+        (MsgPayload::HostToLobbyStartProposal(s1), MsgPayload::HostToLobbyStartProposal(s2))
+        | (MsgPayload::HostToLobbyStartConfirmed(s1), MsgPayload::HostToLobbyStartConfirmed(s2)) => {
+            assert_eq!(s1, s2)
+        }
+        (MsgPayload::GuestToHostStartAck(a1), MsgPayload::GuestToHostStartAck(a2)) => {
+            assert_eq!(a1, a2)
+        }
+        (
+            MsgPayload::GuestToHostRecoveryResponse(r1),
+            MsgPayload::GuestToHostRecoveryResponse(r2),
+        ) => {
+            assert_eq!(r1.request_id, r2.request_id);
+            assert_eq!(r1.finalized.len(), r2.finalized.len());
+            for (s1, s2) in r1.finalized.iter().zip(&r2.finalized) {
+                assert_eq!(s1.start, s2.start);
+                assert_eq!(s1.inputs, s2.inputs);
+            }
+        }
+        // the contents are compared by the re-serialized bytes below
+        (MsgPayload::Batch(b1), MsgPayload::Batch(b2)) => assert_eq!(b1.len(), b2.len()),
+        (MsgPayload::DeliveryAck(s1), MsgPayload::DeliveryAck(s2)) => assert_eq!(s1, s2),
         _ => panic!("Variant mismatch after round trip"),
     }
 
     assert_eq!(decoded.to_bytes(), bytes);
+    assert_eq!(peek_variant(&bytes).unwrap(), payload.kind());
 }
 
 #[test]
@@ -72,3 +309,231 @@ fn test_msg_payload_unknown_variant() {
     let bytes = vec![255u8];
     assert!(MsgPayload::<PlayerInput>::from_bytes(&bytes).is_err());
 }
+
+#[test]
+fn test_peek_variant_of_empty_and_unknown_bytes() {
+    // An empty buffer peeks as an empty message, and an unknown variant number is an error.
+    assert_eq!(peek_variant(&[]).unwrap(), MsgKind::Empty);
+    assert!(peek_variant(&[43]).is_err());
+    assert!(peek_variant(&[255]).is_err());
+}
+
+#[test]
+fn test_msg_kind_variant_nums_round_trip() {
+    // Every variant number in use maps to a kind and back; the first unused one maps to none.
+    for variant_num in 0..=42 {
+        let kind = MsgKind::from_variant_num(variant_num).unwrap();
+        assert_eq!(kind.variant_num(), variant_num);
+    }
+    assert_eq!(MsgKind::from_variant_num(43), None);
+}
+
+#[test]
+fn test_legacy_pong_pong_bytes_decode() {
+    // A pong-pong from a guest built before it carried the guest's RTT (wire
+    // id 8, then just the ping id) still decodes, as a legacy pong-pong,
+    // rather than failing as a malformed `PongPong`.
+    let mut bytes = vec![8u8];
+    bytes.extend(bincode::serde::encode_to_vec(7u32, bincode::config::standard()).unwrap());
+
+    let decoded = MsgPayload::<PlayerInput>::from_bytes(&bytes).unwrap();
+
+    assert!(matches!(decoded, MsgPayload::GuestToHostLegacyPongPong(7)));
+    assert_eq!(decoded.to_bytes(), bytes);
+}
+
+#[test_case(MsgKind::Empty, 0; "empty")]
+#[test_case(MsgKind::Invalid, 1; "invalid")]
+#[test_case(MsgKind::GuestToHostAckFinalization, 2; "guest to host ack finalization")]
+#[test_case(MsgKind::HostToLobbyFinalizedSlice, 3; "host to lobby finalized slice")]
+#[test_case(MsgKind::PeerInputs, 4; "peer inputs")]
+#[test_case(MsgKind::HostToGuestPreSimSync, 5; "host to guest pre sim sync")]
+#[test_case(MsgKind::GuestToHostPing, 6; "guest to host ping")]
+#[test_case(MsgKind::HostToGuestPong, 7; "host to guest pong")]
+#[test_case(MsgKind::GuestToHostLegacyPongPong, 8; "guest to host legacy pong pong")]
+#[test_case(MsgKind::HostToGuestRateAdjust, 9; "host to guest rate adjust")]
+#[test_case(MsgKind::HostToLobbyRoundTransition, 10; "host to lobby round transition")]
+#[test_case(MsgKind::GuestToHostRoundTransitionAck, 11; "guest to host round transition ack")]
+#[test_case(MsgKind::HostToLobbyPlayerMuted, 12; "host to lobby player muted")]
+#[test_case(MsgKind::GuestToHostEvents, 13; "guest to host events")]
+#[test_case(MsgKind::HostToLobbyEvents, 14; "host to lobby events")]
+#[test_case(MsgKind::GuestToHostAckEvents, 15; "guest to host ack events")]
+#[test_case(MsgKind::PeerInputChainHead, 16; "peer input chain head")]
+#[test_case(MsgKind::PeerDeterminismSample, 17; "peer determinism sample")]
+#[test_case(MsgKind::HostToLobbyRecoveryRequest, 18; "host to lobby recovery request")]
+#[test_case(MsgKind::GuestToHostRecoveryResponse, 19; "guest to host recovery response")]
+#[test_case(MsgKind::HostToLobbyAnnotations, 20; "host to lobby annotations")]
+#[test_case(MsgKind::GuestToHostAckAnnotations, 21; "guest to host ack annotations")]
+#[test_case(MsgKind::HostToLobbySeed, 22; "host to lobby seed")]
+#[test_case(MsgKind::GuestToHostAckSeeds, 23; "guest to host ack seeds")]
+#[test_case(MsgKind::HostToGuestPingReport, 24; "host to guest ping report")]
+#[test_case(MsgKind::GuestToHostPongPong, 25; "guest to host pong pong")]
+#[test_case(MsgKind::HostToLobbyEndSession, 26; "host to lobby end session")]
+#[test_case(MsgKind::GuestToHostEndAck, 27; "guest to host end ack")]
+#[test_case(MsgKind::HostToLobbySeatTransferred, 28; "host to lobby seat transferred")]
+#[test_case(MsgKind::HostToLobbyStartProposal, 29; "host to lobby start proposal")]
+#[test_case(MsgKind::GuestToHostStartAck, 30; "guest to host start ack")]
+#[test_case(MsgKind::HostToLobbyStartConfirmed, 31; "host to lobby start confirmed")]
+#[test_case(MsgKind::GuestToHostInputSchema, 32; "guest to host input schema")]
+#[test_case(MsgKind::GuestToHostCapabilities, 33; "guest to host capabilities")]
+#[test_case(MsgKind::GuestToHostInputHashReport, 34; "guest to host input hash report")]
+#[test_case(MsgKind::HostToLobbyPlayerJoined, 35; "host to lobby player joined")]
+#[test_case(MsgKind::HostToLobbyPlayerRemoved, 36; "host to lobby player removed")]
+#[test_case(MsgKind::GuestToHostClockPing, 37; "guest to host clock ping")]
+#[test_case(MsgKind::HostToGuestClockPong, 38; "host to guest clock pong")]
+#[test_case(MsgKind::GuestToHostBufferReset, 39; "guest to host buffer reset")]
+#[test_case(MsgKind::Batch, 40; "batch")]
+#[test_case(MsgKind::DeliveryAck, 41; "delivery ack")]
+#[test_case(MsgKind::GuestToHostLocalInputs, 42; "guest to host local inputs")]
+fn test_msg_kind_wire_ids_are_pinned(kind: MsgKind, wire_id: u8) {
+    // Each kind keeps the wire id it was assigned, whatever the declaration
+    // order, so peers on other builds and captured message bytes still decode.
+    assert_eq!(kind.variant_num(), wire_id);
+    assert_eq!(MsgKind::from_variant_num(wire_id), Some(kind));
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_pre_rename_names_are_the_renamed_kinds() {
+    // The pre-rename names build the renamed messages, with the same kinds and
+    // wire bytes.
+    let ack = PeerwiseFinalizedInputsSeen::new_test(HashMap::from([(PlayerNum(1), 3u32)]));
+    let old_and_new: [(MsgPayload<PlayerInput>, MsgPayload<PlayerInput>, MsgKind); 6] = [
+        (
+            MsgPayload::AckFinalization(ack.clone()),
+            MsgPayload::GuestToHostAckFinalization(ack),
+            MsgKind::AckFinalization,
+        ),
+        (
+            MsgPayload::HostFinalizedSlice(HostFinalizedSlice::new_test(PlayerNum(1), 5, 0, 3)),
+            MsgPayload::HostToLobbyFinalizedSlice(HostFinalizedSlice::new_test(
+                PlayerNum(1),
+                5,
+                0,
+                3,
+            )),
+            MsgKind::HostFinalizedSlice,
+        ),
+        (
+            MsgPayload::PreSimSync(PreSimSync::default()),
+            MsgPayload::HostToGuestPreSimSync(PreSimSync::default()),
+            MsgKind::PreSimSync,
+        ),
+        (
+            MsgPayload::Ping(7),
+            MsgPayload::GuestToHostPing(7),
+            MsgKind::Ping,
+        ),
+        (
+            MsgPayload::Pong(7),
+            MsgPayload::HostToGuestPong(7),
+            MsgKind::Pong,
+        ),
+        (
+            MsgPayload::PongPong(PongPong {
+                ping_id: 7,
+                guest_rtt_micros: 500,
+            }),
+            MsgPayload::GuestToHostPongPong(PongPong {
+                ping_id: 7,
+                guest_rtt_micros: 500,
+            }),
+            MsgKind::PongPong,
+        ),
+    ];
+    for (old, new, old_kind) in old_and_new {
+        assert_eq!(old.kind(), new.kind());
+        assert_eq!(old_kind, new.kind());
+        assert_eq!(old.to_bytes(), new.to_bytes());
+    }
+}
+
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostPing(1); "guest ping")]
+#[test_case(MsgPayload::<PlayerInput>::HostToGuestPong(1); "host pong")]
+#[test_case(MsgPayload::<PlayerInput>::HostToGuestRateAdjust(1); "host rate adjust")]
+#[test_case(MsgPayload::<PlayerInput>::HostToLobbyRoundTransition(1); "host round transition")]
+#[test_case(MsgPayload::<PlayerInput>::GuestToHostAckEvents(vec![]); "guest ack events")]
+#[test_case(MsgPayload::<PlayerInput>::PeerInputs(
+    PlayerInputSlice::<PlayerInput>::new_test(0, 1)
+); "peer inputs")]
+fn test_peeked_kind_routes_like_decoded_payload(payload: MsgPayload<PlayerInput>) {
+    // Routing on the peeked kind gives the same answers as routing on the decoded payload.
+    let kind = peek_variant(&payload.to_bytes()).unwrap();
+    assert_eq!(kind.is_guest_reply(), payload.is_guest_reply());
+    assert_eq!(
+        kind.is_host_reply_for_all(),
+        payload.is_host_reply_for_all()
+    );
+    assert_eq!(
+        kind.is_host_reply_for_one(),
+        payload.is_host_reply_for_one()
+    );
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_peek_variant_of_compressed_payload() {
+    // The compressed flag is ignored when peeking, so compressed messages peek as their own kind.
+    let payload = MsgPayload::<PlayerInput>::PeerInputs(PlayerInputSlice {
+        start: 0,
+        inputs: vec![Default::default(); 2000],
+    });
+    let bytes = payload.to_bytes();
+    assert_ne!(bytes[0] & COMPRESSED_FLAG, 0);
+    assert_eq!(peek_variant(&bytes).unwrap(), MsgKind::PeerInputs);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_large_payload_is_compressed_and_round_trips() {
+    // A long, highly repetitive slice should be compressed (flag bit set, fewer
+    // bytes than the raw encoding) and decode back to the same inputs.
+    let slice = PlayerInputSlice::<PlayerInput> {
+        start: 7,
+        inputs: vec![Default::default(); 2000],
+    };
+    let payload = MsgPayload::<PlayerInput>::PeerInputs(slice.clone());
+    let bytes = payload.to_bytes();
+    assert_ne!(bytes[0] & COMPRESSED_FLAG, 0);
+    assert!(
+        bytes.len()
+            < payload
+                .to_bytes_with_compression_threshold(usize::MAX)
+                .len()
+    );
+
+    let Ok(MsgPayload::PeerInputs(decoded)) = MsgPayload::<PlayerInput>::from_bytes(&bytes) else {
+        panic!("expected PeerInputs");
+    };
+    assert_eq!(decoded.start, slice.start);
+    assert_eq!(decoded.inputs, slice.inputs);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_small_payload_is_not_compressed() {
+    // Payloads under the threshold are sent with a plain header byte.
+    let bytes = MsgPayload::<PlayerInput>::GuestToHostPing(42).to_bytes();
+    assert_eq!(bytes[0] & COMPRESSED_FLAG, 0);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn test_compressed_payload_with_oversized_length_prefix_is_rejected() {
+    // A compressed payload claiming to expand to an enormous size is rejected
+    // rather than allocating.
+    let mut bytes = vec![4 | COMPRESSED_FLAG];
+    bytes.extend(u32::MAX.to_le_bytes());
+    bytes.extend([0u8; 8]);
+    assert!(MsgPayload::<PlayerInput>::from_bytes(&bytes).is_err());
+}
+
+#[cfg(not(feature = "compression"))]
+#[test]
+fn test_compressed_payload_without_feature_is_an_error() {
+    // Without the `compression` feature, a message with the compressed flag
+    // set can't be decoded and must produce an error.
+    let mut bytes = MsgPayload::<PlayerInput>::GuestToHostPing(42).to_bytes();
+    bytes[0] |= COMPRESSED_FLAG;
+    assert!(MsgPayload::<PlayerInput>::from_bytes(&bytes).is_err());
+}
@@ -0,0 +1,91 @@
+use test_case::test_case;
+
+use crate::{
+    input_patterns::{alternating, seeded_random},
+    input_trait::SimInput,
+    multiplayer_input_buffer::MultiplayerInputBuffers,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+fn jump(jump: bool) -> PlayerInput {
+    let mut input = PlayerInput::default();
+    input.jump = jump;
+    input
+}
+
+#[test]
+fn test_from_fn_passes_each_index() {
+    // `from_fn` builds the slice from `start`, calling the closure with each
+    // input's index.
+    let slice = PlayerInputSlice::<PlayerInput>::from_fn(3, 4, |index| {
+        PlayerInput::new_test_simple(index as u8)
+    });
+    assert_eq!(slice.start, 3);
+    assert_eq!(
+        slice.inputs,
+        (3..7)
+            .map(|x| PlayerInput::new_test_simple(x).to_bytes())
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test_case(1, vec![true, false, true, false, true, false] ; "period 1")]
+#[test_case(2, vec![true, true, false, false, true, true] ; "period 2")]
+#[test_case(0, vec![true, false, true, false, true, false] ; "period 0 is treated as 1")]
+fn test_alternating(period: u32, expected_jumps: Vec<bool>) {
+    // The pattern holds each of pressed and released for `period` inputs,
+    // starting pressed.
+    let pattern = alternating(period, jump(true), jump(false));
+    let jumps: Vec<bool> = (0..6).map(|index| pattern(index).jump).collect();
+    assert_eq!(jumps, expected_jumps);
+}
+
+#[test]
+fn test_seeded_random_depends_only_on_seed_and_index() {
+    // The same seed gives the same input at each index, whichever order the
+    // indices are generated in; a different seed gives a different sequence.
+    let to_input = |x: u64| PlayerInput::new_test_simple(x as u8);
+    let forwards: Vec<_> = (0..32).map(seeded_random(7, to_input)).collect();
+    let mut backwards: Vec<_> = (0..32).rev().map(seeded_random(7, to_input)).collect();
+    backwards.reverse();
+    assert_eq!(forwards, backwards);
+
+    let other_seed: Vec<_> = (0..32).map(seeded_random(8, to_input)).collect();
+    assert_ne!(forwards, other_seed);
+}
+
+#[test]
+fn test_append_inputs_from_fn_continues_from_the_buffer_end() {
+    // Appending from a closure picks up at the player's next index, so
+    // repeated fills continue the pattern.
+    let mut buffers = MultiplayerInputBuffers::<PlayerInput>::new(2, 8);
+    let pattern = alternating(2, jump(true), jump(false));
+    buffers.append_inputs_from_fn(PlayerNum(1), 3, &pattern);
+    buffers.append_inputs_from_fn(PlayerNum(1), 3, &pattern);
+
+    assert_eq!(buffers.get_num_inputs(PlayerNum(1)), 6);
+    assert_eq!(buffers.get_num_finalized_inputs(PlayerNum(1)), 0);
+    assert_eq!(
+        buffers.get_slice_to_end_for_peer(PlayerNum(1), 0).inputs,
+        PlayerInputSlice::<PlayerInput>::from_fn(0, 6, pattern).inputs
+    );
+}
+
+#[test]
+fn test_append_finalized_inputs_from_fn() {
+    // The finalized variant appends inputs that are already final.
+    let mut buffers = MultiplayerInputBuffers::<PlayerInput>::new(2, 8);
+    buffers.append_finalized_inputs_from_fn(PlayerNum(0), 5, |index| {
+        PlayerInput::new_test_simple(index as u8)
+    });
+
+    assert_eq!(buffers.get_num_finalized_inputs(PlayerNum(0)), 5);
+    assert_eq!(
+        buffers.get_finalized_slice_for_peer(PlayerNum(0), 0).inputs,
+        PlayerInputSlice::<PlayerInput>::from_fn(0, 5, |index| PlayerInput::new_test_simple(
+            index as u8
+        ))
+        .inputs
+    );
+}
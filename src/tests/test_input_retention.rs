@@ -0,0 +1,191 @@
+use crate::{
+    input_trait::SimInput,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const HOST: PlayerNum = PlayerNum(0);
+const GUEST: PlayerNum = PlayerNum(1);
+
+fn input(x: u32) -> PlayerInput {
+    PlayerInput::new_test_simple((x % 200) as u8)
+}
+
+/// Runs `num_frames` frames of a 2 player session, each collecting one input on both sides and exchanging every message.
+fn run_frames(host: &mut Host, guest: &mut Guest, num_frames: u32) {
+    for _ in 0..num_frames {
+        let frame = host.get_own_num_inputs();
+        host.add_host_input_directly(input(frame));
+        guest.add_own_input(input(frame + 1));
+        host.rx_guest_input_slice(GUEST, guest.get_msg_own_input_slice());
+        for player in [HOST, GUEST] {
+            guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(player));
+        }
+        let ack = guest.get_msg_ack_finalization();
+        host.rx_finalized_ticks_observations(GUEST, ack);
+    }
+}
+
+fn retaining(max_retained_inputs: u32) -> (Host, Guest) {
+    let host = Host::new(2, 50, 5, 60).with_max_retained_inputs(max_retained_inputs);
+    let guest = Guest::new(2, GUEST, 60).with_max_retained_inputs(max_retained_inputs);
+    (host, guest)
+}
+
+#[test]
+fn test_every_input_is_kept_by_default() {
+    // Without a retention limit, no input is ever dropped.
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    run_frames(&mut host, &mut guest, 200);
+
+    assert_eq!(host.max_retained_inputs(), None);
+    for player in [HOST, GUEST] {
+        assert_eq!(host.first_retained_input(player), 0);
+        assert_eq!(guest.first_retained_input(player), 0);
+    }
+}
+
+#[test]
+fn test_retained_inputs_stay_bounded() {
+    // In a long session, each side keeps between the limit and about twice
+    // the limit of each player's inputs, while the counts of inputs are
+    // unchanged.
+    let (mut host, mut guest) = retaining(20);
+    run_frames(&mut host, &mut guest, 500);
+
+    for first_retained in [
+        host.first_retained_input(HOST),
+        host.first_retained_input(GUEST),
+        guest.first_retained_input(HOST),
+        guest.first_retained_input(GUEST),
+    ] {
+        let num_retained = 500 - first_retained;
+        assert!(
+            (20..=40).contains(&num_retained),
+            "{num_retained} inputs retained"
+        );
+    }
+    assert_eq!(host.get_own_num_inputs(), 500);
+    assert_eq!(guest.get_own_num_inputs(), 500);
+    assert_eq!(host.get_snapshottable_sim_tick(), 500);
+}
+
+#[test]
+fn test_zero_retention_keeps_the_last_input_for_prediction() {
+    // A limit of 0 is raised to 1, so the last finalized input is never
+    // dropped and ticks past the end are still predicted from it rather than
+    // read as the default input.
+    let (mut host, mut guest) = retaining(0);
+    run_frames(&mut host, &mut guest, 200);
+
+    assert_eq!(host.max_retained_inputs(), Some(1));
+    assert_eq!(guest.get_peer_input_for_tick(HOST, 200), input(199));
+}
+
+#[test]
+fn test_retained_inputs_read_at_their_ticks() {
+    // The retained inputs are read at the same ticks as without dropping,
+    // on both sides.
+    let (mut host, mut guest) = retaining(20);
+    run_frames(&mut host, &mut guest, 300);
+
+    for tick in 280..300 {
+        assert_eq!(host.get_peer_input_for_tick(HOST, tick), input(tick));
+        assert_eq!(host.get_peer_input_for_tick(GUEST, tick), input(tick + 1));
+        assert_eq!(guest.get_peer_input_for_tick(HOST, tick), input(tick));
+        assert_eq!(guest.get_peer_input_for_tick(GUEST, tick), input(tick + 1));
+    }
+}
+
+#[test]
+fn test_unacked_inputs_are_kept_on_the_host() {
+    // The host keeps every input its guest hasn't acked, since it may still
+    // need to send them.
+    let (mut host, mut guest) = retaining(10);
+    for frame in 0..100 {
+        host.add_host_input_directly(input(frame));
+        guest.add_own_input(input(frame + 1));
+        host.rx_guest_input_slice(GUEST, guest.get_msg_own_input_slice());
+    }
+    // a stale ack with nothing finalized lets nothing be dropped
+    let ack = guest.get_msg_ack_finalization();
+    host.rx_finalized_ticks_observations(GUEST, ack);
+
+    assert_eq!(host.first_retained_input(HOST), 0);
+    assert_eq!(host.first_retained_input(GUEST), 0);
+}
+
+#[test]
+fn test_disconnected_guest_doesnt_hold_back_dropping() {
+    // In a 3 player session, a guest that disconnected without acking
+    // anything doesn't keep the host from dropping inputs the connected
+    // guest has acked.
+    let mut host = Host::new(3, 50, 5, 60).with_max_retained_inputs(10);
+    let mut guest = Guest::new(3, GUEST, 60).with_max_retained_inputs(10);
+    let other_guest = PlayerNum(2);
+    host.rx_guest_input_slice(other_guest, PlayerInputSlice::new_test(0, 100).into());
+    host.player_disconnected(other_guest);
+    for frame in 0..100 {
+        host.add_host_input_directly(input(frame));
+        guest.add_own_input(input(frame + 1));
+        host.rx_guest_input_slice(GUEST, guest.get_msg_own_input_slice());
+        for player in [HOST, GUEST, other_guest] {
+            guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(player));
+        }
+        let ack = guest.get_msg_ack_finalization();
+        host.rx_finalized_ticks_observations(GUEST, ack);
+    }
+
+    assert!(host.first_retained_input(HOST) > 0);
+}
+
+#[test]
+fn test_unread_channel_inputs_are_kept() {
+    // Inputs not yet queued to a finalized input channel aren't dropped, so
+    // a consumer that falls behind still receives every input.
+    let (mut host, mut guest) = retaining(10);
+    let host_rx = host.finalized_input_rx(HOST);
+    run_frames(&mut host, &mut guest, 100);
+    let mut num_received = 0;
+    while let Some((tick, received)) = host_rx.try_recv() {
+        assert_eq!(received, input(tick));
+        num_received += 1;
+    }
+    run_frames(&mut host, &mut guest, 400);
+    // more than the channel's depth is waiting, so it takes two feeds
+    for _ in 0..2 {
+        while let Some((tick, received)) = host_rx.try_recv() {
+            assert_eq!(received, input(tick));
+            num_received += 1;
+        }
+        host.feed_finalized_input_rxs();
+    }
+
+    assert_eq!(num_received, 500);
+}
+
+#[test]
+fn test_replay_covers_the_retained_inputs() {
+    // A replay can only hold the inputs still retained, so it starts at the
+    // first tick retained for every player.
+    let (mut host, mut guest) = retaining(20);
+    run_frames(&mut host, &mut guest, 200);
+
+    let replay = host.export_replay();
+    let first = host
+        .first_retained_input(HOST)
+        .max(host.first_retained_input(GUEST));
+    assert_eq!(replay.start_tick, first);
+    assert_eq!(replay.num_ticks(), 200 - first);
+    assert_eq!(
+        replay.inputs_by_player[usize::from(GUEST)][0],
+        input(first + 1).to_bytes()
+    );
+}
@@ -0,0 +1,142 @@
+use test_case::test_case;
+
+use crate::{
+    input_schedule::ScheduleInputError,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+fn input(x: u8) -> PlayerInput {
+    PlayerInput::new_test_simple(x)
+}
+
+#[test]
+fn test_guest_collects_scheduled_input_at_its_tick() {
+    // A scheduled input replaces the one passed in at its tick only, and is
+    // no longer scheduled once collected.
+    let mut guest = Guest::new(2, GUEST, 60);
+    guest.schedule_own_input(3, input(7)).unwrap();
+    assert_eq!(guest.num_scheduled_own_inputs(), 1);
+
+    for _ in 0..5 {
+        guest.add_own_input(input(1));
+    }
+
+    let own_inputs: Vec<_> = (0..5)
+        .map(|tick| guest.get_peer_input_for_tick(GUEST, tick))
+        .collect();
+    assert_eq!(
+        own_inputs,
+        vec![input(1), input(1), input(1), input(7), input(1)]
+    );
+    assert_eq!(guest.num_scheduled_own_inputs(), 0);
+}
+
+#[test]
+fn test_later_schedule_replaces_earlier() {
+    // Scheduling a second input for the same tick replaces the first.
+    let mut guest = Guest::new(2, GUEST, 60);
+    guest.schedule_own_input(1, input(7)).unwrap();
+    guest.schedule_own_input(1, input(8)).unwrap();
+    assert_eq!(guest.num_scheduled_own_inputs(), 1);
+
+    guest.add_own_input(input(1));
+    guest.add_own_input(input(1));
+
+    assert_eq!(guest.get_peer_input_for_tick(GUEST, 1), input(8));
+}
+
+#[test_case(0, ScheduleInputError::AlreadyCollected { tick: 0, next: 2 }; "first collected tick")]
+#[test_case(1, ScheduleInputError::AlreadyCollected { tick: 1, next: 2 }; "last collected tick")]
+#[test_case(10, ScheduleInputError::PastSessionEnd { tick: 10, end_tick: 10 }; "session end")]
+#[test_case(50, ScheduleInputError::PastSessionEnd { tick: 50, end_tick: 10 }; "past session end")]
+fn test_unschedulable_ticks_are_refused(tick: u32, expected: ScheduleInputError) {
+    // Ticks whose own input has been collected, and ticks past the end of
+    // the session, can't be scheduled.
+    let mut guest = Guest::new(2, GUEST, 60).with_max_session_ticks(10);
+    guest.add_own_input(input(1));
+    guest.add_own_input(input(1));
+
+    assert_eq!(guest.schedule_own_input(tick, input(7)), Err(expected));
+    assert_eq!(guest.num_scheduled_own_inputs(), 0);
+}
+
+#[test]
+fn test_host_fill_uses_scheduled_input_for_one_tick() {
+    // When the host fills several ticks at once, only the scheduled tick gets
+    // the scheduled input; the ticks after it get the input passed in.
+    let mut host = Host::new(2, 50, 5, 64);
+    host.schedule_own_input(2, input(7)).unwrap();
+
+    host.add_host_input_to_fill_needed(input(1), 4.0 / 64.0);
+
+    let own_inputs: Vec<_> = (0..4)
+        .map(|tick| host.get_peer_input_for_tick(HOST_PLAYER_NUM, tick))
+        .collect();
+    assert_eq!(own_inputs, vec![input(1), input(1), input(7), input(1)]);
+}
+
+#[test]
+fn test_host_with_input_delay_places_scheduled_input_at_its_tick() {
+    // With input delay, a scheduled input still lands on the tick it was
+    // scheduled for, and ticks padded with defaults can't be scheduled.
+    let mut host = Host::new(2, 50, 5, 64).with_input_delay_ticks(2);
+    host.add_host_input_to_fill_needed(input(1), 1.0 / 64.0);
+    assert_eq!(
+        host.schedule_own_input(2, input(9)),
+        Err(ScheduleInputError::AlreadyCollected { tick: 2, next: 3 })
+    );
+    host.schedule_own_input(4, input(7)).unwrap();
+
+    host.add_host_input_to_fill_needed(input(1), 3.0 / 64.0);
+
+    assert_eq!(host.get_peer_input_for_tick(HOST_PLAYER_NUM, 3), input(1));
+    assert_eq!(host.get_peer_input_for_tick(HOST_PLAYER_NUM, 4), input(7));
+    assert_eq!(host.get_peer_input_for_tick(HOST_PLAYER_NUM, 5), input(1));
+}
+
+#[test]
+fn test_schedule_with_start_tick_uses_absolute_ticks() {
+    // Scheduled ticks are absolute sim ticks: ticks before the start tick are
+    // already settled, and later ones land where the sim reads them.
+    let mut host = Host::new(2, 50, 5, 60).with_start_tick(100);
+    assert_eq!(
+        host.schedule_own_input(50, input(7)),
+        Err(ScheduleInputError::AlreadyCollected {
+            tick: 50,
+            next: 100
+        })
+    );
+    host.schedule_own_input(101, input(7)).unwrap();
+
+    for _ in 0..3 {
+        host.add_host_input_directly(input(1));
+    }
+
+    assert_eq!(host.get_peer_input_for_tick(HOST_PLAYER_NUM, 100), input(1));
+    assert_eq!(host.get_peer_input_for_tick(HOST_PLAYER_NUM, 101), input(7));
+}
+
+#[test]
+fn test_cleared_schedule_collects_inputs_passed_in() {
+    // Clearing the schedule drops every scheduled input.
+    let mut guest = Guest::new(2, GUEST, 60);
+    guest.schedule_own_input(0, input(7)).unwrap();
+    guest.schedule_own_input(1, input(8)).unwrap();
+
+    guest.clear_scheduled_own_inputs();
+    guest.add_own_input(input(1));
+    guest.add_own_input(input(1));
+
+    assert_eq!(guest.num_scheduled_own_inputs(), 0);
+    assert_eq!(guest.get_peer_input_for_tick(GUEST, 0), input(1));
+    assert_eq!(guest.get_peer_input_for_tick(GUEST, 1), input(1));
+}
@@ -0,0 +1,168 @@
+use crate::{
+    input_messages::{MsgPayload, PreSimSync},
+    input_schema::InputSchemaMismatch,
+    prelude::*,
+    tests::demo_input_struct::PlayerInput,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+const OTHER_SCHEMA: u32 = 0x5eed;
+
+fn pre_sim_sync_with_schema(input_schema_id: u32) -> MsgPayload<PlayerInput> {
+    PreSimSync {
+        host_tick_countdown: 3,
+        start_tick: 100,
+        input_schema_id,
+        ..Default::default()
+    }
+    .into()
+}
+
+#[test]
+fn test_matching_schemas_are_accepted() {
+    // A guest with the host's schema accepts its countdown and replies with
+    // its own schema, which the host accepts; neither raises an event.
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+    let reply = guest.get_msg_input_schema();
+    assert!(matches!(
+        reply,
+        MsgPayload::GuestToHostInputSchema(PlayerInput::SCHEMA_ID)
+    ));
+    host.rx_guest_input_schema(GUEST, reply).unwrap();
+
+    assert_eq!(guest.get_host_tick(), Some(-3));
+    assert!(host.drain_events().is_empty());
+    assert!(guest.drain_events().is_empty());
+}
+
+#[test]
+fn test_guest_rejects_host_with_other_schema() {
+    // A countdown from a host with another schema is rejected with the two
+    // ids, and neither the countdown nor the host's config is adopted.
+    let mut guest = Guest::new(2, GUEST, 60);
+    let mismatch = InputSchemaMismatch {
+        peer: PlayerNum(0),
+        ours: PlayerInput::SCHEMA_ID,
+        theirs: OTHER_SCHEMA,
+    };
+
+    assert_eq!(
+        guest.rx_pre_sim_sync(pre_sim_sync_with_schema(OTHER_SCHEMA)),
+        Err(mismatch)
+    );
+    assert_eq!(guest.get_host_tick(), None);
+    assert_eq!(guest.start_tick(), 0);
+    assert_eq!(
+        guest.drain_events(),
+        vec![InputMgrEvent::InputSchemaMismatch {
+            peer: PlayerNum(0),
+            ours: PlayerInput::SCHEMA_ID,
+            theirs: OTHER_SCHEMA,
+        }]
+    );
+
+    guest
+        .rx_pre_sim_sync(pre_sim_sync_with_schema(PlayerInput::SCHEMA_ID))
+        .unwrap();
+    assert_eq!(guest.start_tick(), 100);
+}
+
+#[test]
+fn test_host_rejects_guest_with_other_schema() {
+    // A guest schema that differs from the host's is reported as an error
+    // and an event naming the guest.
+    let mut host = Host::new(3, 50, 5, 60);
+
+    assert_eq!(
+        host.rx_guest_input_schema(
+            PlayerNum(2),
+            MsgPayload::GuestToHostInputSchema(OTHER_SCHEMA)
+        ),
+        Err(InputSchemaMismatch {
+            peer: PlayerNum(2),
+            ours: PlayerInput::SCHEMA_ID,
+            theirs: OTHER_SCHEMA,
+        })
+    );
+    assert_eq!(
+        host.drain_events(),
+        vec![InputMgrEvent::InputSchemaMismatch {
+            peer: PlayerNum(2),
+            ours: PlayerInput::SCHEMA_ID,
+            theirs: OTHER_SCHEMA,
+        }]
+    );
+}
+
+#[test]
+fn test_session_guest_replies_with_schema_or_rejects() {
+    // A guest session replies to a matching countdown with its schema (and
+    // its capabilities), and fails to receive one with another schema.
+    let mut guest: Session<PlayerInput> = Guest::new(2, GUEST, 60).into();
+
+    assert_eq!(
+        guest
+            .rx_msg(PlayerNum(0), pre_sim_sync_with_schema(OTHER_SCHEMA))
+            .unwrap_err(),
+        SessionRxError::InputSchemaMismatch(InputSchemaMismatch {
+            peer: PlayerNum(0),
+            ours: PlayerInput::SCHEMA_ID,
+            theirs: OTHER_SCHEMA,
+        })
+    );
+
+    let replies = guest
+        .rx_msg(
+            PlayerNum(0),
+            pre_sim_sync_with_schema(PlayerInput::SCHEMA_ID),
+        )
+        .unwrap();
+    assert_eq!(replies.len(), 2);
+    assert_eq!(replies[0].0, Recipient::Player(PlayerNum(0)));
+    assert!(matches!(
+        replies[0].1,
+        MsgPayload::GuestToHostInputSchema(PlayerInput::SCHEMA_ID)
+    ));
+    assert!(matches!(
+        replies[1].1,
+        MsgPayload::GuestToHostCapabilities(_)
+    ));
+}
+
+#[test]
+fn test_session_host_mutes_guest_with_other_schema() {
+    // A host session mutes a guest with another schema and broadcasts the
+    // mute, while a guest with a matching schema is left alone.
+    let mut host: Session<PlayerInput> = Host::new(3, 50, 5, 60).into();
+
+    let replies = host
+        .rx_msg(
+            PlayerNum(1),
+            MsgPayload::GuestToHostInputSchema(PlayerInput::SCHEMA_ID),
+        )
+        .unwrap();
+    assert!(replies.is_empty());
+
+    let replies = host
+        .rx_msg(
+            PlayerNum(2),
+            MsgPayload::GuestToHostInputSchema(OTHER_SCHEMA),
+        )
+        .unwrap();
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0].0, Recipient::AllPeers);
+    assert!(matches!(
+        replies[0].1,
+        MsgPayload::HostToLobbyPlayerMuted(_)
+    ));
+
+    let host = host.as_host().unwrap();
+    assert_eq!(host.muted_from_tick(PlayerNum(1)), None);
+    assert_eq!(host.muted_from_tick(PlayerNum(2)), Some(0));
+}
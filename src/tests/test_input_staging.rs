@@ -0,0 +1,91 @@
+use serde::Serialize;
+
+use crate::{
+    input_staging::InputStagingQueue, input_trait::SimInput,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr, multiplayer_input_manager_host::HostInputMgr,
+    util_types::PlayerNum,
+};
+
+/// The number of button presses since the previous input.
+#[derive(Default, Clone, Copy, Debug, Serialize)]
+struct PressCount(u8);
+
+impl SimInput for PressCount {
+    type Bytes = u8;
+    fn to_bytes(&self) -> u8 {
+        self.0
+    }
+    fn from_bytes(bytes: u8) -> Self {
+        Self(bytes)
+    }
+}
+
+fn press_queue() -> InputStagingQueue<(), PressCount> {
+    InputStagingQueue::new(|presses: &[()]| PressCount(presses.len() as u8))
+}
+
+#[test]
+fn test_host_reduces_staged_events_into_each_needed_input() {
+    // All events staged since the last call are reduced into one input, which
+    // fills every tick the host needs, and the queue is emptied.
+    let mut host = MultiplayerInputManager::<PressCount, HostInputMgr>::new(2, 50, 5, 10);
+    let mut queue = press_queue();
+    for _ in 0..3 {
+        queue.push(());
+    }
+
+    assert_eq!(
+        host.add_host_staged_input_to_fill_needed(&mut queue, 0.2),
+        2
+    );
+    assert!(queue.is_empty());
+    assert_eq!(host.get_peer_input_for_tick(PlayerNum(0), 0).0, 3);
+    assert_eq!(host.get_peer_input_for_tick(PlayerNum(0), 1).0, 3);
+
+    assert_eq!(
+        host.add_host_staged_input_to_fill_needed(&mut queue, 0.1),
+        1
+    );
+    assert_eq!(host.get_peer_input_for_tick(PlayerNum(0), 2).0, 0);
+}
+
+#[test]
+fn test_host_keeps_staged_events_when_no_input_is_needed() {
+    // If no tick needs an input yet, the staged events carry over to the next
+    // call instead of being dropped.
+    let mut host = MultiplayerInputManager::<PressCount, HostInputMgr>::new(2, 50, 5, 10);
+    let mut queue = press_queue();
+    queue.push(());
+
+    assert_eq!(
+        host.add_host_staged_input_to_fill_needed(&mut queue, 0.0),
+        0
+    );
+    assert_eq!(queue.len(), 1);
+
+    queue.push(());
+    assert_eq!(
+        host.add_host_staged_input_to_fill_needed(&mut queue, 0.1),
+        1
+    );
+    assert_eq!(host.get_peer_input_for_tick(PlayerNum(0), 0).0, 2);
+}
+
+#[test]
+fn test_guest_reduces_staged_events_into_needed_inputs() {
+    // Before syncing, a guest needs one input per call; the staged events are
+    // reduced into it.
+    let mut guest = MultiplayerInputManager::<PressCount, GuestInputMgr>::new(2, 1.into(), 60);
+    let mut queue = press_queue();
+    queue.push(());
+    queue.push(());
+
+    assert_eq!(guest.add_own_staged_input(&mut queue), 1);
+    assert!(queue.is_empty());
+    assert_eq!(guest.add_own_staged_input(&mut queue), 1);
+
+    assert_eq!(guest.get_own_num_inputs(), 2);
+    assert_eq!(guest.get_peer_input_for_tick(PlayerNum(1), 0).0, 2);
+    assert_eq!(guest.get_peer_input_for_tick(PlayerNum(1), 1).0, 0);
+}
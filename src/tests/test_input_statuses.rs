@@ -0,0 +1,141 @@
+use test_case::test_case;
+
+use crate::{
+    input_buffer::InputStatus,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    session::Session,
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+const START_TICK: u32 = 10;
+const MAX_INPUTS_TO_PREDICT: u32 = 3;
+
+/// A 2 player host starting at `START_TICK`, with 4 finalized host inputs and none from the guest.
+fn host_with_inputs() -> Host {
+    let mut host = Host::new(2, 50, MAX_INPUTS_TO_PREDICT, 60).with_start_tick(START_TICK);
+    for x in 0..4 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(x));
+    }
+    host
+}
+
+#[test_case(0, InputStatus::Finalized { num_finalized: 4 }; "finalized")]
+#[test_case(4, InputStatus::NotReceived { num_finalized: 4, predicted: true }; "first predicted tick")]
+#[test_case(6, InputStatus::NotReceived { num_finalized: 4, predicted: true }; "last predicted tick")]
+#[test_case(7, InputStatus::NotReceived { num_finalized: 4, predicted: false }; "beyond the prediction window")]
+fn test_status_carries_finalized_count_and_prediction(offset: u32, expected: InputStatus) {
+    // Statuses carry the player's finalized input count, and whether a tick
+    // not yet received is predicted from the player's last input.
+    let host = host_with_inputs();
+
+    assert_eq!(
+        host.get_input_statuses(START_TICK + offset)[0],
+        (HOST_PLAYER_NUM, expected)
+    );
+}
+
+#[test]
+fn test_player_without_inputs_is_not_predicted() {
+    // A player with no inputs yet has nothing to carry forward, so its ticks
+    // use the default input.
+    let host = host_with_inputs();
+
+    assert_eq!(
+        host.get_input_statuses(START_TICK)[1],
+        (
+            GUEST,
+            InputStatus::NotReceived {
+                num_finalized: 0,
+                predicted: false
+            }
+        )
+    );
+}
+
+#[test]
+fn test_status_helpers() {
+    // The helpers read the finalized count from any variant, and only
+    // predicted ticks count as predicted.
+    let finalized = InputStatus::Finalized { num_finalized: 3 };
+    let non_final = InputStatus::NonFinal { num_finalized: 2 };
+    let predicted = InputStatus::NotReceived {
+        num_finalized: 1,
+        predicted: true,
+    };
+    let default = InputStatus::NotReceived {
+        num_finalized: 0,
+        predicted: false,
+    };
+
+    assert_eq!(
+        [finalized, non_final, predicted, default].map(|s| s.num_finalized()),
+        [3, 2, 1, 0]
+    );
+    assert_eq!(
+        [finalized, non_final, predicted, default].map(|s| s.is_finalized()),
+        [true, false, false, false]
+    );
+    assert_eq!(
+        [finalized, non_final, predicted, default].map(|s| s.is_predicted()),
+        [false, false, true, false]
+    );
+}
+
+#[test_case(START_TICK, START_TICK + 8; "after the start tick")]
+#[test_case(START_TICK - 3, START_TICK + 2; "spanning the start tick")]
+#[test_case(START_TICK + 2, START_TICK + 2; "empty range")]
+fn test_range_matches_per_tick_statuses(start: u32, end: u32) {
+    // The statuses of a range of ticks, per player, match those returned
+    // one tick at a time.
+    let host = host_with_inputs();
+
+    let range = host.get_input_statuses_range(start, end);
+
+    let expected: Vec<_> = [HOST_PLAYER_NUM, GUEST]
+        .into_iter()
+        .map(|player_num| {
+            let statuses = (start..end)
+                .map(|tick| host.get_input_statuses(tick)[player_num.0 as usize].1)
+                .collect();
+            (player_num, statuses)
+        })
+        .collect();
+    assert_eq!(range, expected);
+}
+
+#[test]
+fn test_session_range_on_guest() {
+    // A guest session reports its own unfinalized inputs as non-final, and
+    // predicts the host's missing ones from nothing.
+    let mut guest = Guest::new(2, GUEST, 60);
+    for x in 0..2 {
+        guest.add_own_input(PlayerInput::new_test_simple(x));
+    }
+    let session: Session<PlayerInput> = guest.into();
+
+    let range = session.get_input_statuses_range(0, 2);
+
+    assert_eq!(
+        range,
+        vec![
+            (
+                HOST_PLAYER_NUM,
+                vec![
+                    InputStatus::NotReceived {
+                        num_finalized: 0,
+                        predicted: false
+                    };
+                    2
+                ]
+            ),
+            (GUEST, vec![InputStatus::NonFinal { num_finalized: 0 }; 2]),
+        ]
+    );
+}
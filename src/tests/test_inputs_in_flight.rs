@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr, InputsInFlight},
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST_1: PlayerNum = PlayerNum(1);
+const GUEST_2: PlayerNum = PlayerNum(2);
+
+fn ack(host: &mut Host, guest: PlayerNum, acked: [u32; 3]) {
+    let seen = HashMap::from([
+        (HOST_PLAYER_NUM, acked[0]),
+        (GUEST_1, acked[1]),
+        (GUEST_2, acked[2]),
+    ]);
+    host.rx_finalized_ticks_observations(
+        guest,
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(seen)),
+    );
+}
+
+/// A 3 player host with 10 inputs of its own and 6 finalized inputs from each guest.
+fn host_with_finalized_inputs() -> Host {
+    let mut host = Host::new(3, 50, 5, 60);
+    for _ in 0..10 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    for guest in [GUEST_1, GUEST_2] {
+        host.rx_guest_input_slice(
+            guest,
+            MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 6)),
+        );
+    }
+    host
+}
+
+#[test]
+fn test_downstream_counts_unacked_finalized_inputs_across_peers() {
+    // Downstream, each guest has in flight every finalized input it hasn't
+    // acked, summed over all peers; a guest that has acked everything has
+    // nothing in flight.
+    let mut host = host_with_finalized_inputs();
+    ack(&mut host, GUEST_1, [10, 6, 6]);
+    ack(&mut host, GUEST_2, [4, 6, 1]);
+
+    assert_eq!(
+        host.inputs_in_flight_by_guest(),
+        vec![
+            (
+                GUEST_1,
+                InputsInFlight {
+                    upstream: 0,
+                    downstream: 0,
+                }
+            ),
+            (
+                GUEST_2,
+                InputsInFlight {
+                    upstream: 0,
+                    downstream: 6 + 5,
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_downstream_before_any_acks() {
+    // Until a guest acks, every finalized input is in flight to it.
+    let host = host_with_finalized_inputs();
+    assert_eq!(host.inputs_in_flight(GUEST_1).downstream, 10 + 6 + 6);
+}
+
+#[test]
+fn test_upstream_counts_inputs_held_for_review() {
+    // Upstream, the host counts a guest's inputs it has received but is still
+    // holding in the review window, until they are finalized.
+    let mut host = Host::new(2, 50, 5, 60).with_finalization_delay_ticks(3);
+    host.rx_guest_input_slice(
+        GUEST_1,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 4)),
+    );
+    assert_eq!(host.inputs_in_flight(GUEST_1).upstream, 4);
+
+    for _ in 0..3 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    assert_eq!(host.inputs_in_flight(GUEST_1).upstream, 0);
+}
+
+#[test]
+fn test_guest_counts_own_inputs_not_yet_finalized_by_host() {
+    // A guest's own inputs are in flight from when it collects them until the
+    // host's finalized slice for them arrives.
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST_1, 60);
+    for _ in 0..5 {
+        guest.add_own_input(PlayerInput::default());
+    }
+    assert_eq!(guest.num_own_inputs_in_flight(), 5);
+
+    host.rx_guest_input_slice(GUEST_1, guest.get_msg_own_input_slice());
+    guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(GUEST_1));
+    assert_eq!(guest.num_own_inputs_in_flight(), 0);
+}
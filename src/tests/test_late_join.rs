@@ -0,0 +1,191 @@
+use crate::{
+    events::InputMgrEvent,
+    input_messages::{MsgPayload, PlayerJoined},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    session::{Recipient, Session},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+const JOINER: PlayerNum = PlayerNum(2);
+
+fn input(x: u32) -> PlayerInput {
+    PlayerInput::new_test_simple((x % 100) as u8 + 1)
+}
+
+/// A 2 player host and guest which have exchanged `num_frames` frames of inputs.
+fn two_player_session(num_frames: u32) -> (Host, Guest) {
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    for frame in 0..num_frames {
+        host.add_host_input_directly(input(frame));
+        guest.add_own_input(input(frame));
+    }
+    exchange(&mut host, &mut guest);
+    (host, guest)
+}
+
+/// Sends the guest's inputs to the host, then every player's finalized slice back to the guest.
+fn exchange(host: &mut Host, guest: &mut Guest) {
+    host.rx_guest_input_slice(GUEST, guest.get_msg_own_input_slice());
+    for player in PlayerNum::iter(host.get_num_players()) {
+        guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(player));
+    }
+    host.rx_finalized_ticks_observations(GUEST, guest.get_msg_ack_finalization());
+}
+
+#[test]
+fn test_host_fills_new_player_with_defaults() {
+    // A player added mid-session has default inputs finalized up to the
+    // host's tick, so the snapshottable tick doesn't fall back.
+    let (mut host, _) = two_player_session(10);
+    let snapshottable_tick = host.get_snapshottable_sim_tick();
+
+    let msg = host.add_player(JOINER).unwrap();
+
+    assert!(matches!(
+        msg,
+        MsgPayload::HostToLobbyPlayerJoined(PlayerJoined {
+            player_num: JOINER,
+            first_input: 10,
+        })
+    ));
+    assert_eq!(host.get_num_players(), 3);
+    assert_eq!(host.get_peer_num_final_inputs(JOINER), 10);
+    assert_eq!(
+        host.get_peer_input_for_tick(JOINER, 9),
+        PlayerInput::default()
+    );
+    assert_eq!(host.get_snapshottable_sim_tick(), snapshottable_tick);
+}
+
+#[test]
+fn test_host_adds_players_only_in_the_next_seat() {
+    // Seats are numbered consecutively, so only the seat after the last one
+    // can be added.
+    let (mut host, _) = two_player_session(3);
+
+    assert!(host.add_player(PlayerNum(1)).is_err());
+    assert!(host.add_player(PlayerNum(3)).is_err());
+    assert_eq!(host.get_num_players(), 2);
+    assert!(host.add_player(JOINER).is_ok());
+}
+
+#[test]
+fn test_new_player_inputs_are_finalized_after_the_join() {
+    // The new player's own inputs are accepted from the join on.
+    let (mut host, _) = two_player_session(10);
+    let msg = host.add_player(JOINER).unwrap();
+    let mut joiner = Guest::new(3, JOINER, 60);
+    joiner.rx_player_joined(msg);
+    for frame in 0..4 {
+        joiner.add_own_input(input(frame));
+    }
+
+    host.rx_guest_input_slice(JOINER, joiner.get_msg_own_input_slice());
+
+    assert_eq!(host.get_peer_num_final_inputs(JOINER), 14);
+    assert_eq!(host.get_peer_input_for_tick(JOINER, 10), input(0));
+}
+
+#[test]
+fn test_existing_guest_adds_the_player_and_its_defaults() {
+    // A guest that didn't know the player adds them, finalizes the same
+    // defaults as the host, and tells the game where they join.
+    let (mut host, mut guest) = two_player_session(10);
+    let msg = host.add_player(JOINER).unwrap();
+
+    guest.rx_player_joined(msg);
+
+    assert_eq!(guest.get_num_players(), 3);
+    assert_eq!(guest.get_peer_num_final_inputs(JOINER), 10);
+    assert_eq!(
+        guest.drain_events(),
+        vec![InputMgrEvent::PlayerJoined {
+            player_num: JOINER,
+            first_tick: 10,
+        }]
+    );
+}
+
+#[test]
+fn test_repeated_join_is_only_reported_once() {
+    // A join message that arrives again changes nothing and raises no second
+    // event.
+    let (mut host, mut guest) = two_player_session(10);
+    let msg = host.add_player(JOINER).unwrap();
+    guest.rx_player_joined(msg.clone());
+    guest.drain_events();
+
+    guest.rx_player_joined(msg);
+
+    assert_eq!(guest.get_num_players(), 3);
+    assert_eq!(guest.drain_events(), vec![]);
+}
+
+#[test]
+fn test_joining_guest_starts_its_inputs_at_the_join() {
+    // The new guest pads its own inputs up to the join, so that the first
+    // input it collects is the first one the host accepts from it.
+    let (mut host, _) = two_player_session(10);
+    let msg = host.add_player(JOINER).unwrap();
+    let mut joiner = Guest::new(3, JOINER, 60);
+
+    joiner.rx_player_joined(msg);
+
+    assert_eq!(joiner.get_own_num_inputs(), 10);
+    assert_eq!(joiner.get_peer_num_final_inputs(JOINER), 10);
+    assert_eq!(joiner.drain_events(), vec![]);
+}
+
+#[test]
+fn test_existing_guest_receives_the_new_players_inputs() {
+    // After the join, the new player's finalized inputs reach the existing
+    // guest like any other player's.
+    let (mut host, mut guest) = two_player_session(10);
+    let msg = host.add_player(JOINER).unwrap();
+    let mut joiner = Guest::new(3, JOINER, 60);
+    guest.rx_player_joined(msg.clone());
+    joiner.rx_player_joined(msg);
+    for frame in 10..15 {
+        host.add_host_input_directly(input(frame));
+        guest.add_own_input(input(frame));
+        joiner.add_own_input(input(frame + 50));
+    }
+
+    host.rx_guest_input_slice(JOINER, joiner.get_msg_own_input_slice());
+    exchange(&mut host, &mut guest);
+
+    assert_eq!(guest.get_peer_num_final_inputs(JOINER), 15);
+    assert_eq!(guest.get_peer_input_for_tick(JOINER, 12), input(62));
+    assert_eq!(guest.get_snapshottable_sim_tick(), 15);
+}
+
+#[test]
+fn test_session_records_slices_for_the_new_player() {
+    // A host session keeps accepting acks and recording the new player's
+    // slices, and a guest session adds the player and acks their defaults.
+    let (host, guest) = two_player_session(10);
+    let mut host = Session::from(host);
+    let mut guest = Session::from(guest);
+    let msg = host.as_host_mut().unwrap().add_player(JOINER).unwrap();
+
+    let replies = guest.rx_msg(PlayerNum(0), msg).unwrap();
+    let slice = host.as_host().unwrap().get_msg_finalized_slice(JOINER);
+    host.as_host_mut()
+        .unwrap()
+        .record_msg_sent(Recipient::AllPeers, &slice);
+    for (_, reply) in replies {
+        host.rx_msg(GUEST, reply).unwrap();
+    }
+
+    let acked = host.as_host().unwrap().observation_matrix();
+    assert_eq!(acked[0].0, GUEST);
+    assert!(acked[0].1.contains(&(JOINER, 10)));
+}
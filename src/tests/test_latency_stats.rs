@@ -0,0 +1,92 @@
+use crate::{
+    input_messages::MsgPayload,
+    latency_stats::{LatencySummary, MAX_TRACKED_LATENCY_TICKS},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+fn host_with_inputs(num_inputs: u32) -> MultiplayerInputManager<PlayerInput, HostInputMgr> {
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(3, 500, 5, 60);
+    for _ in 0..num_inputs {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host
+}
+
+fn guest_inputs(start: u32, num_inputs: u32) -> MsgPayload<PlayerInput> {
+    MsgPayload::PeerInputs(PlayerInputSlice::new_test(start, num_inputs))
+}
+
+#[test]
+fn test_guest_latency_percentiles() {
+    // Inputs 0..5 arriving at host tick 10 were finalized 10, 9, 8, 7, and 6
+    // ticks late.
+    let mut host = host_with_inputs(10);
+    host.rx_guest_input_slice(PlayerNum(1), guest_inputs(0, 5));
+
+    assert_eq!(
+        host.finalization_latency_summary(PlayerNum(1)),
+        Some(LatencySummary {
+            num_samples: 5,
+            mean: 8.0,
+            p50: 8,
+            p90: 10,
+            p99: 10,
+            max: 10,
+        })
+    );
+}
+
+#[test]
+fn test_host_inputs_have_zero_latency() {
+    // The host finalizes its own inputs on their own tick.
+    let host = host_with_inputs(10);
+    let summary = host.finalization_latency_summary(PlayerNum(0)).unwrap();
+    assert_eq!((summary.num_samples, summary.max), (10, 0));
+}
+
+#[test]
+fn test_resent_inputs_are_counted_once() {
+    // Only newly finalized inputs are sampled, so resent slices don't skew the
+    // distribution.
+    let mut host = host_with_inputs(10);
+    host.rx_guest_input_slice(PlayerNum(1), guest_inputs(0, 5));
+    host.add_host_input_directly(PlayerInput::default());
+    host.rx_guest_input_slice(PlayerNum(1), guest_inputs(0, 6));
+
+    let summary = host.finalization_latency_summary(PlayerNum(1)).unwrap();
+    assert_eq!((summary.num_samples, summary.p50), (6, 7));
+}
+
+#[test]
+fn test_latency_is_clamped() {
+    // Latencies beyond the tracked maximum are counted as the maximum.
+    let mut host = host_with_inputs(MAX_TRACKED_LATENCY_TICKS + 50);
+    host.rx_guest_input_slice(PlayerNum(1), guest_inputs(0, 1));
+    let summary = host.finalization_latency_summary(PlayerNum(1)).unwrap();
+    assert_eq!(summary.max, MAX_TRACKED_LATENCY_TICKS);
+}
+
+#[test]
+fn test_fairness_spread() {
+    // The spread is the gap between the highest and lowest median latency,
+    // and only players with finalized inputs are in the report.
+    let mut host = host_with_inputs(10);
+    assert_eq!(host.fairness_report().len(), 1);
+
+    host.rx_guest_input_slice(PlayerNum(1), guest_inputs(0, 5));
+    host.rx_guest_input_slice(PlayerNum(2), guest_inputs(0, 10));
+    assert_eq!(host.fairness_report().len(), 3);
+    assert_eq!(host.fairness_spread_ticks(), Some(8));
+}
+
+#[test]
+fn test_no_samples_before_finalization() {
+    // Players with no finalized inputs have no summary, and a host with no
+    // finalized inputs at all has no spread.
+    let host = host_with_inputs(0);
+    assert_eq!(host.finalization_latency_summary(PlayerNum(1)), None);
+    assert_eq!(host.fairness_spread_ticks(), None);
+}
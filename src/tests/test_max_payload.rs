@@ -0,0 +1,249 @@
+use test_case::test_case;
+
+use crate::{
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    session::Session,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+fn slice(start: u32, len: u32) -> PlayerInputSlice<PlayerInput> {
+    PlayerInputSlice::from_fn(start, len, |index| {
+        PlayerInput::new_test_simple(index as u8)
+    })
+}
+
+/// The start and length of each part's slice.
+fn ranges(parts: &[MsgPayload<PlayerInput>]) -> Vec<(u32, u32)> {
+    parts
+        .iter()
+        .map(|msg| match msg {
+            MsgPayload::PeerInputs(slice) => (slice.start, slice.len()),
+            MsgPayload::HostToLobbyFinalizedSlice(slice) => {
+                (slice.inputs.start, slice.inputs.len())
+            }
+            msg => panic!("expected a slice, got {:?}", msg.kind()),
+        })
+        .collect()
+}
+
+#[test_case(1_000, vec![(3, 40)]; "slice that fits isn't split")]
+#[test_case(60, vec![(3, 10), (13, 10), (23, 10), (33, 10)]; "slice is split into consecutive parts")]
+#[test_case(1, (3..43).map(|start| (start, 1)).collect(); "parts keep at least one input")]
+fn test_split_peer_inputs(max_bytes: usize, expected: Vec<(u32, u32)>) {
+    // An input slice too long for the max payload is split into consecutive
+    // slices, each within the max unless it holds a single input.
+    let parts = MsgPayload::PeerInputs(slice(3, 40)).split_to_max_bytes(max_bytes);
+
+    assert_eq!(ranges(&parts), expected);
+    for (part, (_, len)) in parts.iter().zip(ranges(&parts)) {
+        assert!(part.to_bytes().len() <= max_bytes || len == 1);
+    }
+}
+
+#[test]
+fn test_split_finalized_slice_keeps_player_and_host_tick() {
+    // Every part of a finalized slice is for the same player and host tick.
+    let msg: MsgPayload<PlayerInput> = HostFinalizedSlice {
+        player_num: PlayerNum(2),
+        host_tick: 9,
+        inputs: slice(0, 40),
+    }
+    .into();
+
+    let parts = msg.split_to_max_bytes(60);
+
+    assert_eq!(ranges(&parts), vec![(0, 10), (10, 10), (20, 10), (30, 10)]);
+    for part in parts {
+        let MsgPayload::HostToLobbyFinalizedSlice(slice) = part else {
+            panic!("expected a finalized slice");
+        };
+        assert_eq!((slice.player_num, slice.host_tick), (PlayerNum(2), 9));
+    }
+}
+
+#[test]
+fn test_other_messages_are_not_split() {
+    // Only input slices are split.
+    let parts = MsgPayload::<PlayerInput>::GuestToHostPing(1).split_to_max_bytes(1);
+
+    assert_eq!(parts.len(), 1);
+    assert!(matches!(parts[0], MsgPayload::GuestToHostPing(1)));
+}
+
+#[test]
+fn test_host_reassembles_guest_slice_parts() {
+    // A guest's slice, split after a stall, is reassembled by the host
+    // applying each part in turn.
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60).with_max_payload_bytes(60);
+    for x in 0..30 {
+        guest.add_own_input(PlayerInput::new_test_simple(x));
+    }
+
+    let parts = guest.get_msgs_own_input_slice();
+    assert!(parts.len() > 1);
+    for part in parts {
+        host.rx_guest_input_slice(GUEST, part);
+    }
+
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 30);
+    assert_eq!(
+        host.get_peer_input_for_tick(GUEST, 29),
+        PlayerInput::new_test_simple(29)
+    );
+}
+
+#[test]
+fn test_part_after_a_lost_part_is_sent_again() {
+    // A part that arrives after a lost one is ignored, and the next split
+    // slice still covers it.
+    let mut host = Host::new(2, 50, 5, 60).with_max_payload_bytes(60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    for x in 0..30 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(x));
+    }
+
+    let parts = host.get_msgs_finalized_slice(HOST_PLAYER_NUM);
+    for part in parts.into_iter().skip(1) {
+        guest.rx_final_peer_input_slice_from_host(part);
+    }
+    assert_eq!(guest.get_peer_num_final_inputs(HOST_PLAYER_NUM), 0);
+    for part in host.get_msgs_finalized_slice(HOST_PLAYER_NUM) {
+        guest.rx_final_peer_input_slice_from_host(part);
+    }
+
+    assert_eq!(guest.get_peer_num_final_inputs(HOST_PLAYER_NUM), 30);
+}
+
+#[test]
+fn test_session_splits_own_input_slice() {
+    // With a max payload, a session returns an oversized own input slice as
+    // several messages.
+    let mut guest: Session<PlayerInput> =
+        Guest::new(2, GUEST, 60).with_max_payload_bytes(60).into();
+
+    let mut outgoing = vec![];
+    for x in 0..30 {
+        outgoing = guest.add_own_input(PlayerInput::new_test_simple(x), 0.0);
+    }
+
+    assert!(outgoing.len() > 1);
+    assert!(outgoing.iter().all(|(_, msg)| msg.to_bytes().len() <= 60));
+}
+
+/// Every message in `parts`, with the messages of batches listed in turn.
+fn unbatched(parts: Vec<MsgPayload<PlayerInput>>) -> Vec<MsgPayload<PlayerInput>> {
+    parts
+        .into_iter()
+        .flat_map(|msg| match msg {
+            MsgPayload::Batch(msgs) => msgs,
+            msg => vec![msg],
+        })
+        .collect()
+}
+
+#[test]
+fn test_oversized_batch_is_repacked_within_max() {
+    // A batch too long for the max payload has its slices split, and is
+    // repacked into several batches within the max, keeping the order of
+    // every message.
+    let batch = MsgPayload::batch([
+        MsgPayload::PeerInputs(slice(0, 40)),
+        MsgPayload::GuestToHostPing(1),
+        MsgPayload::PeerInputs(slice(40, 40)),
+    ]);
+
+    let parts = batch.split_to_max_bytes(100);
+
+    assert!(parts.len() > 1);
+    assert!(parts.iter().all(|msg| msg.to_bytes().len() <= 100));
+    let msgs = unbatched(parts);
+    assert!(matches!(msgs[2], MsgPayload::GuestToHostPing(1)));
+    assert_eq!(
+        ranges(&[&msgs[..2], &msgs[3..]].concat()),
+        vec![(0, 20), (20, 20), (40, 20), (60, 20)]
+    );
+}
+
+#[test]
+fn test_split_bytes_match_split_messages() {
+    // The finalized slice serialized straight from the buffer is split into
+    // the same parts as the owned message.
+    let mut host = Host::new(2, 50, 5, 60).with_max_payload_bytes(60);
+    for x in 0..30 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(x));
+    }
+
+    let expected: Vec<Vec<u8>> = host
+        .get_msgs_finalized_slice(HOST_PLAYER_NUM)
+        .iter()
+        .map(MsgPayload::to_bytes)
+        .collect();
+
+    assert!(expected.len() > 1);
+    assert_eq!(
+        host.get_msgs_bytes_finalized_slice(HOST_PLAYER_NUM),
+        expected
+    );
+}
+
+#[test]
+fn test_catch_up_and_per_guest_slices_are_split() {
+    // Catch-up slices and each guest's own finalized slices are split to the
+    // max payload too.
+    let mut host = Host::new(2, 5, 5, 60)
+        .with_max_payload_bytes(60)
+        .with_catch_up_check_interval_sec(0.0);
+    for x in 0..30 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(x));
+    }
+
+    let catch_up: Vec<_> = host
+        .poll_catch_up(0.0)
+        .into_iter()
+        .map(|(_, msg)| msg)
+        .collect();
+    let per_guest: Vec<_> = host
+        .get_msgs_finalized_slices_for_guests()
+        .into_iter()
+        .map(|(_, msg)| msg)
+        .collect();
+
+    for parts in [
+        catch_up,
+        per_guest,
+        host.get_msgs_windowed_finalized_slices(GUEST),
+    ] {
+        assert!(parts.len() > 1);
+        assert!(parts.iter().all(|msg| msg.to_bytes().len() <= 60));
+    }
+}
+
+#[test_case(true; "host")]
+#[test_case(false; "guest")]
+fn test_session_splits_frame_batch(is_host: bool) {
+    // A session's frame batch too long for the max payload is sent as several
+    // batches within it.
+    let mut session: Session<PlayerInput> = if is_host {
+        Host::new(2, 50, 5, 60).with_max_payload_bytes(60).into()
+    } else {
+        Guest::new(2, GUEST, 60).with_max_payload_bytes(60).into()
+    };
+    for x in 0..30 {
+        session.add_own_input(PlayerInput::new_test_simple(x), 1.0 / 60.0);
+    }
+
+    let outgoing = session.get_outgoing_msgs_for_frame(0.0);
+
+    assert!(outgoing.len() > 1);
+    assert!(outgoing.iter().all(|(_, msg)| msg.to_bytes().len() <= 60));
+}
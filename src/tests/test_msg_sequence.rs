@@ -0,0 +1,157 @@
+use test_case::test_case;
+
+use crate::{
+    input_messages::{MsgKind, MsgPayload},
+    msg_sequence::{SequenceStats, SequencedMsg},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    session::{Recipient, Session},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+fn ping_bytes(seq: u32) -> Vec<u8> {
+    SequencedMsg::<PlayerInput> {
+        seq,
+        payload: MsgPayload::GuestToHostPing(seq),
+    }
+    .to_bytes()
+}
+
+#[test]
+fn test_sequenced_msg_roundtrip() {
+    // The sequence number and message survive serialization.
+    let msg = SequencedMsg::<PlayerInput> {
+        seq: 70_000,
+        payload: MsgPayload::GuestToHostPing(3),
+    };
+
+    let decoded = SequencedMsg::<PlayerInput>::from_bytes(&msg.to_bytes()).unwrap();
+
+    assert_eq!(decoded.seq, 70_000);
+    assert!(matches!(decoded.payload, MsgPayload::GuestToHostPing(3)));
+}
+
+#[test]
+fn test_truncated_sequenced_msg_is_malformed() {
+    // Bytes too short to hold a sequence number don't decode, and count as
+    // malformed.
+    let mut host = Host::new(2, 50, 5, 60);
+
+    assert!(
+        host.decode_sequenced_msg_from_peer(GUEST, &[1, 2])
+            .is_none()
+    );
+    assert_eq!(host.num_malformed_msgs_from_peer(GUEST), 1);
+}
+
+#[test_case(vec![0, 1, 2], SequenceStats { num_received: 3, ..Default::default() }; "in order")]
+#[test_case(vec![0, 2, 1], SequenceStats { num_received: 3, num_reordered: 1, ..Default::default() }; "reordered")]
+#[test_case(vec![0, 1, 1, 0], SequenceStats { num_received: 2, num_duplicates: 2, ..Default::default() }; "duplicated")]
+#[test_case(vec![0, 3], SequenceStats { num_received: 2, num_missing: 2, ..Default::default() }; "gap")]
+#[test_case(vec![5, 6], SequenceStats { num_received: 2, ..Default::default() }; "counts from the first received")]
+#[test_case(vec![100, 0], SequenceStats { num_received: 1, num_too_old: 1, ..Default::default() }; "too old to tell")]
+fn test_sequence_stats(seqs: Vec<u32>, expected: SequenceStats) {
+    // Each arrival is counted as new, reordered, duplicated or too old, and
+    // skipped sequence numbers as missing.
+    let mut host = Host::new(2, 50, 5, 60);
+
+    for seq in seqs {
+        host.decode_sequenced_msg_from_peer(GUEST, &ping_bytes(seq));
+    }
+
+    assert_eq!(host.sequence_stats(GUEST), expected);
+}
+
+#[test]
+fn test_duplicate_decodes_to_empty() {
+    // A message received twice is only passed on the first time.
+    let mut host = Host::new(2, 50, 5, 60);
+
+    let first = host.decode_sequenced_msg_from_peer(GUEST, &ping_bytes(0));
+    let second = host.decode_sequenced_msg_from_peer(GUEST, &ping_bytes(0));
+
+    assert!(matches!(first, Some(MsgPayload::GuestToHostPing(0))));
+    assert!(matches!(second, Some(MsgPayload::Empty)));
+}
+
+#[test]
+fn test_late_message_fills_gap() {
+    // A late message is still passed on, and no longer counts as missing.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.decode_sequenced_msg_from_peer(GUEST, &ping_bytes(0));
+    host.decode_sequenced_msg_from_peer(GUEST, &ping_bytes(2));
+    assert_eq!(host.sequence_stats(GUEST).estimated_loss_rate(), 1.0 / 3.0);
+
+    let late = host.decode_sequenced_msg_from_peer(GUEST, &ping_bytes(1));
+
+    assert!(matches!(late, Some(MsgPayload::GuestToHostPing(1))));
+    assert_eq!(host.sequence_stats(GUEST).num_missing, 0);
+    assert_eq!(host.sequence_stats(GUEST).estimated_loss_rate(), 0.0);
+}
+
+#[test]
+fn test_sequences_are_per_destination() {
+    // Each peer gets its own sequence, starting from 0.
+    let mut host = Host::new(3, 50, 5, 60);
+
+    let seqs = [PlayerNum(1), PlayerNum(1), PlayerNum(2)]
+        .map(|guest| host.sequence_msg(guest, MsgPayload::Empty).seq);
+
+    assert_eq!(seqs, [0, 1, 0]);
+}
+
+#[test]
+fn test_session_drops_duplicate_ping() {
+    // A duplicated ping is answered once.
+    let mut host: Session<PlayerInput> = Host::new(2, 50, 5, 60).into();
+    let mut guest = Guest::new(2, GUEST, 60);
+    let ping = guest.get_msg_guest_ping();
+    let bytes = guest.sequence_msg(HOST_PLAYER_NUM, ping).to_bytes();
+
+    let first = host.rx_sequenced_bytes(GUEST, &bytes).unwrap();
+    let second = host.rx_sequenced_bytes(GUEST, &bytes).unwrap();
+
+    assert_eq!(first.len(), 1);
+    assert!(second.is_empty());
+    assert_eq!(
+        host.as_host().unwrap().sequence_stats(GUEST).num_duplicates,
+        1
+    );
+}
+
+#[test]
+fn test_session_sequences_broadcast_per_peer() {
+    // A message for all peers is sent to each guest with that guest's own
+    // sequence number.
+    let mut host: Session<PlayerInput> = Host::new(3, 50, 5, 60).into();
+
+    let first = host.to_sequenced_bytes(vec![(Recipient::AllPeers, MsgPayload::Empty)]);
+    let second = host.to_sequenced_bytes(vec![(
+        Recipient::Player(PlayerNum(2)),
+        MsgPayload::HostToGuestPong(1),
+    )]);
+
+    let decoded: Vec<(PlayerNum, u32, MsgKind)> = first
+        .iter()
+        .chain(&second)
+        .map(|(to, bytes)| {
+            let msg = SequencedMsg::<PlayerInput>::from_bytes(bytes).unwrap();
+            (*to, msg.seq, msg.payload.kind())
+        })
+        .collect();
+    assert_eq!(
+        decoded,
+        vec![
+            (PlayerNum(1), 0, MsgKind::Empty),
+            (PlayerNum(2), 0, MsgKind::Empty),
+            (PlayerNum(2), 1, MsgKind::HostToGuestPong),
+        ]
+    );
+}
@@ -192,16 +192,22 @@ fn test_get_input_statuses() {
     }
     buffers.append_input(1.into(), PlayerInput::new_test_simple(12));
 
-    let statuses = buffers.get_input_statuses(2);
-    assert_eq!(statuses.len(), 2);
-    assert!(matches!(
-        statuses[0],
-        (PlayerNum(0), InputStatus::Finalized)
-    ));
-    assert!(matches!(statuses[1], (PlayerNum(1), InputStatus::NonFinal)));
+    assert_eq!(
+        buffers.get_input_statuses(2),
+        vec![
+            (PlayerNum(0), InputStatus::Finalized { num_finalized: 3 }),
+            (PlayerNum(1), InputStatus::NonFinal { num_finalized: 2 })
+        ]
+    );
 
     let statuses_unreceived = buffers.get_input_statuses(3);
     for (_, status) in statuses_unreceived {
-        assert!(matches!(status, InputStatus::NotReceived));
+        assert!(matches!(
+            status,
+            InputStatus::NotReceived {
+                predicted: true,
+                ..
+            }
+        ));
     }
 }
@@ -1,8 +1,12 @@
+use test_case::test_case;
+
 use super::demo_input_struct::PlayerInput;
 use crate::{
     input_messages::{HostFinalizedSlice, MsgPayload},
     multiplayer_input_manager::MultiplayerInputManager,
-    multiplayer_input_manager_guest::{DEFAULT_MAX_CATCHUP_INPUTS, GuestInputMgr},
+    multiplayer_input_manager_guest::{
+        AheadOfHostPolicy, DEFAULT_MAX_CATCHUP_INPUTS, GuestInputMgr,
+    },
     util_types::PlayerNum,
 };
 
@@ -17,11 +21,11 @@ fn test_new_manager() {
 fn test_rtt_observation() {
     let mut manager = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(4, 1.into(), 60);
     manager.observe_rtt_ms_to_host(100.0);
-    assert!((manager.get_rtt_ms_to_host() - 100.0).abs() < f32::EPSILON);
+    assert!((manager.get_rtt_ms_to_host().unwrap() - 100.0).abs() < f32::EPSILON);
 
     manager.observe_rtt_ms_to_host(200.0);
     // With default EWMA alpha, the value will be between 100 and 200
-    let rtt = manager.get_rtt_ms_to_host();
+    let rtt = manager.get_rtt_ms_to_host().unwrap();
     assert!(rtt > 100.0 && rtt < 200.0);
 }
 
@@ -37,7 +41,7 @@ fn test_num_inputs_needed() {
     // With 1000ms RTT at 2 ticks/sec:
     // 1 tick = 500ms
     // 1 way = 1 tick
-    assert!((manager.one_way_in_ticks() - 1.0).abs() < 0.000001);
+    assert!((manager.one_way_in_ticks().unwrap() - 1.0).abs() < 0.000001);
 
     // With host tick 10, and 1 way in ticks = 1
     // to be in sync with the host, we need to be at tick 11;
@@ -134,7 +138,7 @@ pub fn test_get_msg_own_input_slice() {
     }
 
     let msg = manager.get_msg_own_input_slice();
-    if let MsgPayload::PeerInputs(slice) = msg.try_into().unwrap() {
+    if let MsgPayload::PeerInputs(slice) = msg {
         assert_eq!(slice.start, 0);
         assert_eq!(slice.inputs.len(), 10);
     } else {
@@ -153,7 +157,7 @@ pub fn test_get_msg_own_input_slice() {
 
     // now the slice should only contain the last 7 inputs
     let msg = manager.get_msg_own_input_slice();
-    if let MsgPayload::PeerInputs(slice) = msg.try_into().unwrap() {
+    if let MsgPayload::PeerInputs(slice) = msg {
         assert_eq!(slice.start, 3);
         assert_eq!(slice.inputs.len(), 7);
     } else {
@@ -173,9 +177,7 @@ pub fn test_get_msg_ack_finalization() {
 
     let msg_finalize = manager.get_msg_ack_finalization();
     // no finalized inputs yet, only one peer seen
-    if let MsgPayload::GuestToHostAckFinalization(finalized_ticks) =
-        msg_finalize.try_into().unwrap()
-    {
+    if let MsgPayload::GuestToHostAckFinalization(finalized_ticks) = msg_finalize {
         assert_eq!(finalized_ticks.get(own_id.into()), 0);
     } else {
         panic!("Expected AckFinalization");
@@ -193,9 +195,7 @@ pub fn test_get_msg_ack_finalization() {
 
     let msg_finalize = manager.get_msg_ack_finalization();
     // now 3 inputs have been finalized for this peer
-    if let MsgPayload::GuestToHostAckFinalization(finalized_ticks) =
-        msg_finalize.try_into().unwrap()
-    {
+    if let MsgPayload::GuestToHostAckFinalization(finalized_ticks) = msg_finalize {
         assert_eq!(finalized_ticks.get(own_id.into()), 3);
     } else {
         panic!("Expected AckFinalization");
@@ -214,12 +214,107 @@ pub fn test_get_msg_ack_finalization() {
     let msg_finalize = manager.get_msg_ack_finalization();
     // now 3 inputs have been finalized for this peer,
     // and 5 for the other peer
-    if let MsgPayload::GuestToHostAckFinalization(finalized_ticks) =
-        msg_finalize.try_into().unwrap()
-    {
+    if let MsgPayload::GuestToHostAckFinalization(finalized_ticks) = msg_finalize {
         assert_eq!(finalized_ticks.get(own_id.into()), 3);
         assert_eq!(finalized_ticks.get(other_id.into()), 5);
     } else {
         panic!("Expected AckFinalization");
     }
 }
+
+/// A guest at 2 ticks/sec with a 1 tick one-way latency, host tick 10, and
+/// `num_own_inputs` inputs collected.
+fn guest_with_own_inputs(
+    policy: AheadOfHostPolicy,
+    num_own_inputs: u32,
+) -> MultiplayerInputManager<PlayerInput, GuestInputMgr> {
+    let mut manager = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(4, 1.into(), 2)
+        .with_ahead_of_host_policy(policy);
+    manager.observe_rtt_ms_to_host(1000.0);
+    manager.test_advance_host_tick(10);
+    for _ in 0..num_own_inputs {
+        manager.add_own_input(PlayerInput::default());
+    }
+    manager
+}
+
+#[test]
+fn test_ticks_ahead() {
+    // The expected host tick is 11; a guest with 14 inputs is 3 ticks ahead,
+    // and a guest behind the host is reported as 0 ticks ahead.
+    assert_eq!(
+        guest_with_own_inputs(AheadOfHostPolicy::Stall, 14).ticks_ahead(),
+        3.0
+    );
+    assert_eq!(
+        guest_with_own_inputs(AheadOfHostPolicy::Stall, 8).ticks_ahead(),
+        0.0
+    );
+}
+
+#[test]
+fn test_ticks_ahead_before_rtt_observed() {
+    // Without an RTT sample there is no estimate of the host tick, so the guest
+    // is not considered ahead and no delay is recommended.
+    let manager = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(4, 1.into(), 2);
+    assert_eq!(manager.ticks_ahead(), 0.0);
+    assert_eq!(manager.recommended_frame_delay_ms(), 0.0);
+}
+
+#[test_case(AheadOfHostPolicy::Stall, 0 ; "stall collects nothing")]
+#[test_case(AheadOfHostPolicy::Throttle, 1 ; "throttle keeps collecting one")]
+fn test_num_inputs_needed_when_ahead(policy: AheadOfHostPolicy, expected: u32) {
+    // When more than a tick ahead of the host, the number of inputs to collect
+    // depends on the configured policy.
+    let manager = guest_with_own_inputs(policy, 14);
+    assert_eq!(manager.num_inputs_needed(), expected);
+}
+
+#[test_case(11, 0.0 ; "in sync")]
+#[test_case(12, 0.0 ; "one tick ahead is tolerated")]
+#[test_case(13, 500.0 ; "two ticks ahead")]
+#[test_case(20, 500.0 ; "far ahead is capped at one tick per frame")]
+fn test_recommended_frame_delay_ms(num_own_inputs: u32, expected_ms: f32) {
+    // At 2 ticks/sec a tick lasts 500ms; the recommended delay covers the
+    // surplus beyond the one-tick tolerance, capped at one tick per frame.
+    let manager = guest_with_own_inputs(AheadOfHostPolicy::Throttle, num_own_inputs);
+    assert_eq!(manager.recommended_frame_delay_ms(), expected_ms);
+}
+
+#[test_case(2, 1000.0, 1_000_000 ; "one tick")]
+#[test_case(60, 100.0, 3_000_000 ; "whole ticks")]
+#[test_case(60, 33.3333, 999_990 ; "rtt rounded to the microsecond")]
+#[test_case(7, 1.0, 3_500 ; "micro-ticks")]
+fn test_one_way_in_micro_ticks(ticks_per_sec: u32, rtt_ms: f32, expected: i64) {
+    // Pacing works in fixed point: the RTT is rounded to whole microseconds,
+    // and half of it converted to whole micro-ticks.
+    let mut manager =
+        MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(4, 1.into(), ticks_per_sec);
+    manager.observe_rtt_ms_to_host(rtt_ms);
+    assert_eq!(manager.one_way_in_micro_ticks(), Some(expected));
+}
+
+#[test]
+fn test_recommended_frame_delay_is_whole_micros() {
+    // At 3 ticks/sec a tick lasts 333,333.3us; the delay for a guest far
+    // ahead is rounded down to 333,333us before being reported in ms.
+    let mut manager = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(4, 1.into(), 3);
+    manager.observe_rtt_ms_to_host(0.01);
+    manager.test_advance_host_tick(10);
+    for _ in 0..13 {
+        manager.add_own_input(PlayerInput::default());
+    }
+    assert_eq!(manager.recommended_frame_delay_ms(), 333.333);
+}
+
+#[test]
+fn test_rx_host_rate_adjust() {
+    // A guest told it is running 10,000ppm slow should pace its input loop 1%
+    // faster than the nominal tick rate.
+    let mut manager = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(4, 1.into(), 60);
+    assert_eq!(manager.paced_ticks_per_sec(), 60.0);
+
+    manager.rx_host_rate_adjust(MsgPayload::HostToGuestRateAdjust(-10_000));
+    assert_eq!(manager.get_host_reported_skew_ppm(), -10_000);
+    assert!((manager.paced_ticks_per_sec() - 60.6).abs() < 1e-4);
+}
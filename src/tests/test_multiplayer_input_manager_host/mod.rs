@@ -1,4 +1,22 @@
+pub mod test_ack_validation;
+pub mod test_adaptive_slices;
 pub mod test_add_host_input_to_fill_needed;
+pub mod test_guest_input_rate;
+pub mod test_health_score;
+pub mod test_input_delay;
+pub mod test_lag_downgrade;
+pub mod test_lobby_readiness;
+pub mod test_msg_dedup;
+pub mod test_mute_player;
+pub mod test_observation_matrix;
+pub mod test_observation_staleness;
+pub mod test_poll_catch_up;
+pub mod test_recovery;
+pub mod test_resend_policy;
+pub mod test_review_window;
+pub mod test_send_window;
+pub mod test_sync_plan;
+pub mod test_targeted_slices;
 pub mod test_update_time_and_get_num_inputs_needed;
 
 use std::collections::HashMap;
@@ -66,16 +84,17 @@ fn test_get_finalization_start_for_peer() {
         manager.add_host_input_directly(PlayerInput::default());
     }
 
-    // add inputs for only peers 2 and 3
+    // add inputs for all guests, enough to cover every ack below
     let guest_1: PlayerNum = 1.into();
     let guest_2: PlayerNum = 2.into();
     let guest_3: PlayerNum = 3.into();
-    let inputs_to_add = 10;
+    let inputs_to_add = 20;
     let msg = MsgPayload::PeerInputs(PlayerInputSlice::<PlayerInput>::new_test(0, inputs_to_add));
+    manager.rx_guest_input_slice(guest_1, msg.clone());
     manager.rx_guest_input_slice(guest_2, msg.clone());
     manager.rx_guest_input_slice(guest_3, msg);
 
-    // The host has seen inputs for peers 2 and 3, but has not seen any finalization acks from any players. Thus, the host will have 0 as the earliest input needed by at least one peer for all players
+    // The host has seen inputs for all guests, but has not seen any finalization acks from any players. Thus, the host will have 0 as the earliest input needed by at least one peer for all players
     assert_eq!(
         manager.test_get_earliest_num_observed_final_for_peer(HOST_PLAYER_NUM),
         0
@@ -292,12 +311,17 @@ fn test_get_msg_catch_up_with_guest_acks() {
         manager.add_host_input_directly(PlayerInput::default());
     }
 
-    // add ack of peer_id's inputs only up to tick 3;
+    // the guest has sent its first 3 inputs, and every guest acks them
+    manager.rx_guest_input_slice(
+        guest_id,
+        MsgPayload::PeerInputs(PlayerInputSlice::<PlayerInput>::new_test(0, 3)),
+    );
     for guest_idx in 0..3 {
         let msg = MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
             HashMap::from([(guest_id, 3)]),
         ));
-        manager.rx_finalized_ticks_observations(PlayerNum::from_guest_index(guest_idx), msg);
+        manager
+            .rx_finalized_ticks_observations(PlayerNum::from_guest_index(guest_idx).unwrap(), msg);
     }
 
     // The host is at input tick `num_host_inputs`; peers have only acked up to tick 3. But we only allow `max_ticks_behind` of 5, so the host needs to send a catchup slice with inputs up to tick `num_host_inputs - max_ticks_behind`.
@@ -320,7 +344,7 @@ fn test_get_msg_catch_up_with_guest_acks() {
     // finalized inputs such that they don't need to catch up
     // anymore, so if the state is the same, the host should
     // not send any more catch-up inputs
-    let msg = manager.get_msg_finalized_late_inputs_for_guest(guest_id.into());
+    let msg = manager.get_msg_finalized_late_inputs_for_guest(guest_id);
     assert!(matches!(msg, MsgPayload::Empty));
 
     // Now advance the host's input to `num_host_inputs_2`
@@ -333,12 +357,13 @@ fn test_get_msg_catch_up_with_guest_acks() {
         let msg = MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
             HashMap::from([(guest_id, 15)]),
         ));
-        manager.rx_finalized_ticks_observations(PlayerNum::from_guest_index(guest_idx), msg);
+        manager
+            .rx_finalized_ticks_observations(PlayerNum::from_guest_index(guest_idx).unwrap(), msg);
     }
 
     // The peer should now be 15 inputs behind, so the host
     // should send them inputs up to 25
-    let msg = manager.get_msg_finalized_late_inputs_for_guest(guest_id.into());
+    let msg = manager.get_msg_finalized_late_inputs_for_guest(guest_id);
     if let MsgPayload::HostToLobbyFinalizedSlice(slice) = msg {
         assert_eq!(slice.player_num, guest_id);
         assert_eq!(slice.host_tick, num_host_inputs_2);
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use test_case::test_case;
+
+use crate::{
+    InputMgrEvent, Recipient, Session,
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+
+/// A 3 player host with `num_inputs` of its own inputs finalized.
+fn host_with_own_inputs(num_inputs: u32) -> Host {
+    let mut host = Host::new(3, 50, 5, 60);
+    for _ in 0..num_inputs {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host
+}
+
+fn ack_host_inputs(host: &mut Host, guest: u8, num_acked: u32) {
+    host.rx_finalized_ticks_observations(
+        PlayerNum(guest),
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
+            HashMap::from([(HOST_PLAYER_NUM, num_acked)]),
+        )),
+    );
+}
+
+fn host_slice(start: u32, num_inputs: u32) -> MsgPayload<PlayerInput> {
+    HostFinalizedSlice::new_test(HOST_PLAYER_NUM, 0, start, num_inputs).into()
+}
+
+#[test]
+fn test_ack_beyond_finalized_is_clamped_and_flagged() {
+    // With no sends recorded, a guest can't have received more than the host
+    // has finalized; an ack past that is clamped and reported.
+    let mut host = host_with_own_inputs(10);
+    ack_host_inputs(&mut host, 1, 25);
+
+    assert_eq!(
+        host.drain_events(),
+        vec![InputMgrEvent::AckBeyondSent {
+            guest: PlayerNum(1),
+            player_num: HOST_PLAYER_NUM,
+            acked: 25,
+            max_sent: 10,
+        }]
+    );
+    assert_eq!(host.observation_matrix()[0].1[0], (HOST_PLAYER_NUM, 10));
+}
+
+#[test_case(4, 4, None ; "ack within what was sent")]
+#[test_case(6, 4, Some(6) ; "ack past what was sent")]
+fn test_ack_is_checked_against_recorded_sends(acked: u32, expected: u32, flagged: Option<u32>) {
+    // Once sends to a guest are recorded, its acks are bounded by the slices
+    // it was sent, even if the host has finalized more.
+    let mut host = host_with_own_inputs(10);
+    host.record_msg_sent(Recipient::Player(PlayerNum(1)), &host_slice(0, 4));
+    ack_host_inputs(&mut host, 1, acked);
+
+    let flagged_acks: Vec<u32> = host
+        .drain_events()
+        .into_iter()
+        .filter_map(|event| match event {
+            InputMgrEvent::AckBeyondSent { acked, .. } => Some(acked),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(flagged_acks, flagged.into_iter().collect::<Vec<_>>());
+    assert_eq!(
+        host.observation_matrix()[0].1[0],
+        (HOST_PLAYER_NUM, expected)
+    );
+}
+
+#[test_case(Recipient::AllPeers, [7, 7] ; "broadcast is recorded for every guest")]
+#[test_case(Recipient::Player(PlayerNum(2)), [10, 7] ; "send to one guest is recorded for it alone")]
+fn test_recorded_sends_follow_the_recipient(recipient: Recipient, expected: [u32; 2]) {
+    // A guest with no recorded sends is only bounded by what the host has
+    // finalized.
+    let mut host = host_with_own_inputs(10);
+    host.record_msg_sent(recipient, &host_slice(2, 5));
+    ack_host_inputs(&mut host, 1, 10);
+    ack_host_inputs(&mut host, 2, 10);
+
+    let matrix = host.observation_matrix();
+    assert_eq!([matrix[0].1[0].1, matrix[1].1[0].1], expected);
+}
+
+#[test]
+fn test_misbehaving_ack_cannot_move_the_broadcast_start_past_sent() {
+    // Both guests over-ack, but the broadcast still starts from the end of
+    // what they were sent, so they don't miss the inputs in between.
+    let mut host = host_with_own_inputs(10);
+    host.record_msg_sent(Recipient::AllPeers, &host_slice(0, 6));
+    ack_host_inputs(&mut host, 1, 9);
+    ack_host_inputs(&mut host, 2, 9);
+
+    let MsgPayload::HostToLobbyFinalizedSlice(slice) =
+        host.get_msg_finalized_slice(HOST_PLAYER_NUM)
+    else {
+        panic!("expected a finalized slice");
+    };
+    assert_eq!(slice.inputs.start, 6);
+}
+
+#[test]
+fn test_session_records_the_slices_it_returns() {
+    // A host session records its broadcasts, so inputs finalized after the
+    // last broadcast can't be acked.
+    let mut session = Session::from(host_with_own_inputs(0));
+    session.add_own_input(PlayerInput::default(), 0.1);
+    let host = session.as_host_mut().unwrap();
+    let num_broadcast = host.get_own_num_inputs();
+    for _ in 0..4 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    ack_host_inputs(host, 1, num_broadcast + 4);
+
+    assert_eq!(
+        host.drain_events(),
+        vec![InputMgrEvent::AckBeyondSent {
+            guest: PlayerNum(1),
+            player_num: HOST_PLAYER_NUM,
+            acked: num_broadcast + 4,
+            max_sent: num_broadcast,
+        }]
+    );
+}
+
+#[test]
+fn test_ack_for_player_with_no_recorded_sends_is_not_flagged() {
+    // Recording a slice of one player doesn't make the guest's acks of other
+    // players count against nothing sent: with none of their slices
+    // recorded, those acks are only bounded by what the host has finalized.
+    let mut host = host_with_own_inputs(10);
+    host.rx_guest_input_slice(
+        PlayerNum(2),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 6)),
+    );
+    host.record_msg_sent(Recipient::Player(PlayerNum(1)), &host_slice(0, 4));
+    host.rx_finalized_ticks_observations(
+        PlayerNum(1),
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
+            HashMap::from([(PlayerNum(2), 6)]),
+        )),
+    );
+
+    assert!(host.drain_events().is_empty());
+    assert_eq!(host.observation_matrix()[0].1[2], (PlayerNum(2), 6));
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::HostInputMgr,
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    session::Recipient,
+    slice_throttle::{AdaptiveSliceConfig, SliceRedundancy},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+
+const GUEST_1: PlayerNum = PlayerNum(1);
+const GUEST_2: PlayerNum = PlayerNum(2);
+
+/// A 3 player host with 20 finalized inputs for the host and both guests.
+fn host_with_inputs(config: Option<AdaptiveSliceConfig>) -> Host {
+    let mut host = Host::new(3, 50, 5, 60);
+    if let Some(config) = config {
+        host = host.with_adaptive_finalized_slices(config);
+    }
+    add_host_inputs(&mut host, 20);
+    for guest in [GUEST_1, GUEST_2] {
+        host.rx_guest_input_slice(
+            guest,
+            MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 20)),
+        );
+    }
+    host
+}
+
+fn add_host_inputs(host: &mut Host, num_inputs: u32) {
+    for _ in 0..num_inputs {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+}
+
+fn ack(host: &mut Host, guest: PlayerNum, acks: [(u8, u32); 3]) {
+    host.rx_finalized_ticks_observations(
+        guest,
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
+            HashMap::from(acks.map(|(p, n)| (PlayerNum(p), n))),
+        )),
+    );
+}
+
+/// Polls the adaptive slices and records them as sent, returning each one's guest, player and range of input indices.
+fn poll(host: &mut Host, delta: f32) -> Vec<(u8, u8, u32, u32)> {
+    let msgs = host.poll_adaptive_finalized_slices(delta);
+    for (guest, msg) in &msgs {
+        host.record_msg_sent(Recipient::Player(*guest), msg);
+    }
+    msgs.iter()
+        .map(|(guest, msg)| match msg {
+            MsgPayload::HostToLobbyFinalizedSlice(slice) => (
+                guest.as_u8(),
+                slice.player_num.as_u8(),
+                slice.inputs.start,
+                slice.inputs.start + slice.inputs.len(),
+            ),
+            other => panic!("unexpected message: {other:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn test_broadcast_redundancy_is_counted_per_guest() {
+    // A broadcast slice counts the inputs each guest had already acked, and
+    // those it had already been sent, as redundant for that guest.
+    let mut host = host_with_inputs(None);
+    ack(&mut host, GUEST_1, [(0, 10), (1, 20), (2, 20)]);
+    let slice = host.get_msg_finalized_slice(PlayerNum(0));
+
+    host.record_msg_sent(Recipient::AllPeers, &slice);
+    host.record_msg_sent(Recipient::AllPeers, &slice);
+
+    assert_eq!(
+        host.slice_redundancy(GUEST_1),
+        SliceRedundancy {
+            num_inputs_sent: 40,
+            num_acked_inputs: 20,
+            num_resent_inputs: 10,
+        }
+    );
+    assert_eq!(
+        host.slice_redundancy(GUEST_2),
+        SliceRedundancy {
+            num_inputs_sent: 40,
+            num_acked_inputs: 0,
+            num_resent_inputs: 20,
+        }
+    );
+    assert_eq!(host.slice_redundancy(GUEST_2).redundant_fraction(), 0.5);
+}
+
+#[test]
+fn test_disabled_by_default() {
+    // Without adaptive slices, polling sends nothing.
+    let mut host = host_with_inputs(None);
+
+    assert!(host.poll_adaptive_finalized_slices(1.0).is_empty());
+    assert_eq!(host.adaptive_finalized_slices(), None);
+    assert_eq!(host.adaptive_resend_interval_sec(GUEST_1), None);
+}
+
+#[test]
+fn test_only_new_inputs_are_sent_between_resends() {
+    // Each guest's slices start from its own ack; until its resend interval
+    // passes, it is only sent inputs past those it was already sent.
+    let mut host = host_with_inputs(Some(AdaptiveSliceConfig::default()));
+    ack(&mut host, GUEST_1, [(0, 5), (1, 20), (2, 10)]);
+    ack(&mut host, GUEST_2, [(0, 20), (1, 20), (2, 20)]);
+
+    assert_eq!(poll(&mut host, 0.0), vec![(1, 0, 5, 20), (1, 2, 10, 20)]);
+    assert_eq!(poll(&mut host, 0.05), vec![]);
+
+    add_host_inputs(&mut host, 5);
+    assert_eq!(poll(&mut host, 0.02), vec![(1, 0, 20, 25), (2, 0, 20, 25)]);
+
+    assert_eq!(
+        poll(&mut host, 0.05),
+        vec![(1, 0, 5, 25), (1, 2, 10, 20), (2, 0, 20, 25)]
+    );
+    assert_eq!(host.slice_redundancy(GUEST_1).num_acked_inputs, 0);
+}
+
+#[test]
+fn test_resend_interval_adapts_to_ack_progress() {
+    // While a guest acks everything sent by its last resend, its resends are
+    // spaced out up to the max; once it falls behind, they close in again.
+    let config = AdaptiveSliceConfig {
+        min_resend_interval_sec: 0.1,
+        max_resend_interval_sec: 0.4,
+    };
+    let mut host = host_with_inputs(Some(config));
+    ack(&mut host, GUEST_2, [(0, 20), (1, 20), (2, 20)]);
+
+    let intervals: Vec<f32> = (0..4)
+        .map(|_| {
+            poll(&mut host, 1.0);
+            host.adaptive_resend_interval_sec(GUEST_2).unwrap()
+        })
+        .collect();
+    assert_eq!(intervals, vec![0.1, 0.2, 0.4, 0.4]);
+
+    add_host_inputs(&mut host, 5);
+    poll(&mut host, 0.0);
+    poll(&mut host, 1.0);
+    poll(&mut host, 1.0);
+    assert_eq!(host.adaptive_resend_interval_sec(GUEST_2), Some(0.2));
+}
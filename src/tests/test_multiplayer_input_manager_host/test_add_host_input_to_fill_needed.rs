@@ -492,7 +492,7 @@ fn test_add_host_input_tick_rate_precision(ticks_per_sec: u32) {
     // Due to f32 floating point precision and ceiling behavior,
     // we expect approximately 10 ticks, but allow for ±1 due to accumulation
     assert!(
-        num_inputs >= 9 && num_inputs <= 11,
+        (9..=11).contains(&num_inputs),
         "Expected approximately 10 inputs at {} ticks/sec, got {}",
         ticks_per_sec,
         num_inputs
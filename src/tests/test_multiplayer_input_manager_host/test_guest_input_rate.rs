@@ -0,0 +1,93 @@
+use test_case::test_case;
+
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+const TICKS_PER_SEC: u32 = 60;
+
+/// Runs the host for `num_frames` frames of 1/60 sec; guest 1 sends one input
+/// per frame, except that it skips every `skip_every`th frame (if given).
+fn run_host_with_guest(
+    num_frames: u32,
+    skip_every: Option<u32>,
+) -> MultiplayerInputManager<PlayerInput, HostInputMgr> {
+    let mut host =
+        MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 5, 5, TICKS_PER_SEC);
+    let mut guest_inputs = 0;
+    for frame in 1..=num_frames {
+        host.add_host_input_to_fill_needed(PlayerInput::default(), 1.0 / TICKS_PER_SEC as f32);
+        if skip_every.is_some_and(|n| frame % n == 0) {
+            continue;
+        }
+        host.rx_guest_input_slice(
+            PlayerNum(1),
+            MsgPayload::PeerInputs(PlayerInputSlice::new_test(guest_inputs, 1)),
+        );
+        guest_inputs += 1;
+    }
+    host
+}
+
+#[test]
+fn test_no_rate_before_first_window() {
+    // With less than one sample window of arrivals, there is no rate or skew
+    // estimate, and no rate adjust message.
+    let host = run_host_with_guest(10, None);
+    assert_eq!(host.guest_input_rate(PlayerNum(1)), None);
+    assert_eq!(host.guest_clock_skew_ppm(PlayerNum(1)), None);
+    assert!(matches!(
+        host.get_msg_rate_adjust_for_guest(PlayerNum(1)),
+        MsgPayload::Empty
+    ));
+}
+
+#[test_case(None, 60.0 ; "guest keeps up")]
+#[test_case(Some(10), 54.0 ; "guest drops every 10th frame")]
+fn test_guest_input_rate(skip_every: Option<u32>, expected_rate: f32) {
+    // After several seconds, the arrival rate converges on the rate at which
+    // the guest actually produces inputs.
+    let host = run_host_with_guest(600, skip_every);
+    let rate = host.guest_input_rate(PlayerNum(1)).unwrap();
+    assert!(
+        (rate - expected_rate).abs() < 1.0,
+        "expected ~{expected_rate}, got {rate}"
+    );
+}
+
+#[test]
+fn test_slow_guest_gets_rate_adjust() {
+    // A guest producing 10% fewer inputs than the tick rate has a skew of
+    // about -100,000ppm, which is reported in a rate adjust message.
+    let host = run_host_with_guest(600, Some(10));
+    let skew = host.guest_clock_skew_ppm(PlayerNum(1)).unwrap();
+    assert!((skew + 100_000.0).abs() < 15_000.0, "got {skew}");
+
+    let MsgPayload::HostToGuestRateAdjust(skew_ppm) =
+        host.get_msg_rate_adjust_for_guest(PlayerNum(1))
+    else {
+        panic!("expected a rate adjust message");
+    };
+    assert_eq!(skew_ppm, skew.round() as i32);
+}
+
+#[test]
+fn test_skew_within_threshold_sends_nothing() {
+    // A guest that keeps up is within the default threshold, so no rate adjust
+    // message is produced; lowering the threshold to 0 would always send one.
+    let host = run_host_with_guest(600, None);
+    assert!(matches!(
+        host.get_msg_rate_adjust_for_guest(PlayerNum(1)),
+        MsgPayload::Empty
+    ));
+
+    let host = run_host_with_guest(600, None).with_rate_adjust_threshold_ppm(0);
+    assert!(matches!(
+        host.get_msg_rate_adjust_for_guest(PlayerNum(1)),
+        MsgPayload::HostToGuestRateAdjust(_)
+    ));
+}
@@ -0,0 +1,148 @@
+use test_case::test_case;
+
+use crate::{
+    health_score::{HEALTH_HYSTERESIS_POINTS, HealthComponents, HealthScore},
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+fn components() -> HealthComponents {
+    HealthComponents {
+        rtt_ms: None,
+        jitter_ms: None,
+        loss: None,
+        ticks_behind: 0,
+        recent_stalls: 0,
+        num_stalls: 0,
+    }
+}
+
+fn guest_score(host: &mut Host, guest: PlayerNum) -> HealthScore {
+    let scores = host.poll_health_scores();
+    scores
+        .into_iter()
+        .find(|(player_num, _)| *player_num == guest)
+        .unwrap()
+        .1
+}
+
+#[test_case(components(), 100; "nothing measured")]
+#[test_case(HealthComponents { rtt_ms: Some(50.0), jitter_ms: Some(5.0), loss: Some(0.0), ticks_behind: 2, ..components() }, 100; "good bounds")]
+#[test_case(HealthComponents { rtt_ms: Some(175.0), ..components() }, 85; "half of the rtt penalty")]
+#[test_case(HealthComponents { rtt_ms: Some(1000.0), ..components() }, 70; "rtt penalty is capped")]
+#[test_case(HealthComponents { jitter_ms: Some(50.0), ..components() }, 85; "jitter")]
+#[test_case(HealthComponents { loss: Some(0.1), ..components() }, 75; "loss")]
+#[test_case(HealthComponents { ticks_behind: 30, ..components() }, 80; "finalization lag")]
+#[test_case(HealthComponents { recent_stalls: 3, num_stalls: 40, ..components() }, 90; "recent stalls only")]
+#[test_case(HealthComponents { rtt_ms: Some(300.0), jitter_ms: Some(50.0), loss: Some(0.1), ticks_behind: 30, recent_stalls: 3, num_stalls: 3 }, 0; "everything bad")]
+fn test_raw_score_weighting(components: HealthComponents, expected: u8) {
+    // Each signal takes off up to its weight, linearly between its good and
+    // bad bounds; signals not yet measured take off nothing.
+    assert_eq!(components.raw_score(), expected);
+}
+
+#[test]
+fn test_unmeasured_guests_score_full_health() {
+    // Guests that haven't pinged or fallen behind yet score 100, with no
+    // measured components.
+    let mut host = Host::new(3, 50, 5, 60);
+    let scores = host.poll_health_scores();
+
+    assert_eq!(
+        scores.iter().map(|(p, _)| *p).collect::<Vec<_>>(),
+        vec![PlayerNum(1), PlayerNum(2)]
+    );
+    for (_, score) in scores {
+        assert_eq!(score.score, 100);
+        assert_eq!(score.components, components());
+    }
+}
+
+#[test]
+fn test_loss_estimated_from_gaps_in_ping_ids() {
+    // Consecutive ping ids mean no loss; skipped ids count as lost pings.
+    let mut host = Host::new(3, 50, 5, 60);
+    for id in 0..10 {
+        host.rx_guest_ping_and_reply(PlayerNum(1), MsgPayload::GuestToHostPing(id));
+    }
+    for id in [0, 1, 4, 5] {
+        host.rx_guest_ping_and_reply(PlayerNum(2), MsgPayload::GuestToHostPing(id));
+    }
+    let scores = host.poll_health_scores();
+
+    assert_eq!(scores[0].1.components.loss, Some(0.0));
+    let loss = scores[1].1.components.loss.unwrap();
+    assert!(loss > 0.1, "{loss}");
+}
+
+#[test]
+fn test_jitter_tracks_rtt_deviation() {
+    // Steady RTTs have no jitter, while alternating ones do.
+    let mut host = Host::new(3, 50, 5, 60);
+    for _ in 0..5 {
+        host.observe_guest_rtt_ms(PlayerNum(1), 80.0);
+    }
+    for rtt in [80.0, 120.0, 80.0, 120.0] {
+        host.observe_guest_rtt_ms(PlayerNum(2), rtt);
+    }
+    let scores = host.poll_health_scores();
+
+    assert_eq!(scores[0].1.components.rtt_ms, Some(80.0));
+    assert_eq!(scores[0].1.components.jitter_ms, Some(0.0));
+    assert!(scores[1].1.components.jitter_ms.unwrap() > 30.0);
+    assert!(scores[1].1.score < scores[0].1.score);
+}
+
+#[test]
+fn test_score_has_hysteresis() {
+    // Small moves of the raw score don't change the reported score, larger
+    // ones do, and full health is reported as soon as it is reached.
+    let mut host = Host::new(2, 50, 5, 60);
+    let add_host_inputs = |host: &mut Host, n: u32| {
+        for _ in 0..n {
+            host.add_host_input_directly(PlayerInput::default());
+        }
+    };
+
+    add_host_inputs(&mut host, 3);
+    assert_eq!(guest_score(&mut host, GUEST).score, 99);
+
+    add_host_inputs(&mut host, 2);
+    let score = guest_score(&mut host, GUEST);
+    assert!(99 - score.components.raw_score() < HEALTH_HYSTERESIS_POINTS);
+    assert_eq!(score.score, 99);
+
+    add_host_inputs(&mut host, 5);
+    assert_eq!(guest_score(&mut host, GUEST).score, 94);
+
+    host.rx_guest_input_slice(
+        GUEST,
+        PlayerInputSlice::<PlayerInput>::new_test(0, 10).into(),
+    );
+    assert_eq!(guest_score(&mut host, GUEST).score, 100);
+}
+
+#[test]
+fn test_catch_ups_count_as_stalls() {
+    // Each catch-up sent to a guest counts as a stall, which stays recent for
+    // the stall window of sim time.
+    let mut host = Host::new(2, 5, 5, 60).with_catch_up_check_interval_sec(0.1);
+    for _ in 0..20 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    assert_eq!(host.poll_catch_up(0.1).len(), 1);
+
+    let components = guest_score(&mut host, GUEST).components;
+    assert_eq!((components.recent_stalls, components.num_stalls), (1, 1));
+
+    host.add_host_input_to_fill_needed(PlayerInput::default(), 11.0);
+    let components = guest_score(&mut host, GUEST).components;
+    assert_eq!((components.recent_stalls, components.num_stalls), (0, 1));
+}
@@ -0,0 +1,105 @@
+use crate::{
+    input_buffer::InputStatus,
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    tests::demo_input_struct::PlayerInput,
+};
+
+// a tick rate whose tick duration is exact in f32
+const TICKS_PER_SEC: u32 = 4;
+const TICK: f32 = 1.0 / TICKS_PER_SEC as f32;
+
+fn delayed_host(delay: u32) -> MultiplayerInputManager<PlayerInput, HostInputMgr> {
+    MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 100, 5, TICKS_PER_SEC)
+        .with_input_delay_ticks(delay)
+}
+
+#[test]
+fn test_delayed_inputs_take_effect_after_delay() {
+    // With a delay of 3 ticks, the host's first 3 ticks are default inputs,
+    // and each collected input is finalized 3 ticks after it was collected.
+    let mut host = delayed_host(3);
+    for i in 1..=5 {
+        host.add_host_input_to_fill_needed(PlayerInput::new_test_simple(i), TICK);
+    }
+
+    assert_eq!(host.get_peer_num_final_inputs(HOST_PLAYER_NUM), 5);
+    assert_eq!(host.get_own_num_inputs(), 8);
+    for tick in 0..3 {
+        assert_eq!(
+            host.get_peer_input_for_tick(HOST_PLAYER_NUM, tick),
+            PlayerInput::default()
+        );
+    }
+    for i in 1..=5u8 {
+        assert_eq!(
+            host.get_peer_input_for_tick(HOST_PLAYER_NUM, 2 + i as u32),
+            PlayerInput::new_test_simple(i)
+        );
+    }
+}
+
+#[test]
+fn test_finalized_slice_excludes_delayed_inputs() {
+    // Finalized slices broadcast by the host stop at the host's clock, even
+    // though later inputs are already in its buffer.
+    let mut host = delayed_host(3);
+    host.add_host_input_to_fill_needed(PlayerInput::new_test_simple(1), 4.0 * TICK);
+
+    let MsgPayload::HostToLobbyFinalizedSlice(slice) =
+        host.get_msg_finalized_slice(HOST_PLAYER_NUM)
+    else {
+        panic!("Expected HostFinalizedSlice");
+    };
+    assert_eq!(slice.host_tick, 4);
+    assert_eq!(slice.inputs.len(), 4);
+}
+
+#[test]
+fn test_guest_uses_provisional_host_inputs() {
+    // Provisional host inputs are stored by guests as non-final inputs, so they
+    // replace the LOCF prediction until the finalized inputs arrive.
+    let mut host = delayed_host(2);
+    let mut guest =
+        MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, 1.into(), TICKS_PER_SEC);
+    host.add_host_input_to_fill_needed(PlayerInput::new_test_simple(1), TICK);
+    host.add_host_input_to_fill_needed(PlayerInput::new_test_simple(2), TICK);
+
+    guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(HOST_PLAYER_NUM));
+    guest.rx_peer_input_slice(HOST_PLAYER_NUM, host.get_msg_provisional_own_inputs());
+
+    assert_eq!(
+        guest.get_peer_input_for_tick(HOST_PLAYER_NUM, 3),
+        PlayerInput::new_test_simple(2)
+    );
+    assert_eq!(
+        guest.get_input_statuses(3)[0],
+        (HOST_PLAYER_NUM, InputStatus::NonFinal { num_finalized: 2 })
+    );
+
+    host.add_host_input_to_fill_needed(PlayerInput::new_test_simple(3), 2.0 * TICK);
+    guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(HOST_PLAYER_NUM));
+    assert_eq!(
+        guest.get_input_statuses(3)[0],
+        (HOST_PLAYER_NUM, InputStatus::Finalized { num_finalized: 4 })
+    );
+    assert_eq!(
+        guest.get_peer_input_for_tick(HOST_PLAYER_NUM, 3),
+        PlayerInput::new_test_simple(2)
+    );
+}
+
+#[test]
+fn test_no_provisional_inputs_without_delay() {
+    // Without input delay, every host input is finalized immediately, so there
+    // is nothing provisional to send.
+    let mut host = delayed_host(0);
+    host.add_host_input_to_fill_needed(PlayerInput::new_test_simple(1), 3.0 * TICK);
+    assert_eq!(host.get_own_num_inputs(), 3);
+    assert!(matches!(
+        host.get_msg_provisional_own_inputs(),
+        MsgPayload::Empty
+    ));
+}
@@ -0,0 +1,182 @@
+use test_case::test_case;
+
+use crate::{
+    events::InputMgrEvent,
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HostInputMgr, LagDowngradePolicy},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST_1: PlayerNum = PlayerNum(1);
+const GUEST_2: PlayerNum = PlayerNum(2);
+
+const POLICY: LagDowngradePolicy = LagDowngradePolicy {
+    max_ticks_behind: 10,
+    sustained_sec: 1.0,
+};
+
+/// A 3 player host with 30 inputs of its own, and `guest_1_inputs` and 30 inputs received from its guests.
+fn host_with_lagging_guest(guest_1_inputs: u32) -> Host {
+    let mut host = Host::new(3, 50, 5, 60)
+        .with_catch_up_check_interval_sec(0.0)
+        .with_lag_downgrade_policy(POLICY);
+    for _ in 0..30 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host.rx_guest_input_slice(
+        GUEST_1,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, guest_1_inputs)),
+    );
+    host.rx_guest_input_slice(
+        GUEST_2,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 30)),
+    );
+    host
+}
+
+fn downgraded(events: Vec<InputMgrEvent>) -> Vec<PlayerNum> {
+    events
+        .into_iter()
+        .filter_map(|event| match event {
+            InputMgrEvent::GuestDowngraded { player_num, .. } => Some(player_num),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_no_downgrade_without_policy() {
+    // Without opting in, even a guest far behind for a long time is left
+    // alone.
+    let mut host = Host::new(3, 50, 5, 60);
+    for _ in 0..100 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    assert!(host.poll_lagging_guests(60.0).is_empty());
+    assert_eq!(host.muted_from_tick(GUEST_1), None);
+}
+
+#[test_case(15, 0.5, false; "lagging for less than the sustained period")]
+#[test_case(15, 1.0, true; "lagging for the sustained period")]
+#[test_case(15, 1.5, true; "lagging for longer than the sustained period")]
+#[test_case(20, 1.5, false; "at the lag threshold")]
+fn test_downgrade_after_sustained_lag(guest_1_inputs: u32, lag_sec: f32, expect_downgrade: bool) {
+    // Only a guest further behind than the threshold for the whole sustained
+    // period is downgraded; guests keeping up are never touched.
+    let mut host = host_with_lagging_guest(guest_1_inputs);
+
+    let mut msgs = vec![];
+    for _ in 0..(lag_sec / 0.25) as u32 {
+        msgs.extend(host.poll_lagging_guests(0.25));
+    }
+
+    assert_eq!(msgs.len(), expect_downgrade as usize);
+    assert_eq!(host.muted_from_tick(GUEST_1).is_some(), expect_downgrade);
+    assert_eq!(host.muted_from_tick(GUEST_2), None);
+    let expected_events = if expect_downgrade {
+        vec![GUEST_1]
+    } else {
+        vec![]
+    };
+    assert_eq!(downgraded(host.drain_events()), expected_events);
+}
+
+#[test]
+fn test_recovering_guest_restarts_the_sustained_period() {
+    // A guest that catches up before the sustained period ends starts over
+    // the next time it falls behind.
+    let mut host = host_with_lagging_guest(15);
+    host.poll_lagging_guests(0.75);
+    host.rx_guest_input_slice(
+        GUEST_1,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(15, 15)),
+    );
+    host.poll_lagging_guests(0.25);
+    for _ in 0..20 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host.rx_guest_input_slice(
+        GUEST_2,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(30, 20)),
+    );
+
+    assert!(host.poll_lagging_guests(0.75).is_empty());
+    assert_eq!(host.poll_lagging_guests(0.25).len(), 1);
+}
+
+#[test]
+fn test_downgraded_seat_is_filled_with_defaults() {
+    // A downgraded guest is muted from the host's tick, and its seat is
+    // filled with default inputs up to the host's tick by catch-up, so the
+    // match is no longer held back by it.
+    let mut host = host_with_lagging_guest(10);
+    let msgs = host.poll_lagging_guests(1.0);
+
+    assert!(matches!(msgs[..], [MsgPayload::HostToLobbyPlayerMuted(_)]));
+    assert_eq!(host.muted_from_tick(GUEST_1), Some(30));
+    assert!(host.poll_lagging_guests(1.0).is_empty());
+
+    host.poll_catch_up(1.0);
+    assert!(host.get_peer_num_final_inputs(GUEST_1) >= 30);
+}
+
+#[test]
+fn test_lobby_is_notified_of_downgrade() {
+    // Guests raise a PlayerMuted event when the host's mute arrives, once
+    // even if the message is resent.
+    let mut host = host_with_lagging_guest(10);
+    let msg = host.poll_lagging_guests(1.0).remove(0);
+    let mut guest = Guest::new(3, GUEST_1, 60);
+
+    guest.rx_player_muted(msg.clone());
+    guest.rx_player_muted(msg);
+
+    assert_eq!(
+        guest.drain_events(),
+        vec![InputMgrEvent::PlayerMuted {
+            player_num: GUEST_1,
+            from_tick: 30,
+        }]
+    );
+}
+
+#[test_case(26, false; "8 sim ticks behind")]
+#[test_case(24, true; "12 sim ticks behind")]
+fn test_lag_is_measured_in_sim_ticks(guest_1_inputs: u32, expect_downgrade: bool) {
+    // With each input covering 2 sim ticks, a guest 6 inputs behind is 12 sim
+    // ticks behind, past the policy's 10.
+    let mut host = Host::new(3, 50, 5, 60)
+        .with_sim_ticks_per_input(2)
+        .with_lag_downgrade_policy(POLICY);
+    for _ in 0..30 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host.rx_guest_input_slice(
+        GUEST_1,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, guest_1_inputs)),
+    );
+    host.rx_guest_input_slice(
+        GUEST_2,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 30)),
+    );
+
+    host.poll_lagging_guests(1.0);
+    assert_eq!(host.muted_from_tick(GUEST_1).is_some(), expect_downgrade);
+}
+
+#[test]
+fn test_disconnected_guest_is_not_downgraded() {
+    // A disconnected guest's seat is already filled with defaults, so it is
+    // never downgraded, however far behind it falls.
+    let mut host = host_with_lagging_guest(0);
+    host.player_disconnected(GUEST_1);
+
+    assert!(host.poll_lagging_guests(1.0).is_empty());
+    assert_eq!(host.muted_from_tick(GUEST_1), None);
+}
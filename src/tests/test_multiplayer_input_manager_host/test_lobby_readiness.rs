@@ -0,0 +1,85 @@
+use crate::{
+    input_messages::{MsgPayload, PongPong, PreSimSync},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::{HostInputMgr, LobbyPeerStatus},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+
+#[test]
+fn test_readiness_before_any_pings() {
+    // Every guest is listed, with nothing known about it yet.
+    let mut host = Host::new(3, 50, 5, 60);
+    let unknown = LobbyPeerStatus {
+        rtt_ms: None,
+        pings_exchanged: 0,
+        last_seen_ms: None,
+    };
+    assert_eq!(
+        host.lobby_readiness(),
+        vec![(PlayerNum(1), unknown), (PlayerNum(2), unknown)]
+    );
+    assert_eq!(host.recommended_countdown_ticks(), None);
+    assert!(
+        host.get_msg_pre_sim_sync_with_recommended_countdown()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_readiness_tracks_pings_and_last_seen() {
+    // A ping marks the guest as seen; a completed round trip counts as an
+    // exchanged ping and gives an RTT. Last seen grows with lobby time.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.advance_lobby_time(1.0);
+    host.rx_guest_ping_and_reply(PlayerNum(1), MsgPayload::GuestToHostPing(7));
+    host.advance_lobby_time(0.25);
+
+    let (_, status) = host.lobby_readiness()[0];
+    assert_eq!(status.pings_exchanged, 0);
+    assert_eq!(status.last_seen_ms, Some(250.0));
+
+    host.rx_guest_pong_pong(
+        PlayerNum(1),
+        MsgPayload::GuestToHostPongPong(PongPong {
+            ping_id: 7,
+            guest_rtt_micros: 50_000,
+        }),
+    )
+    .unwrap();
+    let (_, status) = host.lobby_readiness()[0];
+    assert_eq!(status.pings_exchanged, 1);
+    assert_eq!(status.last_seen_ms, Some(0.0));
+    assert!(status.rtt_ms.is_some());
+}
+
+#[test]
+fn test_countdown_follows_worst_rtt() {
+    // At 60 ticks/sec, the worst RTT of 110ms is 6.6 ticks, rounded up to 7,
+    // plus the margin; the countdown feeds straight into PreSimSync.
+    let mut host = Host::new(3, 50, 5, 60).with_countdown_margin_ticks(4);
+    host.observe_guest_rtt_ms(PlayerNum(1), 40.0);
+    assert_eq!(host.recommended_countdown_ticks(), None);
+
+    host.observe_guest_rtt_ms(PlayerNum(2), 110.0);
+    assert_eq!(host.recommended_countdown_ticks(), Some(11));
+
+    let Some(MsgPayload::HostToGuestPreSimSync(PreSimSync {
+        host_tick_countdown,
+        ..
+    })) = host.get_msg_pre_sim_sync_with_recommended_countdown()
+    else {
+        panic!("expected a PreSimSync");
+    };
+    assert_eq!(host_tick_countdown, 11);
+}
+
+#[test]
+fn test_countdown_is_capped() {
+    // A very slow guest can't overflow the u8 countdown.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.observe_guest_rtt_ms(PlayerNum(1), 10_000.0);
+    assert_eq!(host.recommended_countdown_ticks(), Some(u8::MAX));
+}
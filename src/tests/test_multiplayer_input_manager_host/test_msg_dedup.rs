@@ -0,0 +1,66 @@
+use test_case::test_case;
+
+use crate::{
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+
+/// How a second message differs from the first one sent to guest 1.
+enum Resend {
+    Identical,
+    LaterHostTick,
+    ToOtherGuest,
+    OtherRange,
+    OtherKind,
+    AfterWindow,
+}
+
+#[test_case(Some(0.5), Resend::Identical, true; "identical msg is suppressed")]
+#[test_case(Some(0.5), Resend::LaterHostTick, true; "same inputs stamped with a later host tick are suppressed")]
+#[test_case(Some(0.5), Resend::ToOtherGuest, false; "same msg to another guest is sent")]
+#[test_case(Some(0.5), Resend::OtherRange, false; "different input range is sent")]
+#[test_case(Some(0.5), Resend::OtherKind, false; "different msg kind is sent")]
+#[test_case(Some(0.5), Resend::AfterWindow, false; "identical msg after the window is sent")]
+#[test_case(None, Resend::Identical, false; "nothing is suppressed without a window")]
+fn test_resend_suppression(window_sec: Option<f32>, resend: Resend, suppressed: bool) {
+    // A 3 player host sends guest 1 a finalized slice of player 2's inputs,
+    // then a second message; only a repeat of the same content to the same
+    // guest within the window is suppressed and counted.
+    let mut host = Host::new(3, 2, 5, 10);
+    if let Some(window_sec) = window_sec {
+        host = host.with_msg_dedup_window_sec(window_sec);
+    }
+    host.add_host_input_to_fill_needed(PlayerInput::default(), 1.0);
+    let first =
+        MsgPayload::HostToLobbyFinalizedSlice(HostFinalizedSlice::new_test(PlayerNum(2), 10, 0, 4));
+    host.suppress_duplicate_msg_for_guest(PlayerNum(1), first.clone());
+
+    let slice_at = |host_tick, start| {
+        MsgPayload::HostToLobbyFinalizedSlice(HostFinalizedSlice::new_test(
+            PlayerNum(2),
+            host_tick,
+            start,
+            4,
+        ))
+    };
+    let (dest, second) = match resend {
+        Resend::Identical => (PlayerNum(1), first),
+        Resend::LaterHostTick => (PlayerNum(1), slice_at(12, 0)),
+        Resend::ToOtherGuest => (PlayerNum(2), first),
+        Resend::OtherRange => (PlayerNum(1), slice_at(10, 1)),
+        Resend::OtherKind => (PlayerNum(1), MsgPayload::HostToGuestRateAdjust(5)),
+        Resend::AfterWindow => {
+            host.add_host_input_to_fill_needed(PlayerInput::default(), 0.6);
+            (PlayerNum(1), first)
+        }
+    };
+
+    let sent = host.suppress_duplicate_msg_for_guest(dest, second);
+    assert_eq!(matches!(sent, MsgPayload::Empty), suppressed);
+    assert_eq!(host.num_suppressed_duplicate_msgs(dest), suppressed as u32);
+}
@@ -0,0 +1,110 @@
+use crate::{
+    input_messages::{MsgPayload, PlayerMuted},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    rx_outcome::RxRejection,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+fn host_with_inputs(num_inputs: u32) -> MultiplayerInputManager<PlayerInput, HostInputMgr> {
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(3, 5, 5, 60)
+        .with_catch_up_check_interval_sec(0.1);
+    for _ in 0..num_inputs {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host
+}
+
+fn guest_inputs(start: u32, num_inputs: u32) -> MsgPayload<PlayerInput> {
+    MsgPayload::PeerInputs(PlayerInputSlice::new_test(start, num_inputs))
+}
+
+#[test]
+fn test_mute_truncates_guest_slices() {
+    // Inputs from a muted guest are accepted up to the mute tick, and slices
+    // entirely past it are rejected.
+    let mut host = host_with_inputs(20);
+    host.rx_guest_input_slice(GUEST, guest_inputs(0, 4));
+
+    let msg = host.mute_player(GUEST, 6);
+    assert!(matches!(
+        msg,
+        MsgPayload::HostToLobbyPlayerMuted(PlayerMuted {
+            player_num: GUEST,
+            first_ignored_input: 6
+        })
+    ));
+    assert_eq!(host.muted_from_tick(GUEST), Some(6));
+
+    let outcome = host.rx_guest_input_slice(GUEST, guest_inputs(4, 5));
+    assert_eq!(outcome.newly_finalized, 2);
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 6);
+
+    let outcome = host.rx_guest_input_slice(GUEST, guest_inputs(6, 3));
+    assert_eq!(outcome.rejected, Some(RxRejection::PlayerMuted));
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 6);
+}
+
+#[test]
+fn test_mute_does_not_undo_finalized_inputs() {
+    // Muting at a tick the guest has already finalized takes effect from the
+    // guest's next unfinalized input instead.
+    let mut host = host_with_inputs(20);
+    host.rx_guest_input_slice(GUEST, guest_inputs(0, 8));
+
+    host.mute_player(GUEST, 3);
+    assert_eq!(host.muted_from_tick(GUEST), Some(8));
+    assert_eq!(host.muted_from_tick(PlayerNum(2)), None);
+}
+
+#[test]
+fn test_muted_seat_is_filled_with_defaults() {
+    // Like a disconnected player, a muted player's seat is filled with final
+    // default inputs up to the host's tick by poll_catch_up.
+    let mut host = host_with_inputs(20);
+    host.rx_guest_input_slice(GUEST, guest_inputs(0, 18));
+    host.rx_guest_input_slice(PlayerNum(2), guest_inputs(0, 18));
+    host.mute_player(GUEST, 18);
+
+    let msgs = host.poll_catch_up(0.1);
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].0, GUEST);
+    assert!(host.get_peer_num_final_inputs(GUEST) >= 20);
+    assert_eq!(
+        host.get_peer_input_for_tick(GUEST, 19),
+        PlayerInput::default()
+    );
+}
+
+#[test]
+fn test_guest_ignores_muted_peer_inputs() {
+    // After the host's mute broadcast, a guest ignores inputs sent directly by
+    // the muted peer past the mute tick, but still accepts earlier ones.
+    let mut host = host_with_inputs(20);
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(3, 2.into(), 60);
+
+    guest.rx_player_muted(host.mute_player(GUEST, 3));
+    assert_eq!(guest.muted_from_tick(GUEST), Some(3));
+
+    let outcome = guest.rx_peer_input_slice(GUEST, guest_inputs(0, 5));
+    assert_eq!(outcome.new_inputs, 3);
+    let outcome = guest.rx_peer_input_slice(GUEST, guest_inputs(3, 2));
+    assert_eq!(outcome.rejected, Some(RxRejection::PlayerMuted));
+}
+
+#[test]
+fn test_mute_persists_across_rounds() {
+    // A player muted in one round is muted from the start of the next.
+    let mut host = host_with_inputs(20);
+    host.mute_player(GUEST, 10);
+
+    host.start_new_round();
+    host.rx_round_transition_ack(GUEST, MsgPayload::GuestToHostRoundTransitionAck(1));
+    assert_eq!(host.muted_from_tick(GUEST), Some(0));
+    let outcome = host.rx_guest_input_slice(GUEST, guest_inputs(0, 3));
+    assert_eq!(outcome.rejected, Some(RxRejection::PlayerMuted));
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use crate::{
+    finalized_observations_per_guest::ObservationBlocker,
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+
+/// A 3 player host with 10 finalized inputs for every player, so guests can ack up to 10 of each.
+fn host_with_finalized_inputs() -> Host {
+    let mut host = Host::new(3, 50, 5, 60);
+    for _ in 0..10 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    for guest in [PlayerNum(1), PlayerNum(2)] {
+        host.rx_guest_input_slice(
+            guest,
+            MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 10)),
+        );
+    }
+    host
+}
+
+fn ack(host: &mut Host, guest: u8, acked: [u32; 3]) {
+    let seen = HashMap::from([
+        (PlayerNum(0), acked[0]),
+        (PlayerNum(1), acked[1]),
+        (PlayerNum(2), acked[2]),
+    ]);
+    host.rx_finalized_ticks_observations(
+        PlayerNum(guest),
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(seen)),
+    );
+}
+
+#[test]
+fn test_observation_matrix_lists_each_guests_acks() {
+    // The matrix has a row per guest with its acks for every peer, sorted by
+    // player num.
+    let mut host = host_with_finalized_inputs();
+    ack(&mut host, 1, [4, 6, 2]);
+    ack(&mut host, 2, [3, 5, 7]);
+
+    assert_eq!(
+        host.observation_matrix(),
+        vec![
+            (
+                PlayerNum(1),
+                vec![(PlayerNum(0), 4), (PlayerNum(1), 6), (PlayerNum(2), 2)]
+            ),
+            (
+                PlayerNum(2),
+                vec![(PlayerNum(0), 3), (PlayerNum(1), 5), (PlayerNum(2), 7)]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_observation_matrix_keeps_player_nums_after_a_removal() {
+    // Removing a guest drops its row, and the remaining guest's row keeps its
+    // own player num.
+    let mut host = host_with_finalized_inputs();
+    host.remove_player(PlayerNum(1)).unwrap();
+    ack(&mut host, 2, [3, 5, 7]);
+
+    let rows: Vec<PlayerNum> = host
+        .observation_matrix()
+        .into_iter()
+        .map(|(guest, _)| guest)
+        .collect();
+    assert_eq!(rows, vec![PlayerNum(2)]);
+}
+
+#[test]
+fn test_observation_blockers_name_the_guests_with_the_minimum_ack() {
+    // For each peer, the blockers are the guests that have acked the fewest
+    // of its inputs; tied guests are all listed.
+    let mut host = host_with_finalized_inputs();
+    ack(&mut host, 1, [4, 5, 2]);
+    ack(&mut host, 2, [3, 5, 7]);
+
+    assert_eq!(
+        host.observation_blockers(),
+        vec![
+            ObservationBlocker {
+                peer: HOST_PLAYER_NUM,
+                min_acked: 3,
+                guests: vec![PlayerNum(2)],
+            },
+            ObservationBlocker {
+                peer: PlayerNum(1),
+                min_acked: 5,
+                guests: vec![PlayerNum(1), PlayerNum(2)],
+            },
+            ObservationBlocker {
+                peer: PlayerNum(2),
+                min_acked: 2,
+                guests: vec![PlayerNum(1)],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_solo_host_has_no_blockers() {
+    // Without guests there is nothing to block on.
+    let host = Host::new(1, 50, 5, 60);
+    assert!(host.observation_matrix().is_empty());
+    assert!(host.observation_blockers().is_empty());
+}
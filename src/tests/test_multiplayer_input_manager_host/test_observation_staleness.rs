@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use test_case::test_case;
+
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+
+/// A 3 player host at 60 inputs/sec, with the given staleness timeout.
+fn host_with_timeout(timeout_sec: Option<f32>) -> Host {
+    let host = Host::new(3, 1000, 5, 60);
+    match timeout_sec {
+        Some(timeout_sec) => host.with_observation_staleness_timeout_sec(timeout_sec),
+        None => host,
+    }
+}
+
+/// Runs the host's sim forward by whole seconds.
+fn run_sec(host: &mut Host, sec: u32) {
+    for _ in 0..sec {
+        host.add_host_input_to_fill_needed(PlayerInput::default(), 1.0);
+    }
+}
+
+fn ack_host_inputs(host: &mut Host, guest: PlayerNum, num_acked: u32) {
+    host.rx_finalized_ticks_observations(
+        guest,
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
+            HashMap::from([(HOST_PLAYER_NUM, num_acked)]),
+        )),
+    );
+}
+
+/// The start and length of the host's broadcast finalized slice.
+fn host_slice_range(host: &Host) -> (u32, u32) {
+    match host.get_msg_finalized_slice(HOST_PLAYER_NUM) {
+        MsgPayload::HostToLobbyFinalizedSlice(slice) => (slice.inputs.start, slice.inputs.len()),
+        other => panic!("unexpected message: {other:?}"),
+    }
+}
+
+#[test]
+fn test_without_timeout_a_silent_guest_pins_the_broadcast_start() {
+    // With no timeout (the default), a guest that never acks holds every
+    // broadcast slice at input 0, however long the host runs.
+    let mut host = host_with_timeout(None);
+    run_sec(&mut host, 1);
+    ack_host_inputs(&mut host, PlayerNum(1), 50);
+    run_sec(&mut host, 9);
+
+    assert_eq!(host.observation_staleness_timeout_sec(), None);
+    assert!(host.guests_with_stale_observations().is_empty());
+    assert_eq!(host_slice_range(&host), (0, 600));
+}
+
+#[test]
+fn test_stale_guest_stops_holding_back_the_broadcast_start() {
+    // Once the silent guest has gone past the timeout without acking, the
+    // broadcast starts from what the guest that keeps acking has seen.
+    let mut host = host_with_timeout(Some(2.0));
+    run_sec(&mut host, 1);
+    ack_host_inputs(&mut host, PlayerNum(1), 50);
+
+    // 1 sec without an ack is still within the timeout
+    assert!(host.guests_with_stale_observations().is_empty());
+    assert_eq!(host_slice_range(&host), (0, 60));
+
+    run_sec(&mut host, 2);
+    assert!(host.is_observation_stale(PlayerNum(2)));
+    assert!(!host.is_observation_stale(PlayerNum(1)));
+    assert_eq!(host.guests_with_stale_observations(), vec![PlayerNum(2)]);
+    assert_eq!(host_slice_range(&host), (50, 130));
+}
+
+#[test]
+fn test_guest_counts_again_once_it_acks() {
+    // A stale guest that acks again holds back the broadcast start again.
+    let mut host = host_with_timeout(Some(2.0));
+    run_sec(&mut host, 3);
+    ack_host_inputs(&mut host, PlayerNum(1), 150);
+    assert_eq!(host_slice_range(&host), (150, 30));
+
+    ack_host_inputs(&mut host, PlayerNum(2), 10);
+    assert!(host.guests_with_stale_observations().is_empty());
+    assert_eq!(host_slice_range(&host), (10, 170));
+}
+
+#[test_case(false ; "owned message")]
+#[test_case(true ; "serialized straight from the buffer")]
+fn test_all_guests_stale_sends_nothing_already_finalized(from_bytes: bool) {
+    // With every guest stale there is no ack to start from, so the broadcast
+    // slice is empty rather than the whole history.
+    let mut host = host_with_timeout(Some(2.0));
+    run_sec(&mut host, 1);
+    ack_host_inputs(&mut host, PlayerNum(1), 50);
+    ack_host_inputs(&mut host, PlayerNum(2), 40);
+    run_sec(&mut host, 3);
+
+    assert_eq!(
+        host.guests_with_stale_observations(),
+        vec![PlayerNum(1), PlayerNum(2)]
+    );
+    let msg = if from_bytes {
+        MsgPayload::from_bytes(&host.get_msg_bytes_finalized_slice(HOST_PLAYER_NUM)).unwrap()
+    } else {
+        host.get_msg_finalized_slice(HOST_PLAYER_NUM)
+    };
+    let MsgPayload::HostToLobbyFinalizedSlice(slice) = msg else {
+        panic!("unexpected message: {msg:?}");
+    };
+    assert_eq!((slice.inputs.start, slice.inputs.len()), (240, 0));
+}
+
+#[test]
+fn test_new_round_resets_ack_times() {
+    // Ack times are per round: after `start_new_round`, guests get the full
+    // timeout again before they count as stale.
+    let mut host = host_with_timeout(Some(2.0));
+    run_sec(&mut host, 3);
+    assert_eq!(host.guests_with_stale_observations().len(), 2);
+
+    host.start_new_round();
+    run_sec(&mut host, 1);
+    assert!(host.guests_with_stale_observations().is_empty());
+}
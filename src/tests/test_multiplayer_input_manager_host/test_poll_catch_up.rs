@@ -0,0 +1,106 @@
+use test_case::test_case;
+
+use crate::{
+    input_messages::MsgPayload, multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::HostInputMgr, tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+const MAX_TICKS_BEHIND: u32 = 5;
+
+fn host_with_inputs(num_inputs: u32) -> MultiplayerInputManager<PlayerInput, HostInputMgr> {
+    let mut host =
+        MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(3, MAX_TICKS_BEHIND, 5, 60)
+            .with_catch_up_check_interval_sec(0.1)
+            .with_catch_up_hysteresis_ticks(3);
+    add_host_inputs(&mut host, num_inputs);
+    host
+}
+
+fn add_host_inputs(host: &mut MultiplayerInputManager<PlayerInput, HostInputMgr>, n: u32) {
+    for _ in 0..n {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+}
+
+fn caught_up_guests(msgs: &[(PlayerNum, MsgPayload<PlayerInput>)]) -> Vec<PlayerNum> {
+    msgs.iter().map(|(guest, _)| *guest).collect()
+}
+
+#[test]
+fn test_checks_guests_on_interval() {
+    // Guests are only checked once the check interval has elapsed, and the
+    // returned slices catch each lagging guest up to the target.
+    let mut host = host_with_inputs(20);
+
+    assert!(host.poll_catch_up(0.05).is_empty());
+    let msgs = host.poll_catch_up(0.05);
+    assert_eq!(caught_up_guests(&msgs), vec![PlayerNum(1), PlayerNum(2)]);
+    assert!(matches!(
+        msgs[0].1,
+        MsgPayload::HostToLobbyFinalizedSlice(_)
+    ));
+    assert_eq!(host.get_peer_num_final_inputs(PlayerNum(1)), 16);
+}
+
+#[test]
+fn test_hysteresis_avoids_tiny_catch_ups() {
+    // Once caught up, a guest is not caught up again until it falls at least
+    // the hysteresis number of ticks behind the target.
+    let mut host = host_with_inputs(20);
+    host.poll_catch_up(0.1);
+    // the catch-up fills up to and including the target tick
+    assert_eq!(host.get_peer_num_final_inputs(PlayerNum(1)), 16);
+
+    add_host_inputs(&mut host, 3);
+    assert!(host.poll_catch_up(0.1).is_empty());
+
+    add_host_inputs(&mut host, 1);
+    assert_eq!(host.poll_catch_up(0.1).len(), 2);
+}
+
+#[test]
+fn test_disconnected_guests_always_filled_to_host_tick() {
+    // Disconnected guests are filled up to the host's tick without hysteresis.
+    let mut host = host_with_inputs(20);
+    host.player_disconnected(PlayerNum(2));
+    let msgs = host.poll_catch_up(0.1);
+    assert_eq!(host.get_peer_num_final_inputs(PlayerNum(2)), 21);
+
+    add_host_inputs(&mut host, 2);
+    let msgs_after = host.poll_catch_up(0.1);
+    assert_eq!(msgs.len(), 2);
+    assert_eq!(caught_up_guests(&msgs_after), vec![PlayerNum(2)]);
+}
+
+#[test]
+fn test_skips_guests_pending_round_ack() {
+    // Guests that haven't acked a new round aren't sent catch-up slices.
+    let mut host = host_with_inputs(0);
+    host.start_new_round();
+    host.rx_round_transition_ack(PlayerNum(1), MsgPayload::GuestToHostRoundTransitionAck(1));
+    add_host_inputs(&mut host, 20);
+
+    let msgs = host.poll_catch_up(0.1);
+    assert_eq!(caught_up_guests(&msgs), vec![PlayerNum(1)]);
+}
+
+#[test_case(false; "disconnected")]
+#[test_case(true; "muted")]
+fn test_guest_dropped_mid_round_transition_is_filled(muted: bool) {
+    // A guest that drops before acking a new round is no longer waited on:
+    // its seat is filled with default inputs up to the host's tick.
+    let mut host = host_with_inputs(0);
+    host.start_new_round();
+    host.rx_round_transition_ack(PlayerNum(1), MsgPayload::GuestToHostRoundTransitionAck(1));
+    if muted {
+        host.mute_player(PlayerNum(2), 0);
+    } else {
+        host.player_disconnected(PlayerNum(2));
+    }
+    add_host_inputs(&mut host, 20);
+
+    let msgs = host.poll_catch_up(0.1);
+    assert_eq!(caught_up_guests(&msgs), vec![PlayerNum(1), PlayerNum(2)]);
+    assert_eq!(host.get_peer_num_final_inputs(PlayerNum(2)), 21);
+}
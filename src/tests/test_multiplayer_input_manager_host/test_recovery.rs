@@ -0,0 +1,178 @@
+use crate::{
+    events::InputMgrEvent,
+    host_recovery::RecoveryResponse,
+    input_messages::MsgPayload,
+    input_trait::SimInput,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    rx_outcome::RxRejection,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUESTS: [PlayerNum; 2] = [PlayerNum(1), PlayerNum(2)];
+
+/// A 3 player match in which the host has finalized 8 inputs for each player. Guest 1 has seen all of them, while guest 2 last heard from the host when it had only 5 of its own.
+fn played_match() -> (Host, Guest, Guest) {
+    let mut host = Host::new(3, 50, 5, 60);
+    let mut guest_1 = Guest::new(3, GUESTS[0], 60);
+    let mut guest_2 = Guest::new(3, GUESTS[1], 60);
+    for x in 0..8 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(x));
+        guest_1.add_own_input(PlayerInput::new_test_simple(x + 10));
+        guest_2.add_own_input(PlayerInput::new_test_simple(x + 20));
+        if x == 4 {
+            guest_2
+                .rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(HOST_PLAYER_NUM));
+        }
+    }
+    host.rx_guest_input_slice(GUESTS[0], guest_1.get_msg_own_input_slice());
+    host.rx_guest_input_slice(GUESTS[1], guest_2.get_msg_own_input_slice());
+    for player_num in PlayerNum::iter(3) {
+        let slice = host.get_msg_finalized_slice(player_num);
+        guest_1.rx_final_peer_input_slice_from_host(slice.clone());
+        if player_num != HOST_PLAYER_NUM {
+            guest_2.rx_final_peer_input_slice_from_host(slice);
+        }
+    }
+    (host, guest_1, guest_2)
+}
+
+/// Runs a full recovery of a fresh host against these guests.
+fn recover(guests: [&Guest; 2]) -> Host {
+    let mut host = Host::new(3, 50, 5, 60);
+    let request = host.begin_recovery(1).unwrap();
+    for (guest_num, guest) in GUESTS.into_iter().zip(guests) {
+        let response = guest.rx_recovery_request_and_reply(request.clone());
+        host.rx_recovery_response(guest_num, response);
+    }
+    host
+}
+
+fn response(
+    request_id: u32,
+    finalized: Vec<PlayerInputSlice<PlayerInput>>,
+) -> MsgPayload<PlayerInput> {
+    MsgPayload::GuestToHostRecoveryResponse(RecoveryResponse {
+        request_id,
+        finalized,
+    })
+}
+
+#[test]
+fn test_restarted_host_recovers_finalized_history() {
+    // Once every guest has responded, the restarted host has the original
+    // host's finalized history, even though no single guest lagging behind
+    // has all of it.
+    let (original, guest_1, guest_2) = played_match();
+    let mut host = recover([&guest_1, &guest_2]);
+
+    assert!(!host.is_recovering());
+    assert_eq!(
+        host.get_final_inputs_by_tick(),
+        original.get_final_inputs_by_tick()
+    );
+    assert_eq!(
+        host.drain_events(),
+        vec![InputMgrEvent::HostRecovered {
+            num_finalized: vec![8, 8, 8]
+        }]
+    );
+}
+
+#[test]
+fn test_recovered_host_resumes_finalization() {
+    // After recovery, the host's clock resumes from its recovered inputs, and
+    // its finalized slices start from what each guest reported having.
+    let (_, guest_1, guest_2) = played_match();
+    let mut host = recover([&guest_1, &guest_2]);
+
+    host.add_host_input_to_fill_needed(PlayerInput::default(), 1.0 / 60.0);
+    assert_eq!(host.get_own_num_inputs(), 9);
+
+    let MsgPayload::HostToLobbyFinalizedSlice(slice) =
+        host.get_msg_finalized_slice(HOST_PLAYER_NUM)
+    else {
+        panic!("expected a finalized slice");
+    };
+    assert_eq!(slice.inputs.start, 5);
+    assert_eq!(slice.inputs.len(), 4);
+}
+
+#[test]
+fn test_conflicting_guests_limit_the_recovered_prefix() {
+    // Where guests disagree about a finalized input, the recovered history
+    // for that player stops just before it.
+    let mut host = Host::new(3, 50, 5, 60);
+    let request_id = 7;
+    host.begin_recovery(request_id).unwrap();
+    let mut tampered = PlayerInputSlice::new_test(0, 6);
+    tampered.inputs[3] = PlayerInput::new_test_simple(99).to_bytes();
+    host.rx_recovery_response(
+        GUESTS[0],
+        response(
+            request_id,
+            vec![
+                PlayerInputSlice::new_test(0, 6),
+                PlayerInputSlice::new_test(0, 6),
+                PlayerInputSlice::new_test(0, 6),
+            ],
+        ),
+    );
+    host.rx_recovery_response(
+        GUESTS[1],
+        response(
+            request_id,
+            vec![
+                tampered,
+                PlayerInputSlice::new_test(0, 4),
+                PlayerInputSlice::new_test(0, 6),
+            ],
+        ),
+    );
+
+    for (player_num, expected) in PlayerNum::iter(3).zip([3, 6, 6]) {
+        assert_eq!(host.get_peer_num_final_inputs(player_num), expected);
+    }
+}
+
+#[test]
+fn test_recovering_host_ignores_inputs_and_stale_responses() {
+    // While recovering, the host collects no inputs of its own, rejects guest
+    // input slices, and ignores responses to other requests; finishing early
+    // uses only the responses it has.
+    let mut host = Host::new(3, 50, 5, 60);
+    host.begin_recovery(2).unwrap();
+    host.add_host_input_to_fill_needed(PlayerInput::default(), 1.0);
+    assert_eq!(host.get_own_num_inputs(), 0);
+    let outcome = host.rx_guest_input_slice(
+        GUESTS[0],
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 3)),
+    );
+    assert_eq!(outcome.rejected, Some(RxRejection::HostRecovering));
+
+    let stale = response(1, vec![PlayerInputSlice::new_test(0, 4); 3]);
+    assert_eq!(
+        host.rx_recovery_response(GUESTS[0], stale).rejected,
+        Some(RxRejection::UnexpectedMsg)
+    );
+    host.rx_recovery_response(
+        GUESTS[0],
+        response(2, vec![PlayerInputSlice::new_test(0, 2); 3]),
+    );
+    assert!(host.is_recovering());
+    assert_eq!(host.finish_recovery(), Ok(vec![2, 2, 2]));
+    assert!(host.finish_recovery().is_err());
+}
+
+#[test]
+fn test_host_with_inputs_cant_begin_recovery() {
+    // A host that already has inputs hasn't restarted, so can't recover.
+    let (mut host, _, _) = played_match();
+    assert!(host.begin_recovery(1).is_err());
+    assert!(!host.is_recovering());
+}
@@ -0,0 +1,159 @@
+use test_case::test_case;
+
+use crate::{
+    ResendPolicy,
+    input_messages::MsgPayload,
+    input_trait::SimInput,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+const OTHER_GUEST: PlayerNum = PlayerNum(2);
+
+fn input(x: u8) -> PlayerInput {
+    PlayerInput::new_test_simple(x)
+}
+
+/// The guest's first slice, of 4 inputs.
+fn first_slice() -> MsgPayload<PlayerInput> {
+    MsgPayload::PeerInputs(PlayerInputSlice {
+        start: 0,
+        inputs: (0..4).map(|i| input(10 + i).to_bytes()).collect(),
+    })
+}
+
+/// The guest's resend after a client-side rollback changed its inputs, with 2 new inputs.
+fn resent_slice() -> MsgPayload<PlayerInput> {
+    MsgPayload::PeerInputs(PlayerInputSlice {
+        start: 0,
+        inputs: (0..6).map(|i| input(20 + i).to_bytes()).collect(),
+    })
+}
+
+fn slices_in_order(resend_first: bool) -> Vec<MsgPayload<PlayerInput>> {
+    if resend_first {
+        vec![resent_slice(), first_slice()]
+    } else {
+        vec![first_slice(), resent_slice()]
+    }
+}
+
+/// A 3 player host with a review window, which receives `slices` from the guest in order and then approves them.
+fn host_after(policy: ResendPolicy, slices: Vec<MsgPayload<PlayerInput>>) -> Host {
+    let mut host = Host::new(3, 50, 5, 60)
+        .with_finalization_delay_ticks(10)
+        .with_resend_policy(policy);
+    for slice in slices {
+        host.rx_guest_input_slice(GUEST, slice);
+    }
+    host.approve_inputs_pending_review(GUEST, 6);
+    host
+}
+
+fn finalized_inputs(host: &Host) -> Vec<PlayerInput> {
+    (0..host.get_peer_num_final_inputs(GUEST))
+        .map(|tick| host.get_peer_input_for_tick(GUEST, tick))
+        .collect()
+}
+
+#[test]
+fn test_default_policy_is_first_write_wins() {
+    // Hosts keep the first version of a resent input unless configured
+    // otherwise.
+    assert_eq!(
+        Host::new(2, 50, 5, 60).resend_policy(),
+        ResendPolicy::FirstWriteWins
+    );
+}
+
+#[test_case(false, vec![10, 11, 12, 13, 24, 25] ; "first slice first")]
+#[test_case(true, vec![20, 21, 22, 23, 24, 25] ; "resend first")]
+fn test_first_write_wins_keeps_first_received(resend_first: bool, expected: Vec<u8>) {
+    // With first-write-wins, each input keeps the version that arrived first.
+    let host = host_after(ResendPolicy::FirstWriteWins, slices_in_order(resend_first));
+
+    assert_eq!(
+        finalized_inputs(&host),
+        expected.into_iter().map(input).collect::<Vec<_>>()
+    );
+}
+
+#[test_case(false ; "first slice first")]
+#[test_case(true ; "resend first")]
+fn test_last_write_wins_keeps_newest_slice(resend_first: bool) {
+    // With last-write-wins, the newest slice's version is finalized whichever
+    // order the slices arrive in.
+    let host = host_after(ResendPolicy::LastWriteWins, slices_in_order(resend_first));
+
+    assert_eq!(
+        finalized_inputs(&host),
+        (20..26).map(input).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_last_write_wins_keeps_host_replacements() {
+    // A newer slice doesn't undo an input the host replaced during review.
+    let mut host = Host::new(2, 50, 5, 60)
+        .with_finalization_delay_ticks(10)
+        .with_resend_policy(ResendPolicy::LastWriteWins);
+    host.rx_guest_input_slice(GUEST, first_slice());
+    host.replace_input_pending_review(GUEST, 1, input(99))
+        .unwrap();
+
+    host.rx_guest_input_slice(GUEST, resent_slice());
+    host.approve_inputs_pending_review(GUEST, 6);
+
+    assert_eq!(
+        finalized_inputs(&host),
+        [20, 99, 22, 23, 24, 25].map(input).to_vec()
+    );
+}
+
+#[test]
+fn test_last_write_wins_leaves_finalized_inputs_alone() {
+    // Inputs already finalized never change, even if a newer slice disagrees.
+    let mut host = Host::new(2, 50, 5, 60)
+        .with_finalization_delay_ticks(10)
+        .with_resend_policy(ResendPolicy::LastWriteWins);
+    host.rx_guest_input_slice(GUEST, first_slice());
+    host.approve_inputs_pending_review(GUEST, 2);
+
+    host.rx_guest_input_slice(GUEST, resent_slice());
+    host.approve_inputs_pending_review(GUEST, 6);
+
+    assert_eq!(
+        finalized_inputs(&host),
+        [10, 11, 22, 23, 24, 25].map(input).to_vec()
+    );
+}
+
+#[test_case(ResendPolicy::FirstWriteWins, false ; "first write wins, first slice first")]
+#[test_case(ResendPolicy::FirstWriteWins, true ; "first write wins, resend first")]
+#[test_case(ResendPolicy::LastWriteWins, false ; "last write wins, first slice first")]
+#[test_case(ResendPolicy::LastWriteWins, true ; "last write wins, resend first")]
+fn test_peers_converge_on_host_finalized_inputs(policy: ResendPolicy, resend_first: bool) {
+    // Another guest that received the guest's slices directly, in the
+    // opposite order to the host, ends up with exactly the inputs the host
+    // finalized once the host's finalized slice arrives.
+    let host = host_after(policy, slices_in_order(resend_first));
+    let mut other_guest = Guest::new(3, OTHER_GUEST, 60);
+    for slice in slices_in_order(!resend_first) {
+        other_guest.rx_peer_input_slice(GUEST, slice);
+    }
+
+    other_guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(GUEST));
+
+    assert_eq!(other_guest.get_peer_num_final_inputs(GUEST), 6);
+    let other_guest_inputs: Vec<PlayerInput> = (0..6)
+        .map(|tick| other_guest.get_peer_input_for_tick(GUEST, tick))
+        .collect();
+    assert_eq!(other_guest_inputs, finalized_inputs(&host));
+}
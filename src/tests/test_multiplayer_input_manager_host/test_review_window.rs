@@ -0,0 +1,101 @@
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+/// A 2 player host with a review window of 3 ticks, which has received 4 inputs from the guest.
+fn host_with_pending_inputs() -> Host {
+    let mut host = Host::new(2, 50, 5, 60).with_finalization_delay_ticks(3);
+    host.rx_guest_input_slice(
+        GUEST,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 4)),
+    );
+    host
+}
+
+#[test]
+fn test_guest_inputs_are_finalized_after_review_window() {
+    // Guest inputs are held as provisional until the host has advanced by the
+    // review window, and are then finalized.
+    let mut host = host_with_pending_inputs();
+    assert_eq!(host.get_peer_num_inputs(GUEST), 4);
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 0);
+
+    for _ in 0..2 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 0);
+
+    host.add_host_input_directly(PlayerInput::default());
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 4);
+    assert!(host.get_inputs_pending_review(GUEST).is_empty());
+}
+
+#[test]
+fn test_approval_finalizes_immediately() {
+    // Approving inputs finalizes them without waiting for the review window,
+    // and only up to the given tick.
+    let mut host = host_with_pending_inputs();
+    let outcome = host.approve_inputs_pending_review(GUEST, 2);
+    assert_eq!(outcome.newly_finalized, 2);
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 2);
+
+    let pending_ticks: Vec<u32> = host
+        .get_inputs_pending_review(GUEST)
+        .into_iter()
+        .map(|(tick, _)| tick)
+        .collect();
+    assert_eq!(pending_ticks, vec![2, 3]);
+}
+
+#[test]
+fn test_replacement_is_finalized_and_survives_resends() {
+    // A replaced input is what gets finalized, even if the guest resends its
+    // original slice during the review window.
+    let mut host = host_with_pending_inputs();
+    let replacement = PlayerInput::new_test_simple(9);
+    host.replace_input_pending_review(GUEST, 1, replacement)
+        .unwrap();
+    host.rx_guest_input_slice(
+        GUEST,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 5)),
+    );
+
+    host.approve_inputs_pending_review(GUEST, 5);
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 5);
+    assert_eq!(host.get_peer_input_for_tick(GUEST, 1), replacement);
+}
+
+#[test]
+fn test_replacing_an_input_not_pending_review_is_an_error() {
+    // Inputs that have already been finalized, or haven't arrived yet, can't
+    // be replaced.
+    let mut host = host_with_pending_inputs();
+    host.approve_inputs_pending_review(GUEST, 1);
+    for tick in [0, 4] {
+        assert!(
+            host.replace_input_pending_review(GUEST, tick, PlayerInput::default())
+                .is_err()
+        );
+    }
+}
+
+#[test]
+fn test_no_review_window_finalizes_on_arrival() {
+    // Without a review window, guest inputs are finalized as soon as they
+    // arrive, as before.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.rx_guest_input_slice(
+        GUEST,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 4)),
+    );
+    assert_eq!(host.finalization_delay_ticks(), 0);
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 4);
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::HostInputMgr,
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    session::{Recipient, Session},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+
+/// A 3 player host with 20 finalized inputs for the host and both guests.
+fn host_with_window(window: Option<u32>) -> Host {
+    let mut host = Host::new(3, 50, 5, 60);
+    if let Some(window) = window {
+        host = host.with_send_window_ticks(window);
+    }
+    for _ in 0..20 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    for guest in [PlayerNum(1), PlayerNum(2)] {
+        host.rx_guest_input_slice(
+            guest,
+            MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 20)),
+        );
+    }
+    host
+}
+
+fn ack(host: &mut Host, guest: PlayerNum, acks: [(u8, u32); 3]) {
+    host.rx_finalized_ticks_observations(
+        guest,
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
+            HashMap::from(acks.map(|(p, n)| (PlayerNum(p), n))),
+        )),
+    );
+}
+
+/// Each message's player and the range of input indices it covers.
+fn ranges(msgs: &[MsgPayload<PlayerInput>]) -> Vec<(u8, u32, u32)> {
+    msgs.iter()
+        .map(|msg| match msg {
+            MsgPayload::HostToLobbyFinalizedSlice(slice) => (
+                slice.player_num.as_u8(),
+                slice.inputs.start,
+                slice.inputs.start + slice.inputs.len(),
+            ),
+            other => panic!("unexpected message: {other:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn test_window_bounds_ticks_past_each_guest_ack() {
+    // Each guest's slices start at its own acks, and carry at most the
+    // window's worth of ticks past them; a fast guest isn't held back by a
+    // slow one.
+    let mut host = host_with_window(Some(8));
+    ack(&mut host, PlayerNum(1), [(0, 2), (1, 20), (2, 15)]);
+    ack(&mut host, PlayerNum(2), [(0, 18), (1, 18), (2, 20)]);
+
+    assert_eq!(
+        ranges(&host.get_msgs_windowed_finalized_slices(PlayerNum(1))),
+        vec![(0, 2, 10), (2, 15, 20)]
+    );
+    assert_eq!(
+        ranges(&host.get_msgs_windowed_finalized_slices(PlayerNum(2))),
+        vec![(0, 18, 20), (1, 18, 20)]
+    );
+}
+
+#[test]
+fn test_no_window_sends_everything_unacked() {
+    // Without a window, each slice runs to the end of the finalized inputs.
+    let host = host_with_window(None);
+    assert_eq!(host.send_window_ticks(), None);
+    assert_eq!(
+        ranges(&host.get_msgs_windowed_finalized_slices(PlayerNum(1))),
+        vec![(0, 0, 20), (1, 0, 20), (2, 0, 20)]
+    );
+}
+
+#[test]
+fn test_occupancy_is_worst_unacked_player() {
+    // Occupancy is the largest number of unacked ticks across players, and
+    // drops to 0 once the guest is current.
+    let mut host = host_with_window(Some(8));
+    assert_eq!(host.send_window_occupancy(PlayerNum(1)), 20);
+
+    ack(&mut host, PlayerNum(1), [(0, 12), (1, 20), (2, 17)]);
+    assert_eq!(host.send_window_occupancy(PlayerNum(1)), 8);
+
+    ack(&mut host, PlayerNum(1), [(0, 20), (1, 20), (2, 20)]);
+    assert_eq!(host.send_window_occupancy(PlayerNum(1)), 0);
+    assert!(
+        host.get_msgs_windowed_finalized_slices(PlayerNum(1))
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_session_sends_windowed_slices_to_each_guest() {
+    // With a window, a session's frame messages carry no broadcast finalized
+    // slices; each guest is sent its own windowed slices instead.
+    let mut host = host_with_window(Some(8));
+    ack(&mut host, PlayerNum(1), [(0, 2), (1, 20), (2, 15)]);
+    ack(&mut host, PlayerNum(2), [(0, 18), (1, 18), (2, 20)]);
+    let mut session = Session::from(host);
+
+    let mut sent = vec![];
+    for (recipient, msg) in session.get_outgoing_msgs_for_frame(0.0) {
+        let parts = match msg {
+            MsgPayload::Batch(parts) => parts,
+            msg => vec![msg],
+        };
+        let slices: Vec<_> = parts
+            .into_iter()
+            .filter(|part| matches!(part, MsgPayload::HostToLobbyFinalizedSlice(_)))
+            .collect();
+        sent.extend(ranges(&slices).into_iter().map(|range| (recipient, range)));
+    }
+
+    assert_eq!(
+        sent,
+        vec![
+            (Recipient::Player(PlayerNum(1)), (0, 2, 10)),
+            (Recipient::Player(PlayerNum(1)), (2, 15, 20)),
+            (Recipient::Player(PlayerNum(2)), (0, 18, 20)),
+            (Recipient::Player(PlayerNum(2)), (1, 18, 20)),
+        ]
+    );
+}
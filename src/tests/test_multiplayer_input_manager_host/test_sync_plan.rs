@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+/// A 3 player host whose inputs are finalized for 12 ticks across all players.
+fn host_at_tick_12(max_inputs_per_msg: u32) -> MultiplayerInputManager<PlayerInput, HostInputMgr> {
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(3, 50, 5, 60)
+        .with_max_inputs_per_sync_msg(max_inputs_per_msg);
+    for _ in 0..20 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    for guest in [PlayerNum(1), PlayerNum(2)] {
+        host.rx_guest_input_slice(
+            guest,
+            MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 12)),
+        );
+    }
+    host
+}
+
+/// Each message's player and the range of input indices it covers.
+fn plan_ranges(plan: &[MsgPayload<PlayerInput>]) -> Vec<(u8, u32, u32)> {
+    plan.iter()
+        .map(|msg| match msg {
+            MsgPayload::HostToLobbyFinalizedSlice(slice) => (
+                slice.player_num.as_u8(),
+                slice.inputs.start,
+                slice.inputs.start + slice.inputs.len(),
+            ),
+            other => panic!("unexpected message in sync plan: {other:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn test_plan_starts_from_guest_acks() {
+    // The plan only covers what this guest hasn't acked, up to the
+    // snapshottable tick, and skips players the guest is already current on.
+    let mut host = host_at_tick_12(256);
+    host.rx_finalized_ticks_observations(
+        PlayerNum(2),
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
+            HashMap::from([(PlayerNum(0), 4), (PlayerNum(1), 6), (PlayerNum(2), 12)]),
+        )),
+    );
+
+    let plan = host.sync_plan_for(PlayerNum(2));
+    assert_eq!(plan_ranges(&plan), vec![(0, 4, 12), (1, 6, 12)]);
+}
+
+#[test]
+fn test_plan_is_paginated_by_tick_range() {
+    // With a small page size, messages are split into tick ranges, and each
+    // range is sent for every player before moving on to the next.
+    let host = host_at_tick_12(5);
+
+    let plan = host.sync_plan_for(PlayerNum(1));
+    assert_eq!(
+        plan_ranges(&plan),
+        vec![
+            (0, 0, 5),
+            (1, 0, 5),
+            (2, 0, 5),
+            (0, 5, 10),
+            (1, 5, 10),
+            (2, 5, 10),
+            (0, 10, 12),
+            (1, 10, 12),
+            (2, 10, 12),
+        ]
+    );
+}
+
+#[test]
+fn test_plan_brings_fresh_guest_current() {
+    // Applying the plan to a guest that has no inputs brings its snapshottable
+    // tick up to the host's.
+    let host = host_at_tick_12(5);
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(3, 1.into(), 60);
+
+    for msg in host.sync_plan_for(PlayerNum(1)) {
+        guest.rx_final_peer_input_slice_from_host(msg);
+    }
+    assert_eq!(
+        guest.get_snapshottable_sim_tick(),
+        host.get_snapshottable_sim_tick()
+    );
+}
+
+#[test]
+fn test_plan_is_empty_for_current_guest() {
+    // A guest that has acked everything up to the snapshottable tick needs no
+    // messages.
+    let mut host = host_at_tick_12(256);
+    host.rx_finalized_ticks_observations(
+        PlayerNum(1),
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
+            HashMap::from([(PlayerNum(0), 12), (PlayerNum(1), 12), (PlayerNum(2), 12)]),
+        )),
+    );
+    assert!(host.sync_plan_for(PlayerNum(1)).is_empty());
+}
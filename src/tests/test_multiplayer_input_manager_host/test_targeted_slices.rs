@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::HostInputMgr,
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    session::{OutgoingMsgs, Recipient, Session},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+
+const GUEST_1: PlayerNum = PlayerNum(1);
+const GUEST_2: PlayerNum = PlayerNum(2);
+
+/// A 3 player host with 20 finalized inputs for the host and both guests.
+fn host_with_inputs() -> Host {
+    let mut host = Host::new(3, 50, 5, 60);
+    for _ in 0..20 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    for guest in [GUEST_1, GUEST_2] {
+        host.rx_guest_input_slice(
+            guest,
+            MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 20)),
+        );
+    }
+    host
+}
+
+fn ack(host: &mut Host, guest: PlayerNum, acks: [(u8, u32); 3]) {
+    host.rx_finalized_ticks_observations(
+        guest,
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
+            HashMap::from(acks.map(|(p, n)| (PlayerNum(p), n))),
+        )),
+    );
+}
+
+/// The player and range of input indices of a finalized slice; `None` for an empty message.
+fn range(msg: &MsgPayload<PlayerInput>) -> Option<(u8, u32, u32)> {
+    match msg {
+        MsgPayload::HostToLobbyFinalizedSlice(slice) => Some((
+            slice.player_num.as_u8(),
+            slice.inputs.start,
+            slice.inputs.start + slice.inputs.len(),
+        )),
+        MsgPayload::Empty => None,
+        other => panic!("unexpected message: {other:?}"),
+    }
+}
+
+fn addressed_ranges(outgoing: &OutgoingMsgs<PlayerInput>) -> Vec<(Recipient, (u8, u32, u32))> {
+    outgoing
+        .iter()
+        .map(|(recipient, msg)| (*recipient, range(msg).unwrap()))
+        .collect()
+}
+
+#[test]
+fn test_slice_starts_from_the_recipients_own_ack() {
+    // A well-acked guest's slice starts at its own ack, while the broadcast
+    // starts at the fewest acked by any guest.
+    let mut host = host_with_inputs();
+    ack(&mut host, GUEST_1, [(0, 18), (1, 20), (2, 20)]);
+    ack(&mut host, GUEST_2, [(0, 3), (1, 20), (2, 20)]);
+
+    let subject = PlayerNum(0);
+    assert_eq!(
+        range(&host.get_msg_finalized_slice_for_guest(subject, GUEST_1)),
+        Some((0, 18, 20))
+    );
+    assert_eq!(
+        range(&host.get_msg_finalized_slice_for_guest(subject, GUEST_2)),
+        Some((0, 3, 20))
+    );
+    assert_eq!(
+        range(&host.get_msg_finalized_slice(subject)),
+        Some((0, 3, 20))
+    );
+}
+
+#[test]
+fn test_current_recipient_gets_an_empty_message() {
+    // A guest that has acked every finalized input of the subject is sent
+    // nothing for it.
+    let mut host = host_with_inputs();
+    ack(&mut host, GUEST_1, [(0, 20), (1, 20), (2, 20)]);
+
+    assert!(matches!(
+        host.get_msg_finalized_slice_for_guest(PlayerNum(2), GUEST_1),
+        MsgPayload::Empty
+    ));
+}
+
+#[test]
+fn test_slices_for_guests_are_addressed_to_each_guest() {
+    // Each guest's slices are addressed to it alone, skipping the players it
+    // is current on.
+    let mut host = host_with_inputs();
+    ack(&mut host, GUEST_1, [(0, 18), (1, 20), (2, 20)]);
+    ack(&mut host, GUEST_2, [(0, 20), (1, 5), (2, 20)]);
+
+    assert_eq!(
+        addressed_ranges(&host.get_msgs_finalized_slices_for_guests()),
+        vec![
+            (Recipient::Player(GUEST_1), (0, 18, 20)),
+            (Recipient::Player(GUEST_2), (1, 5, 20)),
+        ]
+    );
+}
+
+#[test]
+fn test_session_addresses_slices_to_connections_and_records_them() {
+    // A session sends each seat's slices to the connection controlling it,
+    // and records them as sent to the seat.
+    let mut host = host_with_inputs();
+    ack(&mut host, GUEST_1, [(0, 20), (1, 20), (2, 20)]);
+    ack(&mut host, GUEST_2, [(0, 10), (1, 20), (2, 20)]);
+    let new_device = PlayerNum(7);
+    host.transfer_seat(GUEST_1, new_device).unwrap();
+    let mut session: Session<PlayerInput> = host.into();
+
+    let outgoing = session.get_finalized_slices_for_guests();
+
+    let host = session.as_host().unwrap();
+    // the moved seat's acks were reset, so it is sent everything
+    assert_eq!(
+        addressed_ranges(&outgoing),
+        vec![
+            (Recipient::Player(new_device), (0, 0, 20)),
+            (Recipient::Player(new_device), (1, 0, 20)),
+            (Recipient::Player(new_device), (2, 0, 20)),
+            (Recipient::Player(GUEST_2), (0, 10, 20)),
+        ]
+    );
+    assert_eq!(host.slice_redundancy(GUEST_2).num_inputs_sent, 10);
+}
@@ -622,3 +622,21 @@ fn test_alternating_add_and_update() {
     // (may have more due to ceil effects)
     assert_eq!(total_added, 10);
 }
+
+#[test]
+fn test_no_drift_over_a_long_session() {
+    // Summing an hour of 60hz frame times must still ask for an hour of
+    // inputs; with no inputs added, the result is the total expected so far.
+    let mut manager = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(
+        4,
+        MAX_GUEST_TICKS_BEHIND,
+        MAX_TICKS_PREDICT_LOCF,
+        60,
+    );
+
+    let mut num_inputs_needed = 0;
+    for _ in 0..60 * 60 * 60 {
+        num_inputs_needed = manager.update_time_and_get_num_inputs_needed(1.0 / 60.0);
+    }
+    assert!(num_inputs_needed.abs_diff(216_000) <= 1);
+}
@@ -0,0 +1,123 @@
+use crate::{
+    input_messages::{MsgKind, MsgPayload},
+    msg_sequence::SequencedMsg,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    net_stats::MsgCounts,
+    session::{Recipient, Session},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST_1: PlayerNum = PlayerNum(1);
+const GUEST_2: PlayerNum = PlayerNum(2);
+
+#[test]
+fn test_decoded_msgs_are_counted_by_peer_and_kind() {
+    // Each decoded message counts towards its sender and kind, at its size on
+    // the wire.
+    let mut host = Host::new(3, 50, 5, 60);
+    let ping = MsgPayload::<PlayerInput>::GuestToHostPing(7).to_bytes();
+    let empty = MsgPayload::<PlayerInput>::Empty.to_bytes();
+
+    host.decode_msg_from_peer(GUEST_1, &ping);
+    host.decode_msg_from_peer(GUEST_1, &ping);
+    host.decode_msg_from_peer(GUEST_2, &empty);
+
+    let stats = host.get_net_stats();
+    assert_eq!(
+        stats.peer(GUEST_1).received[&MsgKind::GuestToHostPing],
+        MsgCounts {
+            num_msgs: 2,
+            num_bytes: 2 * ping.len() as u64
+        }
+    );
+    assert_eq!(stats.peer(GUEST_2).total_received().num_msgs, 1);
+    assert_eq!(stats.total_received().num_msgs, 3);
+    assert_eq!(stats.total_sent(), MsgCounts::default());
+}
+
+#[test]
+fn test_malformed_msgs_are_not_counted() {
+    // Bytes that don't decode aren't counted as a message of any kind.
+    let mut host = Host::new(2, 50, 5, 60);
+
+    host.decode_msg_from_peer(GUEST_1, &[255, 1, 2]);
+
+    assert_eq!(host.get_net_stats().total_received(), MsgCounts::default());
+}
+
+#[test]
+fn test_sequenced_msgs_are_counted_with_their_seq() {
+    // A sequenced message counts its sequence number's bytes, and duplicates
+    // count as traffic too.
+    let mut host = Host::new(2, 50, 5, 60);
+    let bytes = SequencedMsg::<PlayerInput> {
+        seq: 0,
+        payload: MsgPayload::GuestToHostPing(7),
+    }
+    .to_bytes();
+
+    host.decode_sequenced_msg_from_peer(GUEST_1, &bytes);
+    host.decode_sequenced_msg_from_peer(GUEST_1, &bytes);
+
+    assert_eq!(
+        host.get_net_stats().peer(GUEST_1).received[&MsgKind::GuestToHostPing],
+        MsgCounts {
+            num_msgs: 2,
+            num_bytes: 2 * bytes.len() as u64
+        }
+    );
+}
+
+#[test]
+fn test_rolling_rate_drops_traffic_older_than_a_second() {
+    // The per-second rates only count the last second of traffic, while the
+    // totals keep everything.
+    let mut guest = Guest::new(2, GUEST_1, 60);
+    let ping = MsgPayload::GuestToHostPing(1);
+
+    guest.record_msg_bytes_sent(HOST_PLAYER_NUM, &ping, 100);
+    guest.update_net_stats(0.5);
+    guest.record_msg_bytes_sent(HOST_PLAYER_NUM, &ping, 50);
+    assert_eq!(guest.get_net_stats().sent_bytes_per_sec(), 150);
+
+    guest.update_net_stats(0.5);
+    assert_eq!(guest.get_net_stats().sent_bytes_per_sec(), 50);
+
+    guest.update_net_stats(0.5);
+    let stats = guest.get_net_stats();
+    assert_eq!(stats.peer(HOST_PLAYER_NUM).sent_bytes_per_sec, 0);
+    assert_eq!(
+        stats.total_sent(),
+        MsgCounts {
+            num_msgs: 2,
+            num_bytes: 150
+        }
+    );
+}
+
+#[test]
+fn test_session_counts_broadcast_once_per_peer() {
+    // A message for all peers serialized by a session counts once for each
+    // peer it is sent to.
+    let mut host: Session<PlayerInput> = Host::new(3, 50, 5, 60).into();
+
+    let sent = host.to_sequenced_bytes(vec![(Recipient::AllPeers, MsgPayload::HostToGuestPong(1))]);
+
+    let stats = host.get_net_stats();
+    for (peer, bytes) in &sent {
+        assert_eq!(
+            stats.peer(*peer).sent[&MsgKind::HostToGuestPong],
+            MsgCounts {
+                num_msgs: 1,
+                num_bytes: bytes.len() as u64
+            }
+        );
+    }
+    assert_eq!(stats.total_sent().num_msgs, 2);
+}
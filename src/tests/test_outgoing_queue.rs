@@ -0,0 +1,152 @@
+use crate::{
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    outgoing_queue::{OutgoingQueue, OutgoingQueueSummary},
+    session::Recipient,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Msg = MsgPayload<PlayerInput>;
+
+const HOST: PlayerNum = PlayerNum(0);
+const GUEST_1: PlayerNum = PlayerNum(1);
+const GUEST_2: PlayerNum = PlayerNum(2);
+
+fn finalized(player: u8, start: u32, num_inputs: u32) -> Msg {
+    MsgPayload::HostToLobbyFinalizedSlice(HostFinalizedSlice::new_test(
+        PlayerNum(player),
+        start + num_inputs,
+        start,
+        num_inputs,
+    ))
+}
+
+fn kinds(msgs: &[(Recipient, Msg)]) -> Vec<(Recipient, String)> {
+    msgs.iter()
+        .map(|(recipient, msg)| (*recipient, format!("{:?}", msg.kind())))
+        .collect()
+}
+
+#[test]
+fn test_reachable_peers_pass_straight_through() {
+    // With every peer reachable, messages are returned unchanged.
+    let mut queue = OutgoingQueue::<PlayerInput>::new(3, HOST);
+    let sent = queue.route(vec![
+        (Recipient::AllPeers, finalized(0, 0, 3)),
+        (Recipient::Player(GUEST_1), MsgPayload::HostToGuestPong(1)),
+    ]);
+    assert_eq!(
+        kinds(&sent),
+        vec![
+            (Recipient::AllPeers, "HostToLobbyFinalizedSlice".to_string()),
+            (Recipient::Player(GUEST_1), "HostToGuestPong".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_unreachable_peer_is_split_out_of_broadcasts() {
+    // A broadcast goes to the reachable peers directly, and is queued for the
+    // unreachable one until it is reachable again.
+    let mut queue = OutgoingQueue::<PlayerInput>::new(3, HOST);
+    queue.set_reachable(GUEST_2, false);
+    let sent = queue.route(vec![(Recipient::AllPeers, finalized(0, 0, 3))]);
+    assert_eq!(
+        kinds(&sent),
+        vec![(
+            Recipient::Player(GUEST_1),
+            "HostToLobbyFinalizedSlice".to_string()
+        )]
+    );
+    assert_eq!(queue.summary(GUEST_2).num_queued, 1);
+
+    queue.set_reachable(GUEST_2, true);
+    let sent = queue.route(vec![]);
+    assert_eq!(
+        kinds(&sent),
+        vec![(
+            Recipient::Player(GUEST_2),
+            "HostToLobbyFinalizedSlice".to_string()
+        )]
+    );
+    assert_eq!(queue.summary(GUEST_2).num_queued, 0);
+}
+
+#[test]
+fn test_superseded_msgs_are_collapsed() {
+    // Newer slices covering older ones replace them, per player, and
+    // cumulative messages keep only the newest copy; pings are kept.
+    let mut queue = OutgoingQueue::<PlayerInput>::new(2, HOST);
+    queue.set_reachable(GUEST_1, false);
+    for num_inputs in 1..=4 {
+        queue.route(vec![
+            (Recipient::AllPeers, finalized(0, 0, num_inputs)),
+            (Recipient::AllPeers, finalized(1, 2, 1)),
+            (
+                Recipient::Player(GUEST_1),
+                MsgPayload::HostToGuestRateAdjust(10),
+            ),
+            (
+                Recipient::Player(GUEST_1),
+                MsgPayload::HostToGuestPong(num_inputs),
+            ),
+        ]);
+    }
+    assert_eq!(
+        queue.summary(GUEST_1),
+        OutgoingQueueSummary {
+            num_queued: 7,
+            num_collapsed: 9,
+            num_dropped: 0,
+        }
+    );
+
+    queue.set_reachable(GUEST_1, true);
+    let sent = queue.route(vec![]);
+    let slice_lens: Vec<u32> = sent
+        .iter()
+        .filter_map(|(_, msg)| match msg {
+            MsgPayload::HostToLobbyFinalizedSlice(slice) if slice.player_num == HOST => {
+                Some(slice.inputs.len())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(slice_lens, vec![4]);
+}
+
+#[test]
+fn test_partly_overlapping_slices_are_kept() {
+    // A slice only supersedes slices whose inputs it fully covers.
+    let mut queue = OutgoingQueue::<PlayerInput>::new(2, GUEST_1);
+    queue.set_reachable(HOST, false);
+    queue.route(vec![(
+        Recipient::Player(HOST),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 5)),
+    )]);
+    queue.route(vec![(
+        Recipient::Player(HOST),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(2, 5)),
+    )]);
+    assert_eq!(queue.summary(HOST).num_queued, 2);
+    assert_eq!(queue.summary(HOST).num_collapsed, 0);
+}
+
+#[test]
+fn test_full_queue_drops_oldest() {
+    // Past the max depth, the oldest queued messages are dropped.
+    let mut queue = OutgoingQueue::<PlayerInput>::new(2, HOST).with_max_depth(3);
+    queue.set_reachable(GUEST_1, false);
+    for ping_id in 0..5 {
+        queue.route(vec![(
+            Recipient::Player(GUEST_1),
+            MsgPayload::HostToGuestPong(ping_id),
+        )]);
+    }
+    assert_eq!(queue.summary(GUEST_1).num_dropped, 2);
+
+    queue.set_reachable(GUEST_1, true);
+    let sent = queue.route(vec![]);
+    assert!(matches!(sent[0].1, MsgPayload::HostToGuestPong(2)));
+    assert_eq!(sent.len(), 3);
+}
@@ -0,0 +1,133 @@
+use test_case::test_case;
+
+use crate::{
+    input_messages::{MsgPayload, PongPong},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    rtt::{PingReport, ms_to_micros},
+    session::{OutgoingMsgs, Recipient, Session},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+const HOST: PlayerNum = PlayerNum(0);
+const GUEST: PlayerNum = PlayerNum(1);
+
+/// Delivers each message to `to` as bytes, returning its replies.
+fn deliver(
+    from: PlayerNum,
+    to: &mut Session<PlayerInput>,
+    outgoing: OutgoingMsgs<PlayerInput>,
+) -> OutgoingMsgs<PlayerInput> {
+    let mut replies = Vec::new();
+    for (recipient, msg) in outgoing {
+        assert_eq!(recipient, Recipient::Player(to.own_player_num()));
+        replies.extend(to.rx_bytes(from, &msg.to_bytes()).unwrap());
+    }
+    replies
+}
+
+#[test_case(52_500, 48_000, 4_500 ; "host slower to turn around")]
+#[test_case(48_000, 52_501, -4_501 ; "guest slower to turn around")]
+#[test_case(50_000, 50_000, 0 ; "same turnaround")]
+fn test_turnaround_gap(guest_rtt_micros: u32, host_rtt_micros: u32, expected: i32) {
+    // The turnaround gap is the difference between the guest's and the host's
+    // RTT.
+    let report = PingReport::new(guest_rtt_micros, host_rtt_micros);
+    assert_eq!(report.turnaround_gap_micros, expected);
+}
+
+#[test]
+fn test_four_way_handshake_via_sessions() {
+    // A full ping, pong, pong-pong cycle through `Session`s leaves host and
+    // guest with the same report, built from both sides' RTTs.
+    let mut host: Session<PlayerInput> =
+        MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 50, 5, 60).into();
+    let mut guest: Session<PlayerInput> =
+        MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, GUEST, 60).into();
+    assert_eq!(host.as_host().unwrap().ping_report(GUEST), None);
+
+    let ping = guest.as_guest_mut().unwrap().get_msg_guest_ping();
+    let pong = deliver(GUEST, &mut host, vec![(Recipient::Player(HOST), ping)]);
+    let pong_pong = deliver(HOST, &mut guest, pong);
+    let report_msgs = deliver(GUEST, &mut host, pong_pong);
+    assert!(deliver(HOST, &mut guest, report_msgs).is_empty());
+
+    let host_mgr = host.as_host().unwrap();
+    let guest_mgr = guest.as_guest().unwrap();
+    let report = host_mgr.ping_report(GUEST).unwrap();
+    assert_eq!(guest_mgr.ping_report(), Some(report));
+    assert_eq!(
+        report.guest_rtt_micros as i64,
+        ms_to_micros(guest_mgr.get_rtt_ms_to_host().unwrap())
+    );
+    assert_eq!(
+        report,
+        PingReport::new(report.guest_rtt_micros, report.host_rtt_micros)
+    );
+}
+
+#[test]
+fn test_host_replies_to_pong_pong_with_report() {
+    // The host's reply to a pong-pong is its report, carrying the RTT the
+    // guest measured.
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 50, 5, 60);
+    host.rx_guest_ping_and_reply(GUEST, MsgPayload::GuestToHostPing(3));
+    let reply = host
+        .rx_guest_pong_pong(
+            GUEST,
+            MsgPayload::GuestToHostPongPong(PongPong {
+                ping_id: 3,
+                guest_rtt_micros: 40_000,
+            }),
+        )
+        .unwrap();
+
+    let MsgPayload::HostToGuestPingReport(report) = reply else {
+        panic!("expected a ping report, got {reply:?}");
+    };
+    assert_eq!(report.guest_rtt_micros, 40_000);
+    assert_eq!(host.ping_report(GUEST), Some(report));
+}
+
+#[test]
+fn test_host_completes_ping_cycle_with_legacy_pong_pong() {
+    // A legacy pong-pong from an older guest still gives the host an RTT
+    // sample, but carries no guest RTT, so there is no report to send back.
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 50, 5, 60);
+    host.rx_guest_ping_and_reply(GUEST, MsgPayload::GuestToHostPing(3));
+
+    let reply = host
+        .rx_guest_pong_pong(GUEST, MsgPayload::GuestToHostLegacyPongPong(3))
+        .unwrap();
+
+    assert!(matches!(reply, MsgPayload::Empty));
+    assert_eq!(host.rtts_by_player().len(), 1);
+    assert_eq!(host.ping_report(GUEST), None);
+}
+
+#[test_case(None, 3_000_000 ; "half the rtt without a report")]
+#[test_case(Some(PingReport::new(100_000, 80_000)), 2_400_000 ; "less the turnaround gap")]
+#[test_case(Some(PingReport::new(100_000, 60_000)), 1_800_000 ; "less a wider turnaround gap")]
+#[test_case(Some(PingReport::new(400_000, 0)), 0 ; "never negative")]
+fn test_one_way_uses_turnaround_gap(report: Option<PingReport>, expected: i64) {
+    // With a 100ms RTT at 60 ticks/sec, half the RTT is 3 ticks; the guest
+    // takes the host's extra turnaround time out of its estimate.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, GUEST, 60);
+    guest.observe_rtt_ms_to_host(100.0);
+    if let Some(report) = report {
+        guest.rx_ping_report(MsgPayload::HostToGuestPingReport(report));
+    }
+    assert_eq!(guest.one_way_in_micro_ticks(), Some(expected));
+}
+
+#[test_case(MsgPayload::HostToGuestPong(7) ; "pong to an unknown ping")]
+#[test_case(MsgPayload::HostToGuestRateAdjust(5) ; "not a pong")]
+fn test_guest_rejects_unexpected_pong(msg: MsgPayload<PlayerInput>) {
+    // A pong the guest has no ping for, or a message that isn't a pong, is
+    // an error rather than a panic, and leaves the RTT unmeasured.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, GUEST, 60);
+    assert!(guest.rx_host_pong_and_reply(msg).is_err());
+    assert_eq!(guest.get_rtt_ms_to_host(), None);
+}
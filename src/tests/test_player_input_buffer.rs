@@ -1,5 +1,9 @@
+use test_case::test_case;
+
 use crate::{
-    input_buffer::PlayerInputBuffer,
+    input_buffer::{
+        FinalizedSliceOutcome, IgnoredSliceReason, InputConfidence, InputStatus, PlayerInputBuffer,
+    },
     input_trait::SimInput,
     tests::demo_input_struct::{PlayerInput, PlayerInputBinary},
     util_types::PlayerInputSlice,
@@ -67,6 +71,32 @@ fn test_receive_finalized_input_slice() {
     assert_eq!(buffer.finalized_inputs(), 5);
 }
 
+#[test]
+fn test_receive_finalized_input_slice_outcome() {
+    // Applied slices report how many inputs they newly finalized (none for a
+    // pure resend), and a slice that would leave a gap reports the index of
+    // the first missing input.
+    let mut buffer = PlayerInputBuffer::<T>::default();
+    assert_eq!(
+        buffer.receive_finalized_input_slice(PlayerInputSlice::<T>::new_test(0, 5)),
+        FinalizedSliceOutcome::Applied { newly_finalized: 5 }
+    );
+    assert_eq!(
+        buffer.receive_finalized_input_slice(PlayerInputSlice::<T>::new_test(3, 4)),
+        FinalizedSliceOutcome::Applied { newly_finalized: 2 }
+    );
+    assert_eq!(
+        buffer.receive_finalized_input_slice(PlayerInputSlice::<T>::new_test(0, 7)),
+        FinalizedSliceOutcome::Applied { newly_finalized: 0 }
+    );
+    assert_eq!(
+        buffer.receive_finalized_input_slice(PlayerInputSlice::<T>::new_test(9, 2)),
+        FinalizedSliceOutcome::Ignored {
+            reason: IgnoredSliceReason::GapAtTick(7)
+        }
+    );
+}
+
 #[test]
 fn test_receive_peer_input_slice() {
     let mut buffer = PlayerInputBuffer::<T>::default();
@@ -165,3 +195,133 @@ fn test_host_finalize_default_thru_tick_wont_overwrite() {
         );
     }
 }
+
+fn buffer_of(num_inputs: u8, num_finalized: u8) -> PlayerInputBuffer<T> {
+    let mut buffer = PlayerInputBuffer::<T>::default();
+    for x in 0..num_inputs {
+        if x < num_finalized {
+            buffer.host_append_finalized(T::new_test_simple(x).to_bytes());
+        } else {
+            buffer.append_input(T::new_test_simple(x).to_bytes());
+        }
+    }
+    buffer
+}
+
+#[test]
+fn test_dropping_inputs_keeps_indices() {
+    // After dropping the oldest inputs, the rest are read at the same
+    // indices, and the counts of inputs and finalized inputs are unchanged.
+    let mut buffer = buffer_of(10, 8);
+    buffer.drop_inputs_before(5);
+
+    assert_eq!(buffer.first_retained_input(), 5);
+    assert_eq!(buffer.num_inputs_collected(), 10);
+    assert_eq!(buffer.finalized_inputs(), 8);
+    assert_eq!(buffer.get_input_or_prediction(5, 5), T::new_test_simple(5));
+    assert_eq!(buffer.get_input_or_prediction(9, 5), T::new_test_simple(9));
+    assert_eq!(buffer.get_input_or_prediction(12, 5), T::new_test_simple(9));
+    assert_eq!(buffer.get_input_or_prediction(4, 5), T::default());
+    assert_eq!(
+        buffer.get_input_status(4, 5),
+        InputStatus::Finalized { num_finalized: 8 }
+    );
+    assert_eq!(
+        buffer.get_input_status(9, 5),
+        InputStatus::NonFinal { num_finalized: 8 }
+    );
+}
+
+#[test]
+fn test_only_finalized_inputs_are_dropped() {
+    // Inputs that can still change are never dropped, nor are inputs dropped
+    // again.
+    let mut buffer = buffer_of(10, 4);
+    buffer.drop_inputs_before(8);
+    assert_eq!(buffer.first_retained_input(), 4);
+
+    buffer.drop_inputs_before(2);
+    assert_eq!(buffer.first_retained_input(), 4);
+}
+
+#[test]
+fn test_slices_start_at_the_first_retained_input() {
+    // Slices that would start at a dropped input start at the first retained
+    // one instead, while later slices are unchanged.
+    let mut buffer = buffer_of(10, 8);
+    buffer.drop_inputs_before(5);
+
+    let slice = buffer.slice_from(2);
+    assert_eq!(slice.start, 5);
+    assert_eq!(slice.len(), 5);
+    let finalized = buffer.finalized_slice_from(0);
+    assert_eq!(finalized.start, 5);
+    assert_eq!(finalized.len(), 3);
+    assert_eq!(buffer.slice_from(7).start, 7);
+}
+
+#[test]
+fn test_dropped_buffer_keeps_finalizing() {
+    // Slices received after dropping finalize and update inputs at their
+    // original indices.
+    let mut buffer = buffer_of(10, 8);
+    buffer.drop_inputs_before(6);
+
+    buffer.receive_peer_input_slice(PlayerInputSlice {
+        start: 9,
+        inputs: vec![T::new_test_simple(40).to_bytes(); 2],
+    });
+    let outcome = buffer.receive_finalized_input_slice(PlayerInputSlice {
+        start: 7,
+        inputs: vec![T::new_test_simple(30).to_bytes(); 3],
+    });
+
+    assert_eq!(
+        outcome,
+        FinalizedSliceOutcome::Applied { newly_finalized: 2 }
+    );
+    assert_eq!(buffer.finalized_inputs(), 10);
+    assert_eq!(buffer.num_inputs_collected(), 11);
+    assert_eq!(buffer.get_input_or_prediction(7, 5), T::new_test_simple(7));
+    assert_eq!(buffer.get_input_or_prediction(8, 5), T::new_test_simple(30));
+    assert_eq!(
+        buffer.get_input_or_prediction(10, 5),
+        T::new_test_simple(40)
+    );
+}
+
+#[test_case(1, InputConfidence::Finalized; "finalized")]
+#[test_case(4, InputConfidence::ReceivedNonFinal; "received but not finalized")]
+#[test_case(5, InputConfidence::PredictedLOCF { age: 1 }; "first predicted tick")]
+#[test_case(7, InputConfidence::PredictedLOCF { age: 3 }; "last tick in the prediction window")]
+#[test_case(8, InputConfidence::DefaultFallback; "past the prediction window")]
+fn test_input_confidence(tick: u32, confidence: InputConfidence) {
+    // With 5 inputs, 3 of them finalized, and a 3 tick prediction window,
+    // each tick's input is labelled with how it was arrived at.
+    let buffer = buffer_of(5, 3);
+
+    assert_eq!(buffer.get_input_with_confidence(tick, 3).1, confidence);
+}
+
+#[test]
+fn test_input_confidence_falls_back_to_default_without_inputs() {
+    // With nothing to carry forward, even the next tick is the default input.
+    let buffer = PlayerInputBuffer::<T>::default();
+
+    assert_eq!(
+        buffer.get_input_with_confidence(0, 3),
+        (T::default(), InputConfidence::DefaultFallback)
+    );
+}
+
+#[test]
+fn test_dropped_inputs_read_as_default_fallback() {
+    // An input dropped to bound memory can only be returned as the default.
+    let mut buffer = buffer_of(5, 3);
+    buffer.drop_inputs_before(2);
+
+    assert_eq!(
+        buffer.get_input_with_confidence(1, 3),
+        (T::default(), InputConfidence::DefaultFallback)
+    );
+}
@@ -0,0 +1,104 @@
+use crate::{
+    input_messages::{MsgPayload, PreSimSync},
+    player_metadata::{MAX_PLAYER_METADATA_BYTES, PlayerMetadataError},
+    prelude::*,
+    tests::demo_input_struct::PlayerInput,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+#[test]
+fn test_host_metadata_reaches_guests_in_pre_sim_sync() {
+    // Metadata the host registers is queryable on the host, and a guest
+    // adopts it from the PreSimSync; seats without metadata have none.
+    let mut host = Host::new(3, 50, 5, 60);
+    host.register_player_metadata(PlayerNum(0), "alice")
+        .unwrap();
+    host.register_player_metadata(PlayerNum(2), vec![7, 8, 9])
+        .unwrap();
+    assert_eq!(host.player_metadata(PlayerNum(0)), Some(&b"alice"[..]));
+    assert_eq!(host.player_metadata(GUEST), None);
+
+    let mut guest = Guest::new(3, GUEST, 60);
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+    assert_eq!(guest.player_metadata(PlayerNum(0)), Some(&b"alice"[..]));
+    assert_eq!(guest.player_metadata(GUEST), None);
+    assert_eq!(guest.player_metadata(PlayerNum(2)), Some(&[7, 8, 9][..]));
+}
+
+#[test]
+fn test_registering_again_replaces_metadata() {
+    // Registering a seat's metadata again replaces it, and a guest picks up
+    // the change from the next PreSimSync.
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    host.register_player_metadata(GUEST, "bob").unwrap();
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+
+    host.register_player_metadata(GUEST, "robert").unwrap();
+    assert_eq!(guest.player_metadata(GUEST), Some(&b"bob"[..]));
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(2)).unwrap();
+    assert_eq!(guest.player_metadata(GUEST), Some(&b"robert"[..]));
+}
+
+#[test]
+fn test_invalid_metadata_is_refused() {
+    // Metadata for a seat outside the session, or longer than the limit, is
+    // refused without changing what is registered.
+    let mut host = Host::new(2, 50, 5, 60);
+    assert_eq!(
+        host.register_player_metadata(PlayerNum(2), "carol"),
+        Err(PlayerMetadataError::NoSuchPlayer(PlayerNum(2)))
+    );
+    assert_eq!(
+        host.register_player_metadata(GUEST, vec![0; MAX_PLAYER_METADATA_BYTES + 1]),
+        Err(PlayerMetadataError::TooLong {
+            len: MAX_PLAYER_METADATA_BYTES + 1,
+            max: MAX_PLAYER_METADATA_BYTES,
+        })
+    );
+    assert_eq!(host.player_metadata(GUEST), None);
+
+    host.register_player_metadata(GUEST, vec![0; MAX_PLAYER_METADATA_BYTES])
+        .unwrap();
+    assert_eq!(
+        host.player_metadata(GUEST).map(<[u8]>::len),
+        Some(MAX_PLAYER_METADATA_BYTES)
+    );
+}
+
+#[test]
+fn test_guest_drops_invalid_metadata_from_host() {
+    // A guest ignores metadata in the PreSimSync for seats outside the
+    // session or over the size limit, keeping the rest.
+    let mut guest = Guest::new(2, GUEST, 60);
+    let sync: MsgPayload<PlayerInput> = PreSimSync {
+        player_metadata: vec![
+            (PlayerNum(0), b"host".to_vec()),
+            (GUEST, vec![0; MAX_PLAYER_METADATA_BYTES + 1]),
+            (PlayerNum(5), b"nobody".to_vec()),
+        ],
+        ..Default::default()
+    }
+    .into();
+
+    guest.rx_pre_sim_sync(sync).unwrap();
+    assert_eq!(guest.player_metadata(PlayerNum(0)), Some(&b"host"[..]));
+    assert_eq!(guest.player_metadata(GUEST), None);
+    assert_eq!(guest.player_metadata(PlayerNum(5)), None);
+}
+
+#[test]
+fn test_metadata_stays_with_transferred_seat() {
+    // Moving a seat to another connection keeps the seat's metadata.
+    let mut host = Host::new(2, 50, 5, 60);
+    host.register_player_metadata(GUEST, "dave").unwrap();
+
+    host.transfer_seat(GUEST, PlayerNum(5)).unwrap();
+    assert_eq!(host.player_metadata(GUEST), Some(&b"dave"[..]));
+    let session: Session<PlayerInput> = host.into();
+    assert_eq!(session.player_metadata(GUEST), Some(&b"dave"[..]));
+}
@@ -0,0 +1,204 @@
+use test_case::test_case;
+
+use crate::{
+    events::InputMgrEvent,
+    input_buffer::InputConfidence,
+    input_messages::{MsgPayload, PlayerRemoved},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    rx_outcome::RxRejection,
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const STAYER: PlayerNum = PlayerNum(1);
+const LEAVER: PlayerNum = PlayerNum(2);
+
+fn input(x: u32) -> PlayerInput {
+    PlayerInput::new_test_simple((x % 100) as u8 + 1)
+}
+
+/// A 3 player host and the guest that stays, after the host has collected
+/// `num_frames` frames from everyone but only `leaver_frames` from the leaver.
+fn three_player_session(num_frames: u32, leaver_frames: u32) -> (Host, Guest) {
+    let mut host = Host::new(3, 50, 5, 60);
+    let mut stayer = Guest::new(3, STAYER, 60);
+    let mut leaver = Guest::new(3, LEAVER, 60);
+    for frame in 0..num_frames {
+        host.add_host_input_directly(input(frame));
+        stayer.add_own_input(input(frame));
+    }
+    for frame in 0..leaver_frames {
+        leaver.add_own_input(input(frame + 50));
+    }
+    host.rx_guest_input_slice(LEAVER, leaver.get_msg_own_input_slice());
+    exchange(&mut host, &mut stayer);
+    (host, stayer)
+}
+
+/// Sends the staying guest's inputs to the host, then every player's finalized slice back to it.
+fn exchange(host: &mut Host, stayer: &mut Guest) {
+    host.rx_guest_input_slice(STAYER, stayer.get_msg_own_input_slice());
+    for player in PlayerNum::iter(host.get_num_players()) {
+        stayer.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(player));
+    }
+    host.rx_finalized_ticks_observations(STAYER, stayer.get_msg_ack_finalization());
+}
+
+#[test]
+fn test_host_fills_removed_player_with_defaults() {
+    // Removing a lagging player finalizes defaults for them up to the host's
+    // tick, so the snapshottable tick no longer waits on them.
+    let (mut host, _) = three_player_session(10, 4);
+    assert_eq!(host.get_snapshottable_sim_tick(), 4);
+
+    let msg = host.remove_player(LEAVER).unwrap();
+
+    assert!(matches!(
+        msg,
+        MsgPayload::HostToLobbyPlayerRemoved(PlayerRemoved {
+            player_num: LEAVER,
+            num_inputs: 10,
+        })
+    ));
+    assert_eq!(host.get_snapshottable_sim_tick(), 10);
+    assert_eq!(host.get_peer_num_final_inputs(LEAVER), 10);
+    assert_eq!(
+        host.get_peer_input_for_tick(LEAVER, 7),
+        PlayerInput::default()
+    );
+}
+
+#[test]
+fn test_removed_player_reads_as_default_past_the_end() {
+    // After the end of a removed player's inputs, every tick reads as a
+    // finalized default, without storing anything.
+    let (mut host, _) = three_player_session(10, 10);
+    host.remove_player(LEAVER).unwrap();
+    for frame in 10..30 {
+        host.add_host_input_directly(input(frame));
+    }
+
+    assert_eq!(
+        host.get_peer_input_for_tick(LEAVER, 25),
+        PlayerInput::default()
+    );
+    assert_eq!(host.get_peer_num_final_inputs(LEAVER), 10);
+}
+
+#[test]
+fn test_host_rejects_removed_players_inputs_and_acks() {
+    // A removed player that is still connected can't add inputs or acks.
+    let (mut host, _) = three_player_session(10, 10);
+    let mut leaver = Guest::new(3, LEAVER, 60);
+    for frame in 0..12 {
+        leaver.add_own_input(input(frame));
+    }
+    host.remove_player(LEAVER).unwrap();
+
+    let outcome = host.rx_guest_input_slice(LEAVER, leaver.get_msg_own_input_slice());
+    assert_eq!(outcome.rejected, Some(RxRejection::PlayerRemoved));
+    let outcome = host.rx_finalized_ticks_observations(LEAVER, leaver.get_msg_ack_finalization());
+    assert_eq!(outcome.rejected, Some(RxRejection::PlayerRemoved));
+}
+
+#[test_case(PlayerNum(0); "host")]
+#[test_case(PlayerNum(3); "unknown seat")]
+fn test_host_only_removes_known_guests(player_num: PlayerNum) {
+    // Only an existing guest seat can be removed.
+    let (mut host, _) = three_player_session(3, 3);
+
+    assert!(host.remove_player(player_num).is_err());
+}
+
+#[test]
+fn test_host_removes_a_player_only_once() {
+    // A second removal of the same player is an error.
+    let (mut host, _) = three_player_session(3, 3);
+    host.remove_player(LEAVER).unwrap();
+
+    assert!(host.remove_player(LEAVER).is_err());
+}
+
+#[test]
+fn test_guest_excludes_removed_player_after_their_last_inputs() {
+    // A guest only stops waiting on a removed player once it has their
+    // finalized inputs up to the end, and reports the removal once.
+    let (mut host, mut stayer) = three_player_session(10, 4);
+    let msg = host.remove_player(LEAVER).unwrap();
+
+    stayer.rx_player_removed(msg.clone());
+    assert_eq!(stayer.get_snapshottable_sim_tick(), 4);
+    exchange(&mut host, &mut stayer);
+    stayer.rx_player_removed(msg);
+
+    assert_eq!(stayer.get_snapshottable_sim_tick(), 10);
+    assert_eq!(
+        stayer.drain_events(),
+        vec![InputMgrEvent::PlayerRemoved {
+            player_num: LEAVER,
+            from_tick: 10,
+        }]
+    );
+}
+
+#[test]
+fn test_guest_rejects_removed_players_direct_inputs() {
+    // A guest no longer accepts inputs sent directly by a removed player.
+    let (mut host, mut stayer) = three_player_session(10, 10);
+    let mut leaver = Guest::new(3, LEAVER, 60);
+    for frame in 0..12 {
+        leaver.add_own_input(input(frame));
+    }
+    stayer.rx_player_removed(host.remove_player(LEAVER).unwrap());
+
+    let outcome = stayer.rx_peer_input_slice(LEAVER, leaver.get_msg_own_input_slice());
+
+    assert_eq!(outcome.rejected, Some(RxRejection::PlayerRemoved));
+}
+
+#[test]
+fn test_host_frees_removed_players_inputs_once_acked() {
+    // The host drops a removed player's inputs as the remaining guests ack
+    // them, even without a retention limit.
+    let (mut host, mut stayer) = three_player_session(10, 4);
+    stayer.rx_player_removed(host.remove_player(LEAVER).unwrap());
+    assert_eq!(host.first_retained_input(LEAVER), 4);
+
+    exchange(&mut host, &mut stayer);
+
+    assert_eq!(host.first_retained_input(LEAVER), 10);
+    assert_eq!(host.first_retained_input(STAYER), 0);
+}
+
+#[test]
+fn test_removed_player_stays_removed_in_a_new_round() {
+    // Starting a new round doesn't bring a removed player back.
+    let (mut host, _) = three_player_session(10, 10);
+    host.remove_player(LEAVER).unwrap();
+
+    host.start_new_round();
+    host.add_host_input_directly(input(0));
+
+    assert!(host.remove_player(LEAVER).is_err());
+    assert_eq!(
+        host.get_peer_input_for_tick(LEAVER, 0),
+        PlayerInput::default()
+    );
+}
+
+#[test]
+fn test_removed_players_inputs_past_the_end_are_finalized() {
+    // A removed player's defaults past their end are final, not predictions.
+    let (mut host, _) = three_player_session(10, 10);
+    host.remove_player(LEAVER).unwrap();
+
+    assert_eq!(
+        host.get_input_with_confidence(LEAVER, 12),
+        (PlayerInput::default(), InputConfidence::Finalized)
+    );
+}
@@ -1,5 +1,6 @@
 use crate::util_types::PlayerNum;
 use std::convert::TryFrom;
+use test_case::test_case;
 
 #[test]
 fn test_from_u8() {
@@ -43,3 +44,74 @@ fn test_into_values() {
     let val_string: String = num.into();
     assert_eq!(val_string, "9");
 }
+
+#[test]
+fn test_iter_all_players() {
+    // iter yields every player in the session, starting with the host.
+    let players: Vec<_> = PlayerNum::iter(3).collect();
+    assert_eq!(players, vec![PlayerNum(0), PlayerNum(1), PlayerNum(2)]);
+}
+
+#[test_case(0, vec![] ; "no players")]
+#[test_case(1, vec![] ; "host only")]
+#[test_case(3, vec![PlayerNum(1), PlayerNum(2)] ; "host and two guests")]
+fn test_iter_guests(num_players: u8, expected: Vec<PlayerNum>) {
+    // iter_guests yields every player except the host, and is empty for
+    // sessions without guests.
+    assert_eq!(
+        PlayerNum::iter_guests(num_players).collect::<Vec<_>>(),
+        expected
+    );
+}
+
+#[test_case(1 ; "host only")]
+#[test_case(4 ; "host and three guests")]
+#[test_case(u8::MAX ; "largest session")]
+fn test_iter_guests_matches_guest_indices(num_players: u8) {
+    // iter_guests yields the same players, in the same order, as converting
+    // each guest index with from_guest_index.
+    let from_indices: Vec<_> = (0..num_players as usize - 1)
+        .map(|guest_idx| PlayerNum::from_guest_index(guest_idx).unwrap())
+        .collect();
+    assert_eq!(
+        PlayerNum::iter_guests(num_players).collect::<Vec<_>>(),
+        from_indices
+    );
+}
+
+#[test_case("PNum_3", Ok(PlayerNum(3)) ; "display form")]
+#[test_case("3", Ok(PlayerNum(3)) ; "bare number")]
+#[test_case(" PNum_0 ", Ok(PlayerNum(0)) ; "surrounding whitespace")]
+#[test_case("PNum_256", Err(()) ; "out of range")]
+#[test_case("player 3", Err(()) ; "garbage")]
+fn test_from_str(s: &str, expected: Result<PlayerNum, ()>) {
+    // PlayerNum parses its own Display output as well as bare numbers.
+    assert_eq!(s.parse::<PlayerNum>().map_err(|_| ()), expected);
+}
+
+#[test]
+fn test_display_round_trips_through_from_str() {
+    // Formatting a PlayerNum and parsing it back gives the same PlayerNum.
+    let num = PlayerNum(42);
+    assert_eq!(num.to_string().parse::<PlayerNum>(), Ok(num));
+}
+
+#[test_case(0, Ok(PlayerNum(1)) ; "first guest")]
+#[test_case(253, Ok(PlayerNum(254)) ; "second to last guest")]
+#[test_case(254, Ok(PlayerNum(255)) ; "last guest")]
+#[test_case(255, Err(()) ; "too large")]
+#[test_case(usize::MAX, Err(()) ; "usize max")]
+fn test_from_guest_index(index: usize, expected: Result<PlayerNum, ()>) {
+    // from_guest_index is checked rather than silently truncating large indices.
+    assert_eq!(PlayerNum::from_guest_index(index).map_err(|_| ()), expected);
+}
+
+#[test]
+fn test_const_constructors() {
+    // The basic constructors and accessors are usable in const contexts.
+    const HOST: PlayerNum = PlayerNum::new_host();
+    const GUEST: PlayerNum = PlayerNum::new_guest(2);
+    const { assert!(GUEST.is_guest()) };
+    assert!(HOST.is_host());
+    assert_eq!(PlayerNum::from_u8(2), GUEST);
+}
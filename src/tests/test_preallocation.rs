@@ -0,0 +1,45 @@
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+const MATCH_TICKS: u32 = 3 * 60 * 60;
+
+#[test]
+fn test_preallocated_buffers_do_not_grow_during_match() {
+    // Filling every buffer up to the pre-allocated length doesn't change its
+    // capacity, i.e. no reallocation happens mid-match.
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 5, 5, 60)
+        .with_preallocated_ticks(MATCH_TICKS);
+    let capacity = host.capacity_ticks();
+    assert!(capacity >= MATCH_TICKS);
+
+    for _ in 0..MATCH_TICKS {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host.rx_guest_input_slice(
+        PlayerNum(1),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, MATCH_TICKS)),
+    );
+    assert_eq!(host.capacity_ticks(), capacity);
+}
+
+#[test]
+fn test_preallocation_repeats_for_new_rounds() {
+    // The buffers for each new round are pre-allocated like the first.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, 1.into(), 60)
+        .with_preallocated_ticks(MATCH_TICKS);
+    guest.start_new_round();
+    assert!(guest.capacity_ticks() >= MATCH_TICKS);
+}
+
+#[test]
+fn test_no_preallocation_by_default() {
+    // Without the option, buffers start empty and grow on demand.
+    let guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, 1.into(), 60);
+    assert_eq!(guest.capacity_ticks(), 0);
+}
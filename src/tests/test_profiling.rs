@@ -0,0 +1,177 @@
+use std::{cell::Cell, rc::Rc};
+
+use test_case::test_case;
+
+use crate::{
+    CallBudget, CallProfiler, CallStats, ProfileProbe,
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    session::Session,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+/// A probe whose clock and allocation count only move when the test says so.
+#[derive(Debug, Clone, Default)]
+struct ScriptedProbe {
+    nanos: Rc<Cell<u64>>,
+    bytes: Rc<Cell<u64>>,
+}
+
+impl ScriptedProbe {
+    /// Simulates a call that takes `nanos` and allocates `bytes`.
+    fn spend(&self, nanos: u64, bytes: u64) {
+        self.nanos.set(self.nanos.get() + nanos);
+        self.bytes.set(self.bytes.get() + bytes);
+    }
+}
+
+impl ProfileProbe for ScriptedProbe {
+    fn now_nanos(&self) -> u64 {
+        self.nanos.get()
+    }
+
+    fn bytes_allocated(&self) -> u64 {
+        self.bytes.get()
+    }
+}
+
+#[test]
+fn test_calls_aggregate_per_frame() {
+    // Calls under the same name are summed over the frame, with the worst
+    // single call kept; ending the frame returns it and starts an empty one.
+    let probe = ScriptedProbe::default();
+    let mut profiler = CallProfiler::new(probe.clone());
+    profiler.measure("rx_msg", || probe.spend(100, 16));
+    profiler.measure("rx_msg", || probe.spend(300, 0));
+    profiler.measure("add_own_input", || probe.spend(50, 8));
+
+    let frame = profiler.end_frame();
+    assert_eq!(frame.frame, 0);
+    assert_eq!(
+        frame.calls["rx_msg"],
+        CallStats {
+            calls: 2,
+            total_nanos: 400,
+            max_nanos: 300,
+            bytes_allocated: 16,
+            max_bytes: 16,
+            over_budget: 0,
+        }
+    );
+    assert_eq!((frame.total_nanos(), frame.bytes_allocated()), (450, 24));
+    assert_eq!(profiler.current_frame().frame, 1);
+    assert!(profiler.current_frame().calls.is_empty());
+}
+
+#[test]
+fn test_measure_returns_the_call_result() {
+    // Wrapping a manager call doesn't change what it returns, and time spent
+    // outside `measure` isn't counted.
+    let probe = ScriptedProbe::default();
+    let mut profiler = CallProfiler::new(probe.clone());
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 50, 5, 60);
+    host.add_host_input_directly(PlayerInput::default());
+
+    probe.spend(1_000, 1_000);
+    let num_inputs = profiler.measure("get_peer_num_final_inputs", || {
+        probe.spend(20, 0);
+        host.get_peer_num_final_inputs(HOST_PLAYER_NUM)
+    });
+    assert_eq!(num_inputs, 1);
+    assert_eq!(profiler.current_frame().total_nanos(), 20);
+}
+
+#[test]
+fn test_session_measures_its_frame_calls() {
+    // With a profiler attached to its manager, a session measures its own
+    // input, receive and per-frame send calls under their names.
+    let probe = ScriptedProbe::default();
+    let host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 50, 5, 60)
+        .with_profiler(CallProfiler::new(Box::new(probe.clone())));
+    let mut session = Session::from(host);
+
+    session.add_own_input(PlayerInput::default(), 1.0 / 60.0);
+    session
+        .rx_msg(
+            PlayerNum(1),
+            MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 1)),
+        )
+        .unwrap();
+    session.get_outgoing_msgs_for_frame(1.0 / 60.0);
+
+    let frame = session.as_host_mut().unwrap().end_profile_frame().unwrap();
+    let calls: Vec<(&str, u32)> = frame
+        .calls
+        .iter()
+        .map(|(name, stats)| (*name, stats.calls))
+        .collect();
+    assert_eq!(
+        calls,
+        vec![
+            ("add_own_input", 1),
+            ("get_outgoing_msgs_for_frame", 1),
+            ("rx_msg", 1)
+        ]
+    );
+}
+
+#[test_case(CallBudget { max_nanos: Some(100), max_bytes: None }, 101, 0, true ; "over the time budget")]
+#[test_case(CallBudget { max_nanos: Some(100), max_bytes: None }, 100, 1 << 20, false ; "at the time budget with time only")]
+#[test_case(CallBudget { max_nanos: None, max_bytes: Some(64) }, 5_000, 65, true ; "over the byte budget")]
+#[test_case(CallBudget::default(), u64::MAX / 2, u64::MAX / 2, false ; "unbounded budget")]
+fn test_calls_over_budget_are_counted(
+    budget: CallBudget,
+    nanos: u64,
+    bytes: u64,
+    over_budget: bool,
+) {
+    // Without assertions, a call over budget is only counted.
+    let probe = ScriptedProbe::default();
+    let mut profiler = CallProfiler::new(probe.clone()).with_budget("poll_catch_up", budget);
+    profiler.measure("poll_catch_up", || probe.spend(nanos, bytes));
+
+    let expected: Vec<&str> = if over_budget {
+        vec!["poll_catch_up"]
+    } else {
+        vec![]
+    };
+    assert_eq!(profiler.current_frame().calls_over_budget(), expected);
+}
+
+#[test]
+fn test_budgets_only_apply_to_their_call() {
+    // A budget on one call name doesn't apply to calls under other names.
+    let probe = ScriptedProbe::default();
+    let mut profiler = CallProfiler::new(probe.clone())
+        .with_budget(
+            "rx_msg",
+            CallBudget {
+                max_nanos: Some(10),
+                max_bytes: Some(0),
+            },
+        )
+        .with_budget_assertions(true);
+    profiler.measure("get_msg_finalized_slice", || probe.spend(1_000, 1_000));
+
+    assert!(profiler.current_frame().calls_over_budget().is_empty());
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "`rx_msg` went over its budget")]
+fn test_budget_assertions_panic_in_debug() {
+    // With budget assertions on, a call over budget panics in debug builds.
+    let probe = ScriptedProbe::default();
+    let mut profiler = CallProfiler::new(probe.clone())
+        .with_budget(
+            "rx_msg",
+            CallBudget {
+                max_nanos: Some(10),
+                max_bytes: None,
+            },
+        )
+        .with_budget_assertions(true);
+    profiler.measure("rx_msg", || probe.spend(11, 0));
+}
@@ -0,0 +1,217 @@
+use test_case::test_case;
+
+use crate::{
+    input_messages::{HostFinalizedSlice, MsgKind, MsgPayload},
+    input_trait::SimInput,
+    msg_sequence::{SEQUENCE_WINDOW, SequencedMsg},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    session::{Recipient, Session},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+type Msg = MsgPayload<PlayerInput>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+const RESEND_AFTER_SEC: f32 = 0.2;
+
+fn reliable_host() -> Host {
+    Host::new(2, 50, 5, 60).with_reliable_delivery(RESEND_AFTER_SEC)
+}
+
+fn reliable_guest() -> Guest {
+    Guest::new(2, GUEST, 60).with_reliable_delivery(RESEND_AFTER_SEC)
+}
+
+fn finalized(start: u32, num_inputs: u32) -> Msg {
+    MsgPayload::HostToLobbyFinalizedSlice(HostFinalizedSlice::new_test(
+        HOST_PLAYER_NUM,
+        start + num_inputs,
+        start,
+        num_inputs,
+    ))
+}
+
+fn seqs_and_kinds(
+    msgs: &[(PlayerNum, SequencedMsg<PlayerInput>)],
+) -> Vec<(PlayerNum, u32, MsgKind)> {
+    msgs.iter()
+        .map(|(to, msg)| (*to, msg.seq, msg.payload.kind()))
+        .collect()
+}
+
+#[test_case(finalized(0, 3), true; "finalized slice")]
+#[test_case(MsgPayload::Batch(vec![MsgPayload::HostToGuestPong(1), finalized(0, 3)]), true; "batch holding a finalized slice")]
+#[test_case(MsgPayload::HostToGuestPong(1), false; "pong")]
+#[test_case(MsgPayload::Empty, false; "empty")]
+fn test_only_critical_msgs_are_held(msg: Msg, expected_held: bool) {
+    // Critical messages, alone or in a batch, are held until acked; others
+    // are sent once.
+    let mut host = reliable_host();
+
+    host.sequence_msg(GUEST, msg);
+
+    assert_eq!(host.num_pending_reliable_msgs(GUEST) == 1, expected_held);
+}
+
+#[test]
+fn test_unacked_msg_is_resent_with_its_seq() {
+    // An unacked critical message is resent, with its original sequence
+    // number, each time the resend interval passes.
+    let mut host = reliable_host();
+    host.sequence_msg(GUEST, MsgPayload::HostToGuestPong(1));
+    host.sequence_msg(GUEST, finalized(0, 3));
+
+    let early = host.update_reliable_delivery(0.1);
+    let due = host.update_reliable_delivery(0.1);
+    let again = host.update_reliable_delivery(0.2);
+
+    assert!(early.is_empty());
+    let expected = vec![(GUEST, 1, MsgKind::HostToLobbyFinalizedSlice)];
+    assert_eq!(seqs_and_kinds(&due), expected);
+    assert_eq!(seqs_and_kinds(&again), expected);
+    assert_eq!(host.num_resent_msgs(GUEST), 2);
+}
+
+#[test]
+fn test_ack_stops_resends() {
+    // Once the receiver acks a critical message, it is no longer resent.
+    let mut host = reliable_host();
+    let mut guest = reliable_guest();
+    let sent = host.sequence_msg(GUEST, finalized(0, 3));
+
+    let received = guest.decode_sequenced_msg_from_peer(HOST_PLAYER_NUM, &sent.to_bytes());
+    let acks = guest.update_reliable_delivery(0.0);
+    assert!(matches!(
+        received,
+        Some(MsgPayload::HostToLobbyFinalizedSlice(_))
+    ));
+    assert_eq!(
+        seqs_and_kinds(&acks),
+        vec![(HOST_PLAYER_NUM, 0, MsgKind::DeliveryAck)]
+    );
+
+    let ack = host.decode_sequenced_msg_from_peer(GUEST, &acks[0].1.to_bytes());
+
+    assert!(matches!(ack, Some(MsgPayload::Empty)));
+    assert_eq!(host.num_pending_reliable_msgs(GUEST), 0);
+    assert!(host.update_reliable_delivery(1.0).is_empty());
+}
+
+#[test]
+fn test_duplicates_are_acked_again() {
+    // A resent message that already arrived is dropped but acked again, in
+    // case the first ack was lost.
+    let mut host = reliable_host();
+    let mut guest = reliable_guest();
+    let bytes = host.sequence_msg(GUEST, finalized(0, 3)).to_bytes();
+    guest.decode_sequenced_msg_from_peer(HOST_PLAYER_NUM, &bytes);
+    guest.update_reliable_delivery(0.0);
+
+    let duplicate = guest.decode_sequenced_msg_from_peer(HOST_PLAYER_NUM, &bytes);
+    let acks = guest.update_reliable_delivery(0.0);
+
+    assert!(matches!(duplicate, Some(MsgPayload::Empty)));
+    match &acks[..] {
+        [
+            (
+                HOST_PLAYER_NUM,
+                SequencedMsg {
+                    payload: MsgPayload::DeliveryAck(seqs),
+                    ..
+                },
+            ),
+        ] => {
+            assert_eq!(seqs, &vec![0])
+        }
+        _ => panic!("expected one ack, got {:?}", seqs_and_kinds(&acks)),
+    }
+}
+
+#[test]
+fn test_too_old_resend_is_not_acked() {
+    // A critical message lost the first time, then resent after more than
+    // `SEQUENCE_WINDOW` newer messages have arrived, is dropped as too old to
+    // tell from a duplicate. It must not be acked, so the sender keeps
+    // holding it rather than giving up on a message that was never applied.
+    let mut host = reliable_host();
+    let mut guest = reliable_guest();
+    host.sequence_msg(GUEST, finalized(0, 3));
+    for ping in 0..=SEQUENCE_WINDOW {
+        let bytes = host
+            .sequence_msg(GUEST, MsgPayload::HostToGuestPong(ping))
+            .to_bytes();
+        guest.decode_sequenced_msg_from_peer(HOST_PLAYER_NUM, &bytes);
+    }
+
+    let resent = host.update_reliable_delivery(RESEND_AFTER_SEC);
+    assert_eq!(
+        seqs_and_kinds(&resent),
+        vec![(GUEST, 0, MsgKind::HostToLobbyFinalizedSlice)]
+    );
+    let received = guest.decode_sequenced_msg_from_peer(HOST_PLAYER_NUM, &resent[0].1.to_bytes());
+
+    assert!(matches!(received, Some(MsgPayload::Empty)));
+    assert!(guest.update_reliable_delivery(0.0).is_empty());
+    assert_eq!(host.num_pending_reliable_msgs(GUEST), 1);
+}
+
+#[test]
+fn test_superseded_msg_is_no_longer_resent() {
+    // A longer finalized slice replaces the held one it covers.
+    let mut host = reliable_host();
+    host.sequence_msg(GUEST, finalized(0, 3));
+    host.sequence_msg(GUEST, finalized(0, 5));
+
+    let resends = host.update_reliable_delivery(RESEND_AFTER_SEC);
+
+    assert_eq!(host.num_pending_reliable_msgs(GUEST), 1);
+    assert_eq!(
+        seqs_and_kinds(&resends),
+        vec![(GUEST, 1, MsgKind::HostToLobbyFinalizedSlice)]
+    );
+}
+
+#[test]
+fn test_disabled_by_default() {
+    // Without reliable delivery, nothing is held, acked or resent.
+    let mut host = Host::new(2, 50, 5, 60);
+    let mut guest = Guest::new(2, GUEST, 60);
+    let bytes = host.sequence_msg(GUEST, finalized(0, 3)).to_bytes();
+    guest.decode_sequenced_msg_from_peer(HOST_PLAYER_NUM, &bytes);
+
+    assert_eq!(host.reliable_delivery_resend_after_sec(), None);
+    assert_eq!(host.num_pending_reliable_msgs(GUEST), 0);
+    assert!(host.update_reliable_delivery(1.0).is_empty());
+    assert!(guest.update_reliable_delivery(1.0).is_empty());
+}
+
+#[test]
+fn test_session_recovers_lost_finalized_slice() {
+    // A finalized slice lost on the way to the guest arrives when resent, and
+    // the guest's ack ends the resends.
+    let mut host: Session<PlayerInput> = reliable_host().into();
+    let mut guest: Session<PlayerInput> = reliable_guest().into();
+    let lost = host.to_sequenced_bytes(vec![(Recipient::AllPeers, finalized(0, 3))]);
+    assert_eq!(lost.len(), 1);
+
+    let resent = host.update_reliable_delivery(RESEND_AFTER_SEC);
+    assert_eq!(resent.len(), 1);
+    guest
+        .rx_sequenced_bytes(HOST_PLAYER_NUM, &resent[0].1)
+        .unwrap();
+    for (_, ack) in guest.update_reliable_delivery(0.0) {
+        host.rx_sequenced_bytes(GUEST, &ack).unwrap();
+    }
+
+    assert_eq!(
+        guest.get_peer_input_for_tick(HOST_PLAYER_NUM, 2).to_bytes(),
+        PlayerInputSlice::<PlayerInput>::new_test(0, 3).inputs[2]
+    );
+    assert_eq!(host.as_host().unwrap().num_pending_reliable_msgs(GUEST), 0);
+    assert!(host.update_reliable_delivery(1.0).is_empty());
+}
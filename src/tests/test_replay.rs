@@ -0,0 +1,283 @@
+use test_case::test_case;
+
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    replay::{REPLAY_FORMAT_VERSION, REPLAY_MAGIC, Replay, ReplayPlayer, ReplayRecorder},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+/// A 3 player host with 10 ticks finalized across all players, starting at tick 100.
+fn host_with_inputs() -> MultiplayerInputManager<PlayerInput, HostInputMgr> {
+    let mut host =
+        MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(3, 5, 5, 60).with_start_tick(100);
+    for i in 0..12 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(i));
+    }
+    for guest in [PlayerNum(1), PlayerNum(2)] {
+        host.rx_guest_input_slice(
+            guest,
+            MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 10)),
+        );
+    }
+    host
+}
+
+#[test]
+fn test_export_replay_covers_finalized_ticks() {
+    // The exported replay covers the ticks finalized across all players, and
+    // looks inputs up by sim tick.
+    let host = host_with_inputs();
+    let replay = host.export_replay();
+
+    assert_eq!(replay.num_ticks(), 10);
+    assert_eq!(replay.start_tick, 100);
+    assert_eq!(
+        replay.input(PlayerNum(0), 103),
+        Some(host.get_peer_input_for_tick(PlayerNum(0), 103))
+    );
+    assert_eq!(replay.input(PlayerNum(0), 110), None);
+}
+
+#[test_case(false; "without checksums")]
+#[test_case(true; "with checksums")]
+fn test_replay_round_trips_through_bytes(with_checksums: bool) {
+    // A replay written to bytes reads back identically.
+    let replay = host_with_inputs().export_replay();
+    let bytes = replay.to_bytes(with_checksums).unwrap();
+    assert_eq!(Replay::<PlayerInput>::from_bytes(&bytes).unwrap(), replay);
+}
+
+#[test]
+fn test_replay_header_layout() {
+    // The header matches the documented layout, so external tools can parse it.
+    let bytes = host_with_inputs().export_replay().to_bytes(true).unwrap();
+
+    assert_eq!(bytes[0..4], REPLAY_MAGIC);
+    assert_eq!(
+        u16::from_le_bytes([bytes[4], bytes[5]]),
+        REPLAY_FORMAT_VERSION
+    );
+    assert_eq!(u16::from_le_bytes([bytes[6], bytes[7]]), 1);
+    assert_eq!(u32::from_le_bytes(bytes[8..12].try_into().unwrap()), 60);
+    assert_eq!(u32::from_le_bytes(bytes[12..16].try_into().unwrap()), 100);
+    assert_eq!(u32::from_le_bytes(bytes[16..20].try_into().unwrap()), 10);
+    assert_eq!(bytes[20], 3);
+}
+
+#[test]
+fn test_replay_total_length() {
+    // The file is the header, plus a framed chunk per player, plus a checksum
+    // per tick.
+    let replay = host_with_inputs().export_replay();
+    let bytes = replay.to_bytes(true).unwrap();
+    let width = u16::from_le_bytes([bytes[21], bytes[22]]) as usize;
+    assert_eq!(bytes.len(), 23 + 3 * (5 + 10 * width) + 10 * 4);
+}
+
+#[test]
+fn test_corrupted_input_fails_checksum() {
+    // Flipping a byte in a player's inputs is caught by the per-tick checksums.
+    let mut bytes = host_with_inputs().export_replay().to_bytes(true).unwrap();
+    bytes[23 + 5] ^= 0xff;
+    let err = Replay::<PlayerInput>::from_bytes(&bytes).unwrap_err();
+    assert!(err.contains("checksum mismatch at tick 100"), "{err}");
+}
+
+#[test_case(|b: &mut Vec<u8>| b[0] = b'X'; "bad magic")]
+#[test_case(|b: &mut Vec<u8>| b[4] = 2; "unknown version")]
+#[test_case(|b: &mut Vec<u8>| b[6] = 0x02; "unknown flag")]
+#[test_case(|b: &mut Vec<u8>| b.truncate(30); "truncated")]
+#[test_case(|b: &mut Vec<u8>| b.push(0); "trailing bytes")]
+#[test_case(|b: &mut Vec<u8>| b[23] = 1; "chunk out of order")]
+fn test_reader_rejects_malformed_replays(corrupt: fn(&mut Vec<u8>)) {
+    // Replays that deviate from the format are rejected rather than misread.
+    let mut bytes = host_with_inputs().export_replay().to_bytes(false).unwrap();
+    corrupt(&mut bytes);
+    assert!(Replay::<PlayerInput>::from_bytes(&bytes).is_err());
+}
+
+#[test]
+fn test_export_archived_replay() {
+    // Completed rounds can be exported too, while unarchived rounds can't.
+    let mut host = host_with_inputs();
+    host.start_new_round();
+
+    assert_eq!(host.export_archived_replay(0).unwrap().num_ticks(), 10);
+    assert!(host.export_archived_replay(1).is_none());
+}
+
+/// A 2 player host and guest keeping only their `max_retained_inputs` most recent inputs.
+fn retaining_session(
+    max_retained_inputs: u32,
+) -> (
+    MultiplayerInputManager<PlayerInput, HostInputMgr>,
+    MultiplayerInputManager<PlayerInput, GuestInputMgr>,
+) {
+    (
+        MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 50, 5, 60)
+            .with_max_retained_inputs(max_retained_inputs),
+        MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, PlayerNum(1), 60)
+            .with_max_retained_inputs(max_retained_inputs),
+    )
+}
+
+/// Runs one frame: both sides collect an input, and every message is exchanged.
+fn run_frame(
+    host: &mut MultiplayerInputManager<PlayerInput, HostInputMgr>,
+    guest: &mut MultiplayerInputManager<PlayerInput, GuestInputMgr>,
+) {
+    let frame = host.get_own_num_inputs() as u8;
+    host.add_host_input_directly(PlayerInput::new_test_simple(frame));
+    guest.add_own_input(PlayerInput::new_test_simple(frame + 1));
+    host.rx_guest_input_slice(PlayerNum(1), guest.get_msg_own_input_slice());
+    for player in [PlayerNum(0), PlayerNum(1)] {
+        guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(player));
+    }
+    host.rx_finalized_ticks_observations(PlayerNum(1), guest.get_msg_ack_finalization());
+}
+
+#[test]
+fn test_recorder_matches_export_when_nothing_is_dropped() {
+    // Recording a match as it is finalized gives the same replay as exporting
+    // it at the end.
+    let mut host =
+        MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(3, 5, 5, 60).with_start_tick(100);
+    let mut recorder = ReplayRecorder::new(&host);
+    for i in 0..12 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(i));
+    }
+    for (guest, len) in [(PlayerNum(1), 4), (PlayerNum(2), 10)] {
+        host.rx_guest_input_slice(
+            guest,
+            MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, len)),
+        );
+    }
+    assert_eq!(recorder.record(&host), Ok(4));
+    host.rx_guest_input_slice(
+        PlayerNum(1),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(4, 6)),
+    );
+    assert_eq!(recorder.record(&host), Ok(6));
+
+    assert_eq!(recorder.into_replay(), host.export_replay());
+}
+
+#[test]
+fn test_recorder_keeps_inputs_the_buffers_drop() {
+    // With a retention limit, the recorder still holds every tick of the
+    // match, while an export only covers the retained ones.
+    let (mut host, mut guest) = retaining_session(4);
+    let mut recorder = ReplayRecorder::new(&host);
+    for _ in 0..50 {
+        run_frame(&mut host, &mut guest);
+        recorder.record(&host).unwrap();
+    }
+
+    let replay = recorder.into_replay();
+    assert_eq!(replay.num_ticks(), 50);
+    assert_eq!(
+        replay.input(PlayerNum(0), 0),
+        Some(PlayerInput::new_test_simple(0))
+    );
+    assert!(host.export_replay().start_tick > 0);
+}
+
+#[test]
+fn test_recorder_fails_once_unrecorded_inputs_are_dropped() {
+    // Recording too rarely to keep up with the retention limit is an error,
+    // rather than a replay with a gap.
+    let (mut host, mut guest) = retaining_session(4);
+    let mut recorder = ReplayRecorder::new(&host);
+    for _ in 0..50 {
+        run_frame(&mut host, &mut guest);
+    }
+
+    assert!(recorder.record(&host).is_err());
+    assert_eq!(recorder.replay().num_ticks(), 0);
+}
+
+#[test]
+fn test_recorder_fails_in_another_round() {
+    // A recorder covers a single round.
+    let mut host = host_with_inputs();
+    let mut recorder = ReplayRecorder::new(&host);
+
+    host.start_new_round();
+
+    assert!(recorder.record(&host).is_err());
+}
+
+#[test]
+fn test_recorder_pads_players_added_mid_match() {
+    // A player added after recording started has default inputs for the
+    // ticks already recorded, as the host finalized for them.
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(1, 5, 5, 60);
+    let mut recorder = ReplayRecorder::new(&host);
+    for i in 0..5 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(i + 1));
+    }
+    recorder.record(&host).unwrap();
+
+    host.add_player(PlayerNum(1)).unwrap();
+    host.rx_guest_input_slice(
+        PlayerNum(1),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(5, 0)),
+    );
+    recorder.record(&host).unwrap();
+
+    let replay = recorder.into_replay();
+    assert_eq!(replay.num_players(), 2);
+    assert_eq!(replay.input(PlayerNum(1), 2), Some(PlayerInput::default()));
+}
+
+#[test]
+fn test_player_feeds_one_tick_at_a_time() {
+    // Each step finalizes one more tick for every player in the manager, until
+    // the replay runs out.
+    let replay = host_with_inputs().export_replay();
+    let mut player = ReplayPlayer::new(replay.clone());
+    let mut mgr = player.new_manager();
+
+    assert!(player.step(&mut mgr));
+    assert!(player.step(&mut mgr));
+
+    assert_eq!(mgr.get_snapshottable_sim_tick(), 102);
+    assert_eq!(
+        Some(mgr.get_peer_input_for_tick(PlayerNum(2), 101)),
+        replay.input(PlayerNum(2), 101)
+    );
+}
+
+#[test]
+fn test_player_stops_at_the_end_of_the_replay() {
+    // Once every tick has been played, steps change nothing.
+    let replay = host_with_inputs().export_replay();
+    let mut player = ReplayPlayer::new(replay);
+    let mut mgr = player.new_manager();
+    while player.step(&mut mgr) {}
+
+    assert!(player.is_finished());
+    assert_eq!(player.num_ticks_played(), 10);
+    assert!(player.next_tick_msgs().is_empty());
+    assert_eq!(mgr.get_snapshottable_sim_tick(), 110);
+}
+
+#[test]
+fn test_recorded_match_replays_through_bytes() {
+    // A recorded match written to bytes and played back gives the manager the
+    // same finalized inputs as the original.
+    let host = host_with_inputs();
+    let mut recorder = ReplayRecorder::new(&host);
+    recorder.record(&host).unwrap();
+    let bytes = recorder.into_replay().to_bytes(true).unwrap();
+
+    let mut player = ReplayPlayer::new(Replay::<PlayerInput>::from_bytes(&bytes).unwrap());
+    let mut mgr = player.new_manager();
+    while player.step(&mut mgr) {}
+
+    assert_eq!(mgr.export_replay(), host.export_replay());
+}
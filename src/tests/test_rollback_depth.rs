@@ -0,0 +1,106 @@
+use crate::{
+    events::InputMgrEvent,
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+/// Finalizes the first `num_inputs` inputs of both players on a 2 player guest.
+fn finalize_both_players(guest: &mut Guest, num_inputs: u32) {
+    for player in [PlayerNum(0), PlayerNum(1)] {
+        guest.rx_final_peer_input_slice_from_host(MsgPayload::HostToLobbyFinalizedSlice(
+            HostFinalizedSlice::new_test(player, num_inputs, 0, num_inputs),
+        ));
+    }
+}
+
+#[test]
+fn test_depth_is_own_tick_minus_snapshottable_tick() {
+    // The depth grows with each own input, and shrinks as inputs are
+    // finalized across all players.
+    let mut guest = Guest::new(2, 1.into(), 60);
+    assert_eq!(guest.max_rollback_depth(), 0);
+    for _ in 0..6 {
+        guest.add_own_input(PlayerInput::default());
+    }
+    assert_eq!(guest.max_rollback_depth(), 6);
+
+    finalize_both_players(&mut guest, 4);
+    assert_eq!(guest.get_snapshottable_sim_tick(), 4);
+    assert_eq!(guest.max_rollback_depth(), 2);
+}
+
+#[test]
+fn test_guest_stops_collecting_inputs_at_cap() {
+    // Once the depth reaches the cap, the guest asks for no more inputs
+    // until more inputs are finalized.
+    let mut guest = Guest::new(2, 1.into(), 60).with_max_rollback_depth_cap(3);
+    for _ in 0..2 {
+        guest.add_own_input(PlayerInput::default());
+    }
+    assert_eq!(guest.num_inputs_needed(), 1);
+    guest.add_own_input(PlayerInput::default());
+    assert_eq!(guest.num_inputs_needed(), 0);
+
+    finalize_both_players(&mut guest, 1);
+    assert_eq!(guest.num_inputs_needed(), 1);
+}
+
+#[test]
+fn test_cap_event_is_raised_each_time_the_cap_is_reached() {
+    // The event is raised when the depth reaches the cap, not again while
+    // it stays there, and again after it has dropped below the cap.
+    let mut guest = Guest::new(2, 1.into(), 60).with_max_rollback_depth_cap(2);
+    for _ in 0..3 {
+        guest.add_own_input(PlayerInput::default());
+    }
+    assert_eq!(
+        guest.drain_events(),
+        vec![InputMgrEvent::RollbackDepthCapReached { depth: 2 }]
+    );
+
+    finalize_both_players(&mut guest, 3);
+    guest.add_own_input(PlayerInput::default());
+    assert_eq!(guest.drain_events(), vec![]);
+    guest.add_own_input(PlayerInput::default());
+    assert_eq!(
+        guest.drain_events(),
+        vec![InputMgrEvent::RollbackDepthCapReached { depth: 2 }]
+    );
+}
+
+#[test]
+fn test_summary_covers_only_the_most_recent_window() {
+    // With a window of 4 samples, only the depths 3, 4, 5 and 6 remain.
+    let mut guest = Guest::new(2, 1.into(), 60).with_rollback_depth_window(4);
+    assert_eq!(guest.rollback_depth_summary(), None);
+    for _ in 0..6 {
+        guest.add_own_input(PlayerInput::default());
+    }
+
+    let summary = guest.rollback_depth_summary().unwrap();
+    assert_eq!(summary.num_samples, 4);
+    assert_eq!(summary.mean, 4.5);
+    assert_eq!((summary.p50, summary.max), (4, 6));
+}
+
+#[test]
+fn test_host_raises_cap_event_for_lagging_guest() {
+    // The host's depth grows while a guest sends nothing; the host keeps
+    // collecting its own inputs, but raises the event.
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 50, 5, 60)
+        .with_max_rollback_depth_cap(4);
+    for _ in 0..5 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    assert_eq!(host.max_rollback_depth(), 5);
+    assert_eq!(
+        host.drain_events(),
+        vec![InputMgrEvent::RollbackDepthCapReached { depth: 4 }]
+    );
+}
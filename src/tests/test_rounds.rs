@@ -0,0 +1,140 @@
+use crate::{
+    input_messages::{MsgPayload, PongPong},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+const GUEST: PlayerNum = PlayerNum::new_guest(1);
+
+fn host_after_one_round() -> MultiplayerInputManager<PlayerInput, HostInputMgr> {
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 5, 5, 60);
+    host.add_host_input_to_fill_needed(PlayerInput::default(), 10.0 / 60.0);
+    host.rx_guest_input_slice(
+        GUEST,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 8)),
+    );
+    host
+}
+
+#[test]
+fn test_host_start_new_round_archives_and_resets() {
+    // Starting a new round archives the finished round's finalized inputs,
+    // resets all buffers, and returns a broadcast announcing the new round.
+    let mut host = host_after_one_round();
+    assert_eq!(host.get_snapshottable_sim_tick(), 8);
+
+    let msg = host.start_new_round();
+    assert!(matches!(msg, MsgPayload::HostToLobbyRoundTransition(1)));
+    assert!(msg.is_host_reply_for_all());
+
+    assert_eq!(host.current_round(), 1);
+    assert_eq!(host.get_own_num_inputs(), 0);
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 0);
+    assert_eq!(host.get_archived_final_inputs_by_tick(0).unwrap().len(), 8);
+    assert!(host.get_archived_final_inputs_by_tick(1).is_none());
+}
+
+#[test]
+fn test_host_restarts_sim_time_for_new_round() {
+    // The host's own tick counter restarts from 0 in the new round.
+    let mut host = host_after_one_round();
+    host.start_new_round();
+    host.add_host_input_to_fill_needed(PlayerInput::default(), 3.0 / 60.0);
+    assert_eq!(host.get_own_num_inputs(), 3);
+}
+
+#[test]
+fn test_host_ignores_guest_until_round_ack() {
+    // Stale inputs from a guest that hasn't acked the new round are dropped;
+    // once the guest acks, its inputs are accepted again.
+    let mut host = host_after_one_round();
+    host.start_new_round();
+
+    host.rx_guest_input_slice(
+        GUEST,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 12)),
+    );
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 0);
+
+    // an ack for the wrong round doesn't count
+    host.rx_round_transition_ack(GUEST, MsgPayload::GuestToHostRoundTransitionAck(0));
+    host.rx_guest_input_slice(
+        GUEST,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 12)),
+    );
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 0);
+
+    host.rx_round_transition_ack(GUEST, MsgPayload::GuestToHostRoundTransitionAck(1));
+    host.rx_guest_input_slice(
+        GUEST,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 4)),
+    );
+    assert_eq!(host.get_peer_num_final_inputs(GUEST), 4);
+}
+
+#[test]
+fn test_host_preserves_rtt_across_rounds() {
+    // RTT estimates survive a round transition.
+    let mut host = host_after_one_round();
+    let pong = host.rx_guest_ping_and_reply(GUEST, MsgPayload::GuestToHostPing(0));
+    assert!(matches!(pong, MsgPayload::HostToGuestPong(0)));
+    host.rx_guest_pong_pong(
+        GUEST,
+        MsgPayload::GuestToHostPongPong(PongPong {
+            ping_id: 0,
+            guest_rtt_micros: 50_000,
+        }),
+    )
+    .unwrap();
+    let rtts_before = host.rtts_by_player();
+
+    host.start_new_round();
+    assert_eq!(host.rtts_by_player(), rtts_before);
+}
+
+#[test]
+fn test_guest_rx_round_transition() {
+    // A guest receiving a round transition archives its buffers, keeps its RTT
+    // estimate, and replies with an ack for the new round.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, GUEST, 60);
+    guest.observe_rtt_ms_to_host(50.0);
+    for _ in 0..6 {
+        guest.add_own_input(PlayerInput::default());
+    }
+
+    let reply = guest.rx_round_transition_and_reply(MsgPayload::HostToLobbyRoundTransition(1));
+    assert!(matches!(
+        reply,
+        MsgPayload::GuestToHostRoundTransitionAck(1)
+    ));
+    assert!(reply.is_guest_reply());
+    assert_eq!(guest.current_round(), 1);
+    assert_eq!(guest.get_own_num_inputs(), 0);
+    assert_eq!(guest.get_rtt_ms_to_host(), Some(50.0));
+}
+
+#[test]
+fn test_guest_ignores_stale_round_transition() {
+    // A repeated announcement for a round the guest has already started is a
+    // no-op, and produces no reply.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, GUEST, 60);
+    guest.rx_round_transition_and_reply(MsgPayload::HostToLobbyRoundTransition(1));
+    guest.add_own_input(PlayerInput::default());
+
+    let reply = guest.rx_round_transition_and_reply(MsgPayload::HostToLobbyRoundTransition(1));
+    assert!(matches!(reply, MsgPayload::Empty));
+    assert_eq!(guest.get_own_num_inputs(), 1);
+}
+
+#[test]
+fn test_guest_skipping_rounds_keeps_archive_aligned() {
+    // If a guest misses an entire round, an empty archive is recorded for it so
+    // that archive indices still match round indices.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, GUEST, 60);
+    guest.rx_round_transition_and_reply(MsgPayload::HostToLobbyRoundTransition(3));
+    assert_eq!(guest.current_round(), 3);
+    assert_eq!(guest.num_archived_rounds(), 3);
+}
@@ -0,0 +1,140 @@
+use test_case::test_case;
+
+use crate::{
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    rtt::{RttConfig, RttSummary},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+#[test]
+fn test_guest_rtt_is_reliable_after_warm_up() {
+    // The first sample initializes the estimate; it is only reported as
+    // reliable once the configured number of samples has been observed.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, 1.into(), 60)
+        .with_rtt_config(RttConfig {
+            smoothing: 0.5,
+            warm_up_samples: 2,
+            ..RttConfig::default()
+        });
+    assert_eq!(guest.rtt_to_host_summary(), None);
+
+    guest.observe_rtt_ms_to_host(100.0);
+    assert_eq!(
+        guest.rtt_to_host_summary(),
+        Some(RttSummary {
+            rtt_ms: 100.0,
+            num_samples: 1,
+            is_reliable: false,
+        })
+    );
+
+    guest.observe_rtt_ms_to_host(200.0);
+    assert_eq!(
+        guest.rtt_to_host_summary(),
+        Some(RttSummary {
+            rtt_ms: 150.0,
+            num_samples: 2,
+            is_reliable: true,
+        })
+    );
+}
+
+#[test]
+fn test_host_rtt_starts_from_first_sample() {
+    // Like the guest, the host's estimate for each guest starts from the
+    // first sample instead of being biased towards 0.
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(3, 50, 5, 60);
+    host.observe_guest_rtt_ms(PlayerNum(2), 80.0);
+    host.observe_guest_rtt_ms(PlayerNum(1), 40.0);
+    for _ in 0..2 {
+        host.observe_guest_rtt_ms(PlayerNum(1), 40.0);
+    }
+
+    assert_eq!(
+        host.rtt_summaries_by_player(),
+        vec![
+            (
+                PlayerNum(1),
+                RttSummary {
+                    rtt_ms: 40.0,
+                    num_samples: 3,
+                    is_reliable: true,
+                }
+            ),
+            (
+                PlayerNum(2),
+                RttSummary {
+                    rtt_ms: 80.0,
+                    num_samples: 1,
+                    is_reliable: false,
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_guest_tracks_rtt_jitter_and_min() {
+    // The jitter is the smoothed deviation of samples from the smoothed RTT,
+    // and starts from the second sample; the min is the lowest sample.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, 1.into(), 60)
+        .with_rtt_config(RttConfig {
+            smoothing: 0.5,
+            ..RttConfig::default()
+        });
+    guest.observe_rtt_ms_to_host(100.0);
+    assert_eq!(guest.get_rtt_jitter_ms_to_host(), None);
+    assert_eq!(guest.get_min_rtt_ms_to_host(), Some(100.0));
+
+    guest.observe_rtt_ms_to_host(300.0);
+    guest.observe_rtt_ms_to_host(200.0);
+
+    assert_eq!(guest.get_rtt_ms_to_host(), Some(200.0));
+    assert_eq!(guest.get_rtt_jitter_ms_to_host(), Some(100.0));
+    assert_eq!(guest.get_min_rtt_ms_to_host(), Some(100.0));
+}
+
+#[test]
+fn test_host_tracks_rtt_jitter_and_min_per_guest() {
+    // The host keeps a separate jitter and min for each guest.
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(3, 50, 5, 60)
+        .with_rtt_config(RttConfig {
+            smoothing: 0.5,
+            ..RttConfig::default()
+        });
+    for rtt_ms in [60.0, 20.0] {
+        host.observe_guest_rtt_ms(PlayerNum(1), rtt_ms);
+    }
+    host.observe_guest_rtt_ms(PlayerNum(2), 80.0);
+
+    assert_eq!(host.get_guest_rtt_jitter_ms(PlayerNum(1)), Some(40.0));
+    assert_eq!(host.get_guest_min_rtt_ms(PlayerNum(1)), Some(20.0));
+    assert_eq!(host.get_guest_rtt_jitter_ms(PlayerNum(2)), None);
+    assert_eq!(host.get_guest_min_rtt_ms(PlayerNum(2)), Some(80.0));
+}
+
+#[test_case(0.0, 1; "no padding")]
+#[test_case(0.5, 3; "half the one way jitter")]
+fn test_guest_pads_inputs_needed_for_jitter(jitter_padding: f32, expected: u32) {
+    // A guest level with the expected host tick collects one input, unless
+    // the host tick is padded for jitter: here 200ms of jitter pads by half of
+    // 100ms one way, i.e. 3 ticks at 60 ticks per sec.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, 1.into(), 60)
+        .with_rtt_config(RttConfig {
+            smoothing: 0.5,
+            jitter_padding,
+            ..RttConfig::default()
+        });
+    guest.observe_rtt_ms_to_host(100.0);
+    guest.observe_rtt_ms_to_host(300.0);
+    guest.test_advance_host_tick(100);
+    for _ in 0..106 {
+        guest.add_own_input(PlayerInput::default());
+    }
+
+    assert_eq!(guest.get_rtt_jitter_ms_to_host(), Some(200.0));
+    assert_eq!(guest.num_inputs_needed(), expected);
+}
@@ -0,0 +1,156 @@
+use crate::{
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    rx_outcome::{RxOutcome, RxRejection},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+fn new_host() -> MultiplayerInputManager<PlayerInput, HostInputMgr> {
+    MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 5, 5, 60)
+}
+
+fn new_guest() -> MultiplayerInputManager<PlayerInput, GuestInputMgr> {
+    MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(3, GUEST, 60)
+}
+
+fn peer_inputs(start: u32, num_inputs: u32) -> MsgPayload<PlayerInput> {
+    MsgPayload::PeerInputs(PlayerInputSlice::new_test(start, num_inputs))
+}
+
+#[test]
+fn test_host_rx_guest_slice_reports_new_final_inputs() {
+    // Inputs received by the host are finalized on arrival, so each new input
+    // is counted as both new and newly finalized; overlapping resends only
+    // count what they add.
+    let mut host = new_host();
+    let outcome = host.rx_guest_input_slice(GUEST, peer_inputs(0, 4));
+    assert_eq!(
+        outcome,
+        RxOutcome {
+            new_inputs: 4,
+            newly_finalized: 4,
+            rewritten_inputs: 0,
+            rejected: None
+        }
+    );
+
+    let outcome = host.rx_guest_input_slice(GUEST, peer_inputs(2, 4));
+    assert_eq!((outcome.new_inputs, outcome.newly_finalized), (2, 2));
+
+    let outcome = host.rx_guest_input_slice(GUEST, peer_inputs(0, 6));
+    assert!(!outcome.changed());
+    assert_eq!(outcome.rejected, None);
+}
+
+#[test]
+fn test_host_rejections() {
+    // Slices that would leave a gap, messages of the wrong variant, and
+    // messages from guests that haven't acked the round are all rejected.
+    let mut host = new_host();
+    assert_eq!(
+        host.rx_guest_input_slice(GUEST, peer_inputs(3, 2)).rejected,
+        Some(RxRejection::GapAtTick(0))
+    );
+    assert_eq!(
+        host.rx_guest_input_slice(GUEST, MsgPayload::GuestToHostPing(1))
+            .rejected,
+        Some(RxRejection::UnexpectedMsg)
+    );
+
+    host.start_new_round();
+    assert_eq!(
+        host.rx_guest_input_slice(GUEST, peer_inputs(0, 2)).rejected,
+        Some(RxRejection::AwaitingRoundAck)
+    );
+    assert_eq!(
+        host.rx_finalized_ticks_observations(GUEST, MsgPayload::Empty)
+            .rejected,
+        Some(RxRejection::AwaitingRoundAck)
+    );
+}
+
+#[test]
+fn test_guest_rx_peer_slice_reports_non_final_inputs() {
+    // Inputs sent directly by peers are new but not finalized.
+    let mut guest = new_guest();
+    let outcome = guest.rx_peer_input_slice(PlayerNum(2), peer_inputs(0, 3));
+    assert_eq!((outcome.new_inputs, outcome.newly_finalized), (3, 0));
+}
+
+#[test]
+fn test_guest_rx_finalized_slice_reports_finalization() {
+    // A finalized slice from the host finalizes inputs the guest already had
+    // as non-final, and appends the rest.
+    let mut guest = new_guest();
+    guest.rx_peer_input_slice(PlayerNum(2), peer_inputs(0, 3));
+
+    let outcome = guest.rx_final_peer_input_slice_from_host(MsgPayload::HostToLobbyFinalizedSlice(
+        HostFinalizedSlice::new_test(PlayerNum(2), 5, 0, 5),
+    ));
+    assert_eq!(
+        outcome,
+        RxOutcome {
+            new_inputs: 2,
+            newly_finalized: 5,
+            rewritten_inputs: 0,
+            rejected: None
+        }
+    );
+
+    let outcome = guest.rx_final_peer_input_slice_from_host(MsgPayload::HostToLobbyFinalizedSlice(
+        HostFinalizedSlice::new_test(PlayerNum(2), 9, 7, 2),
+    ));
+    assert_eq!(outcome.rejected, Some(RxRejection::GapAtTick(5)));
+}
+
+#[test]
+fn test_guest_rx_reports_rewritten_provisional_inputs() {
+    // A finalized slice that differs from the provisional inputs a guest
+    // already held reports them as rewritten, though none are new.
+    let mut guest = new_guest();
+    let provisional = PlayerInputSlice::from_fn(0, 3, |_| PlayerInput::new_test_simple(1));
+    guest.rx_peer_input_slice(PlayerNum(2), MsgPayload::PeerInputs(provisional));
+
+    let finalized = HostFinalizedSlice {
+        player_num: PlayerNum(2),
+        host_tick: 3,
+        inputs: PlayerInputSlice::from_fn(0, 3, |i| PlayerInput::new_test_simple(1 + i as u8 % 2)),
+    };
+    let outcome = guest.rx_final_peer_input_slice_from_host(finalized.into());
+
+    assert_eq!(
+        (
+            outcome.new_inputs,
+            outcome.rewritten_inputs,
+            outcome.newly_finalized
+        ),
+        (0, 1, 3)
+    );
+    assert!(outcome.changed());
+}
+
+#[test]
+fn test_gap_rejections_are_counted_per_player() {
+    // Each slice rejected for a gap is counted against its player, and the
+    // rejection reports the sim tick of the first missing input.
+    let mut host =
+        MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(3, 5, 5, 60).with_start_tick(100);
+    host.rx_guest_input_slice(GUEST, peer_inputs(0, 2));
+    for _ in 0..3 {
+        assert_eq!(
+            host.rx_guest_input_slice(GUEST, peer_inputs(4, 2)).rejected,
+            Some(RxRejection::GapAtTick(102))
+        );
+    }
+    host.rx_guest_input_slice(PlayerNum(2), peer_inputs(1, 2));
+    host.rx_guest_input_slice(GUEST, peer_inputs(2, 2));
+
+    assert_eq!(host.num_ignored_slices(GUEST), 3);
+    assert_eq!(host.num_ignored_slices(PlayerNum(2)), 1);
+    assert_eq!(host.num_ignored_slices(PlayerNum(0)), 0);
+}
@@ -0,0 +1,87 @@
+use serde::Serialize;
+
+use crate::{
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    input_trait::SimInput,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+/// An analog trigger whose valid range is 0..=100.
+#[derive(Default, Clone, Copy, Debug, Serialize)]
+struct TriggerInput(u8);
+
+impl SimInput for TriggerInput {
+    type Bytes = u8;
+    fn to_bytes(&self) -> u8 {
+        self.0
+    }
+    fn from_bytes(bytes: u8) -> Self {
+        Self(bytes)
+    }
+    fn sanitize(&mut self) {
+        self.0 = self.0.min(100);
+    }
+}
+
+fn slice(start: u32, inputs: &[u8]) -> PlayerInputSlice<TriggerInput> {
+    PlayerInputSlice {
+        start,
+        inputs: inputs.to_vec(),
+    }
+}
+
+#[test]
+fn test_own_inputs_are_sanitized() {
+    // Out-of-range own inputs are clamped before entering the buffer, and
+    // only the changed ones are counted.
+    let mut guest = MultiplayerInputManager::<TriggerInput, GuestInputMgr>::new(2, 1.into(), 60);
+    for x in [50, 200, 100, 255] {
+        guest.add_own_input(TriggerInput(x));
+    }
+
+    let stored: Vec<u8> = (0..4)
+        .map(|tick| guest.get_peer_input_for_tick(PlayerNum(1), tick).0)
+        .collect();
+    assert_eq!(stored, vec![50, 100, 100, 100]);
+    assert_eq!(guest.num_sanitized_inputs(PlayerNum(1)), 2);
+}
+
+#[test]
+fn test_guest_slices_on_host_are_sanitized_and_counted_once() {
+    // The host clamps inputs in received slices, and doesn't count a
+    // resent input again.
+    let mut host = MultiplayerInputManager::<TriggerInput, HostInputMgr>::new(2, 50, 5, 60);
+    host.rx_guest_input_slice(PlayerNum(1), MsgPayload::PeerInputs(slice(0, &[1, 150])));
+    host.rx_guest_input_slice(
+        PlayerNum(1),
+        MsgPayload::PeerInputs(slice(0, &[1, 150, 180])),
+    );
+
+    assert_eq!(host.get_peer_input_for_tick(PlayerNum(1), 1).0, 100);
+    assert_eq!(host.get_peer_input_for_tick(PlayerNum(1), 2).0, 100);
+    assert_eq!(host.num_sanitized_inputs(PlayerNum(1)), 2);
+    assert_eq!(host.num_sanitized_inputs(PlayerNum(0)), 0);
+}
+
+#[test]
+fn test_peer_and_finalized_slices_on_guest_are_sanitized() {
+    // Both slices sent directly by peers and finalized slices from the host
+    // are clamped on the guest.
+    let mut guest = MultiplayerInputManager::<TriggerInput, GuestInputMgr>::new(3, 1.into(), 60);
+    guest.rx_peer_input_slice(PlayerNum(2), MsgPayload::PeerInputs(slice(0, &[120])));
+    guest.rx_final_peer_input_slice_from_host(MsgPayload::HostToLobbyFinalizedSlice(
+        HostFinalizedSlice {
+            player_num: PlayerNum(0),
+            host_tick: 1,
+            inputs: slice(0, &[240]),
+        },
+    ));
+
+    assert_eq!(guest.get_peer_input_for_tick(PlayerNum(2), 0).0, 100);
+    assert_eq!(guest.get_peer_input_for_tick(PlayerNum(0), 0).0, 100);
+    assert_eq!(guest.num_sanitized_inputs(PlayerNum(2)), 1);
+    assert_eq!(guest.num_sanitized_inputs(PlayerNum(0)), 1);
+}
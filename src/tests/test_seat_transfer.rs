@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use crate::{
+    events::InputMgrEvent,
+    input_messages::{MsgPayload, SeatTransfer},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    session::{OutgoingMsgs, Recipient, Session, SessionRxError},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const SEAT: PlayerNum = PlayerNum(1);
+/// The connection id the transport gives the seat's new device.
+const NEW_DEVICE: PlayerNum = PlayerNum(7);
+
+/// A 3 player host that has finalized 4 inputs from SEAT, sent by its original device.
+fn host_with_seat_history() -> Host {
+    let mut host = Host::new(3, 50, 5, 10);
+    host.rx_guest_input_slice(
+        SEAT,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 4)),
+    );
+    host
+}
+
+#[test]
+fn test_transfer_maps_the_seat_to_the_new_connection() {
+    // After a transfer, the new connection controls the seat and the old one
+    // controls nothing; other seats are unaffected.
+    let mut host = host_with_seat_history();
+    assert_eq!(host.seat_for_connection(SEAT), Some(SEAT));
+    assert_eq!(host.seat_for_connection(NEW_DEVICE), None);
+
+    let msg = host.transfer_seat(SEAT, NEW_DEVICE).unwrap();
+    let MsgPayload::HostToLobbySeatTransferred(transfer) = msg else {
+        panic!("expected a seat transfer, got {msg:?}");
+    };
+    assert_eq!(
+        transfer,
+        SeatTransfer {
+            seat: SEAT,
+            connection: NEW_DEVICE,
+            first_input: 4,
+        }
+    );
+    assert_eq!(host.connection_for_seat(SEAT), NEW_DEVICE);
+    assert_eq!(host.seat_for_connection(NEW_DEVICE), Some(SEAT));
+    assert_eq!(host.seat_for_connection(SEAT), None);
+    assert_eq!(host.seat_for_connection(PlayerNum(2)), Some(PlayerNum(2)));
+    assert!(
+        host.drain_events()
+            .contains(&InputMgrEvent::SeatTransferred {
+                seat: SEAT,
+                connection: NEW_DEVICE,
+            })
+    );
+}
+
+#[test]
+fn test_transfer_back_to_the_original_connection() {
+    // Moving a seat back to its own player num restores the original mapping.
+    let mut host = host_with_seat_history();
+    host.transfer_seat(SEAT, NEW_DEVICE).unwrap();
+    host.transfer_seat(SEAT, SEAT).unwrap();
+    assert_eq!(host.connection_for_seat(SEAT), SEAT);
+    assert_eq!(host.seat_for_connection(NEW_DEVICE), None);
+}
+
+#[test]
+fn test_invalid_transfers_are_rejected() {
+    // The host's seat and seats outside the lobby can't be moved, and a
+    // connection can't control two seats.
+    let mut host = host_with_seat_history();
+    assert!(host.transfer_seat(HOST_PLAYER_NUM, NEW_DEVICE).is_err());
+    assert!(host.transfer_seat(PlayerNum(3), NEW_DEVICE).is_err());
+    assert!(host.transfer_seat(SEAT, PlayerNum(2)).is_err());
+    assert_eq!(host.connection_for_seat(SEAT), SEAT);
+}
+
+#[test]
+fn test_new_connection_is_resent_the_seat_history() {
+    // The host forgets the seat's acks, since the new connection has received
+    // nothing, so the seat's finalized slice starts again from input 0.
+    let mut host = host_with_seat_history();
+    for guest in [SEAT, PlayerNum(2)] {
+        host.rx_finalized_ticks_observations(
+            guest,
+            MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
+                HashMap::from([(SEAT, 4)]),
+            )),
+        );
+    }
+    assert_eq!(finalized_slice_start(&host), 4);
+
+    host.transfer_seat(SEAT, NEW_DEVICE).unwrap();
+    assert_eq!(finalized_slice_start(&host), 0);
+}
+
+fn finalized_slice_start(host: &Host) -> u32 {
+    let MsgPayload::HostToLobbyFinalizedSlice(slice) = host.get_msg_finalized_slice(SEAT) else {
+        panic!("expected a finalized slice");
+    };
+    slice.inputs.start
+}
+
+#[test]
+fn test_new_device_continues_the_seat_history() {
+    // The new device pads its own inputs up to the transfer's first input, so
+    // the inputs it collects continue the seat's history.
+    let mut new_device = Guest::new(3, SEAT, 10);
+    new_device.rx_seat_transfer(MsgPayload::HostToLobbySeatTransferred(SeatTransfer {
+        seat: SEAT,
+        connection: NEW_DEVICE,
+        first_input: 4,
+    }));
+    new_device.add_own_input(PlayerInput::new_test_simple(9));
+
+    assert_eq!(new_device.get_own_num_inputs(), 5);
+    let MsgPayload::PeerInputs(slice) = new_device.get_msg_own_input_slice() else {
+        panic!("expected an own input slice");
+    };
+    assert_eq!(slice.start + slice.len(), 5);
+}
+
+/// Delivers each message from `from` to `to` as bytes, returning its replies.
+fn deliver(
+    from: PlayerNum,
+    to: &mut Session<PlayerInput>,
+    outgoing: OutgoingMsgs<PlayerInput>,
+) -> Result<OutgoingMsgs<PlayerInput>, SessionRxError> {
+    let mut replies = Vec::new();
+    for (_, msg) in outgoing {
+        replies.extend(to.rx_bytes(from, &msg.to_bytes())?);
+    }
+    Ok(replies)
+}
+
+#[test]
+fn test_sessions_route_by_connection_after_a_transfer() {
+    // Through `Session`s, the host handles the new device's messages as the
+    // seat's, rejects the old device's, and addresses replies for the seat to
+    // the new device; guests accept the seat's direct inputs only from the new
+    // device.
+    let mut host: Session<PlayerInput> = host_with_seat_history().into();
+    let mut other_guest: Session<PlayerInput> = Guest::new(3, PlayerNum(2), 10).into();
+    let mut new_device = Guest::new(3, SEAT, 10);
+
+    let transfer = host
+        .as_host_mut()
+        .unwrap()
+        .transfer_seat(SEAT, NEW_DEVICE)
+        .unwrap();
+    new_device.rx_seat_transfer(transfer.clone());
+    deliver(
+        HOST_PLAYER_NUM,
+        &mut other_guest,
+        vec![(Recipient::AllPeers, transfer)],
+    )
+    .unwrap();
+
+    new_device.add_own_input(PlayerInput::default());
+    let slice = vec![(
+        Recipient::Player(HOST_PLAYER_NUM),
+        new_device.get_msg_own_input_slice(),
+    )];
+    deliver(NEW_DEVICE, &mut host, slice.clone()).unwrap();
+    assert_eq!(host.get_peer_num_final_inputs(SEAT), 5);
+    assert!(matches!(
+        deliver(SEAT, &mut host, slice.clone()),
+        Err(SessionRxError::InvalidSender)
+    ));
+
+    let pong = deliver(
+        NEW_DEVICE,
+        &mut host,
+        vec![(
+            Recipient::Player(HOST_PLAYER_NUM),
+            new_device.get_msg_guest_ping(),
+        )],
+    )
+    .unwrap();
+    assert_eq!(pong[0].0, Recipient::Player(NEW_DEVICE));
+
+    deliver(NEW_DEVICE, &mut other_guest, slice.clone()).unwrap();
+    assert_eq!(other_guest.get_peer_num_inputs(SEAT), 5);
+    assert!(matches!(
+        deliver(SEAT, &mut other_guest, slice),
+        Err(SessionRxError::InvalidSender)
+    ));
+}
+
+#[test]
+fn test_new_connection_is_resent_the_annotations() {
+    // The host forgets which annotations the seat has acked, so the new
+    // connection is sent them all again.
+    let mut host = host_with_seat_history();
+    host.add_annotation(10, 1, vec![]).unwrap();
+    for guest in [SEAT, PlayerNum(2)] {
+        host.rx_guest_annotations_ack(guest, MsgPayload::GuestToHostAckAnnotations(1));
+    }
+    assert!(matches!(host.get_msg_annotations(), MsgPayload::Empty));
+
+    host.transfer_seat(SEAT, NEW_DEVICE).unwrap();
+    assert!(matches!(
+        host.get_msg_annotations(),
+        MsgPayload::HostToLobbyAnnotations(_)
+    ));
+}
+
+#[test]
+fn test_transfer_reconnects_a_disconnected_seat() {
+    // A seat whose old connection dropped is no longer filled with defaults
+    // once it moves to a new connection.
+    let mut host = host_with_seat_history();
+    host.player_disconnected(SEAT);
+    host.transfer_seat(SEAT, NEW_DEVICE).unwrap();
+    for _ in 0..20 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+
+    host.poll_catch_up(1.0);
+    assert_eq!(host.get_peer_num_final_inputs(SEAT), 4);
+}
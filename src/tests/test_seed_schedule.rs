@@ -0,0 +1,140 @@
+use test_case::test_case;
+
+use crate::{
+    input_messages::MsgPayload, multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr, multiplayer_input_manager_host::HostInputMgr,
+    session::Session, tests::demo_input_struct::PlayerInput, util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+/// A 2 player host whose session starts at tick 100 with seed 11, with 3 of its own inputs finalized.
+fn host() -> Host {
+    let mut host = Host::new(2, 50, 5, 60)
+        .with_start_tick(100)
+        .with_initial_seed(11);
+    for _ in 0..3 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host
+}
+
+#[test]
+fn test_initial_seed_travels_in_pre_sim_sync() {
+    // Guests adopt the host's initial seed from the PreSimSync, and it
+    // applies from the start tick (and to any tick before it).
+    let mut host = host();
+    let mut guest = Guest::new(2, GUEST, 60);
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+    for tick in [0, 100, 500] {
+        assert_eq!(guest.seed_for_tick(tick), 11);
+        assert_eq!(host.seed_for_tick(tick), 11);
+    }
+}
+
+#[test]
+fn test_seed_changes_reach_guests_until_acked() {
+    // Seed changes are resent one at a time, oldest first, until the guest
+    // acks each, and then give the same seed per tick as on the host.
+    let mut host = host();
+    let mut guest = Guest::new(2, GUEST, 60);
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+    host.set_seed(105, 22).unwrap();
+    host.set_seed(110, 33).unwrap();
+
+    for _ in 0..2 {
+        assert!(guest.rx_seed_from_host(host.get_msg_seed()));
+        assert!(!guest.rx_seed_from_host(host.get_msg_seed()));
+        host.rx_guest_seeds_ack(GUEST, guest.get_msg_ack_seeds());
+    }
+    assert!(matches!(host.get_msg_seed(), MsgPayload::Empty));
+
+    for (tick, seed) in [(104, 11), (105, 22), (109, 22), (110, 33), (900, 33)] {
+        assert_eq!(host.seed_for_tick(tick), seed);
+        assert_eq!(guest.seed_for_tick(tick), seed);
+    }
+}
+
+#[test]
+fn test_guest_ignores_out_of_order_changes() {
+    // A change that arrives before the one preceding it isn't stored, since
+    // the host resends the earlier change until it is acked.
+    let mut host = host();
+    host.set_seed(105, 22).unwrap();
+    let first = host.get_msg_seed();
+    host.rx_guest_seeds_ack(GUEST, MsgPayload::GuestToHostAckSeeds(1));
+    host.set_seed(110, 33).unwrap();
+    let second = host.get_msg_seed();
+
+    let mut guest = Guest::new(2, GUEST, 60);
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+    assert!(!guest.rx_seed_from_host(second.clone()));
+    assert!(guest.rx_seed_from_host(first));
+    assert!(guest.rx_seed_from_host(second));
+    assert_eq!(guest.get_num_seed_changes(), 2);
+}
+
+#[test_case(false; "disconnected guest")]
+#[test_case(true; "muted guest")]
+fn test_seed_changes_stop_once_live_guests_ack(muted: bool) {
+    // In a 3 player session, once the live guest has acked the seed change,
+    // the host stops resending it, even though a guest that disconnected or
+    // was muted never acked it.
+    let mut host = Host::new(3, 50, 5, 60).with_start_tick(100);
+    host.set_seed(100, 5).unwrap();
+    if muted {
+        host.mute_player(PlayerNum(2), 0);
+    } else {
+        host.player_disconnected(PlayerNum(2));
+    }
+
+    host.rx_guest_seeds_ack(GUEST, MsgPayload::GuestToHostAckSeeds(1));
+
+    assert!(matches!(host.get_msg_seed(), MsgPayload::Empty));
+}
+
+#[test_case(102; "already finalized")]
+#[test_case(99; "before start tick")]
+#[test_case(104; "before last change")]
+fn test_set_seed_rejects_invalid_ticks(tick: u32) {
+    // Seed changes can't rewrite finalized ticks, and must be made in tick
+    // order.
+    let mut host = host();
+    host.set_seed(105, 22).unwrap();
+    assert!(host.set_seed(tick, 33).is_err());
+    assert_eq!(host.get_num_seed_changes(), 1);
+}
+
+#[test]
+fn test_session_delivers_seed_changes_with_inputs() {
+    // A host driven through `Session` sends its seed changes along with its
+    // inputs, and the guest's session replies with an ack.
+    let mut host = Session::from(Host::new(2, 50, 5, 60));
+    let mut guest = Session::from(Guest::new(2, GUEST, 60));
+    host.as_host_mut().unwrap().set_seed(2, 42).unwrap();
+
+    for (_, msg) in host.add_own_input(PlayerInput::default(), 0.1) {
+        for (_, reply) in guest.rx_msg(PlayerNum(0), msg).unwrap() {
+            host.rx_msg(GUEST, reply).unwrap();
+        }
+    }
+    assert_eq!(guest.as_guest().unwrap().seed_for_tick(2), 42);
+    assert!(matches!(
+        host.as_host().unwrap().get_msg_seed(),
+        MsgPayload::Empty
+    ));
+}
+
+#[test]
+fn test_new_round_clears_seed_changes() {
+    // Seed changes belong to a round, like inputs, while the initial seed
+    // carries over.
+    let mut host = host();
+    host.set_seed(105, 22).unwrap();
+    host.start_new_round();
+    assert_eq!(host.get_num_seed_changes(), 0);
+    assert_eq!(host.seed_for_tick(105), 11);
+}
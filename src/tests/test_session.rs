@@ -0,0 +1,83 @@
+use crate::{prelude::*, tests::demo_input_struct::PlayerInput, util_types::PlayerInputSlice};
+
+fn host_and_guest() -> (Session<PlayerInput>, Session<PlayerInput>) {
+    let host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 50, 5, 10);
+    let guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, PlayerNum(1), 10);
+    (host.into(), guest.into())
+}
+
+/// Delivers each message to `to` as bytes, returning its replies.
+fn deliver(
+    from: PlayerNum,
+    to: &mut Session<PlayerInput>,
+    outgoing: OutgoingMsgs<PlayerInput>,
+) -> OutgoingMsgs<PlayerInput> {
+    let mut replies = Vec::new();
+    for (recipient, msg) in outgoing {
+        assert!(
+            matches!(recipient, Recipient::AllPeers)
+                || recipient == Recipient::Player(to.own_player_num())
+        );
+        replies.extend(to.rx_bytes(from, &msg.to_bytes()).unwrap());
+    }
+    replies
+}
+
+#[test]
+fn test_sessions_exchange_inputs_without_role_specific_code() {
+    // A host and a guest driven only through `Session` finalize each other's
+    // inputs, with the same code path for both roles.
+    let (mut host, mut guest) = host_and_guest();
+    assert!(host.is_host() && !guest.is_host());
+
+    for _ in 0..3 {
+        let from_host = host.add_own_input(PlayerInput::new_test_simple(1), 0.1);
+        let acks = deliver(PlayerNum(0), &mut guest, from_host);
+        deliver(PlayerNum(1), &mut host, acks);
+
+        let from_guest = guest.add_own_input(PlayerInput::new_test_simple(2), 0.1);
+        let finalized = deliver(PlayerNum(1), &mut host, from_guest);
+        let acks = deliver(PlayerNum(0), &mut guest, finalized);
+        deliver(PlayerNum(1), &mut host, acks);
+    }
+
+    for session in [&host, &guest] {
+        assert_eq!(session.get_own_num_inputs(), 3);
+        assert_eq!(session.get_snapshottable_sim_tick(), 3);
+        assert_eq!(
+            session.get_peer_input_for_tick(PlayerNum(1), 2),
+            PlayerInput::new_test_simple(2)
+        );
+    }
+}
+
+#[test]
+fn test_session_rx_errors() {
+    // Messages for the other role, from an invalid sender, or that can't be
+    // decoded are reported as errors rather than handled.
+    let (mut host, mut guest) = host_and_guest();
+    let rate_adjust = MsgPayload::<PlayerInput>::HostToGuestRateAdjust(10);
+    assert_eq!(
+        host.rx_msg(PlayerNum(1), rate_adjust).err(),
+        Some(SessionRxError::WrongRole)
+    );
+    let inputs = MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 1));
+    assert_eq!(
+        host.rx_msg(PlayerNum(0), inputs).err(),
+        Some(SessionRxError::InvalidSender)
+    );
+    assert_eq!(
+        guest.rx_bytes(PlayerNum(0), &[255]).err(),
+        Some(SessionRxError::Decode)
+    );
+}
+
+#[test]
+fn test_session_exposes_role_specific_managers() {
+    // The wrapped manager is available for role-specific calls.
+    let (host, mut guest) = host_and_guest();
+    assert!(host.as_guest().is_none());
+    assert!(guest.as_host_mut().is_none());
+    assert_eq!(guest.as_guest_mut().unwrap().num_inputs_needed(), 1);
+    assert_eq!(host.as_host().unwrap().input_delay_ticks(), 0);
+}
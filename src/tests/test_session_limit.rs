@@ -0,0 +1,129 @@
+use test_case::test_case;
+
+use crate::{
+    events::InputMgrEvent,
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    rx_outcome::RxRejection,
+    session_limit::{MAX_SESSION_TICKS, signed_host_tick},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+fn session_ended_events(events: Vec<InputMgrEvent>) -> Vec<u32> {
+    events
+        .into_iter()
+        .filter_map(|event| match event {
+            InputMgrEvent::SessionEnded { end_tick } => Some(end_tick),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test_case(0, 0; "zero")]
+#[test_case(MAX_SESSION_TICKS, i32::MAX; "max session tick")]
+#[test_case(MAX_SESSION_TICKS + 1, i32::MAX; "just past max session tick")]
+#[test_case(u32::MAX, i32::MAX; "u32 max")]
+fn test_signed_host_tick_saturates(host_tick: u32, expected: i32) {
+    // Host ticks convert exactly up to the session limit, and saturate rather
+    // than wrapping to negative values beyond it.
+    assert_eq!(signed_host_tick(host_tick), expected);
+}
+
+#[test]
+fn test_guest_stops_collecting_at_session_end() {
+    // Once the guest's own inputs reach the session end, it needs no more
+    // inputs, ignores any it is given, and raises a single SessionEnded event.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, 1.into(), 60)
+        .with_max_session_ticks(3);
+    assert_eq!(guest.remaining_session_ticks(), 3);
+    for _ in 0..5 {
+        guest.add_own_input(PlayerInput::default());
+    }
+
+    assert_eq!(guest.get_own_num_inputs(), 3);
+    assert!(guest.is_session_ended());
+    assert_eq!(guest.num_inputs_needed(), 0);
+    assert_eq!(session_ended_events(guest.drain_events()), vec![3]);
+}
+
+#[test]
+fn test_host_fills_only_up_to_session_end() {
+    // However much time passes, the host adds no inputs past the session end.
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 50, 5, 10)
+        .with_max_session_ticks(5);
+    host.add_host_input_to_fill_needed(PlayerInput::default(), 1.0);
+    host.add_host_input_to_fill_needed(PlayerInput::default(), 1.0);
+
+    assert_eq!(host.get_own_num_inputs(), 5);
+    assert_eq!(host.get_peer_num_final_inputs(PlayerNum(0)), 5);
+    assert_eq!(session_ended_events(host.drain_events()), vec![5]);
+}
+
+#[test]
+fn test_host_near_max_session_tick() {
+    // A solo session started just before MAX_SESSION_TICKS ends exactly
+    // there, without overflowing any tick arithmetic.
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(1, 50, 5, 10)
+        .with_start_tick(MAX_SESSION_TICKS - 2);
+    host.add_host_input_to_fill_needed(PlayerInput::default(), 1.0);
+
+    assert_eq!(host.get_own_num_inputs(), 2);
+    assert!(host.is_session_ended());
+    let ticks: Vec<u32> = host
+        .get_final_inputs_by_tick()
+        .into_iter()
+        .map(|(tick, _)| tick)
+        .collect();
+    assert_eq!(ticks, vec![MAX_SESSION_TICKS - 2, MAX_SESSION_TICKS - 1]);
+}
+
+#[test]
+fn test_max_session_ticks_is_clamped() {
+    // A configured limit can't exceed MAX_SESSION_TICKS.
+    let host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 50, 5, 10)
+        .with_max_session_ticks(u32::MAX);
+    assert_eq!(host.max_session_ticks(), MAX_SESSION_TICKS);
+}
+
+#[test]
+fn test_host_drops_guest_inputs_past_session_end() {
+    // Inputs in a guest slice past the session end are dropped, and a slice
+    // entirely past it is rejected, even if its start is near u32::MAX.
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 50, 5, 10)
+        .with_max_session_ticks(4);
+    let outcome = host.rx_guest_input_slice(
+        PlayerNum(1),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 6)),
+    );
+    assert_eq!(outcome.newly_finalized, 4);
+
+    for start in [4, u32::MAX - 1] {
+        let outcome = host.rx_guest_input_slice(
+            PlayerNum(1),
+            MsgPayload::PeerInputs(PlayerInputSlice {
+                start,
+                inputs: vec![Default::default(); 2],
+            }),
+        );
+        assert_eq!(outcome.rejected, Some(RxRejection::PastSessionEnd));
+    }
+    assert_eq!(host.get_peer_num_final_inputs(PlayerNum(1)), 4);
+}
+
+#[test]
+fn test_guest_saturates_huge_host_tick() {
+    // A finalized slice claiming a host tick beyond i32::MAX saturates the
+    // guest's signed host tick instead of wrapping it negative.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, 1.into(), 60);
+    guest.rx_final_peer_input_slice_from_host(MsgPayload::HostToLobbyFinalizedSlice(
+        HostFinalizedSlice {
+            player_num: PlayerNum(0),
+            host_tick: u32::MAX,
+            inputs: PlayerInputSlice::new_test(0, 1),
+        },
+    ));
+    assert_eq!(guest.get_host_tick(), Some(i32::MAX));
+}
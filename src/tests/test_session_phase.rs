@@ -0,0 +1,67 @@
+use crate::{
+    input_messages::{MsgPayload, PreSimSync},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    session::{Session, SessionPhase},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+
+#[test]
+fn test_pre_sim_sync_lists_every_seat() {
+    // The host fills the peer list from its own player set.
+    let mut host = Host::new(4, 50, 5, 60);
+    let MsgPayload::HostToGuestPreSimSync(PreSimSync { peers, .. }) = host.get_msg_pre_sim_sync(3)
+    else {
+        panic!("Expected PreSimSync");
+    };
+    assert_eq!(peers, vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn test_guest_adopts_session_config() {
+    // The tick rate and session length travel in the PreSimSync, so guests
+    // don't need to be configured to match the host.
+    let mut host = Host::new(2, 50, 5, 30).with_max_session_ticks(900);
+    let mut guest = Guest::new(2, GUEST, 60);
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+    assert_eq!(guest.max_session_ticks(), 900);
+    assert_eq!(guest.paced_ticks_per_sec(), 30.0);
+}
+
+#[test]
+fn test_host_phases() {
+    // The host is in the lobby until it builds the PreSimSync, counts down
+    // until it collects its first input, and has ended once its inputs reach
+    // the session's end.
+    let mut host = Host::new(2, 50, 5, 60).with_max_session_ticks(2);
+    assert_eq!(host.session_phase(), SessionPhase::Lobby);
+    host.get_msg_pre_sim_sync(3);
+    assert_eq!(host.session_phase(), SessionPhase::Countdown);
+    host.add_host_input_directly(PlayerInput::default());
+    assert_eq!(host.session_phase(), SessionPhase::Running);
+    host.add_host_input_directly(PlayerInput::default());
+    assert_eq!(host.session_phase(), SessionPhase::Ended);
+}
+
+#[test]
+fn test_guest_phases_follow_the_host_tick() {
+    // A guest counts down while the host's tick is negative, and is running
+    // once the host's tick reaches 0.
+    let mut host = Session::from(Host::new(2, 50, 5, 60));
+    let mut guest = Session::from(Guest::new(2, GUEST, 60));
+    assert_eq!(guest.session_phase(), SessionPhase::Lobby);
+    let sync = host.as_host_mut().unwrap().get_msg_pre_sim_sync(3);
+    guest.rx_msg(PlayerNum(0), sync).unwrap();
+    assert_eq!(guest.session_phase(), SessionPhase::Countdown);
+    for (_, msg) in host.add_own_input(PlayerInput::default(), 0.1) {
+        guest.rx_msg(PlayerNum(0), msg).unwrap();
+    }
+    assert_eq!(guest.session_phase(), SessionPhase::Running);
+}
@@ -0,0 +1,156 @@
+use test_case::test_case;
+
+use crate::{
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    sim_net::{SimLatency, SimNet, SimNetConfig},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const TICKS_PER_SEC: u32 = 60;
+
+fn lobby(num_players: u8, config: SimNetConfig) -> SimNet<PlayerInput> {
+    let host = Host::new(num_players, TICKS_PER_SEC, 5, TICKS_PER_SEC);
+    let guests = PlayerNum::iter_guests(num_players)
+        .map(|seat| Guest::new(num_players, seat, TICKS_PER_SEC))
+        .collect();
+    SimNet::new(host, guests, config).unwrap()
+}
+
+fn input_for(player_num: PlayerNum, index: u32) -> PlayerInput {
+    PlayerInput::new_test_simple((index as u8).wrapping_add(player_num.0))
+}
+
+#[test_case(SimLatency::Constant(3), 3.0; "constant")]
+#[test_case(SimLatency::Uniform { min: 2, max: 6 }, 4.0; "uniform")]
+#[test_case(SimLatency::Spiky { base: 2, spike: 22, spike_one_in: 10 }, 4.0; "spiky")]
+fn test_mean_latency(latency: SimLatency, expected: f32) {
+    // The mean delay of each distribution, used for the guests' RTT.
+    assert!((latency.mean_ticks() - expected).abs() < 1e-4);
+}
+
+#[test]
+fn test_reliable_network_converges() {
+    // Over a reliable network, every node finalizes nearly all of the host's
+    // inputs, and they all agree on them.
+    let mut net = lobby(3, SimNetConfig::default());
+
+    net.run(120, input_for);
+
+    assert!(net.min_snapshottable_tick() + 10 >= net.host().get_own_num_inputs());
+    assert!(net.finalized_inputs_agree());
+    assert_eq!(net.stats().num_lost, 0);
+    assert_eq!(net.stats().num_rejected, 0);
+}
+
+#[test]
+fn test_lossy_reordering_network_converges() {
+    // Losing 1 in 20 messages and reordering the rest only delays
+    // finalization; every node still ends up with the same inputs.
+    let mut net = lobby(
+        4,
+        SimNetConfig {
+            latency: SimLatency::Uniform { min: 1, max: 6 },
+            loss_one_in: Some(20),
+            reorder: true,
+            ..SimNetConfig::default()
+        },
+    );
+
+    net.run(600, input_for);
+
+    let stats = net.stats();
+    assert!(stats.num_lost > 0, "{stats:?}");
+    assert!(net.min_snapshottable_tick() + 30 >= net.host().get_own_num_inputs());
+    assert!(net.finalized_inputs_agree());
+    assert_eq!(
+        net.node(PlayerNum(3))
+            .get_peer_input_for_tick(PlayerNum(2), 100),
+        input_for(PlayerNum(2), 100)
+    );
+}
+
+#[test]
+fn test_same_seed_replays_exactly() {
+    // Runs from the same seed lose and delay the same messages.
+    let config = SimNetConfig {
+        latency: SimLatency::Spiky {
+            base: 2,
+            spike: 12,
+            spike_one_in: 8,
+        },
+        loss_one_in: Some(10),
+        ..SimNetConfig::default()
+    };
+    let mut first = lobby(3, config);
+    let mut second = lobby(3, config);
+
+    first.run(200, input_for);
+    second.run(200, input_for);
+
+    assert_eq!(first.stats(), second.stats());
+    assert_eq!(
+        first.min_snapshottable_tick(),
+        second.min_snapshottable_tick()
+    );
+}
+
+#[test]
+fn test_zero_seed_is_random() {
+    // A zero seed still loses only some messages, rather than every one.
+    let config = SimNetConfig {
+        seed: 0,
+        loss_one_in: Some(10),
+        ..SimNetConfig::default()
+    };
+    let mut net = lobby(2, config);
+
+    net.run(200, input_for);
+
+    let stats = net.stats();
+    assert!(stats.num_lost > 0 && stats.num_delivered > 0, "{stats:?}");
+}
+
+#[test]
+fn test_guests_must_fill_seats_in_order() {
+    // Each guest must be in the seat it is connected to.
+    let host = Host::new(3, TICKS_PER_SEC, 5, TICKS_PER_SEC);
+    let guests = vec![
+        Guest::new(3, PlayerNum(2), TICKS_PER_SEC),
+        Guest::new(3, PlayerNum(1), TICKS_PER_SEC),
+    ];
+
+    assert!(SimNet::new(host, guests, SimNetConfig::default()).is_err());
+}
+
+#[test]
+fn test_missing_guest_is_an_error() {
+    // A network needs a guest for every seat but the host's.
+    let host = Host::new(3, TICKS_PER_SEC, 5, TICKS_PER_SEC);
+    let guests = vec![Guest::new(3, PlayerNum(1), TICKS_PER_SEC)];
+
+    assert!(SimNet::new(host, guests, SimNetConfig::default()).is_err());
+}
+
+#[test]
+fn test_net_stats_account_for_every_message() {
+    // Every message a node sends is either received by its peer or still in
+    // flight, and both ends see traffic in their rolling rates.
+    let mut net = lobby(2, SimNetConfig::default());
+    let guest = PlayerNum(1);
+
+    net.run(60, input_for);
+
+    let host_stats = net.node(PlayerNum(0)).get_net_stats();
+    let guest_stats = net.node(guest).get_net_stats();
+    let num_sent = host_stats.total_sent().num_msgs + guest_stats.total_sent().num_msgs;
+    let num_received = host_stats.total_received().num_msgs + guest_stats.total_received().num_msgs;
+    assert_eq!(num_sent, num_received + net.num_in_flight() as u64);
+    assert!(host_stats.peer(guest).sent_bytes_per_sec > 0);
+    assert!(guest_stats.received_bytes_per_sec() > 0);
+}
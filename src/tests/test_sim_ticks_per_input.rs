@@ -0,0 +1,82 @@
+use test_case::test_case;
+
+use crate::{
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr, multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput, util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+const HOST: PlayerNum = PlayerNum(0);
+const GUEST: PlayerNum = PlayerNum(1);
+
+/// A 2 player host running its sim at 50hz from tick 100, collecting one input per 2 sim ticks, and a guest synced to it.
+fn host_and_guest() -> (Host, Guest) {
+    let mut host = Host::new(2, 50, 5, 50)
+        .with_start_tick(100)
+        .with_sim_ticks_per_input(2);
+    let mut guest = Guest::new(2, GUEST, 50);
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+    (host, guest)
+}
+
+#[test]
+fn test_host_collects_inputs_at_the_reduced_rate() {
+    // A second of sim time at 50hz needs 25 inputs, which cover 50 sim ticks.
+    let (mut host, _) = host_and_guest();
+    host.add_host_input_to_fill_needed(PlayerInput::default(), 1.0);
+    assert_eq!(host.get_own_num_inputs(), 25);
+    assert_eq!(host.get_peer_num_final_inputs(HOST), 25);
+}
+
+#[test_case(100, 0; "first tick of first input")]
+#[test_case(101, 0; "second tick of first input")]
+#[test_case(102, 1; "first tick of second input")]
+#[test_case(105, 2; "second tick of third input")]
+fn test_each_input_covers_several_sim_ticks(tick: u32, input: u8) {
+    // Tick getters map every sim tick an input covers to that input, on the
+    // host and on a guest that adopted the ratio from the PreSimSync.
+    let (mut host, mut guest) = host_and_guest();
+    assert_eq!(guest.sim_ticks_per_input(), 2);
+    for x in 0..3 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(x));
+        guest.add_own_input(PlayerInput::new_test_simple(x + 10));
+    }
+    host.rx_guest_input_slice(GUEST, guest.get_msg_own_input_slice());
+    for player in [HOST, GUEST] {
+        guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(player));
+    }
+
+    for node_input in [
+        host.get_peer_input_for_tick(HOST, tick),
+        guest.get_peer_input_for_tick(HOST, tick),
+    ] {
+        assert_eq!(node_input, PlayerInput::new_test_simple(input));
+    }
+    assert_eq!(
+        host.get_peer_input_for_tick(GUEST, tick),
+        PlayerInput::new_test_simple(input + 10)
+    );
+    assert_eq!(host.get_snapshottable_sim_tick(), 106);
+    assert_eq!(guest.get_snapshottable_sim_tick(), 106);
+}
+
+#[test]
+fn test_annotations_are_reported_once_per_input() {
+    // Per-input data is reported at the first sim tick its input covers, so
+    // a game stepping every sim tick sees it once.
+    let (mut host, _) = host_and_guest();
+    host.add_annotation(103, 7, vec![]).unwrap();
+    assert_eq!(host.annotations_for_tick(102).len(), 1);
+    assert!(host.annotations_for_tick(103).is_empty());
+}
+
+#[test]
+fn test_session_end_counts_partly_covered_inputs() {
+    // A session ending at sim tick 111 needs 6 inputs to cover its 11 ticks.
+    let (host, _) = host_and_guest();
+    let host = host.with_max_session_ticks(111);
+    assert_eq!(host.remaining_session_ticks(), 6);
+}
@@ -0,0 +1,178 @@
+//! A soak test of a full-length session, gated behind the `soak` feature and ignored by default since it steps ~430k ticks. Run it with:
+//!
+//! `cargo test --release --features soak test_soak -- --ignored`
+//!
+//! Integer overflow panics in debug builds, so running it without `--release` also checks that no counter overflows along the way.
+
+use crate::{
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    session::Session,
+    tests::{demo_input_struct::PlayerInput, lobby_sim::Network},
+    util_types::PlayerNum,
+};
+
+const TICKS_PER_SEC: u32 = 60;
+/// Two hours at 60hz.
+const SOAK_TICKS: u32 = 2 * 60 * 60 * TICKS_PER_SEC;
+const NUM_PLAYERS: u8 = 4;
+/// Each message is lost with probability 1 in `LOSS_ONE_IN`.
+const LOSS_ONE_IN: u64 = 50;
+/// Each message is delayed by `BASE_DELAY_TICKS` plus up to `JITTER_TICKS`, so messages may arrive out of order.
+const BASE_DELAY_TICKS: u32 = 2;
+const JITTER_TICKS: u32 = 3;
+/// The longest a node's snapshottable tick may go without advancing.
+const MAX_STALL_TICKS: u32 = 30;
+
+/// What the soak run observed.
+#[derive(Debug)]
+struct SoakReport {
+    /// The longest run of ticks, across all nodes, in which a node's snapshottable tick didn't advance
+    longest_stall_ticks: u32,
+    host_num_inputs: u32,
+    /// The lowest snapshottable tick across all nodes at the end of the run
+    min_snapshottable_tick: u32,
+    /// True if any node's buffers had to grow past their preallocation
+    any_reallocated: bool,
+    /// The fraction of messages the network lost
+    loss_rate: f64,
+    /// The newest host tick each guest has seen
+    guest_host_ticks: Vec<Option<i32>>,
+    num_malformed: u32,
+}
+
+fn capacity_ticks(session: &Session<PlayerInput>) -> u32 {
+    match session {
+        Session::Host(host) => host.capacity_ticks(),
+        Session::Guest(guest) => guest.capacity_ticks(),
+    }
+}
+
+fn run_soak(num_ticks: u32) -> SoakReport {
+    let preallocated_ticks = num_ticks + TICKS_PER_SEC;
+    let mut nodes: Vec<Session<PlayerInput>> = vec![
+        MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(
+            NUM_PLAYERS,
+            TICKS_PER_SEC,
+            5,
+            TICKS_PER_SEC,
+        )
+        .with_preallocated_ticks(preallocated_ticks)
+        .into(),
+    ];
+    let mean_rtt_ms =
+        2.0 * (BASE_DELAY_TICKS as f32 + JITTER_TICKS as f32 / 2.0) * 1000.0 / TICKS_PER_SEC as f32;
+    for guest_num in PlayerNum::iter_guests(NUM_PLAYERS) {
+        let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(
+            NUM_PLAYERS,
+            guest_num,
+            TICKS_PER_SEC,
+        )
+        .with_preallocated_ticks(preallocated_ticks);
+        // pings are timed with the wall clock, so the test supplies the RTT
+        guest.observe_rtt_ms_to_host(mean_rtt_ms);
+        nodes.push(guest.into());
+    }
+
+    let mut network = Network::new(
+        NUM_PLAYERS,
+        0x9e37_79b9_7f4a_7c15,
+        Some(LOSS_ONE_IN),
+        BASE_DELAY_TICKS,
+        JITTER_TICKS,
+    );
+    let preallocated_capacities: Vec<u32> = nodes.iter().map(capacity_ticks).collect();
+    let mut last_snapshottable = vec![(0, 0); nodes.len()];
+    let mut longest_stall_ticks = 0;
+    let delta = 1.0 / TICKS_PER_SEC as f32;
+
+    for now in 0..num_ticks {
+        for (player, node) in nodes.iter_mut().enumerate() {
+            let player_num = PlayerNum(player as u8);
+            let input = PlayerInput::new_test_simple((now as u8).wrapping_add(player as u8));
+            let num_inputs = match node {
+                Session::Host(_) => 1,
+                Session::Guest(guest) => guest.num_inputs_needed(),
+            };
+            for _ in 0..num_inputs {
+                let outgoing = node.add_own_input(input, delta);
+                network.send(now, player_num, outgoing);
+            }
+            node.drain_events();
+        }
+
+        network.deliver_due(now, &mut nodes);
+
+        for (node, (tick, since)) in nodes.iter().zip(last_snapshottable.iter_mut()) {
+            let snapshottable = node.get_snapshottable_sim_tick();
+            if snapshottable > *tick {
+                *tick = snapshottable;
+                *since = now;
+            } else if now > TICKS_PER_SEC {
+                longest_stall_ticks = longest_stall_ticks.max(now - *since);
+            }
+        }
+    }
+
+    SoakReport {
+        longest_stall_ticks,
+        host_num_inputs: nodes[0].get_own_num_inputs(),
+        min_snapshottable_tick: nodes
+            .iter()
+            .map(|node| node.get_snapshottable_sim_tick())
+            .min()
+            .unwrap_or(0),
+        any_reallocated: nodes
+            .iter()
+            .zip(preallocated_capacities)
+            .any(|(node, capacity)| capacity_ticks(node) != capacity),
+        loss_rate: network.num_lost as f64 / network.num_sent as f64,
+        guest_host_ticks: nodes
+            .iter()
+            .filter_map(|node| node.as_guest().map(|guest| guest.get_host_tick()))
+            .collect(),
+        num_malformed: PlayerNum::iter(NUM_PLAYERS)
+            .map(|from| {
+                nodes
+                    .iter()
+                    .map(|node| match node {
+                        Session::Host(host) => host.num_malformed_msgs_from_peer(from),
+                        Session::Guest(guest) => guest.num_malformed_msgs_from_peer(from),
+                    })
+                    .sum::<u32>()
+            })
+            .sum(),
+    }
+}
+
+#[test]
+#[ignore = "steps a two hour session; run with --ignored"]
+fn test_soak_two_hour_session() {
+    // A 4 player lobby stepped for two hours at 60hz over a network that loses
+    // 2% of messages and delays the rest by 2-5 ticks. Throughout, every
+    // node's finalization keeps advancing; at the end, the buffers still fit
+    // their preallocation (so memory stayed bounded), the host has kept pace
+    // with the frame times it was given, every node has finalized nearly all
+    // of the host's inputs, and the guests' signed host ticks haven't wrapped.
+    let report = run_soak(SOAK_TICKS);
+
+    assert!(report.loss_rate > 0.01, "{report:?}");
+    assert!(report.longest_stall_ticks <= MAX_STALL_TICKS, "{report:?}");
+    assert!(!report.any_reallocated, "{report:?}");
+    assert!(
+        report.host_num_inputs.abs_diff(SOAK_TICKS) <= 1,
+        "{report:?}"
+    );
+    assert!(
+        report.min_snapshottable_tick + MAX_STALL_TICKS >= report.host_num_inputs,
+        "{report:?}"
+    );
+    assert!(
+        report
+            .guest_host_ticks
+            .iter()
+            .all(|tick| tick.is_some_and(|tick| tick >= 0))
+    );
+    assert_eq!(report.num_malformed, 0);
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use test_case::test_case;
+
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    multiplayer_input_manager_spectator::SpectatorInputMgr,
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    rx_outcome::RxRejection,
+    session::{Recipient, Session, SessionRxError},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Spectator = MultiplayerInputManager<PlayerInput, SpectatorInputMgr>;
+type Msg = MsgPayload<PlayerInput>;
+
+const GUEST: PlayerNum = PlayerNum(1);
+const SPECTATOR: PlayerNum = PlayerNum(5);
+
+/// A 2 player host with 10 finalized inputs for both players, and `SPECTATOR` registered.
+fn host_with_spectator() -> Host {
+    let mut host = Host::new(2, 50, 5, 60);
+    for _ in 0..10 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host.rx_guest_input_slice(
+        GUEST,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 10)),
+    );
+    host.add_spectator(SPECTATOR).unwrap();
+    host
+}
+
+fn ack(acks: [u32; 2]) -> Msg {
+    MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(HashMap::from([
+        (HOST_PLAYER_NUM, acks[0]),
+        (GUEST, acks[1]),
+    ])))
+}
+
+/// The recipient, player and range of input indices of each finalized slice.
+fn ranges(msgs: &[(Recipient, Msg)]) -> Vec<(Recipient, u8, u32, u32)> {
+    msgs.iter()
+        .filter_map(|(recipient, msg)| match msg {
+            MsgPayload::HostToLobbyFinalizedSlice(slice) => Some((
+                *recipient,
+                slice.player_num.as_u8(),
+                slice.inputs.start,
+                slice.inputs.start + slice.inputs.len(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Passes the host's spectator slices to the spectator, and its acks back to the host.
+fn deliver(host: &mut Host, spectator: &mut Spectator) {
+    for (_, msg) in host.get_msgs_finalized_slices_for_spectators() {
+        for (_, reply) in spectator.handle_msg(msg).unwrap() {
+            host.rx_spectator_ack(SPECTATOR, reply);
+        }
+    }
+}
+
+#[test]
+fn test_spectator_follows_finalized_inputs() {
+    // A spectator fed its own finalized slices finalizes every player's
+    // inputs, and can snapshot as far as the host.
+    let mut host = host_with_spectator();
+    let mut spectator = Spectator::new(2, SPECTATOR, 60);
+
+    deliver(&mut host, &mut spectator);
+
+    assert_eq!(spectator.get_snapshottable_sim_tick(), 10);
+    assert_eq!(
+        spectator.get_peer_input_for_tick(GUEST, 3),
+        host.get_peer_input_for_tick(GUEST, 3)
+    );
+    assert_eq!(spectator.get_host_tick(), Some(10));
+    assert_eq!(host.spectator_num_acked(SPECTATOR, GUEST), 10);
+}
+
+#[test]
+fn test_spectator_slices_start_at_its_ack() {
+    // Once a spectator has acked some inputs, its next slices only carry the
+    // inputs finalized since, and players it is current on are skipped.
+    let mut host = host_with_spectator();
+    host.rx_spectator_ack(SPECTATOR, ack([10, 4]));
+
+    let msgs = host.get_msgs_finalized_slices_for_spectators();
+
+    assert_eq!(
+        ranges(&msgs),
+        vec![(Recipient::Player(SPECTATOR), 1, 4, 10)]
+    );
+}
+
+#[test]
+fn test_spectator_acks_dont_hold_back_broadcasts() {
+    // A spectator that has acked nothing doesn't pull the broadcast slices
+    // back to the start; only the guest's acks do.
+    let mut host = host_with_spectator();
+    host.rx_finalized_ticks_observations(GUEST, ack([8, 8]));
+
+    let broadcast = host.get_msg_finalized_slice(HOST_PLAYER_NUM);
+
+    assert_eq!(
+        ranges(&[(Recipient::AllPeers, broadcast)]),
+        vec![(Recipient::AllPeers, 0, 8, 10)]
+    );
+    assert_eq!(host.spectator_num_acked(SPECTATOR, HOST_PLAYER_NUM), 0);
+}
+
+#[test]
+fn test_spectator_acks_dont_hold_back_retention() {
+    // Inputs acked by every guest are dropped even if a spectator hasn't
+    // acked them, and the spectator's slices start at the oldest input left.
+    let mut host = Host::new(2, 50, 5, 60).with_max_retained_inputs(2);
+    host.add_spectator(SPECTATOR).unwrap();
+    for _ in 0..10 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host.rx_guest_input_slice(
+        GUEST,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 10)),
+    );
+    host.rx_finalized_ticks_observations(GUEST, ack([10, 10]));
+
+    let msgs = host.get_msgs_finalized_slices_for_spectators();
+
+    assert_eq!(host.first_retained_input(HOST_PLAYER_NUM), 8);
+    assert_eq!(ranges(&msgs)[0], (Recipient::Player(SPECTATOR), 0, 8, 10));
+}
+
+#[test]
+fn test_spectator_ack_is_clamped_to_finalized() {
+    // A spectator can't ack inputs the host hasn't finalized.
+    let mut host = host_with_spectator();
+
+    host.rx_spectator_ack(SPECTATOR, ack([50, 3]));
+
+    assert_eq!(host.spectator_num_acked(SPECTATOR, HOST_PLAYER_NUM), 10);
+    assert_eq!(host.spectator_num_acked(SPECTATOR, GUEST), 3);
+}
+
+#[test_case(HOST_PLAYER_NUM; "the host")]
+#[test_case(GUEST; "a guest")]
+fn test_seats_cant_spectate(connection: PlayerNum) {
+    // Connections that control a seat can't be registered as spectators.
+    let mut host = Host::new(2, 50, 5, 60);
+
+    assert!(host.add_spectator(connection).is_err());
+    assert!(host.spectators().is_empty());
+}
+
+#[test]
+fn test_removed_spectator_gets_nothing() {
+    // Once removed, a spectator is sent no slices and its acks are rejected.
+    let mut host = host_with_spectator();
+
+    assert!(host.remove_spectator(SPECTATOR));
+    assert!(!host.remove_spectator(SPECTATOR));
+    assert!(host.get_msgs_finalized_slices_for_spectators().is_empty());
+    assert_eq!(
+        host.rx_spectator_ack(SPECTATOR, ack([1, 1])).rejected,
+        Some(RxRejection::UnknownSpectator)
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_spectator_connection_cant_be_a_seat() {
+    // A spectator's connection must lie past the session's seats.
+    Spectator::new(2, GUEST, 60);
+}
+
+#[test_case(MsgPayload::HostToGuestPong(1); "pong")]
+#[test_case(MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 3)); "peer inputs")]
+fn test_spectator_rejects_guest_msgs(msg: Msg) {
+    // Messages about collecting or pacing inputs aren't for spectators.
+    let mut spectator = Spectator::new(2, SPECTATOR, 60);
+
+    assert_eq!(
+        spectator.handle_msg(msg).err(),
+        Some(SessionRxError::WrongRole)
+    );
+}
+
+#[test]
+fn test_spectator_adopts_pre_sim_sync() {
+    // A spectator takes the session's start tick from the host's PreSimSync,
+    // and sends no reply.
+    let mut host = Host::new(2, 50, 5, 60).with_start_tick(30);
+    let mut spectator = Spectator::new(2, SPECTATOR, 60);
+
+    let replies = spectator.handle_msg(host.get_msg_pre_sim_sync(3)).unwrap();
+
+    assert!(replies.is_empty());
+    assert_eq!(spectator.start_tick(), 30);
+    assert_eq!(spectator.get_host_tick(), Some(-3));
+}
+
+#[test]
+fn test_session_routes_spectator_acks() {
+    // A host session takes a spectator's acks without treating it as a seat,
+    // rejects other messages from it, and still rejects unknown connections.
+    let mut host: Session<PlayerInput> = host_with_spectator().into();
+
+    assert!(host.rx_msg(SPECTATOR, ack([10, 6])).unwrap().is_empty());
+    assert_eq!(
+        host.rx_msg(SPECTATOR, MsgPayload::HostToGuestPong(1)).err(),
+        Some(SessionRxError::WrongRole)
+    );
+    assert_eq!(
+        host.rx_msg(PlayerNum(6), ack([10, 6])).err(),
+        Some(SessionRxError::InvalidSender)
+    );
+    assert_eq!(
+        ranges(&host.get_finalized_slices_for_spectators()),
+        vec![(Recipient::Player(SPECTATOR), 1, 6, 10)]
+    );
+}
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use test_case::test_case;
+
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    rx_outcome::RxRejection,
+    session::{Recipient, Session},
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+type Msg = MsgPayload<PlayerInput>;
+
+/// The couch guest's own seat, whose connection reaches the host.
+const COUCH: PlayerNum = PlayerNum(1);
+/// A second player on the couch guest's device.
+const COUCH_2: PlayerNum = PlayerNum(2);
+/// An online guest on its own device.
+const ONLINE: PlayerNum = PlayerNum(3);
+
+/// A couch guest with both couch seats local, with `num_inputs` inputs for each.
+fn couch_guest(num_inputs: u8) -> Guest {
+    let mut guest = Guest::new_with_local_players(4, vec![COUCH, COUCH_2], 60);
+    for x in 0..num_inputs {
+        guest
+            .add_own_input_for(COUCH, PlayerInput::new_test_simple(x))
+            .unwrap();
+        guest
+            .add_own_input_for(COUCH_2, PlayerInput::new_test_simple(x + 10))
+            .unwrap();
+    }
+    guest
+}
+
+/// A 4 player host that lets the couch guest's connection control `COUCH_2`.
+fn host_with_couch() -> Host {
+    let mut host = Host::new(4, 50, 5, 60);
+    host.add_local_seat(COUCH_2, COUCH).unwrap();
+    host
+}
+
+/// The player and range of input indices of each slice in a bundle.
+fn bundle_ranges(msg: &Msg) -> Vec<(u8, u32, u32)> {
+    let MsgPayload::GuestToHostLocalInputs(bundle) = msg else {
+        panic!("expected a bundle of local inputs, got {msg:?}");
+    };
+    bundle
+        .iter()
+        .map(|local| {
+            (
+                local.player_num.as_u8(),
+                local.inputs.start,
+                local.inputs.start + local.inputs.len(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_guest_bundles_every_local_player() {
+    // A guest with several local players sends one message carrying a slice
+    // for each of them, own seat first.
+    let mut guest = couch_guest(3);
+    guest
+        .add_own_input_for(COUCH_2, PlayerInput::default())
+        .unwrap();
+
+    let msg = guest.get_msg_own_input_slice();
+
+    assert_eq!(bundle_ranges(&msg), vec![(1, 0, 3), (2, 0, 4)]);
+    assert_eq!(guest.local_player_nums(), vec![COUCH, COUCH_2]);
+    assert_eq!(
+        Msg::from_bytes(&guest.get_msg_bytes_own_input_slice())
+            .map(|msg| bundle_ranges(&msg))
+            .unwrap(),
+        bundle_ranges(&msg)
+    );
+}
+
+#[test]
+fn test_single_local_player_sends_a_plain_slice() {
+    // With only its own seat, a guest sends the usual input slice.
+    let mut guest = Guest::new_with_local_players(4, vec![COUCH], 60);
+    guest
+        .add_own_input_for(COUCH, PlayerInput::default())
+        .unwrap();
+
+    assert!(matches!(
+        guest.get_msg_own_input_slice(),
+        MsgPayload::PeerInputs(_)
+    ));
+}
+
+#[test_case(HOST_PLAYER_NUM; "the host")]
+#[test_case(ONLINE; "an online guest")]
+fn test_inputs_for_other_players_are_rejected(player_num: PlayerNum) {
+    // A guest only collects inputs for its local players.
+    let mut guest = couch_guest(0);
+
+    assert!(
+        guest
+            .add_own_input_for(player_num, PlayerInput::default())
+            .is_err()
+    );
+    assert_eq!(guest.get_peer_num_inputs(player_num), 0);
+}
+
+#[test_case(vec![]; "no local players")]
+#[test_case(vec![HOST_PLAYER_NUM, COUCH]; "the host's seat")]
+#[test_case(vec![COUCH, PlayerNum(4)]; "a seat outside the session")]
+#[test_case(vec![COUCH, COUCH_2, COUCH]; "a seat listed twice")]
+#[should_panic]
+fn test_invalid_local_players_panic(own_player_nums: Vec<PlayerNum>) {
+    // Local players must be distinct guest seats of the session.
+    Guest::new_with_local_players(4, own_player_nums, 60);
+}
+
+#[test]
+fn test_host_finalizes_bundled_inputs() {
+    // The host finalizes each local player's slice from the bundle, and
+    // replies with the finalized slices for both couch seats.
+    let mut host = host_with_couch();
+    let guest = couch_guest(3);
+
+    let replies = host
+        .handle_msg(COUCH, guest.get_msg_own_input_slice())
+        .unwrap();
+
+    assert_eq!(host.get_peer_num_final_inputs(COUCH), 3);
+    assert_eq!(host.get_peer_num_final_inputs(COUCH_2), 3);
+    let finalized_players: Vec<u8> = replies
+        .iter()
+        .filter_map(|(_, msg)| match msg {
+            MsgPayload::HostToLobbyFinalizedSlice(slice) => Some(slice.player_num.as_u8()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(finalized_players, vec![1, 2]);
+}
+
+#[test]
+fn test_bundled_slices_for_other_seats_are_rejected() {
+    // Slices for seats the sender's connection doesn't control are rejected,
+    // while its own seat's slice is still accepted.
+    let mut host = Host::new(4, 50, 5, 60);
+
+    let outcomes = host.rx_guest_local_inputs(COUCH, couch_guest(2).get_msg_own_input_slice());
+
+    assert_eq!(outcomes[0].1.rejected, None);
+    assert_eq!(outcomes[1].1.rejected, Some(RxRejection::NotLocalSeat));
+    assert_eq!(host.get_peer_num_final_inputs(COUCH_2), 0);
+}
+
+#[test]
+fn test_guest_finalizes_its_local_players() {
+    // The host's finalized slices for the couch seats finalize both of them
+    // on the couch guest.
+    let mut host = host_with_couch();
+    let mut guest = couch_guest(3);
+
+    for (_, msg) in host
+        .handle_msg(COUCH, guest.get_msg_own_input_slice())
+        .unwrap()
+    {
+        guest.handle_msg(HOST_PLAYER_NUM, msg).unwrap();
+    }
+
+    assert_eq!(guest.get_peer_num_final_inputs(COUCH), 3);
+    assert_eq!(guest.get_peer_num_final_inputs(COUCH_2), 3);
+}
+
+#[test]
+fn test_guest_ack_covers_its_local_seats() {
+    // A couch guest's ack counts for every seat its connection controls, so
+    // the extra seat doesn't hold the broadcast start back.
+    let mut host = Host::new(3, 50, 5, 60);
+    host.add_local_seat(COUCH_2, COUCH).unwrap();
+    for _ in 0..3 {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+
+    host.rx_finalized_ticks_observations(
+        COUCH,
+        MsgPayload::GuestToHostAckFinalization(PeerwiseFinalizedInputsSeen::new_test(
+            HashMap::from([(HOST_PLAYER_NUM, 3)]),
+        )),
+    );
+
+    let MsgPayload::HostToLobbyFinalizedSlice(slice) =
+        host.get_msg_finalized_slice(HOST_PLAYER_NUM)
+    else {
+        panic!("expected a finalized slice");
+    };
+    assert_eq!(slice.inputs.start, 3);
+}
+
+#[test]
+fn test_connection_with_local_seats_maps_to_its_own_seat() {
+    // A connection controlling several seats is handled as its own seat, and
+    // the extra seat's own connection controls nothing.
+    let host = host_with_couch();
+
+    assert_eq!(host.seat_for_connection(COUCH), Some(COUCH));
+    assert_eq!(host.seat_for_connection(COUCH_2), None);
+    assert_eq!(host.seats_sharing_connection(COUCH_2), vec![COUCH, COUCH_2]);
+    assert_eq!(host.seats_sharing_connection(ONLINE), vec![ONLINE]);
+}
+
+#[test_case(HOST_PLAYER_NUM, COUCH; "the host's seat")]
+#[test_case(COUCH_2, HOST_PLAYER_NUM; "onto the host")]
+#[test_case(PlayerNum(4), COUCH; "a seat outside the session")]
+#[test_case(COUCH_2, COUCH; "a seat already local")]
+fn test_invalid_local_seats_are_rejected(seat: PlayerNum, guest: PlayerNum) {
+    // Only guest seats can be made local to a guest, and only once.
+    let mut host = host_with_couch();
+
+    assert!(host.add_local_seat(seat, guest).is_err());
+}
+
+#[test]
+fn test_session_broadcasts_once_per_connection() {
+    // A host session sends a connection controlling several seats one copy of
+    // each broadcast.
+    let mut host: Session<PlayerInput> = host_with_couch().into();
+
+    let sent = host.to_sequenced_bytes(vec![(
+        Recipient::AllPeers,
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 1)),
+    )]);
+
+    let connections: Vec<PlayerNum> = sent.into_iter().map(|(connection, _)| connection).collect();
+    assert_eq!(connections, vec![COUCH, ONLINE]);
+}
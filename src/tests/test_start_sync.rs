@@ -0,0 +1,250 @@
+use test_case::test_case;
+
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::{HOST_PLAYER_NUM, HostInputMgr},
+    session::{Recipient, Session, SessionPhase},
+    start_sync::{ScheduledStart, StartAck},
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+// a power of two, so that tick and second conversions are exact
+const TICKS_PER_SEC: u32 = 64;
+const TICK_SEC: f32 = 1.0 / TICKS_PER_SEC as f32;
+const GUEST_1: PlayerNum = PlayerNum(1);
+const GUEST_2: PlayerNum = PlayerNum(2);
+
+fn ack(host: &mut Host, guest: PlayerNum, proposal_num: u32, ticks_until_start: i32) {
+    host.rx_start_ack(
+        guest,
+        MsgPayload::GuestToHostStartAck(StartAck {
+            proposal_num,
+            ticks_until_start,
+        }),
+    );
+}
+
+fn scheduled_start(msg: MsgPayload<PlayerInput>) -> ScheduledStart {
+    match msg {
+        MsgPayload::HostToLobbyStartProposal(start)
+        | MsgPayload::HostToLobbyStartConfirmed(start) => start,
+        msg => panic!("expected a scheduled start, got {msg:?}"),
+    }
+}
+
+#[test]
+fn test_proposal_starts_countdown_ticks_after_the_lobby_tick() {
+    // A proposal schedules the start the given number of lobby ticks after
+    // the host's current lobby tick, and moves the host into the countdown.
+    let mut host = Host::new(3, 50, 5, TICKS_PER_SEC);
+    host.advance_lobby_time(10.0 * TICK_SEC);
+    assert_eq!(host.session_phase(), SessionPhase::Lobby);
+
+    let proposal = scheduled_start(host.propose_start(64));
+
+    assert_eq!(
+        proposal,
+        ScheduledStart {
+            proposal_num: 0,
+            host_lobby_tick: 10,
+            start_lobby_tick: 74,
+        }
+    );
+    assert_eq!(host.session_phase(), SessionPhase::Countdown);
+    assert_eq!(host.ticks_until_start(), None);
+}
+
+#[test]
+fn test_host_confirms_only_once_every_guest_has_acked() {
+    // The start is confirmed only once every guest has acked the latest
+    // proposal; until then the confirmation is empty.
+    let mut host = Host::new(3, 50, 5, TICKS_PER_SEC);
+    host.propose_start(64);
+    ack(&mut host, GUEST_1, 0, 60);
+    assert!(matches!(host.get_msg_start_confirmed(), MsgPayload::Empty));
+
+    ack(&mut host, GUEST_2, 0, 62);
+    let confirmed = scheduled_start(host.get_msg_start_confirmed());
+    assert_eq!(confirmed.start_lobby_tick, 64);
+    assert_eq!(host.ticks_until_start(), Some(64));
+}
+
+#[test]
+fn test_host_confirms_without_a_disconnected_guest() {
+    // A guest that disconnects before acking isn't waited for: the start is
+    // confirmed once the remaining guests have acked.
+    let mut host = Host::new(3, 50, 5, TICKS_PER_SEC);
+    host.propose_start(64);
+    ack(&mut host, GUEST_1, 0, 60);
+    host.player_disconnected(GUEST_2);
+
+    let confirmed = scheduled_start(host.get_msg_start_confirmed());
+    assert_eq!(confirmed.start_lobby_tick, 64);
+}
+
+#[test]
+fn test_acks_of_an_earlier_proposal_are_ignored() {
+    // Proposing again forgets the acks of the old proposal, and acks that
+    // still carry the old proposal number don't count toward the new one.
+    let mut host = Host::new(2, 50, 5, TICKS_PER_SEC);
+    host.propose_start(64);
+    ack(&mut host, GUEST_1, 0, 60);
+
+    let proposal = scheduled_start(host.propose_start(32));
+    assert_eq!(proposal.proposal_num, 1);
+    assert!(matches!(host.get_msg_start_confirmed(), MsgPayload::Empty));
+    ack(&mut host, GUEST_1, 0, 60);
+    assert!(matches!(host.get_msg_start_confirmed(), MsgPayload::Empty));
+
+    ack(&mut host, GUEST_1, 1, 28);
+    assert_eq!(
+        scheduled_start(host.get_msg_start_confirmed()).start_lobby_tick,
+        32
+    );
+}
+
+#[test_case(60, 10, 64; "guest in time keeps the proposed start")]
+#[test_case(-6, 10, 81; "late guest pushes the start back")]
+#[test_case(60, 70, 75; "confirming after the proposed start pushes it back")]
+fn test_confirmed_start_leaves_time_for_confirmation_to_arrive(
+    ticks_until_start: i32,
+    confirm_at_lobby_tick: u32,
+    expected_start: u32,
+) {
+    // The confirmed start is the proposed one unless a guest's transit time,
+    // inferred from its ack, means the confirmation would reach it too late;
+    // then the start is pushed back to just after its estimated arrival.
+    let mut host = Host::new(2, 50, 5, TICKS_PER_SEC);
+    host.propose_start(64);
+    ack(&mut host, GUEST_1, 0, ticks_until_start);
+    host.advance_lobby_time(confirm_at_lobby_tick as f32 * TICK_SEC);
+
+    let confirmed = scheduled_start(host.get_msg_start_confirmed());
+
+    assert_eq!(confirmed.host_lobby_tick, confirm_at_lobby_tick);
+    assert_eq!(confirmed.start_lobby_tick, expected_start);
+}
+
+#[test]
+fn test_confirmed_start_is_fixed() {
+    // Once confirmed, the start doesn't move: later confirmations repeat it
+    // with the current lobby tick, and further proposals return the
+    // confirmation instead.
+    let mut host = Host::new(2, 50, 5, TICKS_PER_SEC);
+    host.propose_start(64);
+    ack(&mut host, GUEST_1, 0, 60);
+    host.get_msg_start_confirmed();
+    host.advance_lobby_time(20.0 * TICK_SEC);
+
+    let repeated = host.propose_start(200);
+
+    assert!(matches!(repeated, MsgPayload::HostToLobbyStartConfirmed(_)));
+    let repeated = scheduled_start(repeated);
+    assert_eq!(repeated.host_lobby_tick, 20);
+    assert_eq!(repeated.start_lobby_tick, 64);
+    assert_eq!(host.ticks_until_start(), Some(44));
+}
+
+#[test]
+fn test_guest_acks_with_rtt_adjusted_estimate() {
+    // A guest estimates the ticks left at the proposal's arrival as the
+    // proposed countdown less half its RTT to the host.
+    let mut guest = Guest::new(2, GUEST_1, TICKS_PER_SEC);
+    guest.observe_rtt_ms_to_host(125.0);
+
+    let reply =
+        guest.rx_start_proposal_and_reply(MsgPayload::HostToLobbyStartProposal(ScheduledStart {
+            proposal_num: 3,
+            host_lobby_tick: 100,
+            start_lobby_tick: 164,
+        }));
+
+    match reply {
+        MsgPayload::GuestToHostStartAck(ack) => assert_eq!(
+            ack,
+            StartAck {
+                proposal_num: 3,
+                ticks_until_start: 60,
+            }
+        ),
+        msg => panic!("expected a start ack, got {msg:?}"),
+    }
+    assert_eq!(guest.ticks_until_start(), None);
+}
+
+#[test]
+fn test_guest_counts_down_to_confirmed_start() {
+    // On confirmation, a guest re-estimates the ticks left from the
+    // confirmation's lobby tick, adopts them as its negative host tick, and
+    // counts down with its lobby clock.
+    let mut guest = Guest::new(2, GUEST_1, TICKS_PER_SEC);
+    guest.observe_rtt_ms_to_host(125.0);
+    assert_eq!(guest.session_phase(), SessionPhase::Lobby);
+
+    guest.rx_start_confirmed(MsgPayload::HostToLobbyStartConfirmed(ScheduledStart {
+        proposal_num: 0,
+        host_lobby_tick: 20,
+        start_lobby_tick: 64,
+    }));
+
+    assert_eq!(guest.ticks_until_start(), Some(40));
+    assert_eq!(guest.get_host_tick(), Some(-40));
+    assert_eq!(guest.session_phase(), SessionPhase::Countdown);
+
+    guest.advance_lobby_time(30.0 * TICK_SEC);
+    assert_eq!(guest.ticks_until_start(), Some(10));
+    guest.advance_lobby_time(12.0 * TICK_SEC);
+    assert_eq!(guest.ticks_until_start(), Some(-2));
+}
+
+#[test]
+fn test_guest_ignores_proposals_after_confirmation() {
+    // A proposal arriving after the start has been confirmed (e.g. one that
+    // was delayed) doesn't reset the guest's countdown.
+    let mut guest = Guest::new(2, GUEST_1, TICKS_PER_SEC);
+    guest.rx_start_confirmed(MsgPayload::HostToLobbyStartConfirmed(ScheduledStart {
+        proposal_num: 0,
+        host_lobby_tick: 0,
+        start_lobby_tick: 64,
+    }));
+
+    let reply =
+        guest.rx_start_proposal_and_reply(MsgPayload::HostToLobbyStartProposal(ScheduledStart {
+            proposal_num: 1,
+            host_lobby_tick: 0,
+            start_lobby_tick: 128,
+        }));
+
+    assert!(matches!(reply, MsgPayload::Empty));
+    assert_eq!(guest.ticks_until_start(), Some(64));
+}
+
+#[test]
+fn test_start_agreement_via_sessions() {
+    // Through `Session`s, a guest acks the host's proposal, the host
+    // broadcasts its confirmation in reply to the last ack, and both sides
+    // count down to the same start.
+    let mut host: Session<PlayerInput> = Host::new(2, 50, 5, TICKS_PER_SEC).into();
+    let mut guest: Session<PlayerInput> = Guest::new(2, GUEST_1, TICKS_PER_SEC).into();
+
+    let proposal = host.as_host_mut().unwrap().propose_start(64);
+    let acks = guest.rx_msg(HOST_PLAYER_NUM, proposal).unwrap();
+    assert_eq!(acks.len(), 1);
+    assert_eq!(acks[0].0, Recipient::Player(HOST_PLAYER_NUM));
+
+    let (_, ack) = acks.into_iter().next().unwrap();
+    let confirmations = host.rx_msg(GUEST_1, ack).unwrap();
+    assert_eq!(confirmations.len(), 1);
+    assert_eq!(confirmations[0].0, Recipient::AllPeers);
+
+    let (_, confirmation) = confirmations.into_iter().next().unwrap();
+    guest.rx_msg(HOST_PLAYER_NUM, confirmation).unwrap();
+    assert_eq!(host.as_host().unwrap().ticks_until_start(), Some(64));
+    assert_eq!(guest.as_guest().unwrap().ticks_until_start(), Some(64));
+}
@@ -0,0 +1,155 @@
+use crate::{
+    input_buffer::{InputConfidence, InputStatus},
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::PlayerNum,
+};
+
+const START_TICK: u32 = 72_000;
+const HOST: PlayerNum = PlayerNum(0);
+const GUEST: PlayerNum = PlayerNum(1);
+
+fn host_and_guest() -> (
+    MultiplayerInputManager<PlayerInput, HostInputMgr>,
+    MultiplayerInputManager<PlayerInput, GuestInputMgr>,
+) {
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 5, 5, 60)
+        .with_start_tick(START_TICK);
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, GUEST, 60);
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+    (host, guest)
+}
+
+#[test]
+fn test_guest_adopts_start_tick_from_pre_sim_sync() {
+    // The host's start tick and player list travel in the PreSimSync message.
+    let (mut host, guest) = host_and_guest();
+    assert_eq!(guest.start_tick(), START_TICK);
+    assert_eq!(guest.get_snapshottable_sim_tick(), START_TICK);
+    let MsgPayload::HostToGuestPreSimSync(sync) = host.get_msg_pre_sim_sync(3) else {
+        panic!("Expected PreSimSync");
+    };
+    assert_eq!(sync.peers, vec![0, 1]);
+}
+
+#[test]
+fn test_exchange_with_nonzero_start_tick() {
+    // Slices and acks count from the start tick, so a full exchange works
+    // unchanged, while the tick-facing API reports absolute sim ticks.
+    let (mut host, mut guest) = host_and_guest();
+    for x in 0..4 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(x));
+        guest.add_own_input(PlayerInput::new_test_simple(10 + x));
+    }
+    host.rx_guest_input_slice(GUEST, guest.get_msg_own_input_slice());
+    guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(HOST));
+    guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(GUEST));
+
+    assert_eq!(host.get_snapshottable_sim_tick(), START_TICK + 4);
+    assert_eq!(guest.get_snapshottable_sim_tick(), START_TICK + 4);
+    assert!(guest.diff_against(&host).is_converged());
+    assert_eq!(
+        guest.get_peer_input_for_tick(HOST, START_TICK + 2),
+        PlayerInput::new_test_simple(2)
+    );
+    assert_eq!(
+        host.get_inputs_map_for_tick(START_TICK + 3)[&1],
+        PlayerInput::new_test_simple(13)
+    );
+
+    let final_ticks: Vec<u32> = guest
+        .get_final_inputs_by_tick()
+        .into_iter()
+        .map(|(tick, _)| tick)
+        .collect();
+    assert_eq!(
+        final_ticks,
+        (START_TICK..START_TICK + 4).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_ticks_before_start_are_finalized_defaults() {
+    // Ticks before the start tick aren't part of the session's timeline; they
+    // read as finalized default inputs.
+    let (_, guest) = host_and_guest();
+    assert_eq!(
+        guest.get_peer_input_for_tick(HOST, START_TICK - 1),
+        PlayerInput::default()
+    );
+    assert_eq!(
+        guest.get_input_statuses(START_TICK - 1),
+        vec![
+            (HOST, InputStatus::Finalized { num_finalized: 0 }),
+            (GUEST, InputStatus::Finalized { num_finalized: 0 })
+        ]
+    );
+    assert_eq!(
+        guest.get_input_statuses(START_TICK)[0],
+        (
+            HOST,
+            InputStatus::NotReceived {
+                num_finalized: 0,
+                predicted: false
+            }
+        )
+    );
+    assert!(
+        guest
+            .get_inputs_and_finalization_status(0)
+            .iter()
+            .all(|(_, _, is_final)| *is_final)
+    );
+}
+
+#[test]
+fn test_guest_keeps_start_tick_once_started() {
+    // A PreSimSync arriving after inputs have been collected can't move the
+    // guest's timeline.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, GUEST, 60);
+    guest.add_own_input(PlayerInput::default());
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 5, 5, 60)
+        .with_start_tick(START_TICK);
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+    assert_eq!(guest.start_tick(), 0);
+}
+
+#[test]
+fn test_button_history_stops_at_start_tick() {
+    // Button history doesn't reach back before the start of the timeline.
+    let (_, mut guest) = host_and_guest();
+    guest.add_own_input(PlayerInput::new_test_simple(1));
+    guest.add_own_input(PlayerInput::new_test_simple(1));
+    let history: Vec<bool> = guest.button_history(GUEST, |i| i.jump, 5).collect();
+    assert_eq!(history, vec![true, true]);
+}
+
+#[test]
+fn test_input_confidence_counts_from_the_start_tick() {
+    // Confidence is looked up by absolute sim tick: ticks before the start
+    // are finalized defaults, and predictions age from the last input.
+    let (mut host, mut guest) = host_and_guest();
+    for x in 0..4 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(x));
+    }
+    guest.rx_final_peer_input_slice_from_host(host.get_msg_finalized_slice(HOST));
+
+    assert_eq!(
+        guest.get_input_with_confidence(HOST, START_TICK - 1),
+        (PlayerInput::default(), InputConfidence::Finalized)
+    );
+    assert_eq!(
+        guest.get_input_with_confidence(HOST, START_TICK + 2),
+        (PlayerInput::new_test_simple(2), InputConfidence::Finalized)
+    );
+    assert_eq!(
+        guest.get_input_with_confidence(HOST, START_TICK + 5),
+        (
+            PlayerInput::new_test_simple(3),
+            InputConfidence::PredictedLOCF { age: 2 }
+        )
+    );
+}
@@ -0,0 +1,96 @@
+use test_case::test_case;
+
+use crate::{
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr, multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput, tick_consumption::TickConsumptionError,
+    util_types::PlayerNum,
+};
+
+type Host = MultiplayerInputManager<PlayerInput, HostInputMgr>;
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+/// A 2 player host with `num_inputs` of its own inputs.
+fn host_with_inputs(num_inputs: u32) -> Host {
+    let mut host = Host::new(2, 50, 5, 60);
+    for _ in 0..num_inputs {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host
+}
+
+#[test]
+fn test_ticks_consumed_in_order() {
+    // Consuming each collected tick in turn succeeds, and the next
+    // unconsumed tick follows the last one consumed.
+    let mut host = host_with_inputs(3);
+    assert_eq!(host.next_unconsumed_tick(), 0);
+
+    for tick in 0..3 {
+        assert_eq!(host.mark_tick_consumed(tick), Ok(()));
+        assert_eq!(host.next_unconsumed_tick(), tick + 1);
+    }
+}
+
+#[test_case(1, TickConsumptionError::Repeated { tick: 1, next: 2 }; "repeated tick")]
+#[test_case(3, TickConsumptionError::Skipped { tick: 3, next: 2 }; "skipped tick")]
+fn test_out_of_order_ticks_are_refused(tick: u32, expected: TickConsumptionError) {
+    // Consuming a tick again, or jumping past the next unconsumed tick, is
+    // refused without changing what has been consumed.
+    let mut host = host_with_inputs(5);
+    host.mark_tick_consumed(0).unwrap();
+    host.mark_tick_consumed(1).unwrap();
+
+    assert_eq!(host.mark_tick_consumed(tick), Err(expected));
+    assert_eq!(host.next_unconsumed_tick(), 2);
+}
+
+#[test]
+fn test_uncollected_tick_is_refused() {
+    // A tick can't be consumed before this node has collected its own input
+    // for it.
+    let mut guest = Guest::new(2, PlayerNum(1), 60);
+    guest.add_own_input(PlayerInput::default());
+    guest.mark_tick_consumed(0).unwrap();
+
+    assert_eq!(
+        guest.mark_tick_consumed(1),
+        Err(TickConsumptionError::NotCollected { tick: 1, end: 1 })
+    );
+    guest.add_own_input(PlayerInput::default());
+    assert_eq!(guest.mark_tick_consumed(1), Ok(()));
+}
+
+#[test]
+fn test_consumption_counts_sim_ticks_from_the_start_tick() {
+    // With a later start tick and inputs covering two sim ticks each,
+    // consumption starts at the start tick and steps one sim tick at a time.
+    let mut host = Host::new(2, 50, 5, 60)
+        .with_start_tick(100)
+        .with_sim_ticks_per_input(2);
+    host.add_host_input_directly(PlayerInput::default());
+    assert_eq!(host.next_unconsumed_tick(), 100);
+
+    host.mark_tick_consumed(100).unwrap();
+    host.mark_tick_consumed(101).unwrap();
+    assert_eq!(
+        host.mark_tick_consumed(102),
+        Err(TickConsumptionError::NotCollected {
+            tick: 102,
+            end: 102
+        })
+    );
+}
+
+#[test]
+fn test_consumption_restarts_at_a_new_round() {
+    // A new round's ticks restart from the start tick, and so does
+    // consumption.
+    let mut host = host_with_inputs(2);
+    host.mark_tick_consumed(0).unwrap();
+    host.mark_tick_consumed(1).unwrap();
+
+    host.start_new_round();
+
+    assert_eq!(host.next_unconsumed_tick(), 0);
+}
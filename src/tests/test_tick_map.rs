@@ -0,0 +1,102 @@
+use test_case::test_case;
+
+use crate::{
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr, multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput, tick_map::TickMap, util_types::PlayerNum,
+};
+
+const HOST: PlayerNum = PlayerNum(0);
+const GUEST: PlayerNum = PlayerNum(1);
+
+#[test_case(99, None ; "before the start tick")]
+#[test_case(100, Some(0) ; "at the start tick")]
+#[test_case(101, Some(0) ; "second tick of the first input")]
+#[test_case(102, Some(1) ; "next input")]
+#[test_case(u32::MAX, Some((u32::MAX - 100) / 2) ; "last tick")]
+fn test_to_relative(abs_tick: u32, expected: Option<u32>) {
+    // Absolute ticks map to the input covering them, counted from the start
+    // tick; ticks before the start have no input.
+    assert_eq!(TickMap::new(100, 2).to_relative(abs_tick), expected);
+}
+
+#[test_case(0, Some(100) ; "first input")]
+#[test_case(3, Some(106) ; "later input")]
+#[test_case((u32::MAX - 100) / 2, Some(u32::MAX - 1) ; "last input")]
+#[test_case(u32::MAX / 2, None ; "past the last tick")]
+#[test_case(u32::MAX, None ; "index overflows")]
+fn test_to_absolute(rel_index: u32, expected: Option<u32>) {
+    // Input indices map to the first absolute tick they cover, with no result
+    // for indices whose tick would be past u32::MAX.
+    assert_eq!(TickMap::new(100, 2).to_absolute(rel_index), expected);
+}
+
+#[test]
+fn test_round_trip() {
+    // Converting an index to a tick and back gives the same index, as does
+    // converting any tick the input covers.
+    let map = TickMap::new(7, 3);
+    for index in 0..20 {
+        let tick = map.to_absolute(index).unwrap();
+        for covered in tick..tick + 3 {
+            assert_eq!(map.to_relative(covered), Some(index));
+        }
+    }
+}
+
+#[test_case(0, 0 ; "before the start tick")]
+#[test_case(100, 0 ; "at the start tick")]
+#[test_case(101, 1 ; "partly covered input counts")]
+#[test_case(104, 2 ; "whole inputs")]
+fn test_num_relative_before(abs_tick: u32, expected: u32) {
+    // Counts the inputs covering any tick before `abs_tick`.
+    assert_eq!(TickMap::new(100, 2).num_relative_before(abs_tick), expected);
+}
+
+#[test]
+fn test_sim_ticks_per_input_is_at_least_one() {
+    // A map with 0 sim ticks per input would divide by zero, so it is clamped.
+    assert_eq!(TickMap::new(0, 0).sim_ticks_per_input(), 1);
+    assert_eq!(TickMap::default(), TickMap::new(0, 1));
+}
+
+#[test]
+fn test_manager_api_uses_absolute_ticks() {
+    // With a start tick and several sim ticks per input, the managers share a
+    // tick map, and their tick arguments and results are absolute sim ticks.
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 50, 5, 50)
+        .with_start_tick(100)
+        .with_sim_ticks_per_input(2);
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, GUEST, 50);
+    guest.rx_pre_sim_sync(host.get_msg_pre_sim_sync(3)).unwrap();
+    assert_eq!(guest.tick_map(), host.tick_map());
+    let map = host.tick_map();
+    assert_eq!(map, TickMap::new(100, 2));
+
+    for x in 0..3 {
+        host.add_host_input_directly(PlayerInput::new_test_simple(x));
+        guest.add_own_input(PlayerInput::new_test_simple(10 + x));
+    }
+    host.rx_guest_input_slice(GUEST, guest.get_msg_own_input_slice());
+
+    assert_eq!(
+        host.get_snapshottable_sim_tick(),
+        map.to_absolute(3).unwrap()
+    );
+    let second_input_tick = map.to_absolute(1).unwrap();
+    for tick in second_input_tick..second_input_tick + 2 {
+        assert_eq!(
+            host.get_peer_input_for_tick(HOST, tick),
+            PlayerInput::new_test_simple(1)
+        );
+        assert_eq!(
+            host.get_inputs_map_for_tick(tick)[&GUEST.0],
+            PlayerInput::new_test_simple(11)
+        );
+    }
+    assert!(
+        host.get_input_statuses(map.start_tick() - 1)
+            .iter()
+            .all(|(_, status)| status.is_finalized())
+    );
+}
@@ -0,0 +1,136 @@
+use test_case::test_case;
+
+use crate::{
+    InputMgrEvent, MAX_HELD_SLICES_PER_PLAYER, RxOutcome, RxRejection,
+    input_messages::{HostFinalizedSlice, MsgPayload},
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+type Guest = MultiplayerInputManager<PlayerInput, GuestInputMgr>;
+
+/// A guest of a 2 player lobby, which doesn't know about player 2.
+fn two_player_guest() -> Guest {
+    Guest::new(2, PlayerNum(1), 60)
+}
+
+fn final_slice(player_num: u8, start: u32, num_inputs: u32) -> MsgPayload<PlayerInput> {
+    HostFinalizedSlice::new_test(PlayerNum(player_num), 10, start, num_inputs).into()
+}
+
+#[test]
+fn test_slice_for_unknown_player_is_held() {
+    // A finalized slice for a player past `num_players` doesn't panic: it is
+    // held, and the game is told about the unknown player.
+    let mut guest = two_player_guest();
+    let outcome = guest.rx_final_peer_input_slice_from_host(final_slice(2, 0, 5));
+
+    assert_eq!(outcome, RxOutcome::rejected(RxRejection::UnknownPlayer));
+    assert_eq!(guest.num_unknown_player_slices(), 1);
+    assert_eq!(
+        guest.drain_events(),
+        vec![InputMgrEvent::UnknownPlayer {
+            player_num: PlayerNum(2)
+        }]
+    );
+}
+
+#[test]
+fn test_unknown_player_event_is_raised_once_per_player() {
+    // Further slices for a player already held don't raise the event again;
+    // a second unknown player raises its own.
+    let mut guest = two_player_guest();
+    guest.rx_final_peer_input_slice_from_host(final_slice(2, 0, 5));
+    guest.rx_final_peer_input_slice_from_host(final_slice(2, 0, 8));
+    guest.rx_final_peer_input_slice_from_host(final_slice(3, 0, 2));
+
+    assert_eq!(
+        guest.drain_events(),
+        vec![
+            InputMgrEvent::UnknownPlayer {
+                player_num: PlayerNum(2)
+            },
+            InputMgrEvent::UnknownPlayer {
+                player_num: PlayerNum(3)
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_registering_applies_held_slices_in_order() {
+    // Once the player is registered, the held slices are applied oldest
+    // first, and the player's inputs are finalized through the last one.
+    let mut guest = two_player_guest();
+    guest.rx_final_peer_input_slice_from_host(final_slice(2, 0, 5));
+    guest.rx_final_peer_input_slice_from_host(final_slice(2, 3, 6));
+
+    let applied = guest.register_players(3);
+    let finalized: Vec<(PlayerNum, u32)> = applied
+        .iter()
+        .map(|(player_num, outcome)| (*player_num, outcome.newly_finalized))
+        .collect();
+    assert_eq!(finalized, vec![(PlayerNum(2), 5), (PlayerNum(2), 4)]);
+    assert_eq!(guest.get_peer_num_final_inputs(PlayerNum(2)), 9);
+    assert_eq!(guest.num_unknown_player_slices(), 0);
+}
+
+#[test]
+fn test_registering_keeps_slices_for_players_still_unknown() {
+    // Registering fewer players than were seen only applies the slices of
+    // the newly known ones.
+    let mut guest = two_player_guest();
+    guest.rx_final_peer_input_slice_from_host(final_slice(2, 0, 5));
+    guest.rx_final_peer_input_slice_from_host(final_slice(3, 0, 5));
+
+    assert_eq!(guest.register_players(3).len(), 1);
+    assert_eq!(guest.num_unknown_player_slices(), 1);
+    assert_eq!(guest.get_peer_num_final_inputs(PlayerNum(2)), 5);
+}
+
+#[test_case(2 ; "same number of players")]
+#[test_case(1 ; "fewer players")]
+fn test_registering_no_new_players_does_nothing(num_players: u8) {
+    // Registering no more players than the guest has applies nothing.
+    let mut guest = two_player_guest();
+    guest.rx_final_peer_input_slice_from_host(final_slice(2, 0, 5));
+
+    assert!(guest.register_players(num_players).is_empty());
+    assert_eq!(guest.num_unknown_player_slices(), 1);
+}
+
+#[test]
+fn test_held_slices_are_capped_per_player() {
+    // Past the cap, the oldest slices are dropped; since later slices from
+    // the host start from the same acked input, the newest still applies.
+    let mut guest = two_player_guest();
+    for num_inputs in 1..=(MAX_HELD_SLICES_PER_PLAYER as u32 + 4) {
+        guest.rx_final_peer_input_slice_from_host(final_slice(2, 0, num_inputs));
+    }
+    assert_eq!(
+        guest.num_unknown_player_slices(),
+        MAX_HELD_SLICES_PER_PLAYER
+    );
+
+    guest.register_players(3);
+    assert_eq!(
+        guest.get_peer_num_final_inputs(PlayerNum(2)),
+        MAX_HELD_SLICES_PER_PLAYER as u32 + 4
+    );
+}
+
+#[test]
+fn test_peer_slice_for_unknown_player_is_rejected() {
+    // A provisional slice straight from an unknown peer is rejected rather
+    // than panicking; only the host's finalized slices are held.
+    let mut guest = two_player_guest();
+    let outcome = guest.rx_peer_input_slice(
+        PlayerNum(2),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, 5)),
+    );
+
+    assert_eq!(outcome, RxOutcome::rejected(RxRejection::UnknownPlayer));
+    assert_eq!(guest.num_unknown_player_slices(), 0);
+}
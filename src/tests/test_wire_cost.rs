@@ -0,0 +1,88 @@
+use serde::Serialize;
+use test_case::test_case;
+
+use crate::{
+    input_messages::MsgKind,
+    input_trait::SimInput,
+    tests::demo_input_struct::PlayerInput,
+    wire_cost::{REPORT_NUM_PLAYERS, REPORT_SLICE_LENS, wire_cost_report},
+};
+
+/// An input whose representation is one byte wider than `PlayerInput`'s.
+#[derive(Default, Clone, Debug, Serialize)]
+struct WideInput;
+
+impl SimInput for WideInput {
+    type Bytes = [u8; 4];
+    fn to_bytes(&self) -> Self::Bytes {
+        [0; 4]
+    }
+    fn from_bytes(_bytes: Self::Bytes) -> Self {
+        WideInput
+    }
+}
+
+#[test]
+fn test_report_covers_every_representative_message() {
+    // The report has one entry per slice length for each kind of slice, and
+    // one per lobby size for acks.
+    let report = wire_cost_report::<PlayerInput>();
+    assert_eq!(report.input_bytes, 3);
+    for num_inputs in REPORT_SLICE_LENS {
+        assert!(report.cost(MsgKind::PeerInputs, num_inputs).is_some());
+        assert!(
+            report
+                .cost(MsgKind::HostToLobbyFinalizedSlice, num_inputs)
+                .is_some()
+        );
+    }
+    for num_players in REPORT_NUM_PLAYERS {
+        let ack = report
+            .cost(MsgKind::GuestToHostAckFinalization, num_players as u32)
+            .unwrap();
+        assert_eq!(ack.overhead_per_input, None);
+    }
+    assert_eq!(
+        report.costs.len(),
+        2 * REPORT_SLICE_LENS.len() + REPORT_NUM_PLAYERS.len()
+    );
+}
+
+#[test_case(1, 8, 5.0; "single input")]
+#[test_case(8, 29, 0.625; "eight inputs")]
+#[test_case(32, 101, 0.15625; "thirty two inputs")]
+fn test_peer_slice_cost(num_inputs: u32, expected_bytes: usize, expected_overhead: f32) {
+    // A slice costs its header, start index and length once, plus 3 bytes
+    // per input, so the overhead per input shrinks as slices grow.
+    let report = wire_cost_report::<PlayerInput>();
+    let cost = report.cost(MsgKind::PeerInputs, num_inputs).unwrap();
+    assert_eq!(cost.bytes, expected_bytes);
+    assert_eq!(cost.overhead_per_input, Some(expected_overhead));
+}
+
+#[test]
+fn test_each_extra_input_byte_costs_a_byte_per_input() {
+    // Widening the input representation by a byte adds a byte per input to
+    // every slice, and leaves acks unchanged.
+    let narrow = wire_cost_report::<PlayerInput>();
+    let wide = wire_cost_report::<WideInput>();
+    assert_eq!(wide.input_bytes, narrow.input_bytes + 1);
+
+    for (narrow_cost, wide_cost) in narrow.costs.iter().zip(&wide.costs) {
+        assert_eq!(
+            wide_cost.bytes - narrow_cost.bytes,
+            wide_cost.num_inputs as usize
+        );
+        assert_eq!(wide_cost.overhead_per_input, narrow_cost.overhead_per_input);
+    }
+}
+
+#[test]
+fn test_report_displays_a_line_per_message() {
+    // The report prints the input size, then one line per message.
+    let report = wire_cost_report::<PlayerInput>();
+    let text = report.to_string();
+    assert_eq!(text.lines().count(), 1 + report.costs.len());
+    assert!(text.starts_with("input: 3 bytes\n"));
+    assert!(text.contains("PeerInputs with 1 inputs: 8 bytes (5.00 overhead bytes per input)"));
+}
@@ -0,0 +1,52 @@
+use test_case::test_case;
+
+use crate::{
+    input_messages::MsgPayload,
+    multiplayer_input_manager::MultiplayerInputManager,
+    multiplayer_input_manager_guest::GuestInputMgr,
+    multiplayer_input_manager_host::HostInputMgr,
+    tests::demo_input_struct::PlayerInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+#[test_case(0; "no inputs")]
+#[test_case(5; "a few inputs")]
+#[test_case(400; "enough inputs to be compressed")]
+fn test_guest_own_slice_bytes_match_owned_message(num_inputs: u32) {
+    // Serializing the guest's own slice straight from the buffer gives
+    // exactly the bytes of the owned message, and decodes back to it.
+    let mut guest = MultiplayerInputManager::<PlayerInput, GuestInputMgr>::new(2, 1.into(), 60);
+    for x in 0..num_inputs {
+        guest.add_own_input(PlayerInput::new_test_simple((x % 7) as u8));
+    }
+
+    let owned = guest.get_msg_own_input_slice();
+    let bytes = guest.get_msg_bytes_own_input_slice();
+    assert_eq!(bytes, owned.to_bytes());
+    let decoded = MsgPayload::<PlayerInput>::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.to_bytes(), bytes);
+}
+
+#[test_case(0; "no inputs")]
+#[test_case(5; "a few inputs")]
+#[test_case(400; "enough inputs to be compressed")]
+fn test_host_finalized_slice_bytes_match_owned_message(num_inputs: u32) {
+    // Serializing a finalized slice straight from the host's buffer gives
+    // exactly the bytes of the owned message, and decodes back to it.
+    let mut host = MultiplayerInputManager::<PlayerInput, HostInputMgr>::new(2, 50, 5, 60);
+    for _ in 0..num_inputs {
+        host.add_host_input_directly(PlayerInput::default());
+    }
+    host.rx_guest_input_slice(
+        PlayerNum(1),
+        MsgPayload::PeerInputs(PlayerInputSlice::new_test(0, num_inputs)),
+    );
+
+    for player in [PlayerNum(0), PlayerNum(1)] {
+        let owned = host.get_msg_finalized_slice(player);
+        let bytes = host.get_msg_bytes_finalized_slice(player);
+        assert_eq!(bytes, owned.to_bytes());
+        let decoded = MsgPayload::<PlayerInput>::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+    }
+}
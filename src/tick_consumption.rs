@@ -0,0 +1,48 @@
+//! An optional guard rail for the sim loop against simulating a tick twice or skipping one.
+//!
+//! Games that opt in call `MultiplayerInputManager::mark_tick_consumed` once for each tick they advance their sim past, in order, and can ask `next_unconsumed_tick` which tick is due. Each tick can be consumed only once, only after the one before it, and only once this node has collected its own input for it. Re-simulating ticks after a rollback isn't consumption: mark each tick the first time the sim steps past it.
+//!
+//! Consumption restarts from the start tick at each new round.
+
+/// Why `mark_tick_consumed` refused a tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickConsumptionError {
+    /// The tick has already been consumed; `next` is the next unconsumed tick.
+    Repeated { tick: u32, next: u32 },
+    /// Consuming the tick would skip the ticks from `next`, the next unconsumed tick.
+    Skipped { tick: u32, next: u32 },
+    /// This node hasn't collected its own input for the tick yet; `end` is the first sim tick past its collected inputs.
+    NotCollected { tick: u32, end: u32 },
+}
+
+/// The number of ticks the sim has consumed since the start tick.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct TickConsumption {
+    num_consumed: u32,
+}
+
+impl TickConsumption {
+    pub(crate) fn next_tick(&self, start_tick: u32) -> u32 {
+        start_tick + self.num_consumed
+    }
+
+    /// Consumes `tick` if it is the next unconsumed tick and falls before `end`, the first sim tick without a collected input.
+    pub(crate) fn consume(
+        &mut self,
+        tick: u32,
+        start_tick: u32,
+        end: u32,
+    ) -> Result<(), TickConsumptionError> {
+        let next = self.next_tick(start_tick);
+        if tick < next {
+            Err(TickConsumptionError::Repeated { tick, next })
+        } else if tick > next {
+            Err(TickConsumptionError::Skipped { tick, next })
+        } else if tick >= end {
+            Err(TickConsumptionError::NotCollected { tick, end })
+        } else {
+            self.num_consumed += 1;
+            Ok(())
+        }
+    }
+}
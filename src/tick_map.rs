@@ -0,0 +1,65 @@
+//! Translation between absolute sim ticks and buffer-relative input indices.
+//!
+//! Every tick taken or returned by the public API is an absolute sim tick, counted from 0 regardless of where the session's timeline starts. Input buffers, slices and acks are instead indexed from the first input of the timeline, and each input may cover several sim ticks (see `MultiplayerInputManager::with_sim_ticks_per_input`). A `TickMap` owns that translation, so that code converting between the two doesn't need to know how the buffers are laid out.
+
+use serde::{Deserialize, Serialize};
+
+/// Maps absolute sim ticks to input indices and back, for one set of buffers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TickMap {
+    /// The sim tick of the input at index 0
+    start_tick: u32,
+    /// The number of sim ticks each input covers (at least 1)
+    sim_ticks_per_input: u32,
+}
+
+impl Default for TickMap {
+    fn default() -> Self {
+        Self::new(0, 1)
+    }
+}
+
+impl TickMap {
+    /// A map for buffers whose first input is at `start_tick`, with each input covering `sim_ticks_per_input` sim ticks (at least 1).
+    pub fn new(start_tick: u32, sim_ticks_per_input: u32) -> Self {
+        Self {
+            start_tick,
+            sim_ticks_per_input: sim_ticks_per_input.max(1),
+        }
+    }
+
+    pub fn start_tick(&self) -> u32 {
+        self.start_tick
+    }
+
+    pub fn sim_ticks_per_input(&self) -> u32 {
+        self.sim_ticks_per_input
+    }
+
+    pub(crate) fn set_start_tick(&mut self, start_tick: u32) {
+        self.start_tick = start_tick;
+    }
+
+    pub(crate) fn set_sim_ticks_per_input(&mut self, sim_ticks_per_input: u32) {
+        self.sim_ticks_per_input = sim_ticks_per_input.max(1);
+    }
+
+    /// The index of the input covering this absolute sim tick; `None` for ticks before the start tick.
+    pub fn to_relative(&self, abs_tick: u32) -> Option<u32> {
+        Some(abs_tick.checked_sub(self.start_tick)? / self.sim_ticks_per_input)
+    }
+
+    /// The first absolute sim tick covered by the input at this index; `None` if that tick is past `u32::MAX`.
+    pub fn to_absolute(&self, rel_index: u32) -> Option<u32> {
+        rel_index
+            .checked_mul(self.sim_ticks_per_input)?
+            .checked_add(self.start_tick)
+    }
+
+    /// The number of inputs covering the sim ticks from the start tick up to (but not including) `abs_tick`, counting an input that is only partly before `abs_tick`.
+    pub fn num_relative_before(&self, abs_tick: u32) -> u32 {
+        abs_tick
+            .saturating_sub(self.start_tick)
+            .div_ceil(self.sim_ticks_per_input)
+    }
+}
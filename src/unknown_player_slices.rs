@@ -0,0 +1,53 @@
+//! Finalized slices for players a guest doesn't know about yet.
+//!
+//! A guest's buffers are sized for the `num_players` it was created with, so a finalized slice from the host for a player beyond that (e.g. one who joined mid-session, before the game told the guest about the join) has nowhere to go. Rather than being dropped, such slices are held here, and `InputMgrEvent::UnknownPlayer` prompts the game to process the join. Once the game calls `MultiplayerInputManager::register_players`, the held slices are applied in the order they arrived.
+//!
+//! At most `MAX_HELD_SLICES_PER_PLAYER` slices are held per player, dropping the oldest. The host resends finalized inputs from the fewest any guest has acked, and a guest can't ack a player it doesn't know, so later slices cover what the dropped ones held.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::{
+    input_trait::SimInput,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+/// The most finalized slices a guest holds for each player it doesn't know about yet.
+pub const MAX_HELD_SLICES_PER_PLAYER: usize = 16;
+
+/// The finalized slices held for each unknown player, oldest first.
+#[derive(Debug, Clone)]
+pub(crate) struct UnknownPlayerSlices<T: SimInput>(
+    BTreeMap<PlayerNum, VecDeque<PlayerInputSlice<T>>>,
+);
+
+impl<T: SimInput> Default for UnknownPlayerSlices<T> {
+    fn default() -> Self {
+        Self(BTreeMap::default())
+    }
+}
+
+impl<T: SimInput> UnknownPlayerSlices<T> {
+    /// Holds a slice for an unknown player, dropping the player's oldest if it already has the most held. Returns true if this is the first slice held for the player.
+    pub(crate) fn hold(&mut self, player_num: PlayerNum, slice: PlayerInputSlice<T>) -> bool {
+        let held = self.0.entry(player_num).or_default();
+        if held.len() >= MAX_HELD_SLICES_PER_PLAYER {
+            held.pop_front();
+        }
+        held.push_back(slice);
+        held.len() == 1
+    }
+
+    /// The number of slices held across all players.
+    pub(crate) fn len(&self) -> usize {
+        self.0.values().map(VecDeque::len).sum()
+    }
+
+    /// Removes the slices held for players below `num_players`, by player and then oldest first.
+    pub(crate) fn take_known(&mut self, num_players: u8) -> Vec<(PlayerNum, PlayerInputSlice<T>)> {
+        let unknown = self.0.split_off(&PlayerNum(num_players));
+        std::mem::replace(&mut self.0, unknown)
+            .into_iter()
+            .flat_map(|(player_num, held)| held.into_iter().map(move |slice| (player_num, slice)))
+            .collect()
+    }
+}
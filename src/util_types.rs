@@ -1,4 +1,7 @@
-use std::fmt::{Debug, Display};
+use std::{
+    fmt::{Debug, Display},
+    str::FromStr,
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -13,30 +16,40 @@ pub struct PlayerNum(pub(crate) u8);
 impl PlayerNum {
     const HOST: u8 = 0;
 
-    pub fn from_u8(player_num: u8) -> Self {
+    pub const fn from_u8(player_num: u8) -> Self {
         PlayerNum(player_num)
     }
 
-    pub fn new_host() -> Self {
+    pub const fn new_host() -> Self {
         PlayerNum(Self::HOST)
     }
-    pub fn new_guest(player_num: u8) -> Self {
+    pub const fn new_guest(player_num: u8) -> Self {
         assert!(player_num != Self::HOST);
         PlayerNum(player_num)
     }
 
-    pub fn is_host(&self) -> bool {
+    pub const fn is_host(&self) -> bool {
         self.0 == Self::HOST
     }
 
-    pub fn is_guest(&self) -> bool {
+    pub const fn is_guest(&self) -> bool {
         self.0 != Self::HOST
     }
 
-    pub fn as_u8(&self) -> u8 {
+    pub const fn as_u8(&self) -> u8 {
         self.0
     }
 
+    /// Iterates over every player in a session of `num_players`, starting with the host.
+    pub fn iter(num_players: u8) -> impl Iterator<Item = PlayerNum> + Clone {
+        (0..num_players).map(PlayerNum)
+    }
+
+    /// Iterates over the guests in a session of `num_players` (i.e. every player except the host).
+    pub fn iter_guests(num_players: u8) -> impl Iterator<Item = PlayerNum> + Clone {
+        (1..num_players.max(1)).map(PlayerNum)
+    }
+
     /// For situations where you need to index into a guest-only array
     /// (e.g. an array of inputs from all guests, excluding the host)
     pub fn guest_index(&self) -> Option<usize> {
@@ -48,11 +61,15 @@ impl PlayerNum {
     }
 
     /// Create a PlayerNum from a guest index (0-based, excluding host;
-    /// so i.e. PlayerNum::from_guest_index(0) == PlayerNum(1))
-    /// Panics if index is too large to fit in u8
+    /// so i.e. PlayerNum::from_guest_index(0) == Ok(PlayerNum(1)))
     ///
-    pub fn from_guest_index(index: usize) -> Self {
-        PlayerNum((index + 1) as u8)
+    /// Returns an error if the resulting player_num would not fit in a u8.
+    pub fn from_guest_index(index: usize) -> Result<Self, &'static str> {
+        if index >= u8::MAX as usize {
+            Err("guest index must be less than 255")
+        } else {
+            Ok(PlayerNum((index + 1) as u8))
+        }
     }
 }
 
@@ -62,27 +79,39 @@ impl Display for PlayerNum {
     }
 }
 
-impl Into<String> for PlayerNum {
-    fn into(self) -> String {
-        self.0.to_string()
+/// Parses either the `Display` form (`PNum_3`) or a bare number (`3`).
+impl FromStr for PlayerNum {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.trim().strip_prefix("PNum_").unwrap_or(s.trim());
+        digits
+            .parse::<u8>()
+            .map(PlayerNum)
+            .map_err(|_| "PlayerNum must be a number less than 256, optionally prefixed with PNum_")
     }
 }
 
-impl Into<u32> for PlayerNum {
-    fn into(self) -> u32 {
-        self.0 as u32
+impl From<PlayerNum> for String {
+    fn from(value: PlayerNum) -> Self {
+        value.0.to_string()
     }
 }
 
-impl Into<u8> for PlayerNum {
-    fn into(self) -> u8 {
-        self.0
+impl From<PlayerNum> for u32 {
+    fn from(value: PlayerNum) -> Self {
+        value.0 as u32
+    }
+}
+
+impl From<PlayerNum> for u8 {
+    fn from(value: PlayerNum) -> Self {
+        value.0
     }
 }
 
-impl Into<usize> for PlayerNum {
-    fn into(self) -> usize {
-        self.0 as usize
+impl From<PlayerNum> for usize {
+    fn from(value: PlayerNum) -> Self {
+        value.0 as usize
     }
 }
 
@@ -128,10 +157,73 @@ where
     T: SimInput,
 {
     pub fn len(&self) -> u32 {
-        return self.inputs.len() as u32;
+        self.inputs.len() as u32
+    }
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
     }
     pub fn max_tick(&self) -> u32 {
-        return (self.start + self.len()) as u32 - 1;
+        self.start + self.len() - 1
+    }
+    /// A slice of `len` inputs from index `start`, with the input at each index given by `f` (see `input_patterns` for some ready-made generators).
+    pub fn from_fn(start: u32, len: u32, f: impl Fn(u32) -> T) -> Self {
+        PlayerInputSlice {
+            start,
+            inputs: (start..start + len)
+                .map(|index| f(index).to_bytes())
+                .collect(),
+        }
+    }
+    /// Drops any inputs at or after index `end`.
+    pub(crate) fn truncate_before(&mut self, end: u32) {
+        self.inputs
+            .truncate(end.saturating_sub(self.start) as usize);
+    }
+    /// Drops any inputs before index `start`.
+    pub(crate) fn drop_before(&mut self, start: u32) {
+        let num_dropped = start.saturating_sub(self.start).min(self.len());
+        self.inputs.drain(..num_dropped as usize);
+        self.start += num_dropped;
+    }
+    /// Moves the inputs at or after index `at` into a new slice.
+    pub(crate) fn split_off(&mut self, at: u32) -> Self {
+        let at = at.saturating_sub(self.start).min(self.len());
+        PlayerInputSlice {
+            start: self.start + at,
+            inputs: self.inputs.split_off(at as usize),
+        }
+    }
+}
+
+/// A borrowed view of a `PlayerInputSlice`, which serializes to the same bytes without copying the inputs out of the buffer.
+///
+/// Use this on the serialization path (e.g. `MultiplayerInputManager::get_msg_bytes_own_input_slice`), where the inputs are only read to be encoded.
+#[derive(Debug, Serialize)]
+#[serde(bound = "")]
+pub struct PlayerInputSliceRef<'a, T>
+where
+    T: SimInput,
+{
+    pub start: u32,
+    pub inputs: &'a [T::Bytes],
+}
+
+impl<T> PlayerInputSliceRef<'_, T>
+where
+    T: SimInput,
+{
+    pub fn len(&self) -> u32 {
+        self.inputs.len() as u32
+    }
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+    /// Copies the inputs into an owned slice.
+    pub fn to_owned_slice(&self) -> PlayerInputSlice<T> {
+        PlayerInputSlice {
+            start: self.start,
+            inputs: self.inputs.to_vec(),
+        }
     }
 }
 
@@ -0,0 +1,133 @@
+//! The serialized size of the messages that carry inputs, for designing a `SimInput::Bytes` representation.
+//!
+//! Every input a player collects is sent to each peer at least twice (in its own `PeerInputs`, and again in the host's `HostToLobbyFinalizedSlice`), so each byte added to `SimInput::Bytes` is multiplied by the tick rate, the number of players and the number of times it is resent. `wire_cost_report` serializes representative messages so that the cost of a representation can be checked before shipping it:
+//!
+//! `println!("{}", wire_cost_report::<MyInput>());`
+//!
+//! Sizes are uncompressed, and are for default inputs at input index `REPORT_START_INDEX`. Inputs with variable-length fields (e.g. bincode's varint integers) may serialize larger for other values.
+
+use std::fmt::Display;
+
+use crate::{
+    input_messages::{HostFinalizedSlice, MsgKind, MsgPayload, to_bincode_bytes},
+    input_trait::SimInput,
+    peerwise_finalized_input::PeerwiseFinalizedInputsSeen,
+    util_types::{PlayerInputSlice, PlayerNum},
+};
+
+/// The input index at which the report's slices start, and which its acks report: a minute into a session at 60hz.
+pub const REPORT_START_INDEX: u32 = 3600;
+/// The slice lengths the report covers.
+pub const REPORT_SLICE_LENS: [u32; 3] = [1, 8, 32];
+/// The lobby sizes the report's acks cover.
+pub const REPORT_NUM_PLAYERS: [u8; 3] = [2, 4, 8];
+
+/// The serialized size of one representative message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WireCost {
+    pub kind: MsgKind,
+    /// The number of inputs the message carries; 0 for acks
+    pub num_inputs: u32,
+    /// The number of players in the lobby; only acks depend on it
+    pub num_players: u8,
+    /// The serialized size, header byte included
+    pub bytes: usize,
+    /// The bytes beyond the inputs themselves, per input carried; `None` for messages without inputs
+    pub overhead_per_input: Option<f32>,
+}
+
+/// The serialized sizes of representative messages for an input type (see the module docs).
+#[derive(Debug, Clone, PartialEq)]
+pub struct WireCostReport {
+    /// The serialized size of a single input
+    pub input_bytes: usize,
+    pub costs: Vec<WireCost>,
+}
+
+impl WireCostReport {
+    /// The cost of the representative message of this kind with this many inputs (for slices) or players (for acks).
+    pub fn cost(&self, kind: MsgKind, size: u32) -> Option<&WireCost> {
+        self.costs.iter().find(|cost| {
+            cost.kind == kind
+                && match cost.kind {
+                    MsgKind::GuestToHostAckFinalization => cost.num_players as u32 == size,
+                    _ => cost.num_inputs == size,
+                }
+        })
+    }
+}
+
+/// Serializes representative input-carrying messages for `T` and reports their sizes (see the module docs).
+pub fn wire_cost_report<T: SimInput>() -> WireCostReport {
+    let input_bytes = to_bincode_bytes(&T::default().to_bytes()).len();
+    let slice = |num_inputs: u32| PlayerInputSlice::<T> {
+        start: REPORT_START_INDEX,
+        inputs: vec![T::default().to_bytes(); num_inputs as usize],
+    };
+    let cost_with_inputs = |msg: MsgPayload<T>, num_inputs: u32| {
+        let bytes = msg.uncompressed_len();
+        WireCost {
+            kind: msg.kind(),
+            num_inputs,
+            num_players: 0,
+            bytes,
+            overhead_per_input: Some(
+                (bytes - num_inputs as usize * input_bytes) as f32 / num_inputs as f32,
+            ),
+        }
+    };
+
+    let mut costs = Vec::new();
+    for num_inputs in REPORT_SLICE_LENS {
+        costs.push(cost_with_inputs(
+            MsgPayload::PeerInputs(slice(num_inputs)),
+            num_inputs,
+        ));
+    }
+    for num_inputs in REPORT_SLICE_LENS {
+        let finalized = HostFinalizedSlice {
+            player_num: PlayerNum(1),
+            host_tick: REPORT_START_INDEX + num_inputs,
+            inputs: slice(num_inputs),
+        };
+        costs.push(cost_with_inputs(
+            MsgPayload::HostToLobbyFinalizedSlice(finalized),
+            num_inputs,
+        ));
+    }
+    for num_players in REPORT_NUM_PLAYERS {
+        let observed = vec![REPORT_START_INDEX; num_players as usize];
+        let ack = MsgPayload::<T>::GuestToHostAckFinalization(
+            PeerwiseFinalizedInputsSeen::new_from_observed(num_players, &observed),
+        );
+        costs.push(WireCost {
+            kind: ack.kind(),
+            num_inputs: 0,
+            num_players,
+            bytes: ack.uncompressed_len(),
+            overhead_per_input: None,
+        });
+    }
+    WireCostReport { input_bytes, costs }
+}
+
+impl Display for WireCostReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "input: {} bytes", self.input_bytes)?;
+        for cost in &self.costs {
+            match cost.overhead_per_input {
+                Some(overhead) => writeln!(
+                    f,
+                    "{:?} with {} inputs: {} bytes ({overhead:.2} overhead bytes per input)",
+                    cost.kind, cost.num_inputs, cost.bytes
+                )?,
+                None => writeln!(
+                    f,
+                    "{:?} for {} players: {} bytes",
+                    cost.kind, cost.num_players, cost.bytes
+                )?,
+            }
+        }
+        Ok(())
+    }
+}